@@ -1,5 +1,6 @@
+pub mod ownership;
 pub mod password;
 pub mod webauthn;
 
-pub use password::{hash_password, verify_password};
+pub use password::{generate_password, hash_password, verify_password};
 pub use webauthn::create_webauthn;
@@ -0,0 +1,184 @@
+//! Whether a category, feed or entry belongs to a user.
+//!
+//! Each check is one query joining up to the owning category. Anything in
+//! the trash, or behind a feed or category in the trash, counts as missing,
+//! and so does anything of another user, so handlers answer 404 either way
+//! and never reveal that someone else's ID exists.
+
+use std::collections::HashSet;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::{AppError, AppResult};
+use crate::models::entry::{self, EntryWithFeed};
+
+fn exists(conn: &Connection, sql: &str, id: i64, user_id: i64) -> AppResult<bool> {
+    let found = conn
+        .query_row(sql, params![id, user_id], |_| Ok(()))
+        .optional()?;
+    Ok(found.is_some())
+}
+
+pub fn assert_category_owned(conn: &Connection, user_id: i64, category_id: i64) -> AppResult<()> {
+    let owned = exists(
+        conn,
+        "SELECT 1 FROM category WHERE id = ?1 AND user_id = ?2 AND deleted_at IS NULL",
+        category_id,
+        user_id,
+    )?;
+    if !owned {
+        return Err(AppError::CategoryNotFound);
+    }
+    Ok(())
+}
+
+pub fn assert_feed_owned(conn: &Connection, user_id: i64, feed_id: i64) -> AppResult<()> {
+    let owned = exists(
+        conn,
+        r#"
+        SELECT 1 FROM feed f
+        INNER JOIN category c ON f.category_id = c.id
+        WHERE f.id = ?1 AND c.user_id = ?2 AND f.deleted_at IS NULL AND c.deleted_at IS NULL
+        "#,
+        feed_id,
+        user_id,
+    )?;
+    if !owned {
+        return Err(AppError::FeedNotFound);
+    }
+    Ok(())
+}
+
+pub fn assert_entry_owned(conn: &Connection, user_id: i64, entry_id: i64) -> AppResult<()> {
+    let owned = exists(
+        conn,
+        r#"
+        SELECT 1 FROM entry e
+        INNER JOIN feed f ON e.feed_id = f.id
+        INNER JOIN category c ON f.category_id = c.id
+        WHERE e.id = ?1 AND c.user_id = ?2 AND f.deleted_at IS NULL AND c.deleted_at IS NULL
+        "#,
+        entry_id,
+        user_id,
+    )?;
+    if !owned {
+        return Err(AppError::EntryNotFound);
+    }
+    Ok(())
+}
+
+/// Which of `entry_ids` belong to the user, in one query
+pub fn owned_entry_ids(
+    conn: &Connection,
+    user_id: i64,
+    entry_ids: &[i64],
+) -> AppResult<HashSet<i64>> {
+    if entry_ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let placeholders: Vec<String> = (0..entry_ids.len())
+        .map(|i| format!("?{}", i + 2))
+        .collect();
+    let sql = format!(
+        r#"
+        SELECT e.id FROM entry e
+        INNER JOIN feed f ON e.feed_id = f.id
+        INNER JOIN category c ON f.category_id = c.id
+        WHERE c.user_id = ?1 AND f.deleted_at IS NULL AND c.deleted_at IS NULL
+          AND e.id IN ({})
+        "#,
+        placeholders.join(", ")
+    );
+
+    let mut params_vec: Vec<&dyn rusqlite::ToSql> = vec![&user_id];
+    params_vec.extend(entry_ids.iter().map(|id| id as &dyn rusqlite::ToSql));
+
+    let mut stmt = conn.prepare(&sql)?;
+    let ids = stmt
+        .query_map(params_vec.as_slice(), |row| row.get(0))?
+        .collect::<Result<HashSet<_>, _>>()?;
+    Ok(ids)
+}
+
+/// The entry with its feed, for handlers that need more than the check
+pub fn find_owned_entry(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+) -> AppResult<EntryWithFeed> {
+    entry::find_by_id_with_feed_and_user(conn, entry_id, user_id)?.ok_or(AppError::EntryNotFound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, feed, user};
+
+    #[test]
+    fn test_ownership() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let owner = user::create_user(&conn, "owner", "hash", user::Role::User).unwrap();
+        let other = user::create_user(&conn, "other", "hash", user::Role::User).unwrap();
+        let cat = category::create_category(&conn, owner.id, "News").unwrap();
+        let f = feed::create_feed(
+            &conn,
+            cat.id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let (e, _) =
+            entry::upsert_entry(&conn, f.id, "guid", None, None, None, None, None, None).unwrap();
+
+        assert_category_owned(&conn, owner.id, cat.id).unwrap();
+        assert_feed_owned(&conn, owner.id, f.id).unwrap();
+        assert_entry_owned(&conn, owner.id, e.id).unwrap();
+        assert_eq!(
+            find_owned_entry(&conn, owner.id, e.id).unwrap().entry.id,
+            e.id
+        );
+        assert_eq!(
+            owned_entry_ids(&conn, owner.id, &[e.id, 999]).unwrap(),
+            HashSet::from([e.id])
+        );
+        assert!(owned_entry_ids(&conn, other.id, &[e.id])
+            .unwrap()
+            .is_empty());
+
+        assert!(matches!(
+            assert_category_owned(&conn, other.id, cat.id),
+            Err(AppError::CategoryNotFound)
+        ));
+        assert!(matches!(
+            assert_feed_owned(&conn, other.id, f.id),
+            Err(AppError::FeedNotFound)
+        ));
+        assert!(matches!(
+            assert_entry_owned(&conn, other.id, e.id),
+            Err(AppError::EntryNotFound)
+        ));
+        assert!(matches!(
+            assert_entry_owned(&conn, owner.id, 999),
+            Err(AppError::EntryNotFound)
+        ));
+
+        // Trashing the category hides everything under it
+        category::delete_category(&conn, cat.id, owner.id).unwrap();
+        assert!(assert_category_owned(&conn, owner.id, cat.id).is_err());
+        assert!(matches!(
+            assert_feed_owned(&conn, owner.id, f.id),
+            Err(AppError::FeedNotFound)
+        ));
+        assert!(matches!(
+            find_owned_entry(&conn, owner.id, e.id),
+            Err(AppError::EntryNotFound)
+        ));
+    }
+}
@@ -3,6 +3,9 @@ use argon2::{
     Argon2,
 };
 
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::RngCore;
+
 use crate::error::{AppError, AppResult};
 
 pub fn hash_password(password: &str) -> AppResult<String> {
@@ -15,6 +18,13 @@ pub fn hash_password(password: &str) -> AppResult<String> {
         .map_err(|e| AppError::Internal(format!("Password hashing failed: {}", e)))
 }
 
+/// Random password for accounts created on someone's behalf
+pub fn generate_password() -> String {
+    let mut bytes = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
 pub fn verify_password(password: &str, hash: &str) -> bool {
     let parsed_hash = match PasswordHash::new(hash) {
         Ok(h) => h,
@@ -50,6 +60,13 @@ mod tests {
         assert!(verify_password(password, &hash2));
     }
 
+    #[test]
+    fn test_generate_password() {
+        let password = generate_password();
+        assert_eq!(password.len(), 16);
+        assert_ne!(password, generate_password());
+    }
+
     #[test]
     fn test_invalid_hash() {
         assert!(!verify_password("password", "invalid_hash"));
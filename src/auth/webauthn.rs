@@ -29,10 +29,27 @@ mod tests {
             multi_user_enabled: false,
             image_proxy_secret: vec![0u8; 32],
             image_proxy_secret_generated: false,
+            image_proxy_previous_secrets: vec![],
             user_agent: "test".to_string(),
             webauthn_rp_id: "localhost".to_string(),
             webauthn_rp_origin: "http://localhost:3000".to_string(),
             webauthn_rp_name: "rdrs".to_string(),
+            vapid_private_key: None,
+            vapid_subject: "mailto:admin@localhost".to_string(),
+            tls_cert_path: None,
+            tls_key_path: None,
+            unix_socket_path: None,
+            reuse_port: false,
+            shutdown_drain_timeout_secs: 30,
+            max_body_bytes: 2 * 1024 * 1024,
+            max_import_bytes: 256 * 1024 * 1024,
+            log_json: false,
+            session_ttl_secs: 7 * 24 * 3600,
+            session_short_ttl_secs: 2 * 3600,
+            feed_cookie_secret: None,
+            privacy_mode: false,
+            update_check_enabled: false,
+            db_reader_count: 0,
         }
     }
 
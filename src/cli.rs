@@ -0,0 +1,323 @@
+//! Command-line interface. Without a subcommand the binary runs the server;
+//! the other subcommands work directly on the database configured through
+//! the config file and the usual environment variables.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use rusqlite::Connection;
+
+use crate::auth::{generate_password, hash_password};
+use crate::config::{Config, ConfigError, ListenTarget};
+use crate::error::{AppError, AppResult};
+use crate::models::login_attempt;
+use crate::models::user::{self, Role};
+use crate::services::opml;
+
+/// Minimum password length, matching registration
+const MIN_PASSWORD_LEN: usize = 6;
+
+#[derive(Debug, Parser)]
+#[command(name = "rdrs", version = crate::GIT_VERSION, about = "RSS Reader in Rust")]
+pub struct Cli {
+    /// TOML config file; environment variables override its settings
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    /// The config file from `--config`, or else from `RDRS_CONFIG`
+    pub fn config_path(&self) -> Option<PathBuf> {
+        self.config.clone().or_else(|| {
+            env::var_os("RDRS_CONFIG")
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        })
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the web server (default)
+    Serve,
+    /// User administration
+    #[command(subcommand)]
+    Admin(AdminCommand),
+    /// Database maintenance
+    #[command(subcommand)]
+    Db(DbCommand),
+    /// Import data for a user
+    #[command(subcommand)]
+    Import(ImportCommand),
+    /// Configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AdminCommand {
+    /// Create a user; a random password is generated and printed if none is given
+    CreateUser {
+        username: String,
+        #[arg(long)]
+        password: Option<String>,
+        /// Create the user as an admin
+        #[arg(long)]
+        admin: bool,
+    },
+    /// Set a new password for a user and lift any login lockout; a random one
+    /// is generated and printed if none is given
+    ResetPassword {
+        username: String,
+        #[arg(long)]
+        password: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DbCommand {
+    /// Rebuild the database file and truncate the WAL
+    Vacuum,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Load the config file and environment variables and report any error
+    Check,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportCommand {
+    /// Import feeds and categories from an OPML file
+    Opml {
+        file: PathBuf,
+        /// User who will own the imported feeds
+        #[arg(long)]
+        user: String,
+    },
+}
+
+/// Use the given password or generate one; the flag tells whether it was
+/// generated and must be shown to the operator
+fn resolve_password(password: Option<String>) -> AppResult<(String, bool)> {
+    let Some(password) = password else {
+        return Ok((generate_password(), true));
+    };
+    if password.len() < MIN_PASSWORD_LEN {
+        return Err(AppError::Validation(format!(
+            "Password must be at least {} characters",
+            MIN_PASSWORD_LEN
+        )));
+    }
+    Ok((password, false))
+}
+
+fn find_user(conn: &Connection, username: &str) -> AppResult<user::User> {
+    user::find_by_username(conn, username)?.ok_or(AppError::UserNotFound)
+}
+
+/// Validate the configuration for `rdrs config check`, returning a summary
+/// of where the server would listen and store its data
+pub fn check_config(path: Option<&Path>) -> Result<String, ConfigError> {
+    let config = Config::load(path)?;
+    let listen = match config.listen_target().map_err(ConfigError::Conflict)? {
+        ListenTarget::Tcp { port } => format!("http://0.0.0.0:{}", port),
+        ListenTarget::Tls { port, .. } => format!("https://0.0.0.0:{}", port),
+        ListenTarget::Unix { path } => format!("unix:{}", path),
+    };
+
+    let mut lines = vec![
+        match path {
+            Some(path) => format!("Configuration OK ({} and environment)", path.display()),
+            None => "Configuration OK (environment only)".to_string(),
+        },
+        format!("Listen: {}", listen),
+        format!("Database: {}", config.database_url),
+    ];
+    if config.image_proxy_secret_generated {
+        lines.push(
+            "Warning: IMAGE_PROXY_SECRET is not set; proxy URLs will break on restart".to_string(),
+        );
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Run a non-server command, returning the message to print on success
+pub fn run(conn: &Connection, command: Command) -> AppResult<String> {
+    match command {
+        Command::Serve => Err(AppError::Internal(
+            "serve is handled by the binary".to_string(),
+        )),
+        Command::Config(_) => Err(AppError::Internal(
+            "config is handled by the binary".to_string(),
+        )),
+        Command::Admin(AdminCommand::CreateUser {
+            username,
+            password,
+            admin,
+        }) => {
+            if username.is_empty() {
+                return Err(AppError::Validation("Username is required".to_string()));
+            }
+            let (password, generated) = resolve_password(password)?;
+            let role = if admin { Role::Admin } else { Role::User };
+            let user = user::create_user(conn, &username, &hash_password(&password)?, role)?;
+
+            let mut message = format!("Created {} '{}'", user.role.as_str(), user.username);
+            if generated {
+                message.push_str(&format!("\nPassword: {}", password));
+            }
+            Ok(message)
+        }
+        Command::Admin(AdminCommand::ResetPassword { username, password }) => {
+            let user = find_user(conn, &username)?;
+            let (password, generated) = resolve_password(password)?;
+            user::update_password(conn, user.id, &hash_password(&password)?)?;
+            user::unlock_user(conn, user.id)?;
+            login_attempt::clear(conn, user.id)?;
+
+            let mut message = format!("Password reset for '{}'", user.username);
+            if generated {
+                message.push_str(&format!("\nPassword: {}", password));
+            }
+            Ok(message)
+        }
+        Command::Db(DbCommand::Vacuum) => {
+            conn.execute_batch("VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")?;
+            Ok("Database vacuumed".to_string())
+        }
+        Command::Import(ImportCommand::Opml { file, user }) => {
+            let user = find_user(conn, &user)?;
+            let reader = std::fs::File::open(&file).map_err(|e| {
+                AppError::Validation(format!("Failed to read {}: {}", file.display(), e))
+            })?;
+            let outlines = opml::parse_opml_reader(std::io::BufReader::new(reader))?;
+            let result = opml::import_outlines(conn, user.id, outlines)?;
+            Ok(format!(
+                "Imported {} feeds ({} skipped), created {} categories",
+                result.feeds_created, result.feeds_skipped, result.categories_created
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::verify_password;
+    use crate::db::init_db;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn parse(args: &[&str]) -> Command {
+        Cli::try_parse_from(args).unwrap().command.unwrap()
+    }
+
+    #[test]
+    fn test_parse_without_subcommand() {
+        let cli = Cli::try_parse_from(["rdrs"]).unwrap();
+        assert!(cli.command.is_none());
+    }
+
+    #[test]
+    fn test_parse_config_flag() {
+        let cli =
+            Cli::try_parse_from(["rdrs", "config", "check", "--config", "rdrs.toml"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Config(ConfigCommand::Check))
+        ));
+        assert_eq!(cli.config_path(), Some(PathBuf::from("rdrs.toml")));
+
+        let error = check_config(Some(Path::new("/nonexistent/rdrs.toml"))).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Cannot read /nonexistent/rdrs.toml"));
+    }
+
+    #[test]
+    fn test_create_user_and_reset_password() {
+        let conn = setup_db();
+
+        let command = parse(&["rdrs", "admin", "create-user", "alice", "--admin"]);
+        let message = run(&conn, command).unwrap();
+        assert!(message.starts_with("Created admin 'alice'\nPassword: "));
+
+        let alice = user::find_by_username(&conn, "alice").unwrap().unwrap();
+        assert_eq!(alice.role, Role::Admin);
+
+        let command = parse(&[
+            "rdrs",
+            "admin",
+            "reset-password",
+            "alice",
+            "--password",
+            "new-secret",
+        ]);
+        let message = run(&conn, command).unwrap();
+        assert_eq!(message, "Password reset for 'alice'");
+
+        let alice = user::find_by_username(&conn, "alice").unwrap().unwrap();
+        assert!(verify_password("new-secret", &alice.password_hash));
+    }
+
+    #[test]
+    fn test_reset_password_validation() {
+        let conn = setup_db();
+
+        let command = parse(&["rdrs", "admin", "reset-password", "nobody"]);
+        assert!(matches!(run(&conn, command), Err(AppError::UserNotFound)));
+
+        run(&conn, parse(&["rdrs", "admin", "create-user", "bob"])).unwrap();
+        let command = parse(&[
+            "rdrs",
+            "admin",
+            "reset-password",
+            "bob",
+            "--password",
+            "abc",
+        ]);
+        assert!(matches!(run(&conn, command), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn test_import_opml() {
+        let conn = setup_db();
+        run(&conn, parse(&["rdrs", "admin", "create-user", "carol"])).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("feeds.opml");
+        std::fs::write(
+            &path,
+            r#"<?xml version="1.0"?>
+<opml version="2.0"><body>
+  <outline text="Tech">
+    <outline type="rss" text="Rust" xmlUrl="https://blog.rust-lang.org/feed.xml"/>
+  </outline>
+</body></opml>"#,
+        )
+        .unwrap();
+
+        let file = path.to_str().unwrap();
+        let command = parse(&["rdrs", "import", "opml", file, "--user", "carol"]);
+        let message = run(&conn, command).unwrap();
+        assert_eq!(
+            message,
+            "Imported 1 feeds (0 skipped), created 1 categories"
+        );
+    }
+
+    #[test]
+    fn test_vacuum() {
+        let conn = setup_db();
+        let message = run(&conn, parse(&["rdrs", "db", "vacuum"])).unwrap();
+        assert_eq!(message, "Database vacuumed");
+    }
+}
@@ -1,6 +1,10 @@
 use base64::{engine::general_purpose::STANDARD, Engine};
 use rand::RngCore;
+use std::collections::BTreeMap;
 use std::env;
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
 
 /// Default user agent for HTTP requests (transparent and responsible crawling)
 pub const DEFAULT_USER_AGENT: &str = concat!(
@@ -9,70 +13,466 @@ pub const DEFAULT_USER_AGENT: &str = concat!(
     " (RSS Reader; +https://github.com/henry40408/rdrs)"
 );
 
+/// Default idle lifetime of a remembered session (7 days)
+pub const DEFAULT_SESSION_TTL_SECS: i64 = 7 * 24 * 3600;
+
+/// Default idle lifetime of a session that was not remembered (2 hours)
+pub const DEFAULT_SESSION_SHORT_TTL_SECS: i64 = 2 * 3600;
+
+/// Default time requests in flight at shutdown get to finish (30 seconds)
+pub const DEFAULT_SHUTDOWN_DRAIN_TIMEOUT_SECS: i64 = 30;
+
+/// Default largest request body (2 MiB)
+pub const DEFAULT_MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Default largest upload to the import endpoints (256 MiB)
+pub const DEFAULT_MAX_IMPORT_BYTES: usize = 256 * 1024 * 1024;
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub database_url: String,
+    pub db_reader_count: usize,
     pub server_port: u16,
+    /// Initial value; admins can change it at runtime
     pub signup_enabled: bool,
     pub multi_user_enabled: bool,
     pub image_proxy_secret: Vec<u8>,
     pub image_proxy_secret_generated: bool,
+    /// Retired image proxy secrets still accepted when verifying proxy URLs
+    pub image_proxy_previous_secrets: Vec<Vec<u8>>,
+    /// Initial value; admins can change it at runtime
     pub user_agent: String,
     pub webauthn_rp_id: String,
     pub webauthn_rp_origin: String,
     pub webauthn_rp_name: String,
+    pub vapid_private_key: Option<String>,
+    pub vapid_subject: String,
+    pub tls_cert_path: Option<String>,
+    pub tls_key_path: Option<String>,
+    pub unix_socket_path: Option<String>,
+    /// Bind the TCP port with `SO_REUSEPORT`, so a new process can start
+    /// listening before the old one stops
+    pub reuse_port: bool,
+    /// How long requests in flight at shutdown may take to finish before
+    /// their connections are dropped
+    pub shutdown_drain_timeout_secs: i64,
+    /// Largest request body accepted, except by the import endpoints
+    pub max_body_bytes: usize,
+    /// Largest OPML, bookmark or backup upload accepted
+    pub max_import_bytes: usize,
+    /// Emit logs as JSON lines instead of human-readable text
+    pub log_json: bool,
+    /// Idle time after which a remembered session expires; activity renews it
+    pub session_ttl_secs: i64,
+    /// Idle time after which a session without "remember me" expires
+    pub session_short_ttl_secs: i64,
+    /// Key for encrypting per-feed cookie jars; feed cookies are disabled when unset
+    pub feed_cookie_secret: Option<Vec<u8>>,
+    /// Make no outbound requests beyond feeds, the pages and images they
+    /// link to, and services a user or admin set up
+    pub privacy_mode: bool,
+    /// Check GitHub for newer releases every day and tell admins about them
+    pub update_check_enabled: bool,
+}
+
+/// Where the HTTP server accepts connections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenTarget {
+    /// Plain HTTP on `0.0.0.0:<port>`
+    Tcp { port: u16 },
+    /// HTTPS on `0.0.0.0:<port>` with a PEM certificate chain and private key
+    Tls {
+        port: u16,
+        cert_path: String,
+        key_path: String,
+    },
+    /// Plain HTTP on a Unix domain socket
+    Unix { path: String },
+}
+
+/// Settings that can be set, as environment variables; in a config file the
+/// same names are written in lower case
+const SETTINGS: &[&str] = &[
+    "DATABASE_URL",
+    "DB_READERS",
+    "SERVER_PORT",
+    "SIGNUP_ENABLED",
+    "MULTI_USER_ENABLED",
+    "IMAGE_PROXY_SECRET",
+    "IMAGE_PROXY_PREVIOUS_SECRETS",
+    "USER_AGENT",
+    "WEBAUTHN_RP_ID",
+    "WEBAUTHN_RP_ORIGIN",
+    "WEBAUTHN_RP_NAME",
+    "VAPID_PRIVATE_KEY",
+    "VAPID_SUBJECT",
+    "TLS_CERT_PATH",
+    "TLS_KEY_PATH",
+    "UNIX_SOCKET_PATH",
+    "REUSE_PORT",
+    "SHUTDOWN_DRAIN_TIMEOUT",
+    "MAX_BODY_SIZE",
+    "MAX_IMPORT_SIZE",
+    "LOG_FORMAT",
+    "SESSION_TTL",
+    "SESSION_SHORT_TTL",
+    "FEED_COOKIE_SECRET",
+    "PRIVACY_MODE",
+    "UPDATE_CHECK_ENABLED",
+];
+
+/// Why the configuration could not be loaded
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ConfigError {
+    #[error("Cannot read {path}: {message}")]
+    File { path: String, message: String },
+    /// `key` names the setting as it was given: an environment variable, or
+    /// a key and the file it is in
+    #[error("{key}: {message}")]
+    Invalid { key: String, message: String },
+    /// Settings that are fine on their own but not together
+    #[error("{0}")]
+    Conflict(String),
+}
+
+fn invalid(key: String, message: impl Into<String>) -> ConfigError {
+    ConfigError::Invalid {
+        key,
+        message: message.into(),
+    }
+}
+
+/// Settings from a config file, each overridden by its environment variable
+struct Loader<'a> {
+    /// Setting → (value, name of the key in the file)
+    file: BTreeMap<String, (String, String)>,
+    env: &'a dyn Fn(&str) -> Option<String>,
+}
+
+impl<'a> Loader<'a> {
+    fn new(
+        file: Option<(&str, &str)>,
+        env: &'a dyn Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let mut settings = BTreeMap::new();
+        if let Some((path, contents)) = file {
+            let table = contents
+                .parse::<toml::Table>()
+                .map_err(|e| ConfigError::File {
+                    path: path.to_string(),
+                    message: e.to_string(),
+                })?;
+            for (key, value) in table {
+                let name = format!("{} in {}", key, path);
+                let setting = key.to_uppercase();
+                if !SETTINGS.contains(&setting.as_str()) {
+                    return Err(invalid(name, "unknown setting"));
+                }
+                let value = match value {
+                    toml::Value::String(s) => s,
+                    toml::Value::Integer(n) => n.to_string(),
+                    toml::Value::Boolean(b) => b.to_string(),
+                    toml::Value::Array(items) => items
+                        .into_iter()
+                        .map(|item| match item {
+                            toml::Value::String(s) => Ok(s),
+                            _ => Err(invalid(name.clone(), "expected a list of strings")),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?
+                        .join(","),
+                    _ => return Err(invalid(name, "expected a string, number or boolean")),
+                };
+                settings.insert(setting, (value, name));
+            }
+        }
+        Ok(Loader {
+            file: settings,
+            env,
+        })
+    }
+
+    /// The value of `setting` and its name for errors; empty means unset
+    fn get(&self, setting: &str) -> Option<(String, String)> {
+        if let Some(value) = (self.env)(setting).filter(|v| !v.is_empty()) {
+            return Some((value, setting.to_string()));
+        }
+        self.file
+            .get(setting)
+            .filter(|(v, _)| !v.is_empty())
+            .cloned()
+    }
+
+    fn string(&self, setting: &str) -> Option<String> {
+        self.get(setting).map(|(value, _)| value)
+    }
+
+    fn parse<T: FromStr>(&self, setting: &str, expected: &str) -> Result<Option<T>, ConfigError> {
+        self.get(setting)
+            .map(|(value, key)| {
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid(key, format!("expected {}, got '{}'", expected, value)))
+            })
+            .transpose()
+    }
+
+    fn flag(&self, setting: &str) -> Result<bool, ConfigError> {
+        let Some((value, key)) = self.get(setting) else {
+            return Ok(false);
+        };
+        match value.to_lowercase().as_str() {
+            "true" | "1" => Ok(true),
+            "false" | "0" => Ok(false),
+            _ => Err(invalid(
+                key,
+                format!("expected true or false, got '{}'", value),
+            )),
+        }
+    }
+
+    fn duration(&self, setting: &str, default: i64) -> Result<i64, ConfigError> {
+        let Some((value, key)) = self.get(setting) else {
+            return Ok(default);
+        };
+        parse_duration_secs(&value)
+            .filter(|secs| *secs > 0)
+            .ok_or_else(|| {
+                invalid(
+                    key,
+                    format!("expected a duration such as 30m or 7d, got '{}'", value),
+                )
+            })
+    }
+
+    fn size(&self, setting: &str, default: usize) -> Result<usize, ConfigError> {
+        let Some((value, key)) = self.get(setting) else {
+            return Ok(default);
+        };
+        parse_size_bytes(&value)
+            .filter(|bytes| *bytes > 0)
+            .ok_or_else(|| {
+                invalid(
+                    key,
+                    format!("expected a size such as 512k or 2m, got '{}'", value),
+                )
+            })
+    }
+
+    fn secret(&self, setting: &str) -> Result<Option<Vec<u8>>, ConfigError> {
+        self.get(setting)
+            .map(|(value, key)| decode_secret(&value).ok_or_else(|| invalid(key, SECRET_FORMAT)))
+            .transpose()
+    }
+
+    fn secrets(&self, setting: &str) -> Result<Vec<Vec<u8>>, ConfigError> {
+        let Some((value, key)) = self.get(setting) else {
+            return Ok(Vec::new());
+        };
+        value
+            .split(',')
+            .map(|s| decode_secret(s.trim()).ok_or_else(|| invalid(key.clone(), SECRET_FORMAT)))
+            .collect()
+    }
 }
 
+const SECRET_FORMAT: &str = "expected base64 of at least 16 bytes or at least 16 characters";
+
 impl Config {
-    pub fn from_env() -> Self {
-        let (image_proxy_secret, image_proxy_secret_generated) = Self::load_image_proxy_secret();
-        let server_port = env::var("SERVER_PORT")
-            .ok()
-            .and_then(|p| p.parse().ok())
+    /// Load the configuration: defaults, then the TOML config file at `path`
+    /// if given, then environment variables, each overriding the one before
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let file = path
+            .map(|path| {
+                let name = path.display().to_string();
+                std::fs::read_to_string(path)
+                    .map(|contents| (name.clone(), contents))
+                    .map_err(|e| ConfigError::File {
+                        path: name,
+                        message: e.to_string(),
+                    })
+            })
+            .transpose()?;
+        let file = file
+            .as_ref()
+            .map(|(name, contents)| (name.as_str(), contents.as_str()));
+        Self::from_sources(file, &|setting| env::var(setting).ok())
+    }
+
+    fn from_sources(
+        file: Option<(&str, &str)>,
+        env: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let settings = Loader::new(file, env)?;
+        let server_port = settings
+            .parse("SERVER_PORT", "a port number")?
             .unwrap_or(3000);
+        let (image_proxy_secret, image_proxy_secret_generated) =
+            match settings.secret("IMAGE_PROXY_SECRET")? {
+                Some(secret) => (secret, false),
+                None => (generate_secret(), true),
+            };
+        let log_json = match settings.get("LOG_FORMAT") {
+            None => false,
+            Some((value, _)) if value.eq_ignore_ascii_case("json") => true,
+            Some((value, _)) if value.eq_ignore_ascii_case("text") => false,
+            Some((value, key)) => {
+                return Err(invalid(
+                    key,
+                    format!("expected text or json, got '{}'", value),
+                ))
+            }
+        };
 
-        Self {
-            database_url: env::var("DATABASE_URL").unwrap_or_else(|_| "rdrs.sqlite3".to_string()),
+        let config = Self {
+            database_url: settings
+                .string("DATABASE_URL")
+                .unwrap_or_else(|| "rdrs.sqlite3".to_string()),
+            db_reader_count: settings.parse("DB_READERS", "a number")?.unwrap_or(4),
             server_port,
-            signup_enabled: env::var("SIGNUP_ENABLED")
-                .map(|v| v.to_lowercase() == "true" || v == "1")
-                .unwrap_or(false),
-            multi_user_enabled: env::var("MULTI_USER_ENABLED")
-                .map(|v| v.to_lowercase() == "true" || v == "1")
-                .unwrap_or(false),
+            signup_enabled: settings.flag("SIGNUP_ENABLED")?,
+            multi_user_enabled: settings.flag("MULTI_USER_ENABLED")?,
             image_proxy_secret,
             image_proxy_secret_generated,
-            user_agent: env::var("USER_AGENT").unwrap_or_else(|_| DEFAULT_USER_AGENT.to_string()),
-            webauthn_rp_id: env::var("WEBAUTHN_RP_ID").unwrap_or_else(|_| "localhost".to_string()),
-            webauthn_rp_origin: env::var("WEBAUTHN_RP_ORIGIN")
-                .unwrap_or_else(|_| format!("http://localhost:{}", server_port)),
-            webauthn_rp_name: env::var("WEBAUTHN_RP_NAME").unwrap_or_else(|_| "rdrs".to_string()),
-        }
+            image_proxy_previous_secrets: settings.secrets("IMAGE_PROXY_PREVIOUS_SECRETS")?,
+            user_agent: settings
+                .string("USER_AGENT")
+                .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string()),
+            webauthn_rp_id: settings
+                .string("WEBAUTHN_RP_ID")
+                .unwrap_or_else(|| "localhost".to_string()),
+            webauthn_rp_origin: settings
+                .string("WEBAUTHN_RP_ORIGIN")
+                .unwrap_or_else(|| format!("http://localhost:{}", server_port)),
+            webauthn_rp_name: settings
+                .string("WEBAUTHN_RP_NAME")
+                .unwrap_or_else(|| "rdrs".to_string()),
+            vapid_private_key: settings.string("VAPID_PRIVATE_KEY"),
+            vapid_subject: settings
+                .string("VAPID_SUBJECT")
+                .unwrap_or_else(|| "mailto:admin@localhost".to_string()),
+            tls_cert_path: settings.string("TLS_CERT_PATH"),
+            tls_key_path: settings.string("TLS_KEY_PATH"),
+            unix_socket_path: settings.string("UNIX_SOCKET_PATH"),
+            reuse_port: settings.flag("REUSE_PORT")?,
+            shutdown_drain_timeout_secs: settings.duration(
+                "SHUTDOWN_DRAIN_TIMEOUT",
+                DEFAULT_SHUTDOWN_DRAIN_TIMEOUT_SECS,
+            )?,
+            max_body_bytes: settings.size("MAX_BODY_SIZE", DEFAULT_MAX_BODY_BYTES)?,
+            max_import_bytes: settings.size("MAX_IMPORT_SIZE", DEFAULT_MAX_IMPORT_BYTES)?,
+            log_json,
+            session_ttl_secs: settings.duration("SESSION_TTL", DEFAULT_SESSION_TTL_SECS)?,
+            session_short_ttl_secs: settings
+                .duration("SESSION_SHORT_TTL", DEFAULT_SESSION_SHORT_TTL_SECS)?,
+            feed_cookie_secret: settings.secret("FEED_COOKIE_SECRET")?,
+            privacy_mode: settings.flag("PRIVACY_MODE")?,
+            update_check_enabled: settings.flag("UPDATE_CHECK_ENABLED")?,
+        };
+        config.listen_target().map_err(ConfigError::Conflict)?;
+        Ok(config)
     }
 
-    fn load_image_proxy_secret() -> (Vec<u8>, bool) {
-        if let Ok(secret_str) = env::var("IMAGE_PROXY_SECRET") {
-            // Try to decode as base64 first
-            if let Ok(decoded) = STANDARD.decode(&secret_str) {
-                if decoded.len() >= 16 {
-                    return (decoded, false);
-                }
+    /// Secrets accepted for image proxy URLs, current secret first
+    pub fn image_proxy_secrets(&self) -> Vec<&[u8]> {
+        std::iter::once(self.image_proxy_secret.as_slice())
+            .chain(self.image_proxy_previous_secrets.iter().map(Vec::as_slice))
+            .collect()
+    }
+
+    pub fn push_enabled(&self) -> bool {
+        self.vapid_private_key.is_some()
+    }
+
+    /// Resolve the listener from the TLS and Unix socket settings
+    pub fn listen_target(&self) -> Result<ListenTarget, String> {
+        match (
+            &self.unix_socket_path,
+            &self.tls_cert_path,
+            &self.tls_key_path,
+        ) {
+            (Some(_), Some(_), _) | (Some(_), _, Some(_)) => {
+                Err("UNIX_SOCKET_PATH cannot be combined with TLS_CERT_PATH/TLS_KEY_PATH".into())
             }
-            // Use raw bytes if at least 16 characters
-            if secret_str.len() >= 16 {
-                return (secret_str.into_bytes(), false);
+            (Some(path), None, None) => Ok(ListenTarget::Unix { path: path.clone() }),
+            (None, Some(cert_path), Some(key_path)) => Ok(ListenTarget::Tls {
+                port: self.server_port,
+                cert_path: cert_path.clone(),
+                key_path: key_path.clone(),
+            }),
+            (None, Some(_), None) | (None, None, Some(_)) => {
+                Err("TLS_CERT_PATH and TLS_KEY_PATH must be set together".into())
             }
+            (None, None, None) => Ok(ListenTarget::Tcp {
+                port: self.server_port,
+            }),
         }
+    }
 
-        // Generate a random 32-byte secret
-        let mut secret = vec![0u8; 32];
-        rand::thread_rng().fill_bytes(&mut secret);
-        (secret, true)
+    /// Idle lifetime of a remembered (`persistent`) or short-lived session
+    pub fn session_ttl(&self, persistent: bool) -> chrono::Duration {
+        if persistent {
+            chrono::Duration::seconds(self.session_ttl_secs)
+        } else {
+            chrono::Duration::seconds(self.session_short_ttl_secs)
+        }
     }
+}
 
-    pub fn can_register(&self, user_count: i64) -> bool {
-        self.signup_enabled && (self.multi_user_enabled || user_count == 0)
+/// A random 32-byte secret, used when `IMAGE_PROXY_SECRET` is unset
+fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    secret
+}
+
+/// Decode a secret given as base64 (at least 16 bytes) or as raw text of at
+/// least 16 characters
+fn decode_secret(value: &str) -> Option<Vec<u8>> {
+    if let Ok(decoded) = STANDARD.decode(value) {
+        if decoded.len() >= 16 {
+            return Some(decoded);
+        }
     }
+    if value.len() >= 16 {
+        return Some(value.as_bytes().to_vec());
+    }
+    None
+}
+
+/// Parse a duration such as `3600`, `90s`, `30m`, `12h` or `7d` into seconds
+pub fn parse_duration_secs(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => value.split_at(i),
+        None => (value, "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return None,
+    };
+    number.parse::<i64>().ok()?.checked_mul(multiplier)
+}
+
+/// Parse a size such as `512k`, `2m` or `1g` (in KiB, MiB or GiB), or a
+/// plain number of bytes
+pub fn parse_size_bytes(value: &str) -> Option<usize> {
+    let value = value.trim().to_ascii_lowercase();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => value.split_at(i),
+        None => (value.as_str(), ""),
+    };
+    let multiplier = match unit.trim_end_matches(['b', 'i']) {
+        "" => 1,
+        "k" => 1024,
+        "m" => 1024 * 1024,
+        "g" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.parse::<usize>().ok()?.checked_mul(multiplier)
 }
 
 #[cfg(test)]
@@ -87,31 +487,182 @@ mod tests {
             multi_user_enabled: false,
             image_proxy_secret: vec![0u8; 32],
             image_proxy_secret_generated: false,
+            image_proxy_previous_secrets: vec![],
             user_agent: DEFAULT_USER_AGENT.to_string(),
             webauthn_rp_id: "localhost".to_string(),
             webauthn_rp_origin: "http://localhost:3000".to_string(),
             webauthn_rp_name: "rdrs".to_string(),
+            vapid_private_key: None,
+            vapid_subject: "mailto:admin@localhost".to_string(),
+            tls_cert_path: None,
+            tls_key_path: None,
+            unix_socket_path: None,
+            reuse_port: false,
+            shutdown_drain_timeout_secs: 30,
+            max_body_bytes: 2 * 1024 * 1024,
+            max_import_bytes: 256 * 1024 * 1024,
+            log_json: false,
+            session_ttl_secs: DEFAULT_SESSION_TTL_SECS,
+            session_short_ttl_secs: DEFAULT_SESSION_SHORT_TTL_SECS,
+            feed_cookie_secret: None,
+            privacy_mode: false,
+            update_check_enabled: false,
+            db_reader_count: 0,
         }
     }
 
+    fn load(file: Option<&str>, vars: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        let vars: BTreeMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Config::from_sources(file.map(|contents| ("rdrs.toml", contents)), &|setting| {
+            vars.get(setting).cloned()
+        })
+    }
+
+    fn load_error(file: Option<&str>, vars: &[(&str, &str)]) -> String {
+        load(file, vars).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_layered_sources() {
+        let config = load(None, &[]).unwrap();
+        assert_eq!(config.database_url, "rdrs.sqlite3");
+        assert_eq!(config.server_port, 3000);
+        assert!(config.image_proxy_secret_generated);
+        assert_eq!(
+            config.shutdown_drain_timeout_secs,
+            DEFAULT_SHUTDOWN_DRAIN_TIMEOUT_SECS
+        );
+
+        let file = r#"
+            database_url = "/data/rdrs.sqlite3"
+            server_port = 8080
+            signup_enabled = true
+            session_ttl = "30d"
+            reuse_port = true
+            shutdown_drain_timeout = "2m"
+            image_proxy_previous_secrets = ["an-old-proxy-secret", "an-older-proxy-secret"]
+        "#;
+        let config = load(Some(file), &[("SERVER_PORT", "9000"), ("DATABASE_URL", "")]).unwrap();
+        // Environment variables win, unless they are empty
+        assert_eq!(config.server_port, 9000);
+        assert_eq!(config.database_url, "/data/rdrs.sqlite3");
+        assert!(config.signup_enabled);
+        assert_eq!(config.session_ttl_secs, 30 * 86400);
+        assert!(config.reuse_port);
+        assert_eq!(config.shutdown_drain_timeout_secs, 120);
+        assert_eq!(config.image_proxy_previous_secrets.len(), 2);
+        assert_eq!(config.webauthn_rp_origin, "http://localhost:9000");
+    }
+
+    #[test]
+    fn test_errors_name_the_setting() {
+        assert_eq!(
+            load_error(None, &[("SERVER_PORT", "eighty")]),
+            "SERVER_PORT: expected a port number, got 'eighty'"
+        );
+        assert_eq!(
+            load_error(Some("signup_enabled = \"yes\""), &[]),
+            "signup_enabled in rdrs.toml: expected true or false, got 'yes'"
+        );
+        assert_eq!(
+            load_error(Some("server_prot = 80"), &[]),
+            "server_prot in rdrs.toml: unknown setting"
+        );
+        assert_eq!(
+            load_error(None, &[("FEED_COOKIE_SECRET", "short")]),
+            format!("FEED_COOKIE_SECRET: {}", SECRET_FORMAT)
+        );
+        assert!(load_error(Some("server_port ="), &[]).starts_with("Cannot read rdrs.toml"));
+
+        let conflict = load(
+            None,
+            &[
+                ("UNIX_SOCKET_PATH", "/run/rdrs.sock"),
+                ("TLS_CERT_PATH", "cert.pem"),
+                ("TLS_KEY_PATH", "key.pem"),
+            ],
+        );
+        assert!(matches!(conflict, Err(ConfigError::Conflict(_))));
+    }
+
+    #[test]
+    fn test_parse_duration_secs() {
+        assert_eq!(parse_duration_secs("3600"), Some(3600));
+        assert_eq!(parse_duration_secs("90s"), Some(90));
+        assert_eq!(parse_duration_secs("30m"), Some(1800));
+        assert_eq!(parse_duration_secs("12h"), Some(43200));
+        assert_eq!(parse_duration_secs(" 7d "), Some(604800));
+        assert_eq!(parse_duration_secs("7w"), None);
+        assert_eq!(parse_duration_secs("d"), None);
+        assert_eq!(parse_duration_secs(""), None);
+    }
+
     #[test]
-    fn test_can_register() {
+    fn test_parse_size_bytes() {
+        assert_eq!(parse_size_bytes("4096"), Some(4096));
+        assert_eq!(parse_size_bytes("512k"), Some(512 * 1024));
+        assert_eq!(parse_size_bytes(" 2M "), Some(2 * 1024 * 1024));
+        assert_eq!(parse_size_bytes("2MiB"), Some(2 * 1024 * 1024));
+        assert_eq!(parse_size_bytes("1gb"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse_size_bytes("2t"), None);
+        assert_eq!(parse_size_bytes("m"), None);
+        assert_eq!(parse_size_bytes(""), None);
+    }
+
+    #[test]
+    fn test_decode_secret() {
+        let encoded = STANDARD.encode([7u8; 32]);
+        assert_eq!(decode_secret(&encoded), Some(vec![7u8; 32]));
+        assert_eq!(
+            decode_secret("a-long-raw-secret"),
+            Some(b"a-long-raw-secret".to_vec())
+        );
+        assert_eq!(decode_secret("short"), None);
+    }
+
+    #[test]
+    fn test_listen_target() {
         let config = test_config();
+        assert_eq!(config.listen_target(), Ok(ListenTarget::Tcp { port: 3000 }));
 
-        assert!(config.can_register(0));
-        assert!(!config.can_register(1));
+        let tls = Config {
+            tls_cert_path: Some("cert.pem".to_string()),
+            tls_key_path: Some("key.pem".to_string()),
+            ..config.clone()
+        };
+        assert_eq!(
+            tls.listen_target(),
+            Ok(ListenTarget::Tls {
+                port: 3000,
+                cert_path: "cert.pem".to_string(),
+                key_path: "key.pem".to_string(),
+            })
+        );
+
+        let unix = Config {
+            unix_socket_path: Some("/run/rdrs.sock".to_string()),
+            ..config.clone()
+        };
+        assert_eq!(
+            unix.listen_target(),
+            Ok(ListenTarget::Unix {
+                path: "/run/rdrs.sock".to_string()
+            })
+        );
 
-        let config_multi = Config {
-            multi_user_enabled: true,
+        let cert_only = Config {
+            tls_cert_path: Some("cert.pem".to_string()),
             ..config.clone()
         };
-        assert!(config_multi.can_register(0));
-        assert!(config_multi.can_register(5));
+        assert!(cert_only.listen_target().is_err());
 
-        let config_disabled = Config {
-            signup_enabled: false,
-            ..config
+        let both = Config {
+            unix_socket_path: Some("/run/rdrs.sock".to_string()),
+            ..tls
         };
-        assert!(!config_disabled.can_register(0));
+        assert!(both.listen_target().is_err());
     }
 }
@@ -1,5 +1,5 @@
 pub mod pool;
 pub mod schema;
 
-pub use pool::{DbError, DbPool};
+pub use pool::{open_readers, DbError, DbPool};
 pub use schema::init_db;
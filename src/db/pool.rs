@@ -1,7 +1,8 @@
 use std::fmt;
+use std::sync::{Arc, Mutex};
 
-use rusqlite::Connection;
-use tokio::sync::{mpsc, oneshot};
+use rusqlite::{Connection, OpenFlags};
+use tokio::sync::{mpsc, oneshot, Semaphore};
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info};
 
@@ -38,14 +39,40 @@ struct DbMessage {
     respond: oneshot::Sender<Box<dyn std::any::Any + Send>>,
 }
 
-/// A prioritized database connection pool backed by a single SQLite connection.
+/// Read-only connections checked out one at a time by `DbPool::read`.
+struct ReaderPool {
+    conns: Mutex<Vec<Connection>>,
+    permits: Semaphore,
+}
+
+/// A prioritized database connection pool backed by a single SQLite writer connection.
 ///
-/// All database access goes through an actor task that owns the `Connection`.
+/// All writes go through an actor task that owns the writer `Connection`.
 /// User-priority work is always processed before background-priority work.
+/// Optional read-only connections serve `read` calls concurrently with the writer.
 #[derive(Clone)]
 pub struct DbPool {
     user_tx: mpsc::Sender<DbMessage>,
     bg_tx: mpsc::Sender<DbMessage>,
+    readers: Option<Arc<ReaderPool>>,
+}
+
+/// Open `count` read-only connections to the database file at `path`.
+///
+/// Readers rely on WAL mode so they never block the writer.
+pub fn open_readers(path: &str, count: usize) -> rusqlite::Result<Vec<Connection>> {
+    (0..count)
+        .map(|_| {
+            let conn = Connection::open_with_flags(
+                path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY
+                    | OpenFlags::SQLITE_OPEN_URI
+                    | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )?;
+            conn.execute_batch("PRAGMA query_only = ON; PRAGMA foreign_keys = ON;")?;
+            Ok(conn)
+        })
+        .collect()
 }
 
 impl DbPool {
@@ -66,7 +93,29 @@ impl DbPool {
 
         let handle = tokio::spawn(actor_loop(conn, user_rx, bg_rx));
 
-        (DbPool { user_tx, bg_tx }, handle)
+        (
+            DbPool {
+                user_tx,
+                bg_tx,
+                readers: None,
+            },
+            handle,
+        )
+    }
+
+    /// Create a DbPool with a writer connection and a set of read-only connections.
+    ///
+    /// With no readers this behaves exactly like `new` and `read` runs on the writer.
+    pub fn with_readers(conn: Connection, readers: Vec<Connection>) -> (Self, JoinHandle<()>) {
+        let (mut pool, handle) = Self::new(conn);
+        if !readers.is_empty() {
+            debug!("Database read pool with {} connections", readers.len());
+            pool.readers = Some(Arc::new(ReaderPool {
+                permits: Semaphore::new(readers.len()),
+                conns: Mutex::new(readers),
+            }));
+        }
+        (pool, handle)
     }
 
     /// Gracefully shutdown the database connection.
@@ -136,6 +185,52 @@ impl DbPool {
     {
         self.execute(DbPriority::Background, f).await
     }
+
+    /// Execute a read-only closure on a reader connection (for SELECT-heavy handlers).
+    ///
+    /// Falls back to the writer with User priority when no readers are configured.
+    /// Writes attempted here fail because readers are opened with `query_only`.
+    pub async fn read<F, T>(&self, f: F) -> Result<T, DbError>
+    where
+        F: FnOnce(&Connection) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let Some(readers) = self.readers.clone() else {
+            return self.user(f).await;
+        };
+
+        let permit = readers
+            .permits
+            .acquire()
+            .await
+            .map_err(|_| DbError::ActorStopped)?;
+        let conn = readers
+            .conns
+            .lock()
+            .map_err(|_| DbError::ActorStopped)?
+            .pop()
+            .ok_or(DbError::ActorStopped)?;
+
+        match tokio::task::spawn_blocking(move || {
+            let result = f(&conn);
+            (conn, result)
+        })
+        .await
+        {
+            Ok((conn, result)) => {
+                if let Ok(mut conns) = readers.conns.lock() {
+                    conns.push(conn);
+                }
+                drop(permit);
+                Ok(result)
+            }
+            Err(_) => {
+                // The closure panicked and took its connection with it
+                permit.forget();
+                Err(DbError::ActorStopped)
+            }
+        }
+    }
 }
 
 /// The actor loop that owns the Connection and processes messages.
@@ -227,6 +322,84 @@ mod tests {
         assert_eq!(result, "hello");
     }
 
+    #[tokio::test]
+    async fn test_read_falls_back_to_writer() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE test (id INTEGER PRIMARY KEY);")
+            .unwrap();
+
+        let (pool, _handle) = DbPool::new(conn);
+
+        let count = pool
+            .read(|conn| {
+                conn.query_row("SELECT COUNT(*) FROM test", [], |row| row.get::<_, i64>(0))
+                    .unwrap()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_read_uses_reader_connections() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("readers.sqlite3");
+        let path = path.to_str().unwrap();
+
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(
+            "PRAGMA journal_mode=WAL; CREATE TABLE test (id INTEGER PRIMARY KEY, value TEXT);",
+        )
+        .unwrap();
+        let readers = open_readers(path, 2).unwrap();
+        let (pool, _handle) = DbPool::with_readers(conn, readers);
+
+        pool.user(|conn| {
+            conn.execute("INSERT INTO test (value) VALUES ('hello')", [])
+                .unwrap();
+        })
+        .await
+        .unwrap();
+
+        // Committed writes are visible to readers
+        let value = pool
+            .read(|conn| {
+                conn.query_row("SELECT value FROM test WHERE id = 1", [], |row| {
+                    row.get::<_, String>(0)
+                })
+                .unwrap()
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, "hello");
+
+        // Readers reject writes
+        let write = pool
+            .read(|conn| conn.execute("INSERT INTO test (value) VALUES ('nope')", []))
+            .await
+            .unwrap();
+        assert!(write.is_err());
+
+        // Concurrent reads beyond the pool size wait for a free connection
+        let handles: Vec<_> = (0..5)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move {
+                    pool.read(|conn| {
+                        conn.query_row("SELECT COUNT(*) FROM test", [], |row| row.get::<_, i64>(0))
+                            .unwrap()
+                    })
+                    .await
+                    .unwrap()
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.await.unwrap(), 1);
+        }
+    }
+
     #[tokio::test]
     async fn test_background_execute() {
         let conn = Connection::open_in_memory().unwrap();
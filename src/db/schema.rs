@@ -1,6 +1,7 @@
 use rusqlite::Connection;
 
 use crate::error::AppResult;
+use crate::models::entry_cluster;
 
 pub fn init_db(conn: &Connection) -> AppResult<()> {
     conn.execute_batch(
@@ -96,6 +97,19 @@ pub fn init_db(conn: &Connection) -> AppResult<()> {
         CREATE INDEX IF NOT EXISTS idx_entry_summary_user_status ON entry_summary(user_id, status);
         CREATE INDEX IF NOT EXISTS idx_entry_summary_entry_id ON entry_summary(entry_id);
 
+        CREATE TABLE IF NOT EXISTS category_digest (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+            category_id INTEGER NOT NULL REFERENCES category(id) ON DELETE CASCADE,
+            status TEXT NOT NULL CHECK (status IN ('pending', 'processing', 'completed', 'failed')),
+            digest_text TEXT,
+            error_message TEXT,
+            entry_count INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(user_id, category_id)
+        );
+
         CREATE TABLE IF NOT EXISTS image (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             entity_type TEXT NOT NULL,
@@ -140,13 +154,343 @@ pub fn init_db(conn: &Connection) -> AppResult<()> {
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             challenge BLOB NOT NULL UNIQUE,
             user_id INTEGER REFERENCES user(id) ON DELETE CASCADE,
-            challenge_type TEXT NOT NULL CHECK (challenge_type IN ('registration', 'authentication')),
+            challenge_type TEXT NOT NULL CHECK (challenge_type IN ('registration', 'authentication', 'discoverable_authentication')),
             state_data TEXT NOT NULL,
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
             expires_at TEXT NOT NULL
         );
 
         CREATE INDEX IF NOT EXISTS idx_webauthn_challenge_expires_at ON webauthn_challenge(expires_at);
+
+        CREATE TABLE IF NOT EXISTS push_subscription (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+            endpoint TEXT NOT NULL UNIQUE,
+            p256dh TEXT NOT NULL,
+            auth TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_push_subscription_user_id ON push_subscription(user_id);
+
+        CREATE TABLE IF NOT EXISTS notification_rule (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+            feed_id INTEGER REFERENCES feed(id) ON DELETE CASCADE,
+            category_id INTEGER REFERENCES category(id) ON DELETE CASCADE,
+            keyword TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_notification_rule_user_id ON notification_rule(user_id);
+
+        CREATE TABLE IF NOT EXISTS feed_event (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            feed_id INTEGER NOT NULL REFERENCES feed(id) ON DELETE CASCADE,
+            event_type TEXT NOT NULL,
+            message TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_feed_event_feed_id ON feed_event(feed_id);
+
+        CREATE TABLE IF NOT EXISTS login_attempt (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_login_attempt_user_id ON login_attempt(user_id, created_at);
+
+        CREATE TABLE IF NOT EXISTS invite (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            token TEXT NOT NULL UNIQUE,
+            created_by INTEGER REFERENCES user(id) ON DELETE SET NULL,
+            used_by INTEGER REFERENCES user(id) ON DELETE SET NULL,
+            expires_at TEXT NOT NULL,
+            used_at TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS feed_cookie (
+            feed_id INTEGER PRIMARY KEY REFERENCES feed(id) ON DELETE CASCADE,
+            ciphertext BLOB NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS feed_rewrite (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            feed_id INTEGER NOT NULL REFERENCES feed(id) ON DELETE CASCADE,
+            field TEXT NOT NULL,
+            pattern TEXT NOT NULL,
+            replacement TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_feed_rewrite_feed_id ON feed_rewrite(feed_id);
+
+        CREATE TABLE IF NOT EXISTS feed_script (
+            feed_id INTEGER PRIMARY KEY REFERENCES feed(id) ON DELETE CASCADE,
+            source TEXT NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS blocked_host (
+            pattern TEXT NOT NULL,
+            source TEXT NOT NULL,
+            PRIMARY KEY (source, pattern)
+        );
+
+        CREATE TABLE IF NOT EXISTS blocklist_import (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            url TEXT,
+            imported_at TEXT,
+            last_error TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS instance_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            signup_enabled INTEGER,
+            user_agent TEXT,
+            sync_interval_hours INTEGER,
+            trash_retention_days INTEGER,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS change_seq (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            value INTEGER NOT NULL DEFAULT 0,
+            pruned_seq INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS entry_tombstone (
+            seq INTEGER PRIMARY KEY,
+            entry_id INTEGER NOT NULL,
+            user_id INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+            deleted_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_entry_tombstone_user_seq ON entry_tombstone(user_id, seq);
+
+        CREATE TABLE IF NOT EXISTS deleted_feed_owner (
+            feed_id INTEGER PRIMARY KEY,
+            user_id INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS author (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            feed_id INTEGER NOT NULL REFERENCES feed(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            email TEXT,
+            uri TEXT,
+            avatar_url TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(feed_id, name)
+        );
+
+        CREATE TABLE IF NOT EXISTS entry_author (
+            entry_id INTEGER NOT NULL REFERENCES entry(id) ON DELETE CASCADE,
+            author_id INTEGER NOT NULL REFERENCES author(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (entry_id, author_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_entry_author_author_id ON entry_author(author_id);
+
+        CREATE TABLE IF NOT EXISTS discussion_cache (
+            url TEXT PRIMARY KEY,
+            results TEXT NOT NULL,
+            fetched_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS related_cache (
+            entry_id INTEGER PRIMARY KEY REFERENCES entry(id) ON DELETE CASCADE,
+            related_ids TEXT NOT NULL,
+            computed_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS feed_weekly_stats (
+            feed_id INTEGER NOT NULL REFERENCES feed(id) ON DELETE CASCADE,
+            week TEXT NOT NULL,
+            entry_count INTEGER NOT NULL,
+            refreshed_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (feed_id, week)
+        );
+
+        CREATE TABLE IF NOT EXISTS entry_revision (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entry_id INTEGER NOT NULL REFERENCES entry(id) ON DELETE CASCADE,
+            title TEXT,
+            content TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_entry_revision_entry_id ON entry_revision(entry_id);
+
+        CREATE TABLE IF NOT EXISTS api_token (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            token TEXT NOT NULL UNIQUE,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            last_used_at TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_api_token_user_id ON api_token(user_id);
+
+        CREATE TABLE IF NOT EXISTS undo_action (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+            token TEXT NOT NULL UNIQUE,
+            payload TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_undo_action_created_at ON undo_action(created_at);
+
+        CREATE TABLE IF NOT EXISTS masquerade_audit (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            admin_id INTEGER REFERENCES user(id) ON DELETE SET NULL,
+            admin_username TEXT,
+            user_id INTEGER REFERENCES user(id) ON DELETE SET NULL,
+            username TEXT,
+            method TEXT NOT NULL,
+            path TEXT NOT NULL,
+            status INTEGER NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_masquerade_audit_admin_id ON masquerade_audit(admin_id);
+
+        CREATE TABLE IF NOT EXISTS notification (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+            kind TEXT NOT NULL,
+            title TEXT NOT NULL,
+            body TEXT,
+            link TEXT,
+            dedupe_key TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            read_at TEXT,
+            UNIQUE(user_id, dedupe_key)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_notification_user_id ON notification(user_id);
+
+        CREATE TABLE IF NOT EXISTS job (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            user_id INTEGER REFERENCES user(id) ON DELETE CASCADE,
+            payload TEXT NOT NULL,
+            dedupe_key TEXT,
+            priority INTEGER NOT NULL DEFAULT 0,
+            status TEXT NOT NULL DEFAULT 'pending' CHECK(status IN ('pending', 'running', 'completed', 'dead')),
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            run_after TEXT NOT NULL DEFAULT (datetime('now')),
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_job_status_run_after ON job(status, run_after);
+        CREATE INDEX IF NOT EXISTS idx_job_user_id ON job(user_id);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_job_active_dedupe_key
+            ON job(dedupe_key) WHERE status IN ('pending', 'running');
+
+        CREATE TABLE IF NOT EXISTS shared_summary (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            link_key TEXT NOT NULL UNIQUE,
+            summary_text TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS entry_question (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+            entry_id INTEGER NOT NULL REFERENCES entry(id) ON DELETE CASCADE,
+            question TEXT NOT NULL,
+            answer TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_entry_question_user_entry
+            ON entry_question(user_id, entry_id);
+
+        CREATE TABLE IF NOT EXISTS tag (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+            name TEXT NOT NULL COLLATE NOCASE,
+            description TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(user_id, name)
+        );
+
+        CREATE TABLE IF NOT EXISTS entry_tag (
+            entry_id INTEGER NOT NULL REFERENCES entry(id) ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tag(id) ON DELETE CASCADE,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (entry_id, tag_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_entry_tag_tag_id ON entry_tag(tag_id);
+
+        CREATE TABLE IF NOT EXISTS interest_model (
+            user_id INTEGER PRIMARY KEY REFERENCES user(id) ON DELETE CASCADE,
+            model TEXT NOT NULL,
+            examples INTEGER NOT NULL,
+            trained_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS entry_interest (
+            entry_id INTEGER PRIMARY KEY REFERENCES entry(id) ON DELETE CASCADE,
+            score REAL NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS entry_view (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+            entry_id INTEGER REFERENCES entry(id) ON DELETE SET NULL,
+            feed_id INTEGER REFERENCES feed(id) ON DELETE SET NULL,
+            opened_at TEXT NOT NULL DEFAULT (datetime('now')),
+            seconds INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_entry_view_user_opened ON entry_view(user_id, opened_at);
+
+        CREATE TABLE IF NOT EXISTS highlight (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+            entry_id INTEGER NOT NULL REFERENCES entry(id) ON DELETE CASCADE,
+            quote TEXT NOT NULL,
+            position_start INTEGER,
+            position_end INTEGER,
+            note TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_highlight_user_entry ON highlight(user_id, entry_id);
+
+        CREATE TABLE IF NOT EXISTS entry_note (
+            entry_id INTEGER PRIMARY KEY REFERENCES entry(id) ON DELETE CASCADE,
+            user_id INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+            content TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS entry_save (
+            entry_id INTEGER NOT NULL REFERENCES entry(id) ON DELETE CASCADE,
+            user_id INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+            service TEXT NOT NULL,
+            status TEXT NOT NULL,
+            message TEXT,
+            bookmark_url TEXT,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (entry_id, service)
+        );
         "#,
     )?;
 
@@ -160,6 +504,576 @@ pub fn init_db(conn: &Connection) -> AppResult<()> {
     // Migration: Add theme column if not exists
     let _ = conn.execute("ALTER TABLE user_settings ADD COLUMN theme TEXT", []);
 
+    // Migration: Add pending URL tracking columns for feed URL migration
+    let _ = conn.execute("ALTER TABLE feed ADD COLUMN pending_url TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE feed ADD COLUMN pending_url_count INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: Add error_since column to track how long a feed has been failing
+    let _ = conn.execute("ALTER TABLE feed ADD COLUMN error_since TEXT", []);
+
+    // Migration: Add per-feed date sanity strategy for entry timestamps
+    let _ = conn.execute("ALTER TABLE feed ADD COLUMN date_strategy TEXT", []);
+
+    // Migration: Add published_raw column to keep the original date string
+    let _ = conn.execute("ALTER TABLE entry ADD COLUMN published_raw TEXT", []);
+
+    // Migration: Add mark-as-read-on-scroll preference columns
+    let _ = conn.execute(
+        "ALTER TABLE user_settings ADD COLUMN mark_read_on_scroll INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE user_settings ADD COLUMN scroll_dwell_ms INTEGER NOT NULL DEFAULT 1000",
+        [],
+    );
+
+    // Migration: Denormalize per-feed entry counters so the feeds page does not
+    // aggregate the entry table on every load. Backfilled once when added.
+    let added_counters = conn
+        .execute(
+            "ALTER TABLE feed ADD COLUMN entry_count INTEGER NOT NULL DEFAULT 0",
+            [],
+        )
+        .is_ok();
+    let _ = conn.execute(
+        "ALTER TABLE feed ADD COLUMN unread_count INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE feed ADD COLUMN last_entry_at TEXT", []);
+    if added_counters {
+        conn.execute(
+            r#"
+            UPDATE feed SET
+                entry_count = (SELECT COUNT(*) FROM entry WHERE entry.feed_id = feed.id),
+                unread_count = (
+                    SELECT COUNT(*) FROM entry
+                    WHERE entry.feed_id = feed.id AND entry.read_at IS NULL
+                ),
+                last_entry_at = (
+                    SELECT MAX(COALESCE(published_at, created_at)) FROM entry
+                    WHERE entry.feed_id = feed.id
+                )
+            "#,
+            [],
+        )?;
+    }
+
+    // Keep the feed counters in step with every entry write path
+    conn.execute_batch(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS trg_entry_insert_feed_counts
+        AFTER INSERT ON entry
+        BEGIN
+            UPDATE feed SET
+                entry_count = entry_count + 1,
+                unread_count = unread_count + (NEW.read_at IS NULL),
+                last_entry_at = MAX(
+                    COALESCE(last_entry_at, ''),
+                    COALESCE(NEW.published_at, NEW.created_at)
+                )
+            WHERE id = NEW.feed_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_entry_read_feed_counts
+        AFTER UPDATE OF read_at ON entry
+        WHEN (OLD.read_at IS NULL) != (NEW.read_at IS NULL)
+        BEGIN
+            UPDATE feed SET
+                unread_count = unread_count + (CASE WHEN NEW.read_at IS NULL THEN 1 ELSE -1 END)
+            WHERE id = NEW.feed_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_entry_delete_feed_counts
+        AFTER DELETE ON entry
+        BEGIN
+            UPDATE feed SET
+                entry_count = entry_count - 1,
+                unread_count = unread_count - (OLD.read_at IS NULL),
+                last_entry_at = (
+                    SELECT MAX(COALESCE(published_at, created_at)) FROM entry
+                    WHERE entry.feed_id = OLD.feed_id
+                )
+            WHERE id = OLD.feed_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_entry_move_feed_counts
+        AFTER UPDATE OF feed_id ON entry
+        WHEN OLD.feed_id != NEW.feed_id
+        BEGIN
+            UPDATE feed SET
+                entry_count = entry_count - 1,
+                unread_count = unread_count - (OLD.read_at IS NULL),
+                last_entry_at = (
+                    SELECT MAX(COALESCE(published_at, created_at)) FROM entry
+                    WHERE entry.feed_id = OLD.feed_id
+                )
+            WHERE id = OLD.feed_id;
+            UPDATE feed SET
+                entry_count = entry_count + 1,
+                unread_count = unread_count + (NEW.read_at IS NULL),
+                last_entry_at = MAX(
+                    COALESCE(last_entry_at, ''),
+                    COALESCE(NEW.published_at, NEW.created_at)
+                )
+            WHERE id = NEW.feed_id;
+        END;
+        "#,
+    )?;
+
+    // Migration: Allow discoverable authentication challenges. SQLite cannot
+    // change a CHECK constraint, so an older table is built again.
+    let challenge_sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'webauthn_challenge'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !challenge_sql.contains("discoverable_authentication") {
+        conn.execute_batch(
+            r#"
+            BEGIN;
+            CREATE TABLE webauthn_challenge_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                challenge BLOB NOT NULL UNIQUE,
+                user_id INTEGER REFERENCES user(id) ON DELETE CASCADE,
+                challenge_type TEXT NOT NULL CHECK (challenge_type IN ('registration', 'authentication', 'discoverable_authentication')),
+                state_data TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                expires_at TEXT NOT NULL
+            );
+            INSERT INTO webauthn_challenge_new
+                SELECT id, challenge, user_id, challenge_type, state_data, created_at, expires_at
+                FROM webauthn_challenge;
+            DROP TABLE webauthn_challenge;
+            ALTER TABLE webauthn_challenge_new RENAME TO webauthn_challenge;
+            CREATE INDEX IF NOT EXISTS idx_webauthn_challenge_expires_at ON webauthn_challenge(expires_at);
+            COMMIT;
+            "#,
+        )?;
+    }
+
+    // Migration: Add locked_until column for brute-force login lockout
+    let _ = conn.execute("ALTER TABLE user ADD COLUMN locked_until TEXT", []);
+
+    // Migration: Add remember-me flag and device info to sessions
+    let _ = conn.execute(
+        "ALTER TABLE session ADD COLUMN persistent INTEGER NOT NULL DEFAULT 1",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE session ADD COLUMN user_agent TEXT", []);
+
+    // Migration: Add per-feed extra request headers, stored as a JSON object
+    let _ = conn.execute("ALTER TABLE feed ADD COLUMN custom_headers TEXT", []);
+
+    // Migration: Add must_change_password flag for admin-issued temporary passwords
+    let _ = conn.execute(
+        "ALTER TABLE user ADD COLUMN must_change_password INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: Add discussion link and comment count announced by the feed
+    let _ = conn.execute("ALTER TABLE entry ADD COLUMN comments_url TEXT", []);
+    let _ = conn.execute("ALTER TABLE entry ADD COLUMN comments_count INTEGER", []);
+
+    // Migration: Add engagement score used to rank feeds by read/star activity
+    let _ = conn.execute(
+        "ALTER TABLE feed ADD COLUMN engagement_score REAL NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: Add auto-mark-read rules (days) to feeds and categories
+    let _ = conn.execute("ALTER TABLE feed ADD COLUMN auto_read_days INTEGER", []);
+    let _ = conn.execute("ALTER TABLE category ADD COLUMN auto_read_days INTEGER", []);
+
+    // Migration: Add UI language preference; NULL follows Accept-Language
+    let _ = conn.execute("ALTER TABLE user_settings ADD COLUMN locale TEXT", []);
+
+    // Migration: Add IANA time zone preference; NULL means UTC
+    let _ = conn.execute("ALTER TABLE user_settings ADD COLUMN timezone TEXT", []);
+
+    // Migration: Add sanitizer policy (JSON); NULL means the default policy
+    let _ = conn.execute(
+        "ALTER TABLE user_settings ADD COLUMN sanitize_policy TEXT",
+        [],
+    );
+
+    // Migration: Add preference to mark entries unread again when their content changes
+    let _ = conn.execute(
+        "ALTER TABLE user_settings ADD COLUMN unread_on_update INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: Add per-feed threshold (percent of text changed) for marking
+    // updated entries unread, and when an entry last changed that much
+    let _ = conn.execute("ALTER TABLE feed ADD COLUMN update_threshold INTEGER", []);
+    let _ = conn.execute("ALTER TABLE entry ADD COLUMN content_updated_at TEXT", []);
+
+    // Migration: Add synthetic flag for feeds that hold entries from elsewhere
+    // (imported bookmarks) and are never fetched
+    let _ = conn.execute(
+        "ALTER TABLE feed ADD COLUMN synthetic INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: Add soft-delete timestamps for the trash
+    let _ = conn.execute("ALTER TABLE category ADD COLUMN deleted_at TEXT", []);
+    let _ = conn.execute("ALTER TABLE feed ADD COLUMN deleted_at TEXT", []);
+
+    // Migration: Add failure streak, backoff and quarantine for failing feeds
+    let _ = conn.execute(
+        "ALTER TABLE feed ADD COLUMN failure_count INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE feed ADD COLUMN next_fetch_at TEXT", []);
+    let _ = conn.execute("ALTER TABLE feed ADD COLUMN quarantined_at TEXT", []);
+
+    // Migration: Keep the full article fetched for an entry so search finds it
+    let _ = conn.execute("ALTER TABLE entry ADD COLUMN full_content TEXT", []);
+
+    // Migration: Story keys group copies of a story across feeds. Computed in
+    // Rust, so existing entries are keyed once when the column is added.
+    if conn
+        .execute("ALTER TABLE entry ADD COLUMN story_key TEXT", [])
+        .is_ok()
+    {
+        entry_cluster::backfill_story_keys(conn)?;
+    }
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_entry_story_key ON entry(story_key)",
+        [],
+    )?;
+
+    // Full-text index over entry titles, content, summaries and notes, kept
+    // in step with every write path by triggers. Backfilled once when
+    // created. FTS5 tables cannot gain columns, so an index from before
+    // notes is dropped and built again.
+    let has_search_index: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = 'entry_fts')",
+        [],
+        |row| row.get(0),
+    )?;
+    let has_search_index = has_search_index && {
+        let has_note_column: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM pragma_table_info('entry_fts') WHERE name = 'note')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_note_column {
+            conn.execute("DROP TABLE entry_fts", [])?;
+        }
+        has_note_column
+    };
+    conn.execute_batch(
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS entry_fts USING fts5(
+            title, content, full_content, summary, note,
+            tokenize = 'trigram'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS trg_entry_fts_insert
+        AFTER INSERT ON entry
+        BEGIN
+            INSERT INTO entry_fts (rowid, title, content, full_content)
+            VALUES (NEW.id, NEW.title, NEW.content, NEW.full_content);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_entry_fts_update
+        AFTER UPDATE OF title, content, full_content ON entry
+        WHEN OLD.title IS NOT NEW.title
+            OR OLD.content IS NOT NEW.content
+            OR OLD.full_content IS NOT NEW.full_content
+        BEGIN
+            UPDATE entry_fts SET
+                title = NEW.title,
+                content = NEW.content,
+                full_content = NEW.full_content
+            WHERE rowid = NEW.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_entry_fts_delete
+        AFTER DELETE ON entry
+        BEGIN
+            DELETE FROM entry_fts WHERE rowid = OLD.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_entry_summary_fts_insert
+        AFTER INSERT ON entry_summary
+        BEGIN
+            UPDATE entry_fts SET summary = (
+                SELECT group_concat(summary_text, ' ') FROM entry_summary
+                WHERE entry_id = NEW.entry_id
+            )
+            WHERE rowid = NEW.entry_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_entry_summary_fts_update
+        AFTER UPDATE OF summary_text ON entry_summary
+        BEGIN
+            UPDATE entry_fts SET summary = (
+                SELECT group_concat(summary_text, ' ') FROM entry_summary
+                WHERE entry_id = NEW.entry_id
+            )
+            WHERE rowid = NEW.entry_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_entry_summary_fts_delete
+        AFTER DELETE ON entry_summary
+        BEGIN
+            UPDATE entry_fts SET summary = (
+                SELECT group_concat(summary_text, ' ') FROM entry_summary
+                WHERE entry_id = OLD.entry_id
+            )
+            WHERE rowid = OLD.entry_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_entry_note_fts_insert
+        AFTER INSERT ON entry_note
+        BEGIN
+            UPDATE entry_fts SET note = NEW.content WHERE rowid = NEW.entry_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_entry_note_fts_update
+        AFTER UPDATE OF content ON entry_note
+        BEGIN
+            UPDATE entry_fts SET note = NEW.content WHERE rowid = NEW.entry_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_entry_note_fts_delete
+        AFTER DELETE ON entry_note
+        BEGIN
+            UPDATE entry_fts SET note = NULL WHERE rowid = OLD.entry_id;
+        END;
+        "#,
+    )?;
+    if !has_search_index {
+        conn.execute(
+            r#"
+            INSERT INTO entry_fts (rowid, title, content, full_content, summary, note)
+            SELECT id, title, content, full_content, (
+                SELECT group_concat(summary_text, ' ') FROM entry_summary
+                WHERE entry_summary.entry_id = entry.id
+            ), (
+                SELECT content FROM entry_note WHERE entry_note.entry_id = entry.id
+            )
+            FROM entry
+            "#,
+            [],
+        )?;
+    }
+
+    // Migration: Every entry write takes the next number from `change_seq`,
+    // so clients can ask what changed since the last number they saw.
+    // Existing entries all start at 1.
+    conn.execute("INSERT OR IGNORE INTO change_seq (id) VALUES (1)", [])?;
+    let added_seq = conn
+        .execute(
+            "ALTER TABLE entry ADD COLUMN created_seq INTEGER NOT NULL DEFAULT 0",
+            [],
+        )
+        .is_ok();
+    let _ = conn.execute(
+        "ALTER TABLE entry ADD COLUMN updated_seq INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+    if added_seq {
+        conn.execute_batch(
+            "UPDATE change_seq SET value = MAX(value, 1) WHERE id = 1;
+             UPDATE entry SET created_seq = 1, updated_seq = 1;",
+        )?;
+    }
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_entry_updated_seq ON entry(updated_seq)",
+        [],
+    )?;
+
+    // Deleted entries leave a tombstone for their owner. A feed or category
+    // being deleted takes its entries with it, by which time the owner can no
+    // longer be looked up, so it is noted in `deleted_feed_owner` first.
+    // Moving a feed or category to the trash or back renumbers its entries.
+    conn.execute_batch(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS trg_entry_insert_seq
+        AFTER INSERT ON entry
+        BEGIN
+            UPDATE change_seq SET value = value + 1 WHERE id = 1;
+            UPDATE entry SET
+                created_seq = (SELECT value FROM change_seq WHERE id = 1),
+                updated_seq = (SELECT value FROM change_seq WHERE id = 1)
+            WHERE id = NEW.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_entry_update_seq
+        AFTER UPDATE OF
+            feed_id, title, link, content, summary, author, published_at,
+            read_at, starred_at, full_content, comments_count
+        ON entry
+        WHEN OLD.feed_id IS NOT NEW.feed_id
+            OR OLD.title IS NOT NEW.title
+            OR OLD.link IS NOT NEW.link
+            OR OLD.content IS NOT NEW.content
+            OR OLD.summary IS NOT NEW.summary
+            OR OLD.author IS NOT NEW.author
+            OR OLD.published_at IS NOT NEW.published_at
+            OR OLD.read_at IS NOT NEW.read_at
+            OR OLD.starred_at IS NOT NEW.starred_at
+            OR OLD.full_content IS NOT NEW.full_content
+            OR OLD.comments_count IS NOT NEW.comments_count
+        BEGIN
+            UPDATE change_seq SET value = value + 1 WHERE id = 1;
+            UPDATE entry SET updated_seq = (SELECT value FROM change_seq WHERE id = 1)
+            WHERE id = NEW.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_entry_delete_seq
+        AFTER DELETE ON entry
+        BEGIN
+            UPDATE change_seq SET value = value + 1 WHERE id = 1;
+            INSERT INTO entry_tombstone (seq, entry_id, user_id)
+            SELECT (SELECT value FROM change_seq WHERE id = 1), OLD.id, user.id
+            FROM user
+            WHERE user.id = COALESCE(
+                (
+                    SELECT category.user_id FROM feed
+                    JOIN category ON category.id = feed.category_id
+                    WHERE feed.id = OLD.feed_id
+                ),
+                (SELECT user_id FROM deleted_feed_owner WHERE feed_id = OLD.feed_id)
+            );
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_category_delete_owner
+        BEFORE DELETE ON category
+        BEGIN
+            INSERT OR IGNORE INTO deleted_feed_owner (feed_id, user_id)
+            SELECT id, OLD.user_id FROM feed WHERE category_id = OLD.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_feed_delete_owner
+        BEFORE DELETE ON feed
+        BEGIN
+            INSERT OR IGNORE INTO deleted_feed_owner (feed_id, user_id)
+            SELECT OLD.id, user_id FROM category WHERE id = OLD.category_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_feed_delete_owner_done
+        AFTER DELETE ON feed
+        BEGIN
+            DELETE FROM deleted_feed_owner WHERE feed_id = OLD.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_feed_trash_seq
+        AFTER UPDATE OF deleted_at ON feed
+        WHEN OLD.deleted_at IS NOT NEW.deleted_at
+        BEGIN
+            UPDATE change_seq SET value = value + 1 WHERE id = 1;
+            UPDATE entry SET updated_seq = (SELECT value FROM change_seq WHERE id = 1)
+            WHERE feed_id = NEW.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_category_trash_seq
+        AFTER UPDATE OF deleted_at ON category
+        WHEN OLD.deleted_at IS NOT NEW.deleted_at
+        BEGIN
+            UPDATE change_seq SET value = value + 1 WHERE id = 1;
+            UPDATE entry SET updated_seq = (SELECT value FROM change_seq WHERE id = 1)
+            WHERE feed_id IN (SELECT id FROM feed WHERE category_id = NEW.id);
+        END;
+        "#,
+    )?;
+
+    // Migration: Positions users give categories and feeds; NULL until
+    // arranged, so those sort by name after the arranged ones
+    let _ = conn.execute("ALTER TABLE category ADD COLUMN sort_order INTEGER", []);
+    let _ = conn.execute("ALTER TABLE feed ADD COLUMN sort_order INTEGER", []);
+
+    // Migration: Add a color and icon for telling categories apart
+    let _ = conn.execute("ALTER TABLE category ADD COLUMN color TEXT", []);
+    let _ = conn.execute("ALTER TABLE category ADD COLUMN icon TEXT", []);
+
+    // Migration: Add the start page and unread count cap preferences
+    let _ = conn.execute("ALTER TABLE user_settings ADD COLUMN start_page TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE user_settings ADD COLUMN start_category_id INTEGER",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE user_settings ADD COLUMN unread_count_cap INTEGER",
+        [],
+    );
+
+    // Migration: Versions clients send back in `If-Match` so edits from two
+    // devices don't overwrite each other. Only settings users edit count;
+    // fetch bookkeeping leaves them alone.
+    let _ = conn.execute(
+        "ALTER TABLE category ADD COLUMN version INTEGER NOT NULL DEFAULT 1",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE feed ADD COLUMN version INTEGER NOT NULL DEFAULT 1",
+        [],
+    );
+    conn.execute_batch(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS trg_category_version
+        AFTER UPDATE OF name, auto_read_days, color, icon ON category
+        WHEN OLD.name IS NOT NEW.name
+            OR OLD.auto_read_days IS NOT NEW.auto_read_days
+            OR OLD.color IS NOT NEW.color
+            OR OLD.icon IS NOT NEW.icon
+        BEGIN
+            UPDATE category SET version = version + 1 WHERE id = NEW.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS trg_feed_version
+        AFTER UPDATE OF
+            category_id, url, title, description, site_url, custom_user_agent,
+            http2_disabled, custom_headers, auto_read_days, date_strategy, update_threshold
+        ON feed
+        WHEN OLD.category_id IS NOT NEW.category_id
+            OR OLD.url IS NOT NEW.url
+            OR OLD.title IS NOT NEW.title
+            OR OLD.description IS NOT NEW.description
+            OR OLD.site_url IS NOT NEW.site_url
+            OR OLD.custom_user_agent IS NOT NEW.custom_user_agent
+            OR OLD.http2_disabled IS NOT NEW.http2_disabled
+            OR OLD.custom_headers IS NOT NEW.custom_headers
+            OR OLD.auto_read_days IS NOT NEW.auto_read_days
+            OR OLD.date_strategy IS NOT NEW.date_strategy
+            OR OLD.update_threshold IS NOT NEW.update_threshold
+        BEGIN
+            UPDATE feed SET version = version + 1 WHERE id = NEW.id;
+        END;
+        "#,
+    )?;
+
+    // Migration: Style preset and target length each summary was made with
+    let _ = conn.execute(
+        "ALTER TABLE entry_summary ADD COLUMN style TEXT NOT NULL DEFAULT 'paragraph'",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE entry_summary ADD COLUMN length TEXT NOT NULL DEFAULT 'long'",
+        [],
+    );
+
+    // Migration: Opt-in to tagging new entries with the summarizer backend
+    let _ = conn.execute(
+        "ALTER TABLE user_settings ADD COLUMN auto_tag INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: Opt-in to saving starred entries to the save services
+    let _ = conn.execute(
+        "ALTER TABLE user_settings ADD COLUMN auto_save_starred INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+
+    // Migration: When the icon worker last tried the feed's icon, so feeds
+    // without one are retried once a day rather than on every pass
+    let _ = conn.execute("ALTER TABLE feed ADD COLUMN icon_checked_at TEXT", []);
+
     Ok(())
 }
 
@@ -185,5 +1099,80 @@ mod tests {
         assert!(tables.contains(&"passkey".to_string()));
         assert!(tables.contains(&"webauthn_challenge".to_string()));
         assert!(tables.contains(&"entry_summary".to_string()));
+        assert!(tables.contains(&"category_digest".to_string()));
+        assert!(tables.contains(&"push_subscription".to_string()));
+        assert!(tables.contains(&"notification_rule".to_string()));
+        assert!(tables.contains(&"feed_event".to_string()));
+        assert!(tables.contains(&"login_attempt".to_string()));
+        assert!(tables.contains(&"feed_cookie".to_string()));
+        assert!(tables.contains(&"feed_script".to_string()));
+        assert!(tables.contains(&"feed_rewrite".to_string()));
+        assert!(tables.contains(&"blocked_host".to_string()));
+        assert!(tables.contains(&"blocklist_import".to_string()));
+        assert!(tables.contains(&"instance_settings".to_string()));
+        assert!(tables.contains(&"change_seq".to_string()));
+        assert!(tables.contains(&"entry_tombstone".to_string()));
+        assert!(tables.contains(&"deleted_feed_owner".to_string()));
+        assert!(tables.contains(&"author".to_string()));
+        assert!(tables.contains(&"entry_author".to_string()));
+        assert!(tables.contains(&"discussion_cache".to_string()));
+        assert!(tables.contains(&"related_cache".to_string()));
+        assert!(tables.contains(&"feed_weekly_stats".to_string()));
+        assert!(tables.contains(&"entry_revision".to_string()));
+        assert!(tables.contains(&"masquerade_audit".to_string()));
+        assert!(tables.contains(&"notification".to_string()));
+        assert!(tables.contains(&"job".to_string()));
+        assert!(tables.contains(&"shared_summary".to_string()));
+        assert!(tables.contains(&"entry_question".to_string()));
+        assert!(tables.contains(&"tag".to_string()));
+        assert!(tables.contains(&"entry_tag".to_string()));
+        assert!(tables.contains(&"interest_model".to_string()));
+        assert!(tables.contains(&"entry_interest".to_string()));
+        assert!(tables.contains(&"entry_view".to_string()));
+        assert!(tables.contains(&"highlight".to_string()));
+        assert!(tables.contains(&"entry_note".to_string()));
+        assert!(tables.contains(&"entry_save".to_string()));
+        assert!(tables.contains(&"entry_fts".to_string()));
+    }
+
+    #[test]
+    fn test_webauthn_challenge_check_is_migrated() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        // Put back the table as it was before discoverable authentication
+        conn.execute_batch(
+            r#"
+            DROP TABLE webauthn_challenge;
+            CREATE TABLE webauthn_challenge (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                challenge BLOB NOT NULL UNIQUE,
+                user_id INTEGER REFERENCES user(id) ON DELETE CASCADE,
+                challenge_type TEXT NOT NULL CHECK (challenge_type IN ('registration', 'authentication')),
+                state_data TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                expires_at TEXT NOT NULL
+            );
+            INSERT INTO webauthn_challenge (challenge, challenge_type, state_data, expires_at)
+                VALUES (x'01', 'authentication', '{}', '2099-01-01 00:00:00');
+            "#,
+        )
+        .unwrap();
+
+        init_db(&conn).unwrap();
+        // Running again leaves the rebuilt table alone
+        init_db(&conn).unwrap();
+
+        conn.execute(
+            "INSERT INTO webauthn_challenge (challenge, challenge_type, state_data, expires_at)
+             VALUES (x'02', 'discoverable_authentication', '{}', '2099-01-01 00:00:00')",
+            [],
+        )
+        .unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM webauthn_challenge", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(count, 2);
     }
 }
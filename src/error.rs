@@ -3,9 +3,28 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use http_body_util::LengthLimitError;
+use serde::Serialize;
 use serde_json::json;
 use thiserror::Error;
 
+/// The subscription a new feed duplicates, returned with a `409`
+#[derive(Debug, Clone, Serialize)]
+pub struct ExistingFeed {
+    pub id: i64,
+    pub category_id: i64,
+    pub url: String,
+    pub title: Option<String>,
+}
+
+/// A resource as it stands after someone else changed it, returned with a
+/// `412` when the client's `If-Match` version is stale
+#[derive(Debug, Clone, Serialize)]
+pub struct CurrentVersion {
+    pub version: i64,
+    pub current: serde_json::Value,
+}
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Database error: {0}")]
@@ -23,9 +42,18 @@ pub enum AppError {
     #[error("Registration not allowed")]
     RegistrationNotAllowed,
 
+    #[error("Invalid or expired invite")]
+    InvalidInvite,
+
     #[error("User is disabled")]
     UserDisabled,
 
+    #[error("Account temporarily locked after too many failed logins")]
+    AccountLocked,
+
+    #[error("Password change required")]
+    PasswordChangeRequired,
+
     #[error("Unauthorized")]
     Unauthorized,
 
@@ -53,6 +81,9 @@ pub enum AppError {
     #[error("Feed already exists")]
     FeedExists,
 
+    #[error("Feed already subscribed as {}", .0.url)]
+    DuplicateFeed(ExistingFeed),
+
     #[error("Entry not found")]
     EntryNotFound,
 
@@ -101,6 +132,18 @@ pub enum AppError {
     #[error("Challenge not found or expired")]
     ChallengeNotFound,
 
+    #[error("Disabled in privacy mode")]
+    PrivacyMode,
+
+    #[error("Request body too large")]
+    PayloadTooLarge,
+
+    #[error("Sync cursor expired")]
+    CursorExpired,
+
+    #[error("Modified since the given version, now at {}", .0.version)]
+    VersionConflict(CurrentVersion),
+
     #[error("{0}")]
     NotFound(String),
 
@@ -113,13 +156,19 @@ pub enum AppError {
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, message) = match &self {
+        let (status, message): (StatusCode, &str) = match &self {
             AppError::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error"),
             AppError::InvalidCredentials => (StatusCode::UNAUTHORIZED, "Invalid credentials"),
             AppError::UserNotFound => (StatusCode::NOT_FOUND, "User not found"),
             AppError::UsernameExists => (StatusCode::CONFLICT, "Username already exists"),
             AppError::RegistrationNotAllowed => (StatusCode::FORBIDDEN, "Registration not allowed"),
+            AppError::InvalidInvite => (StatusCode::BAD_REQUEST, "Invalid or expired invite"),
             AppError::UserDisabled => (StatusCode::FORBIDDEN, "User is disabled"),
+            AppError::AccountLocked => (
+                StatusCode::LOCKED,
+                "Account temporarily locked after too many failed logins",
+            ),
+            AppError::PasswordChangeRequired => (StatusCode::FORBIDDEN, "Password change required"),
             AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized"),
             AppError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden"),
             AppError::CannotModifySelf => (StatusCode::BAD_REQUEST, "Cannot modify self"),
@@ -129,51 +178,73 @@ impl IntoResponse for AppError {
             AppError::CategoryExists => (StatusCode::CONFLICT, "Category already exists"),
             AppError::FeedNotFound => (StatusCode::NOT_FOUND, "Feed not found"),
             AppError::FeedExists => (StatusCode::CONFLICT, "Feed already exists"),
+            AppError::DuplicateFeed(_) => (StatusCode::CONFLICT, "Feed already subscribed"),
             AppError::EntryNotFound => (StatusCode::NOT_FOUND, "Entry not found"),
             AppError::InvalidUrl => (StatusCode::BAD_REQUEST, "Invalid URL"),
-            AppError::FetchError(msg) => {
-                return (StatusCode::BAD_GATEWAY, Json(json!({ "error": msg }))).into_response()
-            }
+            AppError::FetchError(msg) => (StatusCode::BAD_GATEWAY, msg.as_str()),
             AppError::NoFeedFound => (StatusCode::BAD_REQUEST, "No feed found at URL"),
-            AppError::FeedParseError(msg) => {
-                return (StatusCode::BAD_REQUEST, Json(json!({ "error": msg }))).into_response()
-            }
-            AppError::Validation(msg) => {
-                return (StatusCode::BAD_REQUEST, Json(json!({ "error": msg }))).into_response()
-            }
-            AppError::OpmlParseError(msg) => {
-                return (StatusCode::BAD_REQUEST, Json(json!({ "error": msg }))).into_response()
-            }
+            AppError::FeedParseError(msg) => (StatusCode::BAD_REQUEST, msg.as_str()),
+            AppError::Validation(msg) => (StatusCode::BAD_REQUEST, msg.as_str()),
+            AppError::OpmlParseError(msg) => (StatusCode::BAD_REQUEST, msg.as_str()),
             AppError::InvalidImageUrl => (StatusCode::BAD_REQUEST, "Invalid image URL"),
-            AppError::ImageFetchError(msg) => {
-                return (StatusCode::BAD_GATEWAY, Json(json!({ "error": msg }))).into_response()
-            }
+            AppError::ImageFetchError(msg) => (StatusCode::BAD_GATEWAY, msg.as_str()),
             AppError::ImageTooLarge => (StatusCode::BAD_REQUEST, "Image too large"),
             AppError::UnsupportedImageType => (StatusCode::BAD_REQUEST, "Unsupported image type"),
             AppError::InvalidSignature => (StatusCode::BAD_REQUEST, "Invalid signature"),
             AppError::PasskeyNotFound => (StatusCode::NOT_FOUND, "Passkey not found"),
-            AppError::PasskeyRegistrationFailed(msg) => {
-                return (StatusCode::BAD_REQUEST, Json(json!({ "error": msg }))).into_response()
-            }
-            AppError::PasskeyAuthenticationFailed(msg) => {
-                return (StatusCode::UNAUTHORIZED, Json(json!({ "error": msg }))).into_response()
-            }
+            AppError::PasskeyRegistrationFailed(msg) => (StatusCode::BAD_REQUEST, msg.as_str()),
+            AppError::PasskeyAuthenticationFailed(msg) => (StatusCode::UNAUTHORIZED, msg.as_str()),
             AppError::ChallengeNotFound => {
                 (StatusCode::BAD_REQUEST, "Challenge not found or expired")
             }
-            AppError::NotFound(msg) => {
-                return (StatusCode::NOT_FOUND, Json(json!({ "error": msg }))).into_response()
-            }
+            AppError::PrivacyMode => (StatusCode::FORBIDDEN, "Disabled in privacy mode"),
+            AppError::PayloadTooLarge => (StatusCode::PAYLOAD_TOO_LARGE, "Request body too large"),
+            AppError::CursorExpired => (StatusCode::GONE, "Sync cursor expired"),
+            AppError::VersionConflict(_) => (
+                StatusCode::PRECONDITION_FAILED,
+                "Changed elsewhere in the meantime",
+            ),
+            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.as_str()),
             AppError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error"),
             AppError::DbPool(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error"),
         };
+        let message = crate::i18n::gettext(message);
+
+        // Lets users quote the ID from an error toast when reporting a problem
+        let mut body = match crate::middleware::request_id::current() {
+            Some(request_id) => json!({ "error": message, "request_id": request_id }),
+            None => json!({ "error": message }),
+        };
+        if let AppError::DuplicateFeed(existing) = &self {
+            body["existing_feed"] = json!(existing);
+        }
+        if let AppError::VersionConflict(conflict) = &self {
+            body["version"] = json!(conflict.version);
+            body["current"] = conflict.current.clone();
+        }
 
-        (status, Json(json!({ "error": message }))).into_response()
+        (status, Json(body)).into_response()
     }
 }
 
 pub type AppResult<T> = Result<T, AppError>;
 
+/// Whether reading a request body failed because it went over the size limit
+pub fn body_too_large(err: &(dyn std::error::Error + 'static)) -> bool {
+    let mut current = Some(err);
+    while let Some(e) = current {
+        if e.is::<LengthLimitError>() {
+            return true;
+        }
+        // `io::Error` reports the source of the error it wraps, not the error
+        current = match e.downcast_ref::<std::io::Error>() {
+            Some(io) => io.get_ref().map(|inner| inner as _),
+            None => e.source(),
+        };
+    }
+    false
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -320,6 +391,22 @@ mod tests {
         assert!(body.contains("Feed already exists"));
     }
 
+    #[tokio::test]
+    async fn test_duplicate_feed_response() {
+        let err = AppError::DuplicateFeed(ExistingFeed {
+            id: 7,
+            category_id: 2,
+            url: "https://example.com/feed.xml".to_string(),
+            title: Some("Example".to_string()),
+        });
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = get_response_body(response).await;
+        assert!(body.contains("Feed already subscribed"));
+        assert!(body.contains(r#""existing_feed":{"#));
+        assert!(body.contains(r#""id":7"#));
+    }
+
     #[tokio::test]
     async fn test_entry_not_found_response() {
         let err = AppError::EntryNotFound;
@@ -482,6 +569,43 @@ mod tests {
         assert!(body.contains("Challenge not found"));
     }
 
+    #[tokio::test]
+    async fn test_privacy_mode_response() {
+        let response = AppError::PrivacyMode.into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = get_response_body(response).await;
+        assert!(body.contains("Disabled in privacy mode"));
+    }
+
+    #[tokio::test]
+    async fn test_payload_too_large_response() {
+        let response = AppError::PayloadTooLarge.into_response();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = get_response_body(response).await;
+        assert!(body.contains("Request body too large"));
+    }
+
+    #[tokio::test]
+    async fn test_version_conflict_response() {
+        let err = AppError::VersionConflict(CurrentVersion {
+            version: 12,
+            current: json!({ "id": 3, "read_at": null }),
+        });
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        let body = get_response_body(response).await;
+        assert!(body.contains(r#""version":12"#));
+        assert!(body.contains(r#""current":{"#));
+    }
+
+    #[tokio::test]
+    async fn test_cursor_expired_response() {
+        let response = AppError::CursorExpired.into_response();
+        assert_eq!(response.status(), StatusCode::GONE);
+        let body = get_response_body(response).await;
+        assert!(body.contains("Sync cursor expired"));
+    }
+
     #[test]
     fn test_error_display() {
         assert_eq!(
@@ -1,15 +1,28 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use url::Url;
 
+use crate::auth::{generate_password, hash_password};
 use crate::error::{AppError, AppResult};
 use crate::middleware::AdminUser;
-use crate::models::session;
+use crate::models::blocklist::{self, Source};
+use crate::models::instance_settings::{self, StoredSettings};
+use crate::models::invite::{self, Invite, DEFAULT_INVITE_EXPIRY_HOURS, MAX_INVITE_EXPIRY_HOURS};
+use crate::models::masquerade_audit::{
+    self, MasqueradeAudit, DEFAULT_AUDIT_LIMIT, MAX_AUDIT_LIMIT,
+};
 use crate::models::user::{self, Role, User};
-use crate::AppState;
+use crate::models::{login_attempt, session};
+use crate::services::blocklist::{normalize_pattern, MAX_MANUAL_PATTERNS};
+use crate::services::InstanceSettings;
+use crate::{services, AppState};
 
 pub async fn list_users(
     State(state): State<AppState>,
@@ -19,6 +32,61 @@ pub async fn list_users(
     Ok(Json(users))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CreateUserRequest {
+    pub username: String,
+    #[serde(default = "default_role")]
+    pub role: Role,
+}
+
+fn default_role() -> Role {
+    Role::User
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateUserResponse {
+    #[serde(flatten)]
+    pub user: User,
+    /// Shown once; the user must replace it at first login
+    pub temporary_password: String,
+}
+
+/// Create an account with a generated one-time password, for instances
+/// that keep registration closed
+pub async fn create_user(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Json(req): Json<CreateUserRequest>,
+) -> AppResult<(StatusCode, Json<CreateUserResponse>)> {
+    let username = req.username;
+    if username.is_empty() {
+        return Err(AppError::Validation("Username is required".to_string()));
+    }
+
+    let temporary_password = generate_password();
+    let password_hash = hash_password(&temporary_password)?;
+    let role = req.role;
+    let user = state
+        .db
+        .user(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            let user = user::create_user(&tx, &username, &password_hash, role)?;
+            user::require_password_change(&tx, user.id)?;
+            let user = user::find_by_id(&tx, user.id)?.ok_or(AppError::UserNotFound)?;
+            tx.commit()?;
+            Ok::<_, AppError>(user)
+        })
+        .await??;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateUserResponse {
+            user,
+            temporary_password,
+        }),
+    ))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UpdateUserRequest {
     pub role: Option<Role>,
@@ -63,6 +131,24 @@ pub async fn update_user(
     Ok(StatusCode::OK)
 }
 
+/// Lift a lockout caused by failed logins and forget those failures
+pub async fn unlock_user(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Path(user_id): Path<i64>,
+) -> AppResult<StatusCode> {
+    state
+        .db
+        .user(move |conn| {
+            user::unlock_user(conn, user_id)?;
+            login_attempt::clear(conn, user_id)?;
+            Ok::<_, AppError>(())
+        })
+        .await??;
+
+    Ok(StatusCode::OK)
+}
+
 pub async fn delete_user(
     State(state): State<AppState>,
     admin: AdminUser,
@@ -90,6 +176,7 @@ pub async fn start_masquerade(
         return Err(AppError::AlreadyMasquerading);
     }
 
+    let admin_id = admin.user.id;
     let session_token = admin.session.session_token.clone();
     state
         .db
@@ -101,6 +188,14 @@ pub async fn start_masquerade(
             }
 
             session::start_masquerade(conn, &session_token, target_user_id)?;
+            masquerade_audit::record(
+                conn,
+                admin_id,
+                target_user_id,
+                "POST",
+                &format!("/api/admin/masquerade/{}", target_user_id),
+                StatusCode::OK.as_u16(),
+            )?;
 
             Ok::<_, AppError>(())
         })
@@ -125,3 +220,286 @@ pub async fn stop_masquerade(
 
     Ok(StatusCode::OK)
 }
+
+#[derive(Debug, Serialize)]
+pub struct MasqueradeUser {
+    pub id: i64,
+    pub username: String,
+}
+
+impl From<User> for MasqueradeUser {
+    fn from(user: User) -> Self {
+        MasqueradeUser {
+            id: user.id,
+            username: user.username,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct MasqueradeStatus {
+    pub masquerading: bool,
+    /// The user the session acts as
+    pub user: MasqueradeUser,
+    /// The admin behind it, when masquerading
+    pub original_user: Option<MasqueradeUser>,
+}
+
+pub async fn masquerade_status(
+    State(state): State<AppState>,
+    admin: AdminUser,
+) -> AppResult<Json<MasqueradeStatus>> {
+    let original_user = match admin.session.original_user_id {
+        Some(id) => {
+            state
+                .db
+                .user(move |conn| user::find_by_id(conn, id))
+                .await??
+        }
+        None => None,
+    };
+
+    Ok(Json(MasqueradeStatus {
+        masquerading: admin.session.is_masquerading(),
+        user: admin.user.into(),
+        original_user: original_user.map(Into::into),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MasqueradeAuditQuery {
+    pub admin_id: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+pub async fn list_masquerade_audit(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Query(query): Query<MasqueradeAuditQuery>,
+) -> AppResult<Json<Vec<MasqueradeAudit>>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_AUDIT_LIMIT)
+        .clamp(1, MAX_AUDIT_LIMIT);
+    let records = state
+        .db
+        .read(move |conn| masquerade_audit::list_recent(conn, query.admin_id, limit))
+        .await??;
+    Ok(Json(records))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateInviteRequest {
+    pub expires_in_hours: Option<i64>,
+}
+
+pub async fn create_invite(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Json(req): Json<CreateInviteRequest>,
+) -> AppResult<(StatusCode, Json<Invite>)> {
+    let hours = req.expires_in_hours.unwrap_or(DEFAULT_INVITE_EXPIRY_HOURS);
+    if !(1..=MAX_INVITE_EXPIRY_HOURS).contains(&hours) {
+        return Err(AppError::Validation(format!(
+            "Invite expiry must be between 1 and {} hours",
+            MAX_INVITE_EXPIRY_HOURS
+        )));
+    }
+
+    let created_by = admin.session.original_user_id.unwrap_or(admin.user.id);
+    let invite = state
+        .db
+        .user(move |conn| invite::create_invite(conn, created_by, hours))
+        .await??;
+
+    Ok((StatusCode::CREATED, Json(invite)))
+}
+
+pub async fn list_invites(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> AppResult<Json<Vec<Invite>>> {
+    let invites = state.db.user(invite::list_all).await??;
+    Ok(Json(invites))
+}
+
+pub async fn delete_invite(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Path(invite_id): Path<i64>,
+) -> AppResult<StatusCode> {
+    state
+        .db
+        .user(move |conn| invite::delete_invite(conn, invite_id))
+        .await??;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlocklistResponse {
+    /// Patterns entered by admins
+    pub entries: Vec<String>,
+    pub import_url: Option<String>,
+    /// Number of hosts read from the import URL
+    pub imported_count: i64,
+    pub imported_at: Option<DateTime<Utc>>,
+    pub import_error: Option<String>,
+}
+
+fn load_blocklist(conn: &Connection) -> AppResult<BlocklistResponse> {
+    let status = blocklist::import_status(conn)?;
+    Ok(BlocklistResponse {
+        entries: blocklist::list_patterns(conn, Source::Manual)?,
+        import_url: status.url,
+        imported_count: blocklist::count(conn, Source::Import)?,
+        imported_at: status.imported_at,
+        import_error: status.last_error,
+    })
+}
+
+pub async fn get_blocklist(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> AppResult<Json<BlocklistResponse>> {
+    let response = state.db.read(load_blocklist).await??;
+    Ok(Json(response))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateBlocklistRequest {
+    pub entries: Vec<String>,
+    /// Hosts file to import every day; without one, nothing is imported
+    #[serde(default)]
+    pub import_url: Option<String>,
+}
+
+/// Replace the blocked patterns and the import URL. A new import URL is
+/// imported right away, in the background.
+pub async fn update_blocklist(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Json(req): Json<UpdateBlocklistRequest>,
+) -> AppResult<Json<BlocklistResponse>> {
+    let mut entries = BTreeSet::new();
+    for entry in req.entries.iter().filter(|e| !e.trim().is_empty()) {
+        entries.insert(normalize_pattern(entry).map_err(AppError::Validation)?);
+    }
+    if entries.len() > MAX_MANUAL_PATTERNS {
+        return Err(AppError::Validation(format!(
+            "At most {} blocked patterns are allowed",
+            MAX_MANUAL_PATTERNS
+        )));
+    }
+    let entries: Vec<String> = entries.into_iter().collect();
+
+    let import_url = req
+        .import_url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+    if let Some(url) = &import_url {
+        let is_http = Url::parse(url).is_ok_and(|u| matches!(u.scheme(), "http" | "https"));
+        if !is_http {
+            return Err(AppError::Validation(
+                "Import URL must be an http or https URL".to_string(),
+            ));
+        }
+    }
+
+    let (response, url_changed) = state
+        .db
+        .user(move |conn| {
+            let previous_url = blocklist::import_status(conn)?.url;
+            blocklist::replace_patterns(conn, Source::Manual, &entries)?;
+            blocklist::set_import_url(conn, import_url.as_deref())?;
+            Ok::<_, AppError>((load_blocklist(conn)?, previous_url != import_url))
+        })
+        .await??;
+    state.blocklist.reload(&state.db).await?;
+
+    if url_changed && response.import_url.is_some() {
+        let db = state.db.clone();
+        let blocked = state.blocklist.clone();
+        let user_agent = state.settings.current().user_agent.clone();
+        tokio::spawn(async move {
+            if let Err(e) = services::blocklist::refresh(&db, &blocked, &user_agent).await {
+                tracing::error!("Failed to import blocklist: {}", e);
+            }
+        });
+    }
+
+    Ok(Json(response))
+}
+
+#[derive(Debug, Serialize)]
+pub struct VersionResponse {
+    pub current_version: &'static str,
+    pub git_version: &'static str,
+    /// Whether `UPDATE_CHECK_ENABLED` is in effect
+    pub check_enabled: bool,
+    pub latest_version: Option<String>,
+    pub release_url: Option<String>,
+    pub update_available: bool,
+    pub checked_at: Option<DateTime<Utc>>,
+    pub check_error: Option<String>,
+}
+
+pub async fn get_version(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> AppResult<Json<VersionResponse>> {
+    let status = state.updates.status();
+    Ok(Json(VersionResponse {
+        current_version: crate::PKG_VERSION,
+        git_version: crate::GIT_VERSION,
+        check_enabled: state.config.update_check_enabled && !state.config.privacy_mode,
+        update_available: status.update_available(),
+        latest_version: status.latest_version,
+        release_url: status.release_url,
+        checked_at: status.checked_at,
+        check_error: status.last_error,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstanceSettingsResponse {
+    /// The settings in effect
+    #[serde(flatten)]
+    pub settings: InstanceSettings,
+    /// What admins saved; `null` keeps the configured default
+    pub saved: StoredSettings,
+}
+
+pub async fn get_settings(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> AppResult<Json<InstanceSettingsResponse>> {
+    let saved = state.db.read(instance_settings::get).await??;
+    Ok(Json(InstanceSettingsResponse {
+        settings: InstanceSettings::resolve(&state.config, &saved),
+        saved,
+    }))
+}
+
+/// Replace the saved settings; each `null` or missing one goes back to its
+/// default. Takes effect right away.
+pub async fn update_settings(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Json(mut req): Json<StoredSettings>,
+) -> AppResult<Json<InstanceSettingsResponse>> {
+    req.user_agent = req.user_agent.map(|ua| ua.trim().to_string());
+    services::instance_settings::validate(&req)?;
+
+    let saved = req.clone();
+    state
+        .db
+        .user(move |conn| instance_settings::save(conn, &req))
+        .await??;
+    state.settings.reload(&state.db, &state.config).await?;
+
+    Ok(Json(InstanceSettingsResponse {
+        settings: (*state.settings.current()).clone(),
+        saved,
+    }))
+}
@@ -1,19 +1,27 @@
-use axum::{extract::State, http::StatusCode, Json};
-use axum_extra::extract::cookie::{Cookie, CookieJar};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use axum_extra::extract::cookie::CookieJar;
 use serde::{Deserialize, Serialize};
-use time::Duration;
+use tracing::warn;
 
 use crate::auth::{hash_password, verify_password};
 use crate::error::{AppError, AppResult};
-use crate::middleware::{AuthUser, SESSION_COOKIE_NAME};
+use crate::middleware::{session_cookie, AuthUser, SESSION_COOKIE_NAME};
 use crate::models::session;
 use crate::models::user::{self, Role};
+use crate::models::{invite, login_attempt};
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
 pub struct RegisterRequest {
     pub username: String,
     pub password: String,
+    /// Invitation token; lets the user register even when signup is closed
+    #[serde(default)]
+    pub invite: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -36,8 +44,7 @@ pub async fn register(
         ));
     }
 
-    let can_register = state.config.can_register(0); // We check count inside closure
-    let config = state.config.clone();
+    let settings = state.settings.current();
     let password_hash = hash_password(&req.password)?;
 
     let user = state
@@ -45,7 +52,14 @@ pub async fn register(
         .user(move |conn| {
             let user_count = user::count(conn)?;
 
-            if !config.can_register(user_count) {
+            let invite = match req.invite.as_deref() {
+                Some(token) => {
+                    Some(invite::find_usable(conn, token)?.ok_or(AppError::InvalidInvite)?)
+                }
+                None => None,
+            };
+
+            if invite.is_none() && !settings.can_register(user_count) {
                 return Err(AppError::RegistrationNotAllowed);
             }
 
@@ -55,14 +69,19 @@ pub async fn register(
                 Role::User
             };
 
-            let user = user::create_user(conn, &req.username, &password_hash, role)?;
+            let tx = conn.unchecked_transaction()?;
+            let user = user::create_user(&tx, &req.username, &password_hash, role)?;
+            if let Some(invite) = invite {
+                // Another registration may have used the invite in the meantime
+                if !invite::redeem(&tx, &invite.token, user.id)? {
+                    return Err(AppError::InvalidInvite);
+                }
+            }
+            tx.commit()?;
             Ok::<_, AppError>(user)
         })
         .await??;
 
-    // Suppress unused variable warning
-    let _ = can_register;
-
     Ok((
         StatusCode::CREATED,
         Json(RegisterResponse {
@@ -77,6 +96,24 @@ pub async fn register(
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
+    /// Keep the user signed in with a persistent cookie and the long
+    /// `SESSION_TTL`; otherwise the session ends with the browser or after
+    /// `SESSION_SHORT_TTL` of inactivity
+    #[serde(default = "default_remember")]
+    pub remember: bool,
+}
+
+/// API clients that predate the flag keep getting persistent sessions
+pub(crate) fn default_remember() -> bool {
+    true
+}
+
+/// The request's User-Agent, recorded on new sessions to tell devices apart
+pub(crate) fn user_agent(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.chars().take(512).collect())
 }
 
 #[derive(Debug, Serialize)]
@@ -89,15 +126,34 @@ pub struct LoginResponse {
 pub async fn login(
     State(state): State<AppState>,
     jar: CookieJar,
+    headers: HeaderMap,
     Json(req): Json<LoginRequest>,
 ) -> AppResult<(CookieJar, Json<LoginResponse>)> {
+    let persistent = req.remember;
+    let ttl = state.config.session_ttl(persistent);
+    let user_agent = user_agent(&headers);
     let (user, new_session) = state
         .db
         .user(move |conn| {
             let user =
                 user::find_by_username(conn, &req.username)?.ok_or(AppError::InvalidCredentials)?;
 
+            // Refuse before checking the password so a lock cannot be used
+            // to confirm a guess
+            if user.is_locked() {
+                return Err(AppError::AccountLocked);
+            }
+
             if !verify_password(&req.password, &user.password_hash) {
+                if let Some(until) = login_attempt::record_failure(conn, user.id)? {
+                    warn!(
+                        user_id = user.id,
+                        username = %user.username,
+                        locked_until = %until,
+                        "Account locked after repeated failed logins"
+                    );
+                    return Err(AppError::AccountLocked);
+                }
                 return Err(AppError::InvalidCredentials);
             }
 
@@ -105,17 +161,14 @@ pub async fn login(
                 return Err(AppError::UserDisabled);
             }
 
-            let new_session = session::create_session(conn, user.id)?;
+            login_attempt::clear(conn, user.id)?;
+            let new_session =
+                session::create_session(conn, user.id, ttl, persistent, user_agent.as_deref())?;
             Ok::<_, AppError>((user, new_session))
         })
         .await??;
 
-    let cookie = Cookie::build((SESSION_COOKIE_NAME, new_session.session_token))
-        .path("/")
-        .http_only(true)
-        .same_site(axum_extra::extract::cookie::SameSite::Lax)
-        .max_age(Duration::days(session::SESSION_EXPIRY_DAYS))
-        .build();
+    let cookie = session_cookie(new_session.session_token, persistent.then_some(ttl));
 
     Ok((
         jar.add(cookie),
@@ -0,0 +1,110 @@
+use askama::Template;
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{Html, IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use crate::error::{AppError, AppResult};
+use crate::models::{api_token, entry, user};
+use crate::services::{fetch_and_extract, saved_pages};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct SaveQuery {
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub token: String,
+}
+
+#[derive(Template)]
+#[template(path = "save.html")]
+pub struct SaveTemplate {
+    pub url: String,
+    pub entry_id: Option<i64>,
+    pub title: String,
+    pub error: Option<String>,
+}
+
+/// Authenticate by API token and store the page in the token owner's
+/// "Saved pages" feed
+async fn save_with_token(state: &AppState, token: String, url: String) -> AppResult<entry::Entry> {
+    let user_id = state
+        .db
+        .user(move |conn| {
+            let token = api_token::find_by_token(conn, &token)?.ok_or(AppError::Unauthorized)?;
+            let owner = user::find_by_id(conn, token.user_id)?.ok_or(AppError::Unauthorized)?;
+            if owner.is_disabled() {
+                return Err(AppError::UserDisabled);
+            }
+            api_token::touch(conn, token.id)?;
+            Ok::<_, AppError>(owner.id)
+        })
+        .await??;
+
+    let user_agent = state.settings.current().user_agent.clone();
+    let extracted = fetch_and_extract(&url, &user_agent, Default::default()).await?;
+
+    let (saved, _) = state
+        .db
+        .user(move |conn| {
+            saved_pages::save_page(
+                conn,
+                user_id,
+                &url,
+                extracted.title.as_deref(),
+                &extracted.content,
+            )
+        })
+        .await??;
+    Ok(saved)
+}
+
+/// Bookmarklet target: save `url` with the user identified by `token` and
+/// show a minimal confirmation page
+pub async fn save_page(State(state): State<AppState>, Query(query): Query<SaveQuery>) -> Response {
+    let url = query.url.trim().to_string();
+
+    let (status, template) = match save_with_token(&state, query.token, url.clone()).await {
+        Ok(saved) => (
+            StatusCode::OK,
+            SaveTemplate {
+                title: saved.title.unwrap_or_else(|| url.clone()),
+                url,
+                entry_id: Some(saved.id),
+                error: None,
+            },
+        ),
+        Err(e) => {
+            let message = match &e {
+                AppError::Unauthorized => "Invalid or revoked token".to_string(),
+                AppError::UserDisabled => "User is disabled".to_string(),
+                AppError::InvalidUrl => "Invalid URL".to_string(),
+                AppError::FetchError(msg) | AppError::Validation(msg) => msg.clone(),
+                _ => "Could not save this page".to_string(),
+            };
+            (
+                e.into_response().status(),
+                SaveTemplate {
+                    title: url.clone(),
+                    url,
+                    entry_id: None,
+                    error: Some(message),
+                },
+            )
+        }
+    };
+
+    match template.render() {
+        // The token is in this page's URL, so do not pass it on to links
+        Ok(html) => (
+            status,
+            [(header::REFERRER_POLICY, "no-referrer")],
+            Html(html),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
@@ -0,0 +1,103 @@
+//! Everything the sidebar needs on page load in one response, so the
+//! frontend does not have to fetch the user, settings, categories, feeds and
+//! unread counts separately.
+
+use axum::{extract::State, Json};
+use serde::Serialize;
+
+use super::category::CategoryResponse;
+use super::feed::FeedResponse;
+use crate::error::{AppError, AppResult};
+use crate::middleware::AuthUser;
+use crate::models::{category, entry, feed, image, user_settings, User};
+use crate::services::http::{allows, Outbound};
+use crate::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct BootstrapSettings {
+    pub entries_per_page: i64,
+    pub theme: Option<String>,
+    pub locale: Option<String>,
+    pub timezone: Option<String>,
+    pub mark_read_on_scroll: bool,
+    pub scroll_dwell_ms: i64,
+    pub has_save_services: bool,
+    pub has_kagi_configured: bool,
+    #[serde(flatten)]
+    pub home: user_settings::HomeSettings,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BootstrapCategory {
+    #[serde(flatten)]
+    pub category: CategoryResponse,
+    pub feed_count: i64,
+    pub unread_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BootstrapResponse {
+    pub user: User,
+    pub is_masquerading: bool,
+    pub settings: BootstrapSettings,
+    pub categories: Vec<BootstrapCategory>,
+    pub feeds: Vec<FeedResponse>,
+    pub unread_count: i64,
+}
+
+pub async fn get_bootstrap(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<BootstrapResponse>> {
+    let user_id = auth_user.user.id;
+    let (settings, categories, feeds) = state
+        .db
+        .read(move |conn| {
+            let save_config = user_settings::get_save_services_config(conn, user_id)?;
+            let scroll_read = user_settings::get_scroll_read(conn, user_id)?;
+            let settings = BootstrapSettings {
+                entries_per_page: user_settings::get_entries_per_page(conn, user_id)?,
+                theme: user_settings::get_theme(conn, user_id)?,
+                locale: user_settings::get_locale(conn, user_id)?,
+                timezone: user_settings::get_timezone(conn, user_id)?
+                    .map(|tz| tz.name().to_string()),
+                mark_read_on_scroll: scroll_read.enabled,
+                scroll_dwell_ms: scroll_read.dwell_ms,
+                has_save_services: save_config.has_any_service(),
+                has_kagi_configured: allows(Outbound::Summary) && save_config.has_summarizer(),
+                home: user_settings::get_home_settings(conn, user_id)?,
+            };
+
+            let icons = image::list_entity_ids(conn, image::ENTITY_FEED)?;
+            let feeds: Vec<FeedResponse> = feed::list_by_user(conn, user_id)?
+                .into_iter()
+                .map(|f| {
+                    let has_icon = icons.contains(&f.id);
+                    FeedResponse::from_feed(f, has_icon)
+                })
+                .collect();
+
+            let unread_by_category = entry::count_unread_by_category(conn, user_id)?;
+            let categories = category::list_by_user(conn, user_id)?
+                .into_iter()
+                .map(|cat| BootstrapCategory {
+                    feed_count: feeds.iter().filter(|f| f.category_id == cat.id).count() as i64,
+                    unread_count: unread_by_category.get(&cat.id).copied().unwrap_or(0),
+                    category: cat.into(),
+                })
+                .collect();
+
+            Ok::<_, AppError>((settings, categories, feeds))
+        })
+        .await??;
+
+    let unread_count = feeds.iter().map(|f| f.unread_count).sum();
+    Ok(Json(BootstrapResponse {
+        is_masquerading: auth_user.session.is_masquerading(),
+        user: auth_user.user,
+        settings,
+        categories,
+        feeds,
+        unread_count,
+    }))
+}
@@ -1,13 +1,21 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
+    response::IntoResponse,
     Json,
 };
 use serde::{Deserialize, Serialize};
 
 use crate::error::{AppError, AppResult};
+use crate::middleware::precondition::{version_etag, IfMatch};
 use crate::middleware::AuthUser;
 use crate::models::category::{self, Category};
+use crate::models::entry::{self, EntryFilter};
+use crate::models::{auto_read, category_digest, user_settings, SummaryStatus};
+use crate::services::digest::DigestJob;
+use crate::services::http::{allows, Outbound};
+use crate::services::jobs::{self, Task};
+use crate::services::undo;
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -18,6 +26,14 @@ pub struct CreateCategoryRequest {
 #[derive(Debug, Deserialize)]
 pub struct UpdateCategoryRequest {
     pub name: String,
+    /// Days after which unread entries are marked read, 0 to turn off; left
+    /// unchanged when omitted
+    pub auto_read_days: Option<i64>,
+    /// `#rrggbb` or `#rgb`, empty to clear; left unchanged when omitted
+    pub color: Option<String>,
+    /// An emoji or a built-in icon name, empty to clear; left unchanged when
+    /// omitted
+    pub icon: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -25,18 +41,42 @@ pub struct CategoryResponse {
     pub id: i64,
     pub name: String,
     pub created_at: String,
+    pub auto_read_days: Option<i64>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    /// What `icon` shows as, for built-in icon names
+    pub icon_symbol: Option<String>,
 }
 
 impl From<Category> for CategoryResponse {
     fn from(cat: Category) -> Self {
         CategoryResponse {
+            icon_symbol: cat.icon_symbol(),
             id: cat.id,
             name: cat.name,
             created_at: cat.created_at.to_rfc3339(),
+            auto_read_days: cat.auto_read_days,
+            color: cat.color,
+            icon: cat.icon,
         }
     }
 }
 
+#[derive(Debug, Serialize)]
+pub struct BuiltinIcon {
+    pub name: &'static str,
+    pub symbol: &'static str,
+}
+
+/// Icons a category can use by name
+pub async fn list_category_icons(_auth_user: AuthUser) -> Json<Vec<BuiltinIcon>> {
+    let icons = category::BUILTIN_ICONS
+        .iter()
+        .map(|&(name, symbol)| BuiltinIcon { name, symbol })
+        .collect();
+    Json(icons)
+}
+
 pub async fn list_categories(
     State(state): State<AppState>,
     auth_user: AuthUser,
@@ -51,6 +91,31 @@ pub async fn list_categories(
     Ok(Json(response))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ReorderCategoriesRequest {
+    /// Category IDs in the order they should be listed; those left out
+    /// follow in their current order
+    pub ids: Vec<i64>,
+}
+
+pub async fn reorder_categories(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<ReorderCategoriesRequest>,
+) -> AppResult<Json<Vec<CategoryResponse>>> {
+    let user_id = auth_user.user.id;
+    let categories = state
+        .db
+        .user(move |conn| {
+            category::reorder(conn, user_id, &req.ids)?;
+            category::list_by_user(conn, user_id)
+        })
+        .await??;
+    let response: Vec<CategoryResponse> = categories.into_iter().map(Into::into).collect();
+
+    Ok(Json(response))
+}
+
 pub async fn create_category(
     State(state): State<AppState>,
     auth_user: AuthUser,
@@ -83,24 +148,28 @@ pub async fn get_category(
     State(state): State<AppState>,
     auth_user: AuthUser,
     Path(id): Path<i64>,
-) -> AppResult<Json<CategoryResponse>> {
+) -> AppResult<impl IntoResponse> {
     let user_id = auth_user.user.id;
-    let cat = state
+    let (cat, version) = state
         .db
         .user(move |conn| {
-            category::find_by_id_and_user(conn, id, user_id)?.ok_or(AppError::CategoryNotFound)
+            let cat = category::find_by_id_and_user(conn, id, user_id)?
+                .ok_or(AppError::CategoryNotFound)?;
+            let version = category::version(conn, id)?;
+            Ok::<_, AppError>((cat, version))
         })
         .await??;
 
-    Ok(Json(cat.into()))
+    Ok((version_etag(version), Json(CategoryResponse::from(cat))))
 }
 
 pub async fn update_category(
     State(state): State<AppState>,
     auth_user: AuthUser,
     Path(id): Path<i64>,
+    if_match: IfMatch,
     Json(req): Json<UpdateCategoryRequest>,
-) -> AppResult<Json<CategoryResponse>> {
+) -> AppResult<impl IntoResponse> {
     let name = req.name.trim().to_string();
 
     if name.is_empty() {
@@ -115,24 +184,231 @@ pub async fn update_category(
         ));
     }
 
+    let auto_read_days = req.auto_read_days.map(auto_read::parse_days).transpose()?;
+    let color = req
+        .color
+        .as_deref()
+        .map(category::parse_color)
+        .transpose()?;
+    let icon = req.icon.as_deref().map(category::parse_icon).transpose()?;
+
     let user_id = auth_user.user.id;
-    let cat = state
+    let (cat, version) = state
         .db
-        .user(move |conn| category::update_name(conn, id, user_id, &name))
+        .user(move |conn| {
+            let current = category::find_by_id_and_user(conn, id, user_id)?
+                .ok_or(AppError::CategoryNotFound)?;
+            if_match.check(category::version(conn, id)?, || {
+                Ok(CategoryResponse::from(current))
+            })?;
+
+            let mut cat = category::update_name(conn, id, user_id, &name)?;
+            if let Some(days) = auto_read_days.filter(|d| *d != cat.auto_read_days) {
+                cat = category::set_auto_read_days(conn, id, user_id, days)?;
+            }
+            if color.is_some() || icon.is_some() {
+                let color = color.unwrap_or_else(|| cat.color.clone());
+                let icon = icon.unwrap_or_else(|| cat.icon.clone());
+                cat =
+                    category::set_appearance(conn, id, user_id, color.as_deref(), icon.as_deref())?;
+            }
+            let version = category::version(conn, id)?;
+            Ok::<_, AppError>((cat, version))
+        })
         .await??;
 
-    Ok(Json(cat.into()))
+    Ok((version_etag(version), Json(CategoryResponse::from(cat))))
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeleteCategoryResponse {
+    /// Restores the category and its feeds through `POST /api/undo/{token}`
+    pub undo_token: String,
 }
 
 pub async fn delete_category(
     State(state): State<AppState>,
     auth_user: AuthUser,
     Path(id): Path<i64>,
+) -> AppResult<Json<DeleteCategoryResponse>> {
+    let user_id = auth_user.user.id;
+    let undo_token = state
+        .db
+        .user(move |conn| {
+            category::delete_category(conn, id, user_id)?;
+            let token = undo::record(
+                conn,
+                user_id,
+                &undo::UndoPayload::RestoreCategory { category_id: id },
+            )?;
+            Ok::<_, AppError>(token)
+        })
+        .await??;
+
+    Ok(Json(DeleteCategoryResponse { undo_token }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AutoReadPreviewQuery {
+    pub days: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AutoReadPreviewResponse {
+    pub days: i64,
+    /// Unread, unstarred entries the rule would mark read right now; feeds
+    /// with their own rule are not counted
+    pub affected: i64,
+}
+
+/// Dry run of an auto-mark-read rule on a category
+pub async fn preview_category_auto_read(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+    Query(query): Query<AutoReadPreviewQuery>,
+) -> AppResult<Json<AutoReadPreviewResponse>> {
+    let days = auto_read::parse_days(query.days)?
+        .ok_or_else(|| AppError::Validation("Days must be at least 1".to_string()))?;
+
+    let user_id = auth_user.user.id;
+    let affected = state
+        .db
+        .read(move |conn| {
+            category::find_by_id_and_user(conn, id, user_id)?.ok_or(AppError::CategoryNotFound)?;
+            auto_read::preview_category(conn, id, days)
+        })
+        .await??;
+
+    Ok(Json(AutoReadPreviewResponse { days, affected }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigestQuery {
+    /// Regenerate even if a completed digest exists
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DigestResponse {
+    pub status: SummaryStatus,
+    pub digest_text: Option<String>,
+    pub error: Option<String>,
+    pub entry_count: i64,
+    pub updated_at: String,
+}
+
+impl From<category_digest::CategoryDigest> for DigestResponse {
+    fn from(digest: category_digest::CategoryDigest) -> Self {
+        DigestResponse {
+            status: digest.status,
+            digest_text: digest.digest_text,
+            error: digest.error_message,
+            entry_count: digest.entry_count,
+            updated_at: digest.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+/// POST /api/categories/{id}/digest - Queue or return a digest of unread entries
+pub async fn create_category_digest(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+    Query(query): Query<DigestQuery>,
+) -> AppResult<Json<DigestResponse>> {
+    if !allows(Outbound::Summary) {
+        return Err(AppError::PrivacyMode);
+    }
+    let user_id = auth_user.user.id;
+    let (digest, queued) = state
+        .db
+        .user(move |conn| {
+            category::find_by_id_and_user(conn, id, user_id)?.ok_or(AppError::CategoryNotFound)?;
+
+            // In-flight digests are never restarted; completed ones only on request
+            if let Some(existing) = category_digest::find_by_user_and_category(conn, user_id, id)? {
+                let reuse = match existing.status {
+                    SummaryStatus::Pending | SummaryStatus::Processing => true,
+                    SummaryStatus::Completed => !query.refresh,
+                    SummaryStatus::Failed => false,
+                };
+                if reuse {
+                    return Ok::<_, AppError>((existing, false));
+                }
+            }
+
+            let configured = user_settings::get_save_services_config(conn, user_id)?
+                .kagi
+                .map(|k| k.is_configured())
+                .unwrap_or(false);
+            if !configured {
+                return Err(AppError::Validation("Kagi is not configured".to_string()));
+            }
+
+            let filter = EntryFilter {
+                category_id: Some(id),
+                unread_only: true,
+                ..Default::default()
+            };
+            if entry::count_by_user(conn, user_id, &filter)? == 0 {
+                return Err(AppError::Validation(
+                    "No unread entries in this category".to_string(),
+                ));
+            }
+
+            let digest = category_digest::upsert_pending(conn, user_id, id)?;
+            jobs::enqueue(
+                conn,
+                &Task::Digest(DigestJob {
+                    user_id,
+                    category_id: id,
+                }),
+            )?;
+            Ok((digest, true))
+        })
+        .await??;
+
+    if queued {
+        state.jobs.wake();
+    }
+
+    Ok(Json(digest.into()))
+}
+
+/// GET /api/categories/{id}/digest - Get digest status and text
+pub async fn get_category_digest(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<Json<DigestResponse>> {
+    let user_id = auth_user.user.id;
+    let digest = state
+        .db
+        .user(move |conn| {
+            category::find_by_id_and_user(conn, id, user_id)?.ok_or(AppError::CategoryNotFound)?;
+            category_digest::find_by_user_and_category(conn, user_id, id)?
+                .ok_or(AppError::NotFound("No digest found".to_string()))
+        })
+        .await??;
+
+    Ok(Json(digest.into()))
+}
+
+/// DELETE /api/categories/{id}/digest - Discard the digest
+pub async fn delete_category_digest(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
 ) -> AppResult<StatusCode> {
     let user_id = auth_user.user.id;
     state
         .db
-        .user(move |conn| category::delete_category(conn, id, user_id))
+        .user(move |conn| {
+            category::find_by_id_and_user(conn, id, user_id)?.ok_or(AppError::CategoryNotFound)?;
+            category_digest::delete(conn, user_id, id)
+        })
         .await??;
 
     Ok(StatusCode::NO_CONTENT)
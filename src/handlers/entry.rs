@@ -1,17 +1,59 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
+    http::{
+        header::{CONTENT_TYPE, COOKIE},
+        StatusCode,
+    },
+    response::{
+        sse::{Event, KeepAlive, KeepAliveStream, Sse},
+        IntoResponse, Response,
+    },
     Json,
 };
+use futures_util::stream::{self, BoxStream, StreamExt};
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+use tracing::warn;
 
+use crate::auth::ownership;
 use crate::error::{AppError, AppResult};
 use crate::middleware::auth::AuthUser;
-use crate::models::{category, entry, entry_summary, feed, user_settings, SummaryStatus};
-use crate::services::save::{linkding, BookmarkData, SaveResult};
-use crate::services::{fetch_and_extract, refresh_feed, sanitize_html, SummaryJob, SyncResult};
+use crate::middleware::precondition::{version_etag, IfMatch};
+use crate::models::entry_change::{
+    self, Cursor, EntryChanges, DEFAULT_CHANGES_LIMIT, MAX_CHANGES_LIMIT,
+};
+use crate::models::entry_cluster::{self, StoryGroup};
+use crate::models::entry_question::{self, EntryQuestion};
+use crate::models::entry_search::{self, SearchFields, SearchSnippet};
+use crate::models::{
+    author, discussion_cache, entry, entry_revision, entry_save, entry_summary, entry_view, feed,
+    related_cache, shared_summary, user_settings, SummaryStatus,
+};
+use crate::services::digest::html_to_text;
+use crate::services::discussions::{self, Discussion};
+use crate::services::feed_cookies;
+use crate::services::http::{allows, custom_header_map, Outbound};
+use crate::services::jobs::{self, RefreshJob, SummaryJob, Task};
+use crate::services::offline_bundle::{DEFAULT_BUNDLE_BYTES, MAX_BUNDLE_BYTES};
+use crate::services::related;
+use crate::services::revision_diff::{diff_content, DiffChunk};
+use crate::services::save::{self, SaveResult};
+use crate::services::saved_pages;
+use crate::services::summarize::openai;
+use crate::services::undo;
+use crate::services::{
+    build_bundle, create_proxy_url, fetch_and_extract, refresh_feed, sanitize_html_with_policy,
+    OfflineBundle, SummaryCacheEntry, SummaryLength, SummaryOptions, SummaryStyle, SyncResult,
+};
+use crate::timezone::{self, TimestampZone, Tz};
 use crate::AppState;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ListEntriesQuery {
     pub feed_id: Option<i64>,
     pub category_id: Option<i64>,
@@ -22,25 +64,153 @@ pub struct ListEntriesQuery {
     #[serde(default)]
     pub read_only: bool,
     pub search: Option<String>,
+    /// Where `search` looks, as `title|content|summary`; everywhere if unset
+    #[serde(default)]
+    pub fields: SearchFields,
     pub has_summary: Option<bool>,
+    /// Only entries with this tag
+    pub tag: Option<String>,
+    /// Show one entry per story carried by several feeds, with its
+    /// `group_id` and `group_count`
     #[serde(default)]
-    pub sort: entry::EntrySortOrder,
+    pub collapse: bool,
+    /// Archive view to list, as `read|starred|summarized|read_later`;
+    /// combines with the other filters
+    pub state: Option<entry::EntryState>,
+    /// Defaults to the order of `state`, else newest published first
+    pub sort: Option<entry::EntrySortOrder>,
     #[serde(default = "default_limit")]
     pub limit: i64,
     #[serde(default)]
     pub offset: i64,
+    /// `local` serializes entry timestamps in the user's time zone
+    #[serde(default)]
+    pub tz: TimestampZone,
 }
 
 fn default_limit() -> i64 {
     50
 }
 
+impl ListEntriesQuery {
+    fn filter(&self) -> entry::EntryFilter {
+        let mut filter = entry::EntryFilter {
+            feed_id: self.feed_id,
+            category_id: self.category_id,
+            unread_only: self.unread_only,
+            starred_only: self.starred_only,
+            read_only: self.read_only,
+            search: self.search.clone(),
+            search_fields: self.fields,
+            has_summary: self.has_summary,
+            published_after: None,
+            collapse_duplicates: self.collapse,
+            tag: self.tag.clone(),
+            synthetic_feed_url: None,
+        };
+        if let Some(state) = self.state {
+            state.apply(&mut filter, saved_pages::SAVED_PAGES_FEED_URL);
+        }
+        filter
+    }
+
+    pub(crate) fn sort(&self) -> entry::EntrySortOrder {
+        resolve_sort(self.sort, self.state)
+    }
+
+    /// Order used when `sort` is not given
+    pub(crate) fn sort_default(&self) -> entry::EntrySortOrder {
+        resolve_sort(None, self.state)
+    }
+}
+
+fn resolve_sort(
+    sort: Option<entry::EntrySortOrder>,
+    state: Option<entry::EntryState>,
+) -> entry::EntrySortOrder {
+    sort.or(state.map(|s| s.default_sort())).unwrap_or_default()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimestampQuery {
+    /// `local` serializes entry timestamps in the user's time zone
+    #[serde(default)]
+    pub tz: TimestampZone,
+}
+
+/// Serialize `body`, with entry timestamps in `tz` if the client asked for
+/// local time and the user has a time zone
+async fn zoned_response<T: Serialize>(zone: TimestampZone, tz: Option<Tz>, body: T) -> Response {
+    match (zone, tz) {
+        (TimestampZone::Local, Some(tz)) => {
+            timezone::scope(tz, async move { Json(body).into_response() }).await
+        }
+        _ => Json(body).into_response(),
+    }
+}
+
+/// The user's time zone if the client asked for local timestamps
+async fn local_tz(state: &AppState, user_id: i64, zone: TimestampZone) -> AppResult<Option<Tz>> {
+    match zone {
+        TimestampZone::Utc => Ok(None),
+        TimestampZone::Local => Ok(state
+            .db
+            .read(move |conn| user_settings::get_timezone(conn, user_id))
+            .await??),
+    }
+}
+
+/// `zoned_response` that looks up the user's time zone when needed
+async fn zoned_json<T: Serialize>(
+    state: &AppState,
+    user_id: i64,
+    zone: TimestampZone,
+    body: T,
+) -> AppResult<Response> {
+    let tz = local_tz(state, user_id, zone).await?;
+    Ok(zoned_response(zone, tz, body).await)
+}
+
 /// Entry with summary status
 #[derive(Debug, Serialize)]
 pub struct EntryWithSummary {
     #[serde(flatten)]
     pub entry: entry::EntryWithFeed,
     pub summary_status: Option<SummaryStatus>,
+    pub authors: Vec<AuthorResponse>,
+    /// Where the entry matched, when listing search results
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<SearchSnippet>,
+    /// Copies of the story from other feeds, when listing collapsed
+    #[serde(flatten)]
+    pub group: Option<StoryGroup>,
+}
+
+/// Author credited on an entry; the avatar is served through the image proxy
+#[derive(Debug, Serialize)]
+pub struct AuthorResponse {
+    pub id: i64,
+    pub name: String,
+    pub email: Option<String>,
+    pub uri: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+fn author_responses(
+    authors: Option<Vec<author::Author>>,
+    proxy_secret: &[u8],
+) -> Vec<AuthorResponse> {
+    authors
+        .unwrap_or_default()
+        .into_iter()
+        .map(|a| AuthorResponse {
+            id: a.id,
+            name: a.name,
+            email: a.email,
+            uri: a.uri,
+            avatar_url: a.avatar_url.map(|url| create_proxy_url(&url, proxy_secret)),
+        })
+        .collect()
 }
 
 #[derive(Debug, Serialize)]
@@ -51,88 +221,288 @@ pub struct EntriesResponse {
     pub offset: i64,
 }
 
+/// Pages asking for more entries than this are streamed, loading and
+/// serializing this many entries at a time
+const STREAM_CHUNK_SIZE: i64 = 200;
+
+/// Summary statuses, authors, search snippets and story groups for a page
+/// of entries
+struct PageDetails {
+    statuses: HashMap<i64, SummaryStatus>,
+    authors: HashMap<i64, Vec<author::Author>>,
+    snippets: HashMap<i64, SearchSnippet>,
+    groups: HashMap<i64, StoryGroup>,
+}
+
+impl PageDetails {
+    fn load(
+        conn: &rusqlite::Connection,
+        user_id: i64,
+        entries: &[entry::EntryWithFeed],
+        filter: &entry::EntryFilter,
+    ) -> AppResult<Self> {
+        let entry_ids: Vec<i64> = entries.iter().map(|e| e.entry.id).collect();
+        let snippets = match filter.search {
+            Some(ref search) => {
+                entry_search::snippets(conn, &entry_ids, search, filter.search_fields)?
+            }
+            None => HashMap::new(),
+        };
+        let groups = if filter.collapse_duplicates {
+            entry_cluster::groups(conn, user_id, &entry_ids)?
+        } else {
+            HashMap::new()
+        };
+        Ok(PageDetails {
+            statuses: entry_summary::get_statuses_for_entries(conn, user_id, &entry_ids)?,
+            authors: author::list_by_entries(conn, &entry_ids)?,
+            snippets,
+            groups,
+        })
+    }
+}
+
+/// Attach summary statuses (cache first for in-flight, DB for
+/// completed/failed), authors, search snippets and story groups to a page
+/// of entries
+fn with_summaries(
+    state: &AppState,
+    user_id: i64,
+    entries: Vec<entry::EntryWithFeed>,
+    mut details: PageDetails,
+) -> Vec<EntryWithSummary> {
+    entries
+        .into_iter()
+        .map(|e| {
+            let summary_status = match state.summary_cache.get(user_id, e.entry.id) {
+                Some(cached) => Some(cached.status),
+                None => details.statuses.get(&e.entry.id).copied(),
+            };
+            let authors = author_responses(
+                details.authors.remove(&e.entry.id),
+                &state.config.image_proxy_secret,
+            );
+            let snippet = details.snippets.remove(&e.entry.id);
+            let group = details.groups.remove(&e.entry.id);
+            EntryWithSummary {
+                entry: e,
+                summary_status,
+                authors,
+                snippet,
+                group,
+            }
+        })
+        .collect()
+}
+
+/// Load entries with their summary statuses and authors, without counting
+/// the total
+async fn load_entry_chunk(
+    state: &AppState,
+    user_id: i64,
+    filter: entry::EntryFilter,
+    sort: entry::EntrySortOrder,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Vec<EntryWithSummary>> {
+    let (entries, details) = state
+        .db
+        .read(move |conn| {
+            let entries = entry::list_by_user(conn, user_id, &filter, sort, limit, offset)?;
+            let details = PageDetails::load(conn, user_id, &entries, &filter)?;
+            Ok::<_, AppError>((entries, details))
+        })
+        .await??;
+
+    Ok(with_summaries(state, user_id, entries, details))
+}
+
+/// A page larger than `STREAM_CHUNK_SIZE`, sent in chunks
+struct StreamedPage {
+    filter: entry::EntryFilter,
+    sort: entry::EntrySortOrder,
+    total: i64,
+    limit: i64,
+    offset: i64,
+}
+
+/// Respond with a large page of entries without holding it in memory.
+/// The caller loads the first chunk so that errors such as a missing feed
+/// surface as an error response; the rest is paged out in the background.
+fn stream_entries(
+    state: AppState,
+    user_id: i64,
+    tz: Option<Tz>,
+    page: StreamedPage,
+    first: Vec<EntryWithSummary>,
+) -> Response {
+    let (writer, reader) = tokio::io::duplex(64 * 1024);
+    let task = tokio::spawn(async move {
+        let write = write_entries(&state, user_id, page, first, writer);
+        match tz {
+            Some(tz) => timezone::scope(tz, write).await,
+            None => write.await,
+        }
+    });
+
+    // The status is sent by then, so a failure ends the body with an error,
+    // which aborts the response rather than leave truncated JSON looking
+    // complete
+    let outcome = stream::once(async move {
+        let error = match task.await {
+            Ok(Ok(())) => return None,
+            Ok(Err(e)) => e.to_string(),
+            Err(e) => e.to_string(),
+        };
+        warn!("Streaming entries for user {} failed: {}", user_id, error);
+        Some(Err(std::io::Error::other(error)))
+    })
+    .filter_map(std::future::ready);
+
+    (
+        [(CONTENT_TYPE, "application/json")],
+        Body::from_stream(ReaderStream::new(reader).chain(outcome)),
+    )
+        .into_response()
+}
+
+/// Write a page in the shape of `EntriesResponse`, one chunk at a time
+async fn write_entries<W>(
+    state: &AppState,
+    user_id: i64,
+    page: StreamedPage,
+    first: Vec<EntryWithSummary>,
+    mut out: W,
+) -> AppResult<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let internal = |e: std::io::Error| AppError::Internal(e.to_string());
+    let mut buf = b"{\"entries\":[".to_vec();
+    let mut chunk = first;
+    let mut written = 0;
+    loop {
+        let loaded = chunk.len() as i64;
+        for e in chunk {
+            if written > 0 {
+                buf.push(b',');
+            }
+            serde_json::to_writer(&mut buf, &e).map_err(|e| AppError::Internal(e.to_string()))?;
+            written += 1;
+        }
+        out.write_all(&buf).await.map_err(internal)?;
+        buf.clear();
+
+        let remaining = page.limit - written;
+        if loaded < STREAM_CHUNK_SIZE || remaining <= 0 {
+            break;
+        }
+        let limit = remaining.min(STREAM_CHUNK_SIZE);
+        let offset = page.offset + written;
+        chunk = load_entry_chunk(
+            state,
+            user_id,
+            page.filter.clone(),
+            page.sort,
+            limit,
+            offset,
+        )
+        .await?;
+    }
+
+    let tail = format!(
+        "],\"total\":{},\"limit\":{},\"offset\":{}}}",
+        page.total, page.limit, page.offset
+    );
+    out.write_all(tail.as_bytes()).await.map_err(internal)?;
+    out.shutdown().await.map_err(internal)?;
+    Ok(())
+}
+
 pub async fn list_entries(
     auth_user: AuthUser,
     State(state): State<AppState>,
     Query(query): Query<ListEntriesQuery>,
-) -> AppResult<Json<EntriesResponse>> {
+) -> AppResult<Response> {
     let user_id = auth_user.user.id;
+    let zone = query.tz;
+    if query.limit <= STREAM_CHUNK_SIZE {
+        let response = load_entries(&state, user_id, query).await?;
+        return zoned_json(&state, user_id, zone, response).await;
+    }
 
-    let (entries, total, db_statuses) = state
-        .db
-        .user(move |conn| {
-            // Verify category belongs to user if specified
-            if let Some(category_id) = query.category_id {
-                let cat =
-                    category::find_by_id(conn, category_id)?.ok_or(AppError::CategoryNotFound)?;
-                if cat.user_id != user_id {
-                    return Err(AppError::CategoryNotFound);
-                }
-            }
-
-            // Verify feed belongs to user if specified
-            if let Some(feed_id) = query.feed_id {
-                let f = feed::find_by_id(conn, feed_id)?.ok_or(AppError::FeedNotFound)?;
-                let cat =
-                    category::find_by_id(conn, f.category_id)?.ok_or(AppError::CategoryNotFound)?;
-                if cat.user_id != user_id {
-                    return Err(AppError::FeedNotFound);
-                }
-            }
+    let first = ListEntriesQuery {
+        limit: STREAM_CHUNK_SIZE,
+        ..query.clone()
+    };
+    let first = load_entries(&state, user_id, first).await?;
+    let tz = local_tz(&state, user_id, zone).await?;
+    let page = StreamedPage {
+        filter: query.filter(),
+        sort: query.sort(),
+        total: first.total,
+        limit: query.limit,
+        offset: query.offset,
+    };
+    Ok(stream_entries(state, user_id, tz, page, first.entries))
+}
 
-            let filter = entry::EntryFilter {
-                feed_id: query.feed_id,
-                category_id: query.category_id,
-                unread_only: query.unread_only,
-                starred_only: query.starred_only,
-                read_only: query.read_only,
-                search: query.search.clone(),
-                has_summary: query.has_summary,
-            };
+/// Check the feed and category `filter` narrows to belong to the user. The
+/// listing queries only ever match the user's entries, so this only needs
+/// asking when they came back empty, to tell someone else's feed (404) from
+/// an empty one.
+fn assert_filter_owned(
+    conn: &rusqlite::Connection,
+    user_id: i64,
+    filter: &entry::EntryFilter,
+) -> AppResult<()> {
+    if let Some(category_id) = filter.category_id {
+        ownership::assert_category_owned(conn, user_id, category_id)?;
+    }
+    if let Some(feed_id) = filter.feed_id {
+        ownership::assert_feed_owned(conn, user_id, feed_id)?;
+    }
+    Ok(())
+}
 
+/// Load a page of the user's entries with summary statuses and authors,
+/// shared by the JSON API and the HTML fragments
+pub(crate) async fn load_entries(
+    state: &AppState,
+    user_id: i64,
+    query: ListEntriesQuery,
+) -> AppResult<EntriesResponse> {
+    let (limit, offset) = (query.limit, query.offset);
+    let (entries, total, details) = state
+        .db
+        .read(move |conn| {
+            let filter = query.filter();
             let entries = entry::list_by_user(
                 conn,
                 user_id,
                 &filter,
-                query.sort,
+                query.sort(),
                 query.limit,
                 query.offset,
             )?;
             let total = entry::count_by_user(conn, user_id, &filter)?;
+            if total == 0 {
+                assert_filter_owned(conn, user_id, &filter)?;
+            }
 
-            // Batch query summary statuses from DB
-            let entry_ids: Vec<i64> = entries.iter().map(|e| e.entry.id).collect();
-            let db_statuses = entry_summary::get_statuses_for_entries(conn, user_id, &entry_ids)?;
+            // Batch query summary statuses, authors and snippets from DB
+            let details = PageDetails::load(conn, user_id, &entries, &filter)?;
 
-            Ok::<_, AppError>((entries, total, db_statuses))
+            Ok::<_, AppError>((entries, total, details))
         })
         .await??;
 
-    // Build response with summary status (prefer cache for in-flight, DB for completed/failed)
-    let entries_with_summary: Vec<EntryWithSummary> = entries
-        .into_iter()
-        .map(|e| {
-            // Check cache first for in-flight status
-            let summary_status = if let Some(cached) = state.summary_cache.get(user_id, e.entry.id)
-            {
-                Some(cached.status)
-            } else {
-                // Fall back to DB status
-                db_statuses.get(&e.entry.id).copied()
-            };
-            EntryWithSummary {
-                entry: e,
-                summary_status,
-            }
-        })
-        .collect();
-
-    Ok(Json(EntriesResponse {
-        entries: entries_with_summary,
+    Ok(EntriesResponse {
+        entries: with_summaries(state, user_id, entries, details),
         total,
-        limit: query.limit,
-        offset: query.offset,
-    }))
+        limit,
+        offset,
+    })
 }
 
 #[derive(Debug, Serialize)]
@@ -141,34 +511,54 @@ pub struct EntryResponse {
     pub entry: entry::EntryWithFeed,
     pub sanitized_content: Option<String>,
     pub summary_status: Option<SummaryStatus>,
+    pub authors: Vec<AuthorResponse>,
+    /// Earlier versions kept from feed updates
+    pub revision_count: i64,
+    /// Publication date exactly as the feed wrote it, for debugging dates
+    pub published_raw: Option<String>,
 }
 
 pub async fn get_entry(
     auth_user: AuthUser,
     State(state): State<AppState>,
     Path(id): Path<i64>,
-) -> AppResult<Json<EntryResponse>> {
+    Query(query): Query<TimestampQuery>,
+) -> AppResult<Response> {
     let user_id = auth_user.user.id;
     let proxy_secret = state.config.image_proxy_secret.clone();
-
-    let (entry_with_feed, summary_status_db) = state
+    let blocked = state.blocklist.snapshot();
+
+    let (
+        entry_with_feed,
+        version,
+        summary_status_db,
+        authors,
+        policy,
+        revision_count,
+        published_raw,
+    ) = state
         .db
         .user(move |conn| {
-            let entry_with_feed =
-                entry::find_by_id_with_feed(conn, id)?.ok_or(AppError::EntryNotFound)?;
-
-            // Verify entry belongs to user
-            let cat = category::find_by_id(conn, entry_with_feed.category_id)?
-                .ok_or(AppError::CategoryNotFound)?;
-            if cat.user_id != user_id {
-                return Err(AppError::EntryNotFound);
-            }
+            let entry_with_feed = ownership::find_owned_entry(conn, user_id, id)?;
+            let version = entry::version(conn, id)?;
 
             // Check summary status from DB
             let summary_status_db =
                 entry_summary::find_by_user_and_entry(conn, user_id, id)?.map(|s| s.status);
-
-            Ok::<_, AppError>((entry_with_feed, summary_status_db))
+            let authors = author::list_by_entry(conn, id)?;
+            let policy = user_settings::get_sanitize_policy(conn, user_id)?;
+            let revision_count = entry_revision::count_by_entry(conn, id)?;
+            let published_raw = entry::get_published_raw(conn, id)?;
+
+            Ok::<_, AppError>((
+                entry_with_feed,
+                version,
+                summary_status_db,
+                authors,
+                policy,
+                revision_count,
+                published_raw,
+            ))
         })
         .await??;
 
@@ -178,7 +568,7 @@ pub async fn get_entry(
         .entry
         .content
         .as_ref()
-        .map(|c| sanitize_html(c, &proxy_secret, base_url));
+        .map(|c| sanitize_html_with_policy(c, &proxy_secret, base_url, &policy, &blocked));
 
     // Check summary status (cache first, then DB)
     let summary_status = if let Some(cached) = state.summary_cache.get(user_id, id) {
@@ -187,11 +577,16 @@ pub async fn get_entry(
         summary_status_db
     };
 
-    Ok(Json(EntryResponse {
+    let response = EntryResponse {
         entry: entry_with_feed,
         sanitized_content,
         summary_status,
-    }))
+        authors: author_responses(Some(authors), &proxy_secret),
+        revision_count,
+        published_raw,
+    };
+    let response = zoned_json(&state, user_id, query.tz, response).await?;
+    Ok((version_etag(version), response).into_response())
 }
 
 pub async fn list_feed_entries(
@@ -199,146 +594,128 @@ pub async fn list_feed_entries(
     State(state): State<AppState>,
     Path(feed_id): Path<i64>,
     Query(query): Query<ListEntriesQuery>,
-) -> AppResult<Json<EntriesResponse>> {
+) -> AppResult<Response> {
     let user_id = auth_user.user.id;
+    let filter = entry::EntryFilter {
+        feed_id: Some(feed_id),
+        category_id: None,
+        ..query.filter()
+    };
+    let (sort, limit, offset) = (query.sort(), query.limit, query.offset);
 
-    let (entries, total, db_statuses) = state
+    let page_filter = filter.clone();
+    let (entries, total, details) = state
         .db
-        .user(move |conn| {
-            // Verify feed belongs to user
-            let f = feed::find_by_id(conn, feed_id)?.ok_or(AppError::FeedNotFound)?;
-            let cat =
-                category::find_by_id(conn, f.category_id)?.ok_or(AppError::CategoryNotFound)?;
-            if cat.user_id != user_id {
-                return Err(AppError::FeedNotFound);
-            }
-
-            let filter = entry::EntryFilter {
-                feed_id: Some(feed_id),
-                category_id: None,
-                unread_only: query.unread_only,
-                starred_only: query.starred_only,
-                read_only: query.read_only,
-                search: query.search,
-                has_summary: query.has_summary,
-            };
-
+        .read(move |conn| {
             let entries = entry::list_by_user(
                 conn,
                 user_id,
-                &filter,
-                query.sort,
-                query.limit,
-                query.offset,
+                &page_filter,
+                sort,
+                limit.min(STREAM_CHUNK_SIZE),
+                offset,
             )?;
-            let total = entry::count_by_user(conn, user_id, &filter)?;
+            let total = entry::count_by_user(conn, user_id, &page_filter)?;
+            if total == 0 {
+                assert_filter_owned(conn, user_id, &page_filter)?;
+            }
 
-            // Batch query summary statuses from DB
-            let entry_ids: Vec<i64> = entries.iter().map(|e| e.entry.id).collect();
-            let db_statuses = entry_summary::get_statuses_for_entries(conn, user_id, &entry_ids)?;
+            // Batch query summary statuses, authors and snippets from DB
+            let details = PageDetails::load(conn, user_id, &entries, &page_filter)?;
 
-            Ok::<_, AppError>((entries, total, db_statuses))
+            Ok::<_, AppError>((entries, total, details))
         })
         .await??;
 
-    // Build response with summary status (prefer cache for in-flight, DB for completed/failed)
-    let entries_with_summary: Vec<EntryWithSummary> = entries
-        .into_iter()
-        .map(|e| {
-            let summary_status = if let Some(cached) = state.summary_cache.get(user_id, e.entry.id)
-            {
-                Some(cached.status)
-            } else {
-                db_statuses.get(&e.entry.id).copied()
-            };
-            EntryWithSummary {
-                entry: e,
-                summary_status,
-            }
-        })
-        .collect();
+    let entries = with_summaries(&state, user_id, entries, details);
+    if limit > STREAM_CHUNK_SIZE {
+        let tz = local_tz(&state, user_id, query.tz).await?;
+        let page = StreamedPage {
+            filter,
+            sort,
+            total,
+            limit,
+            offset,
+        };
+        return Ok(stream_entries(state, user_id, tz, page, entries));
+    }
 
-    Ok(Json(EntriesResponse {
-        entries: entries_with_summary,
+    let response = EntriesResponse {
+        entries,
         total,
-        limit: query.limit,
-        offset: query.offset,
-    }))
+        limit,
+        offset,
+    };
+    zoned_json(&state, user_id, query.tz, response).await
+}
+
+/// Apply a read or star change to one of the user's entries, provided it is
+/// still at the version the client saw. Returns the entry and its new version.
+fn change_entry_state(
+    conn: &rusqlite::Connection,
+    user_id: i64,
+    id: i64,
+    if_match: IfMatch,
+    change: fn(&rusqlite::Connection, i64) -> AppResult<entry::Entry>,
+) -> AppResult<(entry::Entry, i64)> {
+    ownership::assert_entry_owned(conn, user_id, id)?;
+    if_match.check(entry::version(conn, id)?, || {
+        entry::find_by_id(conn, id)?.ok_or(AppError::EntryNotFound)
+    })?;
+
+    let updated = change(conn, id)?;
+    Ok((updated, entry::version(conn, id)?))
 }
 
 pub async fn mark_entry_read(
     auth_user: AuthUser,
     State(state): State<AppState>,
     Path(id): Path<i64>,
-) -> AppResult<Json<entry::Entry>> {
+    if_match: IfMatch,
+) -> AppResult<Response> {
     let user_id = auth_user.user.id;
-    let updated = state
+    let (updated, version) = state
         .db
-        .user(move |conn| {
-            // Verify entry belongs to user
-            let entry_with_feed =
-                entry::find_by_id_with_feed(conn, id)?.ok_or(AppError::EntryNotFound)?;
-            let cat = category::find_by_id(conn, entry_with_feed.category_id)?
-                .ok_or(AppError::CategoryNotFound)?;
-            if cat.user_id != user_id {
-                return Err(AppError::EntryNotFound);
-            }
-
-            let updated = entry::mark_as_read(conn, id)?;
-            Ok::<_, AppError>(updated)
-        })
+        .user(move |conn| change_entry_state(conn, user_id, id, if_match, entry::mark_as_read))
         .await??;
-    Ok(Json(updated))
+    Ok((version_etag(version), Json(updated)).into_response())
 }
 
 pub async fn mark_entry_unread(
     auth_user: AuthUser,
     State(state): State<AppState>,
     Path(id): Path<i64>,
-) -> AppResult<Json<entry::Entry>> {
+    if_match: IfMatch,
+) -> AppResult<Response> {
     let user_id = auth_user.user.id;
-    let updated = state
+    let (updated, version) = state
         .db
-        .user(move |conn| {
-            // Verify entry belongs to user
-            let entry_with_feed =
-                entry::find_by_id_with_feed(conn, id)?.ok_or(AppError::EntryNotFound)?;
-            let cat = category::find_by_id(conn, entry_with_feed.category_id)?
-                .ok_or(AppError::CategoryNotFound)?;
-            if cat.user_id != user_id {
-                return Err(AppError::EntryNotFound);
-            }
-
-            let updated = entry::mark_as_unread(conn, id)?;
-            Ok::<_, AppError>(updated)
-        })
+        .user(move |conn| change_entry_state(conn, user_id, id, if_match, entry::mark_as_unread))
         .await??;
-    Ok(Json(updated))
+    Ok((version_etag(version), Json(updated)).into_response())
 }
 
 pub async fn toggle_entry_star(
     auth_user: AuthUser,
     State(state): State<AppState>,
     Path(id): Path<i64>,
-) -> AppResult<Json<entry::Entry>> {
+    if_match: IfMatch,
+) -> AppResult<Response> {
     let user_id = auth_user.user.id;
-    let updated = state
+    let (updated, version, queued) = state
         .db
         .user(move |conn| {
-            // Verify entry belongs to user
-            let entry_with_feed =
-                entry::find_by_id_with_feed(conn, id)?.ok_or(AppError::EntryNotFound)?;
-            let cat = category::find_by_id(conn, entry_with_feed.category_id)?
-                .ok_or(AppError::CategoryNotFound)?;
-            if cat.user_id != user_id {
-                return Err(AppError::EntryNotFound);
-            }
-
-            let updated = entry::toggle_star(conn, id)?;
-            Ok::<_, AppError>(updated)
+            let (updated, version) =
+                change_entry_state(conn, user_id, id, if_match, entry::toggle_star)?;
+            let queued =
+                updated.starred_at.is_some() && jobs::save::queue_starred(conn, user_id, id)?;
+            Ok::<_, AppError>((updated, version, queued))
         })
         .await??;
-    Ok(Json(updated))
+    if queued {
+        state.jobs.wake();
+    }
+    Ok((version_etag(version), Json(updated)).into_response())
 }
 
 #[derive(Debug, Deserialize)]
@@ -353,6 +730,10 @@ pub struct MarkAllReadRequest {
 #[derive(Debug, Serialize)]
 pub struct MarkAllReadResponse {
     pub marked_count: i64,
+    /// Reverts the action through `POST /api/undo/{token}`; absent when
+    /// nothing was marked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub undo_token: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -363,6 +744,10 @@ pub struct MarkReadByIdsRequest {
 #[derive(Debug, Serialize)]
 pub struct MarkReadByIdsResponse {
     pub marked_count: i64,
+    /// Requested IDs that are not the user's entries, or do not exist
+    pub rejected_ids: Vec<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub undo_token: Option<String>,
 }
 
 pub async fn mark_all_read(
@@ -371,186 +756,655 @@ pub async fn mark_all_read(
     Json(body): Json<MarkAllReadRequest>,
 ) -> AppResult<Json<MarkAllReadResponse>> {
     let user_id = auth_user.user.id;
-    let marked_count = state
+    let response = state
         .db
         .user(move |conn| {
             let older_than_days = body.older_than_days;
 
-            let marked_count = if let Some(feed_id) = body.feed_id {
+            let marked = if let Some(feed_id) = body.feed_id {
                 // Verify feed belongs to user
-                let f = feed::find_by_id(conn, feed_id)?.ok_or(AppError::FeedNotFound)?;
-                let cat =
-                    category::find_by_id(conn, f.category_id)?.ok_or(AppError::CategoryNotFound)?;
-                if cat.user_id != user_id {
-                    return Err(AppError::FeedNotFound);
-                }
+                ownership::assert_feed_owned(conn, user_id, feed_id)?;
                 entry::mark_all_read_by_feed(conn, feed_id, older_than_days)?
             } else if let Some(category_id) = body.category_id {
                 // Verify category belongs to user
-                let cat =
-                    category::find_by_id(conn, category_id)?.ok_or(AppError::CategoryNotFound)?;
-                if cat.user_id != user_id {
-                    return Err(AppError::CategoryNotFound);
-                }
+                ownership::assert_category_owned(conn, user_id, category_id)?;
                 entry::mark_all_read_by_category(conn, category_id, older_than_days)?
             } else {
                 entry::mark_all_read_by_user(conn, user_id, older_than_days)?
             };
 
-            Ok::<_, AppError>(marked_count)
+            let marked_count = marked.len() as i64;
+            let undo_token = undo::record_mark_read(conn, user_id, marked)?;
+            Ok::<_, AppError>(MarkAllReadResponse {
+                marked_count,
+                undo_token,
+            })
         })
         .await??;
 
-    Ok(Json(MarkAllReadResponse { marked_count }))
-}
-
-pub async fn mark_read_by_ids(
-    auth_user: AuthUser,
-    State(state): State<AppState>,
-    Json(body): Json<MarkReadByIdsRequest>,
-) -> AppResult<Json<MarkReadByIdsResponse>> {
-    let user_id = auth_user.user.id;
-    let marked_count = state
-        .db
-        .user(move |conn| entry::mark_read_by_ids(conn, user_id, &body.entry_ids))
-        .await??;
-
-    Ok(Json(MarkReadByIdsResponse { marked_count }))
+    Ok(Json(response))
 }
 
-#[derive(Debug, Serialize)]
-pub struct UnreadStatsResponse {
-    pub by_feed: std::collections::HashMap<i64, i64>,
-    pub by_category: std::collections::HashMap<i64, i64>,
+#[derive(Debug, Deserialize)]
+pub struct MarkReadBeforeRequest {
+    /// Reference entry; it is not marked itself
+    pub entry_id: i64,
+    /// Mark entries listed below the reference instead of above it
+    #[serde(default)]
+    pub older: bool,
+    pub feed_id: Option<i64>,
+    pub category_id: Option<i64>,
+    #[serde(default)]
+    pub starred_only: bool,
+    pub search: Option<String>,
+    #[serde(default)]
+    pub fields: SearchFields,
+    pub has_summary: Option<bool>,
+    #[serde(default)]
+    pub sort: entry::EntrySortOrder,
 }
 
-pub async fn get_unread_stats(
+/// Catch up on a list: mark everything above (or below) an entry as read
+pub async fn mark_read_before(
     auth_user: AuthUser,
     State(state): State<AppState>,
-) -> AppResult<Json<UnreadStatsResponse>> {
+    Json(body): Json<MarkReadBeforeRequest>,
+) -> AppResult<Json<MarkAllReadResponse>> {
     let user_id = auth_user.user.id;
-    let (by_feed, by_category) = state
+    let response = state
         .db
         .user(move |conn| {
-            let by_feed = entry::count_unread_by_feed(conn, user_id)?;
-            let by_category = entry::count_unread_by_category(conn, user_id)?;
-            Ok::<_, AppError>((by_feed, by_category))
+            let filter = entry::EntryFilter {
+                feed_id: body.feed_id,
+                category_id: body.category_id,
+                unread_only: true,
+                starred_only: body.starred_only,
+                search: body.search,
+                search_fields: body.fields,
+                has_summary: body.has_summary,
+                ..Default::default()
+            };
+            let marked = entry::mark_read_relative_to(
+                conn,
+                user_id,
+                body.entry_id,
+                &filter,
+                body.sort,
+                body.older,
+            )?
+            .ok_or(AppError::EntryNotFound)?;
+
+            let marked_count = marked.len() as i64;
+            let undo_token = undo::record_mark_read(conn, user_id, marked)?;
+            Ok::<_, AppError>(MarkAllReadResponse {
+                marked_count,
+                undo_token,
+            })
         })
         .await??;
 
-    Ok(Json(UnreadStatsResponse {
-        by_feed,
-        by_category,
-    }))
+    Ok(Json(response))
 }
 
-pub async fn refresh_feed_handler(
+pub async fn mark_read_by_ids(
     auth_user: AuthUser,
     State(state): State<AppState>,
-    Path(feed_id): Path<i64>,
-) -> AppResult<Json<SyncResult>> {
-    // Verify feed belongs to user
+    Json(body): Json<MarkReadByIdsRequest>,
+) -> AppResult<Json<MarkReadByIdsResponse>> {
     let user_id = auth_user.user.id;
-    state
+    let response = state
         .db
         .user(move |conn| {
-            let f = feed::find_by_id(conn, feed_id)?.ok_or(AppError::FeedNotFound)?;
-            let cat =
-                category::find_by_id(conn, f.category_id)?.ok_or(AppError::CategoryNotFound)?;
-            if cat.user_id != user_id {
-                return Err(AppError::FeedNotFound);
-            }
-            Ok::<_, AppError>(())
+            // The update checks ownership itself. Only IDs it left alone,
+            // already read or not the user's, are looked up again to tell
+            // which were rejected.
+            let marked = entry::mark_read_by_ids(conn, user_id, &body.entry_ids)?;
+            let marked_set: HashSet<i64> = marked.iter().copied().collect();
+            let unmarked: Vec<i64> = body
+                .entry_ids
+                .iter()
+                .copied()
+                .filter(|id| !marked_set.contains(id))
+                .collect();
+            let owned = ownership::owned_entry_ids(conn, user_id, &unmarked)?;
+            let mut rejected_ids: Vec<i64> = unmarked
+                .into_iter()
+                .filter(|id| !owned.contains(id))
+                .collect();
+            rejected_ids.sort_unstable();
+            rejected_ids.dedup();
+
+            let marked_count = marked.len() as i64;
+            let undo_token = undo::record_mark_read(conn, user_id, marked)?;
+            Ok::<_, AppError>(MarkReadByIdsResponse {
+                marked_count,
+                rejected_ids,
+                undo_token,
+            })
         })
         .await??;
 
-    let result = refresh_feed(state.db.clone(), feed_id, &state.config.user_agent).await?;
-    Ok(Json(result))
+    Ok(Json(response))
 }
 
-#[derive(Debug, Serialize)]
-pub struct FetchFullContentResponse {
-    pub title: Option<String>,
-    pub content: String,
-    pub sanitized_content: String,
-}
+/// Largest number of entries accepted in one ack-seen batch
+pub const MAX_ACK_BATCH: usize = 500;
 
 #[derive(Debug, Deserialize)]
-pub struct NeighborsQuery {
+pub struct SeenEntry {
+    pub id: i64,
+    /// How long the entry was visible in the viewport
     #[serde(default)]
-    pub unread_only: bool,
-    pub feed_id: Option<i64>,
-    pub category_id: Option<i64>,
+    pub dwell_ms: i64,
 }
 
-pub async fn get_entry_neighbors(
+#[derive(Debug, Deserialize)]
+pub struct AckSeenRequest {
+    pub entries: Vec<SeenEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AckSeenResponse {
+    pub enabled: bool,
+    pub marked_count: i64,
+}
+
+/// Acknowledge entries the client viewport has scrolled past. Clients are
+/// expected to queue acks and flush them in batches; each batch is applied
+/// with a single UPDATE at background priority.
+pub async fn ack_seen(
     auth_user: AuthUser,
     State(state): State<AppState>,
-    Path(id): Path<i64>,
-    Query(query): Query<NeighborsQuery>,
-) -> AppResult<Json<entry::EntryNeighbors>> {
+    Json(body): Json<AckSeenRequest>,
+) -> AppResult<Json<AckSeenResponse>> {
+    if body.entries.len() > MAX_ACK_BATCH {
+        return Err(AppError::Validation(format!(
+            "At most {} entries can be acknowledged at once",
+            MAX_ACK_BATCH
+        )));
+    }
+
     let user_id = auth_user.user.id;
-    let neighbors = state
+    let response = state
         .db
-        .user(move |conn| {
-            // Verify entry belongs to user
-            let entry_with_feed =
-                entry::find_by_id_with_feed(conn, id)?.ok_or(AppError::EntryNotFound)?;
-            let cat = category::find_by_id(conn, entry_with_feed.category_id)?
-                .ok_or(AppError::CategoryNotFound)?;
-            if cat.user_id != user_id {
-                return Err(AppError::EntryNotFound);
+        .background(move |conn| {
+            let settings = user_settings::get_scroll_read(conn, user_id)?;
+            if !settings.enabled {
+                return Ok::<_, AppError>(AckSeenResponse {
+                    enabled: false,
+                    marked_count: 0,
+                });
             }
 
-            let neighbors = entry::find_neighbors(
-                conn,
-                user_id,
-                id,
-                query.unread_only,
-                query.feed_id,
-                query.category_id,
-            )?;
-            Ok::<_, AppError>(neighbors)
+            let mut ids: Vec<i64> = body
+                .entries
+                .iter()
+                .filter(|e| e.dwell_ms >= settings.dwell_ms)
+                .map(|e| e.id)
+                .collect();
+            ids.sort_unstable();
+            ids.dedup();
+
+            let marked_count = entry::mark_read_by_ids(conn, user_id, &ids)?.len() as i64;
+            Ok(AckSeenResponse {
+                enabled: true,
+                marked_count,
+            })
         })
         .await??;
-    Ok(Json(neighbors))
+
+    Ok(Json(response))
 }
 
-pub async fn fetch_full_content(
-    auth_user: AuthUser,
-    State(state): State<AppState>,
-    Path(id): Path<i64>,
-) -> AppResult<Json<FetchFullContentResponse>> {
-    // Verify entry exists and belongs to user
+#[derive(Debug, Deserialize)]
+pub struct UnreadStatsQuery {
+    /// Client timezone offset as reported by `Date.getTimezoneOffset()`, used
+    /// when the user has not set a time zone
+    #[serde(default)]
+    pub tz_offset: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnreadStatsResponse {
+    pub by_feed: HashMap<i64, i64>,
+    pub by_category: HashMap<i64, i64>,
+    #[serde(flatten)]
+    pub totals: entry::StreamTotals,
+}
+
+pub async fn get_unread_stats(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<UnreadStatsQuery>,
+) -> AppResult<Json<UnreadStatsResponse>> {
     let user_id = auth_user.user.id;
-    let link = state
+    let now = chrono::Utc::now();
+    let response = state
+        .db
+        .read(move |conn| {
+            let by_feed = entry::count_unread_by_feed(conn, user_id)?;
+            let by_category = entry::count_unread_by_category(conn, user_id)?;
+            let tz = user_settings::get_timezone(conn, user_id)?;
+
+            let since = |stream: entry::VirtualStream| match tz {
+                Some(tz) => stream.since_in(now, tz),
+                None => stream.since(now, query.tz_offset),
+            };
+            let totals = entry::count_stream_totals(
+                conn,
+                user_id,
+                saved_pages::SAVED_PAGES_FEED_URL,
+                since(entry::VirtualStream::Today),
+                since(entry::VirtualStream::Last24Hours),
+            )?;
+
+            Ok::<_, AppError>(UnreadStatsResponse {
+                by_feed,
+                by_category,
+                totals,
+            })
+        })
+        .await??;
+
+    Ok(Json(response))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamQuery {
+    #[serde(default = "default_stream_unread_only")]
+    pub unread_only: bool,
+    /// Client timezone offset as reported by `Date.getTimezoneOffset()`, used
+    /// when the user has not set a time zone
+    #[serde(default)]
+    pub tz_offset: i32,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+    /// `local` serializes entry timestamps in the user's time zone
+    #[serde(default)]
+    pub tz: TimestampZone,
+}
+
+fn default_stream_unread_only() -> bool {
+    true
+}
+
+async fn list_stream_entries(
+    state: AppState,
+    user_id: i64,
+    stream: entry::VirtualStream,
+    query: StreamQuery,
+) -> AppResult<Response> {
+    let now = chrono::Utc::now();
+    let sort = entry::EntrySortOrder::PublishedAt;
+
+    let (tz, filter, entries, total, details) = state
+        .db
+        .read(move |conn| {
+            let tz = user_settings::get_timezone(conn, user_id)?;
+            let since = match tz {
+                Some(tz) => stream.since_in(now, tz),
+                None => stream.since(now, query.tz_offset),
+            };
+            let filter = entry::EntryFilter {
+                unread_only: query.unread_only,
+                published_after: Some(since),
+                ..Default::default()
+            };
+
+            let entries = entry::list_by_user(
+                conn,
+                user_id,
+                &filter,
+                sort,
+                query.limit.min(STREAM_CHUNK_SIZE),
+                query.offset,
+            )?;
+            let total = entry::count_by_user(conn, user_id, &filter)?;
+
+            let details = PageDetails::load(conn, user_id, &entries, &filter)?;
+
+            Ok::<_, AppError>((tz, filter, entries, total, details))
+        })
+        .await??;
+
+    let entries = with_summaries(&state, user_id, entries, details);
+    if query.limit > STREAM_CHUNK_SIZE {
+        let tz = tz.filter(|_| query.tz == TimestampZone::Local);
+        let page = StreamedPage {
+            filter,
+            sort,
+            total,
+            limit: query.limit,
+            offset: query.offset,
+        };
+        return Ok(stream_entries(state, user_id, tz, page, entries));
+    }
+
+    let response = EntriesResponse {
+        entries,
+        total,
+        limit: query.limit,
+        offset: query.offset,
+    };
+    Ok(zoned_response(query.tz, tz, response).await)
+}
+
+pub async fn list_today_entries(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<StreamQuery>,
+) -> AppResult<Response> {
+    list_stream_entries(state, auth_user.user.id, entry::VirtualStream::Today, query).await
+}
+
+pub async fn list_last_24h_entries(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<StreamQuery>,
+) -> AppResult<Response> {
+    list_stream_entries(
+        state,
+        auth_user.user.id,
+        entry::VirtualStream::Last24Hours,
+        query,
+    )
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OfflineBundleQuery {
+    pub max_bytes: Option<usize>,
+    #[serde(default = "default_offline_limit")]
+    pub limit: i64,
+}
+
+fn default_offline_limit() -> i64 {
+    200
+}
+
+pub async fn get_offline_bundle(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<OfflineBundleQuery>,
+) -> AppResult<Json<OfflineBundle>> {
+    let max_bytes = query
+        .max_bytes
+        .unwrap_or(DEFAULT_BUNDLE_BYTES)
+        .min(MAX_BUNDLE_BYTES);
+    let limit = query.limit.clamp(1, 1000);
+
+    let user_id = auth_user.user.id;
+    let entries = state
+        .db
+        .read(move |conn| {
+            let filter = entry::EntryFilter {
+                unread_only: true,
+                ..Default::default()
+            };
+            entry::list_by_user(
+                conn,
+                user_id,
+                &filter,
+                entry::EntrySortOrder::PublishedAt,
+                limit,
+                0,
+            )
+        })
+        .await??;
+
+    let blocked = state.blocklist.snapshot();
+    let bundle = build_bundle(
+        entries,
+        &state.config.image_proxy_secret,
+        &blocked,
+        max_bytes,
+    );
+    Ok(Json(bundle))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EntryChangesQuery {
+    /// Cursor from the previous call; without one every entry is returned
+    pub since: Option<String>,
+    pub limit: Option<i64>,
+}
+
+pub async fn get_entry_changes(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<EntryChangesQuery>,
+) -> AppResult<Json<EntryChanges>> {
+    let since = match query.since.as_deref() {
+        Some(since) => Cursor::parse(since)
+            .ok_or_else(|| AppError::Validation("Invalid sync cursor".to_string()))?,
+        None => Cursor::default(),
+    };
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_CHANGES_LIMIT)
+        .clamp(1, MAX_CHANGES_LIMIT);
+
+    let user_id = auth_user.user.id;
+    let changes = state
+        .db
+        .read(move |conn| entry_change::changes_since(conn, user_id, since, limit))
+        .await??;
+    Ok(Json(changes))
+}
+
+pub async fn refresh_feed_handler(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path(feed_id): Path<i64>,
+) -> AppResult<Json<SyncResult>> {
+    refresh_owned_feed(&state, auth_user.user.id, feed_id, false).await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshFeedsQuery {
+    pub category_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RefreshFeedsResponse {
+    pub queued: usize,
+}
+
+/// POST /api/feeds/refresh - Queue all of the user's feeds, or those in
+/// `category_id`, to be fetched now. Progress shows in `GET /api/jobs`.
+pub async fn refresh_feeds_handler(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<RefreshFeedsQuery>,
+) -> AppResult<(StatusCode, Json<RefreshFeedsResponse>)> {
+    let user_id = auth_user.user.id;
+    let queued = state
         .db
         .user(move |conn| {
-            let entry_with_feed =
-                entry::find_by_id_with_feed(conn, id)?.ok_or(AppError::EntryNotFound)?;
+            let feeds = match query.category_id {
+                Some(category_id) => {
+                    ownership::assert_category_owned(conn, user_id, category_id)?;
+                    feed::list_by_category(conn, category_id)?
+                }
+                None => feed::list_by_user(conn, user_id)?,
+            };
+
+            let mut queued = 0;
+            for f in feeds.iter().filter(|f| !f.synthetic) {
+                let feed_id = f.id;
+                jobs::enqueue(conn, &Task::Refresh(RefreshJob { user_id, feed_id }))?;
+                queued += 1;
+            }
+            Ok::<_, AppError>(queued)
+        })
+        .await??;
+
+    state.jobs.wake();
+    Ok((StatusCode::ACCEPTED, Json(RefreshFeedsResponse { queued })))
+}
 
-            let cat = category::find_by_id(conn, entry_with_feed.category_id)?
-                .ok_or(AppError::CategoryNotFound)?;
-            if cat.user_id != user_id {
-                return Err(AppError::EntryNotFound);
+/// Lift a feed's quarantine and backoff and fetch it right away
+pub async fn retry_feed_handler(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path(feed_id): Path<i64>,
+) -> AppResult<Json<SyncResult>> {
+    refresh_owned_feed(&state, auth_user.user.id, feed_id, true).await
+}
+
+async fn refresh_owned_feed(
+    state: &AppState,
+    user_id: i64,
+    feed_id: i64,
+    clear_backoff: bool,
+) -> AppResult<Json<SyncResult>> {
+    // Verify feed belongs to user
+    state
+        .db
+        .user(move |conn| {
+            ownership::assert_feed_owned(conn, user_id, feed_id)?;
+            if clear_backoff {
+                feed::clear_backoff(conn, feed_id)?;
             }
+            Ok::<_, AppError>(())
+        })
+        .await??;
+
+    let settings = state.settings.current();
+    let result = state
+        .refreshes
+        .run(feed_id, || {
+            refresh_feed(
+                state.db.clone(),
+                feed_id,
+                &settings.user_agent,
+                state.config.feed_cookie_secret.as_deref(),
+            )
+        })
+        .await?;
+    Ok(Json(result))
+}
+
+#[derive(Debug, Serialize)]
+pub struct FetchFullContentResponse {
+    pub title: Option<String>,
+    pub content: String,
+    pub sanitized_content: String,
+}
+
+/// Filter context of the list an entry was opened from; mirrors `ListEntriesQuery`
+#[derive(Debug, Deserialize)]
+pub struct NeighborsQuery {
+    #[serde(default)]
+    pub unread_only: bool,
+    #[serde(default)]
+    pub starred_only: bool,
+    #[serde(default)]
+    pub read_only: bool,
+    pub feed_id: Option<i64>,
+    pub category_id: Option<i64>,
+    pub search: Option<String>,
+    #[serde(default)]
+    pub fields: SearchFields,
+    pub has_summary: Option<bool>,
+    pub tag: Option<String>,
+    pub state: Option<entry::EntryState>,
+    pub sort: Option<entry::EntrySortOrder>,
+    #[serde(default)]
+    pub collapse: bool,
+}
+
+pub async fn get_entry_neighbors(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<NeighborsQuery>,
+) -> AppResult<Json<entry::EntryNeighbors>> {
+    let user_id = auth_user.user.id;
+    let neighbors = state
+        .db
+        .read(move |conn| {
+            let mut filter = entry::EntryFilter {
+                feed_id: query.feed_id,
+                category_id: query.category_id,
+                unread_only: query.unread_only,
+                starred_only: query.starred_only,
+                read_only: query.read_only,
+                search: query.search,
+                search_fields: query.fields,
+                has_summary: query.has_summary,
+                tag: query.tag,
+                collapse_duplicates: query.collapse,
+                ..Default::default()
+            };
+            if let Some(state) = query.state {
+                state.apply(&mut filter, saved_pages::SAVED_PAGES_FEED_URL);
+            }
+            let sort = resolve_sort(query.sort, query.state);
+            entry::find_neighbors(conn, user_id, id, &filter, sort)?.ok_or(AppError::EntryNotFound)
+        })
+        .await??;
+    Ok(Json(neighbors))
+}
+
+pub async fn fetch_full_content(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> AppResult<Json<FetchFullContentResponse>> {
+    // Verify entry exists and belongs to user
+    let user_id = auth_user.user.id;
+    let (link, entry_feed, policy) = state
+        .db
+        .user(move |conn| {
+            let entry_with_feed = ownership::find_owned_entry(conn, user_id, id)?;
 
             // Check if entry has a link
-            entry_with_feed
+            let link = entry_with_feed
                 .entry
                 .link
-                .ok_or_else(|| AppError::Validation("Entry has no link".to_string()))
+                .ok_or_else(|| AppError::Validation("Entry has no link".to_string()))?;
+            let entry_feed = feed::find_by_id(conn, entry_with_feed.entry.feed_id)?
+                .ok_or(AppError::FeedNotFound)?;
+            let policy = user_settings::get_sanitize_policy(conn, user_id)?;
+            Ok::<_, AppError>((link, entry_feed, policy))
         })
         .await??;
 
-    // Fetch and extract content
-    let extracted = fetch_and_extract(&link, &state.config.user_agent).await?;
+    // Fetch and extract content with the feed's request overrides
+    let settings = state.settings.current();
+    let user_agent = entry_feed
+        .custom_user_agent
+        .as_deref()
+        .unwrap_or(&settings.user_agent);
+    let mut headers = custom_header_map(&entry_feed.custom_headers);
+    // The feed's cookies only go to the feed's own host
+    if feed_cookies::sends_to(&entry_feed.url, &link) {
+        let cookie_secret = state.config.feed_cookie_secret.as_deref();
+        if let Some(cookie) =
+            feed_cookies::load_cookie_header(&state.db, entry_feed.id, cookie_secret).await
+        {
+            headers.insert(COOKIE, cookie);
+        }
+    }
+    let extracted = fetch_and_extract(&link, user_agent, headers).await?;
+
+    // Keep the article so it can be searched
+    let full_content = extracted.content.clone();
+    state
+        .db
+        .user(move |conn| entry::set_full_content(conn, id, &full_content))
+        .await??;
 
     // Sanitize the content (use the entry link as base URL for relative images)
-    let sanitized_content = sanitize_html(
+    let sanitized_content = sanitize_html_with_policy(
         &extracted.content,
         &state.config.image_proxy_secret,
         Some(&link),
+        &policy,
+        &state.blocklist.snapshot(),
     );
 
     Ok(Json(FetchFullContentResponse {
@@ -560,6 +1414,260 @@ pub async fn fetch_full_content(
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CreateManualEntryRequest {
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManualEntryResponse {
+    #[serde(flatten)]
+    pub entry: entry::EntryWithFeed,
+    pub sanitized_content: Option<String>,
+    /// False when the URL was saved before and its content was replaced
+    pub created: bool,
+}
+
+/// Save any URL as an entry of the user's "Saved pages" feed, storing its
+/// extracted article content
+pub async fn create_manual_entry(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<TimestampQuery>,
+    Json(req): Json<CreateManualEntryRequest>,
+) -> AppResult<Response> {
+    let user_id = auth_user.user.id;
+    let url = req.url.trim().to_string();
+    let user_agent = state.settings.current().user_agent.clone();
+    let extracted = fetch_and_extract(&url, &user_agent, Default::default()).await?;
+
+    let (entry_with_feed, created, policy) = state
+        .db
+        .user(move |conn| {
+            let (saved, created) = saved_pages::save_page(
+                conn,
+                user_id,
+                &url,
+                extracted.title.as_deref(),
+                &extracted.content,
+            )?;
+            let entry_with_feed =
+                entry::find_by_id_with_feed(conn, saved.id)?.ok_or(AppError::EntryNotFound)?;
+            let policy = user_settings::get_sanitize_policy(conn, user_id)?;
+            Ok::<_, AppError>((entry_with_feed, created, policy))
+        })
+        .await??;
+
+    let base_url = entry_with_feed.entry.link.as_deref();
+    let blocked = state.blocklist.snapshot();
+    let sanitized_content = entry_with_feed.entry.content.as_ref().map(|c| {
+        sanitize_html_with_policy(
+            c,
+            &state.config.image_proxy_secret,
+            base_url,
+            &policy,
+            &blocked,
+        )
+    });
+
+    let response = ManualEntryResponse {
+        entry: entry_with_feed,
+        sanitized_content,
+        created,
+    };
+    zoned_json(&state, user_id, query.tz, response).await
+}
+
+/// Discussion threads about an entry's article
+#[derive(Debug, Serialize)]
+pub struct DiscussionsResponse {
+    pub discussions: Vec<Discussion>,
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn get_entry_discussions(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> AppResult<Json<DiscussionsResponse>> {
+    let user_id = auth_user.user.id;
+    let (link, cached) = state
+        .db
+        .read(move |conn| {
+            let entry_with_feed = ownership::find_owned_entry(conn, user_id, id)?;
+
+            let link = entry_with_feed
+                .entry
+                .link
+                .ok_or_else(|| AppError::Validation("Entry has no link".to_string()))?;
+            let cached = discussion_cache::find_fresh(conn, &link, discussions::CACHE_TTL_SECS)?;
+            Ok::<_, AppError>((link, cached))
+        })
+        .await??;
+
+    if let Some(cached) = cached {
+        if let Ok(found) = serde_json::from_str(&cached.results) {
+            return Ok(Json(DiscussionsResponse {
+                discussions: found,
+                fetched_at: cached.fetched_at,
+            }));
+        }
+    }
+
+    let user_agent = state.settings.current().user_agent.clone();
+    let found = discussions::find_discussions(&link, &user_agent).await?;
+    let results = serde_json::to_string(&found).map_err(|e| AppError::Internal(e.to_string()))?;
+    state
+        .db
+        .user(move |conn| discussion_cache::store(conn, &link, &results))
+        .await??;
+
+    Ok(Json(DiscussionsResponse {
+        discussions: found,
+        fetched_at: chrono::Utc::now(),
+    }))
+}
+
+/// Every copy of a collapsed story
+#[derive(Debug, Serialize)]
+pub struct StoryCopiesResponse {
+    pub entries: Vec<entry::EntryWithFeed>,
+}
+
+/// Expand a story group from a collapsed list: the entry and its copies in
+/// other feeds, newest first
+pub async fn get_entry_duplicates(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<TimestampQuery>,
+) -> AppResult<Response> {
+    let user_id = auth_user.user.id;
+    let entries = state
+        .db
+        .read(move |conn| entry::list_story_copies(conn, user_id, id))
+        .await??;
+    if !entries.iter().any(|e| e.entry.id == id) {
+        return Err(AppError::EntryNotFound);
+    }
+
+    zoned_json(&state, user_id, query.tz, StoryCopiesResponse { entries }).await
+}
+
+/// Entries from the user's archive on the same topic, best match first
+#[derive(Debug, Serialize)]
+pub struct RelatedEntriesResponse {
+    pub entries: Vec<entry::EntryWithFeed>,
+}
+
+/// Entries similar to an entry, ranked from the search index and cached for
+/// `related::CACHE_TTL_SECS`
+pub async fn get_related_entries(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<TimestampQuery>,
+) -> AppResult<Response> {
+    let user_id = auth_user.user.id;
+    let (entries, ranked) = state
+        .db
+        .read(move |conn| {
+            ownership::assert_entry_owned(conn, user_id, id)?;
+
+            let cached = related_cache::find_fresh(conn, id, related::CACHE_TTL_SECS)?;
+            let (ids, ranked) = match cached {
+                Some(ids) => (ids, None),
+                None => {
+                    let ids = related::find_related(conn, user_id, id, related::RELATED_LIMIT)?;
+                    (ids.clone(), Some(ids))
+                }
+            };
+
+            // Cached IDs may point at entries deleted or moved to the trash since
+            let mut entries = Vec::with_capacity(ids.len());
+            for related_id in ids {
+                if let Some(related) = entry::find_by_id_with_feed(conn, related_id)? {
+                    entries.push(related);
+                }
+            }
+            Ok::<_, AppError>((entries, ranked))
+        })
+        .await??;
+
+    if let Some(ids) = ranked {
+        state
+            .db
+            .user(move |conn| related_cache::store(conn, id, &ids))
+            .await??;
+    }
+
+    zoned_json(
+        &state,
+        user_id,
+        query.tz,
+        RelatedEntriesResponse { entries },
+    )
+    .await
+}
+
+/// A change a feed made to an entry: the title before and after it, and a
+/// word diff of the content
+#[derive(Debug, Serialize)]
+pub struct RevisionResponse {
+    pub id: i64,
+    /// When the change was picked up
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub old_title: Option<String>,
+    pub new_title: Option<String>,
+    pub changes: Vec<DiffChunk>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevisionsResponse {
+    /// Newest change first
+    pub revisions: Vec<RevisionResponse>,
+}
+
+/// GET /api/entries/{id}/revisions - Changes feeds made to an entry
+pub async fn get_entry_revisions(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> AppResult<Json<RevisionsResponse>> {
+    let user_id = auth_user.user.id;
+    let (current, revisions) = state
+        .db
+        .read(move |conn| {
+            let entry_with_feed = ownership::find_owned_entry(conn, user_id, id)?;
+
+            let revisions = entry_revision::list_by_entry(conn, id)?;
+            Ok::<_, AppError>((entry_with_feed.entry, revisions))
+        })
+        .await??;
+
+    // Each revision holds the version before a change; the version after it
+    // is the next revision, or the entry as it is now
+    let afters = revisions
+        .iter()
+        .skip(1)
+        .map(|r| (r.title.as_deref(), r.content.as_deref()))
+        .chain([(current.title.as_deref(), current.content.as_deref())]);
+    let mut changes: Vec<RevisionResponse> = revisions
+        .iter()
+        .zip(afters)
+        .map(|(before, (title, content))| RevisionResponse {
+            id: before.id,
+            created_at: before.created_at,
+            old_title: before.title.clone(),
+            new_title: title.map(str::to_string),
+            changes: diff_content(before.content.as_deref(), content),
+        })
+        .collect();
+    changes.reverse();
+
+    Ok(Json(RevisionsResponse { revisions: changes }))
+}
+
 #[derive(Debug, Serialize)]
 pub struct SaveToServicesResponse {
     pub results: Vec<SaveResult>,
@@ -573,92 +1681,131 @@ pub struct SummaryResponse {
     pub summary_text: Option<String>,
     pub error: Option<String>,
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Style and length the summary is made in, when known
+    #[serde(flatten)]
+    pub options: Option<SummaryOptions>,
+}
+
+impl From<entry_summary::EntrySummary> for SummaryResponse {
+    fn from(summary: entry_summary::EntrySummary) -> Self {
+        SummaryResponse {
+            options: Some(summary.options()),
+            status: summary.status,
+            summary_text: summary.summary_text,
+            error: summary.error_message,
+            created_at: Some(summary.created_at),
+        }
+    }
+}
+
+impl From<SummaryCacheEntry> for SummaryResponse {
+    fn from(cached: SummaryCacheEntry) -> Self {
+        SummaryResponse {
+            status: cached.status,
+            summary_text: cached.summary_text,
+            error: cached.error_message,
+            created_at: Some(cached.created_at),
+            options: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SummarizeQuery {
+    /// Style preset instead of the user's default
+    pub style: Option<SummaryStyle>,
+    /// Target length instead of the user's default
+    pub length: Option<SummaryLength>,
 }
 
-/// POST /api/entries/{id}/summarize - Queue or return cached summary
+/// POST /api/entries/{id}/summarize - Queue or return cached summary. With
+/// `style` or `length`, a finished summary made differently is replaced.
 pub async fn summarize_entry(
     auth_user: AuthUser,
     State(state): State<AppState>,
     Path(id): Path<i64>,
+    Query(query): Query<SummarizeQuery>,
 ) -> AppResult<Json<SummaryResponse>> {
+    if !allows(Outbound::Summary) {
+        return Err(AppError::PrivacyMode);
+    }
     let user_id = auth_user.user.id;
+    let restyle = query.style.is_some() || query.length.is_some();
 
-    // Check cache first for in-flight jobs
-    if let Some(cached) = state.summary_cache.get(user_id, id) {
-        return Ok(Json(SummaryResponse {
-            status: cached.status,
-            summary_text: cached.summary_text,
-            error: cached.error_message,
-            created_at: Some(cached.created_at),
-        }));
+    // Check cache first for in-flight jobs; restyling needs the stored options
+    if let Some(cached) = state.summary_cache.get(user_id, id).filter(|_| !restyle) {
+        return Ok(Json(cached.into()));
     }
 
     // Get entry and verify ownership
-    let link = state
+    let cache = state.summary_cache.clone();
+    let queued = state
         .db
         .user(move |conn| {
-            // Check DB for existing summary
-            if let Some(db_summary) = entry_summary::find_by_user_and_entry(conn, user_id, id)? {
-                return Ok::<_, AppError>(Err(SummaryResponse {
-                    status: db_summary.status,
-                    summary_text: db_summary.summary_text,
-                    error: db_summary.error_message,
-                    created_at: Some(db_summary.created_at),
-                }));
+            // Check DB for existing summary; one being made is never restyled
+            let existing = entry_summary::find_by_user_and_entry(conn, user_id, id)?;
+            if let Some(db_summary) = existing.clone().filter(|s| {
+                !restyle || matches!(s.status, SummaryStatus::Pending | SummaryStatus::Processing)
+            }) {
+                return Ok::<_, AppError>(Err(SummaryResponse::from(db_summary)));
             }
 
-            let entry_with_feed =
-                entry::find_by_id_with_feed(conn, id)?.ok_or(AppError::EntryNotFound)?;
-
-            // Verify entry belongs to user
-            let cat = category::find_by_id(conn, entry_with_feed.category_id)?
-                .ok_or(AppError::CategoryNotFound)?;
-            if cat.user_id != user_id {
-                return Err(AppError::EntryNotFound);
-            }
+            let entry_with_feed = ownership::find_owned_entry(conn, user_id, id)?;
 
             // Check if entry has a link
             let link = entry_with_feed.entry.link.clone().ok_or_else(|| {
                 AppError::Validation("Entry has no link to summarize".to_string())
             })?;
 
-            // Verify Kagi is configured
+            // Verify Kagi or an OpenAI-compatible backend is configured
             let config = user_settings::get_save_services_config(conn, user_id)?;
-            let kagi = config
-                .kagi
-                .ok_or_else(|| AppError::Validation("Kagi is not configured".to_string()))?;
-
-            if !kagi.is_configured() {
+            if !config.has_summarizer() {
                 return Err(AppError::Validation("Kagi is not configured".to_string()));
             }
 
-            // Create pending record in DB
-            entry_summary::upsert_pending(conn, user_id, id)?;
+            let defaults = config.kagi.map(|k| k.options()).unwrap_or_default();
+            let options = SummaryOptions {
+                style: query.style.unwrap_or(defaults.style),
+                length: query.length.unwrap_or(defaults.length),
+            };
+            if let Some(db_summary) = existing.filter(|s| s.options() == options) {
+                return Ok(Err(db_summary.into()));
+            }
+
+            // Someone already summarized the same article
+            let key = shared_summary::link_key(&link, options.style.summary_type());
+            if let Some(text) = shared_summary::find_text(conn, &key)? {
+                let text = options.shape(&text);
+                let summary = entry_summary::upsert_completed(conn, user_id, id, options, &text)?;
+                cache.set_completed(user_id, id, text);
+                return Ok(Err(summary.into()));
+            }
 
-            Ok(Ok(link))
+            // Create pending record in DB and queue the work with it
+            entry_summary::upsert_pending(conn, user_id, id, options)?;
+            jobs::enqueue(
+                conn,
+                &Task::Summary(SummaryJob {
+                    user_id,
+                    entry_id: id,
+                    entry_link: link,
+                    options,
+                }),
+            )?;
+            // Before the worker can see the job, so it cannot finish first
+            cache.set_pending(user_id, id);
+
+            Ok(Ok(options))
         })
         .await??;
 
     // Check if we got a cached summary from DB
-    let link = match link {
-        Ok(link) => link,
+    let options = match queued {
+        Ok(options) => options,
         Err(response) => return Ok(Json(response)),
     };
 
-    // Set pending status in cache
-    state.summary_cache.set_pending(user_id, id);
-
-    let job = SummaryJob {
-        user_id,
-        entry_id: id,
-        entry_link: link,
-    };
-
-    state
-        .summary_tx
-        .send(job)
-        .await
-        .map_err(|e| AppError::Internal(format!("Failed to queue summary job: {}", e)))?;
+    state.jobs.wake();
 
     // Return pending status
     Ok(Json(SummaryResponse {
@@ -666,6 +1813,7 @@ pub async fn summarize_entry(
         summary_text: None,
         error: None,
         created_at: Some(chrono::Utc::now()),
+        options: Some(options),
     }))
 }
 
@@ -679,38 +1827,18 @@ pub async fn get_entry_summary(
 
     // Check cache first for in-flight status
     if let Some(cached) = state.summary_cache.get(user_id, id) {
-        return Ok(Json(SummaryResponse {
-            status: cached.status,
-            summary_text: cached.summary_text,
-            error: cached.error_message,
-            created_at: Some(cached.created_at),
-        }));
+        return Ok(Json(cached.into()));
     }
 
     // Verify entry ownership and get from DB
     let result = state
         .db
         .user(move |conn| {
-            let entry_with_feed =
-                entry::find_by_id_with_feed(conn, id)?.ok_or(AppError::EntryNotFound)?;
-
-            let cat = category::find_by_id(conn, entry_with_feed.category_id)?
-                .ok_or(AppError::CategoryNotFound)?;
-            if cat.user_id != user_id {
-                return Err(AppError::EntryNotFound);
-            }
+            ownership::assert_entry_owned(conn, user_id, id)?;
 
             // Get from DB
-            if let Some(db_summary) = entry_summary::find_by_user_and_entry(conn, user_id, id)? {
-                Ok::<_, AppError>(Some(SummaryResponse {
-                    status: db_summary.status,
-                    summary_text: db_summary.summary_text,
-                    error: db_summary.error_message,
-                    created_at: Some(db_summary.created_at),
-                }))
-            } else {
-                Ok(None)
-            }
+            let summary = entry_summary::find_by_user_and_entry(conn, user_id, id)?;
+            Ok::<_, AppError>(summary.map(SummaryResponse::from))
         })
         .await??;
 
@@ -720,6 +1848,75 @@ pub async fn get_entry_summary(
     }
 }
 
+fn partial_summary_event(text: &str) -> Event {
+    Event::default()
+        .event("partial")
+        .json_data(serde_json::json!({ "text": text }))
+        .unwrap_or_default()
+}
+
+/// An event named after the summary's status carrying the summary
+fn summary_event(response: SummaryResponse) -> Event {
+    Event::default()
+        .event(response.status.as_str())
+        .json_data(&response)
+        .unwrap_or_default()
+}
+
+/// GET /api/entries/{id}/summary/stream - Follow a summary while it is
+/// written: `partial` events with the text so far, then one `completed` or
+/// `failed` event. A summary already finished is sent as that one event.
+pub async fn stream_entry_summary(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> AppResult<Sse<KeepAliveStream<BoxStream<'static, Result<Event, Infallible>>>>> {
+    let user_id = auth_user.user.id;
+    let stored = state
+        .db
+        .read(move |conn| {
+            ownership::assert_entry_owned(conn, user_id, id)?;
+            entry_summary::find_by_user_and_entry(conn, user_id, id)
+        })
+        .await??;
+
+    let cache = state.summary_cache.clone();
+    let events = match cache.subscribe(user_id, id) {
+        Some(rx) => {
+            // Text written before the client connected comes first
+            let written = rx.borrow().clone();
+            let initial = (!written.is_empty()).then(|| partial_summary_event(&written));
+            let updates = stream::unfold(Some(rx), move |rx| {
+                let cache = cache.clone();
+                async move {
+                    let mut rx = rx?;
+                    if rx.changed().await.is_ok() {
+                        let text = rx.borrow_and_update().clone();
+                        return Some((partial_summary_event(&text), Some(rx)));
+                    }
+                    // Closed once the summary completes or fails
+                    let finished = cache.get(user_id, id)?;
+                    Some((summary_event(finished.into()), None))
+                }
+            });
+            stream::iter(initial)
+                .chain(updates)
+                .map(Ok::<_, Infallible>)
+                .boxed()
+        }
+        None => {
+            let response = match (cache.get(user_id, id), stored) {
+                (Some(cached), _) => cached.into(),
+                (None, Some(summary)) => summary.into(),
+                (None, None) => return Err(AppError::NotFound("No summary found".to_string())),
+            };
+            stream::once(async move { Ok(summary_event(response)) }).boxed()
+        }
+    };
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
 /// DELETE /api/entries/{id}/summary - Delete summary from cache and DB
 pub async fn delete_entry_summary(
     auth_user: AuthUser,
@@ -732,14 +1929,7 @@ pub async fn delete_entry_summary(
     state
         .db
         .user(move |conn| {
-            let entry_with_feed =
-                entry::find_by_id_with_feed(conn, id)?.ok_or(AppError::EntryNotFound)?;
-
-            let cat = category::find_by_id(conn, entry_with_feed.category_id)?
-                .ok_or(AppError::CategoryNotFound)?;
-            if cat.user_id != user_id {
-                return Err(AppError::EntryNotFound);
-            }
+            ownership::assert_entry_owned(conn, user_id, id)?;
 
             // Delete from DB
             entry_summary::delete(conn, user_id, id)?;
@@ -753,6 +1943,192 @@ pub async fn delete_entry_summary(
     Ok(Json(serde_json::json!({ "success": true })))
 }
 
+/// Longest question accepted, in characters
+const MAX_QUESTION_CHARS: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct AskRequest {
+    pub question: String,
+}
+
+/// POST /api/entries/{id}/ask - Answer a question about the entry's text
+/// with the user's OpenAI-compatible backend. Earlier questions about the
+/// entry are sent along, so follow-ups can refer to them.
+pub async fn ask_entry(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(req): Json<AskRequest>,
+) -> AppResult<Json<EntryQuestion>> {
+    if !allows(Outbound::Summary) {
+        return Err(AppError::PrivacyMode);
+    }
+    let question = req.question.trim().to_string();
+    if question.is_empty() {
+        return Err(AppError::Validation("Question is required".to_string()));
+    }
+    if question.chars().count() > MAX_QUESTION_CHARS {
+        return Err(AppError::Validation(format!(
+            "Question must be at most {} characters",
+            MAX_QUESTION_CHARS
+        )));
+    }
+
+    let user_id = auth_user.user.id;
+    let (config, html, history) = state
+        .db
+        .user(move |conn| {
+            ownership::assert_entry_owned(conn, user_id, id)?;
+            let config = user_settings::get_save_services_config(conn, user_id)?
+                .openai()
+                .cloned()
+                .ok_or_else(|| {
+                    AppError::Validation("No OpenAI-compatible backend is configured".to_string())
+                })?;
+            let html = entry::find_article_html(conn, id)?.unwrap_or_default();
+            let history = entry_question::list(conn, user_id, id)?;
+            Ok::<_, AppError>((config, html, history))
+        })
+        .await??;
+
+    let article = html_to_text(&html);
+    if article.is_empty() {
+        return Err(AppError::Validation(
+            "Entry has no text to ask about".to_string(),
+        ));
+    }
+    let history: Vec<openai::Turn> = history
+        .into_iter()
+        .map(|q| openai::Turn {
+            question: q.question,
+            answer: q.answer,
+        })
+        .collect();
+
+    let result = openai::ask(&config, &article, &history, &question).await?;
+    let answer = match result.output_text {
+        Some(answer) if result.success => answer,
+        _ => {
+            return Err(AppError::FetchError(
+                result.error.unwrap_or_else(|| "Unknown error".to_string()),
+            ))
+        }
+    };
+
+    let added = state
+        .db
+        .user(move |conn| entry_question::add(conn, user_id, id, &question, &answer))
+        .await??;
+
+    Ok(Json(added))
+}
+
+/// GET /api/entries/{id}/ask - The user's questions about the entry so far
+pub async fn list_entry_questions(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> AppResult<Json<Vec<EntryQuestion>>> {
+    let user_id = auth_user.user.id;
+    let questions = state
+        .db
+        .read(move |conn| {
+            ownership::assert_entry_owned(conn, user_id, id)?;
+            entry_question::list(conn, user_id, id)
+        })
+        .await??;
+
+    Ok(Json(questions))
+}
+
+/// DELETE /api/entries/{id}/ask - Start the conversation about the entry over
+pub async fn clear_entry_questions(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> AppResult<Json<serde_json::Value>> {
+    let user_id = auth_user.user.id;
+    state
+        .db
+        .user(move |conn| {
+            ownership::assert_entry_owned(conn, user_id, id)?;
+            entry_question::clear(conn, user_id, id)
+        })
+        .await??;
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+#[derive(Debug, Serialize)]
+pub struct EntryViewResponse {
+    pub id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EntryViewTimeRequest {
+    /// Seconds spent on the entry since it was opened
+    pub seconds: i64,
+}
+
+/// POST /api/entries/{id}/views - Record that the entry was opened, for
+/// reading statistics
+pub async fn record_entry_view(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> AppResult<(StatusCode, Json<EntryViewResponse>)> {
+    let user_id = auth_user.user.id;
+    let view_id = state
+        .db
+        .user(move |conn| {
+            ownership::assert_entry_owned(conn, user_id, id)?;
+            entry_view::record(conn, user_id, id)
+        })
+        .await??;
+
+    Ok((StatusCode::CREATED, Json(EntryViewResponse { id: view_id })))
+}
+
+/// PUT /api/entries/{id}/views/{view_id} - Report the time spent on the
+/// entry so far
+pub async fn update_entry_view(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path((id, view_id)): Path<(i64, i64)>,
+    Json(req): Json<EntryViewTimeRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    if req.seconds < 0 {
+        return Err(AppError::Validation(
+            "Seconds must not be negative".to_string(),
+        ));
+    }
+
+    let user_id = auth_user.user.id;
+    state
+        .db
+        .user(move |conn| entry_view::set_seconds(conn, user_id, id, view_id, req.seconds))
+        .await??;
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+/// GET /api/entries/{id}/saves - Where the entry was saved, per service
+pub async fn list_entry_saves(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> AppResult<Json<Vec<entry_save::EntrySave>>> {
+    let user_id = auth_user.user.id;
+    let saves = state
+        .db
+        .read(move |conn| {
+            ownership::assert_entry_owned(conn, user_id, id)?;
+            entry_save::list_by_entry(conn, user_id, id)
+        })
+        .await??;
+    Ok(Json(saves))
+}
+
 pub async fn save_to_services(
     auth_user: AuthUser,
     State(state): State<AppState>,
@@ -763,22 +2139,14 @@ pub async fn save_to_services(
     let (entry_data, save_config) = state
         .db
         .user(move |conn| {
-            let entry_with_feed =
-                entry::find_by_id_with_feed(conn, id)?.ok_or(AppError::EntryNotFound)?;
-
-            // Verify entry belongs to user
-            let cat = category::find_by_id(conn, entry_with_feed.category_id)?
-                .ok_or(AppError::CategoryNotFound)?;
-            if cat.user_id != user_id {
-                return Err(AppError::EntryNotFound);
-            }
+            let entry_with_feed = ownership::find_owned_entry(conn, user_id, id)?;
 
             // Check if entry has a link
-            let link = entry_with_feed
-                .entry
-                .link
-                .clone()
-                .ok_or_else(|| AppError::Validation("Entry has no link to save".to_string()))?;
+            if entry_with_feed.entry.link.is_none() {
+                return Err(AppError::Validation(
+                    "Entry has no link to save".to_string(),
+                ));
+            }
 
             // Get save services config
             let config = user_settings::get_save_services_config(conn, user_id)?;
@@ -789,30 +2157,27 @@ pub async fn save_to_services(
                 ));
             }
 
-            let bookmark = BookmarkData {
-                url: link,
-                title: entry_with_feed.entry.title.clone(),
-                description: entry_with_feed.entry.summary.clone(),
-                tags: vec![],
-            };
-
+            let bookmark = save::bookmark_for_entry(conn, user_id, &entry_with_feed, &config)?;
             Ok::<_, AppError>((bookmark, config))
         })
         .await??;
 
-    // Save to all configured services in parallel
     let mut results = Vec::new();
-
-    // Linkding
-    if let Some(linkding_config) = &save_config.linkding {
-        if linkding_config.is_configured() {
-            let result = linkding::save_to_linkding(linkding_config, &entry_data).await?;
-            results.push(result);
-        }
+    for service in save_config.configured_services() {
+        results.push(save::save_to(&save_config, service, &entry_data).await?);
     }
 
-    // Future services can be added here:
-    // if let Some(pocket_config) = &save_config.pocket { ... }
+    // Shown on the entry page next to saves of starred entries
+    let recorded = results.clone();
+    state
+        .db
+        .user(move |conn| {
+            for result in &recorded {
+                entry_save::record(conn, user_id, id, result)?;
+            }
+            Ok::<_, AppError>(())
+        })
+        .await??;
 
     let all_success = results.iter().all(|r| r.success);
 
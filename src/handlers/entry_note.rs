@@ -0,0 +1,106 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::ownership;
+use crate::error::{AppError, AppResult};
+use crate::middleware::AuthUser;
+use crate::models::entry_note::{self, EntryNote};
+use crate::services::markdown;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct SetNoteRequest {
+    pub content: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NoteResponse {
+    /// Markdown as written
+    pub content: String,
+    /// `content` rendered and sanitized
+    pub html: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<EntryNote> for NoteResponse {
+    fn from(note: EntryNote) -> Self {
+        NoteResponse {
+            html: markdown::render(&note.content),
+            content: note.content,
+            created_at: note.created_at,
+            updated_at: note.updated_at,
+        }
+    }
+}
+
+/// GET /api/entries/{id}/note - The user's note on an entry
+pub async fn get_note(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<Json<NoteResponse>> {
+    let user_id = auth_user.user.id;
+
+    let note = state
+        .db
+        .read(move |conn| {
+            ownership::assert_entry_owned(conn, user_id, id)?;
+            entry_note::find(conn, user_id, id)
+        })
+        .await??
+        .ok_or_else(|| AppError::NotFound("Note not found".to_string()))?;
+
+    Ok(Json(note.into()))
+}
+
+/// PUT /api/entries/{id}/note - Write the note on an entry, in Markdown
+pub async fn set_note(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+    Json(req): Json<SetNoteRequest>,
+) -> AppResult<Json<NoteResponse>> {
+    let content = req.content.trim().to_string();
+    if content.is_empty() {
+        return Err(AppError::Validation(
+            "Note must not be empty; delete it instead".to_string(),
+        ));
+    }
+    entry_note::validate(&content)?;
+
+    let user_id = auth_user.user.id;
+    let note = state
+        .db
+        .user(move |conn| {
+            ownership::assert_entry_owned(conn, user_id, id)?;
+            entry_note::set_note(conn, user_id, id, &content)
+        })
+        .await??;
+
+    Ok(Json(note.into()))
+}
+
+/// DELETE /api/entries/{id}/note
+pub async fn delete_note(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<StatusCode> {
+    let user_id = auth_user.user.id;
+
+    let deleted = state
+        .db
+        .user(move |conn| entry_note::delete_note(conn, user_id, id))
+        .await??;
+    if !deleted {
+        return Err(AppError::NotFound("Note not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use futures_util::{stream, StreamExt};
+use serde::Deserialize;
+
+use crate::error::{AppError, AppResult};
+use crate::handlers::proxy::fetch_image;
+use crate::middleware::AuthUser;
+use crate::models::entry::{self, EntryWithFeed};
+use crate::models::user_settings;
+use crate::services::epub::{self, Chapter, Image};
+use crate::services::sanitize::{sanitize_html_with_policy, VideoEmbeds};
+use crate::AppState;
+
+/// Images fetched at the same time while building a book
+const IMAGE_FETCH_CONCURRENCY: usize = 4;
+
+const MAX_STARRED_LIMIT: i64 = 200;
+
+#[derive(Debug, Deserialize)]
+pub struct StarredEpubQuery {
+    #[serde(default = "default_starred_limit")]
+    pub limit: i64,
+}
+
+fn default_starred_limit() -> i64 {
+    50
+}
+
+/// Chapters of `entries` from their fullest text, sanitized with the
+/// user's policy but keeping images at their origin so they can be packed
+async fn chapters(
+    state: &AppState,
+    user_id: i64,
+    entries: Vec<EntryWithFeed>,
+) -> AppResult<Vec<Chapter>> {
+    let (policy, articles) = state
+        .db
+        .read(move |conn| {
+            let policy = user_settings::get_sanitize_policy(conn, user_id)?;
+            let mut articles = Vec::with_capacity(entries.len());
+            for e in entries {
+                let html = entry::find_article_html(conn, e.entry.id)?;
+                articles.push((e, html));
+            }
+            Ok::<_, AppError>((policy, articles))
+        })
+        .await??;
+
+    let mut policy = policy;
+    policy.proxy_images = false;
+    policy.iframe_hosts.clear();
+    policy.video_embeds = VideoEmbeds::Remove;
+    let blocked = state.blocklist.snapshot();
+
+    Ok(articles
+        .into_iter()
+        .map(|(e, html)| {
+            let link = e.entry.link;
+            let html = html
+                .map(|html| {
+                    sanitize_html_with_policy(
+                        &html,
+                        &state.config.image_proxy_secret,
+                        link.as_deref(),
+                        &policy,
+                        &blocked,
+                    )
+                })
+                .unwrap_or_default();
+            Chapter {
+                title: e.entry.title.unwrap_or_else(|| "(Untitled)".to_string()),
+                feed_title: e.feed_title,
+                author: e.entry.author,
+                link,
+                published_at: e.entry.published_at,
+                html,
+            }
+        })
+        .collect())
+}
+
+/// Fetch the images in `chapters` through the image proxy's checks. Images
+/// that fail, or do not fit the book's limits, are left out.
+async fn fetch_images(state: &AppState, chapters: &[Chapter]) -> HashMap<String, Image> {
+    let mut urls = epub::image_urls(chapters);
+    urls.truncate(epub::MAX_IMAGES);
+
+    let mut fetched = stream::iter(urls)
+        .map(|url| async move {
+            let result = fetch_image(state, &url).await;
+            (url, result)
+        })
+        .buffered(IMAGE_FETCH_CONCURRENCY);
+
+    let mut images = HashMap::new();
+    let mut total = 0;
+    while let Some((url, result)) = fetched.next().await {
+        let bytes = match result {
+            Ok((_, bytes)) => bytes,
+            Err(e) => {
+                tracing::debug!("Leaving image {} out of EPUB: {}", url, e);
+                continue;
+            }
+        };
+        if total + bytes.len() > epub::MAX_IMAGE_BYTES {
+            continue;
+        }
+        if let Some(image) = Image::sniff(bytes.to_vec()) {
+            total += image.bytes.len();
+            images.insert(url, image);
+        }
+    }
+    images
+}
+
+async fn book_response(
+    state: &AppState,
+    user_id: i64,
+    title: String,
+    filename: String,
+    entries: Vec<EntryWithFeed>,
+) -> AppResult<Response> {
+    let chapters = chapters(state, user_id, entries).await?;
+    let images = fetch_images(state, &chapters).await;
+    let book = tokio::task::spawn_blocking(move || epub::build(&title, &chapters, &images))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/epub+zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        book,
+    )
+        .into_response())
+}
+
+/// POST /api/entries/{id}/epub - Download an entry as an EPUB
+pub async fn entry_epub(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<Response> {
+    let user_id = auth_user.user.id;
+
+    let entry = state
+        .db
+        .read(move |conn| entry::find_by_id_with_feed_and_user(conn, id, user_id))
+        .await??
+        .ok_or(AppError::EntryNotFound)?;
+
+    let title = entry
+        .entry
+        .title
+        .clone()
+        .unwrap_or_else(|| "(Untitled)".to_string());
+    let filename = format!("rdrs-entry-{}.epub", id);
+    book_response(&state, user_id, title, filename, vec![entry]).await
+}
+
+/// POST /api/entries/starred/epub - Download starred entries as one EPUB,
+/// most recently starred first
+pub async fn starred_epub(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Query(query): Query<StarredEpubQuery>,
+) -> AppResult<Response> {
+    let limit = query.limit.clamp(1, MAX_STARRED_LIMIT);
+    let user_id = auth_user.user.id;
+
+    let entries = state
+        .db
+        .read(move |conn| {
+            let filter = entry::EntryFilter {
+                starred_only: true,
+                ..Default::default()
+            };
+            entry::list_by_user(
+                conn,
+                user_id,
+                &filter,
+                entry::EntrySortOrder::StarredAt,
+                limit,
+                0,
+            )
+        })
+        .await??;
+    if entries.is_empty() {
+        return Err(AppError::Validation("No starred entries".to_string()));
+    }
+
+    let title = format!("Starred entries, {}", chrono::Utc::now().format("%Y-%m-%d"));
+    book_response(
+        &state,
+        user_id,
+        title,
+        "rdrs-starred.epub".to_string(),
+        entries,
+    )
+    .await
+}
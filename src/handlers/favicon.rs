@@ -1,7 +1,12 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
 use axum::{
+    extract::Path,
     http::{header, StatusCode},
     response::{IntoResponse, Response},
 };
+use sha2::{Digest, Sha256};
 
 // Embed generated favicon files at compile time
 const FAVICON_ICO: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/favicon.ico"));
@@ -10,47 +15,101 @@ const FAVICON_16: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/favicon-16x1
 const FAVICON_32: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/favicon-32x32.png"));
 const APPLE_TOUCH_ICON: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/apple-touch-icon.png"));
 
-pub async fn favicon_ico() -> Response {
+/// Cache policy for the unversioned asset paths
+const CACHE_SHORT: &str = "public, max-age=86400";
+
+/// Cache policy for fingerprinted asset paths, whose content never changes
+const CACHE_IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// Embedded assets servable under `/static/{fingerprint}/{name}`
+const ASSETS: &[(&str, &str, &[u8])] = &[
+    ("favicon.ico", "image/x-icon", FAVICON_ICO),
+    ("favicon.svg", "image/svg+xml", FAVICON_SVG),
+    ("favicon-16x16.png", "image/png", FAVICON_16),
+    ("favicon-32x32.png", "image/png", FAVICON_32),
+    ("apple-touch-icon.png", "image/png", APPLE_TOUCH_ICON),
+];
+
+/// Content hash of each embedded asset, keyed by file name
+static FINGERPRINTS: LazyLock<HashMap<&'static str, String>> = LazyLock::new(|| {
+    ASSETS
+        .iter()
+        .map(|(name, _, data)| {
+            let digest = Sha256::digest(data);
+            let hex: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+            (*name, hex)
+        })
+        .collect()
+});
+
+/// Fingerprinted URL for an embedded asset, for use in templates.
+/// Unknown names fall back to the unversioned path.
+pub fn asset_url(name: &str) -> String {
+    match FINGERPRINTS.get(name) {
+        Some(fingerprint) => format!("/static/{}/{}", fingerprint, name),
+        None => format!("/{}", name),
+    }
+}
+
+fn asset_response(
+    content_type: &'static str,
+    data: &'static [u8],
+    cache: &'static str,
+) -> Response {
     (
         StatusCode::OK,
-        [(header::CONTENT_TYPE, "image/x-icon")],
-        FAVICON_ICO,
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, cache),
+        ],
+        data,
     )
         .into_response()
 }
 
+/// Serve a fingerprinted asset. A stale fingerprint is a 404 rather than the
+/// current content, so an immutable cache entry can never hold the wrong bytes.
+pub async fn static_asset(Path((fingerprint, name)): Path<(String, String)>) -> Response {
+    let asset = ASSETS.iter().find(|(n, _, _)| *n == name);
+    match (asset, FINGERPRINTS.get(name.as_str())) {
+        (Some((_, content_type, data)), Some(expected)) if *expected == fingerprint => {
+            asset_response(content_type, data, CACHE_IMMUTABLE)
+        }
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+pub async fn favicon_ico() -> Response {
+    asset_response("image/x-icon", FAVICON_ICO, CACHE_SHORT)
+}
+
 pub async fn favicon_svg() -> Response {
-    (
-        StatusCode::OK,
-        [(header::CONTENT_TYPE, "image/svg+xml")],
-        FAVICON_SVG,
-    )
-        .into_response()
+    asset_response("image/svg+xml", FAVICON_SVG, CACHE_SHORT)
 }
 
 pub async fn favicon_16() -> Response {
-    (
-        StatusCode::OK,
-        [(header::CONTENT_TYPE, "image/png")],
-        FAVICON_16,
-    )
-        .into_response()
+    asset_response("image/png", FAVICON_16, CACHE_SHORT)
 }
 
 pub async fn favicon_32() -> Response {
-    (
-        StatusCode::OK,
-        [(header::CONTENT_TYPE, "image/png")],
-        FAVICON_32,
-    )
-        .into_response()
+    asset_response("image/png", FAVICON_32, CACHE_SHORT)
 }
 
 pub async fn apple_touch_icon() -> Response {
-    (
-        StatusCode::OK,
-        [(header::CONTENT_TYPE, "image/png")],
-        APPLE_TOUCH_ICON,
-    )
-        .into_response()
+    asset_response("image/png", APPLE_TOUCH_ICON, CACHE_SHORT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_url_is_fingerprinted() {
+        let url = asset_url("favicon.svg");
+        let fingerprint = FINGERPRINTS.get("favicon.svg").unwrap();
+        assert_eq!(url, format!("/static/{}/favicon.svg", fingerprint));
+        assert_eq!(fingerprint.len(), 16);
+
+        assert_eq!(asset_url("missing.png"), "/missing.png");
+    }
 }
@@ -1,28 +1,46 @@
 use axum::{
-    extract::{Path, State},
+    body::Body,
+    extract::{FromRequest, Multipart, Path, Query, Request, State},
     http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
-use crate::error::{AppError, AppResult};
+use crate::error::{AppError, AppResult, ExistingFeed};
+use crate::middleware::precondition::{version_etag, IfMatch};
 use crate::middleware::AuthUser;
-use crate::models::{category, feed, image};
-use crate::services::{feed_discovery, opml};
+use crate::models::feed_rewrite::{self, FeedRewrite, RewriteField};
+use crate::models::{
+    auto_read, category, entry, feed, feed_cookie, feed_event, feed_script, feed_stats, image,
+};
+use crate::services::http::{allows, validate_custom_headers, Outbound};
+use crate::services::jobs::{self, import, ImportJob, Task};
+use crate::services::rewrite::{self, Rewriter};
+use crate::services::scripting::EntryHook;
+use crate::services::{
+    backup, bookmark_import, category_suggest, feed_cookies, feed_discovery, icon_refresh, opml,
+    saved_pages, undo,
+};
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateFeedRequest {
     pub url: String,
-    pub category_id: i64,
+    /// Omitted or `null` puts the feed in Uncategorized
+    pub category_id: Option<i64>,
     pub custom_user_agent: Option<String>,
     pub http2_disabled: Option<bool>,
+    pub date_strategy: Option<feed::DateStrategy>,
+    pub custom_headers: Option<BTreeMap<String, String>>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateFeedRequest {
-    pub category_id: i64,
+    /// Omitted or `null` moves the feed to Uncategorized
+    pub category_id: Option<i64>,
     pub url: String,
     pub title: Option<String>,
     pub description: Option<String>,
@@ -30,6 +48,16 @@ pub struct UpdateFeedRequest {
     pub custom_user_agent: Option<String>,
     #[serde(default)]
     pub http2_disabled: bool,
+    /// Left unchanged when omitted
+    pub date_strategy: Option<feed::DateStrategy>,
+    /// Extra request headers; left unchanged when omitted
+    pub custom_headers: Option<BTreeMap<String, String>>,
+    /// Days after which unread entries are marked read, 0 to turn off; left
+    /// unchanged when omitted
+    pub auto_read_days: Option<i64>,
+    /// Percent of an entry's text that must change on refresh for it to be
+    /// marked unread again, 0 to turn off; left unchanged when omitted
+    pub update_threshold: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -49,21 +77,40 @@ pub struct FeedResponse {
     pub fetch_error: Option<String>,
     pub custom_user_agent: Option<String>,
     pub http2_disabled: bool,
+    pub date_strategy: feed::DateStrategy,
+    pub custom_headers: BTreeMap<String, String>,
     pub created_at: String,
     pub updated_at: String,
     pub has_icon: bool,
+    pub entry_count: i64,
+    pub unread_count: i64,
+    pub last_entry_at: Option<String>,
+    pub engagement_score: f64,
+    pub auto_read_days: Option<i64>,
+    pub update_threshold: Option<i64>,
+    pub synthetic: bool,
+    pub failure_count: i64,
+    pub next_fetch_at: Option<String>,
+    pub quarantined_at: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct FeedMetadataResponse {
-    pub feed_url: String,
+    /// Unset, like the other feed fields, when no feed was found in time
+    pub feed_url: Option<String>,
     pub title: Option<String>,
     pub description: Option<String>,
     pub site_url: Option<String>,
+    pub icon_url: Option<String>,
+    /// Existing category that best fits the feed's declared categories and
+    /// title
+    pub suggested_category_id: Option<i64>,
+    /// Steps that failed or ran out of time
+    pub errors: Vec<feed_discovery::StepError>,
 }
 
 impl FeedResponse {
-    fn from_feed(f: feed::Feed, has_icon: bool) -> Self {
+    pub(crate) fn from_feed(f: feed::Feed, has_icon: bool) -> Self {
         FeedResponse {
             id: f.id,
             category_id: f.category_id,
@@ -75,26 +122,79 @@ impl FeedResponse {
             fetch_error: f.fetch_error,
             custom_user_agent: f.custom_user_agent,
             http2_disabled: f.http2_disabled,
+            date_strategy: f.date_strategy,
+            custom_headers: f.custom_headers,
             created_at: f.created_at.to_rfc3339(),
             updated_at: f.updated_at.to_rfc3339(),
             has_icon,
+            entry_count: f.entry_count,
+            unread_count: f.unread_count,
+            last_entry_at: f.last_entry_at.map(|dt| dt.to_rfc3339()),
+            engagement_score: f.engagement_score,
+            auto_read_days: f.auto_read_days,
+            update_threshold: f.update_threshold,
+            synthetic: f.synthetic,
+            failure_count: f.failure_count,
+            next_fetch_at: f.next_fetch_at.map(|dt| dt.to_rfc3339()),
+            quarantined_at: f.quarantined_at.map(|dt| dt.to_rfc3339()),
         }
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ListFeedsQuery {
+    #[serde(default)]
+    pub order: feed::FeedOrder,
+}
+
 pub async fn list_feeds(
     State(state): State<AppState>,
     auth_user: AuthUser,
+    Query(query): Query<ListFeedsQuery>,
 ) -> AppResult<Json<Vec<FeedResponse>>> {
     let user_id = auth_user.user.id;
     let response = state
         .db
         .user(move |conn| {
+            let feeds = feed::list_by_user_ordered(conn, user_id, query.order)?;
+            let icons = image::list_entity_ids(conn, image::ENTITY_FEED)?;
+            let response: Vec<FeedResponse> = feeds
+                .into_iter()
+                .map(|f| {
+                    let has_icon = icons.contains(&f.id);
+                    FeedResponse::from_feed(f, has_icon)
+                })
+                .collect();
+            Ok::<_, AppError>(response)
+        })
+        .await??;
+
+    Ok(Json(response))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReorderFeedsRequest {
+    /// Feed IDs in the order they should be listed; those left out follow
+    /// in their current order
+    pub ids: Vec<i64>,
+}
+
+pub async fn reorder_feeds(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<ReorderFeedsRequest>,
+) -> AppResult<Json<Vec<FeedResponse>>> {
+    let user_id = auth_user.user.id;
+    let response = state
+        .db
+        .user(move |conn| {
+            feed::reorder(conn, user_id, &req.ids)?;
             let feeds = feed::list_by_user(conn, user_id)?;
+            let icons = image::list_entity_ids(conn, image::ENTITY_FEED)?;
             let response: Vec<FeedResponse> = feeds
                 .into_iter()
                 .map(|f| {
-                    let has_icon = image::exists(conn, image::ENTITY_FEED, f.id).unwrap_or(false);
+                    let has_icon = icons.contains(&f.id);
                     FeedResponse::from_feed(f, has_icon)
                 })
                 .collect();
@@ -115,28 +215,46 @@ pub async fn create_feed(
     if url.is_empty() {
         return Err(AppError::Validation("URL cannot be empty".to_string()));
     }
+    if let Some(headers) = &req.custom_headers {
+        validate_custom_headers(headers).map_err(AppError::Validation)?;
+    }
 
-    // Verify category ownership
+    // Verify category ownership and that the URL is not already subscribed
     let user_id = auth_user.user.id;
     let category_id = req.category_id;
+    let entered_url = url.clone();
     state
         .db
         .user(move |conn| {
-            category::find_by_id_and_user(conn, category_id, user_id)?
-                .ok_or(AppError::CategoryNotFound)
+            if let Some(category_id) = category_id {
+                category::find_by_id_and_user(conn, category_id, user_id)?
+                    .ok_or(AppError::CategoryNotFound)?;
+            }
+            reject_duplicate(conn, user_id, &[&entered_url])
         })
         .await??;
 
     // Discover feed metadata
-    let discovered = feed_discovery::discover_feed(&url, &state.config.user_agent).await?;
+    let user_agent = state.settings.current().user_agent.clone();
+    let discovered = feed_discovery::discover_feed(&url, &user_agent).await?;
+
+    // A site URL can lead to a feed that is already subscribed
+    let discovered_url = discovered.feed_url.clone();
+    state
+        .db
+        .user(move |conn| reject_duplicate(conn, user_id, &[&discovered_url]))
+        .await??;
 
     // Create the feed
     let custom_user_agent = req.custom_user_agent;
     let http2_disabled = req.http2_disabled;
+    let date_strategy = req.date_strategy;
+    let custom_headers = req.custom_headers.unwrap_or_default();
     let new_feed = state
         .db
         .user(move |conn| {
-            feed::create_feed(
+            let category_id = category::resolve(conn, user_id, category_id)?;
+            let created = feed::create_feed(
                 conn,
                 category_id,
                 &discovered.feed_url,
@@ -145,7 +263,23 @@ pub async fn create_feed(
                 discovered.site_url.as_deref(),
                 custom_user_agent.as_deref(),
                 http2_disabled,
-            )
+            )?;
+
+            let mut changed = false;
+            if let Some(strategy) = date_strategy.filter(|s| *s != created.date_strategy) {
+                feed::set_date_strategy(conn, created.id, strategy)?;
+                changed = true;
+            }
+            if !custom_headers.is_empty() {
+                feed::set_custom_headers(conn, created.id, &custom_headers)?;
+                changed = true;
+            }
+
+            if changed {
+                feed::find_by_id(conn, created.id)?.ok_or(AppError::FeedNotFound)
+            } else {
+                Ok(created)
+            }
         })
         .await??;
 
@@ -155,13 +289,26 @@ pub async fn create_feed(
     ))
 }
 
+/// Fail with the existing subscription if the user already has one of `urls`
+fn reject_duplicate(conn: &rusqlite::Connection, user_id: i64, urls: &[&str]) -> AppResult<()> {
+    match feed::find_duplicate_by_user(conn, user_id, urls)? {
+        Some(existing) => Err(AppError::DuplicateFeed(ExistingFeed {
+            id: existing.id,
+            category_id: existing.category_id,
+            url: existing.url,
+            title: existing.title,
+        })),
+        None => Ok(()),
+    }
+}
+
 pub async fn get_feed(
     State(state): State<AppState>,
     auth_user: AuthUser,
     Path(id): Path<i64>,
-) -> AppResult<Json<FeedResponse>> {
+) -> AppResult<impl IntoResponse> {
     let user_id = auth_user.user.id;
-    let (f, has_icon) = state
+    let (f, has_icon, version) = state
         .db
         .user(move |conn| {
             let f = feed::find_by_id(conn, id)?.ok_or(AppError::FeedNotFound)?;
@@ -170,27 +317,524 @@ pub async fn get_feed(
                 .ok_or(AppError::FeedNotFound)?;
 
             let has_icon = image::exists(conn, image::ENTITY_FEED, f.id)?;
-            Ok::<_, AppError>((f, has_icon))
+            let version = feed::version(conn, f.id)?;
+            Ok::<_, AppError>((f, has_icon, version))
+        })
+        .await??;
+
+    Ok((
+        version_etag(version),
+        Json(FeedResponse::from_feed(f, has_icon)),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StaleFeedsQuery {
+    /// Look up replacement URLs on each feed's site, one request per feed
+    #[serde(default)]
+    pub discover: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StaleFeedResponse {
+    #[serde(flatten)]
+    pub feed: FeedResponse,
+    pub reason: feed::StaleReason,
+    pub failing_since: Option<String>,
+    pub entry_count: i64,
+    pub suggested_url: Option<String>,
+}
+
+pub async fn list_stale_feeds(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Query(query): Query<StaleFeedsQuery>,
+) -> AppResult<Json<Vec<StaleFeedResponse>>> {
+    let user_id = auth_user.user.id;
+    let stale = state
+        .db
+        .user(move |conn| {
+            let stale = feed::list_stale_by_user(conn, user_id, feed::STALE_FEED_DAYS)?;
+            let with_icons = stale
+                .into_iter()
+                .map(|s| {
+                    let has_icon =
+                        image::exists(conn, image::ENTITY_FEED, s.feed.id).unwrap_or(false);
+                    (s, has_icon)
+                })
+                .collect::<Vec<_>>();
+            Ok::<_, AppError>(with_icons)
         })
         .await??;
 
-    Ok(Json(FeedResponse::from_feed(f, has_icon)))
+    let settings = state.settings.current();
+    let mut response = Vec::with_capacity(stale.len());
+    for (s, has_icon) in stale {
+        // Re-run discovery against the site to suggest a replacement feed URL
+        let suggested_url = match (query.discover, s.feed.site_url.as_deref()) {
+            (true, Some(site_url)) => {
+                match feed_discovery::discover_feed(site_url, &settings.user_agent).await {
+                    Ok(discovered) if discovered.feed_url != s.feed.url => {
+                        Some(discovered.feed_url)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        response.push(StaleFeedResponse {
+            feed: FeedResponse::from_feed(s.feed, has_icon),
+            reason: s.reason,
+            failing_since: s.failing_since.map(|dt| dt.to_rfc3339()),
+            entry_count: s.entry_count,
+            suggested_url,
+        });
+    }
+
+    Ok(Json(response))
+}
+
+pub async fn list_feed_events(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<Json<Vec<feed_event::FeedEvent>>> {
+    let user_id = auth_user.user.id;
+    let events = state
+        .db
+        .user(move |conn| {
+            let f = feed::find_by_id(conn, id)?.ok_or(AppError::FeedNotFound)?;
+
+            category::find_by_id_and_user(conn, f.category_id, user_id)?
+                .ok_or(AppError::FeedNotFound)?;
+
+            feed_event::list_by_feed(conn, f.id, 100)
+        })
+        .await??;
+
+    Ok(Json(events))
+}
+
+pub async fn get_feed_stats(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<Json<feed_stats::FeedStats>> {
+    let user_id = auth_user.user.id;
+    let stats = state
+        .db
+        .read(move |conn| {
+            let f = feed::find_by_id(conn, id)?.ok_or(AppError::FeedNotFound)?;
+
+            category::find_by_id_and_user(conn, f.category_id, user_id)?
+                .ok_or(AppError::FeedNotFound)?;
+
+            feed_stats::find_by_feed(conn, f.id)
+        })
+        .await??;
+
+    Ok(Json(stats))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AutoReadPreviewQuery {
+    pub days: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AutoReadPreviewResponse {
+    pub days: i64,
+    /// Unread, unstarred entries the rule would mark read right now
+    pub affected: i64,
+}
+
+/// Dry run of an auto-mark-read rule on a feed
+pub async fn preview_feed_auto_read(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+    Query(query): Query<AutoReadPreviewQuery>,
+) -> AppResult<Json<AutoReadPreviewResponse>> {
+    let days = auto_read::parse_days(query.days)?
+        .ok_or_else(|| AppError::Validation("Days must be at least 1".to_string()))?;
+
+    let user_id = auth_user.user.id;
+    let affected = state
+        .db
+        .read(move |conn| {
+            let f = feed::find_by_id(conn, id)?.ok_or(AppError::FeedNotFound)?;
+
+            category::find_by_id_and_user(conn, f.category_id, user_id)?
+                .ok_or(AppError::FeedNotFound)?;
+
+            auto_read::preview_feed(conn, f.id, days)
+        })
+        .await??;
+
+    Ok(Json(AutoReadPreviewResponse { days, affected }))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateFeedCookiesRequest {
+    /// `Cookie` header value such as `"session=abc; theme=dark"`; empty clears
+    pub cookies: String,
+}
+
+impl std::fmt::Debug for UpdateFeedCookiesRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UpdateFeedCookiesRequest")
+            .field("cookies", &"[redacted]")
+            .finish()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeedCookiesResponse {
+    /// Whether the feed has a stored cookie jar
+    pub set: bool,
+    /// Names of the stored cookies; values are never returned
+    pub names: Vec<String>,
+}
+
+fn cookie_secret(state: &AppState) -> AppResult<Vec<u8>> {
+    state
+        .config
+        .feed_cookie_secret
+        .clone()
+        .ok_or_else(|| AppError::NotFound("Feed cookies are not configured".to_string()))
+}
+
+fn cookies_response(secret: &[u8], sealed: Option<Vec<u8>>) -> FeedCookiesResponse {
+    match sealed {
+        Some(sealed) => FeedCookiesResponse {
+            set: true,
+            // Cookies sealed under a previous secret are listed without names
+            names: feed_cookies::decrypt(secret, &sealed)
+                .map(|header| feed_cookies::cookie_names(&header))
+                .unwrap_or_default(),
+        },
+        None => FeedCookiesResponse {
+            set: false,
+            names: vec![],
+        },
+    }
+}
+
+pub async fn get_feed_cookies(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<Json<FeedCookiesResponse>> {
+    let secret = cookie_secret(&state)?;
+    let user_id = auth_user.user.id;
+    let sealed = state
+        .db
+        .user(move |conn| {
+            let f = feed::find_by_id(conn, id)?.ok_or(AppError::FeedNotFound)?;
+
+            category::find_by_id_and_user(conn, f.category_id, user_id)?
+                .ok_or(AppError::FeedNotFound)?;
+
+            feed_cookie::find_cookies(conn, f.id)
+        })
+        .await??;
+
+    Ok(Json(cookies_response(&secret, sealed)))
+}
+
+pub async fn update_feed_cookies(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+    Json(req): Json<UpdateFeedCookiesRequest>,
+) -> AppResult<Json<FeedCookiesResponse>> {
+    let secret = cookie_secret(&state)?;
+    let header = feed_cookies::parse_cookie_header(&req.cookies)?;
+    let sealed = if header.is_empty() {
+        None
+    } else {
+        Some(feed_cookies::encrypt(&secret, &header)?)
+    };
+
+    let user_id = auth_user.user.id;
+    let stored = sealed.clone();
+    state
+        .db
+        .user(move |conn| {
+            let f = feed::find_by_id(conn, id)?.ok_or(AppError::FeedNotFound)?;
+
+            category::find_by_id_and_user(conn, f.category_id, user_id)?
+                .ok_or(AppError::FeedNotFound)?;
+
+            match stored {
+                Some(sealed) => feed_cookie::set_cookies(conn, f.id, &sealed),
+                None => feed_cookie::delete_cookies(conn, f.id),
+            }
+        })
+        .await??;
+
+    Ok(Json(cookies_response(&secret, sealed)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateFeedScriptRequest {
+    /// Rhai source defining `on_entry(entry)`; empty removes the script
+    pub script: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeedScriptResponse {
+    pub script: Option<String>,
+}
+
+pub async fn get_feed_script(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<Json<FeedScriptResponse>> {
+    let user_id = auth_user.user.id;
+    let script = state
+        .db
+        .read(move |conn| {
+            let f = feed::find_by_id(conn, id)?.ok_or(AppError::FeedNotFound)?;
+
+            category::find_by_id_and_user(conn, f.category_id, user_id)?
+                .ok_or(AppError::FeedNotFound)?;
+
+            feed_script::find_script(conn, f.id)
+        })
+        .await??;
+
+    Ok(Json(FeedScriptResponse { script }))
+}
+
+/// Set the script run on the feed's entries during sync. Scripts are
+/// compiled first so mistakes are reported here rather than at the next sync.
+pub async fn update_feed_script(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+    Json(req): Json<UpdateFeedScriptRequest>,
+) -> AppResult<Json<FeedScriptResponse>> {
+    let script = match req.script.trim() {
+        "" => None,
+        _ => {
+            EntryHook::compile(&req.script).map_err(AppError::Validation)?;
+            Some(req.script)
+        }
+    };
+
+    let user_id = auth_user.user.id;
+    let stored = script.clone();
+    state
+        .db
+        .user(move |conn| {
+            let f = feed::find_by_id(conn, id)?.ok_or(AppError::FeedNotFound)?;
+
+            category::find_by_id_and_user(conn, f.category_id, user_id)?
+                .ok_or(AppError::FeedNotFound)?;
+
+            match stored {
+                Some(source) => feed_script::set_script(conn, f.id, &source),
+                None => feed_script::delete_script(conn, f.id),
+            }
+        })
+        .await??;
+
+    Ok(Json(FeedScriptResponse { script }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RewriteRequest {
+    pub field: RewriteField,
+    pub pattern: String,
+    #[serde(default)]
+    pub replacement: String,
+}
+
+impl RewriteRequest {
+    fn validate(&self) -> AppResult<()> {
+        rewrite::compile(&self.pattern).map_err(AppError::Validation)?;
+        if self.replacement.len() > rewrite::MAX_RULE_LEN {
+            return Err(AppError::Validation(format!(
+                "Replacement must be {} characters or less",
+                rewrite::MAX_RULE_LEN
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The feed `id` if it belongs to the user
+fn find_user_feed(conn: &rusqlite::Connection, id: i64, user_id: i64) -> AppResult<feed::Feed> {
+    let f = feed::find_by_id(conn, id)?.ok_or(AppError::FeedNotFound)?;
+    category::find_by_id_and_user(conn, f.category_id, user_id)?.ok_or(AppError::FeedNotFound)?;
+    Ok(f)
+}
+
+pub async fn list_feed_rewrites(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<Json<Vec<FeedRewrite>>> {
+    let user_id = auth_user.user.id;
+    let rewrites = state
+        .db
+        .read(move |conn| {
+            let f = find_user_feed(conn, id, user_id)?;
+            feed_rewrite::list_by_feed(conn, f.id)
+        })
+        .await??;
+
+    Ok(Json(rewrites))
+}
+
+/// Add a rule rewriting the titles or content of the feed's new and updated
+/// entries. Entries already stored are left as they are.
+pub async fn create_feed_rewrite(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+    Json(req): Json<RewriteRequest>,
+) -> AppResult<(StatusCode, Json<FeedRewrite>)> {
+    req.validate()?;
+
+    let user_id = auth_user.user.id;
+    let rewrite = state
+        .db
+        .user(move |conn| {
+            let f = find_user_feed(conn, id, user_id)?;
+            feed_rewrite::create_rewrite(conn, f.id, req.field, &req.pattern, &req.replacement)
+        })
+        .await??;
+
+    Ok((StatusCode::CREATED, Json(rewrite)))
+}
+
+pub async fn update_feed_rewrite(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path((id, rewrite_id)): Path<(i64, i64)>,
+    Json(req): Json<RewriteRequest>,
+) -> AppResult<Json<FeedRewrite>> {
+    req.validate()?;
+
+    let user_id = auth_user.user.id;
+    let rewrite = state
+        .db
+        .user(move |conn| {
+            let f = find_user_feed(conn, id, user_id)?;
+            feed_rewrite::update_rewrite(
+                conn,
+                rewrite_id,
+                f.id,
+                req.field,
+                &req.pattern,
+                &req.replacement,
+            )
+        })
+        .await??;
+
+    Ok(Json(rewrite))
+}
+
+pub async fn delete_feed_rewrite(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path((id, rewrite_id)): Path<(i64, i64)>,
+) -> AppResult<StatusCode> {
+    let user_id = auth_user.user.id;
+    state
+        .db
+        .user(move |conn| {
+            let f = find_user_feed(conn, id, user_id)?;
+            feed_rewrite::delete_rewrite(conn, rewrite_id, f.id)
+        })
+        .await??;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// A recent entry with a rule applied
+#[derive(Debug, Serialize)]
+pub struct RewritePreview {
+    pub entry_id: i64,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub changed: bool,
+}
+
+/// Try a rule on the feed's latest entries without saving it
+pub async fn preview_feed_rewrite(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+    Json(req): Json<RewriteRequest>,
+) -> AppResult<Json<Vec<RewritePreview>>> {
+    req.validate()?;
+
+    let user_id = auth_user.user.id;
+    let entries = state
+        .db
+        .read(move |conn| {
+            let f = find_user_feed(conn, id, user_id)?;
+            entry::list_by_feed(conn, f.id, rewrite::PREVIEW_ENTRIES, 0)
+        })
+        .await??;
+
+    let rule = FeedRewrite {
+        id: 0,
+        feed_id: id,
+        field: req.field,
+        pattern: req.pattern,
+        replacement: req.replacement,
+        created_at: chrono::Utc::now(),
+    };
+    let rewriter = Rewriter::new(std::slice::from_ref(&rule));
+    let previews = entries
+        .into_iter()
+        .map(|e| {
+            let before = match rule.field {
+                RewriteField::Title => e.title,
+                RewriteField::Content => e.content,
+            };
+            let after = rewriter.apply(rule.field, before.clone());
+            RewritePreview {
+                entry_id: e.id,
+                changed: after != before,
+                before,
+                after,
+            }
+        })
+        .collect();
+
+    Ok(Json(previews))
 }
 
 pub async fn update_feed(
     State(state): State<AppState>,
     auth_user: AuthUser,
     Path(id): Path<i64>,
+    if_match: IfMatch,
     Json(req): Json<UpdateFeedRequest>,
-) -> AppResult<Json<FeedResponse>> {
+) -> AppResult<impl IntoResponse> {
     let url = req.url.trim().to_string();
 
     if url.is_empty() {
         return Err(AppError::Validation("URL cannot be empty".to_string()));
     }
+    if let Some(headers) = &req.custom_headers {
+        validate_custom_headers(headers).map_err(AppError::Validation)?;
+    }
+    let auto_read_days = req.auto_read_days.map(auto_read::parse_days).transpose()?;
+    let update_threshold = req
+        .update_threshold
+        .map(feed::parse_update_threshold)
+        .transpose()?;
 
     let user_id = auth_user.user.id;
-    let (updated, has_icon) = state
+    let (updated, has_icon, version) = state
         .db
         .user(move |conn| {
             // Find the feed first and verify current ownership
@@ -199,15 +843,19 @@ pub async fn update_feed(
             category::find_by_id_and_user(conn, f.category_id, user_id)?
                 .ok_or(AppError::FeedNotFound)?;
 
+            if_match.check(feed::version(conn, id)?, || {
+                let has_icon = image::exists(conn, image::ENTITY_FEED, f.id)?;
+                Ok(FeedResponse::from_feed(f.clone(), has_icon))
+            })?;
+
             // Verify new category ownership
-            category::find_by_id_and_user(conn, req.category_id, user_id)?
-                .ok_or(AppError::CategoryNotFound)?;
+            let category_id = category::resolve(conn, user_id, req.category_id)?;
 
             let updated = feed::update_feed(
                 conn,
                 id,
                 f.category_id,
-                req.category_id,
+                category_id,
                 &url,
                 req.title.as_deref(),
                 req.description.as_deref(),
@@ -216,21 +864,65 @@ pub async fn update_feed(
                 req.http2_disabled,
             )?;
 
+            let mut changed = false;
+            if let Some(strategy) = req.date_strategy.filter(|s| *s != updated.date_strategy) {
+                feed::set_date_strategy(conn, id, strategy)?;
+                changed = true;
+            }
+            if let Some(headers) = req.custom_headers.filter(|h| *h != updated.custom_headers) {
+                feed::set_custom_headers(conn, id, &headers)?;
+                changed = true;
+            }
+            if let Some(days) = auto_read_days.filter(|d| *d != updated.auto_read_days) {
+                feed::set_auto_read_days(conn, id, days)?;
+                changed = true;
+            }
+            if let Some(percent) = update_threshold.filter(|p| *p != updated.update_threshold) {
+                feed::set_update_threshold(conn, id, percent)?;
+                changed = true;
+            }
+            let updated = if changed {
+                feed::find_by_id(conn, id)?.ok_or(AppError::FeedNotFound)?
+            } else {
+                updated
+            };
+
             let has_icon = image::exists(conn, image::ENTITY_FEED, updated.id)?;
-            Ok::<_, AppError>((updated, has_icon))
+            let version = feed::version(conn, id)?;
+            Ok::<_, AppError>((updated, has_icon, version))
         })
         .await??;
 
-    Ok(Json(FeedResponse::from_feed(updated, has_icon)))
+    Ok((
+        version_etag(version),
+        Json(FeedResponse::from_feed(updated, has_icon)),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteFeedQuery {
+    /// Move starred entries to "Saved pages" instead of deleting them with
+    /// the feed
+    #[serde(default)]
+    pub keep_starred: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeleteFeedResponse {
+    /// Restores the feed through `POST /api/undo/{token}`
+    pub undo_token: String,
+    /// Starred entries moved to "Saved pages"
+    pub kept_starred: usize,
 }
 
 pub async fn delete_feed(
     State(state): State<AppState>,
     auth_user: AuthUser,
     Path(id): Path<i64>,
-) -> AppResult<StatusCode> {
+    Query(query): Query<DeleteFeedQuery>,
+) -> AppResult<Json<DeleteFeedResponse>> {
     let user_id = auth_user.user.id;
-    state
+    let response = state
         .db
         .user(move |conn| {
             let f = feed::find_by_id(conn, id)?.ok_or(AppError::FeedNotFound)?;
@@ -238,12 +930,25 @@ pub async fn delete_feed(
             category::find_by_id_and_user(conn, f.category_id, user_id)?
                 .ok_or(AppError::FeedNotFound)?;
 
+            let kept_starred = if query.keep_starred {
+                saved_pages::keep_starred(conn, user_id, id)?
+            } else {
+                0
+            };
             feed::delete_feed(conn, id, f.category_id)?;
-            Ok::<_, AppError>(())
+            let undo_token = undo::record(
+                conn,
+                user_id,
+                &undo::UndoPayload::RestoreFeed { feed_id: id },
+            )?;
+            Ok::<_, AppError>(DeleteFeedResponse {
+                undo_token,
+                kept_starred,
+            })
         })
         .await??;
 
-    Ok(StatusCode::NO_CONTENT)
+    Ok(Json(response))
 }
 
 pub async fn fetch_metadata(
@@ -257,17 +962,39 @@ pub async fn fetch_metadata(
         return Err(AppError::Validation("URL cannot be empty".to_string()));
     }
 
-    // Just verify the user is authenticated (already done by AuthUser extractor)
-    let _ = auth_user;
+    let user_agent = state.settings.current().user_agent.clone();
+    let metadata =
+        feed_discovery::discover_metadata(&url, &user_agent, feed_discovery::METADATA_BUDGET)
+            .await?;
 
-    let discovered = feed_discovery::discover_feed(&url, &state.config.user_agent).await?;
+    let mut response = FeedMetadataResponse {
+        feed_url: None,
+        title: None,
+        description: None,
+        site_url: None,
+        icon_url: metadata.icon_url,
+        suggested_category_id: None,
+        errors: metadata.errors,
+    };
+    let Some(discovered) = metadata.feed else {
+        return Ok(Json(response));
+    };
 
-    Ok(Json(FeedMetadataResponse {
-        feed_url: discovered.feed_url,
-        title: discovered.title,
-        description: discovered.description,
-        site_url: discovered.site_url,
-    }))
+    let user_id = auth_user.user.id;
+    let (discovered, suggested_category_id) = state
+        .db
+        .user(move |conn| {
+            let suggested = category_suggest::suggest_category(conn, user_id, &discovered)?;
+            Ok::<_, AppError>((discovered, suggested))
+        })
+        .await??;
+
+    response.feed_url = Some(discovered.feed_url);
+    response.title = discovered.title;
+    response.description = discovered.description;
+    response.site_url = discovered.site_url;
+    response.suggested_category_id = suggested_category_id;
+    Ok(Json(response))
 }
 
 pub async fn export_opml(
@@ -297,75 +1024,150 @@ pub async fn export_opml(
     ))
 }
 
+pub async fn export_full(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<impl IntoResponse> {
+    let body = backup::export_body(state.db.clone(), auth_user.user.id).await?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/json"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"rdrs-backup.json\"",
+            ),
+        ],
+        body,
+    ))
+}
+
+pub async fn import_full(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    body: Body,
+) -> AppResult<Json<backup::ImportStats>> {
+    let user_id = auth_user.user.id;
+    let stats = backup::import_body(state.db.clone(), user_id, body).await?;
+
+    let summary = format!(
+        "{} feeds and {} entries added, {} entries merged",
+        stats.feeds_created, stats.entries_created, stats.entries_merged
+    );
+    state
+        .db
+        .user(move |conn| import::notify_import(conn, user_id, "Backup import finished", summary))
+        .await??;
+
+    Ok(Json(stats))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ImportOpmlRequest {
     pub content: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ImportOpmlQuery {
+    /// Queue the import as a job and answer right away
+    #[serde(default)]
+    pub background: bool,
+}
+
 #[derive(Debug, Serialize)]
-pub struct ImportResult {
-    pub categories_created: i32,
-    pub feeds_created: i32,
-    pub feeds_skipped: i32,
+pub struct ImportQueuedResponse {
+    pub job_id: i64,
 }
 
+/// A body the extractors refused, with `413` kept for one over the limit
+fn body_rejection(status: StatusCode, message: String) -> AppError {
+    if status == StatusCode::PAYLOAD_TOO_LARGE {
+        AppError::PayloadTooLarge
+    } else {
+        AppError::Validation(message)
+    }
+}
+
+/// Import OPML uploaded as the `file` field of a multipart form, sent as the
+/// request body, or wrapped in JSON as `content`. Uploads and bodies are
+/// parsed as they arrive. With `background`, the parsed outlines are queued
+/// as an import job and `202` returns its ID.
 pub async fn import_opml(
     State(state): State<AppState>,
     auth_user: AuthUser,
-    Json(req): Json<ImportOpmlRequest>,
-) -> AppResult<Json<ImportResult>> {
-    let outlines = opml::parse_opml(&req.content)?;
+    Query(query): Query<ImportOpmlQuery>,
+    request: Request,
+) -> AppResult<Response> {
+    let content_type = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    let outlines = if content_type.starts_with("multipart/form-data") {
+        let mut multipart = Multipart::from_request(request, &state)
+            .await
+            .map_err(|e| body_rejection(e.status(), e.body_text()))?;
+        let field = loop {
+            let field = multipart
+                .next_field()
+                .await
+                .map_err(|e| body_rejection(e.status(), e.body_text()))?;
+            match field {
+                Some(field) if field.name() == Some("file") => break field,
+                Some(_) => continue,
+                None => return Err(AppError::Validation("Missing file field".to_string())),
+            }
+        };
+        opml::parse_opml_stream(field).await?
+    } else if content_type.starts_with("application/json") {
+        let Json(req) = Json::<ImportOpmlRequest>::from_request(request, &state)
+            .await
+            .map_err(|e| body_rejection(e.status(), e.body_text()))?;
+        opml::parse_opml(&req.content)?
+    } else {
+        opml::parse_opml_stream(request.into_body().into_data_stream()).await?
+    };
 
     let user_id = auth_user.user.id;
+    if query.background {
+        let task = Task::Import(ImportJob { user_id, outlines });
+        let job_id = state
+            .db
+            .user(move |conn| jobs::enqueue(conn, &task))
+            .await??;
+        state.jobs.wake();
+        return Ok((StatusCode::ACCEPTED, Json(ImportQueuedResponse { job_id })).into_response());
+    }
+
     let result = state
         .db
-        .user(move |conn| {
-            let mut categories_created = 0;
-            let mut feeds_created = 0;
-            let mut feeds_skipped = 0;
-
-            for outline in outlines {
-                // Find or create category
-                let cat =
-                    match category::find_by_name_and_user(conn, &outline.category_name, user_id)? {
-                        Some(cat) => cat,
-                        None => {
-                            let new_cat =
-                                category::create_category(conn, user_id, &outline.category_name)?;
-                            categories_created += 1;
-                            new_cat
-                        }
-                    };
-
-                // Create feeds
-                for opml_feed in outline.feeds {
-                    // Check if feed already exists in this category
-                    if feed::find_by_url_and_category(conn, &opml_feed.xml_url, cat.id)?.is_some() {
-                        feeds_skipped += 1;
-                        continue;
-                    }
+        .user(move |conn| import::import_now(conn, user_id, outlines))
+        .await??;
+    state.jobs.wake();
 
-                    // Create the feed
-                    feed::create_feed(
-                        conn,
-                        cat.id,
-                        &opml_feed.xml_url,
-                        opml_feed.title.as_deref(),
-                        None,
-                        opml_feed.html_url.as_deref(),
-                        None,
-                        None,
-                    )?;
-                    feeds_created += 1;
-                }
-            }
+    Ok(Json(result).into_response())
+}
 
-            Ok::<_, AppError>(ImportResult {
-                categories_created,
-                feeds_created,
-                feeds_skipped,
-            })
-        })
+#[derive(Debug, Deserialize)]
+pub struct ImportBookmarksRequest {
+    pub content: String,
+}
+
+/// Import a Pocket or Instapaper export as starred entries of the "Imported" feed
+pub async fn import_bookmarks(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<ImportBookmarksRequest>,
+) -> AppResult<Json<bookmark_import::BookmarkImportResult>> {
+    let bookmarks = bookmark_import::parse_bookmarks(&req.content)?;
+
+    let user_id = auth_user.user.id;
+    let result = state
+        .db
+        .user(move |conn| bookmark_import::import_bookmarks(conn, user_id, bookmarks))
         .await??;
 
     Ok(Json(result))
@@ -402,3 +1204,44 @@ pub async fn get_feed_icon(
     )
         .into_response())
 }
+
+#[derive(Debug, Serialize)]
+pub struct RefreshFeedIconResponse {
+    /// False when no icon could be found; any old icon is kept
+    pub found: bool,
+    pub source_url: Option<String>,
+}
+
+/// POST /api/feeds/{id}/icon/refresh - Fetch the feed's icon again now
+pub async fn refresh_feed_icon(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<Json<RefreshFeedIconResponse>> {
+    if !allows(Outbound::Icon) {
+        return Err(AppError::PrivacyMode);
+    }
+
+    let user_id = auth_user.user.id;
+    state
+        .db
+        .user(move |conn| {
+            let f = feed::find_by_id(conn, id)?.ok_or(AppError::FeedNotFound)?;
+            category::find_by_id_and_user(conn, f.category_id, user_id)?
+                .ok_or(AppError::FeedNotFound)?;
+            if f.synthetic {
+                return Err(AppError::Validation(
+                    "This feed has no site to fetch an icon from".to_string(),
+                ));
+            }
+            Ok(())
+        })
+        .await??;
+
+    let user_agent = state.settings.current().user_agent.clone();
+    let source_url = icon_refresh::refresh_feed_icon(&state.db, id, &user_agent).await?;
+    Ok(Json(RefreshFeedIconResponse {
+        found: source_url.is_some(),
+        source_url,
+    }))
+}
@@ -0,0 +1,314 @@
+//! Server-rendered HTML fragments of the entry list, for progressive
+//! enhancement (for example with HTMX `hx-get`) instead of fetching JSON and
+//! rendering it in the browser. They show the same data as the JSON API.
+
+use askama::Template;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use super::entry::{load_entries, EntryWithSummary, ListEntriesQuery};
+use crate::error::{AppError, AppResult};
+use crate::middleware::AuthUser;
+use crate::models::entry::{self, EntryFilter};
+use crate::models::entry_search::SearchFields;
+use crate::models::{user_settings, SummaryStatus};
+use crate::timezone::{self, Tz};
+use crate::AppState;
+
+pub struct SummaryBadge {
+    pub class: &'static str,
+    pub label: &'static str,
+    pub title: &'static str,
+}
+
+impl SummaryBadge {
+    fn for_status(status: SummaryStatus) -> Self {
+        let (class, label, title) = match status {
+            SummaryStatus::Completed => ("summary-badge", "[S]", "Has Summary"),
+            SummaryStatus::Pending => ("summary-badge-pending", "[P]", "Pending"),
+            SummaryStatus::Processing => ("summary-badge-processing", "[...]", "Processing"),
+            SummaryStatus::Failed => ("summary-badge-failed", "[F]", "Failed"),
+        };
+        SummaryBadge {
+            class,
+            label,
+            title,
+        }
+    }
+}
+
+pub struct Published {
+    pub iso: String,
+    pub date: String,
+}
+
+/// One row of the entry list, with everything the template shows
+pub struct EntryItem {
+    pub id: i64,
+    pub title: String,
+    pub link: Option<String>,
+    pub feed_id: i64,
+    pub feed_title: String,
+    pub feed_has_icon: bool,
+    pub category_id: i64,
+    pub category_name: String,
+    pub published: Option<Published>,
+    pub is_read: bool,
+    pub is_starred: bool,
+    /// Changed beyond the feed's update threshold since it arrived
+    pub is_updated: bool,
+    pub summary_badge: Option<SummaryBadge>,
+}
+
+/// Titles may carry HTML entities or markup; the template escapes the text
+fn plain_text(html: &str) -> String {
+    scraper::Html::parse_fragment(html)
+        .root_element()
+        .text()
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+impl EntryItem {
+    /// Row for `item` with its date shown in `tz` (UTC without one)
+    fn new(item: EntryWithSummary, tz: Option<Tz>) -> Self {
+        let e = item.entry;
+        let title = e.entry.title.as_deref().map(plain_text).unwrap_or_default();
+        EntryItem {
+            id: e.entry.id,
+            title: if title.is_empty() {
+                "Untitled".to_string()
+            } else {
+                title
+            },
+            link: e.entry.link,
+            feed_id: e.entry.feed_id,
+            feed_title: e
+                .feed_title
+                .as_deref()
+                .map(plain_text)
+                .filter(|t| !t.is_empty())
+                .unwrap_or(e.feed_url),
+            feed_has_icon: e.feed_has_icon,
+            category_id: e.category_id,
+            category_name: e.category_name,
+            published: e.entry.published_at.map(|dt| {
+                let dt = timezone::local(dt, tz);
+                Published {
+                    iso: dt.to_rfc3339(),
+                    date: dt.format("%Y-%m-%d").to_string(),
+                }
+            }),
+            is_read: e.entry.read_at.is_some(),
+            is_starred: e.entry.starred_at.is_some(),
+            is_updated: e.entry.content_updated_at.is_some(),
+            summary_badge: item.summary_status.map(SummaryBadge::for_status),
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "fragments/entries.html")]
+pub struct EntriesFragment {
+    pub entries: Vec<EntryItem>,
+    pub offset: i64,
+    pub shown: i64,
+    pub total: i64,
+    /// Query string of the next page, if there is one
+    pub next_page: Option<String>,
+}
+
+impl IntoResponse for EntriesFragment {
+    fn into_response(self) -> Response {
+        match self.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+}
+
+/// Query string selecting the page of `query` that starts at `offset`
+fn page_query(query: &ListEntriesQuery, offset: i64) -> String {
+    let mut params = url::form_urlencoded::Serializer::new(String::new());
+    if let Some(feed_id) = query.feed_id {
+        params.append_pair("feed_id", &feed_id.to_string());
+    }
+    if let Some(category_id) = query.category_id {
+        params.append_pair("category_id", &category_id.to_string());
+    }
+    for (name, set) in [
+        ("unread_only", query.unread_only),
+        ("starred_only", query.starred_only),
+        ("read_only", query.read_only),
+        ("collapse", query.collapse),
+    ] {
+        if set {
+            params.append_pair(name, "true");
+        }
+    }
+    if let Some(search) = &query.search {
+        params.append_pair("search", search);
+        if query.fields != SearchFields::default() {
+            params.append_pair("fields", &query.fields.to_string());
+        }
+    }
+    if let Some(has_summary) = query.has_summary {
+        params.append_pair("has_summary", &has_summary.to_string());
+    }
+    if let Some(tag) = &query.tag {
+        params.append_pair("tag", tag);
+    }
+    if let Some(state) = query.state {
+        params.append_pair("state", state.as_str());
+    }
+    // Left out when it is the default, which depends on `state`
+    if let Some(sort) = query.sort.filter(|sort| *sort != query.sort_default()) {
+        let sort = match sort {
+            entry::EntrySortOrder::PublishedAt => "published_at",
+            entry::EntrySortOrder::ReadAt => "read_at",
+            entry::EntrySortOrder::StarredAt => "starred_at",
+            entry::EntrySortOrder::Interest => "interest",
+        };
+        params.append_pair("sort", sort);
+    }
+    params.append_pair("limit", &query.limit.to_string());
+    params.append_pair("offset", &offset.to_string());
+    params.finish()
+}
+
+/// A page of the entry list followed by a link to the next page. Takes the
+/// same query parameters as `GET /api/entries`.
+pub async fn entries_fragment(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<ListEntriesQuery>,
+) -> AppResult<EntriesFragment> {
+    let user_id = auth_user.user.id;
+    let response = load_entries(&state, user_id, query.clone()).await?;
+    let tz = state
+        .db
+        .read(move |conn| user_settings::get_timezone(conn, user_id))
+        .await??;
+
+    let shown = query.offset + response.entries.len() as i64;
+    let next_page =
+        (!response.entries.is_empty() && shown < response.total).then(|| page_query(&query, shown));
+
+    Ok(EntriesFragment {
+        entries: response
+            .entries
+            .into_iter()
+            .map(|item| EntryItem::new(item, tz))
+            .collect(),
+        offset: query.offset,
+        shown,
+        total: response.total,
+        next_page,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnreadBadgeQuery {
+    pub feed_id: Option<i64>,
+    pub category_id: Option<i64>,
+}
+
+#[derive(Template)]
+#[template(path = "fragments/unread_badge.html")]
+pub struct UnreadBadgeFragment {
+    pub count: i64,
+    /// The count as shown, capped by the user's unread count limit
+    pub label: String,
+}
+
+impl IntoResponse for UnreadBadgeFragment {
+    fn into_response(self) -> Response {
+        match self.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+}
+
+/// Unread count of all entries, a feed or a category. Feeds and categories of
+/// other users count as empty.
+pub async fn unread_badge_fragment(
+    auth_user: AuthUser,
+    State(state): State<AppState>,
+    Query(query): Query<UnreadBadgeQuery>,
+) -> AppResult<UnreadBadgeFragment> {
+    let user_id = auth_user.user.id;
+    let filter = EntryFilter {
+        feed_id: query.feed_id,
+        category_id: query.category_id,
+        unread_only: true,
+        ..Default::default()
+    };
+    let (count, home) = state
+        .db
+        .read(move |conn| {
+            let count = entry::count_by_user(conn, user_id, &filter)?;
+            let home = user_settings::get_home_settings(conn, user_id)?;
+            Ok::<_, AppError>((count, home))
+        })
+        .await??;
+
+    Ok(UnreadBadgeFragment {
+        count,
+        label: home.format_count(count),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timezone::TimestampZone;
+
+    fn query(offset: i64) -> ListEntriesQuery {
+        ListEntriesQuery {
+            feed_id: Some(3),
+            category_id: None,
+            unread_only: true,
+            starred_only: false,
+            read_only: false,
+            search: Some("rust & go".to_string()),
+            fields: SearchFields::default(),
+            has_summary: None,
+            tag: None,
+            collapse: false,
+            state: None,
+            sort: Some(entry::EntrySortOrder::ReadAt),
+            limit: 20,
+            offset,
+            tz: TimestampZone::Utc,
+        }
+    }
+
+    #[test]
+    fn test_page_query() {
+        assert_eq!(
+            page_query(&query(0), 20),
+            "feed_id=3&unread_only=true&search=rust+%26+go&sort=read_at&limit=20&offset=20"
+        );
+
+        let archive = ListEntriesQuery {
+            state: Some(entry::EntryState::Read),
+            ..query(0)
+        };
+        assert_eq!(
+            page_query(&archive, 20),
+            "feed_id=3&unread_only=true&search=rust+%26+go&state=read&limit=20&offset=20"
+        );
+    }
+
+    #[test]
+    fn test_plain_text() {
+        assert_eq!(plain_text("Tom &amp; Jerry"), "Tom & Jerry");
+        assert_eq!(plain_text(" <b>Bold</b> move "), "Bold move");
+    }
+}
@@ -1,7 +1,18 @@
-use axum::Json;
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, Json};
 use serde::Serialize;
 
+use crate::services::WorkerStatus;
 use crate::version::{GIT_VERSION, PKG_VERSION};
+use crate::AppState;
+
+/// WAL size above which the instance reports itself not ready; a WAL this
+/// large means checkpoints are not keeping up
+pub const MAX_WAL_BYTES: u64 = 512 * 1024 * 1024;
+
+/// How long the database actor may take to answer the readiness probe
+const DB_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
 
 #[derive(Serialize)]
 pub struct HealthResponse {
@@ -17,3 +28,96 @@ pub async fn health_check() -> Json<HealthResponse> {
         git_version: GIT_VERSION,
     })
 }
+
+#[derive(Debug, Serialize)]
+pub struct ComponentStatus {
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ComponentStatus {
+    fn ok() -> Self {
+        ComponentStatus {
+            status: "ok",
+            error: None,
+        }
+    }
+
+    fn failed(error: impl Into<String>) -> Self {
+        ComponentStatus {
+            status: "failed",
+            error: Some(error.into()),
+        }
+    }
+
+    fn is_ok(&self) -> bool {
+        self.status == "ok"
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct WalStatus {
+    pub status: &'static str,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    pub status: &'static str,
+    pub database: ComponentStatus,
+    pub workers: Vec<WorkerStatus>,
+    pub wal: WalStatus,
+}
+
+/// Readiness probe: the database actor answers, every background worker has
+/// reported in recently, and the WAL has not grown past `MAX_WAL_BYTES`.
+/// Responds 503 with the same body when any component fails.
+pub async fn readiness_check(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let probe = state
+        .db
+        .user(|conn| conn.query_row("SELECT 1", [], |row| row.get::<_, i64>(0)));
+    let database = match tokio::time::timeout(DB_PROBE_TIMEOUT, probe).await {
+        Ok(Ok(Ok(_))) => ComponentStatus::ok(),
+        Ok(Ok(Err(e))) => ComponentStatus::failed(e.to_string()),
+        Ok(Err(e)) => ComponentStatus::failed(e.to_string()),
+        Err(_) => ComponentStatus::failed("Database did not respond in time"),
+    };
+
+    let workers = state.heartbeats.statuses();
+
+    let size_bytes = if state.config.database_url == ":memory:" {
+        0
+    } else {
+        std::fs::metadata(format!("{}-wal", state.config.database_url))
+            .map(|m| m.len())
+            .unwrap_or(0)
+    };
+    let wal = WalStatus {
+        status: if size_bytes <= MAX_WAL_BYTES {
+            "ok"
+        } else {
+            "failed"
+        },
+        size_bytes,
+    };
+
+    let ready = database.is_ok() && workers.iter().all(|w| w.alive) && wal.status == "ok";
+    let (code, status) = if ready {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
+
+    (
+        code,
+        Json(ReadinessResponse {
+            status,
+            database,
+            workers,
+            wal,
+        }),
+    )
+}
@@ -0,0 +1,112 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+
+use crate::auth::ownership;
+use crate::error::AppResult;
+use crate::middleware::AuthUser;
+use crate::models::highlight::{self, Highlight, PositionSelector};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateHighlightRequest {
+    pub quote: String,
+    pub selector: Option<PositionSelector>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateHighlightRequest {
+    pub note: Option<String>,
+}
+
+/// An empty note is no note
+fn clean_note(note: Option<String>) -> Option<String> {
+    note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty())
+}
+
+/// GET /api/entries/{id}/highlights - The user's highlights in an entry
+pub async fn list_highlights(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<Json<Vec<Highlight>>> {
+    let user_id = auth_user.user.id;
+
+    let highlights = state
+        .db
+        .read(move |conn| {
+            ownership::assert_entry_owned(conn, user_id, id)?;
+            highlight::list_by_entry(conn, user_id, id)
+        })
+        .await??;
+
+    Ok(Json(highlights))
+}
+
+/// POST /api/entries/{id}/highlights - Mark a passage, with an optional note
+pub async fn create_highlight(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+    Json(req): Json<CreateHighlightRequest>,
+) -> AppResult<(StatusCode, Json<Highlight>)> {
+    let note = clean_note(req.note);
+    highlight::validate(&req.quote, req.selector, note.as_deref())?;
+
+    let user_id = auth_user.user.id;
+    let created = state
+        .db
+        .user(move |conn| {
+            ownership::assert_entry_owned(conn, user_id, id)?;
+            highlight::create_highlight(
+                conn,
+                user_id,
+                id,
+                &req.quote,
+                req.selector,
+                note.as_deref(),
+            )
+        })
+        .await??;
+
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// PUT /api/entries/{id}/highlights/{highlight_id} - Change the note
+pub async fn update_highlight(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path((id, highlight_id)): Path<(i64, i64)>,
+    Json(req): Json<UpdateHighlightRequest>,
+) -> AppResult<Json<Highlight>> {
+    let note = clean_note(req.note);
+    highlight::validate_note(note.as_deref())?;
+
+    let user_id = auth_user.user.id;
+    let updated = state
+        .db
+        .user(move |conn| highlight::update_note(conn, user_id, id, highlight_id, note.as_deref()))
+        .await??;
+
+    Ok(Json(updated))
+}
+
+/// DELETE /api/entries/{id}/highlights/{highlight_id}
+pub async fn delete_highlight(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path((id, highlight_id)): Path<(i64, i64)>,
+) -> AppResult<StatusCode> {
+    let user_id = auth_user.user.id;
+
+    state
+        .db
+        .user(move |conn| highlight::delete_highlight(conn, user_id, id, highlight_id))
+        .await??;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,93 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::{AdminUser, AuthUser};
+use crate::models::job::{self, Job, JobStatus, DEFAULT_JOB_LIMIT, MAX_JOB_LIMIT};
+use crate::services::jobs::JobKind;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ListJobsQuery {
+    pub status: Option<JobStatus>,
+    pub limit: Option<i64>,
+    /// Admins only: one user's jobs instead of everyone's
+    pub user_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobResponse {
+    pub id: i64,
+    pub kind: String,
+    pub user_id: Option<i64>,
+    pub status: JobStatus,
+    pub attempts: i64,
+    /// Tries allowed before the job is left dead
+    pub max_attempts: Option<i64>,
+    pub last_error: Option<String>,
+    pub payload: serde_json::Value,
+    /// When a pending job runs next
+    pub run_after: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Job> for JobResponse {
+    fn from(job: Job) -> Self {
+        JobResponse {
+            max_attempts: JobKind::parse(&job.kind).map(|k| k.retry_policy().max_attempts),
+            payload: serde_json::from_str(&job.payload).unwrap_or(serde_json::Value::Null),
+            id: job.id,
+            kind: job.kind,
+            user_id: job.user_id,
+            status: job.status,
+            attempts: job.attempts,
+            last_error: job.last_error,
+            run_after: job.run_after,
+            created_at: job.created_at,
+            updated_at: job.updated_at,
+        }
+    }
+}
+
+async fn list(
+    state: &AppState,
+    user_id: Option<i64>,
+    query: &ListJobsQuery,
+) -> AppResult<Json<Vec<JobResponse>>> {
+    let status = query.status;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_JOB_LIMIT)
+        .clamp(1, MAX_JOB_LIMIT);
+    let jobs = state
+        .db
+        .read(move |conn| job::list(conn, user_id, status, limit))
+        .await??;
+    Ok(Json(jobs.into_iter().map(Into::into).collect()))
+}
+
+/// GET /api/jobs - The user's queued, running and recently finished jobs
+pub async fn list_jobs(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Query(query): Query<ListJobsQuery>,
+) -> AppResult<Json<Vec<JobResponse>>> {
+    if query.user_id.is_some_and(|id| id != auth_user.user.id) {
+        return Err(AppError::Forbidden);
+    }
+    list(&state, Some(auth_user.user.id), &query).await
+}
+
+/// GET /api/admin/jobs - Every user's jobs, or one user's with `user_id`
+pub async fn list_all_jobs(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Query(query): Query<ListJobsQuery>,
+) -> AppResult<Json<Vec<JobResponse>>> {
+    list(&state, query.user_id, &query).await
+}
@@ -1,11 +1,24 @@
 pub mod admin;
 pub mod auth;
+pub mod bookmarklet;
+pub mod bootstrap;
 pub mod category;
 pub mod entry;
+pub mod entry_note;
+pub mod epub;
 pub mod favicon;
 pub mod feed;
+pub mod fragments;
 pub mod health;
+pub mod highlight;
+pub mod job;
+pub mod notification;
 pub mod pages;
 pub mod passkey;
+pub mod pdf;
 pub mod proxy;
+pub mod pwa;
+pub mod tag;
+pub mod trash;
+pub mod undo;
 pub mod user;
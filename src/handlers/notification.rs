@@ -0,0 +1,283 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::ownership;
+use crate::error::{AppError, AppResult};
+use crate::middleware::AuthUser;
+use crate::models::notification::{
+    self, Notification, DEFAULT_NOTIFICATION_LIMIT, MAX_NOTIFICATIONS,
+};
+use crate::models::{notification_rule, push_subscription};
+use crate::services::VapidKeys;
+use crate::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct VapidKeyResponse {
+    pub public_key: String,
+}
+
+pub async fn get_vapid_public_key(
+    State(state): State<AppState>,
+    _auth_user: AuthUser,
+) -> AppResult<Json<VapidKeyResponse>> {
+    let private_key =
+        state.config.vapid_private_key.as_deref().ok_or_else(|| {
+            AppError::NotFound("Push notifications are not configured".to_string())
+        })?;
+
+    let keys = VapidKeys::from_private_key(private_key, &state.config.vapid_subject)?;
+
+    Ok(Json(VapidKeyResponse {
+        public_key: keys.public_key_base64(),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+/// Matches the JSON produced by `PushSubscription.toJSON()` in the browser
+#[derive(Debug, Deserialize)]
+pub struct CreateSubscriptionRequest {
+    pub endpoint: String,
+    pub keys: SubscriptionKeys,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteSubscriptionRequest {
+    pub endpoint: String,
+}
+
+pub async fn list_push_subscriptions(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<Vec<push_subscription::PushSubscription>>> {
+    let user_id = auth_user.user.id;
+
+    let subscriptions = state
+        .db
+        .user(move |conn| push_subscription::list_by_user(conn, user_id))
+        .await??;
+
+    Ok(Json(subscriptions))
+}
+
+pub async fn create_push_subscription(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<CreateSubscriptionRequest>,
+) -> AppResult<(StatusCode, Json<push_subscription::PushSubscription>)> {
+    if !state.config.push_enabled() {
+        return Err(AppError::NotFound(
+            "Push notifications are not configured".to_string(),
+        ));
+    }
+
+    let endpoint = url::Url::parse(&req.endpoint).map_err(|_| AppError::InvalidUrl)?;
+    if endpoint.scheme() != "https" {
+        return Err(AppError::Validation(
+            "Push endpoint must use HTTPS".to_string(),
+        ));
+    }
+    if req.keys.p256dh.is_empty() || req.keys.auth.is_empty() {
+        return Err(AppError::Validation(
+            "Subscription keys are required".to_string(),
+        ));
+    }
+
+    let user_id = auth_user.user.id;
+    let subscription = state
+        .db
+        .user(move |conn| {
+            push_subscription::upsert_subscription(
+                conn,
+                user_id,
+                &req.endpoint,
+                &req.keys.p256dh,
+                &req.keys.auth,
+            )
+        })
+        .await??;
+
+    Ok((StatusCode::CREATED, Json(subscription)))
+}
+
+pub async fn delete_push_subscription(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<DeleteSubscriptionRequest>,
+) -> AppResult<StatusCode> {
+    let user_id = auth_user.user.id;
+
+    let deleted = state
+        .db
+        .user(move |conn| push_subscription::delete_subscription(conn, user_id, &req.endpoint))
+        .await??;
+
+    if !deleted {
+        return Err(AppError::NotFound(
+            "Push subscription not found".to_string(),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRuleRequest {
+    pub feed_id: Option<i64>,
+    pub category_id: Option<i64>,
+    pub keyword: Option<String>,
+}
+
+pub async fn list_notification_rules(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<Vec<notification_rule::NotificationRule>>> {
+    let user_id = auth_user.user.id;
+
+    let rules = state
+        .db
+        .user(move |conn| notification_rule::list_by_user(conn, user_id))
+        .await??;
+
+    Ok(Json(rules))
+}
+
+pub async fn create_notification_rule(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<CreateRuleRequest>,
+) -> AppResult<(StatusCode, Json<notification_rule::NotificationRule>)> {
+    let keyword = req
+        .keyword
+        .map(|k| k.trim().to_string())
+        .filter(|k| !k.is_empty());
+
+    if let Some(ref k) = keyword {
+        if k.len() > 100 {
+            return Err(AppError::Validation(
+                "Keyword must be 100 characters or less".to_string(),
+            ));
+        }
+    }
+
+    let user_id = auth_user.user.id;
+    let rule = state
+        .db
+        .user(move |conn| {
+            // Verify referenced feed and category belong to user
+            if let Some(category_id) = req.category_id {
+                ownership::assert_category_owned(conn, user_id, category_id)?;
+            }
+
+            if let Some(feed_id) = req.feed_id {
+                ownership::assert_feed_owned(conn, user_id, feed_id)?;
+            }
+
+            notification_rule::create_rule(
+                conn,
+                user_id,
+                req.feed_id,
+                req.category_id,
+                keyword.as_deref(),
+            )
+        })
+        .await??;
+
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+pub async fn delete_notification_rule(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<StatusCode> {
+    let user_id = auth_user.user.id;
+
+    state
+        .db
+        .user(move |conn| notification_rule::delete_rule(conn, id, user_id))
+        .await??;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListNotificationsQuery {
+    #[serde(default)]
+    pub unread_only: bool,
+    /// 0 returns only `unread_count`
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationsResponse {
+    pub notifications: Vec<Notification>,
+    pub unread_count: i64,
+}
+
+/// The user's notifications, newest first, with how many are unread
+pub async fn list_notifications(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Query(query): Query<ListNotificationsQuery>,
+) -> AppResult<Json<NotificationsResponse>> {
+    let user_id = auth_user.user.id;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_NOTIFICATION_LIMIT)
+        .clamp(0, MAX_NOTIFICATIONS);
+
+    let response = state
+        .db
+        .read(move |conn| {
+            Ok::<_, AppError>(NotificationsResponse {
+                notifications: notification::list_by_user(conn, user_id, query.unread_only, limit)?,
+                unread_count: notification::count_unread(conn, user_id)?,
+            })
+        })
+        .await??;
+
+    Ok(Json(response))
+}
+
+pub async fn mark_notification_read(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<Json<Notification>> {
+    let user_id = auth_user.user.id;
+
+    let updated = state
+        .db
+        .user(move |conn| notification::mark_read(conn, id, user_id))
+        .await??;
+
+    Ok(Json(updated))
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarkAllNotificationsReadResponse {
+    pub marked_count: i64,
+}
+
+pub async fn mark_all_notifications_read(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<MarkAllNotificationsReadResponse>> {
+    let user_id = auth_user.user.id;
+
+    let marked_count = state
+        .db
+        .user(move |conn| notification::mark_all_read(conn, user_id))
+        .await??;
+
+    Ok(Json(MarkAllNotificationsReadResponse { marked_count }))
+}
@@ -1,16 +1,24 @@
 use askama::Template;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    response::{Html, IntoResponse, Response},
+    response::{Html, IntoResponse, Redirect, Response},
 };
 
+use chrono::{Datelike, Utc};
+use serde::Deserialize;
+
 use crate::config::DEFAULT_USER_AGENT;
 use crate::error::AppError;
 use crate::middleware::auth::{PageAdminUser, PageAuthUser};
 use crate::middleware::flash::{Flash, FlashMessage};
 use crate::models::user_settings;
-use crate::models::{category, entry, feed};
+use crate::models::{category, entry, feed, invite};
+use crate::services::http::{allows, Outbound};
+use crate::services::reading_stats;
+use crate::services::sanitize::SanitizePolicy;
+use crate::services::{LinkdingConfig, SummaryOptions, UpdateStatus};
+use crate::timezone;
 use crate::AppState;
 
 #[derive(Template)]
@@ -36,7 +44,7 @@ pub async fn login_page(State(state): State<AppState>, flash: Flash) -> (Flash,
         .await
         .ok()
         .flatten()
-        .map(|count| state.config.can_register(count))
+        .map(|count| state.settings.current().can_register(count))
         .unwrap_or(false);
 
     (
@@ -52,6 +60,7 @@ pub async fn login_page(State(state): State<AppState>, flash: Flash) -> (Flash,
 #[template(path = "register.html")]
 pub struct RegisterTemplate {
     pub error: Option<String>,
+    pub invite: Option<String>,
     pub flash_messages: Vec<FlashMessage>,
 }
 
@@ -64,27 +73,46 @@ impl IntoResponse for RegisterTemplate {
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RegisterQuery {
+    pub invite: Option<String>,
+}
+
 pub async fn register_page(
     State(state): State<AppState>,
+    Query(query): Query<RegisterQuery>,
     flash: Flash,
 ) -> (Flash, RegisterTemplate) {
-    let can_register = state
+    let token = query.invite.clone();
+    let (can_register, invite_valid) = state
         .db
-        .user(|c| crate::models::user::count(c).ok())
+        .user(move |c| {
+            let count = crate::models::user::count(c).ok()?;
+            let invite_valid = match token.as_deref() {
+                Some(token) => invite::find_usable(c, token).ok()?.is_some(),
+                None => false,
+            };
+            Some((count, invite_valid))
+        })
         .await
         .ok()
         .flatten()
-        .map(|count| state.config.can_register(count))
-        .unwrap_or(false);
+        .map(|(count, invite_valid)| (state.settings.current().can_register(count), invite_valid))
+        .unwrap_or((false, false));
+
+    let error = if query.invite.is_some() && !invite_valid {
+        Some("This invite is invalid or has expired".to_string())
+    } else if !can_register && !invite_valid {
+        Some("Registration is currently disabled".to_string())
+    } else {
+        None
+    };
 
     (
         flash.clone(),
         RegisterTemplate {
-            error: if !can_register {
-                Some("Registration is currently disabled".to_string())
-            } else {
-                None
-            },
+            error,
+            invite: query.invite.filter(|_| invite_valid),
             flash_messages: flash.messages,
         },
     )
@@ -127,26 +155,25 @@ pub async fn unread_page(
     };
 
     let user_id = auth_user.user.id;
-    let (unread_count, entries_per_page, theme) = state
+    let (unread_count, entries_per_page, theme, tz) = state
         .db
         .user(move |c| {
             let unread = entry::count_unread_by_user(c, user_id).unwrap_or(0);
             let epp = user_settings::get_entries_per_page(c, user_id)
                 .unwrap_or(user_settings::DEFAULT_ENTRIES_PER_PAGE);
             let theme = user_settings::get_theme(c, user_id).unwrap_or(None);
-            (unread, epp, theme)
+            let tz = user_settings::get_timezone(c, user_id).unwrap_or(None);
+            (unread, epp, theme, tz)
         })
         .await
-        .unwrap_or((0, user_settings::DEFAULT_ENTRIES_PER_PAGE, None));
+        .unwrap_or((0, user_settings::DEFAULT_ENTRIES_PER_PAGE, None, None));
 
     (
         flash.clone(),
         UnreadTemplate {
             username: auth_user.user.username.clone(),
             role: auth_user.user.role.as_str().to_string(),
-            sign_in_time: auth_user
-                .session
-                .created_at
+            sign_in_time: timezone::local(auth_user.session.created_at, tz)
                 .format("%Y-%m-%d %H:%M:%S")
                 .to_string(),
             unread_count,
@@ -159,6 +186,105 @@ pub async fn unread_page(
     )
 }
 
+/// `/`: the user's chosen start page, the unread page unless set otherwise
+pub async fn home_page(
+    auth_user: PageAuthUser,
+    State(state): State<AppState>,
+    flash: Flash,
+) -> Response {
+    let user_id = auth_user.user.id;
+    let home = state
+        .db
+        .user(move |c| user_settings::get_home_settings(c, user_id).unwrap_or_default())
+        .await
+        .unwrap_or_default();
+
+    match home.start_path() {
+        Some(path) => Redirect::to(&path).into_response(),
+        None => unread_page(auth_user, State(state), flash)
+            .await
+            .into_response(),
+    }
+}
+
+#[derive(Template)]
+#[template(path = "stream.html")]
+pub struct StreamTemplate {
+    pub username: String,
+    pub is_admin: bool,
+    pub is_masquerading: bool,
+    pub flash_messages: Vec<FlashMessage>,
+    pub entries_per_page: i64,
+    pub theme: Option<String>,
+    pub stream_slug: String,
+    pub stream_title: String,
+}
+
+impl IntoResponse for StreamTemplate {
+    fn into_response(self) -> Response {
+        match self.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+}
+
+async fn stream_page(
+    auth_user: PageAuthUser,
+    state: AppState,
+    flash: Flash,
+    stream: entry::VirtualStream,
+) -> (Flash, StreamTemplate) {
+    let is_masquerading = auth_user.session.is_masquerading();
+    let is_admin = if is_masquerading {
+        auth_user.session.original_user_id.is_some()
+    } else {
+        auth_user.user.is_admin()
+    };
+
+    let user_id = auth_user.user.id;
+    let (entries_per_page, theme) = state
+        .db
+        .user(move |c| {
+            let epp = user_settings::get_entries_per_page(c, user_id)
+                .unwrap_or(user_settings::DEFAULT_ENTRIES_PER_PAGE);
+            let theme = user_settings::get_theme(c, user_id).unwrap_or(None);
+            (epp, theme)
+        })
+        .await
+        .unwrap_or((user_settings::DEFAULT_ENTRIES_PER_PAGE, None));
+
+    (
+        flash.clone(),
+        StreamTemplate {
+            username: auth_user.user.username,
+            is_admin,
+            is_masquerading,
+            flash_messages: flash.messages,
+            entries_per_page,
+            theme,
+            stream_slug: stream.slug().to_string(),
+            stream_title: stream.title().to_string(),
+        },
+    )
+}
+
+pub async fn today_page(
+    auth_user: PageAuthUser,
+    State(state): State<AppState>,
+    flash: Flash,
+) -> (Flash, StreamTemplate) {
+    stream_page(auth_user, state, flash, entry::VirtualStream::Today).await
+}
+
+pub async fn last_24h_page(
+    auth_user: PageAuthUser,
+    State(state): State<AppState>,
+    flash: Flash,
+) -> (Flash, StreamTemplate) {
+    stream_page(auth_user, state, flash, entry::VirtualStream::Last24Hours).await
+}
+
 #[derive(Template)]
 #[template(path = "admin.html")]
 pub struct AdminTemplate {
@@ -219,9 +345,32 @@ pub struct UserSettingsTemplate {
     pub flash_messages: Vec<FlashMessage>,
     pub linkding_configured: bool,
     pub linkding_api_url: String,
+    pub linkding_send_highlights: bool,
+    /// Space-separated, as typed in the form
+    pub linkding_default_tags: String,
+    /// One `category: Name = tags` or `feed: Title = tags` per line
+    pub linkding_tag_map: String,
+    pub linkding_unread: bool,
+    pub linkding_shared: bool,
+    pub auto_save_starred: bool,
     pub kagi_configured: bool,
     pub kagi_language: String,
+    pub kagi_style: &'static str,
+    pub kagi_length: &'static str,
+    pub openai_configured: bool,
+    pub openai_base_url: String,
+    pub openai_model: String,
     pub theme: Option<String>,
+    pub locale: Option<String>,
+    pub timezone: Option<String>,
+    pub mark_read_on_scroll: bool,
+    pub scroll_dwell_ms: i64,
+    pub sanitize_policy: SanitizePolicy,
+    pub unread_on_update: bool,
+    pub start_page: &'static str,
+    pub start_category_id: Option<i64>,
+    pub unread_count_cap: Option<i64>,
+    pub categories: Vec<category::Category>,
 }
 
 impl IntoResponse for UserSettingsTemplate {
@@ -233,6 +382,19 @@ impl IntoResponse for UserSettingsTemplate {
     }
 }
 
+/// The Linkding tag mapping as edited in the settings form
+fn linkding_tag_map(config: &LinkdingConfig) -> String {
+    let categories = config
+        .category_tags
+        .iter()
+        .map(|(name, tags)| format!("category: {} = {}", name, tags.join(" ")));
+    let feeds = config
+        .feed_tags
+        .iter()
+        .map(|(name, tags)| format!("feed: {} = {}", name, tags.join(" ")));
+    categories.chain(feeds).collect::<Vec<_>>().join("\n")
+}
+
 pub async fn user_settings_page(
     auth_user: PageAuthUser,
     State(state): State<AppState>,
@@ -250,9 +412,21 @@ pub async fn user_settings_page(
         entries_per_page,
         linkding_configured,
         linkding_api_url,
+        linkding_send_highlights,
+        linkding_options,
+        auto_save_starred,
         kagi_configured,
         kagi_language,
+        kagi_options,
+        openai,
         theme,
+        locale,
+        tz,
+        scroll_read,
+        sanitize_policy,
+        revisions,
+        home,
+        categories,
     ) = state
         .db
         .user(move |c| {
@@ -265,20 +439,44 @@ pub async fn user_settings_page(
             let linkding = save_config.linkding.as_ref();
             let linkding_configured = linkding.map(|c| c.is_configured()).unwrap_or(false);
             let api_url = linkding.map(|c| c.api_url.clone()).unwrap_or_default();
+            let send_highlights = linkding.is_some_and(|c| c.send_highlights);
+            let linkding_options = linkding.cloned().unwrap_or_default();
+            let auto_save = user_settings::get_auto_save_starred(c, user_id).unwrap_or(false);
 
             let kagi = save_config.kagi.as_ref();
             let kagi_configured = kagi.map(|c| c.is_configured()).unwrap_or(false);
             let kagi_lang = kagi.and_then(|c| c.language.clone()).unwrap_or_default();
+            let kagi_options = kagi.map(|c| c.options()).unwrap_or_default();
+            let openai = save_config.openai.clone();
 
             let theme = user_settings::get_theme(c, user_id).unwrap_or(None);
+            let locale = user_settings::get_locale(c, user_id).unwrap_or(None);
+            let tz = user_settings::get_timezone(c, user_id).unwrap_or(None);
+            let scroll_read = user_settings::get_scroll_read(c, user_id).unwrap_or_default();
+            let policy = user_settings::get_sanitize_policy(c, user_id).unwrap_or_default();
+            let revisions = user_settings::get_revision_settings(c, user_id).unwrap_or_default();
+            let home = user_settings::get_home_settings(c, user_id).unwrap_or_default();
+            let categories = category::list_by_user(c, user_id).unwrap_or_default();
 
             (
                 epp,
                 linkding_configured,
                 api_url,
+                send_highlights,
+                linkding_options,
+                auto_save,
                 kagi_configured,
                 kagi_lang,
+                kagi_options,
+                openai,
                 theme,
+                locale,
+                tz,
+                scroll_read,
+                policy,
+                revisions,
+                home,
+                categories,
             )
         })
         .await
@@ -287,8 +485,20 @@ pub async fn user_settings_page(
             false,
             String::new(),
             false,
+            LinkdingConfig::default(),
+            false,
+            false,
             String::new(),
+            SummaryOptions::default(),
+            None,
+            None,
             None,
+            None,
+            user_settings::ScrollReadSettings::default(),
+            SanitizePolicy::default(),
+            user_settings::RevisionSettings::default(),
+            user_settings::HomeSettings::default(),
+            Vec::new(),
         ));
 
     (
@@ -296,9 +506,7 @@ pub async fn user_settings_page(
         UserSettingsTemplate {
             username: auth_user.user.username,
             role: auth_user.user.role.as_str().to_string(),
-            created_at: auth_user
-                .user
-                .created_at
+            created_at: timezone::local(auth_user.user.created_at, tz)
                 .format("%Y-%m-%d %H:%M:%S")
                 .to_string(),
             entries_per_page,
@@ -307,9 +515,33 @@ pub async fn user_settings_page(
             flash_messages: flash.messages,
             linkding_configured,
             linkding_api_url,
+            linkding_send_highlights,
+            linkding_default_tags: linkding_options.default_tags.join(" "),
+            linkding_tag_map: linkding_tag_map(&linkding_options),
+            linkding_unread: linkding_options.unread,
+            linkding_shared: linkding_options.shared,
+            auto_save_starred,
             kagi_configured,
             kagi_language,
+            kagi_style: kagi_options.style.as_str(),
+            kagi_length: kagi_options.length.as_str(),
+            openai_configured: openai.as_ref().is_some_and(|c| c.is_configured()),
+            openai_base_url: openai
+                .as_ref()
+                .map(|c| c.base_url.clone())
+                .unwrap_or_default(),
+            openai_model: openai.map(|c| c.model).unwrap_or_default(),
             theme,
+            locale,
+            timezone: tz.map(|tz| tz.name().to_string()),
+            mark_read_on_scroll: scroll_read.enabled,
+            scroll_dwell_ms: scroll_read.dwell_ms,
+            sanitize_policy,
+            unread_on_update: revisions.unread_on_update,
+            start_page: home.start_page.as_str(),
+            start_category_id: home.start_category_id,
+            unread_count_cap: home.unread_count_cap,
+            categories,
         },
     )
 }
@@ -423,6 +655,8 @@ pub struct EntriesTemplate {
     pub flash_messages: Vec<FlashMessage>,
     pub entries_per_page: i64,
     pub theme: Option<String>,
+    pub mark_read_on_scroll: bool,
+    pub scroll_dwell_ms: i64,
 }
 
 impl IntoResponse for EntriesTemplate {
@@ -447,16 +681,21 @@ pub async fn entries_page(
     };
 
     let user_id = auth_user.user.id;
-    let (entries_per_page, theme) = state
+    let (entries_per_page, theme, scroll_read) = state
         .db
         .user(move |c| {
             let epp = user_settings::get_entries_per_page(c, user_id)
                 .unwrap_or(user_settings::DEFAULT_ENTRIES_PER_PAGE);
             let theme = user_settings::get_theme(c, user_id).unwrap_or(None);
-            (epp, theme)
+            let scroll_read = user_settings::get_scroll_read(c, user_id).unwrap_or_default();
+            (epp, theme, scroll_read)
         })
         .await
-        .unwrap_or((user_settings::DEFAULT_ENTRIES_PER_PAGE, None));
+        .unwrap_or((
+            user_settings::DEFAULT_ENTRIES_PER_PAGE,
+            None,
+            user_settings::ScrollReadSettings::default(),
+        ));
 
     (
         flash.clone(),
@@ -467,6 +706,8 @@ pub async fn entries_page(
             flash_messages: flash.messages,
             entries_per_page,
             theme,
+            mark_read_on_scroll: scroll_read.enabled,
+            scroll_dwell_ms: scroll_read.dwell_ms,
         },
     )
 }
@@ -481,6 +722,7 @@ pub struct EntryTemplate {
     pub flash_messages: Vec<FlashMessage>,
     pub has_save_services: bool,
     pub has_kagi_configured: bool,
+    pub discussions_enabled: bool,
     pub theme: Option<String>,
 }
 
@@ -515,11 +757,7 @@ pub async fn entry_page(
             let save_config =
                 user_settings::get_save_services_config(c, user_id).unwrap_or_default();
 
-            let kagi_configured = save_config
-                .kagi
-                .as_ref()
-                .map(|c| c.is_configured())
-                .unwrap_or(false);
+            let kagi_configured = allows(Outbound::Summary) && save_config.has_summarizer();
 
             let theme = user_settings::get_theme(c, user_id).unwrap_or(None);
 
@@ -538,6 +776,7 @@ pub async fn entry_page(
             flash_messages: flash.messages,
             has_save_services,
             has_kagi_configured,
+            discussions_enabled: allows(Outbound::Discussions),
             theme,
         },
     )
@@ -555,6 +794,8 @@ pub struct SettingsTemplate {
     pub user_agent_is_default: bool,
     pub signup_enabled: bool,
     pub multi_user_enabled: bool,
+    /// Set for admins when a newer release is out
+    pub update: Option<UpdateStatus>,
     pub theme: Option<String>,
 }
 
@@ -579,7 +820,9 @@ pub async fn settings_page(
         auth_user.user.is_admin()
     };
 
-    let user_agent_is_default = state.config.user_agent == DEFAULT_USER_AGENT;
+    let settings = state.settings.current();
+    let user_agent_is_default = settings.user_agent == DEFAULT_USER_AGENT;
+    let update = Some(state.updates.status()).filter(|s| is_admin && s.update_available());
 
     let user_id = auth_user.user.id;
     let theme = state
@@ -596,30 +839,29 @@ pub async fn settings_page(
             is_masquerading,
             flash_messages: flash.messages,
             git_version: crate::GIT_VERSION,
-            user_agent: state.config.user_agent.clone(),
+            user_agent: settings.user_agent.clone(),
             user_agent_is_default,
-            signup_enabled: state.config.signup_enabled,
+            signup_enabled: settings.signup_enabled,
             multi_user_enabled: state.config.multi_user_enabled,
+            update,
             theme,
         },
     )
 }
 
-// Archive entries pages (read/starred/summarized)
 #[derive(Template)]
-#[template(path = "entries_archive.html")]
-pub struct ArchiveEntriesTemplate {
+#[template(path = "stats.html")]
+pub struct StatsTemplate {
     pub username: String,
     pub is_admin: bool,
     pub is_masquerading: bool,
     pub flash_messages: Vec<FlashMessage>,
-    pub entries_per_page: i64,
-    pub page_mode: String,
-    pub page_title: String,
+    /// This year, for the year in review
+    pub year: i32,
     pub theme: Option<String>,
 }
 
-impl IntoResponse for ArchiveEntriesTemplate {
+impl IntoResponse for StatsTemplate {
     fn into_response(self) -> Response {
         match self.render() {
             Ok(html) => Html(html).into_response(),
@@ -628,11 +870,11 @@ impl IntoResponse for ArchiveEntriesTemplate {
     }
 }
 
-pub async fn read_entries_page(
+pub async fn stats_page(
     auth_user: PageAuthUser,
     State(state): State<AppState>,
     flash: Flash,
-) -> (Flash, ArchiveEntriesTemplate) {
+) -> (Flash, StatsTemplate) {
     let is_masquerading = auth_user.session.is_masquerading();
     let is_admin = if is_masquerading {
         auth_user.session.original_user_id.is_some()
@@ -641,36 +883,91 @@ pub async fn read_entries_page(
     };
 
     let user_id = auth_user.user.id;
-    let (entries_per_page, theme) = state
+    let (today, theme) = state
         .db
         .user(move |c| {
-            let epp = user_settings::get_entries_per_page(c, user_id)
-                .unwrap_or(user_settings::DEFAULT_ENTRIES_PER_PAGE);
+            let today =
+                reading_stats::user_today(c, user_id).unwrap_or_else(|_| Utc::now().date_naive());
             let theme = user_settings::get_theme(c, user_id).unwrap_or(None);
-            (epp, theme)
+            (today, theme)
         })
         .await
-        .unwrap_or((user_settings::DEFAULT_ENTRIES_PER_PAGE, None));
+        .unwrap_or_else(|_| (Utc::now().date_naive(), None));
 
     (
         flash.clone(),
-        ArchiveEntriesTemplate {
+        StatsTemplate {
             username: auth_user.user.username,
             is_admin,
             is_masquerading,
             flash_messages: flash.messages,
-            entries_per_page,
-            page_mode: "read".to_string(),
-            page_title: "Read Entries".to_string(),
+            year: today.year(),
             theme,
         },
     )
 }
 
+// Archive entries pages (read/starred/summarized/read later)
+#[derive(Template)]
+#[template(path = "entries_archive.html")]
+pub struct ArchiveEntriesTemplate {
+    pub username: String,
+    pub is_admin: bool,
+    pub is_masquerading: bool,
+    pub flash_messages: Vec<FlashMessage>,
+    pub entries_per_page: i64,
+    pub page_mode: String,
+    pub page_title: String,
+    pub theme: Option<String>,
+}
+
+impl IntoResponse for ArchiveEntriesTemplate {
+    fn into_response(self) -> Response {
+        match self.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+}
+
+pub async fn read_entries_page(
+    auth_user: PageAuthUser,
+    state: State<AppState>,
+    flash: Flash,
+) -> (Flash, ArchiveEntriesTemplate) {
+    archive_entries_page(auth_user, state, flash, entry::EntryState::Read).await
+}
+
 pub async fn starred_entries_page(
+    auth_user: PageAuthUser,
+    state: State<AppState>,
+    flash: Flash,
+) -> (Flash, ArchiveEntriesTemplate) {
+    archive_entries_page(auth_user, state, flash, entry::EntryState::Starred).await
+}
+
+pub async fn summarized_entries_page(
+    auth_user: PageAuthUser,
+    state: State<AppState>,
+    flash: Flash,
+) -> (Flash, ArchiveEntriesTemplate) {
+    archive_entries_page(auth_user, state, flash, entry::EntryState::Summarized).await
+}
+
+pub async fn read_later_entries_page(
+    auth_user: PageAuthUser,
+    state: State<AppState>,
+    flash: Flash,
+) -> (Flash, ArchiveEntriesTemplate) {
+    archive_entries_page(auth_user, state, flash, entry::EntryState::ReadLater).await
+}
+
+/// One archive view; the page lists it with `GET /api/entries?state=`
+async fn archive_entries_page(
     auth_user: PageAuthUser,
     State(state): State<AppState>,
     flash: Flash,
+    view: entry::EntryState,
 ) -> (Flash, ArchiveEntriesTemplate) {
     let is_masquerading = auth_user.session.is_masquerading();
     let is_admin = if is_masquerading {
@@ -699,18 +996,44 @@ pub async fn starred_entries_page(
             is_masquerading,
             flash_messages: flash.messages,
             entries_per_page,
-            page_mode: "starred".to_string(),
-            page_title: "Starred Entries".to_string(),
+            page_mode: view.as_str().to_string(),
+            page_title: view.title().to_string(),
             theme,
         },
     )
 }
 
-pub async fn summarized_entries_page(
+// Category entries page
+#[derive(Template)]
+#[template(path = "category_entries.html")]
+pub struct CategoryEntriesTemplate {
+    pub username: String,
+    pub is_admin: bool,
+    pub is_masquerading: bool,
+    pub flash_messages: Vec<FlashMessage>,
+    pub entries_per_page: i64,
+    pub category_id: i64,
+    pub category_name: String,
+    pub category_color: Option<String>,
+    pub category_icon: Option<String>,
+    pub theme: Option<String>,
+}
+
+impl IntoResponse for CategoryEntriesTemplate {
+    fn into_response(self) -> Response {
+        match self.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+}
+
+pub async fn category_entries_page(
     auth_user: PageAuthUser,
     State(state): State<AppState>,
+    Path(id): Path<i64>,
     flash: Flash,
-) -> (Flash, ArchiveEntriesTemplate) {
+) -> Result<(Flash, CategoryEntriesTemplate), AppError> {
     let is_masquerading = auth_user.session.is_masquerading();
     let is_admin = if is_masquerading {
         auth_user.session.original_user_id.is_some()
@@ -719,47 +1042,50 @@ pub async fn summarized_entries_page(
     };
 
     let user_id = auth_user.user.id;
-    let (entries_per_page, theme) = state
+    let (entries_per_page, cat, theme) = state
         .db
         .user(move |c| {
+            let cat =
+                category::find_by_id_and_user(c, id, user_id)?.ok_or(AppError::CategoryNotFound)?;
             let epp = user_settings::get_entries_per_page(c, user_id)
                 .unwrap_or(user_settings::DEFAULT_ENTRIES_PER_PAGE);
             let theme = user_settings::get_theme(c, user_id).unwrap_or(None);
-            (epp, theme)
+            Ok::<_, AppError>((epp, cat, theme))
         })
-        .await
-        .unwrap_or((user_settings::DEFAULT_ENTRIES_PER_PAGE, None));
+        .await??;
 
-    (
+    Ok((
         flash.clone(),
-        ArchiveEntriesTemplate {
+        CategoryEntriesTemplate {
             username: auth_user.user.username,
             is_admin,
             is_masquerading,
             flash_messages: flash.messages,
             entries_per_page,
-            page_mode: "summarized".to_string(),
-            page_title: "Summarized Entries".to_string(),
+            category_id: id,
+            category_icon: cat.icon_symbol(),
+            category_name: cat.name,
+            category_color: cat.color,
             theme,
         },
-    )
+    ))
 }
 
-// Category entries page
+// Category digest page
 #[derive(Template)]
-#[template(path = "category_entries.html")]
-pub struct CategoryEntriesTemplate {
+#[template(path = "category_digest.html")]
+pub struct CategoryDigestTemplate {
     pub username: String,
     pub is_admin: bool,
     pub is_masquerading: bool,
     pub flash_messages: Vec<FlashMessage>,
-    pub entries_per_page: i64,
     pub category_id: i64,
     pub category_name: String,
+    pub has_kagi_configured: bool,
     pub theme: Option<String>,
 }
 
-impl IntoResponse for CategoryEntriesTemplate {
+impl IntoResponse for CategoryDigestTemplate {
     fn into_response(self) -> Response {
         match self.render() {
             Ok(html) => Html(html).into_response(),
@@ -768,12 +1094,12 @@ impl IntoResponse for CategoryEntriesTemplate {
     }
 }
 
-pub async fn category_entries_page(
+pub async fn category_digest_page(
     auth_user: PageAuthUser,
     State(state): State<AppState>,
     Path(id): Path<i64>,
     flash: Flash,
-) -> Result<(Flash, CategoryEntriesTemplate), AppError> {
+) -> Result<(Flash, CategoryDigestTemplate), AppError> {
     let is_masquerading = auth_user.session.is_masquerading();
     let is_admin = if is_masquerading {
         auth_user.session.original_user_id.is_some()
@@ -782,28 +1108,32 @@ pub async fn category_entries_page(
     };
 
     let user_id = auth_user.user.id;
-    let (entries_per_page, category_name, theme) = state
+    let (category_name, has_kagi_configured, theme) = state
         .db
         .user(move |c| {
             let cat =
                 category::find_by_id_and_user(c, id, user_id)?.ok_or(AppError::CategoryNotFound)?;
-            let epp = user_settings::get_entries_per_page(c, user_id)
-                .unwrap_or(user_settings::DEFAULT_ENTRIES_PER_PAGE);
+            let kagi_configured = allows(Outbound::Summary)
+                && user_settings::get_save_services_config(c, user_id)
+                    .unwrap_or_default()
+                    .kagi
+                    .map(|k| k.is_configured())
+                    .unwrap_or(false);
             let theme = user_settings::get_theme(c, user_id).unwrap_or(None);
-            Ok::<_, AppError>((epp, cat.name, theme))
+            Ok::<_, AppError>((cat.name, kagi_configured, theme))
         })
         .await??;
 
     Ok((
         flash.clone(),
-        CategoryEntriesTemplate {
+        CategoryDigestTemplate {
             username: auth_user.user.username,
             is_admin,
             is_masquerading,
             flash_messages: flash.messages,
-            entries_per_page,
             category_id: id,
             category_name,
+            has_kagi_configured,
             theme,
         },
     ))
@@ -1,16 +1,18 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     Json,
 };
-use axum_extra::extract::cookie::{Cookie, CookieJar};
+use axum_extra::extract::cookie::CookieJar;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use serde::{Deserialize, Serialize};
-use time::Duration;
 use uuid::Uuid;
 use webauthn_rs::prelude::*;
+use webauthn_rs_proto::ResidentKeyRequirement;
 
 use crate::error::{AppError, AppResult};
-use crate::middleware::{AuthUser, SESSION_COOKIE_NAME};
+use crate::handlers::auth::{default_remember, user_agent};
+use crate::middleware::{session_cookie, AuthUser};
 use crate::models::{passkey, session, user, webauthn_challenge};
 use crate::AppState;
 
@@ -39,11 +41,17 @@ pub async fn start_registration(
         .collect();
 
     let user_uuid = Uuid::new_v4();
-    let (ccr, reg_state) = state
+    let (mut ccr, reg_state) = state
         .webauthn
         .start_passkey_registration(user_uuid, &username, &username, Some(exclude_credentials))
         .map_err(|e| AppError::PasskeyRegistrationFailed(e.to_string()))?;
 
+    // Ask for a discoverable credential so the passkey can be offered in the
+    // login form's autofill without typing a username first
+    if let Some(selection) = ccr.public_key.authenticator_selection.as_mut() {
+        selection.resident_key = Some(ResidentKeyRequirement::Preferred);
+    }
+
     // Serialize and store the registration state
     let state_json =
         serde_json::to_string(&reg_state).map_err(|e| AppError::Internal(e.to_string()))?;
@@ -205,9 +213,56 @@ pub async fn start_authentication(
     Ok(Json(StartAuthenticationResponse { options: rcr }))
 }
 
+/// Challenge for conditional mediation. The login page requests it on load
+/// so the browser can list passkeys in the username field's autofill; the
+/// user is resolved from the credential in `finish_authentication`.
+pub async fn authentication_options(
+    State(state): State<AppState>,
+) -> AppResult<Json<StartAuthenticationResponse>> {
+    let (rcr, auth_state) = state
+        .webauthn
+        .start_discoverable_authentication()
+        .map_err(|e| AppError::PasskeyAuthenticationFailed(e.to_string()))?;
+
+    let state_json =
+        serde_json::to_string(&auth_state).map_err(|e| AppError::Internal(e.to_string()))?;
+    let challenge_bytes: Vec<u8> = rcr.public_key.challenge.as_ref().to_vec();
+
+    state
+        .db
+        .user(move |conn| {
+            webauthn_challenge::create_challenge(
+                conn,
+                &challenge_bytes,
+                None,
+                webauthn_challenge::ChallengeType::DiscoverableAuthentication,
+                &state_json,
+            )
+        })
+        .await??;
+
+    Ok(Json(StartAuthenticationResponse { options: rcr }))
+}
+
+#[derive(Deserialize)]
+struct ClientData {
+    challenge: String,
+}
+
+/// The challenge the browser signed, read from the credential's client data
+fn client_data_challenge(credential: &PublicKeyCredential) -> Option<Vec<u8>> {
+    let client_data: ClientData =
+        serde_json::from_slice(credential.response.client_data_json.as_ref()).ok()?;
+    URL_SAFE_NO_PAD
+        .decode(client_data.challenge.trim_end_matches('='))
+        .ok()
+}
+
 #[derive(Debug, Deserialize)]
 pub struct FinishAuthenticationRequest {
     pub credential: PublicKeyCredential,
+    #[serde(default = "default_remember")]
+    pub remember: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -219,25 +274,20 @@ pub struct FinishAuthenticationResponse {
 pub async fn finish_authentication(
     State(state): State<AppState>,
     jar: CookieJar,
+    headers: HeaderMap,
     Json(req): Json<FinishAuthenticationRequest>,
 ) -> AppResult<(CookieJar, Json<FinishAuthenticationResponse>)> {
-    // Find and consume the challenge
+    // Find and consume the challenge this credential was created for, so
+    // concurrent sign-ins from several devices do not take each other's
+    let challenge_bytes =
+        client_data_challenge(&req.credential).ok_or(AppError::ChallengeNotFound)?;
     let challenge = state
         .db
-        .user(|conn| {
-            webauthn_challenge::find_and_delete_challenge(
-                conn,
-                None,
-                webauthn_challenge::ChallengeType::Authentication,
-            )
-        })
+        .user(move |conn| webauthn_challenge::find_and_delete_by_challenge(conn, &challenge_bytes))
         .await??;
 
-    // Deserialize the auth state
-    let auth_state: PasskeyAuthentication = serde_json::from_str(&challenge.state_data)
-        .map_err(|e| AppError::Internal(e.to_string()))?;
-
-    // Find the passkey by credential ID (use raw_id which contains raw bytes)
+    // Find the passkey, and with it the user, by credential ID (use raw_id
+    // which contains raw bytes)
     let credential_id: Vec<u8> = req.credential.raw_id.as_ref().to_vec();
     let (stored_passkey, db_user) = state
         .db
@@ -260,11 +310,30 @@ pub async fn finish_authentication(
     let mut passkey_data: Passkey = serde_json::from_slice(&stored_passkey.public_key)
         .map_err(|e| AppError::Internal(e.to_string()))?;
 
-    // Complete authentication
-    let auth_result = state
-        .webauthn
-        .finish_passkey_authentication(&req.credential, &auth_state)
-        .map_err(|e| AppError::PasskeyAuthenticationFailed(e.to_string()))?;
+    // Complete authentication against the flow that issued the challenge
+    let auth_result = match challenge.challenge_type {
+        webauthn_challenge::ChallengeType::Authentication => {
+            let auth_state: PasskeyAuthentication = serde_json::from_str(&challenge.state_data)
+                .map_err(|e| AppError::Internal(e.to_string()))?;
+            state
+                .webauthn
+                .finish_passkey_authentication(&req.credential, &auth_state)
+        }
+        webauthn_challenge::ChallengeType::DiscoverableAuthentication => {
+            let auth_state: DiscoverableAuthentication =
+                serde_json::from_str(&challenge.state_data)
+                    .map_err(|e| AppError::Internal(e.to_string()))?;
+            state.webauthn.finish_discoverable_authentication(
+                &req.credential,
+                auth_state,
+                &[DiscoverableKey::from(&passkey_data)],
+            )
+        }
+        webauthn_challenge::ChallengeType::Registration => {
+            return Err(AppError::ChallengeNotFound);
+        }
+    }
+    .map_err(|e| AppError::PasskeyAuthenticationFailed(e.to_string()))?;
 
     // Update the counter
     passkey_data.update_credential(&auth_result);
@@ -272,21 +341,25 @@ pub async fn finish_authentication(
     let counter = auth_result.counter() as i64;
     let passkey_user_id = stored_passkey.user_id;
 
+    let persistent = req.remember;
+    let ttl = state.config.session_ttl(persistent);
+    let user_agent = user_agent(&headers);
     let new_session = state
         .db
         .user(move |conn| {
             passkey::update_counter(conn, passkey_id, counter)?;
-            let new_session = session::create_session(conn, passkey_user_id)?;
+            let new_session = session::create_session(
+                conn,
+                passkey_user_id,
+                ttl,
+                persistent,
+                user_agent.as_deref(),
+            )?;
             Ok::<_, AppError>(new_session)
         })
         .await??;
 
-    let cookie = Cookie::build((SESSION_COOKIE_NAME, new_session.session_token))
-        .path("/")
-        .http_only(true)
-        .same_site(axum_extra::extract::cookie::SameSite::Lax)
-        .max_age(Duration::days(session::SESSION_EXPIRY_DAYS))
-        .build();
+    let cookie = session_cookie(new_session.session_token, persistent.then_some(ttl));
 
     Ok((
         jar.add(cookie),
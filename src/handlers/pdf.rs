@@ -0,0 +1,99 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::AuthUser;
+use crate::models::{entry, user_settings};
+use crate::services::pdf::{self, Article};
+use crate::services::sanitize::{sanitize_html_with_policy, VideoEmbeds};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct PdfQuery {
+    /// Body text size in points
+    #[serde(default = "default_font_size")]
+    pub font_size: f32,
+}
+
+fn default_font_size() -> f32 {
+    pdf::DEFAULT_FONT_SIZE
+}
+
+/// GET /api/entries/{id}/export.pdf - Download an entry as a PDF
+pub async fn entry_pdf(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+    Query(query): Query<PdfQuery>,
+) -> AppResult<Response> {
+    if !(pdf::MIN_FONT_SIZE..=pdf::MAX_FONT_SIZE).contains(&query.font_size) {
+        return Err(AppError::Validation(format!(
+            "font_size must be between {} and {}",
+            pdf::MIN_FONT_SIZE,
+            pdf::MAX_FONT_SIZE
+        )));
+    }
+    let user_id = auth_user.user.id;
+
+    let (entry, html, policy) = state
+        .db
+        .read(move |conn| {
+            let entry = entry::find_by_id_with_feed_and_user(conn, id, user_id)?
+                .ok_or(AppError::EntryNotFound)?;
+            let html = entry::find_article_html(conn, id)?;
+            let policy = user_settings::get_sanitize_policy(conn, user_id)?;
+            Ok::<_, AppError>((entry, html, policy))
+        })
+        .await??;
+
+    // Nothing is loaded while typesetting, so images and players only need
+    // to survive as far as their descriptions
+    let mut policy = policy;
+    policy.proxy_images = false;
+    policy.iframe_hosts.clear();
+    policy.video_embeds = VideoEmbeds::Remove;
+    let link = entry.entry.link;
+    let html = html
+        .map(|html| {
+            sanitize_html_with_policy(
+                &html,
+                &state.config.image_proxy_secret,
+                link.as_deref(),
+                &policy,
+                &state.blocklist.snapshot(),
+            )
+        })
+        .unwrap_or_default();
+
+    let article = Article {
+        title: entry
+            .entry
+            .title
+            .unwrap_or_else(|| "(Untitled)".to_string()),
+        feed_title: entry.feed_title,
+        author: entry.entry.author,
+        link,
+        published_at: entry.entry.published_at,
+        html,
+    };
+    let font_size = query.font_size;
+    let document = tokio::task::spawn_blocking(move || pdf::render(&article, font_size))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"rdrs-entry-{}.pdf\"", id),
+            ),
+        ],
+        document,
+    )
+        .into_response())
+}
@@ -1,4 +1,5 @@
 use axum::{
+    body::Bytes,
     extract::{Query, State},
     http::{header, StatusCode},
     response::{IntoResponse, Response},
@@ -11,7 +12,7 @@ use url::Url;
 use crate::{
     error::{AppError, AppResult},
     middleware::auth::AuthUser,
-    services::http::{send_with_retry, RetryConfig, DEFAULT_TIMEOUT},
+    services::http::{client_builder, send_with_retry, Outbound, RetryConfig, DEFAULT_TIMEOUT},
     services::verify_signature,
     AppState,
 };
@@ -21,6 +22,10 @@ const MAX_IMAGE_SIZE: u64 = 10 * 1024 * 1024; // 10MB
 #[derive(Deserialize)]
 pub struct ProxyQuery {
     url: String,
+    /// Expiration as a Unix timestamp
+    e: i64,
+    /// ID of the secret that signed the URL
+    k: String,
     s: String,
 }
 
@@ -35,23 +40,48 @@ pub async fn proxy_image(
         .map_err(|_| AppError::InvalidImageUrl)?;
     let url_str = String::from_utf8(url_bytes).map_err(|_| AppError::InvalidImageUrl)?;
 
-    // Verify signature
-    if !verify_signature(&url_str, &query.s, &state.config.image_proxy_secret) {
+    // Verify signature and expiry against the current and retired secrets
+    let now = chrono::Utc::now().timestamp();
+    let secrets = state.config.image_proxy_secrets();
+    if !verify_signature(&url_str, query.e, &query.k, &query.s, &secrets, now) {
         return Err(AppError::InvalidSignature);
     }
 
+    let (content_type, bytes) = fetch_image(&state, &url_str).await?;
+
+    // Return the image with appropriate headers
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, "public, max-age=86400".to_string()),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+/// Fetch an image the way the proxy does: public hosts only, none on the
+/// blocklist, and no more than `MAX_IMAGE_SIZE`. Returns its content type
+/// and bytes.
+pub(crate) async fn fetch_image(state: &AppState, url_str: &str) -> AppResult<(String, Bytes)> {
     // Parse and validate the URL
-    let url = Url::parse(&url_str).map_err(|_| AppError::InvalidImageUrl)?;
+    let url = Url::parse(url_str).map_err(|_| AppError::InvalidImageUrl)?;
     validate_url(&url)?;
 
+    // Including hosts blocked after a proxy URL was signed
+    if state.blocklist.snapshot().blocks(url.as_str()) {
+        return Err(AppError::Forbidden);
+    }
+
     // Fetch the image
-    let client = reqwest::Client::builder()
+    let client = client_builder(Outbound::Content)?
         .timeout(DEFAULT_TIMEOUT)
         .build()
         .map_err(|e| AppError::ImageFetchError(e.to_string()))?;
 
     let url_str = url.to_string();
-    let user_agent = state.config.user_agent.clone();
+    let user_agent = state.settings.current().user_agent.clone();
     let response = send_with_retry(&RetryConfig::default(), || {
         client.get(&url_str).header("User-Agent", &user_agent)
     })
@@ -94,16 +124,7 @@ pub async fn proxy_image(
         return Err(AppError::ImageTooLarge);
     }
 
-    // Return the image with appropriate headers
-    Ok((
-        StatusCode::OK,
-        [
-            (header::CONTENT_TYPE, content_type),
-            (header::CACHE_CONTROL, "public, max-age=86400".to_string()),
-        ],
-        bytes,
-    )
-        .into_response())
+    Ok((content_type, bytes))
 }
 
 fn validate_url(url: &Url) -> AppResult<()> {
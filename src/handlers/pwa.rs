@@ -0,0 +1,126 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+const MANIFEST: &str = r##"{
+  "name": "RDRS",
+  "short_name": "RDRS",
+  "description": "A self-hosted RSS reader",
+  "start_url": "/",
+  "scope": "/",
+  "display": "standalone",
+  "background_color": "#ffffff",
+  "theme_color": "#111111",
+  "icons": [
+    { "src": "/favicon.svg", "sizes": "any", "type": "image/svg+xml" },
+    { "src": "/apple-touch-icon.png", "sizes": "180x180", "type": "image/png" },
+    { "src": "/favicon-32x32.png", "sizes": "32x32", "type": "image/png" }
+  ]
+}
+"##;
+
+const SERVICE_WORKER: &str = r#"const SHELL_CACHE = 'rdrs-shell-v1';
+const BUNDLE_CACHE = 'rdrs-bundle-v1';
+const BUNDLE_URL = '/api/entries/offline-bundle';
+const SHELL_URLS = ['/', '/favicon.svg', '/manifest.webmanifest'];
+
+self.addEventListener('install', (event) => {
+  event.waitUntil(caches.open(SHELL_CACHE).then((cache) => cache.addAll(SHELL_URLS)));
+  self.skipWaiting();
+});
+
+self.addEventListener('activate', (event) => {
+  event.waitUntil(
+    caches.keys().then((keys) =>
+      Promise.all(
+        keys
+          .filter((key) => key !== SHELL_CACHE && key !== BUNDLE_CACHE)
+          .map((key) => caches.delete(key))
+      )
+    )
+  );
+  self.clients.claim();
+});
+
+async function refreshBundle() {
+  const response = await fetch(BUNDLE_URL, { credentials: 'same-origin' });
+  if (response.ok) {
+    const cache = await caches.open(BUNDLE_CACHE);
+    await cache.put(BUNDLE_URL, response.clone());
+  }
+  return response;
+}
+
+self.addEventListener('message', (event) => {
+  if (event.data === 'refresh-bundle') {
+    event.waitUntil(refreshBundle().catch(() => undefined));
+  }
+});
+
+self.addEventListener('push', (event) => {
+  const data = event.data ? event.data.json() : {};
+  event.waitUntil(
+    self.registration.showNotification(data.title || 'RDRS', {
+      body: data.body || '',
+      icon: '/apple-touch-icon.png',
+      data: { url: data.url || '/' },
+    })
+  );
+});
+
+self.addEventListener('notificationclick', (event) => {
+  event.notification.close();
+  event.waitUntil(self.clients.openWindow(event.notification.data.url));
+});
+
+self.addEventListener('fetch', (event) => {
+  const request = event.request;
+  if (request.method !== 'GET') {
+    return;
+  }
+
+  const url = new URL(request.url);
+  if (url.origin !== self.location.origin) {
+    return;
+  }
+
+  if (url.pathname === BUNDLE_URL) {
+    event.respondWith(refreshBundle().catch(() => caches.match(BUNDLE_URL)));
+    return;
+  }
+
+  if (request.mode === 'navigate') {
+    event.respondWith(
+      fetch(request)
+        .then((response) => {
+          const copy = response.clone();
+          caches.open(SHELL_CACHE).then((cache) => cache.put(request, copy));
+          return response;
+        })
+        .catch(() => caches.match(request).then((cached) => cached || caches.match('/')))
+    );
+  }
+});
+"#;
+
+pub async fn manifest() -> Response {
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/manifest+json")],
+        MANIFEST,
+    )
+        .into_response()
+}
+
+pub async fn service_worker() -> Response {
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/javascript"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        SERVICE_WORKER,
+    )
+        .into_response()
+}
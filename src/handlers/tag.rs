@@ -0,0 +1,110 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+
+use crate::auth::ownership;
+use crate::error::{AppError, AppResult};
+use crate::middleware::AuthUser;
+use crate::models::tag::{self, Tag, MAX_DESCRIPTION_CHARS, MAX_NAME_CHARS};
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTagRequest {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+pub async fn list_tags(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<Vec<Tag>>> {
+    let user_id = auth_user.user.id;
+
+    let tags = state
+        .db
+        .read(move |conn| tag::list_by_user(conn, user_id))
+        .await??;
+
+    Ok(Json(tags))
+}
+
+pub async fn create_tag(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<CreateTagRequest>,
+) -> AppResult<(StatusCode, Json<Tag>)> {
+    let name = req.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::Validation("Tag name is required".to_string()));
+    }
+    // Names are sent back as a comma-separated list when tagging
+    if name.contains(',') {
+        return Err(AppError::Validation(
+            "Tag name cannot contain commas".to_string(),
+        ));
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(AppError::Validation(format!(
+            "Tag name must be {} characters or less",
+            MAX_NAME_CHARS
+        )));
+    }
+    let description = req
+        .description
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty());
+    if description
+        .as_ref()
+        .is_some_and(|d| d.chars().count() > MAX_DESCRIPTION_CHARS)
+    {
+        return Err(AppError::Validation(format!(
+            "Description must be {} characters or less",
+            MAX_DESCRIPTION_CHARS
+        )));
+    }
+
+    let user_id = auth_user.user.id;
+    let created = state
+        .db
+        .user(move |conn| tag::create_tag(conn, user_id, &name, description.as_deref()))
+        .await??;
+
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+pub async fn delete_tag(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<StatusCode> {
+    let user_id = auth_user.user.id;
+
+    state
+        .db
+        .user(move |conn| tag::delete_tag(conn, id, user_id))
+        .await??;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/entries/{id}/tags - Tags put on an entry
+pub async fn list_entry_tags(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<Json<Vec<Tag>>> {
+    let user_id = auth_user.user.id;
+
+    let tags = state
+        .db
+        .read(move |conn| {
+            ownership::assert_entry_owned(conn, user_id, id)?;
+            tag::list_for_entry(conn, id)
+        })
+        .await??;
+
+    Ok(Json(tags))
+}
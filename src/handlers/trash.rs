@@ -0,0 +1,137 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+use crate::handlers::category::CategoryResponse;
+use crate::handlers::feed::FeedResponse;
+use crate::middleware::AuthUser;
+use crate::models::{category, feed, image};
+use crate::services::trash;
+use crate::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct TrashedFeed {
+    pub id: i64,
+    pub category_id: i64,
+    pub url: String,
+    pub title: Option<String>,
+    pub deleted_at: String,
+    pub purge_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrashedCategory {
+    pub id: i64,
+    pub name: String,
+    pub deleted_at: String,
+    pub purge_at: String,
+    /// Feeds deleted together with the category, restored with it
+    pub feeds: Vec<TrashedFeed>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrashResponse {
+    pub categories: Vec<TrashedCategory>,
+    /// Feeds deleted on their own
+    pub feeds: Vec<TrashedFeed>,
+}
+
+fn trashed_times(deleted_at: DateTime<Utc>, retention_days: i64) -> (String, String) {
+    let purge_at = trash::purge_at(deleted_at, retention_days);
+    (deleted_at.to_rfc3339(), purge_at.to_rfc3339())
+}
+
+pub async fn list_trash(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<TrashResponse>> {
+    let user_id = auth_user.user.id;
+    let (categories, feeds) = state
+        .db
+        .user(move |conn| {
+            let categories = category::list_trashed_by_user(conn, user_id)?;
+            let feeds = feed::list_trashed_by_user(conn, user_id)?;
+            Ok::<_, AppError>((categories, feeds))
+        })
+        .await??;
+
+    let retention_days = state.settings.current().trash_retention_days;
+    let mut response = TrashResponse {
+        categories: categories
+            .into_iter()
+            .filter_map(|c| {
+                let (deleted_at, purge_at) = trashed_times(c.deleted_at?, retention_days);
+                Some(TrashedCategory {
+                    id: c.id,
+                    name: c.name,
+                    deleted_at,
+                    purge_at,
+                    feeds: Vec::new(),
+                })
+            })
+            .collect(),
+        feeds: Vec::new(),
+    };
+
+    for f in feeds {
+        let Some(feed_deleted_at) = f.deleted_at else {
+            continue;
+        };
+        let (deleted_at, purge_at) = trashed_times(feed_deleted_at, retention_days);
+        let trashed = TrashedFeed {
+            id: f.id,
+            category_id: f.category_id,
+            url: f.url,
+            title: f.title,
+            deleted_at,
+            purge_at,
+        };
+        // Feeds deleted together with their category share its timestamp
+        let with_category = response
+            .categories
+            .iter_mut()
+            .find(|c| c.id == trashed.category_id && c.deleted_at == trashed.deleted_at);
+        match with_category {
+            Some(c) => c.feeds.push(trashed),
+            None => response.feeds.push(trashed),
+        }
+    }
+
+    Ok(Json(response))
+}
+
+pub async fn restore_category(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<Json<CategoryResponse>> {
+    let user_id = auth_user.user.id;
+    let cat = state
+        .db
+        .user(move |conn| category::restore_category(conn, id, user_id))
+        .await??;
+
+    Ok(Json(cat.into()))
+}
+
+pub async fn restore_feed(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<Json<FeedResponse>> {
+    let user_id = auth_user.user.id;
+    let (restored, has_icon) = state
+        .db
+        .user(move |conn| {
+            let restored = feed::restore_feed(conn, id, user_id)?;
+            let has_icon = image::exists(conn, image::ENTITY_FEED, restored.id)?;
+            Ok::<_, AppError>((restored, has_icon))
+        })
+        .await??;
+
+    Ok(Json(FeedResponse::from_feed(restored, has_icon)))
+}
@@ -0,0 +1,24 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use crate::error::AppResult;
+use crate::middleware::AuthUser;
+use crate::services::undo::{self, UndoResult};
+use crate::AppState;
+
+/// Revert a bulk action using the token it returned
+pub async fn undo_action(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(token): Path<String>,
+) -> AppResult<Json<UndoResult>> {
+    let user_id = auth_user.user.id;
+    let result = state
+        .db
+        .user(move |conn| undo::undo(conn, user_id, &token))
+        .await??;
+
+    Ok(Json(result))
+}
@@ -1,14 +1,32 @@
-use axum::{extract::State, http::StatusCode, Json};
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::extract::cookie::CookieJar;
+use chrono::{DateTime, Days, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::auth::{hash_password, verify_password};
 use crate::error::{AppError, AppResult};
-use crate::middleware::AuthUser;
+use crate::i18n::{self, Locale};
+use crate::middleware::{AuthUser, SESSION_COOKIE_NAME};
+use crate::models::api_token;
 use crate::models::session;
 use crate::models::user;
 use crate::models::user_settings;
-use crate::services::{KagiConfig, LinkdingConfig};
+use crate::models::{feed, image};
+use crate::services::backup;
+use crate::services::reading_stats::{self, ReadingStats};
+use crate::services::save::linkding::validate_tags;
+use crate::services::{
+    KagiConfig, LinkdingConfig, OpenAiConfig, SanitizePolicy, SummaryLength, SummaryStyle,
+};
+use crate::timezone;
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -48,6 +66,186 @@ pub async fn change_password(
     Ok(StatusCode::OK)
 }
 
+#[derive(Deserialize)]
+pub struct DeleteAccountRequest {
+    pub password: String,
+}
+
+/// Delete the signed-in user's account and everything it owns. Categories,
+/// feeds, entries, sessions, passkeys and summaries go through foreign key
+/// cascades; feed icons are not linked by a foreign key and are removed here.
+pub async fn delete_account(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    auth_user: AuthUser,
+    Json(req): Json<DeleteAccountRequest>,
+) -> AppResult<(CookieJar, StatusCode)> {
+    if auth_user.session.is_masquerading() {
+        return Err(AppError::Forbidden);
+    }
+
+    if !verify_password(&req.password, &auth_user.user.password_hash) {
+        return Err(AppError::InvalidCredentials);
+    }
+
+    let user_id = auth_user.user.id;
+    let is_admin = auth_user.user.is_admin();
+    state
+        .db
+        .user(move |conn| {
+            // Keep the instance administrable while other accounts exist
+            if is_admin && user::count_admins(conn)? == 1 && user::count(conn)? > 1 {
+                return Err(AppError::Validation(
+                    "Promote another user to admin before deleting the last admin account"
+                        .to_string(),
+                ));
+            }
+
+            let tx = conn.unchecked_transaction()?;
+            for f in feed::list_by_user(&tx, user_id)? {
+                image::delete_by_entity(&tx, image::ENTITY_FEED, f.id)?;
+            }
+            user::delete_user(&tx, user_id)?;
+            tx.commit()?;
+            Ok::<_, AppError>(())
+        })
+        .await??;
+
+    Ok((jar.remove(SESSION_COOKIE_NAME), StatusCode::NO_CONTENT))
+}
+
+/// Download everything stored for the signed-in user as a JSON archive
+pub async fn export_personal_data(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<impl IntoResponse> {
+    let body = backup::export_personal_data_body(state.db.clone(), auth_user.user.id).await?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/json"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"rdrs-personal-data.json\"",
+            ),
+        ],
+        body,
+    ))
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    pub id: i64,
+    pub device: String,
+    pub user_agent: Option<String>,
+    pub persistent: bool,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub current: bool,
+}
+
+/// Active sessions of the signed-in user, with the device each one was
+/// created from
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<Vec<SessionResponse>>> {
+    let user_id = auth_user.user.id;
+    let current_id = auth_user.session.id;
+    let sessions = state
+        .db
+        .user(move |conn| session::list_by_user(conn, user_id))
+        .await??;
+
+    let sessions = sessions
+        .into_iter()
+        .map(|s| SessionResponse {
+            id: s.id,
+            device: session::describe_device(s.user_agent.as_deref().unwrap_or_default()),
+            user_agent: s.user_agent,
+            persistent: s.persistent,
+            created_at: s.created_at,
+            expires_at: s.expires_at,
+            current: s.id == current_id,
+        })
+        .collect();
+
+    Ok(Json(sessions))
+}
+
+/// Sign out one session, for example on a lost device
+pub async fn delete_session(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<StatusCode> {
+    let user_id = auth_user.user.id;
+    let deleted = state
+        .db
+        .user(move |conn| session::delete_user_session(conn, user_id, id))
+        .await??;
+
+    if !deleted {
+        return Err(AppError::NotFound("Session not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+}
+
+/// Tokens the signed-in user created for the save bookmarklet
+pub async fn list_api_tokens(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<Vec<api_token::ApiToken>>> {
+    let user_id = auth_user.user.id;
+    let tokens = state
+        .db
+        .user(move |conn| api_token::list_by_user(conn, user_id))
+        .await??;
+    Ok(Json(tokens))
+}
+
+pub async fn create_api_token(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<CreateApiTokenRequest>,
+) -> AppResult<(StatusCode, Json<api_token::ApiToken>)> {
+    let name = req.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::Validation("Token name is required".to_string()));
+    }
+
+    let user_id = auth_user.user.id;
+    let token = state
+        .db
+        .user(move |conn| api_token::create_token(conn, user_id, &name))
+        .await??;
+    Ok((StatusCode::CREATED, Json(token)))
+}
+
+/// Revoke a token, for example one pasted somewhere it should not be
+pub async fn delete_api_token(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<i64>,
+) -> AppResult<StatusCode> {
+    let user_id = auth_user.user.id;
+    let deleted = state
+        .db
+        .user(move |conn| api_token::delete_user_token(conn, user_id, id))
+        .await??;
+
+    if !deleted {
+        return Err(AppError::NotFound("API token not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
 pub async fn get_current_user(auth_user: AuthUser) -> Json<crate::models::User> {
     Json(auth_user.user)
 }
@@ -84,22 +282,81 @@ pub async fn update_settings(
 pub struct UpdateLinkdingRequest {
     pub api_url: Option<String>,
     pub api_token: Option<String>,
+    pub send_highlights: Option<bool>,
+    pub default_tags: Option<Vec<String>>,
+    pub category_tags: Option<BTreeMap<String, Vec<String>>>,
+    pub feed_tags: Option<BTreeMap<String, Vec<String>>>,
+    pub unread: Option<bool>,
+    pub shared: Option<bool>,
 }
 
+/// Linkding settings as shown to the user, without the token
 #[derive(Debug, Serialize)]
-pub struct UpdateLinkdingResponse {
+pub struct LinkdingSettingsResponse {
     pub configured: bool,
     pub api_url: Option<String>,
+    pub send_highlights: bool,
+    pub default_tags: Vec<String>,
+    pub category_tags: BTreeMap<String, Vec<String>>,
+    pub feed_tags: BTreeMap<String, Vec<String>>,
+    pub unread: bool,
+    pub shared: bool,
+}
+
+impl From<Option<LinkdingConfig>> for LinkdingSettingsResponse {
+    fn from(config: Option<LinkdingConfig>) -> Self {
+        let configured = config.as_ref().is_some_and(|c| c.is_configured());
+        let api_url = config.as_ref().map(|c| c.api_url.clone());
+        let config = config.unwrap_or_default();
+        Self {
+            configured,
+            api_url,
+            send_highlights: config.send_highlights,
+            default_tags: config.default_tags,
+            category_tags: config.category_tags,
+            feed_tags: config.feed_tags,
+            unread: config.unread,
+            shared: config.shared,
+        }
+    }
+}
+
+/// Trim tag names and drop empty ones, then check the rest
+fn clean_tags(tags: Vec<String>) -> AppResult<Vec<String>> {
+    let tags: Vec<String> = tags
+        .into_iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    validate_tags(&tags)?;
+    Ok(tags)
+}
+
+/// Clean a tag mapping, dropping names without tags
+fn clean_tag_map(map: BTreeMap<String, Vec<String>>) -> AppResult<BTreeMap<String, Vec<String>>> {
+    let mut cleaned = BTreeMap::new();
+    for (name, tags) in map {
+        let name = name.trim().to_string();
+        let tags = clean_tags(tags)?;
+        if !name.is_empty() && !tags.is_empty() {
+            cleaned.insert(name, tags);
+        }
+    }
+    Ok(cleaned)
 }
 
 pub async fn update_linkding_settings(
     State(state): State<AppState>,
     auth_user: AuthUser,
     Json(req): Json<UpdateLinkdingRequest>,
-) -> AppResult<Json<UpdateLinkdingResponse>> {
+) -> AppResult<Json<LinkdingSettingsResponse>> {
     let user_id = auth_user.user.id;
 
-    let (configured, api_url) = state
+    let default_tags = req.default_tags.map(clean_tags).transpose()?;
+    let category_tags = req.category_tags.map(clean_tag_map).transpose()?;
+    let feed_tags = req.feed_tags.map(clean_tag_map).transpose()?;
+
+    let linkding = state
         .db
         .user(move |conn| {
             // Get current config
@@ -110,14 +367,17 @@ pub async fn update_linkding_settings(
             let api_token = req.api_token.filter(|s| !s.is_empty());
 
             if api_url.is_some() || api_token.is_some() {
-                let current = config.linkding.unwrap_or(LinkdingConfig {
-                    api_url: String::new(),
-                    api_token: String::new(),
-                });
+                let current = config.linkding.unwrap_or_default();
 
                 config.linkding = Some(LinkdingConfig {
                     api_url: api_url.unwrap_or(current.api_url),
                     api_token: api_token.unwrap_or(current.api_token),
+                    send_highlights: req.send_highlights.unwrap_or(current.send_highlights),
+                    default_tags: default_tags.unwrap_or(current.default_tags),
+                    category_tags: category_tags.unwrap_or(current.category_tags),
+                    feed_tags: feed_tags.unwrap_or(current.feed_tags),
+                    unread: req.unread.unwrap_or(current.unread),
+                    shared: req.shared.unwrap_or(current.shared),
                 });
             } else {
                 config.linkding = None;
@@ -125,54 +385,28 @@ pub async fn update_linkding_settings(
 
             user_settings::update_save_services(conn, user_id, &config)?;
 
-            let configured = config
-                .linkding
-                .as_ref()
-                .map(|c| c.is_configured())
-                .unwrap_or(false);
-            let url = config.linkding.map(|c| c.api_url);
-
-            Ok::<_, AppError>((configured, url))
+            Ok::<_, AppError>(config.linkding)
         })
         .await??;
 
-    Ok(Json(UpdateLinkdingResponse {
-        configured,
-        api_url,
-    }))
-}
-
-#[derive(Debug, Serialize)]
-pub struct GetLinkdingResponse {
-    pub configured: bool,
-    pub api_url: Option<String>,
+    Ok(Json(linkding.into()))
 }
 
 pub async fn get_linkding_settings(
     State(state): State<AppState>,
     auth_user: AuthUser,
-) -> AppResult<Json<GetLinkdingResponse>> {
+) -> AppResult<Json<LinkdingSettingsResponse>> {
     let user_id = auth_user.user.id;
 
-    let (configured, api_url) = state
+    let linkding = state
         .db
         .user(move |conn| {
             let config = user_settings::get_save_services_config(conn, user_id)?;
-
-            let configured = config
-                .linkding
-                .as_ref()
-                .map(|c| c.is_configured())
-                .unwrap_or(false);
-
-            Ok::<_, AppError>((configured, config.linkding.map(|c| c.api_url)))
+            Ok::<_, AppError>(config.linkding)
         })
         .await??;
 
-    Ok(Json(GetLinkdingResponse {
-        configured,
-        api_url,
-    }))
+    Ok(Json(linkding.into()))
 }
 
 fn extract_kagi_session_token(session_link: &str) -> Result<String, AppError> {
@@ -190,12 +424,16 @@ fn extract_kagi_session_token(session_link: &str) -> Result<String, AppError> {
 pub struct UpdateKagiRequest {
     pub session_link: Option<String>,
     pub language: Option<String>,
+    pub style: Option<SummaryStyle>,
+    pub length: Option<SummaryLength>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct UpdateKagiResponse {
     pub configured: bool,
     pub language: Option<String>,
+    pub style: SummaryStyle,
+    pub length: SummaryLength,
 }
 
 pub async fn update_kagi_settings(
@@ -209,17 +447,21 @@ pub async fn update_kagi_settings(
         None => None,
     };
     let language = req.language.filter(|s| !s.is_empty());
+    let (style, length) = (req.style, req.length);
 
     let user_id = auth_user.user.id;
-    let (configured, lang) = state
+    let (configured, lang, options) = state
         .db
         .user(move |conn| {
             let mut config = user_settings::get_save_services_config(conn, user_id)?;
 
-            if session_token.is_some() || has_language_field {
+            if session_token.is_some() || has_language_field || style.is_some() || length.is_some()
+            {
                 let current = config.kagi.unwrap_or(KagiConfig {
                     session_token: String::new(),
                     language: None,
+                    style: SummaryStyle::default(),
+                    length: SummaryLength::default(),
                 });
 
                 config.kagi = Some(KagiConfig {
@@ -229,8 +471,10 @@ pub async fn update_kagi_settings(
                     } else {
                         current.language
                     },
+                    style: style.unwrap_or(current.style),
+                    length: length.unwrap_or(current.length),
                 });
-            } else if session_token.is_none() && !has_language_field {
+            } else {
                 config.kagi = None;
             }
 
@@ -241,15 +485,22 @@ pub async fn update_kagi_settings(
                 .as_ref()
                 .map(|c| c.is_configured())
                 .unwrap_or(false);
+            let options = config
+                .kagi
+                .as_ref()
+                .map(|c| c.options())
+                .unwrap_or_default();
             let lang = config.kagi.and_then(|c| c.language);
 
-            Ok::<_, AppError>((configured, lang))
+            Ok::<_, AppError>((configured, lang, options))
         })
         .await??;
 
     Ok(Json(UpdateKagiResponse {
         configured,
         language: lang,
+        style: options.style,
+        length: options.length,
     }))
 }
 
@@ -257,6 +508,8 @@ pub async fn update_kagi_settings(
 pub struct GetKagiResponse {
     pub configured: bool,
     pub language: Option<String>,
+    pub style: SummaryStyle,
+    pub length: SummaryLength,
 }
 
 pub async fn get_kagi_settings(
@@ -265,7 +518,7 @@ pub async fn get_kagi_settings(
 ) -> AppResult<Json<GetKagiResponse>> {
     let user_id = auth_user.user.id;
 
-    let (configured, language) = state
+    let (configured, language, options) = state
         .db
         .user(move |conn| {
             let config = user_settings::get_save_services_config(conn, user_id)?;
@@ -275,17 +528,111 @@ pub async fn get_kagi_settings(
                 .as_ref()
                 .map(|c| c.is_configured())
                 .unwrap_or(false);
+            let options = config
+                .kagi
+                .as_ref()
+                .map(|c| c.options())
+                .unwrap_or_default();
 
-            Ok::<_, AppError>((configured, config.kagi.and_then(|c| c.language)))
+            Ok::<_, AppError>((configured, config.kagi.and_then(|c| c.language), options))
         })
         .await??;
 
     Ok(Json(GetKagiResponse {
         configured,
         language,
+        style: options.style,
+        length: options.length,
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateOpenAiRequest {
+    pub base_url: Option<String>,
+    pub api_key: Option<String>,
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OpenAiSettingsResponse {
+    pub configured: bool,
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+}
+
+impl OpenAiSettingsResponse {
+    /// The API key is write-only and never sent back
+    fn from_config(config: Option<OpenAiConfig>) -> Self {
+        OpenAiSettingsResponse {
+            configured: config.as_ref().is_some_and(|c| c.is_configured()),
+            base_url: config.as_ref().map(|c| c.base_url.clone()),
+            model: config.map(|c| c.model),
+        }
+    }
+}
+
+pub async fn update_openai_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<UpdateOpenAiRequest>,
+) -> AppResult<Json<OpenAiSettingsResponse>> {
+    let base_url = req.base_url.filter(|s| !s.trim().is_empty());
+    if let Some(url) = &base_url {
+        Url::parse(url.trim()).map_err(|_| AppError::Validation("Invalid base URL".to_string()))?;
+    }
+    let api_key = req.api_key.filter(|s| !s.is_empty());
+    let model = req.model.filter(|s| !s.trim().is_empty());
+
+    let user_id = auth_user.user.id;
+    let openai = state
+        .db
+        .user(move |conn| {
+            let mut config = user_settings::get_save_services_config(conn, user_id)?;
+
+            if base_url.is_some() || api_key.is_some() || model.is_some() {
+                let current = config.openai.unwrap_or(OpenAiConfig {
+                    base_url: String::new(),
+                    api_key: String::new(),
+                    model: String::new(),
+                });
+
+                config.openai = Some(OpenAiConfig {
+                    base_url: base_url
+                        .map(|u| u.trim().to_string())
+                        .unwrap_or(current.base_url),
+                    api_key: api_key.unwrap_or(current.api_key),
+                    model: model.map(|m| m.trim().to_string()).unwrap_or(current.model),
+                });
+            } else {
+                config.openai = None;
+            }
+
+            user_settings::update_save_services(conn, user_id, &config)?;
+
+            Ok::<_, AppError>(config.openai)
+        })
+        .await??;
+
+    Ok(Json(OpenAiSettingsResponse::from_config(openai)))
+}
+
+pub async fn get_openai_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<OpenAiSettingsResponse>> {
+    let user_id = auth_user.user.id;
+
+    let openai = state
+        .db
+        .user(move |conn| {
+            let config = user_settings::get_save_services_config(conn, user_id)?;
+            Ok::<_, AppError>(config.openai)
+        })
+        .await??;
+
+    Ok(Json(OpenAiSettingsResponse::from_config(openai)))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UpdateThemeRequest {
     pub theme: Option<String>, // "dark", "light", or null/missing for system
@@ -333,3 +680,353 @@ pub async fn update_theme(
 
     Ok(StatusCode::OK)
 }
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateLocaleRequest {
+    pub locale: Option<String>, // "en", "zh-TW", or null/missing to follow the browser
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetLocaleResponse {
+    pub locale: Option<String>,
+    /// Locale this request was served in
+    pub effective: &'static str,
+}
+
+pub async fn get_locale(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<GetLocaleResponse>> {
+    let user_id = auth_user.user.id;
+
+    let locale = state
+        .db
+        .user(move |conn| user_settings::get_locale(conn, user_id))
+        .await??;
+
+    Ok(Json(GetLocaleResponse {
+        locale,
+        effective: i18n::current().as_str(),
+    }))
+}
+
+pub async fn update_locale(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<UpdateLocaleRequest>,
+) -> AppResult<StatusCode> {
+    let user_id = auth_user.user.id;
+
+    // Validate locale value
+    if let Some(ref locale) = req.locale {
+        if Locale::parse(locale).is_none() {
+            return Err(AppError::Validation(
+                "Locale must be one of: en, zh-TW, or null".to_string(),
+            ));
+        }
+    }
+
+    state
+        .db
+        .user(move |conn| user_settings::update_locale(conn, user_id, req.locale.as_deref()))
+        .await??;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTimezoneRequest {
+    pub timezone: Option<String>, // IANA name such as "Asia/Taipei", or null/missing for UTC
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetTimezoneResponse {
+    pub timezone: Option<String>,
+}
+
+pub async fn get_timezone(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<GetTimezoneResponse>> {
+    let user_id = auth_user.user.id;
+
+    let tz = state
+        .db
+        .user(move |conn| user_settings::get_timezone(conn, user_id))
+        .await??;
+
+    Ok(Json(GetTimezoneResponse {
+        timezone: tz.map(|tz| tz.name().to_string()),
+    }))
+}
+
+pub async fn update_timezone(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<UpdateTimezoneRequest>,
+) -> AppResult<StatusCode> {
+    let user_id = auth_user.user.id;
+
+    let tz = match req.timezone.as_deref() {
+        Some(name) => Some(timezone::parse(name).ok_or_else(|| {
+            AppError::Validation(
+                "Time zone must be an IANA name such as Asia/Taipei, or null".to_string(),
+            )
+        })?),
+        None => None,
+    };
+
+    state
+        .db
+        .user(move |conn| user_settings::update_timezone(conn, user_id, tz))
+        .await??;
+
+    Ok(StatusCode::OK)
+}
+
+pub async fn get_scroll_read_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<user_settings::ScrollReadSettings>> {
+    let user_id = auth_user.user.id;
+
+    let settings = state
+        .db
+        .user(move |conn| user_settings::get_scroll_read(conn, user_id))
+        .await??;
+
+    Ok(Json(settings))
+}
+
+pub async fn update_scroll_read_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<user_settings::ScrollReadSettings>,
+) -> AppResult<Json<user_settings::ScrollReadSettings>> {
+    let user_id = auth_user.user.id;
+
+    state
+        .db
+        .user(move |conn| user_settings::update_scroll_read(conn, user_id, req))
+        .await??;
+
+    Ok(Json(req))
+}
+
+pub async fn get_revision_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<user_settings::RevisionSettings>> {
+    let user_id = auth_user.user.id;
+
+    let settings = state
+        .db
+        .user(move |conn| user_settings::get_revision_settings(conn, user_id))
+        .await??;
+
+    Ok(Json(settings))
+}
+
+pub async fn update_revision_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<user_settings::RevisionSettings>,
+) -> AppResult<Json<user_settings::RevisionSettings>> {
+    let user_id = auth_user.user.id;
+
+    state
+        .db
+        .user(move |conn| user_settings::update_revision_settings(conn, user_id, req))
+        .await??;
+
+    Ok(Json(req))
+}
+
+/// Whether new entries are tagged automatically
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AutoTagSettings {
+    pub enabled: bool,
+}
+
+pub async fn get_auto_tag_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<AutoTagSettings>> {
+    let user_id = auth_user.user.id;
+
+    let enabled = state
+        .db
+        .user(move |conn| user_settings::get_auto_tag(conn, user_id))
+        .await??;
+
+    Ok(Json(AutoTagSettings { enabled }))
+}
+
+pub async fn update_auto_tag_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<AutoTagSettings>,
+) -> AppResult<Json<AutoTagSettings>> {
+    let user_id = auth_user.user.id;
+
+    state
+        .db
+        .user(move |conn| user_settings::update_auto_tag(conn, user_id, req.enabled))
+        .await??;
+
+    Ok(Json(req))
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AutoSaveSettings {
+    pub enabled: bool,
+}
+
+pub async fn get_auto_save_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<AutoSaveSettings>> {
+    let user_id = auth_user.user.id;
+
+    let enabled = state
+        .db
+        .user(move |conn| user_settings::get_auto_save_starred(conn, user_id))
+        .await??;
+
+    Ok(Json(AutoSaveSettings { enabled }))
+}
+
+pub async fn update_auto_save_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<AutoSaveSettings>,
+) -> AppResult<Json<AutoSaveSettings>> {
+    let user_id = auth_user.user.id;
+
+    state
+        .db
+        .user(move |conn| user_settings::update_auto_save_starred(conn, user_id, req.enabled))
+        .await??;
+
+    Ok(Json(req))
+}
+
+pub async fn get_home_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<user_settings::HomeSettings>> {
+    let user_id = auth_user.user.id;
+
+    let settings = state
+        .db
+        .user(move |conn| user_settings::get_home_settings(conn, user_id))
+        .await??;
+
+    Ok(Json(settings))
+}
+
+pub async fn update_home_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<user_settings::HomeSettings>,
+) -> AppResult<Json<user_settings::HomeSettings>> {
+    let user_id = auth_user.user.id;
+
+    let settings = state
+        .db
+        .user(move |conn| {
+            user_settings::update_home_settings(conn, user_id, req)?;
+            user_settings::get_home_settings(conn, user_id)
+        })
+        .await??;
+
+    Ok(Json(settings))
+}
+
+pub async fn get_sanitize_policy(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<SanitizePolicy>> {
+    let user_id = auth_user.user.id;
+
+    let policy = state
+        .db
+        .user(move |conn| user_settings::get_sanitize_policy(conn, user_id))
+        .await??;
+
+    Ok(Json(policy))
+}
+
+pub async fn update_sanitize_policy(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<SanitizePolicy>,
+) -> AppResult<Json<SanitizePolicy>> {
+    let user_id = auth_user.user.id;
+
+    let policy = state
+        .db
+        .user(move |conn| user_settings::update_sanitize_policy(conn, user_id, req))
+        .await??;
+
+    Ok(Json(policy))
+}
+
+/// Longest period `GET /api/user/stats?days=` covers
+const MAX_STATS_DAYS: i64 = 366;
+
+#[derive(Debug, Deserialize)]
+pub struct ReadingStatsQuery {
+    /// The last this many days, up to today (default 30)
+    pub days: Option<i64>,
+    /// A whole calendar year instead, up to today if it is this year
+    pub year: Option<i32>,
+}
+
+impl ReadingStatsQuery {
+    /// First and last day asked for, given the user's today
+    fn range(&self, today: NaiveDate) -> AppResult<(NaiveDate, NaiveDate)> {
+        match (self.days, self.year) {
+            (Some(_), Some(_)) => Err(AppError::Validation(
+                "Choose either days or year".to_string(),
+            )),
+            (None, Some(year)) => {
+                let from = NaiveDate::from_ymd_opt(year, 1, 1)
+                    .filter(|from| *from <= today)
+                    .ok_or_else(|| AppError::Validation("Invalid year".to_string()))?;
+                let end = NaiveDate::from_ymd_opt(year, 12, 31).unwrap_or(today);
+                Ok((from, end.min(today)))
+            }
+            (days, None) => {
+                let days = days.unwrap_or(30);
+                if !(1..=MAX_STATS_DAYS).contains(&days) {
+                    return Err(AppError::Validation(format!(
+                        "Days must be between 1 and {}",
+                        MAX_STATS_DAYS
+                    )));
+                }
+                Ok((today - Days::new(days as u64 - 1), today))
+            }
+        }
+    }
+}
+
+/// GET /api/user/stats - Entries read, time spent, top feeds and streaks
+pub async fn get_reading_stats(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Query(query): Query<ReadingStatsQuery>,
+) -> AppResult<Json<ReadingStats>> {
+    let user_id = auth_user.user.id;
+
+    let stats = state
+        .db
+        .read(move |conn| {
+            let today = reading_stats::user_today(conn, user_id)?;
+            let (from, to) = query.range(today)?;
+            reading_stats::user_stats(conn, user_id, from, to)
+        })
+        .await??;
+
+    Ok(Json(stats))
+}
@@ -0,0 +1,248 @@
+//! Translations of the UI and API messages. Catalogs are gettext-style: the
+//! English text is the message ID, and a message missing from a catalog is
+//! shown in English. The locale of the request being handled is set by
+//! `middleware::locale::scope_locale` and read with `current()`, so templates
+//! and error responses do not need it passed around.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en")]
+    En,
+    #[serde(rename = "zh-TW")]
+    ZhTw,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::ZhTw];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::ZhTw => "zh-TW",
+        }
+    }
+
+    /// Name of the language in itself, for language pickers
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Locale::En => "English",
+            Locale::ZhTw => "繁體中文",
+        }
+    }
+
+    /// Parse a stored or submitted locale, which must match `as_str()`
+    pub fn parse(s: &str) -> Option<Self> {
+        Locale::ALL.into_iter().find(|l| l.as_str() == s)
+    }
+
+    /// Closest supported locale to a language tag such as `zh-Hant-TW`.
+    /// Every Chinese variant gets the Traditional Chinese catalog, the only
+    /// one there is.
+    fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split(['-', '_']).next()?.to_ascii_lowercase();
+        match primary.as_str() {
+            "en" => Some(Locale::En),
+            "zh" => Some(Locale::ZhTw),
+            _ => None,
+        }
+    }
+
+    /// Preferred supported locale of an `Accept-Language` header, honoring
+    /// quality values
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        let mut tags: Vec<(&str, f32)> = header
+            .split(',')
+            .filter_map(|part| {
+                let mut fields = part.split(';');
+                let tag = fields.next()?.trim();
+                let q = fields
+                    .find_map(|f| f.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                (!tag.is_empty() && q > 0.0).then_some((tag, q))
+            })
+            .collect();
+        // Stable, so equally weighted tags keep the order they were sent in
+        tags.sort_by(|a, b| b.1.total_cmp(&a.1));
+        tags.into_iter().find_map(|(tag, _)| Locale::from_tag(tag))
+    }
+
+    fn catalog(&self) -> Option<&'static HashMap<String, String>> {
+        match self {
+            Locale::En => None,
+            Locale::ZhTw => Some(&ZH_TW),
+        }
+    }
+}
+
+static ZH_TW: LazyLock<HashMap<String, String>> =
+    LazyLock::new(|| parse_catalog(include_str!("../locales/zh-TW.po")));
+
+/// Unquote a PO string literal, resolving its escapes
+fn unquote(s: &str) -> Option<String> {
+    let inner = s.trim().strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            other => out.push(other),
+        }
+    }
+    Some(out)
+}
+
+/// Parse the subset of the PO format the catalogs use: `msgid`/`msgstr`
+/// pairs, continuation lines and `#` comments. Untranslated (empty) entries
+/// and the header entry are skipped.
+fn parse_catalog(source: &str) -> HashMap<String, String> {
+    enum Field {
+        Id,
+        Str,
+    }
+
+    let mut catalog = HashMap::new();
+    let mut id = String::new();
+    let mut msg = String::new();
+    let mut field = None;
+    let mut flush = |id: &mut String, msg: &mut String| {
+        let (id, msg) = (std::mem::take(id), std::mem::take(msg));
+        if !id.is_empty() && !msg.is_empty() {
+            catalog.insert(id, msg);
+        }
+    };
+
+    for line in source.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("msgid ") {
+            flush(&mut id, &mut msg);
+            id = unquote(rest).unwrap_or_default();
+            field = Some(Field::Id);
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            msg = unquote(rest).unwrap_or_default();
+            field = Some(Field::Str);
+        } else if line.starts_with('"') {
+            let part = unquote(line).unwrap_or_default();
+            match field {
+                Some(Field::Id) => id.push_str(&part),
+                Some(Field::Str) => msg.push_str(&part),
+                None => {}
+            }
+        }
+    }
+    flush(&mut id, &mut msg);
+    catalog
+}
+
+tokio::task_local! {
+    static LOCALE: Locale;
+}
+
+/// Locale of the request being handled, English outside of one
+pub fn current() -> Locale {
+    LOCALE.try_with(|locale| *locale).unwrap_or_default()
+}
+
+/// Run `f` with `locale` as the current locale
+pub async fn scope<F: std::future::Future>(locale: Locale, f: F) -> F::Output {
+    LOCALE.scope(locale, f).await
+}
+
+/// Translate `msgid` into `locale`
+pub fn translate(locale: Locale, msgid: &str) -> &str {
+    locale
+        .catalog()
+        .and_then(|catalog| catalog.get(msgid))
+        .map_or(msgid, String::as_str)
+}
+
+/// Translate `msgid` into the current locale
+pub fn gettext(msgid: &str) -> &str {
+    translate(current(), msgid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!(Locale::parse("en"), Some(Locale::En));
+        assert_eq!(Locale::parse("zh-TW"), Some(Locale::ZhTw));
+        assert_eq!(Locale::parse("fr"), None);
+    }
+
+    #[test]
+    fn test_from_accept_language() {
+        let cases = [
+            ("zh-TW,zh;q=0.9,en;q=0.8", Some(Locale::ZhTw)),
+            ("en-US,en;q=0.9", Some(Locale::En)),
+            ("fr-FR, en;q=0.5, zh-Hant;q=0.7", Some(Locale::ZhTw)),
+            ("zh-CN;q=0, en", Some(Locale::En)),
+            ("zh_TW", Some(Locale::ZhTw)),
+            ("de, fr;q=0.8", None),
+            ("", None),
+        ];
+        for (header, expected) in cases {
+            assert_eq!(Locale::from_accept_language(header), expected, "{}", header);
+        }
+    }
+
+    #[test]
+    fn test_parse_catalog() {
+        let catalog = parse_catalog(
+            r#"
+            # Header entry
+            msgid ""
+            msgstr "Content-Type: text/plain; charset=UTF-8\n"
+
+            msgid "Feeds"
+            msgstr "訂閱"
+
+            msgid "Say \"hi\""
+            msgstr ""
+            "說"
+            "「嗨」"
+
+            msgid "Untranslated"
+            msgstr ""
+            "#,
+        );
+        assert_eq!(catalog.len(), 2);
+        assert_eq!(catalog["Feeds"], "訂閱");
+        assert_eq!(catalog["Say \"hi\""], "說「嗨」");
+    }
+
+    #[test]
+    fn test_translate() {
+        assert_eq!(translate(Locale::En, "Feed not found"), "Feed not found");
+        assert_eq!(translate(Locale::ZhTw, "Feed not found"), "找不到訂閱源");
+        // Missing messages fall back to English
+        assert_eq!(
+            translate(Locale::ZhTw, "No such message"),
+            "No such message"
+        );
+    }
+
+    #[test]
+    fn test_zh_tw_catalog_loads() {
+        assert!(ZH_TW.len() > 50);
+        assert!(ZH_TW.values().all(|msg| !msg.trim().is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_current_locale() {
+        assert_eq!(current(), Locale::En);
+        let msg = scope(Locale::ZhTw, async { gettext("Unauthorized") }).await;
+        assert_eq!(msg, "未授權");
+    }
+}
@@ -1,29 +1,43 @@
 use std::sync::Arc;
 
 use axum::{
+    extract::DefaultBodyLimit,
+    middleware::{from_fn, from_fn_with_state},
     routing::{delete, get, post, put},
     Router,
 };
-use tokio::sync::mpsc;
+use tower_http::{
+    compression::CompressionLayer,
+    limit::RequestBodyLimitLayer,
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::{DefaultOnResponse, TraceLayer},
+    LatencyUnit,
+};
 use webauthn_rs::prelude::Webauthn;
 
 pub mod auth;
+pub mod cli;
 pub mod config;
 pub mod db;
 pub mod error;
 pub mod handlers;
+pub mod i18n;
+pub mod listener;
 pub mod middleware;
 pub mod models;
 pub mod services;
+pub mod timezone;
 pub mod version;
 
-pub use config::Config;
+pub use config::{Config, ConfigError, ListenTarget};
 pub use db::DbPool;
 pub use middleware::auth::SESSION_COOKIE_NAME;
 pub use models::{Role, User};
 pub use version::{GIT_VERSION, PKG_VERSION};
 
-use services::{SummaryCache, SummaryJob};
+use services::{
+    Blocklist, Heartbeats, JobQueue, LiveSettings, RefreshRegistry, SummaryCache, UpdateChecker,
+};
 
 #[derive(Clone)]
 pub struct AppState {
@@ -31,13 +45,34 @@ pub struct AppState {
     pub config: Arc<Config>,
     pub webauthn: Arc<Webauthn>,
     pub summary_cache: Arc<SummaryCache>,
-    pub summary_tx: mpsc::Sender<SummaryJob>,
+    /// Wakes the job worker after a handler queues a job
+    pub jobs: JobQueue,
+    pub heartbeats: Heartbeats,
+    pub refreshes: RefreshRegistry,
+    pub blocklist: Blocklist,
+    pub updates: UpdateChecker,
+    /// Settings admins can change at runtime
+    pub settings: LiveSettings,
 }
 
 pub fn create_router(state: AppState) -> Router {
+    // Imports take uploads far larger than any other request, so they get a
+    // limit of their own
+    let imports = Router::new()
+        .route("/api/opml/import", post(handlers::feed::import_opml))
+        .route("/api/import/full", post(handlers::feed::import_full))
+        .route(
+            "/api/import/bookmarks",
+            post(handlers::feed::import_bookmarks),
+        )
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(state.config.max_import_bytes));
+
     Router::new()
         // Health check
         .route("/health", get(handlers::health::health_check))
+        .route("/health/live", get(handlers::health::health_check))
+        .route("/health/ready", get(handlers::health::readiness_check))
         // Favicon routes
         .route("/favicon.ico", get(handlers::favicon::favicon_ico))
         .route("/favicon.svg", get(handlers::favicon::favicon_svg))
@@ -47,17 +82,52 @@ pub fn create_router(state: AppState) -> Router {
             "/apple-touch-icon.png",
             get(handlers::favicon::apple_touch_icon),
         )
-        .route("/", get(handlers::pages::unread_page))
+        .route(
+            "/static/{fingerprint}/{name}",
+            get(handlers::favicon::static_asset),
+        )
+        // PWA routes
+        .route("/manifest.webmanifest", get(handlers::pwa::manifest))
+        .route("/sw.js", get(handlers::pwa::service_worker))
+        .route("/", get(handlers::pages::home_page))
+        .route("/unread", get(handlers::pages::unread_page))
+        .route("/streams/today", get(handlers::pages::today_page))
+        .route("/streams/last-24h", get(handlers::pages::last_24h_page))
         .route("/login", get(handlers::pages::login_page))
+        .route("/save", get(handlers::bookmarklet::save_page))
         .route("/register", get(handlers::pages::register_page))
         .route("/user-settings", get(handlers::pages::user_settings_page))
         .route("/admin", get(handlers::pages::admin_page))
         .route("/settings", get(handlers::pages::settings_page))
+        .route("/stats", get(handlers::pages::stats_page))
         .route("/api/register", post(handlers::auth::register))
         .route("/api/session", post(handlers::auth::login))
         .route("/api/session", delete(handlers::auth::logout))
+        .route(
+            "/api/bootstrap",
+            get(handlers::bootstrap::get_bootstrap).layer(from_fn(middleware::etag::etag)),
+        )
         .route("/api/user", get(handlers::user::get_current_user))
         .route("/api/user/password", put(handlers::user::change_password))
+        .route(
+            "/api/user/delete-account",
+            post(handlers::user::delete_account),
+        )
+        .route(
+            "/api/user/export",
+            get(handlers::user::export_personal_data),
+        )
+        .route("/api/user/sessions", get(handlers::user::list_sessions))
+        .route(
+            "/api/user/sessions/{id}",
+            delete(handlers::user::delete_session),
+        )
+        .route("/api/user/tokens", get(handlers::user::list_api_tokens))
+        .route("/api/user/tokens", post(handlers::user::create_api_token))
+        .route(
+            "/api/user/tokens/{id}",
+            delete(handlers::user::delete_api_token),
+        )
         .route("/api/user/settings", put(handlers::user::update_settings))
         .route(
             "/api/user/settings/linkding",
@@ -75,17 +145,139 @@ pub fn create_router(state: AppState) -> Router {
             "/api/user/settings/kagi",
             put(handlers::user::update_kagi_settings),
         )
+        .route(
+            "/api/user/settings/openai",
+            get(handlers::user::get_openai_settings),
+        )
+        .route(
+            "/api/user/settings/openai",
+            put(handlers::user::update_openai_settings),
+        )
         .route("/api/user/settings/theme", get(handlers::user::get_theme))
         .route(
             "/api/user/settings/theme",
             put(handlers::user::update_theme),
         )
+        .route("/api/user/settings/locale", get(handlers::user::get_locale))
+        .route(
+            "/api/user/settings/locale",
+            put(handlers::user::update_locale),
+        )
+        .route(
+            "/api/user/settings/timezone",
+            get(handlers::user::get_timezone),
+        )
+        .route(
+            "/api/user/settings/timezone",
+            put(handlers::user::update_timezone),
+        )
+        .route(
+            "/api/user/settings/scroll-read",
+            get(handlers::user::get_scroll_read_settings),
+        )
+        .route(
+            "/api/user/settings/scroll-read",
+            put(handlers::user::update_scroll_read_settings),
+        )
+        .route(
+            "/api/user/settings/revisions",
+            get(handlers::user::get_revision_settings),
+        )
+        .route(
+            "/api/user/settings/revisions",
+            put(handlers::user::update_revision_settings),
+        )
+        .route(
+            "/api/user/settings/auto-tag",
+            get(handlers::user::get_auto_tag_settings),
+        )
+        .route(
+            "/api/user/settings/auto-tag",
+            put(handlers::user::update_auto_tag_settings),
+        )
+        .route(
+            "/api/user/settings/auto-save",
+            get(handlers::user::get_auto_save_settings),
+        )
+        .route(
+            "/api/user/settings/auto-save",
+            put(handlers::user::update_auto_save_settings),
+        )
+        .route("/api/user/stats", get(handlers::user::get_reading_stats))
+        .route("/api/tags", get(handlers::tag::list_tags))
+        .route("/api/tags", post(handlers::tag::create_tag))
+        .route("/api/tags/{id}", delete(handlers::tag::delete_tag))
+        .route(
+            "/api/user/settings/home",
+            get(handlers::user::get_home_settings),
+        )
+        .route(
+            "/api/user/settings/home",
+            put(handlers::user::update_home_settings),
+        )
+        .route(
+            "/api/user/settings/sanitize",
+            get(handlers::user::get_sanitize_policy),
+        )
+        .route(
+            "/api/user/settings/sanitize",
+            put(handlers::user::update_sanitize_policy),
+        )
+        // Push notification routes
+        .route(
+            "/api/user/push-subscriptions/vapid-public-key",
+            get(handlers::notification::get_vapid_public_key),
+        )
+        .route(
+            "/api/user/push-subscriptions",
+            get(handlers::notification::list_push_subscriptions),
+        )
+        .route(
+            "/api/user/push-subscriptions",
+            post(handlers::notification::create_push_subscription),
+        )
+        .route(
+            "/api/user/push-subscriptions",
+            delete(handlers::notification::delete_push_subscription),
+        )
+        .route(
+            "/api/user/notification-rules",
+            get(handlers::notification::list_notification_rules),
+        )
+        .route(
+            "/api/user/notification-rules",
+            post(handlers::notification::create_notification_rule),
+        )
+        .route(
+            "/api/user/notification-rules/{id}",
+            delete(handlers::notification::delete_notification_rule),
+        )
+        // Background jobs
+        .route("/api/jobs", get(handlers::job::list_jobs))
+        // In-app notifications
+        .route(
+            "/api/notifications",
+            get(handlers::notification::list_notifications),
+        )
+        .route(
+            "/api/notifications/read",
+            put(handlers::notification::mark_all_notifications_read),
+        )
+        .route(
+            "/api/notifications/{id}/read",
+            put(handlers::notification::mark_notification_read),
+        )
         .route("/api/admin/users", get(handlers::admin::list_users))
+        .route("/api/admin/users", post(handlers::admin::create_user))
         .route("/api/admin/users/{id}", put(handlers::admin::update_user))
         .route(
             "/api/admin/users/{id}",
             delete(handlers::admin::delete_user),
         )
+        .route(
+            "/api/admin/users/{id}/unlock",
+            post(handlers::admin::unlock_user),
+        )
         .route(
             "/api/admin/masquerade/{id}",
             post(handlers::admin::start_masquerade),
@@ -94,10 +286,41 @@ pub fn create_router(state: AppState) -> Router {
             "/api/admin/unmasquerade",
             post(handlers::admin::stop_masquerade),
         )
+        .route(
+            "/api/admin/masquerade/status",
+            get(handlers::admin::masquerade_status),
+        )
+        .route(
+            "/api/admin/masquerade/audit",
+            get(handlers::admin::list_masquerade_audit),
+        )
+        .route("/api/admin/jobs", get(handlers::job::list_all_jobs))
+        .route("/api/admin/blocklist", get(handlers::admin::get_blocklist))
+        .route(
+            "/api/admin/blocklist",
+            put(handlers::admin::update_blocklist),
+        )
+        .route("/api/admin/version", get(handlers::admin::get_version))
+        .route("/api/admin/settings", get(handlers::admin::get_settings))
+        .route("/api/admin/settings", put(handlers::admin::update_settings))
+        .route("/api/admin/invites", get(handlers::admin::list_invites))
+        .route("/api/admin/invites", post(handlers::admin::create_invite))
+        .route(
+            "/api/admin/invites/{id}",
+            delete(handlers::admin::delete_invite),
+        )
         // Category routes
         .route("/categories", get(handlers::pages::categories_page))
         .route("/api/categories", get(handlers::category::list_categories))
         .route("/api/categories", post(handlers::category::create_category))
+        .route(
+            "/api/categories/reorder",
+            put(handlers::category::reorder_categories),
+        )
+        .route(
+            "/api/categories/icons",
+            get(handlers::category::list_category_icons),
+        )
         .route(
             "/api/categories/{id}",
             get(handlers::category::get_category),
@@ -110,22 +333,110 @@ pub fn create_router(state: AppState) -> Router {
             "/api/categories/{id}",
             delete(handlers::category::delete_category),
         )
+        .route(
+            "/api/categories/{id}/auto-read/preview",
+            get(handlers::category::preview_category_auto_read),
+        )
+        .route(
+            "/api/categories/{id}/digest",
+            get(handlers::category::get_category_digest),
+        )
+        .route(
+            "/api/categories/{id}/digest",
+            post(handlers::category::create_category_digest),
+        )
+        .route(
+            "/api/categories/{id}/digest",
+            delete(handlers::category::delete_category_digest),
+        )
         // Feed routes
         .route("/feeds", get(handlers::pages::feeds_page))
-        .route("/api/feeds", get(handlers::feed::list_feeds))
+        .route(
+            "/api/feeds",
+            get(handlers::feed::list_feeds).layer(from_fn(middleware::etag::etag)),
+        )
         .route("/api/feeds", post(handlers::feed::create_feed))
         .route(
             "/api/feeds/fetch-metadata",
             post(handlers::feed::fetch_metadata),
         )
+        .route("/api/feeds/stale", get(handlers::feed::list_stale_feeds))
+        .route("/api/feeds/reorder", put(handlers::feed::reorder_feeds))
         .route("/api/feeds/{id}", get(handlers::feed::get_feed))
         .route("/api/feeds/{id}", put(handlers::feed::update_feed))
         .route("/api/feeds/{id}", delete(handlers::feed::delete_feed))
         .route("/api/feeds/{id}/icon", get(handlers::feed::get_feed_icon))
+        .route(
+            "/api/feeds/{id}/icon/refresh",
+            post(handlers::feed::refresh_feed_icon),
+        )
+        .route(
+            "/api/feeds/{id}/cookies",
+            get(handlers::feed::get_feed_cookies),
+        )
+        .route(
+            "/api/feeds/{id}/cookies",
+            put(handlers::feed::update_feed_cookies),
+        )
+        .route(
+            "/api/feeds/{id}/script",
+            get(handlers::feed::get_feed_script),
+        )
+        .route(
+            "/api/feeds/{id}/script",
+            put(handlers::feed::update_feed_script),
+        )
+        .route(
+            "/api/feeds/{id}/rewrites",
+            get(handlers::feed::list_feed_rewrites),
+        )
+        .route(
+            "/api/feeds/{id}/rewrites",
+            post(handlers::feed::create_feed_rewrite),
+        )
+        .route(
+            "/api/feeds/{id}/rewrites/preview",
+            post(handlers::feed::preview_feed_rewrite),
+        )
+        .route(
+            "/api/feeds/{id}/rewrites/{rewrite_id}",
+            put(handlers::feed::update_feed_rewrite),
+        )
+        .route(
+            "/api/feeds/{id}/rewrites/{rewrite_id}",
+            delete(handlers::feed::delete_feed_rewrite),
+        )
+        .route(
+            "/api/feeds/{id}/events",
+            get(handlers::feed::list_feed_events),
+        )
+        .route("/api/feeds/{id}/stats", get(handlers::feed::get_feed_stats))
+        .route(
+            "/api/feeds/{id}/auto-read/preview",
+            get(handlers::feed::preview_feed_auto_read),
+        )
         // OPML routes
         .route("/api/opml/export", get(handlers::feed::export_opml))
-        .route("/api/opml/import", post(handlers::feed::import_opml))
+        .route("/api/export/full", get(handlers::feed::export_full))
+        .route("/api/undo/{token}", post(handlers::undo::undo_action))
+        .route("/api/trash", get(handlers::trash::list_trash))
+        .route(
+            "/api/trash/categories/{id}/restore",
+            post(handlers::trash::restore_category),
+        )
+        .route(
+            "/api/trash/feeds/{id}/restore",
+            post(handlers::trash::restore_feed),
+        )
         // Entry routes
+        .route(
+            "/fragments/entries",
+            get(handlers::fragments::entries_fragment),
+        )
+        .route(
+            "/fragments/unread-badge",
+            get(handlers::fragments::unread_badge_fragment),
+        )
         .route("/entries", get(handlers::pages::entries_page))
         .route("/entries/read", get(handlers::pages::read_entries_page))
         .route(
@@ -136,6 +447,10 @@ pub fn create_router(state: AppState) -> Router {
             "/entries/summarized",
             get(handlers::pages::summarized_entries_page),
         )
+        .route(
+            "/entries/read-later",
+            get(handlers::pages::read_later_entries_page),
+        )
         .route("/entries/{id}", get(handlers::pages::entry_page))
         .route("/search", get(handlers::pages::search_page))
         // Category entries page
@@ -143,12 +458,23 @@ pub fn create_router(state: AppState) -> Router {
             "/categories/{id}/entries",
             get(handlers::pages::category_entries_page),
         )
+        .route(
+            "/categories/{id}/digest",
+            get(handlers::pages::category_digest_page),
+        )
         // Feed entries page
         .route(
             "/feeds/{id}/entries",
             get(handlers::pages::feed_entries_page),
         )
-        .route("/api/entries", get(handlers::entry::list_entries))
+        .route(
+            "/api/entries",
+            get(handlers::entry::list_entries).layer(from_fn(middleware::etag::etag)),
+        )
+        .route(
+            "/api/entries/manual",
+            post(handlers::entry::create_manual_entry),
+        )
         .route("/api/entries/{id}", get(handlers::entry::get_entry))
         .route(
             "/api/entries/{id}/read",
@@ -170,6 +496,10 @@ pub fn create_router(state: AppState) -> Router {
             "/api/entries/{id}/save",
             post(handlers::entry::save_to_services),
         )
+        .route(
+            "/api/entries/{id}/saves",
+            get(handlers::entry::list_entry_saves),
+        )
         .route(
             "/api/entries/{id}/summarize",
             post(handlers::entry::summarize_entry),
@@ -182,10 +512,88 @@ pub fn create_router(state: AppState) -> Router {
             "/api/entries/{id}/summary",
             delete(handlers::entry::delete_entry_summary),
         )
+        .route(
+            "/api/entries/{id}/summary/stream",
+            get(handlers::entry::stream_entry_summary),
+        )
+        .route(
+            "/api/entries/{id}/tags",
+            get(handlers::tag::list_entry_tags),
+        )
+        .route("/api/entries/{id}/ask", post(handlers::entry::ask_entry))
+        .route(
+            "/api/entries/{id}/ask",
+            get(handlers::entry::list_entry_questions),
+        )
+        .route(
+            "/api/entries/{id}/ask",
+            delete(handlers::entry::clear_entry_questions),
+        )
+        .route(
+            "/api/entries/{id}/highlights",
+            get(handlers::highlight::list_highlights),
+        )
+        .route(
+            "/api/entries/{id}/highlights",
+            post(handlers::highlight::create_highlight),
+        )
+        .route(
+            "/api/entries/{id}/highlights/{highlight_id}",
+            put(handlers::highlight::update_highlight),
+        )
+        .route(
+            "/api/entries/{id}/highlights/{highlight_id}",
+            delete(handlers::highlight::delete_highlight),
+        )
+        .route(
+            "/api/entries/{id}/note",
+            get(handlers::entry_note::get_note),
+        )
+        .route(
+            "/api/entries/{id}/note",
+            put(handlers::entry_note::set_note),
+        )
+        .route(
+            "/api/entries/{id}/note",
+            delete(handlers::entry_note::delete_note),
+        )
+        .route("/api/entries/{id}/epub", post(handlers::epub::entry_epub))
+        .route(
+            "/api/entries/{id}/export.pdf",
+            get(handlers::pdf::entry_pdf),
+        )
+        .route(
+            "/api/entries/starred/epub",
+            post(handlers::epub::starred_epub),
+        )
+        .route(
+            "/api/entries/{id}/views",
+            post(handlers::entry::record_entry_view),
+        )
+        .route(
+            "/api/entries/{id}/views/{view_id}",
+            put(handlers::entry::update_entry_view),
+        )
         .route(
             "/api/entries/{id}/neighbors",
             get(handlers::entry::get_entry_neighbors),
         )
+        .route(
+            "/api/entries/{id}/discussions",
+            get(handlers::entry::get_entry_discussions),
+        )
+        .route(
+            "/api/entries/{id}/related",
+            get(handlers::entry::get_related_entries),
+        )
+        .route(
+            "/api/entries/{id}/duplicates",
+            get(handlers::entry::get_entry_duplicates),
+        )
+        .route(
+            "/api/entries/{id}/revisions",
+            get(handlers::entry::get_entry_revisions),
+        )
         .route(
             "/api/entries/mark-all-read",
             put(handlers::entry::mark_all_read),
@@ -194,18 +602,47 @@ pub fn create_router(state: AppState) -> Router {
             "/api/entries/mark-read-by-ids",
             put(handlers::entry::mark_read_by_ids),
         )
+        .route(
+            "/api/entries/mark-read-before",
+            put(handlers::entry::mark_read_before),
+        )
+        .route("/api/entries/ack-seen", post(handlers::entry::ack_seen))
         .route(
             "/api/entries/unread-stats",
-            get(handlers::entry::get_unread_stats),
+            get(handlers::entry::get_unread_stats).layer(from_fn(middleware::etag::etag)),
+        )
+        .route(
+            "/api/streams/today",
+            get(handlers::entry::list_today_entries),
+        )
+        .route(
+            "/api/streams/last-24h",
+            get(handlers::entry::list_last_24h_entries),
+        )
+        .route(
+            "/api/entries/offline-bundle",
+            get(handlers::entry::get_offline_bundle),
+        )
+        .route(
+            "/api/entries/changes",
+            get(handlers::entry::get_entry_changes),
         )
         .route(
             "/api/feeds/{id}/entries",
             get(handlers::entry::list_feed_entries),
         )
+        .route(
+            "/api/feeds/refresh",
+            post(handlers::entry::refresh_feeds_handler),
+        )
         .route(
             "/api/feeds/{id}/refresh",
             post(handlers::entry::refresh_feed_handler),
         )
+        .route(
+            "/api/feeds/{id}/retry",
+            post(handlers::entry::retry_feed_handler),
+        )
         // Proxy routes
         .route("/api/proxy/image", get(handlers::proxy::proxy_image))
         // Passkey routes
@@ -221,6 +658,10 @@ pub fn create_router(state: AppState) -> Router {
             "/api/passkey/auth/start",
             post(handlers::passkey::start_authentication),
         )
+        .route(
+            "/api/passkey/auth/options",
+            get(handlers::passkey::authentication_options),
+        )
         .route(
             "/api/passkey/auth/finish",
             post(handlers::passkey::finish_authentication),
@@ -231,5 +672,30 @@ pub fn create_router(state: AppState) -> Router {
             "/api/passkeys/{id}",
             delete(handlers::passkey::delete_passkey),
         )
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(state.config.max_body_bytes))
+        .merge(imports)
+        .layer(from_fn(middleware::session::refresh_session_cookie))
+        .layer(from_fn_with_state(
+            state.clone(),
+            middleware::masquerade::track_masquerade,
+        ))
+        .layer(from_fn_with_state(
+            state.clone(),
+            middleware::locale::scope_locale,
+        ))
+        .layer(from_fn(middleware::request_id::scope_request_id))
+        .layer(CompressionLayer::new())
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(middleware::request_id::make_span)
+                .on_response(
+                    DefaultOnResponse::new()
+                        .level(tracing::Level::INFO)
+                        .latency_unit(LatencyUnit::Millis),
+                ),
+        )
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
         .with_state(state)
 }
@@ -0,0 +1,173 @@
+//! Opening the socket the HTTP server accepts connections on.
+//!
+//! A socket passed in by systemd socket activation (`LISTEN_FDS`) is used as
+//! is, so connections queue in the kernel while rdrs restarts. Otherwise the
+//! socket is bound from the listen target; with `REUSE_PORT`, a new process
+//! can bind the same TCP port before the old one has finished draining.
+
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+
+use socket2::{Domain, Protocol, Socket, Type};
+
+use crate::config::ListenTarget;
+
+/// First file descriptor passed by socket activation
+#[cfg(unix)]
+const LISTEN_FDS_START: i32 = 3;
+
+/// Connections the kernel queues while the server is busy or restarting
+const BACKLOG: i32 = 1024;
+
+/// A socket ready to accept connections
+#[derive(Debug)]
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixListener),
+}
+
+impl Listener {
+    pub fn describe(&self) -> String {
+        match self {
+            Listener::Tcp(listener) => match listener.local_addr() {
+                Ok(addr) => addr.to_string(),
+                Err(_) => "TCP socket".to_string(),
+            },
+            #[cfg(unix)]
+            Listener::Unix(listener) => match listener.local_addr() {
+                Ok(addr) => match addr.as_pathname() {
+                    Some(path) => format!("unix:{}", path.display()),
+                    None => "Unix socket".to_string(),
+                },
+                Err(_) => "Unix socket".to_string(),
+            },
+        }
+    }
+}
+
+/// Number of sockets passed to this process by socket activation, given
+/// `LISTEN_PID` and `LISTEN_FDS`. Variables meant for another process (a
+/// parent that did not clear them) are ignored.
+pub fn activated_fds(listen_pid: Option<&str>, listen_fds: Option<&str>, pid: u32) -> usize {
+    match listen_pid.and_then(|p| p.trim().parse::<u32>().ok()) {
+        Some(listen_pid) if listen_pid == pid => {
+            listen_fds.and_then(|n| n.trim().parse().ok()).unwrap_or(0)
+        }
+        _ => 0,
+    }
+}
+
+/// The socket passed by socket activation, if any. Only the first one is
+/// used.
+#[cfg(unix)]
+pub fn activated() -> io::Result<Option<Listener>> {
+    use std::os::fd::{FromRawFd, OwnedFd};
+
+    let listen_pid = std::env::var("LISTEN_PID").ok();
+    let listen_fds = std::env::var("LISTEN_FDS").ok();
+    let count = activated_fds(
+        listen_pid.as_deref(),
+        listen_fds.as_deref(),
+        std::process::id(),
+    );
+    if count == 0 {
+        return Ok(None);
+    }
+    if count > 1 {
+        tracing::warn!(
+            "{} sockets passed by socket activation, using the first",
+            count
+        );
+    }
+
+    // SAFETY: the socket activation protocol hands this process ownership of
+    // descriptors LISTEN_FDS_START onwards, and nothing else has claimed them
+    let socket = unsafe { Socket::from_raw_fd(LISTEN_FDS_START) };
+    let addr = socket.local_addr()?;
+    socket.set_nonblocking(true)?;
+    if addr.as_socket().is_some() {
+        Ok(Some(Listener::Tcp(socket.into())))
+    } else if addr.domain() == Domain::UNIX {
+        Ok(Some(Listener::Unix(OwnedFd::from(socket).into())))
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "socket passed by socket activation is neither TCP nor a Unix socket",
+        ))
+    }
+}
+
+#[cfg(not(unix))]
+pub fn activated() -> io::Result<Option<Listener>> {
+    Ok(None)
+}
+
+/// Bind a TCP listener on every IPv4 address
+pub fn bind_tcp(port: u16, reuse_port: bool) -> io::Result<TcpListener> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let socket = Socket::new(Domain::IPV4, Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(reuse_port)?;
+    #[cfg(not(unix))]
+    if reuse_port {
+        tracing::warn!("REUSE_PORT is not supported on this platform");
+    }
+    socket.bind(&addr.into())?;
+    socket.listen(BACKLOG)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket.into())
+}
+
+/// Bind the listener for `target`
+pub fn bind(target: &ListenTarget, reuse_port: bool) -> io::Result<Listener> {
+    match target {
+        ListenTarget::Tcp { port } | ListenTarget::Tls { port, .. } => {
+            bind_tcp(*port, reuse_port).map(Listener::Tcp)
+        }
+        #[cfg(unix)]
+        ListenTarget::Unix { path } => {
+            // A socket file left by an unclean exit would make bind fail
+            if std::fs::metadata(path).is_ok() {
+                std::fs::remove_file(path)?;
+            }
+            let listener = std::os::unix::net::UnixListener::bind(path)?;
+            listener.set_nonblocking(true)?;
+            Ok(Listener::Unix(listener))
+        }
+        #[cfg(not(unix))]
+        ListenTarget::Unix { .. } => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Unix sockets are not supported on this platform",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_activated_fds() {
+        assert_eq!(activated_fds(Some("42"), Some("1"), 42), 1);
+        assert_eq!(activated_fds(Some("42"), Some("2"), 42), 2);
+        // Meant for another process
+        assert_eq!(activated_fds(Some("41"), Some("1"), 42), 0);
+        assert_eq!(activated_fds(None, Some("1"), 42), 0);
+        assert_eq!(activated_fds(Some("42"), None, 42), 0);
+        assert_eq!(activated_fds(Some("42"), Some("many"), 42), 0);
+    }
+
+    #[test]
+    fn test_reuse_port() {
+        let first = bind_tcp(0, true).unwrap();
+        let port = first.local_addr().unwrap().port();
+        #[cfg(unix)]
+        {
+            let second = bind_tcp(port, true).unwrap();
+            assert_eq!(second.local_addr().unwrap().port(), port);
+        }
+        assert!(bind_tcp(port, false).is_err());
+    }
+}
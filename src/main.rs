@@ -1,19 +1,89 @@
+use std::future::{Future, IntoFuture};
 use std::sync::Arc;
 use std::time::Duration;
 
-use rdrs::{auth, create_router, db, services, AppState, Config, DbPool};
+use axum::Router;
+use clap::Parser;
+use rdrs::cli::{self, Cli, Command, ConfigCommand};
+use rdrs::listener::{self, Listener};
+use rdrs::{auth, create_router, db, services, AppState, Config, DbPool, ListenTarget};
 use rusqlite::Connection;
 use tokio_util::sync::CancellationToken;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() {
+    let cli = Cli::parse();
+    let config_path = cli.config_path();
+
+    // Checking needs neither logging nor a database
+    if let Some(Command::Config(ConfigCommand::Check)) = cli.command {
+        match cli::check_config(config_path.as_deref()) {
+            Ok(message) => println!("{}", message),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let config = match Config::load(config_path.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Exactly one of the two formatters is active
     tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
+        .with(
+            config
+                .log_json
+                .then(|| tracing_subscriber::fmt::layer().json()),
+        )
+        .with((!config.log_json).then(tracing_subscriber::fmt::layer))
         .with(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
-    let config = Config::from_env();
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => run_server(config).await,
+        command => run_command(&config, command),
+    }
+}
+
+/// Run an admin subcommand against the configured database and exit
+fn run_command(config: &Config, command: Command) {
+    let conn = Connection::open(&config.database_url).expect("Failed to open database");
+    db::init_db(&conn).expect("Failed to initialize database");
+
+    match cli::run(&conn, command) {
+        Ok(message) => println!("{}", message),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn run_server(config: Config) {
+    let listen_target = config
+        .listen_target()
+        .expect("Invalid listener configuration");
+
+    // Bound first, so connections made while the database opens are queued
+    // rather than refused
+    let (listener, activated) = match listener::activated().expect("Failed to use passed socket") {
+        Some(listener) => {
+            tracing::info!("Using socket passed by socket activation");
+            (listener, true)
+        }
+        None => (
+            listener::bind(&listen_target, config.reuse_port).expect("Failed to bind"),
+            false,
+        ),
+    };
 
     if config.image_proxy_secret_generated {
         tracing::warn!(
@@ -21,71 +91,187 @@ async fn main() {
         );
     }
 
+    services::http::set_privacy_mode(config.privacy_mode);
+    if config.privacy_mode {
+        tracing::info!(
+            "Privacy mode: site icons, discussion lookups and summaries are disabled. \
+             Only feeds, the pages and images they link to, and configured services are contacted."
+        );
+    }
+
     let conn = Connection::open(&config.database_url).expect("Failed to open database");
     db::init_db(&conn).expect("Failed to initialize database");
 
-    let (db, db_handle) = DbPool::new(conn);
+    // Read-only connections need a database file shared with the writer
+    let readers = if config.database_url == ":memory:" {
+        Vec::new()
+    } else {
+        db::open_readers(&config.database_url, config.db_reader_count)
+            .expect("Failed to open read-only database connections")
+    };
+
+    let (db, db_handle) = DbPool::with_readers(conn, readers);
 
     let webauthn = auth::create_webauthn(&config).expect("Failed to create WebAuthn");
 
     // Create cancellation token for graceful shutdown
     let cancel_token = CancellationToken::new();
 
+    // Worker liveness for /health/ready; the limits allow for slow jobs
+    let heartbeats = services::Heartbeats::default();
+    let refreshes = services::RefreshRegistry::default();
+
     // Create summary cache (max 1000 entries, 24 hour TTL)
     let summary_cache = services::create_summary_cache(1000, 24);
 
-    // Create summary worker channel (buffer size 100)
-    let (summary_tx, summary_rx) = services::create_summary_channel(100);
+    // Start summary cleanup worker (every 1 hour, delete summaries older than 24 hours)
+    let cleanup_worker_handle = services::start_cleanup_worker(
+        db.clone(),
+        1,
+        24,
+        heartbeats.register("summary_cleanup", Duration::from_secs(300)),
+        cancel_token.clone(),
+    );
 
-    // Start summary worker
-    let summary_worker_handle = services::start_summary_worker(
-        summary_rx,
-        summary_cache.clone(),
+    // Rebuild weekly feed stats every 6 hours
+    let feed_stats_handle = services::start_feed_stats_worker(
         db.clone(),
+        6,
+        heartbeats.register("feed_stats", Duration::from_secs(300)),
         cancel_token.clone(),
     );
 
-    // Recover incomplete summary jobs from database
-    let recovered =
-        services::recover_incomplete_jobs(db.clone(), summary_tx.clone(), summary_cache.clone())
-            .await;
+    // Apply auto-mark-read rules every hour
+    let auto_read_handle = services::start_auto_read_worker(
+        db.clone(),
+        heartbeats.register("auto_read", Duration::from_secs(300)),
+        cancel_token.clone(),
+    );
+
+    // Retrain interest models behind sort=interest once a day
+    let interest_handle = services::start_interest_worker(
+        db.clone(),
+        heartbeats.register("interest", Duration::from_secs(300)),
+        cancel_token.clone(),
+    );
+
+    // Settings admins changed at runtime, over the configured defaults
+    let settings = services::LiveSettings::from_config(&config);
+    if let Err(e) = settings.reload(&db, &config).await {
+        tracing::error!("Failed to load instance settings: {}", e);
+    }
+
+    // Run queued summaries, digests and refreshes, resuming those a previous
+    // run left unfinished
+    let jobs = services::JobQueue::new();
+    let job_context = services::JobContext {
+        db: db.clone(),
+        summary_cache: summary_cache.clone(),
+        refreshes: refreshes.clone(),
+        settings: settings.clone(),
+        feed_cookie_secret: config.feed_cookie_secret.clone(),
+    };
+    let recovered = services::jobs::recover(&db, &summary_cache).await;
     if recovered > 0 {
-        tracing::info!("Recovered {} incomplete summary jobs", recovered);
+        tracing::info!("{} queued jobs waiting to run", recovered);
     }
+    let job_worker_handle = services::start_job_worker(
+        job_context,
+        jobs.clone(),
+        heartbeats.register("job_worker", Duration::from_secs(600)),
+        cancel_token.clone(),
+    );
 
-    // Start summary cleanup worker (every 1 hour, delete summaries older than 24 hours)
-    let cleanup_worker_handle =
-        services::start_cleanup_worker(db.clone(), 1, 24, cancel_token.clone());
+    // Purge feeds and categories whose trash retention has passed
+    let trash_purge_handle = services::start_trash_purge_worker(
+        db.clone(),
+        settings.subscribe(),
+        heartbeats.register("trash_purge", Duration::from_secs(300)),
+        cancel_token.clone(),
+    );
+
+    // Refetch stale feed icons and retry missing ones every day
+    let icon_refresh_handle = services::start_icon_refresh_worker(
+        db.clone(),
+        settings.subscribe(),
+        heartbeats.register("icon_refresh", Duration::from_secs(3600)),
+        cancel_token.clone(),
+    );
+
+    // Load the blocklist and import its hosts file every day
+    let blocklist = services::Blocklist::default();
+    let blocklist_handle = services::start_blocklist_worker(
+        db.clone(),
+        blocklist.clone(),
+        settings.subscribe(),
+        heartbeats.register("blocklist", Duration::from_secs(300)),
+        cancel_token.clone(),
+    );
+
+    // Check for a newer release every day, if asked to
+    let updates = services::UpdateChecker::default();
+    let update_check_handle = if config.update_check_enabled && config.privacy_mode {
+        tracing::warn!("UPDATE_CHECK_ENABLED is ignored in privacy mode");
+        None
+    } else if config.update_check_enabled {
+        Some(services::start_update_checker(
+            db.clone(),
+            updates.clone(),
+            settings.subscribe(),
+            heartbeats.register("update_check", Duration::from_secs(300)),
+            cancel_token.clone(),
+        ))
+    } else {
+        None
+    };
 
     let state = AppState {
         db: db.clone(),
         config: Arc::new(config.clone()),
         webauthn: Arc::new(webauthn),
         summary_cache,
-        summary_tx,
+        jobs,
+        heartbeats: heartbeats.clone(),
+        refreshes: refreshes.clone(),
+        blocklist,
+        updates,
+        settings: settings.clone(),
     };
 
+    // Load VAPID keys for Web Push notifications
+    let vapid = config.vapid_private_key.as_deref().and_then(|key| {
+        match services::VapidKeys::from_private_key(key, &config.vapid_subject) {
+            Ok(keys) => Some(Arc::new(keys)),
+            Err(e) => {
+                tracing::warn!(
+                    "Invalid VAPID_PRIVATE_KEY, push notifications disabled: {}",
+                    e
+                );
+                None
+            }
+        }
+    });
+
     // Start background sync task
     let background_handle = services::start_background_sync(
         db.clone(),
-        config.user_agent.clone(),
+        settings.subscribe(),
+        config.feed_cookie_secret.clone(),
+        vapid,
+        refreshes,
+        heartbeats.register("background_sync", Duration::from_secs(600)),
         cancel_token.clone(),
     );
 
     let app = create_router(state);
 
-    let addr = format!("0.0.0.0:{}", config.server_port);
-    tracing::info!("Starting server on {}", addr);
+    let drain_timeout = Duration::from_secs(config.shutdown_drain_timeout_secs as u64);
+    serve(app, listen_target.clone(), listener, drain_timeout).await;
 
-    let listener = tokio::net::TcpListener::bind(&addr)
-        .await
-        .expect("Failed to bind");
-
-    // Start server with graceful shutdown
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .expect("Server failed");
+    // The socket file belongs to whoever created it
+    if let (ListenTarget::Unix { path }, false) = (&listen_target, activated) {
+        let _ = std::fs::remove_file(path);
+    }
 
     tracing::info!("Server stopped, initiating graceful shutdown...");
 
@@ -97,8 +283,19 @@ async fn main() {
     let shutdown_timeout = tokio::time::timeout(Duration::from_secs(30), async {
         let _ = tokio::join!(
             background_handle,
-            summary_worker_handle,
+            job_worker_handle,
             cleanup_worker_handle,
+            feed_stats_handle,
+            auto_read_handle,
+            interest_handle,
+            trash_purge_handle,
+            icon_refresh_handle,
+            blocklist_handle,
+            async {
+                if let Some(handle) = update_check_handle {
+                    let _ = handle.await;
+                }
+            },
         );
     });
 
@@ -121,6 +318,128 @@ async fn main() {
     tracing::info!("Graceful shutdown complete");
 }
 
+async fn serve(app: Router, target: ListenTarget, listener: Listener, drain_timeout: Duration) {
+    tracing::info!("Starting server on {}", listener.describe());
+
+    match (target, listener) {
+        (
+            ListenTarget::Tls {
+                cert_path,
+                key_path,
+                ..
+            },
+            Listener::Tcp(listener),
+        ) => serve_tls(app, listener, cert_path, key_path, drain_timeout).await,
+        #[cfg(unix)]
+        (ListenTarget::Tls { .. }, Listener::Unix(_)) => {
+            panic!("TLS cannot be served on a Unix socket")
+        }
+        (_, Listener::Tcp(listener)) => {
+            let listener =
+                tokio::net::TcpListener::from_std(listener).expect("Failed to use TCP socket");
+            let stop = CancellationToken::new();
+            let server =
+                axum::serve(listener, app).with_graceful_shutdown(stop.clone().cancelled_owned());
+            drain(server.into_future(), stop, drain_timeout).await;
+        }
+        #[cfg(unix)]
+        (_, Listener::Unix(listener)) => {
+            let listener =
+                tokio::net::UnixListener::from_std(listener).expect("Failed to use Unix socket");
+            let stop = CancellationToken::new();
+            let server =
+                axum::serve(listener, app).with_graceful_shutdown(stop.clone().cancelled_owned());
+            drain(server.into_future(), stop, drain_timeout).await;
+        }
+    }
+}
+
+/// Serve until a shutdown signal, then stop accepting connections and give
+/// requests in flight up to `timeout` to finish. Background tasks keep
+/// running meanwhile; they are stopped once this returns.
+async fn drain<F>(server: F, stop: CancellationToken, timeout: Duration)
+where
+    F: Future<Output = std::io::Result<()>>,
+{
+    tokio::pin!(server);
+
+    tokio::select! {
+        result = &mut server => {
+            result.expect("Server failed");
+            return;
+        }
+        _ = shutdown_signal() => {}
+    }
+
+    tracing::info!(
+        "Stopped accepting connections, draining requests in flight (up to {}s)...",
+        timeout.as_secs()
+    );
+    stop.cancel();
+
+    match tokio::time::timeout(timeout, server).await {
+        Ok(result) => {
+            result.expect("Server failed");
+            tracing::info!("All requests drained");
+        }
+        Err(_) => tracing::warn!(
+            "Requests still in flight after {}s, dropping their connections",
+            timeout.as_secs()
+        ),
+    }
+}
+
+async fn serve_tls(
+    app: Router,
+    listener: std::net::TcpListener,
+    cert_path: String,
+    key_path: String,
+    drain_timeout: Duration,
+) {
+    use axum_server::tls_rustls::RustlsConfig;
+
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let tls_config = RustlsConfig::from_pem_file(&cert_path, &key_path)
+        .await
+        .expect("Failed to load TLS certificate and key");
+
+    // Reload the certificate on SIGHUP so renewals need no restart
+    #[cfg(unix)]
+    {
+        let tls_config = tls_config.clone();
+        tokio::spawn(async move {
+            let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+                .expect("Failed to install SIGHUP handler");
+            while hangup.recv().await.is_some() {
+                match tls_config.reload_from_pem_file(&cert_path, &key_path).await {
+                    Ok(()) => tracing::info!("Reloaded TLS certificate"),
+                    Err(e) => tracing::error!("Failed to reload TLS certificate: {}", e),
+                }
+            }
+        });
+    }
+
+    tracing::info!("Serving HTTPS");
+
+    let handle = axum_server::Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        tracing::info!(
+            "Stopped accepting connections, draining requests in flight (up to {}s)...",
+            drain_timeout.as_secs()
+        );
+        shutdown_handle.graceful_shutdown(Some(drain_timeout));
+    });
+
+    axum_server::from_tcp_rustls(listener, tls_config)
+        .handle(handle)
+        .serve(app.into_make_service())
+        .await
+        .expect("Server failed");
+}
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         tokio::signal::ctrl_c()
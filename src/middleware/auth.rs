@@ -6,13 +6,55 @@ use axum::{
 use axum_extra::extract::CookieJar;
 
 use crate::error::AppError;
+use crate::i18n::gettext;
 use crate::middleware::flash::FlashRedirect;
+use crate::middleware::masquerade::{MasqueradeContext, Masquerading};
+use crate::middleware::session::{session_cookie, SessionRefresh};
 use crate::models::session::{self, Session};
 use crate::models::user::{self, User};
 use crate::AppState;
 
 pub const SESSION_COOKIE_NAME: &str = "session_token";
 
+/// Routes still usable while a temporary password is pending: reading the
+/// account, changing the password and signing out
+const PASSWORD_CHANGE_API_PATHS: &[&str] = &["/api/user", "/api/user/password", "/api/session"];
+
+/// Page where the temporary password is changed
+const PASSWORD_CHANGE_PAGE: &str = "/user-settings";
+
+/// Whether the user must change a temporary password before using `path`.
+/// Admins masquerading as the user are not held up by it.
+fn password_change_pending(user: &User, session: &Session, path: &str, allowed: &[&str]) -> bool {
+    user.must_change_password && !session.is_masquerading() && !allowed.contains(&path)
+}
+
+/// Ask `refresh_session_cookie` to re-issue the cookie for a renewed session.
+/// Browser-session cookies carry no expiry and need no renewal.
+fn mark_refreshed(parts: &Parts, session: &Session, ttl: chrono::Duration) {
+    if !session.persistent {
+        return;
+    }
+    if let Some(refresh) = parts.extensions.get::<SessionRefresh>() {
+        refresh.set(session_cookie(session.session_token.clone(), Some(ttl)));
+    }
+}
+
+/// Tell `track_masquerade` the request comes from an admin masquerading as
+/// `user`
+fn mark_masquerading(parts: &Parts, user: &User, session: &Session) {
+    let Some(admin_id) = session.original_user_id else {
+        return;
+    };
+    if let Some(masquerading) = parts.extensions.get::<Masquerading>() {
+        masquerading.set(MasqueradeContext {
+            admin_id,
+            user_id: user.id,
+            username: user.username.clone(),
+        });
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AuthUser {
     pub user: User,
@@ -35,23 +77,35 @@ impl FromRequestParts<AppState> for AuthUser {
             .map(|c| c.value().to_string())
             .ok_or(AppError::Unauthorized)?;
 
-        let token_clone = token.clone();
-        let (session, expired) = state
+        let config = state.config.clone();
+        let (session, expired, refreshed) = state
             .db
             .user(move |conn| {
                 let session =
-                    session::find_by_token(conn, &token_clone)?.ok_or(AppError::Unauthorized)?;
+                    session::find_by_token(conn, &token)?.ok_or(AppError::Unauthorized)?;
                 if session.is_expired() {
-                    session::delete_session(conn, &token_clone)?;
-                    return Ok::<_, AppError>((session, true));
+                    session::delete_session(conn, &token)?;
+                    return Ok::<_, AppError>((session, true, false));
+                }
+                let ttl = config.session_ttl(session.persistent);
+                let refreshed = session.needs_refresh(ttl);
+                if refreshed {
+                    session::extend_session(conn, &token, ttl)?;
                 }
-                Ok((session, false))
+                Ok((session, false, refreshed))
             })
             .await??;
 
         if expired {
             return Err(AppError::Unauthorized);
         }
+        if refreshed {
+            mark_refreshed(
+                parts,
+                &session,
+                state.config.session_ttl(session.persistent),
+            );
+        }
 
         let user_id = session.user_id;
         let user = state
@@ -62,6 +116,10 @@ impl FromRequestParts<AppState> for AuthUser {
         if user.is_disabled() {
             return Err(AppError::UserDisabled);
         }
+        if password_change_pending(&user, &session, parts.uri.path(), PASSWORD_CHANGE_API_PATHS) {
+            return Err(AppError::PasswordChangeRequired);
+        }
+        mark_masquerading(parts, &user, &session);
 
         Ok(AuthUser { user, session })
     }
@@ -74,17 +132,32 @@ pub struct PageAuthUser {
     pub session: Session,
 }
 
-/// Redirect response for unauthorized page access
-pub struct LoginRedirect;
+/// Redirect response for page access that cannot be served
+pub enum PageRedirect {
+    /// Not signed in
+    Login,
+    /// Signed in with a temporary password that must be changed first
+    ChangePassword,
+}
 
-impl IntoResponse for LoginRedirect {
+impl IntoResponse for PageRedirect {
     fn into_response(self) -> Response {
-        FlashRedirect::warning("/login", "Please log in to continue.").into_response()
+        match self {
+            PageRedirect::Login => {
+                FlashRedirect::warning("/login", gettext("Please log in to continue."))
+                    .into_response()
+            }
+            PageRedirect::ChangePassword => FlashRedirect::warning(
+                PASSWORD_CHANGE_PAGE,
+                gettext("Please change your temporary password to continue."),
+            )
+            .into_response(),
+        }
     }
 }
 
 impl FromRequestParts<AppState> for PageAuthUser {
-    type Rejection = LoginRedirect;
+    type Rejection = PageRedirect;
 
     async fn from_request_parts(
         parts: &mut Parts,
@@ -92,24 +165,28 @@ impl FromRequestParts<AppState> for PageAuthUser {
     ) -> Result<Self, Self::Rejection> {
         let jar = CookieJar::from_request_parts(parts, state)
             .await
-            .map_err(|_| LoginRedirect)?;
+            .map_err(|_| PageRedirect::Login)?;
 
         let token = jar
             .get(SESSION_COOKIE_NAME)
             .map(|c| c.value().to_string())
-            .ok_or(LoginRedirect)?;
+            .ok_or(PageRedirect::Login)?;
 
-        let token_clone = token.clone();
+        let config = state.config.clone();
         let result = state
             .db
             .user(move |conn| {
-                let session = session::find_by_token(conn, &token_clone)
+                let session = session::find_by_token(conn, &token)
                     .map_err(|_| ())?
                     .ok_or(())?;
                 if session.is_expired() {
-                    let _ = session::delete_session(conn, &token_clone);
+                    let _ = session::delete_session(conn, &token);
                     return Err(());
                 }
+                let ttl = config.session_ttl(session.persistent);
+                if session.needs_refresh(ttl) {
+                    session::extend_session(conn, &token, ttl).map_err(|_| ())?;
+                }
                 let user = user::find_by_id(conn, session.user_id)
                     .map_err(|_| ())?
                     .ok_or(())?;
@@ -119,9 +196,18 @@ impl FromRequestParts<AppState> for PageAuthUser {
                 Ok((user, session))
             })
             .await
-            .map_err(|_| LoginRedirect)?;
+            .map_err(|_| PageRedirect::Login)?;
 
-        let (user, session) = result.map_err(|_| LoginRedirect)?;
+        let (user, session) = result.map_err(|_| PageRedirect::Login)?;
+        let path = parts.uri.path();
+        if password_change_pending(&user, &session, path, &[PASSWORD_CHANGE_PAGE]) {
+            return Err(PageRedirect::ChangePassword);
+        }
+        let ttl = state.config.session_ttl(session.persistent);
+        if session.needs_refresh(ttl) {
+            mark_refreshed(parts, &session, ttl);
+        }
+        mark_masquerading(parts, &user, &session);
 
         Ok(PageAuthUser { user, session })
     }
@@ -175,7 +261,7 @@ pub struct PageAdminUser {
 }
 
 impl FromRequestParts<AppState> for PageAdminUser {
-    type Rejection = LoginRedirect;
+    type Rejection = PageRedirect;
 
     async fn from_request_parts(
         parts: &mut Parts,
@@ -193,16 +279,16 @@ impl FromRequestParts<AppState> for PageAdminUser {
                             .ok_or(())
                     })
                     .await
-                    .map_err(|_| LoginRedirect)?
-                    .map_err(|_| LoginRedirect)?;
+                    .map_err(|_| PageRedirect::Login)?
+                    .map_err(|_| PageRedirect::Login)?;
                 if !original_user.is_admin() {
-                    return Err(LoginRedirect);
+                    return Err(PageRedirect::Login);
                 }
             } else {
-                return Err(LoginRedirect);
+                return Err(PageRedirect::Login);
             }
         } else if !page_auth_user.user.is_admin() {
-            return Err(LoginRedirect);
+            return Err(PageRedirect::Login);
         }
 
         Ok(PageAdminUser {
@@ -0,0 +1,93 @@
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+/// Compute a strong ETag from a response body
+pub fn compute_etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    let hex: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("\"{}\"", hex)
+}
+
+/// Whether an `If-None-Match` header value matches the given ETag
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+/// Tag successful GET responses with a content-hash ETag and answer
+/// `304 Not Modified` when the client already holds the same payload.
+///
+/// Meant for polled JSON endpoints: the body is buffered to hash it.
+/// Streamed bodies, whose length is not known up front, are passed through
+/// untagged.
+pub async fn etag(request: Request, next: Next) -> Response {
+    let is_get = request.method() == Method::GET;
+    let if_none_match = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+
+    let response = next.run(request).await;
+    if !is_get
+        || response.status() != StatusCode::OK
+        || response.body().size_hint().exact().is_none()
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+
+    let etag = compute_etag(&bytes);
+    let etag_value = HeaderValue::from_str(&etag).expect("hex ETag is a valid header value");
+
+    if if_none_match.is_some_and(|v| etag_matches(&v, &etag)) {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ETAG, etag_value);
+        return (StatusCode::NOT_MODIFIED, headers).into_response();
+    }
+
+    parts.headers.insert(header::ETAG, etag_value);
+    // Let browsers keep the payload but always revalidate before reusing it
+    parts
+        .headers
+        .entry(header::CACHE_CONTROL)
+        .or_insert(HeaderValue::from_static("private, no-cache"));
+
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_etag_is_stable() {
+        let a = compute_etag(b"[1,2,3]");
+        assert_eq!(a, compute_etag(b"[1,2,3]"));
+        assert_ne!(a, compute_etag(b"[1,2]"));
+        assert!(a.starts_with('"') && a.ends_with('"'));
+        assert_eq!(a.len(), 34);
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = compute_etag(b"{}");
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches(&format!("W/{}", etag), &etag));
+        assert!(etag_matches(&format!("\"other\", {}", etag), &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("\"other\"", &etag));
+    }
+}
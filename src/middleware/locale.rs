@@ -0,0 +1,45 @@
+use axum::{
+    extract::{Request, State},
+    http::header::ACCEPT_LANGUAGE,
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::extract::CookieJar;
+
+use crate::i18n::{self, Locale};
+use crate::middleware::auth::SESSION_COOKIE_NAME;
+use crate::models::user_settings;
+use crate::AppState;
+
+/// Locale chosen in the signed-in user's settings
+async fn user_locale(state: &AppState, jar: &CookieJar) -> Option<Locale> {
+    let token = jar.get(SESSION_COOKIE_NAME)?.value().to_string();
+    let locale = state
+        .db
+        .read(move |conn| user_settings::find_locale_by_session(conn, &token))
+        .await
+        .ok()?
+        .ok()??;
+    Locale::parse(&locale)
+}
+
+/// Make the locale of the request available to `i18n::current()` for the rest
+/// of the request: the user's setting if signed in and set, otherwise the
+/// best match of `Accept-Language`, otherwise English
+pub async fn scope_locale(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    request: Request,
+    next: Next,
+) -> Response {
+    let locale = match user_locale(&state, &jar).await {
+        Some(locale) => locale,
+        None => request
+            .headers()
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(Locale::from_accept_language)
+            .unwrap_or_default(),
+    };
+    i18n::scope(locale, next.run(request)).await
+}
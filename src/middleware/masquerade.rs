@@ -0,0 +1,89 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+use tracing::error;
+
+use crate::models::masquerade_audit;
+use crate::AppState;
+
+/// Header naming the user a masquerading admin is acting as
+pub const MASQUERADING_AS_HEADER: HeaderName = HeaderName::from_static("x-masquerading-as");
+
+/// Who is acting as whom in a masquerading session
+#[derive(Debug, Clone)]
+pub struct MasqueradeContext {
+    pub admin_id: i64,
+    pub user_id: i64,
+    pub username: String,
+}
+
+/// Slot the auth extractors fill in when the session is masquerading, so the
+/// request can be audited and the response labelled
+#[derive(Debug, Clone, Default)]
+pub struct Masquerading(Arc<Mutex<Option<MasqueradeContext>>>);
+
+impl Masquerading {
+    pub fn set(&self, context: MasqueradeContext) {
+        *self.0.lock().unwrap() = Some(context);
+    }
+
+    fn take(&self) -> Option<MasqueradeContext> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+fn is_mutating(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Label responses to masquerading sessions with `X-Masquerading-As`, and
+/// record every mutating request they make in the masquerade audit trail.
+/// Only the path is recorded; query strings may hold tokens.
+pub async fn track_masquerade(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let masquerading = Masquerading::default();
+    request.extensions_mut().insert(masquerading.clone());
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+
+    let mut response = next.run(request).await;
+
+    let Some(context) = masquerading.take() else {
+        return response;
+    };
+    let value = HeaderValue::from_str(&context.username)
+        .unwrap_or_else(|_| HeaderValue::from(context.user_id));
+    response.headers_mut().insert(MASQUERADING_AS_HEADER, value);
+
+    if is_mutating(&method) {
+        let status = response.status().as_u16();
+        let result = state
+            .db
+            .user(move |conn| {
+                masquerade_audit::record(
+                    conn,
+                    context.admin_id,
+                    context.user_id,
+                    method.as_str(),
+                    &path,
+                    status,
+                )
+            })
+            .await;
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => error!("Failed to record masqueraded request: {}", e),
+            Err(e) => error!("Failed to access DB for masquerade audit: {}", e),
+        }
+    }
+
+    response
+}
@@ -1,5 +1,12 @@
 pub mod auth;
+pub mod etag;
 pub mod flash;
+pub mod locale;
+pub mod masquerade;
+pub mod precondition;
+pub mod request_id;
+pub mod session;
 
 pub use auth::{AdminUser, AuthUser, PageAdminUser, PageAuthUser, SESSION_COOKIE_NAME};
 pub use flash::{Flash, FlashMessage, FlashRedirect, SetFlash, FLASH_COOKIE_NAME};
+pub use session::{session_cookie, SessionRefresh};
@@ -0,0 +1,104 @@
+//! Optimistic concurrency for clients editing from several devices.
+//!
+//! Entries, feeds and categories carry a version: an entry's `updated_seq`,
+//! and a counter on feeds and categories that triggers bump whenever a
+//! setting users edit changes. It is sent as the `ETag` of the resource, and
+//! a client that sends it back in `If-Match` has its change refused with a
+//! `412` and the current state if someone else got there first.
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderValue},
+};
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult, CurrentVersion};
+
+/// The version a client based its change on; absent (or `*`) skips the check
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IfMatch(pub Option<i64>);
+
+impl IfMatch {
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value == "*" {
+            return Some(IfMatch(None));
+        }
+        let version = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        version.parse().ok().map(|v| IfMatch(Some(v)))
+    }
+
+    /// Refuse the change unless the resource is still at the client's
+    /// version. `current` is only looked up on a mismatch.
+    pub fn check<T, F>(&self, version: i64, current: F) -> AppResult<()>
+    where
+        T: Serialize,
+        F: FnOnce() -> AppResult<T>,
+    {
+        match self.0 {
+            Some(expected) if expected != version => {
+                let current = serde_json::to_value(current()?)
+                    .map_err(|e| AppError::Internal(e.to_string()))?;
+                Err(AppError::VersionConflict(CurrentVersion {
+                    version,
+                    current,
+                }))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(header::IF_MATCH) else {
+            return Ok(IfMatch(None));
+        };
+        value
+            .to_str()
+            .ok()
+            .and_then(IfMatch::parse)
+            .ok_or_else(|| AppError::Validation("If-Match must be a version number".to_string()))
+    }
+}
+
+/// `ETag` header carrying a resource version
+pub fn version_etag(version: i64) -> [(header::HeaderName, HeaderValue); 1] {
+    let value = HeaderValue::from_str(&format!("\"{}\"", version))
+        .expect("quoted number is a valid header value");
+    [(header::ETAG, value)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(IfMatch::parse("\"42\"").unwrap().0, Some(42));
+        assert_eq!(IfMatch::parse("42").unwrap().0, Some(42));
+        assert_eq!(IfMatch::parse("*").unwrap().0, None);
+        assert!(IfMatch::parse("\"abc\"").is_none());
+        assert!(IfMatch::parse("W/\"42\"").is_none());
+    }
+
+    #[test]
+    fn test_check() {
+        assert!(IfMatch(None).check(3, || Ok(())).is_ok());
+        assert!(IfMatch(Some(3)).check(3, || Ok(())).is_ok());
+
+        let err = IfMatch(Some(2)).check(3, || Ok("now")).unwrap_err();
+        match err {
+            AppError::VersionConflict(conflict) => {
+                assert_eq!(conflict.version, 3);
+                assert_eq!(conflict.current, "now");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use axum::{extract::Request, http::HeaderName, middleware::Next, response::Response};
+use tracing::Span;
+
+/// Header carrying the per-request ID, set by `SetRequestIdLayer`
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// The ID of the request being handled, if called from within one
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+fn header_value(request: &Request) -> Option<&str> {
+    request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+}
+
+/// Make the request ID available to `current()` for the rest of the request,
+/// so error responses can include it
+pub async fn scope_request_id(request: Request, next: Next) -> Response {
+    match header_value(&request).map(str::to_string) {
+        Some(id) => REQUEST_ID.scope(id, next.run(request)).await,
+        None => next.run(request).await,
+    }
+}
+
+/// Tracing span for one HTTP request. Only the path is recorded; query
+/// strings may hold tokens.
+pub fn make_span(request: &Request) -> Span {
+    tracing::info_span!(
+        "request",
+        request_id = header_value(request).unwrap_or("-"),
+        method = %request.method(),
+        path = request.uri().path(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use axum::response::IntoResponse;
+    use http_body_util::BodyExt;
+
+    async fn error_body() -> serde_json::Value {
+        let response = AppError::Unauthorized.into_response();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_error_response_includes_request_id() {
+        let body = REQUEST_ID.scope("req-123".to_string(), error_body()).await;
+        assert_eq!(body["error"], "Unauthorized");
+        assert_eq!(body["request_id"], "req-123");
+    }
+
+    #[tokio::test]
+    async fn test_error_response_without_request_id() {
+        assert!(current().is_none());
+        let body = error_body().await;
+        assert!(body.get("request_id").is_none());
+    }
+}
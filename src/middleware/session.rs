@@ -0,0 +1,66 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::Request,
+    http::{header::SET_COOKIE, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::extract::cookie::{Cookie, SameSite};
+
+use super::auth::SESSION_COOKIE_NAME;
+
+/// Build the session cookie. With a `max_age` it lives as long as the session
+/// would if the user went idle now; without one it is a browser-session
+/// cookie that is dropped when the browser closes.
+pub fn session_cookie(token: String, max_age: Option<chrono::Duration>) -> Cookie<'static> {
+    let mut cookie = Cookie::build((SESSION_COOKIE_NAME, token))
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .build();
+    if let Some(max_age) = max_age {
+        cookie.set_max_age(time::Duration::seconds(max_age.num_seconds()));
+    }
+    cookie
+}
+
+/// Slot the auth extractors fill in when they renew a session, so the cookie
+/// can be re-issued with the renewed lifetime
+#[derive(Debug, Clone, Default)]
+pub struct SessionRefresh(Arc<Mutex<Option<Cookie<'static>>>>);
+
+impl SessionRefresh {
+    pub fn set(&self, cookie: Cookie<'static>) {
+        *self.0.lock().unwrap() = Some(cookie);
+    }
+
+    fn take(&self) -> Option<Cookie<'static>> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+/// Re-issue the session cookie when the request renewed the session
+pub async fn refresh_session_cookie(mut request: Request, next: Next) -> Response {
+    let refresh = SessionRefresh::default();
+    request.extensions_mut().insert(refresh.clone());
+
+    let mut response = next.run(request).await;
+
+    if let Some(cookie) = refresh.take() {
+        // Login and logout set the cookie themselves; theirs wins
+        let prefix = format!("{}=", SESSION_COOKIE_NAME);
+        let already_set = response
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .any(|v| v.as_bytes().starts_with(prefix.as_bytes()));
+        if !already_set {
+            if let Ok(value) = HeaderValue::from_str(&cookie.to_string()) {
+                response.headers_mut().append(SET_COOKIE, value);
+            }
+        }
+    }
+
+    response
+}
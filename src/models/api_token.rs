@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+use crate::models::session::generate_token;
+
+/// A named token that authenticates a user outside a browser session, such
+/// as the save bookmarklet
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiToken {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub token: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").map(|dt| dt.and_utc())
+        })
+        .or_else(|_| dateparser::parse(s).map(|dt| dt.with_timezone(&Utc)))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_token(row: &rusqlite::Row) -> rusqlite::Result<ApiToken> {
+    let created_at: String = row.get(4)?;
+    let last_used_at: Option<String> = row.get(5)?;
+
+    Ok(ApiToken {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        name: row.get(2)?,
+        token: row.get(3)?,
+        created_at: parse_datetime(&created_at),
+        last_used_at: last_used_at.map(|s| parse_datetime(&s)),
+    })
+}
+
+const SELECT_COLUMNS: &str = "id, user_id, name, token, created_at, last_used_at";
+
+pub fn create_token(conn: &Connection, user_id: i64, name: &str) -> AppResult<ApiToken> {
+    let token = generate_token();
+    conn.execute(
+        "INSERT INTO api_token (user_id, name, token) VALUES (?1, ?2, ?3)",
+        params![user_id, name, token],
+    )?;
+
+    find_by_token(conn, &token)?.ok_or(AppError::Internal("Failed to create API token".to_string()))
+}
+
+pub fn find_by_token(conn: &Connection, token: &str) -> AppResult<Option<ApiToken>> {
+    conn.query_row(
+        &format!("SELECT {} FROM api_token WHERE token = ?1", SELECT_COLUMNS),
+        params![token],
+        row_to_token,
+    )
+    .optional()
+    .map_err(AppError::Database)
+}
+
+pub fn list_by_user(conn: &Connection, user_id: i64) -> AppResult<Vec<ApiToken>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM api_token WHERE user_id = ?1 ORDER BY created_at DESC, id DESC",
+        SELECT_COLUMNS
+    ))?;
+    let tokens = stmt
+        .query_map(params![user_id], row_to_token)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(tokens)
+}
+
+/// Record that the token was just used to authenticate
+pub fn touch(conn: &Connection, id: i64) -> AppResult<()> {
+    conn.execute(
+        "UPDATE api_token SET last_used_at = datetime('now') WHERE id = ?1",
+        params![id],
+    )?;
+    Ok(())
+}
+
+/// Revoke one of the user's tokens
+pub fn delete_user_token(conn: &Connection, user_id: i64, id: i64) -> AppResult<bool> {
+    let rows = conn.execute(
+        "DELETE FROM api_token WHERE id = ?1 AND user_id = ?2",
+        params![id, user_id],
+    )?;
+    Ok(rows > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::{self, Role};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_create_find_and_delete_token() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+        let other = user::create_user(&conn, "other", "hash", Role::User).unwrap();
+
+        let token = create_token(&conn, user.id, "Bookmarklet").unwrap();
+        assert_eq!(token.name, "Bookmarklet");
+        assert!(token.last_used_at.is_none());
+
+        let found = find_by_token(&conn, &token.token).unwrap().unwrap();
+        assert_eq!(found.user_id, user.id);
+        assert!(find_by_token(&conn, "missing").unwrap().is_none());
+
+        touch(&conn, token.id).unwrap();
+        let touched = find_by_token(&conn, &token.token).unwrap().unwrap();
+        assert!(touched.last_used_at.is_some());
+
+        assert_eq!(list_by_user(&conn, user.id).unwrap().len(), 1);
+        assert!(!delete_user_token(&conn, other.id, token.id).unwrap());
+        assert!(delete_user_token(&conn, user.id, token.id).unwrap());
+        assert!(find_by_token(&conn, &token.token).unwrap().is_none());
+    }
+}
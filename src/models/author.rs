@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::AppResult;
+
+/// A person credited on a feed's entries. Authors are scoped to a feed and
+/// identified by name, so group blogs list each contributor once.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Author {
+    pub id: i64,
+    pub feed_id: i64,
+    pub name: String,
+    pub email: Option<String>,
+    pub uri: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+/// Author details parsed from a feed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthorInput {
+    pub name: String,
+    pub email: Option<String>,
+    pub uri: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+fn row_to_author(row: &rusqlite::Row) -> rusqlite::Result<Author> {
+    Ok(Author {
+        id: row.get(0)?,
+        feed_id: row.get(1)?,
+        name: row.get(2)?,
+        email: row.get(3)?,
+        uri: row.get(4)?,
+        avatar_url: row.get(5)?,
+    })
+}
+
+/// Insert or refresh an author of a feed. Details missing from `input` keep
+/// their stored values, since not every entry repeats them.
+pub fn upsert_author(conn: &Connection, feed_id: i64, input: &AuthorInput) -> AppResult<Author> {
+    let author = conn.query_row(
+        r#"
+        INSERT INTO author (feed_id, name, email, uri, avatar_url)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        ON CONFLICT(feed_id, name) DO UPDATE SET
+            email = COALESCE(excluded.email, author.email),
+            uri = COALESCE(excluded.uri, author.uri),
+            avatar_url = COALESCE(excluded.avatar_url, author.avatar_url),
+            updated_at = datetime('now')
+        RETURNING id, feed_id, name, email, uri, avatar_url
+        "#,
+        params![
+            feed_id,
+            input.name,
+            input.email,
+            input.uri,
+            input.avatar_url
+        ],
+        row_to_author,
+    )?;
+    Ok(author)
+}
+
+/// Replace the credited authors of an entry, keeping the given order
+pub fn set_entry_authors(conn: &Connection, entry_id: i64, author_ids: &[i64]) -> AppResult<()> {
+    conn.execute(
+        "DELETE FROM entry_author WHERE entry_id = ?1",
+        params![entry_id],
+    )?;
+    for (position, author_id) in author_ids.iter().enumerate() {
+        conn.execute(
+            "INSERT OR IGNORE INTO entry_author (entry_id, author_id, position) \
+             VALUES (?1, ?2, ?3)",
+            params![entry_id, author_id, position as i64],
+        )?;
+    }
+    Ok(())
+}
+
+/// Authors of each of the given entries, in credit order
+pub fn list_by_entries(
+    conn: &Connection,
+    entry_ids: &[i64],
+) -> AppResult<HashMap<i64, Vec<Author>>> {
+    if entry_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let placeholders: Vec<String> = (1..=entry_ids.len()).map(|i| format!("?{}", i)).collect();
+    let sql = format!(
+        r#"
+        SELECT a.id, a.feed_id, a.name, a.email, a.uri, a.avatar_url, ea.entry_id
+        FROM entry_author ea
+        INNER JOIN author a ON a.id = ea.author_id
+        WHERE ea.entry_id IN ({})
+        ORDER BY ea.entry_id, ea.position
+        "#,
+        placeholders.join(", ")
+    );
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(entry_ids), |row| {
+        let entry_id: i64 = row.get(6)?;
+        Ok((entry_id, row_to_author(row)?))
+    })?;
+
+    let mut map: HashMap<i64, Vec<Author>> = HashMap::new();
+    for row in rows {
+        let (entry_id, author) = row?;
+        map.entry(entry_id).or_default().push(author);
+    }
+    Ok(map)
+}
+
+pub fn list_by_entry(conn: &Connection, entry_id: i64) -> AppResult<Vec<Author>> {
+    Ok(list_by_entries(conn, &[entry_id])?
+        .remove(&entry_id)
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, entry, feed, user};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn create_feed(conn: &Connection) -> i64 {
+        let user = user::create_user(conn, "testuser", "hash", user::Role::User).unwrap();
+        let cat = category::create_category(conn, user.id, "Tech").unwrap();
+        feed::create_feed(
+            conn,
+            cat.id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .id
+    }
+
+    fn create_entry(conn: &Connection, feed_id: i64, guid: &str) -> i64 {
+        let (e, _) =
+            entry::upsert_entry(conn, feed_id, guid, None, None, None, None, None, None).unwrap();
+        e.id
+    }
+
+    #[test]
+    fn test_upsert_author_keeps_known_details() {
+        let conn = setup_db();
+        let feed_id = create_feed(&conn);
+
+        let first = upsert_author(
+            &conn,
+            feed_id,
+            &AuthorInput {
+                name: "Alice".to_string(),
+                email: Some("alice@example.com".to_string()),
+                avatar_url: Some("https://example.com/alice.png".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let again = upsert_author(
+            &conn,
+            feed_id,
+            &AuthorInput {
+                name: "Alice".to_string(),
+                uri: Some("https://alice.example.com".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(again.id, first.id);
+        assert_eq!(again.email.as_deref(), Some("alice@example.com"));
+        assert_eq!(again.uri.as_deref(), Some("https://alice.example.com"));
+        assert_eq!(
+            again.avatar_url.as_deref(),
+            Some("https://example.com/alice.png")
+        );
+    }
+
+    #[test]
+    fn test_set_and_list_entry_authors() {
+        let conn = setup_db();
+        let feed_id = create_feed(&conn);
+        let entry_a = create_entry(&conn, feed_id, "a");
+        let entry_b = create_entry(&conn, feed_id, "b");
+
+        let alice = upsert_author(
+            &conn,
+            feed_id,
+            &AuthorInput {
+                name: "Alice".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let bob = upsert_author(
+            &conn,
+            feed_id,
+            &AuthorInput {
+                name: "Bob".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        set_entry_authors(&conn, entry_a, &[bob.id, alice.id]).unwrap();
+        set_entry_authors(&conn, entry_b, &[alice.id]).unwrap();
+
+        let names: Vec<String> = list_by_entry(&conn, entry_a)
+            .unwrap()
+            .into_iter()
+            .map(|a| a.name)
+            .collect();
+        assert_eq!(names, vec!["Bob", "Alice"]);
+
+        let by_entry = list_by_entries(&conn, &[entry_a, entry_b]).unwrap();
+        assert_eq!(by_entry[&entry_b], vec![alice.clone()]);
+
+        // Replacing the credits drops the previous ones
+        set_entry_authors(&conn, entry_a, &[alice.id]).unwrap();
+        assert_eq!(list_by_entry(&conn, entry_a).unwrap(), vec![alice]);
+        assert!(list_by_entries(&conn, &[]).unwrap().is_empty());
+    }
+}
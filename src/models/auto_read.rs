@@ -0,0 +1,159 @@
+//! Auto-mark-read rules: unread entries older than a number of days are
+//! marked read. A feed's own rule overrides its category's. Starred entries
+//! are never touched.
+
+use rusqlite::{params, Connection};
+
+use crate::error::{AppError, AppResult};
+
+/// Longest rule accepted (about ten years)
+pub const MAX_AUTO_READ_DAYS: i64 = 3650;
+
+/// Check a rule from the API, where `0` clears the rule
+pub fn parse_days(days: i64) -> AppResult<Option<i64>> {
+    match days {
+        0 => Ok(None),
+        1..=MAX_AUTO_READ_DAYS => Ok(Some(days)),
+        _ => Err(AppError::Validation(format!(
+            "Auto-mark-read days must be between 0 and {}",
+            MAX_AUTO_READ_DAYS
+        ))),
+    }
+}
+
+fn threshold(days: i64) -> String {
+    format!("-{} days", days)
+}
+
+/// Number of entries a `days` rule on the feed would mark read now
+pub fn preview_feed(conn: &Connection, feed_id: i64, days: i64) -> AppResult<i64> {
+    let count = conn.query_row(
+        r#"
+        SELECT COUNT(*) FROM entry
+        WHERE feed_id = ?1 AND read_at IS NULL AND starred_at IS NULL
+          AND COALESCE(published_at, created_at) < datetime('now', ?2)
+        "#,
+        params![feed_id, threshold(days)],
+        |row| row.get(0),
+    )?;
+    Ok(count)
+}
+
+/// Number of entries a `days` rule on the category would mark read now.
+/// Feeds with their own rule are not affected by the category's.
+pub fn preview_category(conn: &Connection, category_id: i64, days: i64) -> AppResult<i64> {
+    let count = conn.query_row(
+        r#"
+        SELECT COUNT(*) FROM entry e
+        INNER JOIN feed f ON e.feed_id = f.id
+        WHERE f.category_id = ?1 AND f.auto_read_days IS NULL AND f.deleted_at IS NULL
+          AND e.read_at IS NULL AND e.starred_at IS NULL
+          AND COALESCE(e.published_at, e.created_at) < datetime('now', ?2)
+        "#,
+        params![category_id, threshold(days)],
+        |row| row.get(0),
+    )?;
+    Ok(count)
+}
+
+/// Mark read every entry past its feed's or category's rule. Returns the
+/// number of entries marked.
+pub fn apply_rules(conn: &Connection) -> AppResult<usize> {
+    // Without a rule the threshold is NULL and nothing matches
+    let rows = conn.execute(
+        r#"
+        UPDATE entry
+        SET read_at = datetime('now'), updated_at = datetime('now')
+        WHERE read_at IS NULL AND starred_at IS NULL
+          AND COALESCE(published_at, created_at) < (
+            SELECT datetime('now', '-' || COALESCE(f.auto_read_days, c.auto_read_days) || ' days')
+            FROM feed f
+            INNER JOIN category c ON f.category_id = c.id
+            WHERE f.id = entry.feed_id AND f.deleted_at IS NULL
+          )
+        "#,
+        [],
+    )?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, feed, user};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn create_feed(conn: &Connection, category_id: i64, url: &str) -> i64 {
+        feed::create_feed(conn, category_id, url, None, None, None, None, None)
+            .unwrap()
+            .id
+    }
+
+    fn insert_entry(conn: &Connection, feed_id: i64, guid: &str, age: &str, starred: bool) {
+        conn.execute(
+            r#"
+            INSERT INTO entry (feed_id, guid, published_at, starred_at)
+            VALUES (?1, ?2, datetime('now', ?3), CASE WHEN ?4 THEN datetime('now') END)
+            "#,
+            params![feed_id, guid, age, starred],
+        )
+        .unwrap();
+    }
+
+    fn unread(conn: &Connection, feed_id: i64) -> i64 {
+        conn.query_row(
+            "SELECT COUNT(*) FROM entry WHERE feed_id = ?1 AND read_at IS NULL",
+            params![feed_id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_days() {
+        assert_eq!(parse_days(0).unwrap(), None);
+        assert_eq!(parse_days(7).unwrap(), Some(7));
+        assert!(parse_days(-1).is_err());
+        assert!(parse_days(MAX_AUTO_READ_DAYS + 1).is_err());
+    }
+
+    #[test]
+    fn test_preview_and_apply_rules() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", user::Role::User).unwrap();
+        let cat = category::create_category(&conn, user.id, "News").unwrap();
+        let busy = create_feed(&conn, cat.id, "https://busy.example.com/feed");
+        let slow = create_feed(&conn, cat.id, "https://slow.example.com/feed");
+
+        for feed_id in [busy, slow] {
+            insert_entry(&conn, feed_id, "new", "-1 days", false);
+            insert_entry(&conn, feed_id, "old", "-10 days", false);
+            insert_entry(&conn, feed_id, "older", "-40 days", false);
+            insert_entry(&conn, feed_id, "starred", "-40 days", true);
+        }
+
+        // No rules yet
+        assert_eq!(apply_rules(&conn).unwrap(), 0);
+
+        assert_eq!(preview_feed(&conn, busy, 7).unwrap(), 2);
+        assert_eq!(preview_category(&conn, cat.id, 30).unwrap(), 2);
+
+        // The feed rule takes precedence over the category rule
+        feed::set_auto_read_days(&conn, busy, Some(7)).unwrap();
+        category::set_auto_read_days(&conn, cat.id, user.id, Some(30)).unwrap();
+        assert_eq!(preview_category(&conn, cat.id, 30).unwrap(), 1);
+
+        assert_eq!(apply_rules(&conn).unwrap(), 3);
+        assert_eq!(unread(&conn, busy), 2);
+        assert_eq!(unread(&conn, slow), 3);
+
+        // Already read entries are left alone
+        assert_eq!(apply_rules(&conn).unwrap(), 0);
+    }
+}
@@ -0,0 +1,189 @@
+//! Hosts and URL prefixes blocked for every user, set by an admin or
+//! imported from a hosts file; see `services::blocklist`.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::AppResult;
+
+/// Where a blocked pattern came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Entered by an admin
+    Manual,
+    /// Read from the import URL; replaced by each import
+    Import,
+}
+
+impl Source {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Source::Manual => "manual",
+            Source::Import => "import",
+        }
+    }
+}
+
+/// The hosts file the blocklist is imported from, and how the last import went
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportStatus {
+    pub url: Option<String>,
+    pub imported_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .unwrap_or_else(|_| Utc::now())
+}
+
+/// Patterns from `source`, in alphabetical order
+pub fn list_patterns(conn: &Connection, source: Source) -> AppResult<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT pattern FROM blocked_host WHERE source = ?1 ORDER BY pattern")?;
+    let patterns = stmt
+        .query_map(params![source.as_str()], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(patterns)
+}
+
+/// Every blocked pattern, whatever its source
+pub fn list_all(conn: &Connection) -> AppResult<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT DISTINCT pattern FROM blocked_host")?;
+    let patterns = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(patterns)
+}
+
+pub fn count(conn: &Connection, source: Source) -> AppResult<i64> {
+    let count = conn.query_row(
+        "SELECT COUNT(*) FROM blocked_host WHERE source = ?1",
+        params![source.as_str()],
+        |row| row.get(0),
+    )?;
+    Ok(count)
+}
+
+fn insert_patterns(conn: &Connection, source: Source, patterns: &[String]) -> AppResult<()> {
+    conn.execute(
+        "DELETE FROM blocked_host WHERE source = ?1",
+        params![source.as_str()],
+    )?;
+    let mut insert =
+        conn.prepare("INSERT OR IGNORE INTO blocked_host (pattern, source) VALUES (?1, ?2)")?;
+    for pattern in patterns {
+        insert.execute(params![pattern, source.as_str()])?;
+    }
+    Ok(())
+}
+
+/// Replace the patterns from `source` with `patterns`
+pub fn replace_patterns(conn: &Connection, source: Source, patterns: &[String]) -> AppResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    insert_patterns(&tx, source, patterns)?;
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn import_status(conn: &Connection) -> AppResult<ImportStatus> {
+    let status = conn
+        .query_row(
+            "SELECT url, imported_at, last_error FROM blocklist_import WHERE id = 1",
+            [],
+            |row| {
+                let imported_at: Option<String> = row.get(1)?;
+                Ok(ImportStatus {
+                    url: row.get(0)?,
+                    imported_at: imported_at.as_deref().map(parse_datetime),
+                    last_error: row.get(2)?,
+                })
+            },
+        )
+        .optional()?;
+    Ok(status.unwrap_or_default())
+}
+
+/// Set the URL to import from. Without one, imported patterns are dropped.
+pub fn set_import_url(conn: &Connection, url: Option<&str>) -> AppResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT INTO blocklist_import (id, url) VALUES (1, ?1) \
+         ON CONFLICT(id) DO UPDATE SET url = excluded.url, \
+         last_error = CASE WHEN url IS excluded.url THEN last_error END",
+        params![url],
+    )?;
+    if url.is_none() {
+        insert_patterns(&tx, Source::Import, &[])?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Record the outcome of an import: `Ok` replaces the imported patterns
+pub fn record_import(conn: &Connection, result: Result<&[String], &str>) -> AppResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    match result {
+        Ok(patterns) => {
+            insert_patterns(&tx, Source::Import, patterns)?;
+            tx.execute(
+                "UPDATE blocklist_import SET imported_at = datetime('now'), last_error = NULL \
+                 WHERE id = 1",
+                [],
+            )?;
+        }
+        Err(error) => {
+            tx.execute(
+                "UPDATE blocklist_import SET last_error = ?1 WHERE id = 1",
+                params![error],
+            )?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+
+    fn patterns(list: &[&str]) -> Vec<String> {
+        list.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_sources_are_kept_apart() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+
+        replace_patterns(&conn, Source::Manual, &patterns(&["b.test", "a.test"])).unwrap();
+        set_import_url(&conn, Some("https://lists.test/hosts")).unwrap();
+        record_import(&conn, Ok(patterns(&["a.test", "ads.test"]).as_slice())).unwrap();
+
+        assert_eq!(
+            list_patterns(&conn, Source::Manual).unwrap(),
+            vec!["a.test", "b.test"]
+        );
+        assert_eq!(count(&conn, Source::Import).unwrap(), 2);
+        let mut all = list_all(&conn).unwrap();
+        all.sort();
+        assert_eq!(all, vec!["a.test", "ads.test", "b.test"]);
+
+        // A failed import keeps the last good list
+        record_import(&conn, Err("HTTP 404")).unwrap();
+        let status = import_status(&conn).unwrap();
+        assert_eq!(status.url.as_deref(), Some("https://lists.test/hosts"));
+        assert_eq!(status.last_error.as_deref(), Some("HTTP 404"));
+        assert!(status.imported_at.is_some());
+        assert_eq!(count(&conn, Source::Import).unwrap(), 2);
+
+        // Dropping the URL drops what was imported from it
+        set_import_url(&conn, None).unwrap();
+        assert_eq!(count(&conn, Source::Import).unwrap(), 0);
+        assert_eq!(count(&conn, Source::Manual).unwrap(), 2);
+        assert!(import_status(&conn).unwrap().last_error.is_none());
+    }
+}
@@ -1,8 +1,11 @@
+use std::collections::HashSet;
+
 use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::Serialize;
 
 use crate::error::{AppError, AppResult};
+use crate::models::image;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Category {
@@ -10,6 +13,113 @@ pub struct Category {
     pub user_id: i64,
     pub name: String,
     pub created_at: DateTime<Utc>,
+    /// Unread entries older than this many days are marked read, unless
+    /// their feed has its own rule
+    pub auto_read_days: Option<i64>,
+    /// Set while the category is in the trash
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// `#rrggbb` color shown next to the name
+    pub color: Option<String>,
+    /// An emoji, or the name of one of the `BUILTIN_ICONS`
+    pub icon: Option<String>,
+}
+
+/// Name of the category feeds go in when none is chosen
+pub const UNCATEGORIZED: &str = "Uncategorized";
+
+/// Icons that can be chosen by name, with the symbol each one shows as
+pub const BUILTIN_ICONS: &[(&str, &str)] = &[
+    ("news", "📰"),
+    ("tech", "💻"),
+    ("code", "⌨️"),
+    ("science", "🔬"),
+    ("books", "📚"),
+    ("music", "🎵"),
+    ("video", "🎬"),
+    ("games", "🎮"),
+    ("sports", "⚽"),
+    ("food", "🍳"),
+    ("travel", "✈️"),
+    ("finance", "💰"),
+    ("art", "🎨"),
+    ("photo", "📷"),
+    ("podcast", "🎙️"),
+    ("writing", "✍️"),
+    ("world", "🌍"),
+    ("security", "🔒"),
+    ("work", "💼"),
+    ("home", "🏠"),
+    ("star", "⭐"),
+    ("heart", "❤️"),
+];
+
+/// An emoji is a few code points at most, even with modifiers and joiners
+const MAX_EMOJI_CHARS: usize = 10;
+
+impl Category {
+    /// The symbol to show for the category's icon
+    pub fn icon_symbol(&self) -> Option<String> {
+        self.icon
+            .as_deref()
+            .map(|icon| icon_symbol(icon).to_string())
+    }
+}
+
+/// The symbol a stored icon shows as: a built-in icon's symbol, or the emoji
+/// itself
+pub fn icon_symbol(icon: &str) -> &str {
+    BUILTIN_ICONS
+        .iter()
+        .find(|(name, _)| *name == icon)
+        .map_or(icon, |(_, symbol)| symbol)
+}
+
+/// Check a color from the API, where an empty string clears it. Accepts
+/// `#rgb` and `#rrggbb`, stored as lowercase `#rrggbb`.
+pub fn parse_color(color: &str) -> AppResult<Option<String>> {
+    let color = color.trim();
+    if color.is_empty() {
+        return Ok(None);
+    }
+
+    let hex = color
+        .strip_prefix('#')
+        .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()));
+    match hex {
+        Some(hex) if hex.len() == 6 => Ok(Some(format!("#{}", hex.to_ascii_lowercase()))),
+        Some(hex) if hex.len() == 3 => {
+            let expanded: String = hex.chars().flat_map(|c| [c, c]).collect();
+            Ok(Some(format!("#{}", expanded.to_ascii_lowercase())))
+        }
+        _ => Err(AppError::Validation(
+            "Color must be a hex color such as #3366cc".to_string(),
+        )),
+    }
+}
+
+/// Check an icon from the API, where an empty string clears it
+pub fn parse_icon(icon: &str) -> AppResult<Option<String>> {
+    let icon = icon.trim();
+    if icon.is_empty() {
+        return Ok(None);
+    }
+    if BUILTIN_ICONS.iter().any(|(name, _)| *name == icon) {
+        return Ok(Some(icon.to_string()));
+    }
+
+    // Anything else has to look like an emoji rather than text
+    let is_emoji = icon.chars().count() <= MAX_EMOJI_CHARS
+        && !icon.is_ascii()
+        && !icon
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c.is_alphabetic());
+    if is_emoji {
+        Ok(Some(icon.to_string()))
+    } else {
+        Err(AppError::Validation(
+            "Icon must be an emoji or the name of a built-in icon".to_string(),
+        ))
+    }
 }
 
 fn parse_datetime(s: &str) -> DateTime<Utc> {
@@ -24,16 +134,46 @@ fn parse_datetime(s: &str) -> DateTime<Utc> {
 
 fn row_to_category(row: &rusqlite::Row) -> rusqlite::Result<Category> {
     let created_at: String = row.get(3)?;
+    let deleted_at: Option<String> = row.get(5)?;
 
     Ok(Category {
         id: row.get(0)?,
         user_id: row.get(1)?,
         name: row.get(2)?,
         created_at: parse_datetime(&created_at),
+        auto_read_days: row.get(4)?,
+        deleted_at: deleted_at.map(|s| parse_datetime(&s)),
+        color: row.get(6)?,
+        icon: row.get(7)?,
     })
 }
 
+const SELECT_COLUMNS: &str =
+    "id, user_id, name, created_at, auto_read_days, deleted_at, color, icon";
+
+/// Categories the user has arranged come first in that order; the rest
+/// follow by name
+const ORDER_BY: &str = "sort_order ASC NULLS LAST, name ASC";
+
+/// The user's trashed category named `name`. It still holds the name until
+/// it is purged.
+fn find_trashed_by_name(conn: &Connection, user_id: i64, name: &str) -> AppResult<Option<i64>> {
+    let id = conn
+        .query_row(
+            "SELECT id FROM category WHERE user_id = ?1 AND name = ?2 AND deleted_at IS NOT NULL",
+            params![user_id, name],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(id)
+}
+
+/// Create a category. A trashed category of the same name is restored with
+/// its feeds instead.
 pub fn create_category(conn: &Connection, user_id: i64, name: &str) -> AppResult<Category> {
+    if let Some(id) = find_trashed_by_name(conn, user_id, name)? {
+        return restore_category(conn, id, user_id);
+    }
     let result = conn.execute(
         "INSERT INTO category (user_id, name) VALUES (?1, ?2)",
         params![user_id, name],
@@ -53,9 +193,24 @@ pub fn create_category(conn: &Connection, user_id: i64, name: &str) -> AppResult
     }
 }
 
+/// The category's version for `If-Match`, bumped when a setting users edit
+/// changes
+pub fn version(conn: &Connection, id: i64) -> AppResult<i64> {
+    conn.query_row(
+        "SELECT version FROM category WHERE id = ?1 AND deleted_at IS NULL",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()?
+    .ok_or(AppError::CategoryNotFound)
+}
+
 pub fn find_by_id(conn: &Connection, id: i64) -> AppResult<Option<Category>> {
     conn.query_row(
-        "SELECT id, user_id, name, created_at FROM category WHERE id = ?1",
+        &format!(
+            "SELECT {} FROM category WHERE id = ?1 AND deleted_at IS NULL",
+            SELECT_COLUMNS
+        ),
         params![id],
         row_to_category,
     )
@@ -69,7 +224,10 @@ pub fn find_by_id_and_user(
     user_id: i64,
 ) -> AppResult<Option<Category>> {
     conn.query_row(
-        "SELECT id, user_id, name, created_at FROM category WHERE id = ?1 AND user_id = ?2",
+        &format!(
+            "SELECT {} FROM category WHERE id = ?1 AND user_id = ?2 AND deleted_at IS NULL",
+            SELECT_COLUMNS
+        ),
         params![id, user_id],
         row_to_category,
     )
@@ -83,7 +241,10 @@ pub fn find_by_name_and_user(
     user_id: i64,
 ) -> AppResult<Option<Category>> {
     conn.query_row(
-        "SELECT id, user_id, name, created_at FROM category WHERE name = ?1 AND user_id = ?2",
+        &format!(
+            "SELECT {} FROM category WHERE name = ?1 AND user_id = ?2 AND deleted_at IS NULL",
+            SELECT_COLUMNS
+        ),
         params![name, user_id],
         row_to_category,
     )
@@ -91,10 +252,40 @@ pub fn find_by_name_and_user(
     .map_err(AppError::Database)
 }
 
+/// The category a feed goes in: `category_id` if it is one of the user's,
+/// or with `None`, the user's Uncategorized category, created when first
+/// needed.
+///
+/// Uncategorized is an ordinary category row found by its name rather than
+/// a NULL `category_id`, since every ownership check joins a feed to its
+/// category. `update_name` keeps the name reserved, and a trashed
+/// Uncategorized comes back empty here instead of with the feeds deleted
+/// with it.
+pub fn resolve(conn: &Connection, user_id: i64, category_id: Option<i64>) -> AppResult<i64> {
+    match category_id {
+        Some(id) => find_by_id_and_user(conn, id, user_id)?
+            .map(|cat| cat.id)
+            .ok_or(AppError::CategoryNotFound),
+        None => {
+            if let Some(cat) = find_by_name_and_user(conn, UNCATEGORIZED, user_id)? {
+                return Ok(cat.id);
+            }
+            match find_trashed_by_name(conn, user_id, UNCATEGORIZED)? {
+                Some(id) => {
+                    undelete(conn, id)?;
+                    Ok(id)
+                }
+                None => Ok(create_category(conn, user_id, UNCATEGORIZED)?.id),
+            }
+        }
+    }
+}
+
 pub fn list_by_user(conn: &Connection, user_id: i64) -> AppResult<Vec<Category>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, user_id, name, created_at FROM category WHERE user_id = ?1 ORDER BY name ASC",
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM category WHERE user_id = ?1 AND deleted_at IS NULL ORDER BY {}",
+        SELECT_COLUMNS, ORDER_BY
+    ))?;
 
     let categories = stmt
         .query_map(params![user_id], row_to_category)?
@@ -110,8 +301,16 @@ pub fn update_name(
     user_id: i64,
     new_name: &str,
 ) -> AppResult<Category> {
+    let current = find_by_id_and_user(conn, id, user_id)?.ok_or(AppError::CategoryNotFound)?;
+    if current.name != new_name && (current.name == UNCATEGORIZED || new_name == UNCATEGORIZED) {
+        return Err(AppError::Validation(format!(
+            "The {} category cannot be renamed, and no other category can take its name",
+            UNCATEGORIZED
+        )));
+    }
+
     let result = conn.execute(
-        "UPDATE category SET name = ?1 WHERE id = ?2 AND user_id = ?3",
+        "UPDATE category SET name = ?1 WHERE id = ?2 AND user_id = ?3 AND deleted_at IS NULL",
         params![new_name, id, user_id],
     );
 
@@ -127,18 +326,177 @@ pub fn update_name(
     }
 }
 
+/// `current` with `ids` moved to the front in the order given; the rest keep
+/// their order. `missing` is returned for an ID not in `current`.
+pub(crate) fn move_to_front(
+    current: &[i64],
+    ids: &[i64],
+    missing: AppError,
+) -> AppResult<Vec<i64>> {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if !seen.insert(*id) {
+            return Err(AppError::Validation(format!(
+                "ID {} is listed more than once",
+                id
+            )));
+        }
+        if !current.contains(id) {
+            return Err(missing);
+        }
+    }
+
+    let rest = current.iter().filter(|id| !seen.contains(*id));
+    Ok(ids.iter().chain(rest).copied().collect())
+}
+
+/// Arrange the user's categories in the order of `ids`; categories left out
+/// follow in their current order
+pub fn reorder(conn: &Connection, user_id: i64, ids: &[i64]) -> AppResult<()> {
+    let current: Vec<i64> = list_by_user(conn, user_id)?.iter().map(|c| c.id).collect();
+    let order = move_to_front(&current, ids, AppError::CategoryNotFound)?;
+
+    let tx = conn.unchecked_transaction()?;
+    for (position, id) in order.iter().enumerate() {
+        tx.execute(
+            "UPDATE category SET sort_order = ?1 WHERE id = ?2",
+            params![position as i64, id],
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Set or clear (`None`) the category's auto-mark-read rule
+pub fn set_auto_read_days(
+    conn: &Connection,
+    id: i64,
+    user_id: i64,
+    days: Option<i64>,
+) -> AppResult<Category> {
+    let rows = conn.execute(
+        "UPDATE category SET auto_read_days = ?1 WHERE id = ?2 AND user_id = ?3 AND deleted_at IS NULL",
+        params![days, id, user_id],
+    )?;
+
+    if rows == 0 {
+        return Err(AppError::CategoryNotFound);
+    }
+    find_by_id(conn, id)?.ok_or(AppError::CategoryNotFound)
+}
+
+/// Set the category's color and icon, `None` clearing either
+pub fn set_appearance(
+    conn: &Connection,
+    id: i64,
+    user_id: i64,
+    color: Option<&str>,
+    icon: Option<&str>,
+) -> AppResult<Category> {
+    let rows = conn.execute(
+        "UPDATE category SET color = ?1, icon = ?2 WHERE id = ?3 AND user_id = ?4 AND deleted_at IS NULL",
+        params![color, icon, id, user_id],
+    )?;
+
+    if rows == 0 {
+        return Err(AppError::CategoryNotFound);
+    }
+    find_by_id(conn, id)?.ok_or(AppError::CategoryNotFound)
+}
+
+/// Move the category to the trash along with its feeds. Feeds already in
+/// the trash keep their own deletion time, so restoring the category only
+/// brings back the feeds deleted with it.
 pub fn delete_category(conn: &Connection, id: i64, user_id: i64) -> AppResult<()> {
+    let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
     let rows = conn.execute(
-        "DELETE FROM category WHERE id = ?1 AND user_id = ?2",
-        params![id, user_id],
+        "UPDATE category SET deleted_at = ?1 WHERE id = ?2 AND user_id = ?3 AND deleted_at IS NULL",
+        params![now, id, user_id],
     )?;
 
     if rows == 0 {
         return Err(AppError::CategoryNotFound);
     }
+
+    conn.execute(
+        "UPDATE feed SET deleted_at = ?1 WHERE category_id = ?2 AND deleted_at IS NULL",
+        params![now, id],
+    )?;
+    Ok(())
+}
+
+/// The user's categories in the trash, most recently deleted first
+pub fn list_trashed_by_user(conn: &Connection, user_id: i64) -> AppResult<Vec<Category>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM category WHERE user_id = ?1 AND deleted_at IS NOT NULL ORDER BY deleted_at DESC, id DESC",
+        SELECT_COLUMNS
+    ))?;
+
+    let categories = stmt
+        .query_map(params![user_id], row_to_category)?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(categories)
+}
+
+/// Take a category out of the trash with the feeds that were deleted with it
+pub fn restore_category(conn: &Connection, id: i64, user_id: i64) -> AppResult<Category> {
+    let deleted_at: String = conn
+        .query_row(
+            "SELECT deleted_at FROM category WHERE id = ?1 AND user_id = ?2 AND deleted_at IS NOT NULL",
+            params![id, user_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or(AppError::CategoryNotFound)?;
+
+    conn.execute(
+        "UPDATE feed SET deleted_at = NULL WHERE category_id = ?1 AND deleted_at = ?2",
+        params![id, deleted_at],
+    )?;
+    conn.execute(
+        "UPDATE category SET deleted_at = NULL WHERE id = ?1",
+        params![id],
+    )?;
+
+    find_by_id(conn, id)?.ok_or(AppError::CategoryNotFound)
+}
+
+/// Take a trashed category out of the trash without touching its feeds, so
+/// a feed restored on its own has somewhere to go
+pub(crate) fn undelete(conn: &Connection, id: i64) -> AppResult<()> {
+    conn.execute(
+        "UPDATE category SET deleted_at = NULL WHERE id = ?1",
+        params![id],
+    )?;
     Ok(())
 }
 
+/// Permanently delete categories trashed before `before`, with their feeds
+/// and entries. Returns the number of categories removed.
+pub fn purge_trashed(conn: &Connection, before: DateTime<Utc>) -> AppResult<usize> {
+    let before = before.format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT f.id FROM feed f
+        INNER JOIN category c ON f.category_id = c.id
+        WHERE c.deleted_at IS NOT NULL AND c.deleted_at < ?1
+        "#,
+    )?;
+    let feed_ids = stmt
+        .query_map(params![before], |row| row.get::<_, i64>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    for feed_id in feed_ids {
+        image::delete_by_entity(conn, image::ENTITY_FEED, feed_id)?;
+    }
+
+    let rows = conn.execute(
+        "DELETE FROM category WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+        params![before],
+    )?;
+    Ok(rows)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,6 +533,91 @@ mod tests {
         assert_eq!(found_by_user.name, "Books");
     }
 
+    #[test]
+    fn test_set_auto_read_days() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let other_id = create_test_user(&conn, "other");
+        let category = create_category(&conn, user_id, "Books").unwrap();
+        assert_eq!(category.auto_read_days, None);
+
+        let updated = set_auto_read_days(&conn, category.id, user_id, Some(7)).unwrap();
+        assert_eq!(updated.auto_read_days, Some(7));
+
+        let cleared = set_auto_read_days(&conn, category.id, user_id, None).unwrap();
+        assert_eq!(cleared.auto_read_days, None);
+
+        let result = set_auto_read_days(&conn, category.id, other_id, Some(7));
+        assert!(matches!(result, Err(AppError::CategoryNotFound)));
+    }
+
+    #[test]
+    fn test_set_appearance() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let other_id = create_test_user(&conn, "other");
+        let category = create_category(&conn, user_id, "News").unwrap();
+        assert_eq!(category.color, None);
+        assert_eq!(category.icon_symbol(), None);
+
+        let updated =
+            set_appearance(&conn, category.id, user_id, Some("#3366cc"), Some("news")).unwrap();
+        assert_eq!(updated.color.as_deref(), Some("#3366cc"));
+        assert_eq!(updated.icon.as_deref(), Some("news"));
+        assert_eq!(updated.icon_symbol().as_deref(), Some("📰"));
+
+        let cleared = set_appearance(&conn, category.id, user_id, None, None).unwrap();
+        assert_eq!(cleared.color, None);
+        assert_eq!(cleared.icon, None);
+
+        let result = set_appearance(&conn, category.id, other_id, None, Some("🦀"));
+        assert!(matches!(result, Err(AppError::CategoryNotFound)));
+    }
+
+    #[test]
+    fn test_version() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category = create_category(&conn, user_id, "News").unwrap();
+        assert_eq!(version(&conn, category.id).unwrap(), 1);
+
+        update_name(&conn, category.id, user_id, "World").unwrap();
+        assert_eq!(version(&conn, category.id).unwrap(), 2);
+
+        // Saving the same values, or moving it around, is not an edit
+        update_name(&conn, category.id, user_id, "World").unwrap();
+        reorder(&conn, user_id, &[category.id]).unwrap();
+        assert_eq!(version(&conn, category.id).unwrap(), 2);
+
+        delete_category(&conn, category.id, user_id).unwrap();
+        assert!(matches!(
+            version(&conn, category.id),
+            Err(AppError::CategoryNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#3366CC").unwrap().as_deref(), Some("#3366cc"));
+        assert_eq!(parse_color("#f0a").unwrap().as_deref(), Some("#ff00aa"));
+        assert_eq!(parse_color(" ").unwrap(), None);
+        assert!(parse_color("red").is_err());
+        assert!(parse_color("#12345").is_err());
+        assert!(parse_color("#ggg").is_err());
+    }
+
+    #[test]
+    fn test_parse_icon() {
+        assert_eq!(parse_icon("books").unwrap().as_deref(), Some("books"));
+        assert_eq!(parse_icon("🦀").unwrap().as_deref(), Some("🦀"));
+        assert_eq!(parse_icon("👩‍💻").unwrap().as_deref(), Some("👩‍💻"));
+        assert_eq!(parse_icon("").unwrap(), None);
+        assert!(parse_icon("crab").is_err());
+        assert!(parse_icon("日本").is_err());
+        assert!(parse_icon("<b>").is_err());
+        assert_eq!(icon_symbol("🦀"), "🦀");
+    }
+
     #[test]
     fn test_duplicate_category_name() {
         let conn = setup_db();
@@ -212,6 +655,88 @@ mod tests {
         assert_eq!(categories[2].name, "Zebra");
     }
 
+    #[test]
+    fn test_reorder() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let other_id = create_test_user(&conn, "other");
+
+        let zebra = create_category(&conn, user_id, "Zebra").unwrap();
+        let apple = create_category(&conn, user_id, "Apple").unwrap();
+        let mango = create_category(&conn, user_id, "Mango").unwrap();
+        let theirs = create_category(&conn, other_id, "Theirs").unwrap();
+
+        reorder(&conn, user_id, &[mango.id, zebra.id]).unwrap();
+        let names: Vec<String> = list_by_user(&conn, user_id)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, ["Mango", "Zebra", "Apple"]);
+
+        // A category added later goes last
+        create_category(&conn, user_id, "Banana").unwrap();
+        let last = list_by_user(&conn, user_id).unwrap().pop().unwrap();
+        assert_eq!(last.name, "Banana");
+
+        assert!(matches!(
+            reorder(&conn, user_id, &[theirs.id]),
+            Err(AppError::CategoryNotFound)
+        ));
+        assert!(matches!(
+            reorder(&conn, user_id, &[apple.id, apple.id]),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let other_id = create_test_user(&conn, "other");
+        let books = create_category(&conn, user_id, "Books").unwrap();
+
+        assert_eq!(resolve(&conn, user_id, Some(books.id)).unwrap(), books.id);
+        assert!(matches!(
+            resolve(&conn, other_id, Some(books.id)),
+            Err(AppError::CategoryNotFound)
+        ));
+
+        let bucket = resolve(&conn, user_id, None).unwrap();
+        assert_eq!(resolve(&conn, user_id, None).unwrap(), bucket);
+        let found = find_by_id(&conn, bucket).unwrap().unwrap();
+        assert_eq!(found.name, UNCATEGORIZED);
+        assert_ne!(resolve(&conn, other_id, None).unwrap(), bucket);
+
+        // The name is reserved for the bucket
+        assert!(matches!(
+            update_name(&conn, bucket, user_id, "Inbox"),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            update_name(&conn, books.id, user_id, UNCATEGORIZED),
+            Err(AppError::Validation(_))
+        ));
+        update_name(&conn, bucket, user_id, UNCATEGORIZED).unwrap();
+
+        // A trashed bucket comes back without the feeds deleted with it
+        conn.execute(
+            "INSERT INTO feed (category_id, url) VALUES (?1, 'https://example.com/feed.xml')",
+            params![bucket],
+        )
+        .unwrap();
+        delete_category(&conn, bucket, user_id).unwrap();
+        assert_eq!(resolve(&conn, user_id, None).unwrap(), bucket);
+        let live: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM feed WHERE category_id = ?1 AND deleted_at IS NULL",
+                params![bucket],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(live, 0);
+    }
+
     #[test]
     fn test_update_name() {
         let conn = setup_db();
@@ -245,6 +770,78 @@ mod tests {
         assert!(find_by_id(&conn, category.id).unwrap().is_none());
     }
 
+    #[test]
+    fn test_trash_and_restore_category() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let other_id = create_test_user(&conn, "other");
+        let category = create_category(&conn, user_id, "Books").unwrap();
+        let feed_id = |url: &str| {
+            conn.execute(
+                "INSERT INTO feed (category_id, url) VALUES (?1, ?2)",
+                params![category.id, url],
+            )
+            .unwrap();
+            conn.last_insert_rowid()
+        };
+        let kept = feed_id("https://example.com/a.xml");
+        let trashed_earlier = feed_id("https://example.com/b.xml");
+        conn.execute(
+            "UPDATE feed SET deleted_at = datetime('now', '-1 day') WHERE id = ?1",
+            params![trashed_earlier],
+        )
+        .unwrap();
+
+        delete_category(&conn, category.id, user_id).unwrap();
+        let trashed = list_trashed_by_user(&conn, user_id).unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert!(trashed[0].deleted_at.is_some());
+        assert!(list_by_user(&conn, user_id).unwrap().is_empty());
+
+        let result = restore_category(&conn, category.id, other_id);
+        assert!(matches!(result, Err(AppError::CategoryNotFound)));
+
+        let restored = restore_category(&conn, category.id, user_id).unwrap();
+        assert!(restored.deleted_at.is_none());
+        let live: Vec<i64> = conn
+            .prepare("SELECT id FROM feed WHERE deleted_at IS NULL")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(live, vec![kept]);
+    }
+
+    #[test]
+    fn test_trashed_name_is_restored_and_purged() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let old = create_category(&conn, user_id, "Books").unwrap();
+        delete_category(&conn, old.id, user_id).unwrap();
+
+        // Creating the name again brings back the trashed category
+        let again = create_category(&conn, user_id, "Books").unwrap();
+        assert_eq!(again.id, old.id);
+        assert!(list_trashed_by_user(&conn, user_id).unwrap().is_empty());
+
+        // Renaming onto a trashed name is refused rather than purging it
+        let movies = create_category(&conn, user_id, "Movies").unwrap();
+        delete_category(&conn, movies.id, user_id).unwrap();
+        let result = update_name(&conn, old.id, user_id, "Movies");
+        assert!(matches!(result, Err(AppError::CategoryExists)));
+        assert_eq!(list_trashed_by_user(&conn, user_id).unwrap().len(), 1);
+        assert_eq!(
+            purge_trashed(&conn, Utc::now() - chrono::Duration::days(1)).unwrap(),
+            0
+        );
+        assert_eq!(
+            purge_trashed(&conn, Utc::now() + chrono::Duration::days(1)).unwrap(),
+            1
+        );
+        assert!(list_trashed_by_user(&conn, user_id).unwrap().is_empty());
+    }
+
     #[test]
     fn test_ownership_check() {
         let conn = setup_db();
@@ -0,0 +1,266 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+use crate::models::SummaryStatus;
+
+/// A combined summary of a category's unread entries
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryDigest {
+    pub id: i64,
+    pub user_id: i64,
+    pub category_id: i64,
+    pub status: SummaryStatus,
+    pub digest_text: Option<String>,
+    pub error_message: Option<String>,
+    pub entry_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").map(|dt| dt.and_utc())
+        })
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_category_digest(row: &rusqlite::Row) -> rusqlite::Result<CategoryDigest> {
+    let status_str: String = row.get(3)?;
+    let created_at: String = row.get(7)?;
+    let updated_at: String = row.get(8)?;
+
+    Ok(CategoryDigest {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        category_id: row.get(2)?,
+        status: SummaryStatus::parse(&status_str).unwrap_or(SummaryStatus::Failed),
+        digest_text: row.get(4)?,
+        error_message: row.get(5)?,
+        entry_count: row.get(6)?,
+        created_at: parse_datetime(&created_at),
+        updated_at: parse_datetime(&updated_at),
+    })
+}
+
+const SELECT_COLUMNS: &str = "id, user_id, category_id, status, digest_text, error_message, \
+                              entry_count, created_at, updated_at";
+
+/// Find a digest by user and category
+pub fn find_by_user_and_category(
+    conn: &Connection,
+    user_id: i64,
+    category_id: i64,
+) -> AppResult<Option<CategoryDigest>> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM category_digest WHERE user_id = ?1 AND category_id = ?2",
+            SELECT_COLUMNS
+        ),
+        params![user_id, category_id],
+        row_to_category_digest,
+    )
+    .optional()
+    .map_err(AppError::Database)
+}
+
+fn find_or_not_found(
+    conn: &Connection,
+    user_id: i64,
+    category_id: i64,
+) -> AppResult<CategoryDigest> {
+    find_by_user_and_category(conn, user_id, category_id)?
+        .ok_or(AppError::NotFound("Category digest not found".to_string()))
+}
+
+/// Create or reset a digest with pending status
+pub fn upsert_pending(
+    conn: &Connection,
+    user_id: i64,
+    category_id: i64,
+) -> AppResult<CategoryDigest> {
+    conn.execute(
+        r#"
+        INSERT INTO category_digest (user_id, category_id, status)
+        VALUES (?1, ?2, 'pending')
+        ON CONFLICT(user_id, category_id) DO UPDATE SET
+            status = 'pending',
+            digest_text = NULL,
+            error_message = NULL,
+            entry_count = 0,
+            updated_at = datetime('now')
+        "#,
+        params![user_id, category_id],
+    )?;
+
+    find_or_not_found(conn, user_id, category_id)
+}
+
+/// Update status to processing
+pub fn set_processing(conn: &Connection, user_id: i64, category_id: i64) -> AppResult<()> {
+    let rows = conn.execute(
+        r#"
+        UPDATE category_digest
+        SET status = 'processing', updated_at = datetime('now')
+        WHERE user_id = ?1 AND category_id = ?2
+        "#,
+        params![user_id, category_id],
+    )?;
+
+    if rows == 0 {
+        return Err(AppError::NotFound("Category digest not found".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Set digest as completed with the combined text and the number of entries covered
+pub fn set_completed(
+    conn: &Connection,
+    user_id: i64,
+    category_id: i64,
+    digest_text: &str,
+    entry_count: i64,
+) -> AppResult<CategoryDigest> {
+    let rows = conn.execute(
+        r#"
+        UPDATE category_digest
+        SET status = 'completed', digest_text = ?3, entry_count = ?4, error_message = NULL,
+            updated_at = datetime('now')
+        WHERE user_id = ?1 AND category_id = ?2
+        "#,
+        params![user_id, category_id, digest_text, entry_count],
+    )?;
+
+    if rows == 0 {
+        return Err(AppError::NotFound("Category digest not found".to_string()));
+    }
+
+    find_or_not_found(conn, user_id, category_id)
+}
+
+/// Set digest as failed with error message
+pub fn set_failed(
+    conn: &Connection,
+    user_id: i64,
+    category_id: i64,
+    error_message: &str,
+) -> AppResult<CategoryDigest> {
+    let rows = conn.execute(
+        r#"
+        UPDATE category_digest
+        SET status = 'failed', error_message = ?3, updated_at = datetime('now')
+        WHERE user_id = ?1 AND category_id = ?2
+        "#,
+        params![user_id, category_id, error_message],
+    )?;
+
+    if rows == 0 {
+        return Err(AppError::NotFound("Category digest not found".to_string()));
+    }
+
+    find_or_not_found(conn, user_id, category_id)
+}
+
+/// List all digests belonging to a user
+pub fn list_by_user(conn: &Connection, user_id: i64) -> AppResult<Vec<CategoryDigest>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM category_digest WHERE user_id = ?1 ORDER BY id",
+        SELECT_COLUMNS
+    ))?;
+    let digests = stmt
+        .query_map(params![user_id], row_to_category_digest)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(digests)
+}
+
+/// Delete a digest
+pub fn delete(conn: &Connection, user_id: i64, category_id: i64) -> AppResult<bool> {
+    let rows = conn.execute(
+        "DELETE FROM category_digest WHERE user_id = ?1 AND category_id = ?2",
+        params![user_id, category_id],
+    )?;
+
+    Ok(rows > 0)
+}
+
+/// Find digests left pending or processing, as `(user_id, category_id)`
+pub fn find_incomplete(conn: &Connection) -> AppResult<Vec<(i64, i64)>> {
+    let mut stmt = conn.prepare(
+        "SELECT user_id, category_id FROM category_digest WHERE status IN ('pending', 'processing')",
+    )?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::Role;
+    use crate::models::{category, user};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn create_test_category(conn: &Connection) -> (i64, i64) {
+        let user_id = user::create_user(conn, "testuser", "hash123", Role::User)
+            .unwrap()
+            .id;
+        let category_id = category::create_category(conn, user_id, "Tech").unwrap().id;
+        (user_id, category_id)
+    }
+
+    #[test]
+    fn test_digest_lifecycle() {
+        let conn = setup_db();
+        let (user_id, category_id) = create_test_category(&conn);
+
+        let digest = upsert_pending(&conn, user_id, category_id).unwrap();
+        assert_eq!(digest.status, SummaryStatus::Pending);
+        assert!(digest.digest_text.is_none());
+
+        set_processing(&conn, user_id, category_id).unwrap();
+        let digest = set_completed(&conn, user_id, category_id, "Roundup", 12).unwrap();
+        assert_eq!(digest.status, SummaryStatus::Completed);
+        assert_eq!(digest.digest_text.as_deref(), Some("Roundup"));
+        assert_eq!(digest.entry_count, 12);
+
+        // Regenerating resets the previous result
+        let digest = upsert_pending(&conn, user_id, category_id).unwrap();
+        assert_eq!(digest.status, SummaryStatus::Pending);
+        assert!(digest.digest_text.is_none());
+        assert_eq!(digest.entry_count, 0);
+
+        assert_eq!(list_by_user(&conn, user_id).unwrap().len(), 1);
+        assert!(delete(&conn, user_id, category_id).unwrap());
+        assert!(list_by_user(&conn, user_id).unwrap().is_empty());
+        assert!(find_by_user_and_category(&conn, user_id, category_id)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_find_incomplete() {
+        let conn = setup_db();
+        let (user_id, category_id) = create_test_category(&conn);
+
+        upsert_pending(&conn, user_id, category_id).unwrap();
+        assert_eq!(
+            find_incomplete(&conn).unwrap(),
+            vec![(user_id, category_id)]
+        );
+
+        set_completed(&conn, user_id, category_id, "text", 1).unwrap();
+        assert!(find_incomplete(&conn).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+//! Cached discussion lookups, keyed by article URL so entries that share a
+//! link across feeds and users share one lookup. Results are stored as the
+//! JSON produced by `services::discussions`.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppResult;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedDiscussions {
+    pub results: String,
+    pub fetched_at: DateTime<Utc>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .unwrap_or_else(|_| Utc::now())
+}
+
+/// Results for `url` fetched less than `max_age_secs` ago
+pub fn find_fresh(
+    conn: &Connection,
+    url: &str,
+    max_age_secs: i64,
+) -> AppResult<Option<CachedDiscussions>> {
+    let cached = conn
+        .query_row(
+            "SELECT results, fetched_at FROM discussion_cache \
+             WHERE url = ?1 AND fetched_at > datetime('now', ?2)",
+            params![url, format!("-{} seconds", max_age_secs)],
+            |row| {
+                let fetched_at: String = row.get(1)?;
+                Ok(CachedDiscussions {
+                    results: row.get(0)?,
+                    fetched_at: parse_datetime(&fetched_at),
+                })
+            },
+        )
+        .optional()?;
+    Ok(cached)
+}
+
+pub fn store(conn: &Connection, url: &str, results: &str) -> AppResult<()> {
+    conn.execute(
+        "INSERT INTO discussion_cache (url, results) VALUES (?1, ?2) \
+         ON CONFLICT(url) DO UPDATE SET results = excluded.results, fetched_at = datetime('now')",
+        params![url, results],
+    )?;
+    Ok(())
+}
+
+/// Drop lookups older than `max_age_secs`
+pub fn delete_expired(conn: &Connection, max_age_secs: i64) -> AppResult<usize> {
+    let rows = conn.execute(
+        "DELETE FROM discussion_cache WHERE fetched_at <= datetime('now', ?1)",
+        params![format!("-{} seconds", max_age_secs)],
+    )?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+
+    const URL: &str = "https://example.com/post";
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_store_and_find_fresh() {
+        let conn = setup_db();
+        assert!(find_fresh(&conn, URL, 3600).unwrap().is_none());
+
+        store(&conn, URL, "[]").unwrap();
+        store(&conn, URL, r#"[{"title":"Post"}]"#).unwrap();
+
+        let cached = find_fresh(&conn, URL, 3600).unwrap().unwrap();
+        assert_eq!(cached.results, r#"[{"title":"Post"}]"#);
+    }
+
+    #[test]
+    fn test_stale_results_expire() {
+        let conn = setup_db();
+        store(&conn, URL, "[]").unwrap();
+        conn.execute(
+            "UPDATE discussion_cache SET fetched_at = datetime('now', '-2 hours')",
+            [],
+        )
+        .unwrap();
+
+        assert!(find_fresh(&conn, URL, 3600).unwrap().is_none());
+        assert!(find_fresh(&conn, URL, 3 * 3600).unwrap().is_some());
+
+        assert_eq!(delete_expired(&conn, 3600).unwrap(), 1);
+        assert!(find_fresh(&conn, URL, 3 * 3600).unwrap().is_none());
+    }
+}
@@ -3,6 +3,12 @@ use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 
 use crate::error::{AppError, AppResult};
+use crate::models::entry_cluster::story_key;
+use crate::models::entry_revision;
+use crate::models::entry_search::{self, SearchFields};
+use crate::services::digest::html_to_text;
+use crate::services::revision_diff::changed_percent;
+use crate::timezone::{self, Tz};
 
 /// Sort order for entries
 #[derive(Debug, Clone, Copy, Deserialize, Default, PartialEq)]
@@ -12,6 +18,23 @@ pub enum EntrySortOrder {
     PublishedAt, // COALESCE(published_at, created_at) DESC
     ReadAt,    // read_at DESC
     StarredAt, // starred_at DESC
+    Interest,  // entry_interest.score DESC
+}
+
+impl EntrySortOrder {
+    /// SQL expression entries are ordered by (descending, ties broken by id)
+    fn sort_key(&self) -> &'static str {
+        match self {
+            EntrySortOrder::PublishedAt => "COALESCE(e.published_at, e.created_at)",
+            EntrySortOrder::ReadAt => "e.read_at",
+            EntrySortOrder::StarredAt => "e.starred_at",
+            // Text so it compares like the other keys when paging; entries
+            // no model has scored yet sit in the middle
+            EntrySortOrder::Interest => {
+                "printf('%.6f', COALESCE((SELECT ei.score FROM entry_interest ei WHERE ei.entry_id = e.id), 0.5))"
+            }
+        }
+    }
 }
 
 /// Parse Chinese month names to month number
@@ -107,11 +130,24 @@ pub struct Entry {
     pub content: Option<String>,
     pub summary: Option<String>,
     pub author: Option<String>,
+    // Timestamps are serialized in the zone of `timezone::scope`, UTC by default
+    #[serde(serialize_with = "timezone::serialize_option")]
     pub published_at: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "timezone::serialize_option")]
     pub read_at: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "timezone::serialize_option")]
     pub starred_at: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "timezone::serialize")]
     pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "timezone::serialize")]
     pub updated_at: DateTime<Utc>,
+    /// Discussion page announced by the feed (`<comments>`, `wfw:commentRss`)
+    pub comments_url: Option<String>,
+    pub comments_count: Option<i64>,
+    /// Last time a refresh changed the text beyond the feed's
+    /// `update_threshold`
+    #[serde(serialize_with = "timezone::serialize_option")]
+    pub content_updated_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -133,7 +169,131 @@ pub struct EntryFilter {
     pub starred_only: bool,
     pub read_only: bool,
     pub search: Option<String>,
+    /// Where `search` looks; everywhere by default
+    #[serde(default)]
+    pub search_fields: SearchFields,
     pub has_summary: Option<bool>,
+    /// Only entries published (or, without a date, fetched) at or after this time
+    pub published_after: Option<DateTime<Utc>>,
+    /// Keep only the newest matching copy of stories carried by several feeds
+    #[serde(default)]
+    pub collapse_duplicates: bool,
+    /// Only entries with the user's tag of this name
+    pub tag: Option<String>,
+    /// Only entries of the user's synthetic feed with this URL
+    pub synthetic_feed_url: Option<String>,
+}
+
+/// Archive views of a user's entries, chosen with `state=` when listing
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryState {
+    Read,
+    Starred,
+    /// Entries the user asked to summarize, whatever became of the summary
+    Summarized,
+    /// Unread pages the user saved for later
+    ReadLater,
+}
+
+impl EntryState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryState::Read => "read",
+            EntryState::Starred => "starred",
+            EntryState::Summarized => "summarized",
+            EntryState::ReadLater => "read_later",
+        }
+    }
+
+    /// Page the view is shown on
+    pub fn path(&self) -> &'static str {
+        match self {
+            EntryState::Read => "/entries/read",
+            EntryState::Starred => "/entries/starred",
+            EntryState::Summarized => "/entries/summarized",
+            EntryState::ReadLater => "/entries/read-later",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            EntryState::Read => "Read Entries",
+            EntryState::Starred => "Starred Entries",
+            EntryState::Summarized => "Summarized Entries",
+            EntryState::ReadLater => "Read Later",
+        }
+    }
+
+    /// Order the view is listed in unless another is asked for
+    pub fn default_sort(&self) -> EntrySortOrder {
+        match self {
+            EntryState::Read => EntrySortOrder::ReadAt,
+            EntryState::Starred => EntrySortOrder::StarredAt,
+            EntryState::Summarized | EntryState::ReadLater => EntrySortOrder::PublishedAt,
+        }
+    }
+
+    /// Narrow `filter` to the view. Read later is the synthetic feed
+    /// identified by `read_later_url`.
+    pub fn apply(&self, filter: &mut EntryFilter, read_later_url: &str) {
+        match self {
+            EntryState::Read => filter.read_only = true,
+            EntryState::Starred => filter.starred_only = true,
+            EntryState::Summarized => filter.has_summary = Some(true),
+            EntryState::ReadLater => {
+                filter.unread_only = true;
+                filter.synthetic_feed_url = Some(read_later_url.to_string());
+            }
+        }
+    }
+}
+
+/// Date-bounded views over all of a user's feeds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VirtualStream {
+    Today,
+    Last24Hours,
+}
+
+impl VirtualStream {
+    pub fn slug(&self) -> &'static str {
+        match self {
+            VirtualStream::Today => "today",
+            VirtualStream::Last24Hours => "last-24h",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            VirtualStream::Today => "Today",
+            VirtualStream::Last24Hours => "Last 24 Hours",
+        }
+    }
+
+    /// Lower bound of the stream. `tz_offset_minutes` follows JavaScript's
+    /// `Date.getTimezoneOffset()` (minutes behind UTC) and only affects Today.
+    pub fn since(&self, now: DateTime<Utc>, tz_offset_minutes: i32) -> DateTime<Utc> {
+        match self {
+            VirtualStream::Last24Hours => now - chrono::Duration::hours(24),
+            VirtualStream::Today => {
+                let offset = chrono::Duration::minutes(tz_offset_minutes.clamp(-840, 840) as i64);
+                let local_midnight = (now - offset)
+                    .date_naive()
+                    .and_time(NaiveTime::MIN)
+                    .and_utc();
+                local_midnight + offset
+            }
+        }
+    }
+
+    /// Lower bound of the stream with Today starting at midnight in `tz`
+    pub fn since_in(&self, now: DateTime<Utc>, tz: Tz) -> DateTime<Utc> {
+        match self {
+            VirtualStream::Last24Hours => self.since(now, 0),
+            VirtualStream::Today => timezone::start_of_day(now, tz),
+        }
+    }
 }
 
 fn parse_datetime(s: &str) -> DateTime<Utc> {
@@ -157,6 +317,7 @@ fn row_to_entry(row: &rusqlite::Row) -> rusqlite::Result<Entry> {
     let starred_at: Option<String> = row.get(10)?;
     let created_at: String = row.get(11)?;
     let updated_at: String = row.get(12)?;
+    let content_updated_at: Option<String> = row.get(15)?;
 
     Ok(Entry {
         id: row.get(0)?,
@@ -172,6 +333,9 @@ fn row_to_entry(row: &rusqlite::Row) -> rusqlite::Result<Entry> {
         starred_at: starred_at.map(|s| parse_datetime(&s)),
         created_at: parse_datetime(&created_at),
         updated_at: parse_datetime(&updated_at),
+        comments_url: row.get(13)?,
+        comments_count: row.get(14)?,
+        content_updated_at: content_updated_at.map(|s| parse_datetime(&s)),
     })
 }
 
@@ -181,7 +345,8 @@ fn row_to_entry_with_feed(row: &rusqlite::Row) -> rusqlite::Result<EntryWithFeed
     let starred_at: Option<String> = row.get(10)?;
     let created_at: String = row.get(11)?;
     let updated_at: String = row.get(12)?;
-    let has_icon: i64 = row.get(17)?;
+    let content_updated_at: Option<String> = row.get(15)?;
+    let has_icon: i64 = row.get(20)?;
 
     Ok(EntryWithFeed {
         entry: Entry {
@@ -198,16 +363,19 @@ fn row_to_entry_with_feed(row: &rusqlite::Row) -> rusqlite::Result<EntryWithFeed
             starred_at: starred_at.map(|s| parse_datetime(&s)),
             created_at: parse_datetime(&created_at),
             updated_at: parse_datetime(&updated_at),
+            comments_url: row.get(13)?,
+            comments_count: row.get(14)?,
+            content_updated_at: content_updated_at.map(|s| parse_datetime(&s)),
         },
-        feed_title: row.get(13)?,
-        feed_url: row.get(14)?,
-        category_id: row.get(15)?,
-        category_name: row.get(16)?,
+        feed_title: row.get(16)?,
+        feed_url: row.get(17)?,
+        category_id: row.get(18)?,
+        category_name: row.get(19)?,
         feed_has_icon: has_icon > 0,
     })
 }
 
-const SELECT_COLUMNS: &str = "id, feed_id, guid, title, link, content, summary, author, published_at, read_at, starred_at, created_at, updated_at";
+const SELECT_COLUMNS: &str = "id, feed_id, guid, title, link, content, summary, author, published_at, read_at, starred_at, created_at, updated_at, comments_url, comments_count, content_updated_at";
 
 pub fn find_by_id(conn: &Connection, id: i64) -> AppResult<Option<Entry>> {
     conn.query_row(
@@ -219,17 +387,29 @@ pub fn find_by_id(conn: &Connection, id: i64) -> AppResult<Option<Entry>> {
     .map_err(AppError::Database)
 }
 
+/// The entry's version for `If-Match`: the change number of its latest write
+pub fn version(conn: &Connection, id: i64) -> AppResult<i64> {
+    conn.query_row(
+        "SELECT updated_seq FROM entry WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()?
+    .ok_or(AppError::EntryNotFound)
+}
+
 pub fn find_by_id_with_feed(conn: &Connection, id: i64) -> AppResult<Option<EntryWithFeed>> {
     conn.query_row(
         r#"
         SELECT e.id, e.feed_id, e.guid, e.title, e.link, e.content, e.summary, e.author,
                e.published_at, e.read_at, e.starred_at, e.created_at, e.updated_at,
+               e.comments_url, e.comments_count, e.content_updated_at,
                f.title, f.url, c.id, c.name,
                (SELECT COUNT(*) FROM image i WHERE i.entity_type = 'feed' AND i.entity_id = f.id) as has_icon
         FROM entry e
         INNER JOIN feed f ON e.feed_id = f.id
         INNER JOIN category c ON f.category_id = c.id
-        WHERE e.id = ?1
+        WHERE e.id = ?1 AND f.deleted_at IS NULL
         "#,
         params![id],
         row_to_entry_with_feed,
@@ -238,6 +418,62 @@ pub fn find_by_id_with_feed(conn: &Connection, id: i64) -> AppResult<Option<Entr
     .map_err(AppError::Database)
 }
 
+/// Entry `id` with its feed, if it belongs to `user_id` and neither its feed
+/// nor its category is in the trash
+pub fn find_by_id_with_feed_and_user(
+    conn: &Connection,
+    id: i64,
+    user_id: i64,
+) -> AppResult<Option<EntryWithFeed>> {
+    conn.query_row(
+        r#"
+        SELECT e.id, e.feed_id, e.guid, e.title, e.link, e.content, e.summary, e.author,
+               e.published_at, e.read_at, e.starred_at, e.created_at, e.updated_at,
+               e.comments_url, e.comments_count, e.content_updated_at,
+               f.title, f.url, c.id, c.name,
+               (SELECT COUNT(*) FROM image i WHERE i.entity_type = 'feed' AND i.entity_id = f.id) as has_icon
+        FROM entry e
+        INNER JOIN feed f ON e.feed_id = f.id
+        INNER JOIN category c ON f.category_id = c.id
+        WHERE e.id = ?1 AND c.user_id = ?2 AND f.deleted_at IS NULL AND c.deleted_at IS NULL
+        "#,
+        params![id, user_id],
+        row_to_entry_with_feed,
+    )
+    .optional()
+    .map_err(AppError::Database)
+}
+
+/// The user's copies of the story in `entry_id`, the entry included, newest
+/// first. Empty if the entry is not the user's.
+pub fn list_story_copies(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+) -> AppResult<Vec<EntryWithFeed>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT e.id, e.feed_id, e.guid, e.title, e.link, e.content, e.summary, e.author,
+               e.published_at, e.read_at, e.starred_at, e.created_at, e.updated_at,
+               e.comments_url, e.comments_count, e.content_updated_at,
+               f.title, f.url, c.id, c.name,
+               (SELECT COUNT(*) FROM image i WHERE i.entity_type = 'feed' AND i.entity_id = f.id) as has_icon
+        FROM entry e
+        INNER JOIN feed f ON e.feed_id = f.id
+        INNER JOIN category c ON f.category_id = c.id
+        WHERE c.user_id = ?1 AND f.deleted_at IS NULL AND c.deleted_at IS NULL
+          AND (e.id = ?2 OR e.story_key = (SELECT story_key FROM entry WHERE id = ?2))
+        ORDER BY COALESCE(e.published_at, e.created_at) DESC, e.id DESC
+        "#,
+    )?;
+
+    let entries = stmt
+        .query_map(params![user_id, entry_id], row_to_entry_with_feed)?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(entries)
+}
+
 pub fn find_by_guid_and_feed(
     conn: &Connection,
     guid: &str,
@@ -274,15 +510,16 @@ pub fn list_by_feed(
     Ok(entries)
 }
 
-pub fn list_by_user(
-    conn: &Connection,
+/// WHERE conditions and bound parameters selecting a user's entries that match
+/// `filter`. The user id is always bound as `?1`.
+fn filter_conditions(
     user_id: i64,
     filter: &EntryFilter,
-    sort_order: EntrySortOrder,
-    limit: i64,
-    offset: i64,
-) -> AppResult<Vec<EntryWithFeed>> {
-    let mut conditions = vec!["c.user_id = ?1".to_string()];
+) -> (Vec<String>, Vec<Box<dyn rusqlite::ToSql>>) {
+    let mut conditions = vec![
+        "c.user_id = ?1".to_string(),
+        "f.deleted_at IS NULL".to_string(),
+    ];
     let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(user_id)];
 
     if let Some(feed_id) = filter.feed_id {
@@ -308,13 +545,20 @@ pub fn list_by_user(
     }
 
     if let Some(ref search) = filter.search {
-        let search_pattern = format!("%{}%", search);
-        let param_idx = params_vec.len() + 1;
+        let (condition, value) =
+            entry_search::match_condition(search, filter.search_fields, params_vec.len() + 1);
+        conditions.push(condition);
+        params_vec.push(Box::new(value));
+    }
+
+    if let Some(published_after) = filter.published_after {
         conditions.push(format!(
-            "(e.title LIKE ?{} COLLATE NOCASE OR e.content LIKE ?{} COLLATE NOCASE)",
-            param_idx, param_idx
+            "COALESCE(e.published_at, e.created_at) >= ?{}",
+            params_vec.len() + 1
+        ));
+        params_vec.push(Box::new(
+            published_after.format("%Y-%m-%d %H:%M:%S").to_string(),
         ));
-        params_vec.push(Box::new(search_pattern));
     }
 
     if let Some(has_summary) = filter.has_summary {
@@ -331,18 +575,70 @@ pub fn list_by_user(
         }
     }
 
+    if let Some(ref tag) = filter.tag {
+        conditions.push(format!(
+            r#"EXISTS (
+                SELECT 1 FROM entry_tag et INNER JOIN tag t ON et.tag_id = t.id
+                WHERE et.entry_id = e.id AND t.user_id = ?1 AND t.name = ?{}
+            )"#,
+            params_vec.len() + 1
+        ));
+        params_vec.push(Box::new(tag.clone()));
+    }
+
+    if let Some(ref url) = filter.synthetic_feed_url {
+        conditions.push(format!(
+            "f.synthetic = 1 AND f.url = ?{}",
+            params_vec.len() + 1
+        ));
+        params_vec.push(Box::new(url.clone()));
+    }
+
+    if filter.collapse_duplicates {
+        // Rank the matching copies of each story (entries without a story key
+        // stand alone) and keep the first. The subquery binds the same
+        // numbered parameters as the conditions it repeats.
+        let matched = conditions.join(" AND ");
+        conditions.push(format!(
+            r#"e.id IN (
+                SELECT id FROM (
+                    SELECT e.id, ROW_NUMBER() OVER (
+                        PARTITION BY COALESCE(e.story_key, e.id)
+                        ORDER BY COALESCE(e.published_at, e.created_at) DESC, e.id DESC
+                    ) AS copy_rank
+                    FROM entry e
+                    INNER JOIN feed f ON e.feed_id = f.id
+                    INNER JOIN category c ON f.category_id = c.id
+                    WHERE {}
+                )
+                WHERE copy_rank = 1
+            )"#,
+            matched
+        ));
+    }
+
+    (conditions, params_vec)
+}
+
+pub fn list_by_user(
+    conn: &Connection,
+    user_id: i64,
+    filter: &EntryFilter,
+    sort_order: EntrySortOrder,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Vec<EntryWithFeed>> {
+    let (conditions, mut params_vec) = filter_conditions(user_id, filter);
+
     let where_clause = conditions.join(" AND ");
 
-    let order_by = match sort_order {
-        EntrySortOrder::PublishedAt => "COALESCE(e.published_at, e.created_at) DESC",
-        EntrySortOrder::ReadAt => "e.read_at DESC",
-        EntrySortOrder::StarredAt => "e.starred_at DESC",
-    };
+    let order_by = format!("{} DESC, e.id DESC", sort_order.sort_key());
 
     let sql = format!(
         r#"
         SELECT e.id, e.feed_id, e.guid, e.title, e.link, e.content, e.summary, e.author,
                e.published_at, e.read_at, e.starred_at, e.created_at, e.updated_at,
+               e.comments_url, e.comments_count, e.content_updated_at,
                f.title, f.url, c.id, c.name,
                (SELECT COUNT(*) FROM image i WHERE i.entity_type = 'feed' AND i.entity_id = f.id) as has_icon
         FROM entry e
@@ -374,52 +670,7 @@ pub fn list_by_user(
 }
 
 pub fn count_by_user(conn: &Connection, user_id: i64, filter: &EntryFilter) -> AppResult<i64> {
-    let mut conditions = vec!["c.user_id = ?1".to_string()];
-    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(user_id)];
-
-    if let Some(feed_id) = filter.feed_id {
-        conditions.push(format!("e.feed_id = ?{}", params_vec.len() + 1));
-        params_vec.push(Box::new(feed_id));
-    }
-
-    if let Some(category_id) = filter.category_id {
-        conditions.push(format!("c.id = ?{}", params_vec.len() + 1));
-        params_vec.push(Box::new(category_id));
-    }
-
-    if filter.unread_only {
-        conditions.push("e.read_at IS NULL".to_string());
-    }
-
-    if filter.starred_only {
-        conditions.push("e.starred_at IS NOT NULL".to_string());
-    }
-
-    if filter.read_only {
-        conditions.push("e.read_at IS NOT NULL".to_string());
-    }
-
-    if let Some(ref search) = filter.search {
-        let search_pattern = format!("%{}%", search);
-        let param_idx = params_vec.len() + 1;
-        conditions.push(format!(
-            "(e.title LIKE ?{} COLLATE NOCASE OR e.content LIKE ?{} COLLATE NOCASE)",
-            param_idx, param_idx
-        ));
-        params_vec.push(Box::new(search_pattern));
-    }
-
-    if let Some(has_summary) = filter.has_summary {
-        if has_summary {
-            conditions.push(
-                "EXISTS (SELECT 1 FROM entry_summary es WHERE es.user_id = ?1 AND es.entry_id = e.id)".to_string()
-            );
-        } else {
-            conditions.push(
-                "NOT EXISTS (SELECT 1 FROM entry_summary es WHERE es.user_id = ?1 AND es.entry_id = e.id)".to_string()
-            );
-        }
-    }
+    let (conditions, params_vec) = filter_conditions(user_id, filter);
 
     let where_clause = conditions.join(" AND ");
 
@@ -448,7 +699,7 @@ pub fn count_unread_by_user(conn: &Connection, user_id: i64) -> AppResult<i64> {
         FROM entry e
         INNER JOIN feed f ON e.feed_id = f.id
         INNER JOIN category c ON f.category_id = c.id
-        WHERE c.user_id = ?1 AND e.read_at IS NULL
+        WHERE c.user_id = ?1 AND f.deleted_at IS NULL AND e.read_at IS NULL
         "#,
         params![user_id],
         |row| row.get(0),
@@ -457,6 +708,58 @@ pub fn count_unread_by_user(conn: &Connection, user_id: i64) -> AppResult<i64> {
     Ok(count)
 }
 
+/// Counts shown next to the user's streams
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StreamTotals {
+    pub unread: i64,
+    pub starred: i64,
+    /// Unread entries of the synthetic feed identified by `read_later_url`
+    pub read_later: i64,
+    pub today: i64,
+    pub last_24h: i64,
+}
+
+/// Count unread, starred, read-later and date-bounded unread entries in a
+/// single pass over the user's entries
+pub fn count_stream_totals(
+    conn: &Connection,
+    user_id: i64,
+    read_later_url: &str,
+    today_since: DateTime<Utc>,
+    last_24h_since: DateTime<Utc>,
+) -> AppResult<StreamTotals> {
+    let totals = conn.query_row(
+        r#"
+        SELECT COALESCE(SUM(e.read_at IS NULL), 0),
+               COALESCE(SUM(e.starred_at IS NOT NULL), 0),
+               COALESCE(SUM(e.read_at IS NULL AND f.synthetic = 1 AND f.url = ?2), 0),
+               COALESCE(SUM(e.read_at IS NULL AND COALESCE(e.published_at, e.created_at) >= ?3), 0),
+               COALESCE(SUM(e.read_at IS NULL AND COALESCE(e.published_at, e.created_at) >= ?4), 0)
+        FROM entry e
+        INNER JOIN feed f ON e.feed_id = f.id
+        INNER JOIN category c ON f.category_id = c.id
+        WHERE c.user_id = ?1 AND f.deleted_at IS NULL
+        "#,
+        params![
+            user_id,
+            read_later_url,
+            today_since.format("%Y-%m-%d %H:%M:%S").to_string(),
+            last_24h_since.format("%Y-%m-%d %H:%M:%S").to_string(),
+        ],
+        |row| {
+            Ok(StreamTotals {
+                unread: row.get(0)?,
+                starred: row.get(1)?,
+                read_later: row.get(2)?,
+                today: row.get(3)?,
+                last_24h: row.get(4)?,
+            })
+        },
+    )?;
+
+    Ok(totals)
+}
+
 /// Returns a map of feed_id -> unread count for a user
 pub fn count_unread_by_feed(
     conn: &Connection,
@@ -468,7 +771,7 @@ pub fn count_unread_by_feed(
         FROM feed f
         INNER JOIN category c ON f.category_id = c.id
         LEFT JOIN entry e ON e.feed_id = f.id AND e.read_at IS NULL
-        WHERE c.user_id = ?1
+        WHERE c.user_id = ?1 AND f.deleted_at IS NULL
         GROUP BY f.id
         "#,
     )?;
@@ -495,9 +798,9 @@ pub fn count_unread_by_category(
         r#"
         SELECT c.id, COUNT(e.id)
         FROM category c
-        LEFT JOIN feed f ON f.category_id = c.id
+        LEFT JOIN feed f ON f.category_id = c.id AND f.deleted_at IS NULL
         LEFT JOIN entry e ON e.feed_id = f.id AND e.read_at IS NULL
-        WHERE c.user_id = ?1
+        WHERE c.user_id = ?1 AND c.deleted_at IS NULL
         GROUP BY c.id
         "#,
     )?;
@@ -545,14 +848,57 @@ pub fn upsert_entry(
         // We don't update published_at because:
         // 1. The published date shouldn't change for existing entries
         // 2. Some feeds don't provide dates, causing fallback to current time on each refresh
+        //
+        // A changed title or content text keeps the previous version as a
+        // revision and, if the user asked for it, makes the entry unread again.
+        // Markup-only changes, which some feeds make on every fetch, do not count.
+        // Text changing by at least the feed's update threshold also makes the
+        // entry unread and flags it as updated.
+        let percent_changed = if existing.content.as_deref() != content {
+            changed_percent(
+                &html_to_text(existing.content.as_deref().unwrap_or_default()),
+                &html_to_text(content.unwrap_or_default()),
+            )
+        } else {
+            0
+        };
+        let changed = existing.title.as_deref() != title || percent_changed > 0;
+        if changed {
+            entry_revision::record_revision(
+                conn,
+                existing.id,
+                existing.title.as_deref(),
+                existing.content.as_deref(),
+            )?;
+        }
         conn.execute(
             r#"
             UPDATE entry
-            SET title = ?1, link = ?2, content = ?3, summary = ?4, author = ?5,
+            SET title = ?1, link = ?2, content = ?3, summary = ?4, author = ?5, story_key = ?9,
+                read_at = CASE WHEN (?7 AND (
+                    SELECT s.unread_on_update FROM feed f
+                    JOIN category c ON c.id = f.category_id
+                    JOIN user_settings s ON s.user_id = c.user_id
+                    WHERE f.id = entry.feed_id
+                )) OR ?8 >= (SELECT update_threshold FROM feed WHERE id = entry.feed_id)
+                THEN NULL ELSE read_at END,
+                content_updated_at = CASE
+                    WHEN ?8 >= (SELECT update_threshold FROM feed WHERE id = entry.feed_id)
+                    THEN datetime('now') ELSE content_updated_at END,
                 updated_at = datetime('now')
             WHERE id = ?6
             "#,
-            params![title, link, content, summary, author, existing.id],
+            params![
+                title,
+                link,
+                content,
+                summary,
+                author,
+                existing.id,
+                changed,
+                percent_changed,
+                story_key(content)
+            ],
         )?;
 
         let updated = find_by_id(conn, existing.id)?.ok_or(AppError::EntryNotFound)?;
@@ -562,8 +908,9 @@ pub fn upsert_entry(
     // Insert new entry
     conn.execute(
         r#"
-        INSERT INTO entry (feed_id, guid, title, link, content, summary, author, published_at)
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        INSERT INTO entry (feed_id, guid, title, link, content, summary, author, published_at,
+                           story_key)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
         "#,
         params![
             feed_id,
@@ -573,7 +920,8 @@ pub fn upsert_entry(
             content,
             summary,
             author,
-            published_at_str
+            published_at_str,
+            story_key(content)
         ],
     )?;
 
@@ -583,6 +931,128 @@ pub fn upsert_entry(
     Ok((entry, true))
 }
 
+/// Insert an entry restored from a backup. If the entry already exists, only
+/// its read and star state are merged in. Returns true if a new entry was created.
+#[allow(clippy::too_many_arguments)]
+pub fn restore_entry(
+    conn: &Connection,
+    feed_id: i64,
+    guid: &str,
+    title: Option<&str>,
+    link: Option<&str>,
+    content: Option<&str>,
+    summary: Option<&str>,
+    author: Option<&str>,
+    published_at: Option<DateTime<Utc>>,
+    read_at: Option<DateTime<Utc>>,
+    starred_at: Option<DateTime<Utc>>,
+) -> AppResult<bool> {
+    let format = |dt: DateTime<Utc>| dt.format("%Y-%m-%d %H:%M:%S").to_string();
+    let published_at_str = published_at.map(format);
+    let read_at_str = read_at.map(format);
+    let starred_at_str = starred_at.map(format);
+
+    if let Some(existing) = find_by_guid_and_feed(conn, guid, feed_id)? {
+        conn.execute(
+            r#"
+            UPDATE entry
+            SET read_at = COALESCE(read_at, ?1), starred_at = COALESCE(starred_at, ?2),
+                updated_at = datetime('now')
+            WHERE id = ?3
+            "#,
+            params![read_at_str, starred_at_str, existing.id],
+        )?;
+        return Ok(false);
+    }
+
+    conn.execute(
+        r#"
+        INSERT INTO entry (feed_id, guid, title, link, content, summary, author, published_at, read_at, starred_at, story_key)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+        "#,
+        params![
+            feed_id,
+            guid,
+            title,
+            link,
+            content,
+            summary,
+            author,
+            published_at_str,
+            read_at_str,
+            starred_at_str,
+            story_key(content)
+        ],
+    )?;
+
+    Ok(true)
+}
+
+/// List a user's entries with an id greater than `after_id`, in id order.
+/// Used to page through every entry without OFFSET scans.
+pub fn list_by_user_after_id(
+    conn: &Connection,
+    user_id: i64,
+    after_id: i64,
+    limit: i64,
+) -> AppResult<Vec<Entry>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT e.id, e.feed_id, e.guid, e.title, e.link, e.content, e.summary, e.author,
+               e.published_at, e.read_at, e.starred_at, e.created_at, e.updated_at,
+               e.comments_url, e.comments_count, e.content_updated_at
+        FROM entry e
+        INNER JOIN feed f ON e.feed_id = f.id
+        INNER JOIN category c ON f.category_id = c.id
+        WHERE c.user_id = ?1 AND f.deleted_at IS NULL AND e.id > ?2
+        ORDER BY e.id ASC
+        LIMIT ?3
+        "#,
+    )?;
+
+    let entries = stmt
+        .query_map(params![user_id, after_id, limit], row_to_entry)?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(entries)
+}
+
+/// Store the date string exactly as it appeared in the feed, for debugging
+/// entries whose published date had to be sanitized
+pub fn set_published_raw(conn: &Connection, id: i64, raw: Option<&str>) -> AppResult<()> {
+    conn.execute(
+        "UPDATE entry SET published_raw = ?1 WHERE id = ?2",
+        params![raw, id],
+    )?;
+    Ok(())
+}
+
+pub fn get_published_raw(conn: &Connection, id: i64) -> AppResult<Option<String>> {
+    let raw = conn
+        .query_row(
+            "SELECT published_raw FROM entry WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or(AppError::EntryNotFound)?;
+    Ok(raw)
+}
+
+/// Store the discussion link and comment count announced by the feed
+pub fn set_comments(
+    conn: &Connection,
+    id: i64,
+    comments_url: Option<&str>,
+    comments_count: Option<i64>,
+) -> AppResult<()> {
+    conn.execute(
+        "UPDATE entry SET comments_url = ?1, comments_count = ?2 WHERE id = ?3",
+        params![comments_url, comments_count, id],
+    )?;
+    Ok(())
+}
+
 pub fn mark_as_read(conn: &Connection, id: i64) -> AppResult<Entry> {
     let rows = conn.execute(
         "UPDATE entry SET read_at = datetime('now'), updated_at = datetime('now') WHERE id = ?1 AND read_at IS NULL",
@@ -630,34 +1100,75 @@ pub fn toggle_star(conn: &Connection, id: i64) -> AppResult<Entry> {
     find_by_id(conn, id)?.ok_or(AppError::EntryNotFound)
 }
 
-pub fn mark_all_read_by_feed(
-    conn: &Connection,
-    feed_id: i64,
-    older_than_days: Option<i64>,
-) -> AppResult<i64> {
-    let age_condition = older_than_days
-        .map(|days| {
-            format!(
-                " AND COALESCE(published_at, created_at) < datetime('now', '-{} days')",
+/// Keep the article fetched from the entry's link so search can find it
+pub fn set_full_content(conn: &Connection, id: i64, content: &str) -> AppResult<()> {
+    let rows = conn.execute(
+        "UPDATE entry SET full_content = ?2 WHERE id = ?1",
+        params![id, content],
+    )?;
+
+    if rows == 0 {
+        return Err(AppError::EntryNotFound);
+    }
+    Ok(())
+}
+
+/// The fullest text known for an entry: the fetched article, else the feed
+/// content, else its summary
+pub fn find_article_html(conn: &Connection, id: i64) -> AppResult<Option<String>> {
+    let html = conn
+        .query_row(
+            "SELECT COALESCE(full_content, content, summary) FROM entry WHERE id = ?1",
+            params![id],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()?
+        .ok_or(AppError::EntryNotFound)?;
+    Ok(html)
+}
+
+/// Execute an UPDATE ending in `RETURNING id` and collect the IDs
+fn updated_ids(
+    conn: &Connection,
+    sql: &str,
+    params: &[&dyn rusqlite::ToSql],
+) -> AppResult<Vec<i64>> {
+    let mut stmt = conn.prepare(sql)?;
+    let ids = stmt
+        .query_map(params, |row| row.get(0))?
+        .collect::<Result<Vec<i64>, _>>()?;
+    Ok(ids)
+}
+
+/// Returns the IDs of the entries that were marked as read
+pub fn mark_all_read_by_feed(
+    conn: &Connection,
+    feed_id: i64,
+    older_than_days: Option<i64>,
+) -> AppResult<Vec<i64>> {
+    let age_condition = older_than_days
+        .map(|days| {
+            format!(
+                " AND COALESCE(published_at, created_at) < datetime('now', '-{} days')",
                 days
             )
         })
         .unwrap_or_default();
 
     let sql = format!(
-        "UPDATE entry SET read_at = datetime('now'), updated_at = datetime('now') WHERE feed_id = ?1 AND read_at IS NULL{}",
+        "UPDATE entry SET read_at = datetime('now'), updated_at = datetime('now') WHERE feed_id = ?1 AND read_at IS NULL{} RETURNING id",
         age_condition
     );
 
-    let rows = conn.execute(&sql, params![feed_id])?;
-    Ok(rows as i64)
+    updated_ids(conn, &sql, params![feed_id])
 }
 
+/// Returns the IDs of the entries that were marked as read
 pub fn mark_all_read_by_user(
     conn: &Connection,
     user_id: i64,
     older_than_days: Option<i64>,
-) -> AppResult<i64> {
+) -> AppResult<Vec<i64>> {
     let age_condition = older_than_days
         .map(|days| {
             format!(
@@ -674,14 +1185,45 @@ pub fn mark_all_read_by_user(
         WHERE read_at IS NULL{} AND feed_id IN (
             SELECT f.id FROM feed f
             INNER JOIN category c ON f.category_id = c.id
-            WHERE c.user_id = ?1
+            WHERE c.user_id = ?1 AND f.deleted_at IS NULL
         )
+        RETURNING id
         "#,
         age_condition
     );
 
-    let rows = conn.execute(&sql, params![user_id])?;
-    Ok(rows as i64)
+    updated_ids(conn, &sql, params![user_id])
+}
+
+/// Sort key of a listing; NULL keys order last in a DESC listing, like the
+/// empty string
+fn listing_key(sort_order: EntrySortOrder) -> String {
+    format!("COALESCE({}, '')", sort_order.sort_key())
+}
+
+/// The user's entry's value of `listing_key`, or None if the entry is not theirs
+fn entry_listing_key(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    sort_order: EntrySortOrder,
+) -> AppResult<Option<String>> {
+    conn.query_row(
+        &format!(
+            r#"
+            SELECT {}
+            FROM entry e
+            INNER JOIN feed f ON e.feed_id = f.id
+            INNER JOIN category c ON f.category_id = c.id
+            WHERE e.id = ?1 AND c.user_id = ?2 AND f.deleted_at IS NULL AND c.deleted_at IS NULL
+            "#,
+            listing_key(sort_order)
+        ),
+        params![entry_id, user_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(AppError::Database)
 }
 
 /// Result of finding neighboring entries
@@ -689,115 +1231,162 @@ pub fn mark_all_read_by_user(
 pub struct EntryNeighbors {
     pub prev_id: Option<i64>,
     pub next_id: Option<i64>,
+    /// 1-based position of the entry within the filtered stream
+    pub position: i64,
+    /// Number of entries in the filtered stream
+    pub total: i64,
 }
 
-/// Find neighboring entries (prev/next) for a given entry within a user's entries.
-/// Entries are ordered by COALESCE(published_at, created_at) DESC.
-/// - prev_id: the entry that comes before (newer/higher in list)
-/// - next_id: the entry that comes after (older/lower in list)
-/// - unread_only: if true, only consider unread entries as neighbors
-/// - feed_id: if Some, only consider entries from this feed
-/// - category_id: if Some, only consider entries from this category
+/// Find neighboring entries (prev/next) for a given entry within the stream
+/// selected by `filter`, in the same order `list_by_user` returns it.
+/// - prev_id: the entry that comes before (higher in list)
+/// - next_id: the entry that comes after (lower in list)
+///
+/// The entry itself may no longer match the filter, e.g. an unread-only stream
+/// after the entry was opened and marked read. It still counts towards
+/// `position` and `total` so they match the list the reader came from.
+/// Returns None if the entry does not belong to the user.
 pub fn find_neighbors(
     conn: &Connection,
     user_id: i64,
     entry_id: i64,
-    unread_only: bool,
-    feed_id: Option<i64>,
-    category_id: Option<i64>,
-) -> AppResult<EntryNeighbors> {
-    // Get the current entry's sort timestamp
-    let sort_time: Option<String> = conn
-        .query_row(
+    filter: &EntryFilter,
+    sort_order: EntrySortOrder,
+) -> AppResult<Option<EntryNeighbors>> {
+    let key = listing_key(sort_order);
+
+    let Some(sort_key) = entry_listing_key(conn, user_id, entry_id, sort_order)? else {
+        return Ok(None);
+    };
+
+    let (conditions, mut params_vec) = filter_conditions(user_id, filter);
+    let where_clause = conditions.join(" AND ");
+    let key_idx = params_vec.len() + 1;
+    let id_idx = params_vec.len() + 2;
+    params_vec.push(Box::new(sort_key));
+    params_vec.push(Box::new(entry_id));
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+
+    let before = format!(
+        "({key} > ?{k} OR ({key} = ?{k} AND e.id > ?{i}))",
+        key = key,
+        k = key_idx,
+        i = id_idx
+    );
+    let after = format!(
+        "({key} < ?{k} OR ({key} = ?{k} AND e.id < ?{i}))",
+        key = key,
+        k = key_idx,
+        i = id_idx
+    );
+
+    let query_id = |condition: &str, order: &str| -> AppResult<Option<i64>> {
+        let sql = format!(
             r#"
-            SELECT COALESCE(e.published_at, e.created_at)
+            SELECT e.id
             FROM entry e
             INNER JOIN feed f ON e.feed_id = f.id
             INNER JOIN category c ON f.category_id = c.id
-            WHERE e.id = ?1 AND c.user_id = ?2
+            WHERE {} AND {}
+            ORDER BY {} {order}, e.id {order}
+            LIMIT 1
             "#,
-            params![entry_id, user_id],
-            |row| row.get(0),
-        )
-        .optional()?;
-
-    let sort_time = match sort_time {
-        Some(t) => t,
-        None => {
-            return Ok(EntryNeighbors {
-                prev_id: None,
-                next_id: None,
-            })
-        }
+            where_clause,
+            condition,
+            key,
+            order = order
+        );
+        Ok(conn
+            .query_row(&sql, params_refs.as_slice(), |row| row.get(0))
+            .optional()?)
     };
 
-    // Build filter conditions
-    let mut conditions = Vec::new();
-    if unread_only {
-        conditions.push("e.read_at IS NULL".to_string());
-    }
-    if let Some(fid) = feed_id {
-        conditions.push(format!("e.feed_id = {}", fid));
-    }
-    if let Some(cid) = category_id {
-        conditions.push(format!("c.id = {}", cid));
-    }
+    let count = |condition: &str| -> AppResult<i64> {
+        let sql = format!(
+            r#"
+            SELECT COUNT(*)
+            FROM entry e
+            INNER JOIN feed f ON e.feed_id = f.id
+            INNER JOIN category c ON f.category_id = c.id
+            WHERE {} AND {}
+            "#,
+            where_clause, condition
+        );
+        Ok(conn.query_row(&sql, params_refs.as_slice(), |row| row.get(0))?)
+    };
 
-    let extra_conditions = if conditions.is_empty() {
-        String::new()
-    } else {
-        format!(" AND {}", conditions.join(" AND "))
+    // Previous entry (higher in list): smallest key above the current one
+    let prev_id = query_id(&before, "ASC")?;
+    // Next entry (lower in list): largest key below the current one
+    let next_id = query_id(&after, "DESC")?;
+
+    let above = count(&before)?;
+    let below = count(&after)?;
+
+    Ok(Some(EntryNeighbors {
+        prev_id,
+        next_id,
+        position: above + 1,
+        total: above + below + 1,
+    }))
+}
+
+/// Mark as read every entry of the stream selected by `filter` that lists
+/// above `entry_id` (newer, in the default order), or below it when `older`
+/// is set. The reference entry itself is left alone. Returns None if the
+/// entry does not belong to the user, otherwise the IDs of the entries marked.
+pub fn mark_read_relative_to(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    filter: &EntryFilter,
+    sort_order: EntrySortOrder,
+    older: bool,
+) -> AppResult<Option<Vec<i64>>> {
+    let Some(sort_key) = entry_listing_key(conn, user_id, entry_id, sort_order)? else {
+        return Ok(None);
     };
 
-    // Find previous entry (newer, comes before in DESC order)
-    let prev_sql = format!(
-        r#"
-        SELECT e.id
-        FROM entry e
-        INNER JOIN feed f ON e.feed_id = f.id
-        INNER JOIN category c ON f.category_id = c.id
-        WHERE c.user_id = ?1
-          AND COALESCE(e.published_at, e.created_at) > ?2
-          {}
-        ORDER BY COALESCE(e.published_at, e.created_at) ASC
-        LIMIT 1
-        "#,
-        extra_conditions
-    );
-    let prev_id: Option<i64> = conn
-        .query_row(&prev_sql, params![user_id, sort_time], |row| row.get(0))
-        .optional()?;
+    let (mut conditions, mut params_vec) = filter_conditions(user_id, filter);
+    let key_idx = params_vec.len() + 1;
+    let id_idx = params_vec.len() + 2;
+    params_vec.push(Box::new(sort_key));
+    params_vec.push(Box::new(entry_id));
+    let cmp = if older { "<" } else { ">" };
+    conditions.push(format!(
+        "({key} {cmp} ?{k} OR ({key} = ?{k} AND e.id {cmp} ?{i}))",
+        key = listing_key(sort_order),
+        cmp = cmp,
+        k = key_idx,
+        i = id_idx
+    ));
 
-    // Find next entry (older, comes after in DESC order)
-    let next_sql = format!(
+    let sql = format!(
         r#"
-        SELECT e.id
-        FROM entry e
-        INNER JOIN feed f ON e.feed_id = f.id
-        INNER JOIN category c ON f.category_id = c.id
-        WHERE c.user_id = ?1
-          AND (COALESCE(e.published_at, e.created_at) < ?2
-               OR (COALESCE(e.published_at, e.created_at) = ?2 AND e.id < ?3))
-          {}
-        ORDER BY COALESCE(e.published_at, e.created_at) DESC, e.id DESC
-        LIMIT 1
+        UPDATE entry
+        SET read_at = datetime('now'), updated_at = datetime('now')
+        WHERE read_at IS NULL AND id IN (
+            SELECT e.id
+            FROM entry e
+            INNER JOIN feed f ON e.feed_id = f.id
+            INNER JOIN category c ON f.category_id = c.id
+            WHERE {}
+        )
+        RETURNING id
         "#,
-        extra_conditions
+        conditions.join(" AND ")
     );
-    let next_id: Option<i64> = conn
-        .query_row(&next_sql, params![user_id, sort_time, entry_id], |row| {
-            row.get(0)
-        })
-        .optional()?;
 
-    Ok(EntryNeighbors { prev_id, next_id })
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+    updated_ids(conn, &sql, params_refs.as_slice()).map(Some)
 }
 
+/// Returns the IDs of the entries that were marked as read
 pub fn mark_all_read_by_category(
     conn: &Connection,
     category_id: i64,
     older_than_days: Option<i64>,
-) -> AppResult<i64> {
+) -> AppResult<Vec<i64>> {
     let age_condition = older_than_days
         .map(|days| {
             format!(
@@ -812,22 +1401,22 @@ pub fn mark_all_read_by_category(
         UPDATE entry
         SET read_at = datetime('now'), updated_at = datetime('now')
         WHERE read_at IS NULL{} AND feed_id IN (
-            SELECT id FROM feed WHERE category_id = ?1
+            SELECT id FROM feed WHERE category_id = ?1 AND deleted_at IS NULL
         )
+        RETURNING id
         "#,
         age_condition
     );
 
-    let rows = conn.execute(&sql, params![category_id])?;
-    Ok(rows as i64)
+    updated_ids(conn, &sql, params![category_id])
 }
 
 /// Mark multiple entries as read by their IDs.
 /// Only marks entries that belong to the user (via feed -> category -> user).
-/// Returns the count of entries that were actually marked as read.
-pub fn mark_read_by_ids(conn: &Connection, user_id: i64, entry_ids: &[i64]) -> AppResult<i64> {
+/// Returns the IDs of the entries that were actually marked as read.
+pub fn mark_read_by_ids(conn: &Connection, user_id: i64, entry_ids: &[i64]) -> AppResult<Vec<i64>> {
     if entry_ids.is_empty() {
-        return Ok(0);
+        return Ok(Vec::new());
     }
 
     // Build placeholders for IN clause
@@ -847,8 +1436,9 @@ pub fn mark_read_by_ids(conn: &Connection, user_id: i64, entry_ids: &[i64]) -> A
           AND feed_id IN (
               SELECT f.id FROM feed f
               INNER JOIN category c ON f.category_id = c.id
-              WHERE c.user_id = ?1
+              WHERE c.user_id = ?1 AND f.deleted_at IS NULL AND c.deleted_at IS NULL
           )
+        RETURNING id
         "#,
         in_clause
     );
@@ -860,8 +1450,40 @@ pub fn mark_read_by_ids(conn: &Connection, user_id: i64, entry_ids: &[i64]) -> A
 
     let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
 
-    let rows = conn.execute(&sql, params_refs.as_slice())?;
-    Ok(rows as i64)
+    updated_ids(conn, &sql, params_refs.as_slice())
+}
+
+/// Mark entries read by a bulk action unread again, skipping any that are no
+/// longer read or no longer belong to the user. Returns the count.
+pub fn mark_unread_by_ids(conn: &Connection, user_id: i64, entry_ids: &[i64]) -> AppResult<i64> {
+    let mut total = 0;
+    // Stay well below SQLite's bound parameter limit
+    for chunk in entry_ids.chunks(500) {
+        let placeholders: Vec<String> = (0..chunk.len()).map(|i| format!("?{}", i + 2)).collect();
+        let sql = format!(
+            r#"
+            UPDATE entry
+            SET read_at = NULL, updated_at = datetime('now')
+            WHERE read_at IS NOT NULL
+              AND id IN ({})
+              AND feed_id IN (
+                  SELECT f.id FROM feed f
+                  INNER JOIN category c ON f.category_id = c.id
+                  WHERE c.user_id = ?1 AND f.deleted_at IS NULL
+              )
+            "#,
+            placeholders.join(", ")
+        );
+
+        let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(user_id)];
+        for id in chunk {
+            params_vec.push(Box::new(*id));
+        }
+        let params_refs: Vec<&dyn rusqlite::ToSql> =
+            params_vec.iter().map(|p| p.as_ref()).collect();
+        total += conn.execute(&sql, params_refs.as_slice())? as i64;
+    }
+    Ok(total)
 }
 
 #[cfg(test)]
@@ -949,6 +1571,123 @@ mod tests {
         assert_eq!(updated.id, entry.id);
     }
 
+    #[test]
+    fn test_upsert_entry_records_revisions() {
+        use crate::models::user_settings::{self, RevisionSettings};
+
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "News");
+        let feed_id = create_test_feed(&conn, category_id, "https://example.com/feed.xml");
+        let upsert = |title: &str, content: &str| {
+            upsert_entry(
+                &conn,
+                feed_id,
+                "guid-1",
+                Some(title),
+                None,
+                Some(content),
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+            .0
+        };
+
+        let entry = upsert("Title", "First");
+        mark_as_read(&conn, entry.id).unwrap();
+
+        // Unchanged text is not a revision
+        upsert("Title", "First");
+        upsert("Title", "<p>First</p>");
+        assert_eq!(entry_revision::count_by_entry(&conn, entry.id).unwrap(), 0);
+
+        // A stealth edit is kept, and the entry stays read by default
+        let updated = upsert("Title", "Second");
+        assert!(updated.read_at.is_some());
+        let revisions = entry_revision::list_by_entry(&conn, entry.id).unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].content.as_deref(), Some("<p>First</p>"));
+
+        let settings = RevisionSettings {
+            unread_on_update: true,
+        };
+        user_settings::update_revision_settings(&conn, user_id, settings).unwrap();
+        let updated = upsert("New Title", "Second");
+        assert!(updated.read_at.is_none());
+        assert_eq!(entry_revision::count_by_entry(&conn, entry.id).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_upsert_entry_update_threshold() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Status");
+        let feed_id = create_test_feed(&conn, category_id, "https://status.example.com/feed");
+        feed::set_update_threshold(&conn, feed_id, Some(30)).unwrap();
+        let upsert = |content: &str| {
+            upsert_entry(
+                &conn,
+                feed_id,
+                "incident",
+                Some("Incident"),
+                None,
+                Some(content),
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+            .0
+        };
+
+        let entry = upsert("Investigating elevated error rates on the API.");
+        assert!(entry.content_updated_at.is_none());
+        mark_as_read(&conn, entry.id).unwrap();
+
+        // A typo fix stays read
+        let updated = upsert("Investigating elevated error rates on the API!");
+        assert!(updated.read_at.is_some());
+        assert!(updated.content_updated_at.is_none());
+
+        // A new status is unread again and flagged
+        let updated = upsert("Resolved: a fix was deployed and errors are back to normal.");
+        assert!(updated.read_at.is_none());
+        assert!(updated.content_updated_at.is_some());
+    }
+
+    #[test]
+    fn test_set_comments() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed_id = create_test_feed(&conn, category_id, "https://example.com/feed.xml");
+
+        let (entry, _) =
+            upsert_entry(&conn, feed_id, "guid-1", None, None, None, None, None, None).unwrap();
+        assert!(entry.comments_url.is_none());
+
+        set_comments(
+            &conn,
+            entry.id,
+            Some("https://news.ycombinator.com/item?id=1"),
+            Some(42),
+        )
+        .unwrap();
+
+        let found = find_by_id(&conn, entry.id).unwrap().unwrap();
+        assert_eq!(
+            found.comments_url.as_deref(),
+            Some("https://news.ycombinator.com/item?id=1")
+        );
+        assert_eq!(found.comments_count, Some(42));
+
+        let with_feed = find_by_id_with_feed(&conn, entry.id).unwrap().unwrap();
+        assert_eq!(with_feed.entry.comments_count, Some(42));
+        assert_eq!(with_feed.feed_url, "https://example.com/feed.xml");
+    }
+
     #[test]
     fn test_mark_as_read() {
         let conn = setup_db();
@@ -1007,6 +1746,41 @@ mod tests {
         assert!(unstarred.starred_at.is_none());
     }
 
+    #[test]
+    fn test_find_article_html() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed_id = create_test_feed(&conn, category_id, "https://example.com/feed.xml");
+
+        let (entry, _) = upsert_entry(
+            &conn,
+            feed_id,
+            "guid-123",
+            Some("Test"),
+            None,
+            Some("<p>Feed content</p>"),
+            Some("Summary"),
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            find_article_html(&conn, entry.id).unwrap().as_deref(),
+            Some("<p>Feed content</p>")
+        );
+        set_full_content(&conn, entry.id, "<p>Whole article</p>").unwrap();
+        assert_eq!(
+            find_article_html(&conn, entry.id).unwrap().as_deref(),
+            Some("<p>Whole article</p>")
+        );
+        assert!(matches!(
+            find_article_html(&conn, 9999),
+            Err(AppError::EntryNotFound)
+        ));
+    }
+
     #[test]
     fn test_count_unread() {
         let conn = setup_db();
@@ -1039,6 +1813,145 @@ mod tests {
         assert_eq!(count_unread_by_user(&conn, user_id).unwrap(), 3);
     }
 
+    #[test]
+    fn test_count_stream_totals() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed_id = create_test_feed(&conn, category_id, "https://example.com/feed.xml");
+        let saved = feed::create_synthetic_feed(&conn, category_id, "rdrs:saved", "Saved pages")
+            .unwrap()
+            .id;
+
+        let now = Utc::now();
+        let mut ids = Vec::new();
+        for (id, guid, published_at) in [
+            (feed_id, "recent", now - chrono::Duration::hours(2)),
+            (feed_id, "yesterday", now - chrono::Duration::hours(30)),
+            (feed_id, "old", now - chrono::Duration::days(10)),
+            (saved, "saved", now - chrono::Duration::days(3)),
+        ] {
+            let (entry, _) = upsert_entry(
+                &conn,
+                id,
+                guid,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(published_at),
+            )
+            .unwrap();
+            ids.push(entry.id);
+        }
+        toggle_star(&conn, ids[2]).unwrap();
+        mark_as_read(&conn, ids[2]).unwrap();
+
+        let totals = count_stream_totals(
+            &conn,
+            user_id,
+            "rdrs:saved",
+            now - chrono::Duration::hours(36),
+            now - chrono::Duration::hours(24),
+        )
+        .unwrap();
+        assert_eq!(
+            totals,
+            StreamTotals {
+                unread: 3,
+                starred: 1,
+                read_later: 1,
+                today: 2,
+                last_24h: 1,
+            }
+        );
+
+        let other = create_test_user(&conn, "other");
+        let empty = count_stream_totals(&conn, other, "rdrs:saved", now, now).unwrap();
+        assert_eq!(empty, StreamTotals::default());
+    }
+
+    #[test]
+    fn test_mark_read_relative_to() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed_id = create_test_feed(&conn, category_id, "https://example.com/feed.xml");
+
+        let now = Utc::now();
+        let ids: Vec<i64> = (0..5)
+            .map(|i| {
+                upsert_entry(
+                    &conn,
+                    feed_id,
+                    &format!("guid-{}", i),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(now - chrono::Duration::hours(i)),
+                )
+                .unwrap()
+                .0
+                .id
+            })
+            .collect();
+        let filter = EntryFilter {
+            unread_only: true,
+            ..Default::default()
+        };
+
+        // ids[0] is the newest; mark the two listed above ids[2]
+        let marked = mark_read_relative_to(
+            &conn,
+            user_id,
+            ids[2],
+            &filter,
+            EntrySortOrder::default(),
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(marked.len(), 2);
+        assert!(find_by_id(&conn, ids[1])
+            .unwrap()
+            .unwrap()
+            .read_at
+            .is_some());
+        assert!(find_by_id(&conn, ids[2])
+            .unwrap()
+            .unwrap()
+            .read_at
+            .is_none());
+
+        let marked = mark_read_relative_to(
+            &conn,
+            user_id,
+            ids[2],
+            &filter,
+            EntrySortOrder::default(),
+            true,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(marked.len(), 2);
+        assert_eq!(count_unread_by_user(&conn, user_id).unwrap(), 1);
+
+        let other = create_test_user(&conn, "other");
+        let marked = mark_read_relative_to(
+            &conn,
+            other,
+            ids[2],
+            &filter,
+            EntrySortOrder::default(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(marked, None);
+    }
+
     #[test]
     fn test_parse_datetime_rfc3339() {
         let dt = parse_datetime("2026-01-06T14:28:00Z");
@@ -1400,31 +2313,273 @@ mod tests {
 
         // Mark entries 1 and 2 as read (user 1)
         let marked = mark_read_by_ids(&conn, user_id, &[entry1.id, entry2.id]).unwrap();
-        assert_eq!(marked, 2);
+        assert_eq!(marked.len(), 2);
 
         // Entry 3 should still be unread
         assert_eq!(count_unread_by_user(&conn, user_id).unwrap(), 1);
 
         // Try to mark user 2's entry as read with user 1's credentials - should not work
         let marked = mark_read_by_ids(&conn, user_id, &[other_entry.id]).unwrap();
-        assert_eq!(marked, 0);
+        assert!(marked.is_empty());
 
         // User 2's entry should still be unread
         assert_eq!(count_unread_by_user(&conn, user2_id).unwrap(), 1);
 
-        // Mark already-read entries again - should return 0
+        // Mark already-read entries again - should return nothing
         let marked = mark_read_by_ids(&conn, user_id, &[entry1.id, entry2.id]).unwrap();
-        assert_eq!(marked, 0);
+        assert!(marked.is_empty());
 
-        // Empty array should return 0
+        // Empty array should return nothing
         let marked = mark_read_by_ids(&conn, user_id, &[]).unwrap();
-        assert_eq!(marked, 0);
+        assert!(marked.is_empty());
 
         // Mark remaining entry
         let marked = mark_read_by_ids(&conn, user_id, &[entry3.id]).unwrap();
-        assert_eq!(marked, 1);
+        assert_eq!(marked, vec![entry3.id]);
 
         // All user 1 entries should now be read
         assert_eq!(count_unread_by_user(&conn, user_id).unwrap(), 0);
     }
+
+    #[test]
+    fn test_virtual_stream_since() {
+        let now = DateTime::parse_from_rfc3339("2026-03-10T05:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(
+            VirtualStream::Last24Hours.since(now, 0),
+            now - chrono::Duration::hours(24)
+        );
+
+        // UTC midnight
+        assert_eq!(
+            VirtualStream::Today.since(now, 0).to_rfc3339(),
+            "2026-03-10T00:00:00+00:00"
+        );
+        // UTC-8 (offset 480): still March 9 locally, midnight is 08:00 UTC on March 9
+        assert_eq!(
+            VirtualStream::Today.since(now, 480).to_rfc3339(),
+            "2026-03-09T08:00:00+00:00"
+        );
+        // UTC+8 (offset -480): March 10 locally, midnight is 16:00 UTC on March 9
+        assert_eq!(
+            VirtualStream::Today.since(now, -480).to_rfc3339(),
+            "2026-03-09T16:00:00+00:00"
+        );
+
+        // Named zones: Los Angeles is UTC-7 once DST started on March 8
+        assert_eq!(
+            VirtualStream::Today
+                .since_in(now, Tz::America__Los_Angeles)
+                .to_rfc3339(),
+            "2026-03-09T07:00:00+00:00"
+        );
+        assert_eq!(
+            VirtualStream::Last24Hours.since_in(now, Tz::Asia__Taipei),
+            now - chrono::Duration::hours(24)
+        );
+    }
+
+    #[test]
+    fn test_filter_published_after() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed_id = create_test_feed(&conn, category_id, "https://example.com/feed.xml");
+
+        let now = Utc::now();
+        for (guid, published_at) in [
+            ("recent", Some(now - chrono::Duration::hours(2))),
+            ("old", Some(now - chrono::Duration::days(3))),
+            ("undated", None),
+        ] {
+            upsert_entry(
+                &conn,
+                feed_id,
+                guid,
+                Some(guid),
+                None,
+                None,
+                None,
+                None,
+                published_at,
+            )
+            .unwrap();
+        }
+
+        let filter = EntryFilter {
+            published_after: Some(now - chrono::Duration::hours(24)),
+            ..Default::default()
+        };
+        let results =
+            list_by_user(&conn, user_id, &filter, EntrySortOrder::default(), 10, 0).unwrap();
+        let mut guids: Vec<&str> = results.iter().map(|e| e.entry.guid.as_str()).collect();
+        guids.sort_unstable();
+        // Undated entries fall back to their fetch time
+        assert_eq!(guids, vec!["recent", "undated"]);
+        assert_eq!(count_by_user(&conn, user_id, &filter).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_filter_by_tag() {
+        use crate::models::tag;
+
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed_id = create_test_feed(&conn, category_id, "https://example.com/feed.xml");
+        let (tagged, _) = upsert_entry(
+            &conn,
+            feed_id,
+            "tagged",
+            Some("Tagged"),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        upsert_entry(
+            &conn,
+            feed_id,
+            "plain",
+            Some("Plain"),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let rust = tag::create_tag(&conn, user_id, "Rust", None).unwrap();
+        tag::add_entry_tags(&conn, tagged.id, &[rust.id]).unwrap();
+
+        let filter = EntryFilter {
+            tag: Some("rust".to_string()),
+            ..Default::default()
+        };
+        let results =
+            list_by_user(&conn, user_id, &filter, EntrySortOrder::default(), 10, 0).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entry.id, tagged.id);
+        assert_eq!(count_by_user(&conn, user_id, &filter).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_filter_by_state() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed_id = create_test_feed(&conn, category_id, "https://example.com/feed.xml");
+        let saved = feed::create_synthetic_feed(&conn, category_id, "rdrs:saved", "Saved pages")
+            .unwrap()
+            .id;
+        let mut ids = Vec::new();
+        for (id, guid) in [
+            (feed_id, "read"),
+            (feed_id, "starred"),
+            (saved, "saved"),
+            (saved, "saved-read"),
+        ] {
+            let (entry, _) =
+                upsert_entry(&conn, id, guid, None, None, None, None, None, None).unwrap();
+            ids.push(entry.id);
+        }
+        mark_as_read(&conn, ids[0]).unwrap();
+        toggle_star(&conn, ids[1]).unwrap();
+        mark_as_read(&conn, ids[3]).unwrap();
+
+        let guids = |state: EntryState| {
+            let mut filter = EntryFilter::default();
+            state.apply(&mut filter, "rdrs:saved");
+            list_by_user(&conn, user_id, &filter, state.default_sort(), 10, 0)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.entry.guid)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(guids(EntryState::Read), vec!["saved-read", "read"]);
+        assert_eq!(guids(EntryState::Starred), vec!["starred"]);
+        assert_eq!(guids(EntryState::ReadLater), vec!["saved"]);
+        assert!(guids(EntryState::Summarized).is_empty());
+    }
+
+    #[test]
+    fn test_sort_by_interest() {
+        use crate::models::entry_interest;
+
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed_id = create_test_feed(&conn, category_id, "https://example.com/feed.xml");
+        let mut ids = Vec::new();
+        for guid in ["low", "unscored", "high"] {
+            let (e, _) =
+                upsert_entry(&conn, feed_id, guid, None, None, None, None, None, None).unwrap();
+            ids.push(e.id);
+        }
+        entry_interest::set_scores(&conn, &[(ids[0], 0.2), (ids[2], 0.95)]).unwrap();
+
+        let results = list_by_user(
+            &conn,
+            user_id,
+            &EntryFilter::default(),
+            EntrySortOrder::Interest,
+            10,
+            0,
+        )
+        .unwrap();
+        let sorted: Vec<i64> = results.iter().map(|r| r.entry.id).collect();
+        assert_eq!(sorted, vec![ids[2], ids[1], ids[0]]);
+    }
+
+    #[test]
+    fn test_restore_entry_merges_state() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed_id = create_test_feed(&conn, category_id, "https://example.com/feed.xml");
+        let read_at = Utc::now();
+
+        let created = restore_entry(
+            &conn,
+            feed_id,
+            "guid-1",
+            Some("Title"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(created);
+
+        // Restoring again merges read state without duplicating the entry
+        let created = restore_entry(
+            &conn,
+            feed_id,
+            "guid-1",
+            Some("Other Title"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(read_at),
+            None,
+        )
+        .unwrap();
+        assert!(!created);
+
+        let entries = list_by_user_after_id(&conn, user_id, 0, 10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].title.as_deref(), Some("Title"));
+        assert!(entries[0].read_at.is_some());
+        assert!(entries[0].starred_at.is_none());
+    }
 }
@@ -0,0 +1,325 @@
+//! What changed in a user's entries since a sync cursor.
+//!
+//! Every entry write takes the next number from the `change_seq` counter
+//! into `updated_seq` (and `created_seq` when the entry is new), and a
+//! deleted entry leaves a tombstone numbered the same way; triggers in
+//! `db::schema` keep both in step with every write path. Entries of a feed
+//! or category in the trash count as deleted.
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+
+/// Tombstones are kept this long; cursors older than the oldest one left
+/// have expired
+pub const TOMBSTONE_RETENTION_DAYS: i64 = 30;
+
+pub const DEFAULT_CHANGES_LIMIT: i64 = 500;
+pub const MAX_CHANGES_LIMIT: i64 = 5000;
+
+/// A position in the change sequence. A page cut short part way through a
+/// number (a whole feed renumbered at once) also remembers the last entry
+/// returned, written as `<seq>.<entry id>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Cursor {
+    pub seq: i64,
+    pub after_id: Option<i64>,
+}
+
+impl Cursor {
+    pub fn parse(s: &str) -> Option<Self> {
+        let (seq, after_id) = match s.split_once('.') {
+            Some((seq, id)) => (seq, Some(id.parse().ok()?)),
+            None => (s, None),
+        };
+        let seq = seq.parse().ok().filter(|seq| *seq >= 0)?;
+        Some(Cursor { seq, after_id })
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.after_id {
+            Some(id) => write!(f, "{}.{}", self.seq, id),
+            None => write!(f, "{}", self.seq),
+        }
+    }
+}
+
+/// An entry's state as of its latest change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EntryState {
+    pub id: i64,
+    pub feed_id: i64,
+    pub read: bool,
+    pub starred: bool,
+    /// Send back in `If-Match` when changing the entry
+    pub version: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EntryChanges {
+    /// Pass back as `since` to get the changes after these
+    pub cursor: String,
+    /// Whether there are more changes than the limit allowed; ask again
+    /// with `cursor` right away
+    pub has_more: bool,
+    pub created: Vec<EntryState>,
+    pub updated: Vec<EntryState>,
+    pub deleted: Vec<i64>,
+}
+
+/// The user's entries created, changed or deleted after `since`, oldest
+/// change first, at most `limit` of them
+pub fn changes_since(
+    conn: &Connection,
+    user_id: i64,
+    since: Cursor,
+    limit: i64,
+) -> AppResult<EntryChanges> {
+    let (current, pruned): (i64, i64) = conn.query_row(
+        "SELECT value, pruned_seq FROM change_seq WHERE id = 1",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    // Starting over from 0 is always possible; the tombstones it would miss
+    // are for entries the client has never seen
+    if since.seq > 0 && since.seq < pruned {
+        return Err(AppError::CursorExpired);
+    }
+
+    // Changes made while this runs are numbered past `current` and left
+    // for the next call
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT seq, id, kind, feed_id, read, starred FROM (
+            SELECT e.updated_seq AS seq, e.id AS id,
+                   CASE
+                       WHEN f.deleted_at IS NOT NULL OR c.deleted_at IS NOT NULL THEN 'deleted'
+                       WHEN e.created_seq > ?2 THEN 'created'
+                       ELSE 'updated'
+                   END AS kind,
+                   e.feed_id AS feed_id,
+                   e.read_at IS NOT NULL AS read,
+                   e.starred_at IS NOT NULL AS starred
+            FROM entry e
+            INNER JOIN feed f ON e.feed_id = f.id
+            INNER JOIN category c ON f.category_id = c.id
+            WHERE c.user_id = ?1
+              AND (e.updated_seq > ?2 OR (e.updated_seq = ?2 AND e.id > ?3))
+              AND e.updated_seq <= ?4
+            UNION ALL
+            SELECT seq, entry_id, 'deleted', 0, 0, 0
+            FROM entry_tombstone
+            WHERE user_id = ?1
+              AND (seq > ?2 OR (seq = ?2 AND entry_id > ?3))
+              AND seq <= ?4
+        )
+        ORDER BY seq, id
+        LIMIT ?5
+        "#,
+    )?;
+    let rows = stmt
+        .query_map(
+            params![
+                user_id,
+                since.seq,
+                since.after_id.unwrap_or(i64::MAX),
+                current,
+                limit + 1
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(2)?,
+                    EntryState {
+                        id: row.get(1)?,
+                        feed_id: row.get(3)?,
+                        read: row.get(4)?,
+                        starred: row.get(5)?,
+                        version: row.get(0)?,
+                    },
+                ))
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let has_more = rows.len() as i64 > limit;
+    let mut changes = EntryChanges {
+        cursor: Cursor {
+            seq: current,
+            after_id: None,
+        }
+        .to_string(),
+        has_more,
+        created: Vec::new(),
+        updated: Vec::new(),
+        deleted: Vec::new(),
+    };
+    for (seq, kind, state) in rows.into_iter().take(limit as usize) {
+        if has_more {
+            changes.cursor = Cursor {
+                seq,
+                after_id: Some(state.id),
+            }
+            .to_string();
+        }
+        match kind.as_str() {
+            "created" => changes.created.push(state),
+            "updated" => changes.updated.push(state),
+            _ => changes.deleted.push(state.id),
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Drop tombstones left before `before`, expiring cursors older than the
+/// newest one dropped. Returns the number dropped.
+pub fn prune_tombstones(conn: &Connection, before: DateTime<Utc>) -> AppResult<usize> {
+    let before = before.format("%Y-%m-%d %H:%M:%S").to_string();
+    let pruned: Option<i64> = conn.query_row(
+        "SELECT MAX(seq) FROM entry_tombstone WHERE deleted_at < ?1",
+        params![before],
+        |row| row.get(0),
+    )?;
+    let Some(pruned) = pruned else {
+        return Ok(0);
+    };
+
+    conn.execute(
+        "UPDATE change_seq SET pruned_seq = MAX(pruned_seq, ?1) WHERE id = 1",
+        params![pruned],
+    )?;
+    let rows = conn.execute(
+        "DELETE FROM entry_tombstone WHERE seq <= ?1",
+        params![pruned],
+    )?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, entry, feed, user};
+
+    fn setup() -> (Connection, i64, i64, i64) {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user = user::create_user(&conn, "testuser", "hash", user::Role::User).unwrap();
+        let cat = category::create_category(&conn, user.id, "News").unwrap();
+        let f = feed::create_feed(
+            &conn,
+            cat.id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        (conn, user.id, cat.id, f.id)
+    }
+
+    fn add_entry(conn: &Connection, feed_id: i64, guid: &str) -> i64 {
+        entry::upsert_entry(conn, feed_id, guid, None, None, None, None, None, None)
+            .unwrap()
+            .0
+            .id
+    }
+
+    fn changes(conn: &Connection, user_id: i64, since: &str) -> EntryChanges {
+        changes_since(conn, user_id, Cursor::parse(since).unwrap(), 100).unwrap()
+    }
+
+    #[test]
+    fn test_cursor() {
+        assert_eq!(Cursor::parse("42").unwrap().to_string(), "42");
+        let cursor = Cursor::parse("42.7").unwrap();
+        assert_eq!(cursor.after_id, Some(7));
+        assert_eq!(cursor.to_string(), "42.7");
+        assert_eq!(Cursor::parse("-1"), None);
+        assert_eq!(Cursor::parse("abc"), None);
+        assert_eq!(Cursor::parse("1.x"), None);
+    }
+
+    #[test]
+    fn test_changes_since() {
+        let (conn, user_id, category_id, feed_id) = setup();
+        let first = add_entry(&conn, feed_id, "a");
+
+        let all = changes(&conn, user_id, "0");
+        assert_eq!(all.created.len(), 1);
+        assert!(!all.has_more);
+        assert!(changes(&conn, user_id, &all.cursor).created.is_empty());
+
+        let second = add_entry(&conn, feed_id, "b");
+        entry::mark_as_read(&conn, first).unwrap();
+        let delta = changes(&conn, user_id, &all.cursor);
+        assert_eq!(
+            delta.created.iter().map(|e| e.id).collect::<Vec<_>>(),
+            [second]
+        );
+        assert_eq!(
+            delta.updated,
+            [EntryState {
+                id: first,
+                feed_id,
+                read: true,
+                starred: false,
+                version: entry::version(&conn, first).unwrap(),
+            }]
+        );
+
+        // Trashing the feed hides its entries; purging it leaves tombstones
+        let cursor = delta.cursor;
+        feed::delete_feed(&conn, feed_id, category_id).unwrap();
+        let mut trashed = changes(&conn, user_id, &cursor).deleted;
+        trashed.sort();
+        assert_eq!(trashed, [first, second]);
+
+        let cursor = changes(&conn, user_id, &cursor).cursor;
+        feed::purge_trashed(&conn, Utc::now() + chrono::Duration::days(1)).unwrap();
+        let mut purged = changes(&conn, user_id, &cursor).deleted;
+        purged.sort();
+        assert_eq!(purged, [first, second]);
+    }
+
+    #[test]
+    fn test_pages_and_expiry() {
+        let (conn, user_id, _, feed_id) = setup();
+        for i in 0..5 {
+            add_entry(&conn, feed_id, &format!("guid-{}", i));
+        }
+
+        let page = changes_since(&conn, user_id, Cursor::default(), 3).unwrap();
+        assert!(page.has_more);
+        assert_eq!(page.created.len(), 3);
+        let rest = changes(&conn, user_id, &page.cursor);
+        assert!(!rest.has_more);
+        assert_eq!(rest.created.len(), 2);
+
+        // Another user sees none of it
+        let other = user::create_user(&conn, "other", "hash", user::Role::User).unwrap();
+        assert!(changes(&conn, other.id, "0").created.is_empty());
+
+        let cursor = rest.cursor;
+        conn.execute("DELETE FROM entry WHERE feed_id = ?1", params![feed_id])
+            .unwrap();
+        assert_eq!(changes(&conn, user_id, &cursor).deleted.len(), 5);
+        assert_eq!(
+            prune_tombstones(&conn, Utc::now() + chrono::Duration::days(1)).unwrap(),
+            5
+        );
+        assert!(matches!(
+            changes_since(&conn, user_id, Cursor::parse(&cursor).unwrap(), 100),
+            Err(AppError::CursorExpired)
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+//! Groups copies of the same story carried by different feeds, such as a
+//! press release republished by several sites.
+//!
+//! Each entry gets a story key: the most frequent words of its content in
+//! alphabetical order. Copies of a text share their key even when the
+//! markup, title or surrounding links differ, and listing can then keep one
+//! entry per key.
+
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::AppResult;
+use crate::models::entry_search::id_placeholders;
+use crate::services::digest::html_to_text;
+use crate::services::related::key_terms;
+
+/// Texts with fewer distinct words than this get no key, so short teasers
+/// like "Read more on our site" are not taken for the same story
+const MIN_STORY_TERMS: usize = 8;
+
+/// The copies of a story an entry stands for in a collapsed list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct StoryGroup {
+    /// Lowest entry ID of the story; any member ID expands the group
+    pub group_id: i64,
+    pub group_count: i64,
+}
+
+/// Key shared by the copies of the story in `content`, if it is long enough
+/// to tell stories apart
+pub fn story_key(content: Option<&str>) -> Option<String> {
+    let text = html_to_text(content?);
+    let mut terms = key_terms("", &text);
+    if terms.len() < MIN_STORY_TERMS {
+        return None;
+    }
+    terms.sort();
+    Some(terms.join(" "))
+}
+
+/// Story groups of the entries in `entry_ids`, counting the user's entries
+/// in feeds that are not in the trash. Entries without a key are their own
+/// group.
+pub fn groups(
+    conn: &Connection,
+    user_id: i64,
+    entry_ids: &[i64],
+) -> AppResult<HashMap<i64, StoryGroup>> {
+    let mut groups: HashMap<i64, StoryGroup> = entry_ids
+        .iter()
+        .map(|&id| {
+            let group = StoryGroup {
+                group_id: id,
+                group_count: 1,
+            };
+            (id, group)
+        })
+        .collect();
+    if entry_ids.is_empty() {
+        return Ok(groups);
+    }
+
+    let sql = format!(
+        r#"
+        SELECT e.id, MIN(d.id), COUNT(d.id)
+        FROM entry e
+        INNER JOIN entry d ON d.story_key = e.story_key
+        INNER JOIN feed f ON d.feed_id = f.id
+        INNER JOIN category c ON f.category_id = c.id
+        WHERE c.user_id = ?1 AND f.deleted_at IS NULL AND e.id IN ({})
+        GROUP BY e.id
+        "#,
+        id_placeholders(entry_ids, 2)
+    );
+    let mut params_vec: Vec<&dyn rusqlite::ToSql> = vec![&user_id];
+    params_vec.extend(entry_ids.iter().map(|id| id as &dyn rusqlite::ToSql));
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params_vec.as_slice(), |row| {
+        Ok((row.get::<_, i64>(0)?, row.get(1)?, row.get(2)?))
+    })?;
+    for (id, group_id, group_count) in rows.filter_map(Result::ok) {
+        groups.insert(
+            id,
+            StoryGroup {
+                group_id,
+                group_count,
+            },
+        );
+    }
+
+    Ok(groups)
+}
+
+/// Set the story key of entries stored before entries had one
+pub fn backfill_story_keys(conn: &Connection) -> AppResult<()> {
+    let mut stmt = conn.prepare("SELECT id, content FROM entry WHERE content IS NOT NULL")?;
+    let keys: Vec<(i64, String)> = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .filter_map(Result::ok)
+        .filter_map(|(id, content)| story_key(Some(&content)).map(|key| (id, key)))
+        .collect();
+
+    let mut update = conn.prepare("UPDATE entry SET story_key = ?1 WHERE id = ?2")?;
+    for (id, key) in keys {
+        update.execute(params![key, id])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, entry, feed, user};
+
+    const RELEASE: &str = "<p>Acme today announced the general availability of Rocket, \
+        a reusable launch vehicle for small satellites. Rocket lands itself after \
+        every flight and carries payloads of up to two hundred kilograms.</p>";
+
+    #[test]
+    fn test_story_key() {
+        let key = story_key(Some(RELEASE)).unwrap();
+        let copy = format!(
+            "<div>{}<a href=\"https://acme.test\">Acme</a></div>",
+            RELEASE
+        );
+        assert_eq!(story_key(Some(&copy)).unwrap(), key);
+        assert!(key.contains("rocket"));
+
+        assert!(story_key(Some("<p>Read more on our site</p>")).is_none());
+        assert!(story_key(None).is_none());
+    }
+
+    #[test]
+    fn test_groups() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "testuser", "hash", user::Role::User)
+            .unwrap()
+            .id;
+        let cat = category::create_category(&conn, user_id, "News").unwrap();
+        let mut ids = Vec::new();
+        for (i, content) in [RELEASE, RELEASE, "<p>Something else</p>"]
+            .into_iter()
+            .enumerate()
+        {
+            let url = format!("https://site{}.test/feed", i);
+            let f = feed::create_feed(&conn, cat.id, &url, None, None, None, None, None).unwrap();
+            let (e, _) = entry::upsert_entry(
+                &conn,
+                f.id,
+                "guid",
+                None,
+                None,
+                Some(content),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+            ids.push(e.id);
+        }
+
+        let groups = groups(&conn, user_id, &ids).unwrap();
+        let shared = StoryGroup {
+            group_id: ids[0],
+            group_count: 2,
+        };
+        assert_eq!(groups[&ids[0]], shared);
+        assert_eq!(groups[&ids[1]], shared);
+        assert_eq!(groups[&ids[2]].group_count, 1);
+        assert_eq!(groups[&ids[2]].group_id, ids[2]);
+    }
+}
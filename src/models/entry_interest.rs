@@ -0,0 +1,289 @@
+//! Stored interest models and the scores they give entries.
+//!
+//! Each user's model is kept as JSON and retrained from their reading
+//! history; every entry it has seen gets a predicted interest between 0 and
+//! 1 that `sort=interest` orders by.
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppResult;
+
+/// Score of entries no model has seen yet, in the middle of the range
+pub const NEUTRAL_SCORE: f64 = 0.5;
+
+/// An unread entry older than this that was never opened counts as skipped
+pub const SKIPPED_AFTER_DAYS: i64 = 7;
+
+/// Entries read within the same second as this many others were marked read
+/// in bulk, e.g. by mark-all-read or an auto-read rule, and count as skipped
+pub const BULK_READ_COUNT: i64 = 3;
+
+/// How the user treated an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Skipped,
+    Read,
+    Starred,
+}
+
+/// What the model looks at in an entry
+#[derive(Debug, Clone)]
+pub struct EntryFeatures {
+    pub entry_id: i64,
+    pub feed_id: i64,
+    pub title: Option<String>,
+    pub author: Option<String>,
+}
+
+fn row_to_features(row: &rusqlite::Row) -> rusqlite::Result<EntryFeatures> {
+    Ok(EntryFeatures {
+        entry_id: row.get(0)?,
+        feed_id: row.get(1)?,
+        title: row.get(2)?,
+        author: row.get(3)?,
+    })
+}
+
+/// The user's entries of the last `days` whose outcome is known, newest
+/// first, at most `limit`
+pub fn training_entries(
+    conn: &Connection,
+    user_id: i64,
+    days: i64,
+    limit: i64,
+) -> AppResult<Vec<(EntryFeatures, Outcome)>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT e.id, e.feed_id, e.title, e.author,
+               CASE
+                   WHEN e.starred_at IS NOT NULL THEN 2
+                   WHEN e.read_at IS NULL THEN 0
+                   WHEN (
+                       SELECT COUNT(*) FROM entry e2
+                       INNER JOIN feed f2 ON e2.feed_id = f2.id
+                       INNER JOIN category c2 ON f2.category_id = c2.id
+                       WHERE e2.read_at = e.read_at AND c2.user_id = ?1
+                   ) >= ?4 THEN 0
+                   ELSE 1
+               END
+        FROM entry e
+        INNER JOIN feed f ON e.feed_id = f.id
+        INNER JOIN category c ON f.category_id = c.id
+        WHERE c.user_id = ?1
+          AND e.created_at >= datetime('now', '-' || ?2 || ' days')
+          AND (e.read_at IS NOT NULL OR e.starred_at IS NOT NULL
+               OR e.created_at < datetime('now', '-' || ?5 || ' days'))
+        ORDER BY e.id DESC
+        LIMIT ?3
+        "#,
+    )?;
+    let rows = stmt
+        .query_map(
+            params![user_id, days, limit, BULK_READ_COUNT, SKIPPED_AFTER_DAYS],
+            |row| {
+                let outcome = match row.get::<_, i64>(4)? {
+                    2 => Outcome::Starred,
+                    1 => Outcome::Read,
+                    _ => Outcome::Skipped,
+                };
+                Ok((row_to_features(row)?, outcome))
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// The user's unread entries, to score after training
+pub fn unread_entries(conn: &Connection, user_id: i64) -> AppResult<Vec<EntryFeatures>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT e.id, e.feed_id, e.title, e.author
+        FROM entry e
+        INNER JOIN feed f ON e.feed_id = f.id
+        INNER JOIN category c ON f.category_id = c.id
+        WHERE c.user_id = ?1 AND e.read_at IS NULL
+        "#,
+    )?;
+    let rows = stmt
+        .query_map(params![user_id], row_to_features)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Entries by ID, to score as they arrive
+pub fn entries_by_ids(conn: &Connection, ids: &[i64]) -> AppResult<Vec<EntryFeatures>> {
+    let mut stmt = conn.prepare("SELECT id, feed_id, title, author FROM entry WHERE id = ?1")?;
+    let mut rows = Vec::with_capacity(ids.len());
+    for id in ids {
+        if let Some(features) = stmt.query_row(params![id], row_to_features).optional()? {
+            rows.push(features);
+        }
+    }
+    Ok(rows)
+}
+
+/// The user's trained model, as stored by `save_model`
+pub fn find_model(conn: &Connection, user_id: i64) -> AppResult<Option<String>> {
+    let model = conn
+        .query_row(
+            "SELECT model FROM interest_model WHERE user_id = ?1",
+            params![user_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(model)
+}
+
+pub fn save_model(conn: &Connection, user_id: i64, model: &str, examples: i64) -> AppResult<()> {
+    conn.execute(
+        r#"
+        INSERT INTO interest_model (user_id, model, examples)
+        VALUES (?1, ?2, ?3)
+        ON CONFLICT(user_id) DO UPDATE SET
+            model = excluded.model,
+            examples = excluded.examples,
+            trained_at = datetime('now')
+        "#,
+        params![user_id, model, examples],
+    )?;
+    Ok(())
+}
+
+/// Store predicted interest for entries, replacing earlier scores
+pub fn set_scores(conn: &Connection, scores: &[(i64, f64)]) -> AppResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut stmt =
+            tx.prepare("INSERT OR REPLACE INTO entry_interest (entry_id, score) VALUES (?1, ?2)")?;
+        for (entry_id, score) in scores {
+            stmt.execute(params![entry_id, score])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn find_score(conn: &Connection, entry_id: i64) -> AppResult<Option<f64>> {
+    let score = conn
+        .query_row(
+            "SELECT score FROM entry_interest WHERE entry_id = ?1",
+            params![entry_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::Role;
+    use crate::models::{category, entry, feed, user};
+
+    fn setup() -> (Connection, i64, i64) {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "reader", "hash123", Role::User)
+            .unwrap()
+            .id;
+        let category_id = category::create_category(&conn, user_id, "Tech")
+            .unwrap()
+            .id;
+        let feed_id = feed::create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed.xml",
+            Some("Test Feed"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .id;
+        (conn, user_id, feed_id)
+    }
+
+    fn add_entry(conn: &Connection, feed_id: i64, guid: &str) -> i64 {
+        entry::upsert_entry(
+            conn,
+            feed_id,
+            guid,
+            Some(guid),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .0
+        .id
+    }
+
+    #[test]
+    fn test_training_outcomes() {
+        let (conn, user_id, feed_id) = setup();
+        let starred = add_entry(&conn, feed_id, "starred");
+        let read = add_entry(&conn, feed_id, "read");
+        let bulk: Vec<i64> = (0..3)
+            .map(|i| add_entry(&conn, feed_id, &format!("bulk-{}", i)))
+            .collect();
+        let stale = add_entry(&conn, feed_id, "stale");
+        add_entry(&conn, feed_id, "fresh");
+
+        conn.execute(
+            "UPDATE entry SET starred_at = datetime('now') WHERE id = ?1",
+            params![starred],
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE entry SET read_at = datetime('now', '-1 hours') WHERE id = ?1",
+            params![read],
+        )
+        .unwrap();
+        for id in &bulk {
+            conn.execute(
+                "UPDATE entry SET read_at = datetime('now', '-2 hours') WHERE id = ?1",
+                params![id],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "UPDATE entry SET created_at = datetime('now', '-10 days') WHERE id = ?1",
+            params![stale],
+        )
+        .unwrap();
+
+        let rows = training_entries(&conn, user_id, 90, 100).unwrap();
+        let outcome = |id: i64| rows.iter().find(|(e, _)| e.entry_id == id).map(|r| r.1);
+        assert_eq!(rows.len(), 6);
+        assert_eq!(outcome(starred), Some(Outcome::Starred));
+        assert_eq!(outcome(read), Some(Outcome::Read));
+        assert_eq!(outcome(bulk[0]), Some(Outcome::Skipped));
+        assert_eq!(outcome(stale), Some(Outcome::Skipped));
+
+        // Unread entries that are still new are not judged yet
+        assert_eq!(unread_entries(&conn, user_id).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_models_and_scores() {
+        let (conn, user_id, feed_id) = setup();
+        let id = add_entry(&conn, feed_id, "one");
+
+        assert!(find_model(&conn, user_id).unwrap().is_none());
+        save_model(&conn, user_id, "{}", 10).unwrap();
+        save_model(&conn, user_id, "{\"bias\":1}", 12).unwrap();
+        assert_eq!(
+            find_model(&conn, user_id).unwrap().as_deref(),
+            Some("{\"bias\":1}")
+        );
+
+        set_scores(&conn, &[(id, 0.25)]).unwrap();
+        set_scores(&conn, &[(id, 0.75)]).unwrap();
+        assert_eq!(find_score(&conn, id).unwrap(), Some(0.75));
+        assert_eq!(entries_by_ids(&conn, &[id, 999]).unwrap().len(), 1);
+    }
+}
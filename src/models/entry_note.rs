@@ -0,0 +1,221 @@
+//! A free-form Markdown note on an entry, such as why it was starred.
+//!
+//! Each entry has at most one note. Notes are kept as written and rendered
+//! when shown; the search index holds the Markdown source.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::{AppError, AppResult};
+
+pub const MAX_CHARS: usize = 20_000;
+
+#[derive(Debug, Clone)]
+pub struct EntryNote {
+    pub entry_id: i64,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_note(row: &rusqlite::Row) -> rusqlite::Result<EntryNote> {
+    let created_at: String = row.get(2)?;
+    let updated_at: String = row.get(3)?;
+    Ok(EntryNote {
+        entry_id: row.get(0)?,
+        content: row.get(1)?,
+        created_at: parse_datetime(&created_at),
+        updated_at: parse_datetime(&updated_at),
+    })
+}
+
+pub fn validate(content: &str) -> AppResult<()> {
+    if content.chars().count() > MAX_CHARS {
+        return Err(AppError::Validation(format!(
+            "Note must be at most {} characters",
+            MAX_CHARS
+        )));
+    }
+    Ok(())
+}
+
+pub fn find(conn: &Connection, user_id: i64, entry_id: i64) -> AppResult<Option<EntryNote>> {
+    let note = conn
+        .query_row(
+            r#"
+            SELECT entry_id, content, created_at, updated_at FROM entry_note
+            WHERE user_id = ?1 AND entry_id = ?2
+            "#,
+            params![user_id, entry_id],
+            row_to_note,
+        )
+        .optional()?;
+    Ok(note)
+}
+
+/// Write the note on an entry, replacing any earlier one
+pub fn set_note(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    content: &str,
+) -> AppResult<EntryNote> {
+    let note = conn.query_row(
+        r#"
+        INSERT INTO entry_note (entry_id, user_id, content)
+        VALUES (?1, ?2, ?3)
+        ON CONFLICT(entry_id) DO UPDATE SET
+            content = excluded.content,
+            updated_at = datetime('now')
+        RETURNING entry_id, content, created_at, updated_at
+        "#,
+        params![entry_id, user_id, content],
+        row_to_note,
+    )?;
+    Ok(note)
+}
+
+/// Add a note restored from a backup, unless the entry already has one.
+/// Returns whether it was added.
+pub fn restore_note(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    content: &str,
+) -> AppResult<bool> {
+    let rows = conn.execute(
+        "INSERT OR IGNORE INTO entry_note (entry_id, user_id, content) VALUES (?1, ?2, ?3)",
+        params![entry_id, user_id, content],
+    )?;
+    Ok(rows > 0)
+}
+
+/// Remove the note on an entry. Returns whether there was one.
+pub fn delete_note(conn: &Connection, user_id: i64, entry_id: i64) -> AppResult<bool> {
+    let rows = conn.execute(
+        "DELETE FROM entry_note WHERE user_id = ?1 AND entry_id = ?2",
+        params![user_id, entry_id],
+    )?;
+    Ok(rows > 0)
+}
+
+/// The user's notes on entries with IDs after `after_id` up to `last_id`,
+/// for a page of a backup
+pub fn list_by_entry_range(
+    conn: &Connection,
+    user_id: i64,
+    after_id: i64,
+    last_id: i64,
+) -> AppResult<Vec<EntryNote>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT entry_id, content, created_at, updated_at FROM entry_note
+        WHERE user_id = ?1 AND entry_id > ?2 AND entry_id <= ?3
+        ORDER BY entry_id
+        "#,
+    )?;
+    let notes = stmt
+        .query_map(params![user_id, after_id, last_id], row_to_note)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::Role;
+    use crate::models::{category, entry, feed, user};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn setup_entry(conn: &Connection) -> (i64, i64) {
+        let user = user::create_user(conn, "testuser", "hash123", Role::User).unwrap();
+        let cat = category::create_category(conn, user.id, "Tech").unwrap();
+        let f = feed::create_feed(
+            conn,
+            cat.id,
+            "https://example.com/feed.xml",
+            Some("Test Feed"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let (e, _) = entry::upsert_entry(
+            conn,
+            f.id,
+            "guid-1",
+            Some("Entry"),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        (user.id, e.id)
+    }
+
+    #[test]
+    fn test_set_and_delete_note() {
+        let conn = setup_db();
+        let (user_id, entry_id) = setup_entry(&conn);
+        assert!(find(&conn, user_id, entry_id).unwrap().is_none());
+
+        set_note(&conn, user_id, entry_id, "First").unwrap();
+        let note = set_note(&conn, user_id, entry_id, "Second").unwrap();
+        assert_eq!(note.content, "Second");
+        assert_eq!(
+            find(&conn, user_id, entry_id).unwrap().unwrap().content,
+            "Second"
+        );
+        assert!(find(&conn, user_id + 1, entry_id).unwrap().is_none());
+
+        assert!(!restore_note(&conn, user_id, entry_id, "Restored").unwrap());
+        assert_eq!(
+            list_by_entry_range(&conn, user_id, 0, entry_id)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        assert!(delete_note(&conn, user_id, entry_id).unwrap());
+        assert!(!delete_note(&conn, user_id, entry_id).unwrap());
+        assert!(restore_note(&conn, user_id, entry_id, "Restored").unwrap());
+    }
+
+    #[test]
+    fn test_notes_are_searchable() {
+        let conn = setup_db();
+        let (user_id, entry_id) = setup_entry(&conn);
+        let matches = |query: &str| -> i64 {
+            conn.query_row(
+                "SELECT COUNT(*) FROM entry_fts WHERE entry_fts MATCH ?1",
+                params![query],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        set_note(&conn, user_id, entry_id, "Saved for the benchmark numbers").unwrap();
+        assert_eq!(matches("note : benchmark"), 1);
+        set_note(&conn, user_id, entry_id, "Changed my mind").unwrap();
+        assert_eq!(matches("note : benchmark"), 0);
+        assert_eq!(matches("note : mind"), 1);
+        delete_note(&conn, user_id, entry_id).unwrap();
+        assert_eq!(matches("note : mind"), 0);
+        assert!(validate(&"x".repeat(MAX_CHARS + 1)).is_err());
+    }
+}
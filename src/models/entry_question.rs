@@ -0,0 +1,158 @@
+//! Questions asked about an entry and the answers given.
+//!
+//! Each user has their own conversation per entry, sent back with the next
+//! question so follow-ups can refer to earlier answers. A conversation keeps
+//! at most `MAX_TURNS` questions; the oldest are dropped first.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::AppResult;
+
+pub const MAX_TURNS: i64 = 20;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryQuestion {
+    pub id: i64,
+    pub question: String,
+    pub answer: String,
+    pub created_at: DateTime<Utc>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_entry_question(row: &rusqlite::Row) -> rusqlite::Result<EntryQuestion> {
+    let created_at: String = row.get(3)?;
+    Ok(EntryQuestion {
+        id: row.get(0)?,
+        question: row.get(1)?,
+        answer: row.get(2)?,
+        created_at: parse_datetime(&created_at),
+    })
+}
+
+/// The user's conversation about an entry, oldest first
+pub fn list(conn: &Connection, user_id: i64, entry_id: i64) -> AppResult<Vec<EntryQuestion>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, question, answer, created_at FROM entry_question
+        WHERE user_id = ?1 AND entry_id = ?2
+        ORDER BY id
+        "#,
+    )?;
+    let questions = stmt
+        .query_map(params![user_id, entry_id], row_to_entry_question)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(questions)
+}
+
+/// Add a question with its answer, dropping the oldest past `MAX_TURNS`
+pub fn add(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    question: &str,
+    answer: &str,
+) -> AppResult<EntryQuestion> {
+    let added = conn.query_row(
+        r#"
+        INSERT INTO entry_question (user_id, entry_id, question, answer)
+        VALUES (?1, ?2, ?3, ?4)
+        RETURNING id, question, answer, created_at
+        "#,
+        params![user_id, entry_id, question, answer],
+        row_to_entry_question,
+    )?;
+
+    conn.execute(
+        r#"
+        DELETE FROM entry_question
+        WHERE user_id = ?1 AND entry_id = ?2
+          AND id NOT IN (
+              SELECT id FROM entry_question
+              WHERE user_id = ?1 AND entry_id = ?2
+              ORDER BY id DESC LIMIT ?3
+          )
+        "#,
+        params![user_id, entry_id, MAX_TURNS],
+    )?;
+    Ok(added)
+}
+
+/// Forget the user's conversation about an entry
+pub fn clear(conn: &Connection, user_id: i64, entry_id: i64) -> AppResult<usize> {
+    let rows = conn.execute(
+        "DELETE FROM entry_question WHERE user_id = ?1 AND entry_id = ?2",
+        params![user_id, entry_id],
+    )?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::Role;
+    use crate::models::{category, entry, feed, user};
+
+    fn setup() -> (Connection, i64, i64) {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "asker", "hash123", Role::User)
+            .unwrap()
+            .id;
+        let category_id = category::create_category(&conn, user_id, "Tech")
+            .unwrap()
+            .id;
+        let feed_id = feed::create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed.xml",
+            Some("Test Feed"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .id;
+        let (entry, _) = entry::upsert_entry(
+            &conn,
+            feed_id,
+            "guid-123",
+            Some("Long read"),
+            Some("https://example.com/entry"),
+            Some("Content"),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        (conn, user_id, entry.id)
+    }
+
+    #[test]
+    fn test_add_list_and_cap() {
+        let (conn, user_id, entry_id) = setup();
+        assert!(list(&conn, user_id, entry_id).unwrap().is_empty());
+
+        for i in 0..MAX_TURNS + 2 {
+            add(&conn, user_id, entry_id, &format!("q{}", i), "a").unwrap();
+        }
+        let questions = list(&conn, user_id, entry_id).unwrap();
+        assert_eq!(questions.len(), MAX_TURNS as usize);
+        assert_eq!(questions[0].question, "q2");
+        assert_eq!(
+            questions.last().unwrap().question,
+            format!("q{}", MAX_TURNS + 1)
+        );
+
+        assert_eq!(clear(&conn, user_id, entry_id).unwrap(), MAX_TURNS as usize);
+        assert!(list(&conn, user_id, entry_id).unwrap().is_empty());
+    }
+}
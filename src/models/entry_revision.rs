@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::AppResult;
+
+/// Revisions kept per entry; older ones are pruned as new ones arrive
+pub const MAX_REVISIONS_PER_ENTRY: i64 = 20;
+
+/// Title and content an entry had before a feed refresh changed them
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryRevision {
+    pub id: i64,
+    pub entry_id: i64,
+    pub title: Option<String>,
+    pub content: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_revision(row: &rusqlite::Row) -> rusqlite::Result<EntryRevision> {
+    let created_at: String = row.get(4)?;
+
+    Ok(EntryRevision {
+        id: row.get(0)?,
+        entry_id: row.get(1)?,
+        title: row.get(2)?,
+        content: row.get(3)?,
+        created_at: parse_datetime(&created_at),
+    })
+}
+
+/// Keep the previous title and content of an entry, pruning the oldest
+/// revisions beyond `MAX_REVISIONS_PER_ENTRY`
+pub fn record_revision(
+    conn: &Connection,
+    entry_id: i64,
+    title: Option<&str>,
+    content: Option<&str>,
+) -> AppResult<()> {
+    conn.execute(
+        "INSERT INTO entry_revision (entry_id, title, content) VALUES (?1, ?2, ?3)",
+        params![entry_id, title, content],
+    )?;
+    conn.execute(
+        r#"
+        DELETE FROM entry_revision
+        WHERE entry_id = ?1 AND id NOT IN (
+            SELECT id FROM entry_revision WHERE entry_id = ?1 ORDER BY id DESC LIMIT ?2
+        )
+        "#,
+        params![entry_id, MAX_REVISIONS_PER_ENTRY],
+    )?;
+    Ok(())
+}
+
+/// Revisions of an entry, oldest first
+pub fn list_by_entry(conn: &Connection, entry_id: i64) -> AppResult<Vec<EntryRevision>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, entry_id, title, content, created_at FROM entry_revision WHERE entry_id = ?1 ORDER BY id ASC",
+    )?;
+    let revisions = stmt
+        .query_map(params![entry_id], row_to_revision)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(revisions)
+}
+
+pub fn count_by_entry(conn: &Connection, entry_id: i64) -> AppResult<i64> {
+    let count = conn.query_row(
+        "SELECT COUNT(*) FROM entry_revision WHERE entry_id = ?1",
+        params![entry_id],
+        |row| row.get(0),
+    )?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, entry, feed, user};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_record_and_prune_revisions() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", user::Role::User).unwrap();
+        let cat = category::create_category(&conn, user.id, "News").unwrap();
+        let f = feed::create_feed(
+            &conn,
+            cat.id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let (e, _) =
+            entry::upsert_entry(&conn, f.id, "guid", None, None, None, None, None, None).unwrap();
+
+        for i in 0..MAX_REVISIONS_PER_ENTRY + 5 {
+            let content = format!("version {}", i);
+            record_revision(&conn, e.id, Some("Title"), Some(&content)).unwrap();
+        }
+
+        let revisions = list_by_entry(&conn, e.id).unwrap();
+        assert_eq!(revisions.len() as i64, MAX_REVISIONS_PER_ENTRY);
+        assert_eq!(
+            count_by_entry(&conn, e.id).unwrap(),
+            MAX_REVISIONS_PER_ENTRY
+        );
+        // Oldest first, with the oldest five pruned
+        assert_eq!(revisions[0].content.as_deref(), Some("version 5"));
+        assert_eq!(
+            revisions.last().unwrap().content.as_deref(),
+            Some(format!("version {}", MAX_REVISIONS_PER_ENTRY + 4).as_str())
+        );
+    }
+}
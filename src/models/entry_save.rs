@@ -0,0 +1,285 @@
+//! Where an entry was saved, one row per save service.
+//!
+//! Rows are written both by the [Save] button and by the job that saves
+//! starred entries automatically, so the entry page can show what happened
+//! either way. A service the entry was saved to is not tried again.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::AppResult;
+use crate::services::save::SaveResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SaveStatus {
+    /// Queued to be saved
+    Pending,
+    Saved,
+    Failed,
+}
+
+impl SaveStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SaveStatus::Pending => "pending",
+            SaveStatus::Saved => "saved",
+            SaveStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "saved" => SaveStatus::Saved,
+            "failed" => SaveStatus::Failed,
+            _ => SaveStatus::Pending,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntrySave {
+    pub service: String,
+    pub status: SaveStatus,
+    /// The service's answer to the last try
+    pub message: Option<String>,
+    pub bookmark_url: Option<String>,
+    pub attempts: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_save(row: &rusqlite::Row) -> rusqlite::Result<EntrySave> {
+    let status: String = row.get(1)?;
+    let updated_at: String = row.get(5)?;
+    Ok(EntrySave {
+        service: row.get(0)?,
+        status: SaveStatus::parse(&status),
+        message: row.get(2)?,
+        bookmark_url: row.get(3)?,
+        attempts: row.get(4)?,
+        updated_at: parse_datetime(&updated_at),
+    })
+}
+
+/// Mark an entry as waiting to be saved to `service`. Returns false when it
+/// was saved there already.
+pub fn mark_pending(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    service: &str,
+) -> AppResult<bool> {
+    let rows = conn.execute(
+        r#"
+        INSERT INTO entry_save (entry_id, user_id, service, status)
+        VALUES (?1, ?2, ?3, 'pending')
+        ON CONFLICT(entry_id, service) DO UPDATE SET
+            status = 'pending',
+            updated_at = datetime('now')
+        WHERE entry_save.status != 'saved'
+        "#,
+        params![entry_id, user_id, service],
+    )?;
+    Ok(rows > 0)
+}
+
+/// Record the outcome of saving an entry to `result.service`
+pub fn record(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    result: &SaveResult,
+) -> AppResult<()> {
+    let status = if result.success {
+        SaveStatus::Saved
+    } else {
+        SaveStatus::Failed
+    };
+    conn.execute(
+        r#"
+        INSERT INTO entry_save (entry_id, user_id, service, status, message, bookmark_url, attempts)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1)
+        ON CONFLICT(entry_id, service) DO UPDATE SET
+            status = excluded.status,
+            message = excluded.message,
+            bookmark_url = COALESCE(excluded.bookmark_url, entry_save.bookmark_url),
+            attempts = entry_save.attempts + 1,
+            updated_at = datetime('now')
+        "#,
+        params![
+            entry_id,
+            user_id,
+            result.service,
+            status.as_str(),
+            result.message,
+            result.bookmark_url
+        ],
+    )?;
+    Ok(())
+}
+
+/// Services the entry is still waiting to be saved to
+pub fn pending_services(conn: &Connection, user_id: i64, entry_id: i64) -> AppResult<Vec<String>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT service FROM entry_save
+        WHERE user_id = ?1 AND entry_id = ?2 AND status = 'pending'
+        ORDER BY service
+        "#,
+    )?;
+    let services = stmt
+        .query_map(params![user_id, entry_id], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(services)
+}
+
+/// Stop waiting on saves that will not happen, with `message` as the reason
+pub fn fail_pending(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    message: &str,
+) -> AppResult<()> {
+    conn.execute(
+        r#"
+        UPDATE entry_save SET status = 'failed', message = ?3, updated_at = datetime('now')
+        WHERE user_id = ?1 AND entry_id = ?2 AND status = 'pending'
+        "#,
+        params![user_id, entry_id, message],
+    )?;
+    Ok(())
+}
+
+/// Forget saves still waiting, e.g. once the entry is no longer starred
+pub fn clear_pending(conn: &Connection, user_id: i64, entry_id: i64) -> AppResult<()> {
+    conn.execute(
+        "DELETE FROM entry_save WHERE user_id = ?1 AND entry_id = ?2 AND status = 'pending'",
+        params![user_id, entry_id],
+    )?;
+    Ok(())
+}
+
+pub fn list_by_entry(conn: &Connection, user_id: i64, entry_id: i64) -> AppResult<Vec<EntrySave>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT service, status, message, bookmark_url, attempts, updated_at FROM entry_save
+        WHERE user_id = ?1 AND entry_id = ?2
+        ORDER BY service
+        "#,
+    )?;
+    let saves = stmt
+        .query_map(params![user_id, entry_id], row_to_save)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(saves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::Role;
+    use crate::models::{category, entry, feed, user};
+
+    fn setup_entry(conn: &Connection) -> (i64, i64) {
+        let user = user::create_user(conn, "testuser", "hash123", Role::User).unwrap();
+        let cat = category::create_category(conn, user.id, "Tech").unwrap();
+        let f = feed::create_feed(
+            conn,
+            cat.id,
+            "https://example.com/feed.xml",
+            Some("Test Feed"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let (e, _) = entry::upsert_entry(
+            conn,
+            f.id,
+            "guid-1",
+            Some("Entry"),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        (user.id, e.id)
+    }
+
+    fn result(success: bool, bookmark_url: Option<&str>) -> SaveResult {
+        SaveResult {
+            success,
+            service: "linkding".to_string(),
+            message: if success { "Saved" } else { "Timed out" }.to_string(),
+            bookmark_url: bookmark_url.map(str::to_string),
+            already_saved: false,
+        }
+    }
+
+    #[test]
+    fn test_save_status_lifecycle() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let (user_id, entry_id) = setup_entry(&conn);
+
+        assert!(mark_pending(&conn, user_id, entry_id, "linkding").unwrap());
+        assert_eq!(
+            pending_services(&conn, user_id, entry_id).unwrap(),
+            vec!["linkding"]
+        );
+
+        record(&conn, user_id, entry_id, &result(false, None)).unwrap();
+        let saves = list_by_entry(&conn, user_id, entry_id).unwrap();
+        assert_eq!(saves[0].status, SaveStatus::Failed);
+        assert_eq!(saves[0].message.as_deref(), Some("Timed out"));
+
+        // A failed save is tried again
+        assert!(mark_pending(&conn, user_id, entry_id, "linkding").unwrap());
+        record(
+            &conn,
+            user_id,
+            entry_id,
+            &result(true, Some("https://ld.example.com/bookmarks/1")),
+        )
+        .unwrap();
+        let saves = list_by_entry(&conn, user_id, entry_id).unwrap();
+        assert_eq!(saves[0].status, SaveStatus::Saved);
+        assert_eq!(saves[0].attempts, 2);
+
+        // A saved one is not
+        assert!(!mark_pending(&conn, user_id, entry_id, "linkding").unwrap());
+        assert!(pending_services(&conn, user_id, entry_id)
+            .unwrap()
+            .is_empty());
+        assert!(list_by_entry(&conn, user_id + 1, entry_id)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_fail_and_clear_pending() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let (user_id, entry_id) = setup_entry(&conn);
+
+        mark_pending(&conn, user_id, entry_id, "linkding").unwrap();
+        fail_pending(&conn, user_id, entry_id, "Gave up").unwrap();
+        let saves = list_by_entry(&conn, user_id, entry_id).unwrap();
+        assert_eq!(saves[0].status, SaveStatus::Failed);
+        assert_eq!(saves[0].message.as_deref(), Some("Gave up"));
+
+        mark_pending(&conn, user_id, entry_id, "linkding").unwrap();
+        clear_pending(&conn, user_id, entry_id).unwrap();
+        assert!(list_by_entry(&conn, user_id, entry_id).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,544 @@
+//! Full-text search over entries.
+//!
+//! `entry_fts` is an FTS5 table using the trigram tokenizer, kept in step
+//! with entries, fetched full content, summaries and notes by triggers in
+//! `db::schema`. Trigrams match any substring of three or more characters,
+//! in any script, so results stay the same as the `LIKE` search it replaced.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppResult;
+use crate::services::digest::html_to_text;
+
+/// Shortest query the trigram index can look up; shorter ones use `LIKE`
+const MIN_MATCH_CHARS: usize = 3;
+
+/// Approximate number of characters around a match kept in a snippet
+const SNIPPET_TOKENS: i64 = 48;
+
+/// Characters kept before the first match when scanning text for a snippet
+const SNIPPET_LEAD_CHARS: usize = 20;
+
+/// Markers `snippet()` puts around matches, from the private use area so
+/// they never clash with entry text
+const MARK_START: char = '\u{E000}';
+const MARK_END: char = '\u{E001}';
+
+/// `entry_fts` columns by index
+const COLUMN_NAMES: [&str; 5] = ["title", "content", "full_content", "summary", "note"];
+
+/// `entry_fts` columns in snippet preference order, with the field each
+/// belongs to
+const SNIPPET_COLUMNS: [(usize, &str); 5] = [
+    (1, "content"),
+    (2, "content"),
+    (3, "summary"),
+    (4, "note"),
+    (0, "title"),
+];
+
+/// Parts of an entry a search looks in, given as `title|content|summary|note`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct SearchFields {
+    pub title: bool,
+    /// The feed's content and the full article if it was fetched
+    pub content: bool,
+    /// Completed AI summaries
+    pub summary: bool,
+    /// The user's own note on the entry
+    pub note: bool,
+}
+
+impl Default for SearchFields {
+    fn default() -> Self {
+        SearchFields {
+            title: true,
+            content: true,
+            summary: true,
+            note: true,
+        }
+    }
+}
+
+impl FromStr for SearchFields {
+    type Err = String;
+
+    /// An empty list searches every field
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let names: Vec<&str> = s
+            .split(['|', ','])
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect();
+        if names.is_empty() {
+            return Ok(SearchFields::default());
+        }
+
+        let mut fields = SearchFields {
+            title: false,
+            content: false,
+            summary: false,
+            note: false,
+        };
+        for name in names {
+            match name {
+                "title" => fields.title = true,
+                "content" => fields.content = true,
+                "summary" => fields.summary = true,
+                "note" => fields.note = true,
+                other => return Err(format!("Unknown search field: {}", other)),
+            }
+        }
+        Ok(fields)
+    }
+}
+
+impl TryFrom<String> for SearchFields {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for SearchFields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = [
+            ("title", self.title),
+            ("content", self.content),
+            ("summary", self.summary),
+            ("note", self.note),
+        ]
+        .into_iter()
+        .filter_map(|(name, set)| set.then_some(name))
+        .collect();
+        write!(f, "{}", names.join("|"))
+    }
+}
+
+impl SearchFields {
+    /// `entry_fts` columns holding these fields
+    fn columns(&self) -> Vec<&'static str> {
+        let mut columns = Vec::new();
+        if self.title {
+            columns.push("title");
+        }
+        if self.content {
+            columns.extend(["content", "full_content"]);
+        }
+        if self.summary {
+            columns.push("summary");
+        }
+        if self.note {
+            columns.push("note");
+        }
+        columns
+    }
+}
+
+/// WHERE condition selecting entries whose `fields` contain `search`, with
+/// the value to bind to `?{param}`
+pub(crate) fn match_condition(
+    search: &str,
+    fields: SearchFields,
+    param: usize,
+) -> (String, String) {
+    let columns = fields.columns();
+    if search.chars().count() >= MIN_MATCH_CHARS {
+        let condition = format!(
+            "e.id IN (SELECT rowid FROM entry_fts WHERE entry_fts MATCH ?{})",
+            param
+        );
+        return (condition, match_query(search, &columns));
+    }
+
+    let likes: Vec<String> = columns
+        .iter()
+        .map(|column| format!("{} LIKE ?{}", column, param))
+        .collect();
+    let condition = format!(
+        "e.id IN (SELECT rowid FROM entry_fts WHERE {})",
+        likes.join(" OR ")
+    );
+    (condition, format!("%{}%", search))
+}
+
+/// FTS5 query matching `search` as one phrase in any of `columns`
+fn match_query(search: &str, columns: &[&str]) -> String {
+    format!(
+        "{{{}}} : \"{}\"",
+        columns.join(" "),
+        search.replace('"', "\"\"")
+    )
+}
+
+/// Where an entry matched a search
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchSnippet {
+    /// `title`, `content`, `summary` or `note`
+    pub field: &'static str,
+    /// Text around the match, HTML-escaped, with matches wrapped in `<mark>`
+    pub html: String,
+}
+
+/// `?{first}, ?{first + 1}, ...` for an `IN` list of `entry_ids`
+pub(crate) fn id_placeholders(entry_ids: &[i64], first: usize) -> String {
+    let placeholders: Vec<String> = (0..entry_ids.len())
+        .map(|i| format!("?{}", i + first))
+        .collect();
+    placeholders.join(", ")
+}
+
+/// Highlighted snippets for the entries in `entry_ids` that match `search`,
+/// from the first of content, summary, note and title with a match
+pub fn snippets(
+    conn: &Connection,
+    entry_ids: &[i64],
+    search: &str,
+    fields: SearchFields,
+) -> AppResult<HashMap<i64, SearchSnippet>> {
+    if entry_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    if search.chars().count() < MIN_MATCH_CHARS {
+        return scanned_snippets(conn, entry_ids, search, fields);
+    }
+
+    let snippet_columns: Vec<String> = SNIPPET_COLUMNS
+        .iter()
+        .map(|(column, _)| {
+            format!(
+                "snippet(entry_fts, {}, char({}), char({}), '…', {})",
+                column, MARK_START as u32, MARK_END as u32, SNIPPET_TOKENS
+            )
+        })
+        .collect();
+    let sql = format!(
+        "SELECT rowid, {} FROM entry_fts WHERE entry_fts MATCH ?1 AND rowid IN ({})",
+        snippet_columns.join(", "),
+        id_placeholders(entry_ids, 2)
+    );
+
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> =
+        vec![Box::new(match_query(search, &fields.columns()))];
+    for id in entry_ids {
+        params_vec.push(Box::new(*id));
+    }
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params_refs.as_slice(), |row| {
+        let id: i64 = row.get(0)?;
+        let mut texts = Vec::with_capacity(SNIPPET_COLUMNS.len());
+        for i in 0..SNIPPET_COLUMNS.len() {
+            texts.push(row.get::<_, Option<String>>(i + 1)?.unwrap_or_default());
+        }
+        Ok((id, texts))
+    })?;
+
+    let mut snippets = HashMap::new();
+    for (id, texts) in rows.filter_map(Result::ok) {
+        let found = SNIPPET_COLUMNS
+            .iter()
+            .zip(texts)
+            .find(|(_, text)| text.contains(MARK_START));
+        if let Some((&(_, field), text)) = found {
+            let html = snippet_html(&text, field == "content");
+            snippets.insert(id, SearchSnippet { field, html });
+        }
+    }
+
+    Ok(snippets)
+}
+
+/// Snippets for queries too short for the index, found by scanning the
+/// indexed text of each entry
+fn scanned_snippets(
+    conn: &Connection,
+    entry_ids: &[i64],
+    search: &str,
+    fields: SearchFields,
+) -> AppResult<HashMap<i64, SearchSnippet>> {
+    let sql = format!(
+        "SELECT rowid, content, full_content, summary, note, title FROM entry_fts WHERE rowid IN ({})",
+        id_placeholders(entry_ids, 1)
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(entry_ids), |row| {
+        let id: i64 = row.get(0)?;
+        let mut texts = Vec::with_capacity(SNIPPET_COLUMNS.len());
+        for i in 0..SNIPPET_COLUMNS.len() {
+            texts.push(row.get::<_, Option<String>>(i + 1)?.unwrap_or_default());
+        }
+        Ok((id, texts))
+    })?;
+
+    let columns = fields.columns();
+    let mut snippets = HashMap::new();
+    for (id, texts) in rows.filter_map(Result::ok) {
+        let found = SNIPPET_COLUMNS
+            .iter()
+            .zip(texts)
+            .filter(|((column, _), _)| columns.contains(&COLUMN_NAMES[*column]))
+            .find_map(|(&(_, field), text)| {
+                let text = if field == "content" {
+                    html_to_text(&text)
+                } else {
+                    text
+                };
+                mark_matches(&text, search).map(|marked| (field, marked))
+            });
+        if let Some((field, marked)) = found {
+            let html = snippet_html(&marked, false);
+            snippets.insert(id, SearchSnippet { field, html });
+        }
+    }
+
+    Ok(snippets)
+}
+
+/// Cut `text` down to the context around the first case-insensitive match
+/// of `search`, with every match in it wrapped in the snippet markers
+fn mark_matches(text: &str, search: &str) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let needle: Vec<char> = search.chars().collect();
+    if needle.is_empty() || needle.len() > chars.len() {
+        return None;
+    }
+    let matches_at = |i: usize| {
+        chars[i..i + needle.len()]
+            .iter()
+            .zip(&needle)
+            .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+    };
+    let first = (0..=chars.len() - needle.len()).find(|&i| matches_at(i))?;
+
+    // Short texts are shown whole; otherwise start a little before the match
+    let window = 2 * SNIPPET_TOKENS as usize;
+    let start = match chars.len().checked_sub(window) {
+        Some(last_start) => first.saturating_sub(SNIPPET_LEAD_CHARS).min(last_start),
+        None => 0,
+    };
+    let end = chars.len().min(start + window);
+    let mut marked = String::new();
+    if start > 0 {
+        marked.push('…');
+    }
+    let mut i = start;
+    while i < end {
+        if i + needle.len() <= end && matches_at(i) {
+            marked.push(MARK_START);
+            marked.extend(&chars[i..i + needle.len()]);
+            marked.push(MARK_END);
+            i += needle.len();
+        } else {
+            marked.push(chars[i]);
+            i += 1;
+        }
+    }
+    if end < chars.len() {
+        marked.push('…');
+    }
+    Some(marked)
+}
+
+/// Turn a raw snippet into safe HTML. Markup in content columns is dropped,
+/// including a tag cut in half at the start, the text is escaped and the
+/// match markers become `<mark>` elements.
+fn snippet_html(raw: &str, markup: bool) -> String {
+    let text = if markup {
+        let raw = match (raw.find('>'), raw.find('<')) {
+            (Some(close), None) => &raw[close + 1..],
+            (Some(close), Some(open)) if close < open => &raw[close + 1..],
+            _ => raw,
+        };
+        html_to_text(raw)
+    } else {
+        raw.to_string()
+    };
+
+    let mut html = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            MARK_START => html.push_str("<mark>"),
+            MARK_END => html.push_str("</mark>"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, entry, entry_note, entry_summary, feed, user};
+    use crate::services::SummaryOptions;
+
+    fn setup() -> (Connection, i64, i64) {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "testuser", "hash", user::Role::User)
+            .unwrap()
+            .id;
+        let cat = category::create_category(&conn, user_id, "Tech").unwrap();
+        let f = feed::create_feed(
+            &conn,
+            cat.id,
+            "https://example.com/feed.xml",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        (conn, user_id, f.id)
+    }
+
+    fn search(conn: &Connection, user_id: i64, query: &str, fields: &str) -> Vec<i64> {
+        let filter = entry::EntryFilter {
+            search: Some(query.to_string()),
+            search_fields: fields.parse().unwrap(),
+            ..Default::default()
+        };
+        entry::list_by_user(conn, user_id, &filter, Default::default(), 50, 0)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.entry.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        assert_eq!("".parse::<SearchFields>().unwrap(), SearchFields::default());
+        let fields: SearchFields = "title|summary".parse().unwrap();
+        assert!(fields.title && !fields.content && fields.summary);
+        assert_eq!(fields.to_string(), "title|summary");
+        assert!("title|body".parse::<SearchFields>().is_err());
+    }
+
+    #[test]
+    fn test_search_fields() {
+        let (conn, user_id, feed_id) = setup();
+        let a = entry::upsert_entry(
+            &conn,
+            feed_id,
+            "a",
+            Some("Borrow checker"),
+            None,
+            Some("<p>Lifetimes explained</p>"),
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .0;
+        let b = entry::upsert_entry(
+            &conn,
+            feed_id,
+            "b",
+            Some("Other"),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .0;
+        entry::set_full_content(&conn, b.id, "<p>All about lifetimes in depth</p>").unwrap();
+        entry_summary::upsert_pending(&conn, user_id, a.id, SummaryOptions::default()).unwrap();
+        entry_summary::set_completed(&conn, user_id, a.id, "A guide to ownership").unwrap();
+
+        assert_eq!(
+            search(&conn, user_id, "lifetimes", "title"),
+            Vec::<i64>::new()
+        );
+        let mut content = search(&conn, user_id, "LIFETIMES", "content");
+        content.sort();
+        assert_eq!(content, vec![a.id, b.id]);
+        assert_eq!(search(&conn, user_id, "ownership", "summary"), vec![a.id]);
+        assert_eq!(
+            search(&conn, user_id, "ownership", "title|content"),
+            Vec::<i64>::new()
+        );
+        // Too short for trigrams
+        assert_eq!(search(&conn, user_id, "Ot", ""), vec![b.id]);
+
+        entry_note::set_note(&conn, user_id, b.id, "Saved for the *chapter* on scopes").unwrap();
+        assert_eq!(search(&conn, user_id, "chapter", "note"), vec![b.id]);
+        assert_eq!(search(&conn, user_id, "chapter", ""), vec![b.id]);
+        assert_eq!(
+            search(&conn, user_id, "chapter", "title|content|summary"),
+            Vec::<i64>::new()
+        );
+
+        entry_summary::delete(&conn, user_id, a.id).unwrap();
+        assert_eq!(search(&conn, user_id, "ownership", ""), Vec::<i64>::new());
+        conn.execute("DELETE FROM entry WHERE id = ?1", [b.id])
+            .unwrap();
+        assert_eq!(search(&conn, user_id, "lifetimes", ""), vec![a.id]);
+    }
+
+    #[test]
+    fn test_snippets() {
+        let (conn, _user_id, feed_id) = setup();
+        let e = entry::upsert_entry(
+            &conn,
+            feed_id,
+            "a",
+            Some("Rust news"),
+            None,
+            Some("<p>The <b>borrow</b> checker & you</p>"),
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .0;
+
+        let found = snippets(&conn, &[e.id], "checker", SearchFields::default()).unwrap();
+        let snippet = &found[&e.id];
+        assert_eq!(snippet.field, "content");
+        assert_eq!(snippet.html, "The borrow <mark>checker</mark> &amp; you");
+
+        let title_only: SearchFields = "title".parse().unwrap();
+        let found = snippets(&conn, &[e.id], "rust", title_only).unwrap();
+        assert_eq!(found[&e.id].field, "title");
+        assert_eq!(found[&e.id].html, "<mark>Rust</mark> news");
+        // Too short for the index, found by scanning instead
+        let found = snippets(&conn, &[e.id], "ou", SearchFields::default()).unwrap();
+        assert_eq!(
+            found[&e.id].html,
+            "The borrow checker &amp; y<mark>ou</mark>"
+        );
+        let found = snippets(&conn, &[e.id], "ru", title_only).unwrap();
+        assert_eq!(found[&e.id].html, "<mark>Ru</mark>st news");
+    }
+
+    #[test]
+    fn test_mark_matches() {
+        let marked = mark_matches("Go or GO", "go").unwrap();
+        assert_eq!(
+            snippet_html(&marked, false),
+            "<mark>Go</mark> or <mark>GO</mark>"
+        );
+        assert!(mark_matches("Rust", "go").is_none());
+
+        let long = format!("{}needle{}", "a".repeat(100), "b".repeat(200));
+        let marked = mark_matches(&long, "needle").unwrap();
+        assert!(marked.starts_with('…') && marked.ends_with('…'));
+        assert_eq!(marked.chars().count(), 2 * SNIPPET_TOKENS as usize + 4);
+    }
+}
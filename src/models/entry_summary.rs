@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use crate::error::{AppError, AppResult};
+use crate::services::summarize::{SummaryLength, SummaryOptions, SummaryStyle};
 
 /// Summary processing status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -47,6 +48,17 @@ pub struct EntrySummary {
     pub error_message: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub style: SummaryStyle,
+    pub length: SummaryLength,
+}
+
+impl EntrySummary {
+    pub fn options(&self) -> SummaryOptions {
+        SummaryOptions {
+            style: self.style,
+            length: self.length,
+        }
+    }
 }
 
 fn parse_datetime(s: &str) -> DateTime<Utc> {
@@ -62,6 +74,8 @@ fn row_to_entry_summary(row: &rusqlite::Row) -> rusqlite::Result<EntrySummary> {
     let status_str: String = row.get(3)?;
     let created_at: String = row.get(6)?;
     let updated_at: String = row.get(7)?;
+    let style: String = row.get(8)?;
+    let length: String = row.get(9)?;
 
     Ok(EntrySummary {
         id: row.get(0)?,
@@ -72,11 +86,13 @@ fn row_to_entry_summary(row: &rusqlite::Row) -> rusqlite::Result<EntrySummary> {
         error_message: row.get(5)?,
         created_at: parse_datetime(&created_at),
         updated_at: parse_datetime(&updated_at),
+        style: SummaryStyle::parse(&style).unwrap_or_default(),
+        length: SummaryLength::parse(&length).unwrap_or_default(),
     })
 }
 
-const SELECT_COLUMNS: &str =
-    "id, user_id, entry_id, status, summary_text, error_message, created_at, updated_at";
+const SELECT_COLUMNS: &str = "id, user_id, entry_id, status, summary_text, error_message, \
+     created_at, updated_at, style, length";
 
 /// Find a summary by user and entry
 pub fn find_by_user_and_entry(
@@ -96,19 +112,64 @@ pub fn find_by_user_and_entry(
     .map_err(AppError::Database)
 }
 
-/// Create or update a summary with pending status
-pub fn upsert_pending(conn: &Connection, user_id: i64, entry_id: i64) -> AppResult<EntrySummary> {
+/// Create or update a summary with pending status, to be made with `options`
+pub fn upsert_pending(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    options: SummaryOptions,
+) -> AppResult<EntrySummary> {
     conn.execute(
         r#"
-        INSERT INTO entry_summary (user_id, entry_id, status)
-        VALUES (?1, ?2, 'pending')
+        INSERT INTO entry_summary (user_id, entry_id, status, style, length)
+        VALUES (?1, ?2, 'pending', ?3, ?4)
         ON CONFLICT(user_id, entry_id) DO UPDATE SET
             status = 'pending',
             summary_text = NULL,
             error_message = NULL,
+            style = excluded.style,
+            length = excluded.length,
             updated_at = datetime('now')
         "#,
-        params![user_id, entry_id],
+        params![
+            user_id,
+            entry_id,
+            options.style.as_str(),
+            options.length.as_str()
+        ],
+    )?;
+
+    find_by_user_and_entry(conn, user_id, entry_id)?
+        .ok_or(AppError::NotFound("Entry summary not found".to_string()))
+}
+
+/// Create or replace a summary that is already completed
+pub fn upsert_completed(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    options: SummaryOptions,
+    summary_text: &str,
+) -> AppResult<EntrySummary> {
+    conn.execute(
+        r#"
+        INSERT INTO entry_summary (user_id, entry_id, status, summary_text, style, length)
+        VALUES (?1, ?2, 'completed', ?3, ?4, ?5)
+        ON CONFLICT(user_id, entry_id) DO UPDATE SET
+            status = 'completed',
+            summary_text = excluded.summary_text,
+            error_message = NULL,
+            style = excluded.style,
+            length = excluded.length,
+            updated_at = datetime('now')
+        "#,
+        params![
+            user_id,
+            entry_id,
+            summary_text,
+            options.style.as_str(),
+            options.length.as_str()
+        ],
     )?;
 
     find_by_user_and_entry(conn, user_id, entry_id)?
@@ -250,6 +311,18 @@ pub fn get_statuses_for_entries(
     Ok(map)
 }
 
+/// List all summaries belonging to a user
+pub fn list_by_user(conn: &Connection, user_id: i64) -> AppResult<Vec<EntrySummary>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM entry_summary WHERE user_id = ?1 ORDER BY id",
+        SELECT_COLUMNS
+    ))?;
+    let summaries = stmt
+        .query_map(params![user_id], row_to_entry_summary)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(summaries)
+}
+
 /// Get entry IDs that have completed summaries
 pub fn get_completed_entry_ids(conn: &Connection, user_id: i64) -> AppResult<Vec<i64>> {
     let mut stmt = conn.prepare(
@@ -265,11 +338,11 @@ pub fn get_completed_entry_ids(conn: &Connection, user_id: i64) -> AppResult<Vec
 }
 
 /// Find incomplete summaries (pending or processing) for recovery on startup
-/// Returns (user_id, entry_id, entry_link) tuples
-pub fn find_incomplete(conn: &Connection) -> AppResult<Vec<(i64, i64, String)>> {
+/// Returns (user_id, entry_id, entry_link, options) tuples
+pub fn find_incomplete(conn: &Connection) -> AppResult<Vec<(i64, i64, String, SummaryOptions)>> {
     let mut stmt = conn.prepare(
         r#"
-        SELECT es.user_id, es.entry_id, e.link
+        SELECT es.user_id, es.entry_id, e.link, es.style, es.length
         FROM entry_summary es
         INNER JOIN entry e ON es.entry_id = e.id
         WHERE es.status IN ('pending', 'processing') AND e.link IS NOT NULL
@@ -278,10 +351,16 @@ pub fn find_incomplete(conn: &Connection) -> AppResult<Vec<(i64, i64, String)>>
 
     let rows = stmt
         .query_map([], |row| {
+            let style: String = row.get(3)?;
+            let length: String = row.get(4)?;
             Ok((
                 row.get::<_, i64>(0)?,
                 row.get::<_, i64>(1)?,
                 row.get::<_, String>(2)?,
+                SummaryOptions {
+                    style: SummaryStyle::parse(&style).unwrap_or_default(),
+                    length: SummaryLength::parse(&length).unwrap_or_default(),
+                },
             ))
         })?
         .filter_map(Result::ok)
@@ -370,12 +449,34 @@ mod tests {
         let user_id = create_test_user(&conn, "testuser");
         let entry_id = create_test_entry(&conn, user_id);
 
-        let summary = upsert_pending(&conn, user_id, entry_id).unwrap();
+        let summary = upsert_pending(&conn, user_id, entry_id, SummaryOptions::default()).unwrap();
         assert_eq!(summary.status, SummaryStatus::Pending);
         assert!(summary.summary_text.is_none());
         assert!(summary.error_message.is_none());
     }
 
+    #[test]
+    fn test_upsert_completed() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let entry_id = create_test_entry(&conn, user_id);
+
+        upsert_pending(&conn, user_id, entry_id, SummaryOptions::default()).unwrap();
+        set_failed(&conn, user_id, entry_id, "timeout").unwrap();
+
+        let summary = upsert_completed(
+            &conn,
+            user_id,
+            entry_id,
+            SummaryOptions::default(),
+            "Shared text",
+        )
+        .unwrap();
+        assert_eq!(summary.status, SummaryStatus::Completed);
+        assert_eq!(summary.summary_text.as_deref(), Some("Shared text"));
+        assert!(summary.error_message.is_none());
+    }
+
     #[test]
     fn test_status_transitions() {
         let conn = setup_db();
@@ -383,7 +484,7 @@ mod tests {
         let entry_id = create_test_entry(&conn, user_id);
 
         // Create pending
-        upsert_pending(&conn, user_id, entry_id).unwrap();
+        upsert_pending(&conn, user_id, entry_id, SummaryOptions::default()).unwrap();
 
         // Set processing
         set_processing(&conn, user_id, entry_id).unwrap();
@@ -407,7 +508,7 @@ mod tests {
         let user_id = create_test_user(&conn, "testuser");
         let entry_id = create_test_entry(&conn, user_id);
 
-        upsert_pending(&conn, user_id, entry_id).unwrap();
+        upsert_pending(&conn, user_id, entry_id, SummaryOptions::default()).unwrap();
         set_failed(&conn, user_id, entry_id, "API error").unwrap();
 
         let summary = find_by_user_and_entry(&conn, user_id, entry_id)
@@ -423,7 +524,7 @@ mod tests {
         let user_id = create_test_user(&conn, "testuser");
         let entry_id = create_test_entry(&conn, user_id);
 
-        upsert_pending(&conn, user_id, entry_id).unwrap();
+        upsert_pending(&conn, user_id, entry_id, SummaryOptions::default()).unwrap();
         assert!(exists(&conn, user_id, entry_id).unwrap());
 
         let deleted = delete(&conn, user_id, entry_id).unwrap();
@@ -431,6 +532,21 @@ mod tests {
         assert!(!exists(&conn, user_id, entry_id).unwrap());
     }
 
+    #[test]
+    fn test_list_by_user() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let other_id = create_test_user(&conn, "other");
+        let entry_id = create_test_entry(&conn, user_id);
+
+        upsert_pending(&conn, user_id, entry_id, SummaryOptions::default()).unwrap();
+
+        let summaries = list_by_user(&conn, user_id).unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].entry_id, entry_id);
+        assert!(list_by_user(&conn, other_id).unwrap().is_empty());
+    }
+
     #[test]
     fn test_has_completed_summary() {
         let conn = setup_db();
@@ -439,7 +555,7 @@ mod tests {
 
         assert!(!has_completed_summary(&conn, user_id, entry_id).unwrap());
 
-        upsert_pending(&conn, user_id, entry_id).unwrap();
+        upsert_pending(&conn, user_id, entry_id, SummaryOptions::default()).unwrap();
         assert!(!has_completed_summary(&conn, user_id, entry_id).unwrap());
 
         set_completed(&conn, user_id, entry_id, "Summary text").unwrap();
@@ -486,8 +602,8 @@ mod tests {
         }
 
         // Set different statuses
-        upsert_pending(&conn, user_id, entry_ids[0]).unwrap();
-        upsert_pending(&conn, user_id, entry_ids[1]).unwrap();
+        upsert_pending(&conn, user_id, entry_ids[0], SummaryOptions::default()).unwrap();
+        upsert_pending(&conn, user_id, entry_ids[1], SummaryOptions::default()).unwrap();
         set_completed(&conn, user_id, entry_ids[1], "Summary").unwrap();
         // entry_ids[2] has no summary
 
@@ -504,12 +620,17 @@ mod tests {
         let user_id = create_test_user(&conn, "testuser");
         let entry_id = create_test_entry(&conn, user_id);
 
-        upsert_pending(&conn, user_id, entry_id).unwrap();
+        let options = SummaryOptions {
+            style: SummaryStyle::Bullets,
+            length: SummaryLength::Short,
+        };
+        upsert_pending(&conn, user_id, entry_id, options).unwrap();
 
         let incomplete = find_incomplete(&conn).unwrap();
         assert_eq!(incomplete.len(), 1);
         assert_eq!(incomplete[0].0, user_id);
         assert_eq!(incomplete[0].1, entry_id);
+        assert_eq!(incomplete[0].3, options);
     }
 
     #[test]
@@ -519,11 +640,11 @@ mod tests {
         let entry_id = create_test_entry(&conn, user_id);
 
         // Create and complete
-        upsert_pending(&conn, user_id, entry_id).unwrap();
+        upsert_pending(&conn, user_id, entry_id, SummaryOptions::default()).unwrap();
         set_completed(&conn, user_id, entry_id, "Summary").unwrap();
 
         // Upsert should reset to pending
-        let summary = upsert_pending(&conn, user_id, entry_id).unwrap();
+        let summary = upsert_pending(&conn, user_id, entry_id, SummaryOptions::default()).unwrap();
         assert_eq!(summary.status, SummaryStatus::Pending);
         assert!(summary.summary_text.is_none());
     }
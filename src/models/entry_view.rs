@@ -0,0 +1,196 @@
+//! Times a user opened an entry, and how long they spent on it.
+//!
+//! A view is recorded when an entry is opened; clients that track time on
+//! the entry then report the seconds spent so far. Views outlive the
+//! entries and feeds they point at, so reading statistics keep counting
+//! them after a feed is deleted.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+
+use crate::error::{AppError, AppResult};
+
+/// Most seconds one view can add up to; a tab left open overnight is not
+/// eight hours of reading
+pub const MAX_SECONDS: i64 = 2 * 3600;
+
+#[derive(Debug, Clone)]
+pub struct EntryView {
+    pub id: i64,
+    pub entry_id: Option<i64>,
+    pub feed_id: Option<i64>,
+    pub feed_title: Option<String>,
+    pub opened_at: DateTime<Utc>,
+    pub seconds: i64,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .unwrap_or_else(|_| Utc::now())
+}
+
+/// Record that the user opened an entry, returning the view's ID
+pub fn record(conn: &Connection, user_id: i64, entry_id: i64) -> AppResult<i64> {
+    let id = conn.query_row(
+        r#"
+        INSERT INTO entry_view (user_id, entry_id, feed_id)
+        SELECT ?1, id, feed_id FROM entry WHERE id = ?2
+        RETURNING id
+        "#,
+        params![user_id, entry_id],
+        |row| row.get(0),
+    )?;
+    Ok(id)
+}
+
+/// Set the seconds spent on a view so far. Reports arriving out of order
+/// never lower it.
+pub fn set_seconds(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    view_id: i64,
+    seconds: i64,
+) -> AppResult<()> {
+    let rows = conn.execute(
+        r#"
+        UPDATE entry_view SET seconds = MAX(seconds, MIN(?4, ?5))
+        WHERE id = ?1 AND user_id = ?2 AND entry_id = ?3
+        "#,
+        params![view_id, user_id, entry_id, seconds, MAX_SECONDS],
+    )?;
+    if rows == 0 {
+        return Err(AppError::NotFound("View not found".to_string()));
+    }
+    Ok(())
+}
+
+/// The user's views opened at or after `since`, oldest first
+pub fn list_since(
+    conn: &Connection,
+    user_id: i64,
+    since: DateTime<Utc>,
+) -> AppResult<Vec<EntryView>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT v.id, v.entry_id, v.feed_id, f.title, v.opened_at, v.seconds
+        FROM entry_view v
+        LEFT JOIN feed f ON v.feed_id = f.id
+        WHERE v.user_id = ?1 AND v.opened_at >= ?2
+        ORDER BY v.opened_at, v.id
+        "#,
+    )?;
+    let views = stmt
+        .query_map(
+            params![user_id, since.format("%Y-%m-%d %H:%M:%S").to_string()],
+            |row| {
+                let opened_at: String = row.get(4)?;
+                Ok(EntryView {
+                    id: row.get(0)?,
+                    entry_id: row.get(1)?,
+                    feed_id: row.get(2)?,
+                    feed_title: row.get(3)?,
+                    opened_at: parse_datetime(&opened_at),
+                    seconds: row.get(5)?,
+                })
+            },
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(views)
+}
+
+/// When the user opened anything at all, oldest first, for reading streaks
+pub fn opened_times(conn: &Connection, user_id: i64) -> AppResult<Vec<DateTime<Utc>>> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT opened_at FROM entry_view WHERE user_id = ?1 ORDER BY opened_at",
+    )?;
+    let times = stmt
+        .query_map(params![user_id], |row| row.get::<_, String>(0))?
+        .map(|t| t.map(|t| parse_datetime(&t)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(times)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::Role;
+    use crate::models::{category, entry, feed, user};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn setup_entry(conn: &Connection) -> (i64, i64, i64) {
+        let user = user::create_user(conn, "testuser", "hash123", Role::User).unwrap();
+        let cat = category::create_category(conn, user.id, "Tech").unwrap();
+        let f = feed::create_feed(
+            conn,
+            cat.id,
+            "https://example.com/feed.xml",
+            Some("Test Feed"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let (e, _) = entry::upsert_entry(
+            conn,
+            f.id,
+            "guid-1",
+            Some("Entry"),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        (user.id, f.id, e.id)
+    }
+
+    #[test]
+    fn test_record_and_set_seconds() {
+        let conn = setup_db();
+        let (user_id, feed_id, entry_id) = setup_entry(&conn);
+
+        let view_id = record(&conn, user_id, entry_id).unwrap();
+        set_seconds(&conn, user_id, entry_id, view_id, 45).unwrap();
+        // An older report does not lower it, and one view is capped
+        set_seconds(&conn, user_id, entry_id, view_id, 30).unwrap();
+
+        let views = list_since(&conn, user_id, Utc::now() - chrono::Duration::hours(1)).unwrap();
+        assert_eq!(views.len(), 1);
+        assert_eq!(views[0].feed_id, Some(feed_id));
+        assert_eq!(views[0].feed_title.as_deref(), Some("Test Feed"));
+        assert_eq!(views[0].seconds, 45);
+
+        set_seconds(&conn, user_id, entry_id, view_id, 100_000).unwrap();
+        let views = list_since(&conn, user_id, Utc::now() - chrono::Duration::hours(1)).unwrap();
+        assert_eq!(views[0].seconds, MAX_SECONDS);
+
+        assert!(matches!(
+            set_seconds(&conn, user_id, entry_id + 1, view_id, 10),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_views_outlive_entries() {
+        let conn = setup_db();
+        let (user_id, _feed_id, entry_id) = setup_entry(&conn);
+        record(&conn, user_id, entry_id).unwrap();
+
+        conn.execute("DELETE FROM entry WHERE id = ?1", params![entry_id])
+            .unwrap();
+        let views = list_since(&conn, user_id, Utc::now() - chrono::Duration::hours(1)).unwrap();
+        assert_eq!(views.len(), 1);
+        assert_eq!(views[0].entry_id, None);
+        assert_eq!(opened_times(&conn, user_id).unwrap().len(), 1);
+    }
+}
@@ -1,12 +1,27 @@
 use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
 use std::hash::{Hash, Hasher};
 
 use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection, OptionalExtension};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::error::{AppError, AppResult};
-use crate::models::image;
+use crate::models::{category, feed_event, image, notification};
+
+/// Consecutive fetches that must report the same new URL before the feed
+/// is migrated to it
+pub const URL_MIGRATION_THRESHOLD: i64 = 3;
+
+/// Days a feed may keep failing or stay empty before it is flagged as dead
+pub const STALE_FEED_DAYS: i64 = 30;
+
+/// Longest the scheduler waits between attempts at a failing feed
+pub const MAX_BACKOFF_HOURS: i64 = 24;
+
+/// Days of uninterrupted failure after which a feed stops being fetched
+/// until it is retried by hand
+pub const QUARANTINE_DAYS: i64 = 7;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Feed {
@@ -25,6 +40,113 @@ pub struct Feed {
     pub http2_disabled: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub date_strategy: DateStrategy,
+    /// Denormalized entry counters, maintained by triggers on the entry table
+    pub entry_count: i64,
+    pub unread_count: i64,
+    pub last_entry_at: Option<DateTime<Utc>>,
+    /// Extra request headers sent when fetching the feed and its articles
+    pub custom_headers: BTreeMap<String, String>,
+    /// Decayed read/star activity, refreshed periodically by
+    /// `refresh_engagement_scores`
+    pub engagement_score: f64,
+    /// Unread entries older than this many days are marked read; overrides
+    /// the category's rule
+    pub auto_read_days: Option<i64>,
+    /// Entries whose text changes by at least this percent on refresh are
+    /// marked unread again and flagged as updated
+    pub update_threshold: Option<i64>,
+    /// Holds entries that did not come from fetching `url`, such as imported
+    /// bookmarks; never refreshed
+    pub synthetic: bool,
+    /// Set while the feed is in the trash
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Fetches that failed in a row; reset by a successful fetch
+    pub failure_count: i64,
+    /// The scheduler skips the feed until then while it keeps failing
+    pub next_fetch_at: Option<DateTime<Utc>>,
+    /// Set when the feed failed for `QUARANTINE_DAYS`; no longer scheduled
+    pub quarantined_at: Option<DateTime<Utc>>,
+}
+
+/// How entry publication dates from a feed are sanitized on ingestion
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DateStrategy {
+    /// Clamp future dates to fetch time; fill missing dates from the feed
+    /// date, then fetch time
+    #[default]
+    Clamp,
+    /// Ignore declared dates and always use fetch time
+    FetchTime,
+    /// Keep declared dates as-is
+    Trust,
+}
+
+impl DateStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DateStrategy::Clamp => "clamp",
+            DateStrategy::FetchTime => "fetch_time",
+            DateStrategy::Trust => "trust",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "clamp" => Some(DateStrategy::Clamp),
+            "fetch_time" => Some(DateStrategy::FetchTime),
+            "trust" => Some(DateStrategy::Trust),
+            _ => None,
+        }
+    }
+}
+
+/// Order of a user's feed list
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedOrder {
+    /// Feeds the user has arranged first in that order, then by title
+    #[default]
+    Custom,
+    Title,
+    /// Most read and starred feeds first
+    Engagement,
+}
+
+impl FeedOrder {
+    fn order_by(&self) -> &'static str {
+        match self {
+            FeedOrder::Custom => "f.sort_order ASC NULLS LAST, f.title ASC",
+            FeedOrder::Title => "f.title ASC",
+            FeedOrder::Engagement => "f.engagement_score DESC, f.title ASC",
+        }
+    }
+}
+
+/// Days after which a read or star counts half as much toward engagement
+pub const ENGAGEMENT_HALF_LIFE_DAYS: f64 = 14.0;
+
+/// Reads and stars older than this no longer count toward engagement
+const ENGAGEMENT_WINDOW_DAYS: i64 = 180;
+
+/// A star says more about interest than a read
+const STAR_WEIGHT: f64 = 3.0;
+
+/// Why a feed was flagged as dead
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StaleReason {
+    Failing,
+    NoEntries,
+}
+
+#[derive(Debug, Clone)]
+pub struct StaleFeed {
+    pub feed: Feed,
+    pub reason: StaleReason,
+    pub failing_since: Option<DateTime<Utc>>,
+    pub entry_count: i64,
 }
 
 pub fn url_to_bucket(url: &str) -> u8 {
@@ -33,6 +155,49 @@ pub fn url_to_bucket(url: &str) -> u8 {
     (hasher.finish() % 60) as u8
 }
 
+/// Hosts that serve the same FeedBurner feeds under another name
+const FEEDBURNER_ALIASES: &[&str] = &[
+    "feeds2.feedburner.com",
+    "feedproxy.google.com",
+    "feeds.feedburner.com",
+];
+
+/// Form of a feed URL used to spot the same feed subscribed under another
+/// spelling. Scheme, `www.`, default ports, trailing slashes and fragments
+/// are ignored, and FeedBurner aliases map to one host.
+pub fn canonical_url(url: &str) -> String {
+    let Ok(parsed) = url::Url::parse(url.trim()) else {
+        return url.trim().trim_end_matches('/').to_lowercase();
+    };
+
+    let host = parsed.host_str().unwrap_or_default().to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let feedburner = FEEDBURNER_ALIASES.contains(&host);
+    let host = if feedburner {
+        "feeds.feedburner.com"
+    } else {
+        host
+    };
+
+    let mut canonical = host.to_string();
+    if let Some(port) = parsed.port() {
+        canonical.push_str(&format!(":{}", port));
+    }
+    let path = parsed.path().trim_end_matches('/');
+    if feedburner {
+        // FeedBurner paths are case-insensitive and `?format=xml` only picks
+        // the raw rendering of the same feed
+        canonical.push_str(&path.to_lowercase());
+    } else {
+        canonical.push_str(path);
+        if let Some(query) = parsed.query().filter(|q| !q.is_empty()) {
+            canonical.push('?');
+            canonical.push_str(query);
+        }
+    }
+    canonical
+}
+
 fn parse_datetime(s: &str) -> DateTime<Utc> {
     DateTime::parse_from_rfc3339(s)
         .map(|dt| dt.with_timezone(&Utc))
@@ -49,6 +214,14 @@ fn row_to_feed(row: &rusqlite::Row) -> rusqlite::Result<Feed> {
     let http2_disabled: i64 = row.get(12)?;
     let created_at: String = row.get(13)?;
     let updated_at: String = row.get(14)?;
+    let date_strategy: Option<String> = row.get(15)?;
+    let last_entry_at: Option<String> = row.get(18)?;
+    let custom_headers: Option<String> = row.get(19)?;
+    let engagement_score: Option<f64> = row.get(20)?;
+    let synthetic: i64 = row.get(23)?;
+    let deleted_at: Option<String> = row.get(24)?;
+    let next_fetch_at: Option<String> = row.get(26)?;
+    let quarantined_at: Option<String> = row.get(27)?;
 
     Ok(Feed {
         id: row.get(0)?,
@@ -66,9 +239,29 @@ fn row_to_feed(row: &rusqlite::Row) -> rusqlite::Result<Feed> {
         http2_disabled: http2_disabled != 0,
         created_at: parse_datetime(&created_at),
         updated_at: parse_datetime(&updated_at),
+        date_strategy: date_strategy
+            .as_deref()
+            .and_then(DateStrategy::parse)
+            .unwrap_or_default(),
+        entry_count: row.get(16)?,
+        unread_count: row.get(17)?,
+        last_entry_at: last_entry_at.map(|s| parse_datetime(&s)),
+        custom_headers: custom_headers
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        engagement_score: engagement_score.unwrap_or(0.0),
+        auto_read_days: row.get(21)?,
+        update_threshold: row.get(22)?,
+        synthetic: synthetic != 0,
+        deleted_at: deleted_at.map(|s| parse_datetime(&s)),
+        failure_count: row.get(25)?,
+        next_fetch_at: next_fetch_at.map(|s| parse_datetime(&s)),
+        quarantined_at: quarantined_at.map(|s| parse_datetime(&s)),
     })
 }
 
+/// Subscribe a category to a feed. A trashed feed of the category with the
+/// same URL is restored instead, taking the given settings.
 #[allow(clippy::too_many_arguments)]
 pub fn create_feed(
     conn: &Connection,
@@ -81,6 +274,34 @@ pub fn create_feed(
     http2_disabled: Option<bool>,
 ) -> AppResult<Feed> {
     let http2_disabled_int = http2_disabled.unwrap_or(false) as i64;
+    let trashed: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM feed WHERE category_id = ?1 AND url = ?2 AND deleted_at IS NOT NULL",
+            params![category_id, url],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(id) = trashed {
+        // Subscribing again restores the trashed feed with its entries
+        conn.execute(
+            r#"
+            UPDATE feed
+            SET deleted_at = NULL, title = COALESCE(?1, title),
+                description = COALESCE(?2, description), site_url = COALESCE(?3, site_url),
+                custom_user_agent = ?4, http2_disabled = ?5, updated_at = datetime('now')
+            WHERE id = ?6
+            "#,
+            params![
+                title,
+                description,
+                site_url,
+                custom_user_agent,
+                http2_disabled_int,
+                id
+            ],
+        )?;
+        return find_by_id(conn, id)?.ok_or(AppError::FeedNotFound);
+    }
     let result = conn.execute(
         "INSERT INTO feed (category_id, url, title, description, site_url, custom_user_agent, http2_disabled) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
         params![category_id, url, title, description, site_url, custom_user_agent, http2_disabled_int],
@@ -100,11 +321,63 @@ pub fn create_feed(
     }
 }
 
-const SELECT_COLUMNS: &str = "id, category_id, url, title, description, site_url, feed_updated_at, fetched_at, fetch_error, etag, last_modified, custom_user_agent, http2_disabled, created_at, updated_at";
+/// Create a feed that holds entries from elsewhere, such as imported
+/// bookmarks. `url` only identifies it and is never fetched.
+pub fn create_synthetic_feed(
+    conn: &Connection,
+    category_id: i64,
+    url: &str,
+    title: &str,
+) -> AppResult<Feed> {
+    let feed = create_feed(conn, category_id, url, Some(title), None, None, None, None)?;
+    conn.execute(
+        "UPDATE feed SET synthetic = 1 WHERE id = ?1",
+        params![feed.id],
+    )?;
+    find_by_id(conn, feed.id)?.ok_or(AppError::FeedNotFound)
+}
+
+/// Find the user's synthetic feed identified by `url`, creating it in a
+/// category named `title` if the user has none
+pub fn find_or_create_synthetic_feed(
+    conn: &Connection,
+    user_id: i64,
+    url: &str,
+    title: &str,
+) -> AppResult<Feed> {
+    if let Some(found) = list_by_user(conn, user_id)?
+        .into_iter()
+        .find(|f| f.synthetic && f.url == url)
+    {
+        return Ok(found);
+    }
+
+    let cat = match category::find_by_name_and_user(conn, title, user_id)? {
+        Some(cat) => cat,
+        None => category::create_category(conn, user_id, title)?,
+    };
+    create_synthetic_feed(conn, cat.id, url, title)
+}
+
+const SELECT_COLUMNS: &str = "id, category_id, url, title, description, site_url, feed_updated_at, fetched_at, fetch_error, etag, last_modified, custom_user_agent, http2_disabled, created_at, updated_at, date_strategy, entry_count, unread_count, last_entry_at, custom_headers, engagement_score, auto_read_days, update_threshold, synthetic, deleted_at, failure_count, next_fetch_at, quarantined_at";
+
+/// The feed's version for `If-Match`, bumped when a setting users edit changes
+pub fn version(conn: &Connection, id: i64) -> AppResult<i64> {
+    conn.query_row(
+        "SELECT version FROM feed WHERE id = ?1 AND deleted_at IS NULL",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()?
+    .ok_or(AppError::FeedNotFound)
+}
 
 pub fn find_by_id(conn: &Connection, id: i64) -> AppResult<Option<Feed>> {
     conn.query_row(
-        &format!("SELECT {} FROM feed WHERE id = ?1", SELECT_COLUMNS),
+        &format!(
+            "SELECT {} FROM feed WHERE id = ?1 AND deleted_at IS NULL",
+            SELECT_COLUMNS
+        ),
         params![id],
         row_to_feed,
     )
@@ -119,7 +392,7 @@ pub fn find_by_id_and_category(
 ) -> AppResult<Option<Feed>> {
     conn.query_row(
         &format!(
-            "SELECT {} FROM feed WHERE id = ?1 AND category_id = ?2",
+            "SELECT {} FROM feed WHERE id = ?1 AND category_id = ?2 AND deleted_at IS NULL",
             SELECT_COLUMNS
         ),
         params![id, category_id],
@@ -129,6 +402,19 @@ pub fn find_by_id_and_category(
     .map_err(AppError::Database)
 }
 
+/// Find the user's subscription to any of `urls`, comparing canonical URLs
+/// so near-duplicates in any category are caught
+pub fn find_duplicate_by_user(
+    conn: &Connection,
+    user_id: i64,
+    urls: &[&str],
+) -> AppResult<Option<Feed>> {
+    let wanted: Vec<String> = urls.iter().map(|u| canonical_url(u)).collect();
+    Ok(list_by_user(conn, user_id)?
+        .into_iter()
+        .find(|f| !f.synthetic && wanted.contains(&canonical_url(&f.url))))
+}
+
 pub fn find_by_url_and_category(
     conn: &Connection,
     url: &str,
@@ -136,7 +422,7 @@ pub fn find_by_url_and_category(
 ) -> AppResult<Option<Feed>> {
     conn.query_row(
         &format!(
-            "SELECT {} FROM feed WHERE url = ?1 AND category_id = ?2",
+            "SELECT {} FROM feed WHERE url = ?1 AND category_id = ?2 AND deleted_at IS NULL",
             SELECT_COLUMNS
         ),
         params![url, category_id],
@@ -147,17 +433,29 @@ pub fn find_by_url_and_category(
 }
 
 pub fn list_by_user(conn: &Connection, user_id: i64) -> AppResult<Vec<Feed>> {
-    let mut stmt = conn.prepare(
+    list_by_user_ordered(conn, user_id, FeedOrder::Custom)
+}
+
+pub fn list_by_user_ordered(
+    conn: &Connection,
+    user_id: i64,
+    order: FeedOrder,
+) -> AppResult<Vec<Feed>> {
+    let mut stmt = conn.prepare(&format!(
         r#"
         SELECT f.id, f.category_id, f.url, f.title, f.description, f.site_url,
                f.feed_updated_at, f.fetched_at, f.fetch_error, f.etag, f.last_modified,
-               f.custom_user_agent, f.http2_disabled, f.created_at, f.updated_at
+               f.custom_user_agent, f.http2_disabled, f.created_at, f.updated_at,
+               f.date_strategy, f.entry_count, f.unread_count, f.last_entry_at,
+               f.custom_headers, f.engagement_score, f.auto_read_days, f.update_threshold,
+               f.synthetic, f.deleted_at, f.failure_count, f.next_fetch_at, f.quarantined_at
         FROM feed f
         INNER JOIN category c ON f.category_id = c.id
-        WHERE c.user_id = ?1
-        ORDER BY f.title ASC
+        WHERE c.user_id = ?1 AND f.deleted_at IS NULL
+        ORDER BY {}
         "#,
-    )?;
+        order.order_by()
+    ))?;
 
     let feeds = stmt
         .query_map(params![user_id], row_to_feed)?
@@ -169,7 +467,11 @@ pub fn list_by_user(conn: &Connection, user_id: i64) -> AppResult<Vec<Feed>> {
 
 pub fn list_by_category(conn: &Connection, category_id: i64) -> AppResult<Vec<Feed>> {
     let mut stmt = conn.prepare(&format!(
-        "SELECT {} FROM feed WHERE category_id = ?1 ORDER BY title ASC",
+        r#"
+        SELECT {} FROM feed
+        WHERE category_id = ?1 AND deleted_at IS NULL
+        ORDER BY sort_order ASC NULLS LAST, title ASC
+        "#,
         SELECT_COLUMNS
     ))?;
 
@@ -199,7 +501,7 @@ pub fn update_feed(
         r#"
         UPDATE feed
         SET category_id = ?1, url = ?2, title = ?3, description = ?4, site_url = ?5, custom_user_agent = ?6, http2_disabled = ?7, updated_at = datetime('now')
-        WHERE id = ?8 AND category_id = ?9
+        WHERE id = ?8 AND category_id = ?9 AND deleted_at IS NULL
         "#,
         params![new_category_id, url, title, description, site_url, custom_user_agent, http2_disabled_int, id, category_id],
     );
@@ -216,9 +518,79 @@ pub fn update_feed(
     }
 }
 
+/// Replace the extra request headers of a feed; an empty map clears them
+pub fn set_custom_headers(
+    conn: &Connection,
+    id: i64,
+    headers: &BTreeMap<String, String>,
+) -> AppResult<()> {
+    let json = if headers.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(headers).map_err(|e| AppError::Internal(e.to_string()))?)
+    };
+    let rows = conn.execute(
+        "UPDATE feed SET custom_headers = ?1, updated_at = datetime('now') WHERE id = ?2",
+        params![json, id],
+    )?;
+
+    if rows == 0 {
+        return Err(AppError::FeedNotFound);
+    }
+
+    Ok(())
+}
+
+/// Set or clear (`None`) the feed's auto-mark-read rule
+pub fn set_auto_read_days(conn: &Connection, id: i64, days: Option<i64>) -> AppResult<()> {
+    let rows = conn.execute(
+        "UPDATE feed SET auto_read_days = ?1, updated_at = datetime('now') WHERE id = ?2",
+        params![days, id],
+    )?;
+
+    if rows == 0 {
+        return Err(AppError::FeedNotFound);
+    }
+
+    Ok(())
+}
+
+pub fn set_date_strategy(conn: &Connection, id: i64, strategy: DateStrategy) -> AppResult<()> {
+    let rows = conn.execute(
+        "UPDATE feed SET date_strategy = ?1, updated_at = datetime('now') WHERE id = ?2",
+        params![strategy.as_str(), id],
+    )?;
+
+    if rows == 0 {
+        return Err(AppError::FeedNotFound);
+    }
+
+    Ok(())
+}
+
+/// Arrange the user's feeds in the order of `ids`; feeds left out follow in
+/// their current order. Feeds are listed by category, so only the order
+/// within each category shows.
+pub fn reorder(conn: &Connection, user_id: i64, ids: &[i64]) -> AppResult<()> {
+    let current: Vec<i64> = list_by_user(conn, user_id)?.iter().map(|f| f.id).collect();
+    let order = category::move_to_front(&current, ids, AppError::FeedNotFound)?;
+
+    let tx = conn.unchecked_transaction()?;
+    for (position, id) in order.iter().enumerate() {
+        tx.execute(
+            "UPDATE feed SET sort_order = ?1 WHERE id = ?2",
+            params![position as i64, id],
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Move the feed to the trash. It keeps its entries, stars and settings
+/// until it is restored or purged.
 pub fn delete_feed(conn: &Connection, id: i64, category_id: i64) -> AppResult<()> {
     let rows = conn.execute(
-        "DELETE FROM feed WHERE id = ?1 AND category_id = ?2",
+        "UPDATE feed SET deleted_at = datetime('now') WHERE id = ?1 AND category_id = ?2 AND deleted_at IS NULL",
         params![id, category_id],
     )?;
 
@@ -226,12 +598,95 @@ pub fn delete_feed(conn: &Connection, id: i64, category_id: i64) -> AppResult<()
         return Err(AppError::FeedNotFound);
     }
 
-    // Clean up associated image
-    image::delete_by_entity(conn, image::ENTITY_FEED, id)?;
+    Ok(())
+}
 
+/// Hard-delete a feed and its icon
+fn purge_feed(conn: &Connection, id: i64) -> AppResult<()> {
+    conn.execute("DELETE FROM feed WHERE id = ?1", params![id])?;
+    image::delete_by_entity(conn, image::ENTITY_FEED, id)?;
     Ok(())
 }
 
+/// The user's feeds in the trash, most recently deleted first. Includes
+/// feeds deleted along with their category.
+pub fn list_trashed_by_user(conn: &Connection, user_id: i64) -> AppResult<Vec<Feed>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT f.id, f.category_id, f.url, f.title, f.description, f.site_url,
+               f.feed_updated_at, f.fetched_at, f.fetch_error, f.etag, f.last_modified,
+               f.custom_user_agent, f.http2_disabled, f.created_at, f.updated_at,
+               f.date_strategy, f.entry_count, f.unread_count, f.last_entry_at,
+               f.custom_headers, f.engagement_score, f.auto_read_days, f.update_threshold,
+               f.synthetic, f.deleted_at, f.failure_count, f.next_fetch_at, f.quarantined_at
+        FROM feed f
+        INNER JOIN category c ON f.category_id = c.id
+        WHERE c.user_id = ?1 AND f.deleted_at IS NOT NULL
+        ORDER BY f.deleted_at DESC, f.id DESC
+        "#,
+    )?;
+
+    let feeds = stmt
+        .query_map(params![user_id], row_to_feed)?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(feeds)
+}
+
+/// Take one of the user's feeds out of the trash. A trashed category it
+/// belongs to comes back too, without the other feeds deleted with it.
+pub fn restore_feed(conn: &Connection, id: i64, user_id: i64) -> AppResult<Feed> {
+    let category_id: i64 = conn
+        .query_row(
+            r#"
+            SELECT f.category_id FROM feed f
+            INNER JOIN category c ON f.category_id = c.id
+            WHERE f.id = ?1 AND c.user_id = ?2 AND f.deleted_at IS NOT NULL
+            "#,
+            params![id, user_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or(AppError::FeedNotFound)?;
+
+    category::undelete(conn, category_id)?;
+    conn.execute(
+        "UPDATE feed SET deleted_at = NULL WHERE id = ?1",
+        params![id],
+    )?;
+
+    find_by_id(conn, id)?.ok_or(AppError::FeedNotFound)
+}
+
+/// Permanently delete feeds trashed before `before`, with their entries and
+/// icons. Returns the number of feeds removed.
+pub fn purge_trashed(conn: &Connection, before: DateTime<Utc>) -> AppResult<usize> {
+    let before = before.format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut stmt =
+        conn.prepare("SELECT id FROM feed WHERE deleted_at IS NOT NULL AND deleted_at < ?1")?;
+    let ids = stmt
+        .query_map(params![before], |row| row.get::<_, i64>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for id in &ids {
+        purge_feed(conn, *id)?;
+    }
+    Ok(ids.len())
+}
+
+/// How long to wait before fetching a feed again after `failures`
+/// consecutive failures: an hour, doubling with every further failure up to
+/// `MAX_BACKOFF_HOURS`
+pub fn backoff_delay(failures: i64) -> chrono::Duration {
+    let doublings = failures.saturating_sub(1).clamp(0, 16) as u32;
+    let hours = 2_i64.pow(doublings).min(MAX_BACKOFF_HOURS);
+    chrono::Duration::hours(hours)
+}
+
+/// Record the outcome of a fetch. A failure extends the failure streak and
+/// pushes the next scheduled fetch back; a feed failing for
+/// `QUARANTINE_DAYS` is quarantined. A success clears all of it. Returns
+/// whether this fetch put the feed into quarantine.
 pub fn update_fetch_result(
     conn: &Connection,
     id: i64,
@@ -239,21 +694,284 @@ pub fn update_fetch_result(
     fetch_error: Option<&str>,
     etag: Option<&str>,
     last_modified: Option<&str>,
-) -> AppResult<()> {
+) -> AppResult<bool> {
     let fetched_at_str = fetched_at.format("%Y-%m-%d %H:%M:%S").to_string();
+    // error_since keeps the start of the current failure streak
     conn.execute(
         r#"
         UPDATE feed
-        SET fetched_at = ?1, fetch_error = ?2, etag = ?3, last_modified = ?4, updated_at = datetime('now')
+        SET fetched_at = ?1, fetch_error = ?2, etag = ?3, last_modified = ?4,
+            error_since = CASE WHEN ?2 IS NULL THEN NULL ELSE COALESCE(error_since, ?1) END,
+            failure_count = CASE WHEN ?2 IS NULL THEN 0 ELSE failure_count + 1 END,
+            next_fetch_at = NULL,
+            quarantined_at = CASE WHEN ?2 IS NULL THEN NULL ELSE quarantined_at END,
+            updated_at = datetime('now')
         WHERE id = ?5
         "#,
         params![fetched_at_str, fetch_error, etag, last_modified, id],
     )?;
+    if fetch_error.is_none() {
+        return Ok(false);
+    }
+
+    let (failures, error_since, quarantined): (i64, Option<String>, bool) = conn.query_row(
+        "SELECT failure_count, error_since, quarantined_at IS NOT NULL FROM feed WHERE id = ?1",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    let next_fetch_at = (fetched_at + backoff_delay(failures))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    conn.execute(
+        "UPDATE feed SET next_fetch_at = ?1 WHERE id = ?2",
+        params![next_fetch_at, id],
+    )?;
+
+    let quarantine_due = !quarantined
+        && error_since.is_some_and(|s| {
+            fetched_at - parse_datetime(&s) >= chrono::Duration::days(QUARANTINE_DAYS)
+        });
+    if !quarantine_due {
+        return Ok(false);
+    }
+    conn.execute(
+        "UPDATE feed SET quarantined_at = ?1 WHERE id = ?2",
+        params![fetched_at_str, id],
+    )?;
+    let message = format!(
+        "Stopped fetching after {} failures in {} days",
+        failures, QUARANTINE_DAYS
+    );
+    feed_event::create_event(conn, id, feed_event::EVENT_QUARANTINED, Some(&message))?;
+    let name: String = conn.query_row(
+        "SELECT COALESCE(title, url) FROM feed WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    )?;
+    notification::notify_feed_owner(
+        conn,
+        id,
+        notification::KIND_FEED_QUARANTINED,
+        &notification::Message {
+            title: format!("{} is no longer fetched", name),
+            body: Some(message),
+            link: Some(format!("/feeds/{}/entries", id)),
+        },
+    )?;
+    Ok(true)
+}
+
+/// Lift quarantine and backoff so the feed is fetched on its next turn
+pub fn clear_backoff(conn: &Connection, id: i64) -> AppResult<()> {
+    conn.execute(
+        "UPDATE feed SET next_fetch_at = NULL, quarantined_at = NULL WHERE id = ?1",
+        params![id],
+    )?;
+    Ok(())
+}
+
+/// List a user's feeds that have been failing for `days` or longer, or that
+/// are at least `days` old and have never produced an entry.
+/// Validate an update threshold in percent; 0 turns it off
+pub fn parse_update_threshold(percent: i64) -> AppResult<Option<i64>> {
+    match percent {
+        0 => Ok(None),
+        1..=100 => Ok(Some(percent)),
+        _ => Err(AppError::Validation(
+            "Update threshold must be between 0 and 100 percent".to_string(),
+        )),
+    }
+}
+
+/// Set or clear (`None`) the feed's update threshold
+pub fn set_update_threshold(conn: &Connection, id: i64, percent: Option<i64>) -> AppResult<()> {
+    let rows = conn.execute(
+        "UPDATE feed SET update_threshold = ?1, updated_at = datetime('now') WHERE id = ?2",
+        params![percent, id],
+    )?;
+
+    if rows == 0 {
+        return Err(AppError::FeedNotFound);
+    }
+
     Ok(())
 }
 
+pub fn list_stale_by_user(conn: &Connection, user_id: i64, days: i64) -> AppResult<Vec<StaleFeed>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT f.id, f.category_id, f.url, f.title, f.description, f.site_url,
+               f.feed_updated_at, f.fetched_at, f.fetch_error, f.etag, f.last_modified,
+               f.custom_user_agent, f.http2_disabled, f.created_at, f.updated_at,
+               f.date_strategy, f.entry_count, f.unread_count, f.last_entry_at,
+               f.custom_headers, f.engagement_score, f.auto_read_days, f.update_threshold,
+               f.synthetic, f.deleted_at, f.failure_count, f.next_fetch_at, f.quarantined_at,
+               f.error_since,
+               (f.error_since IS NOT NULL AND f.error_since <= datetime('now', ?2)) AS failing
+        FROM feed f
+        INNER JOIN category c ON f.category_id = c.id
+        WHERE c.user_id = ?1
+          AND f.synthetic = 0
+          AND f.deleted_at IS NULL
+          AND (
+            (f.error_since IS NOT NULL AND f.error_since <= datetime('now', ?2))
+            OR (f.created_at <= datetime('now', ?2) AND f.entry_count = 0)
+          )
+        ORDER BY f.title ASC
+        "#,
+    )?;
+
+    let threshold = format!("-{} days", days);
+    let feeds = stmt
+        .query_map(params![user_id, threshold], |row| {
+            let feed = row_to_feed(row)?;
+            let error_since: Option<String> = row.get(28)?;
+            let failing: bool = row.get(29)?;
+            let entry_count = feed.entry_count;
+            Ok(StaleFeed {
+                feed,
+                reason: if failing {
+                    StaleReason::Failing
+                } else {
+                    StaleReason::NoEntries
+                },
+                failing_since: error_since.map(|s| parse_datetime(&s)),
+                entry_count,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(feeds)
+}
+
+/// Where a new URL for a feed was seen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlSource {
+    /// An HTTP 301 or 308 redirect
+    PermanentRedirect,
+    /// The feed's `<link rel="self">`, which is often stale
+    SelfLink,
+}
+
+fn same_host(a: &str, b: &str) -> bool {
+    let host = |u: &str| url::Url::parse(u).ok()?.host_str().map(str::to_string);
+    matches!((host(a), host(b)), (Some(a), Some(b)) if a == b)
+}
+
+/// Record the URL observed while fetching a feed. Only permanent redirects
+/// on the feed's own host move it, after `URL_MIGRATION_THRESHOLD` fetches
+/// in a row redirect to the same URL; the feed's headers and cookies would
+/// otherwise follow it to whatever host it names. Anything else is only
+/// logged as a suggestion.
+///
+/// Returns the new URL if the feed was migrated.
+pub fn record_url_observation(
+    conn: &Connection,
+    id: i64,
+    observed: Option<(&str, UrlSource)>,
+) -> AppResult<Option<String>> {
+    let (url, pending_url, pending_count): (String, Option<String>, i64) = conn
+        .query_row(
+            "SELECT url, pending_url, pending_url_count FROM feed WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?
+        .ok_or(AppError::FeedNotFound)?;
+
+    let (observed, source) = match observed {
+        Some((o, source)) if o != url => (o, source),
+        _ => {
+            if pending_url.is_some() {
+                conn.execute(
+                    "UPDATE feed SET pending_url = NULL, pending_url_count = 0 WHERE id = ?1",
+                    params![id],
+                )?;
+            }
+            return Ok(None);
+        }
+    };
+
+    let migrates = source == UrlSource::PermanentRedirect && same_host(&url, observed);
+    if pending_url.as_deref() != Some(observed) {
+        let message = if migrates {
+            format!("{} now points to {}", url, observed)
+        } else {
+            format!("{} may have moved to {}", url, observed)
+        };
+        feed_event::create_event(
+            conn,
+            id,
+            feed_event::EVENT_URL_CHANGE_DETECTED,
+            Some(&message),
+        )?;
+    }
+    // A fetch without the redirect breaks the streak
+    let count = match (migrates, pending_url.as_deref() == Some(observed)) {
+        (false, _) => 0,
+        (true, true) => pending_count + 1,
+        (true, false) => 1,
+    };
+
+    if count < URL_MIGRATION_THRESHOLD {
+        conn.execute(
+            "UPDATE feed SET pending_url = ?1, pending_url_count = ?2 WHERE id = ?3",
+            params![observed, count, id],
+        )?;
+        return Ok(None);
+    }
+
+    // Clear validators so the first fetch of the new URL is unconditional
+    let result = conn.execute(
+        r#"
+        UPDATE feed
+        SET url = ?1, pending_url = NULL, pending_url_count = 0, etag = NULL, last_modified = NULL, updated_at = datetime('now')
+        WHERE id = ?2
+        "#,
+        params![observed, id],
+    );
+
+    match result {
+        Ok(_) => {
+            feed_event::create_event(
+                conn,
+                id,
+                feed_event::EVENT_URL_CHANGED,
+                Some(&format!("Moved from {} to {}", url, observed)),
+            )?;
+            Ok(Some(observed.to_string()))
+        }
+        Err(rusqlite::Error::SqliteFailure(err, _))
+            if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            conn.execute(
+                "UPDATE feed SET pending_url = NULL, pending_url_count = 0 WHERE id = ?1",
+                params![id],
+            )?;
+            feed_event::create_event(
+                conn,
+                id,
+                feed_event::EVENT_URL_CHANGE_CONFLICT,
+                Some(&format!(
+                    "Already subscribed to {} in this category",
+                    observed
+                )),
+            )?;
+            Ok(None)
+        }
+        Err(e) => Err(AppError::Database(e)),
+    }
+}
+
 pub fn list_by_bucket(conn: &Connection, bucket: u8) -> AppResult<Vec<Feed>> {
-    let mut stmt = conn.prepare(&format!("SELECT {} FROM feed", SELECT_COLUMNS))?;
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT {} FROM feed
+        WHERE synthetic = 0 AND deleted_at IS NULL AND quarantined_at IS NULL
+          AND (next_fetch_at IS NULL OR next_fetch_at <= datetime('now'))
+        "#,
+        SELECT_COLUMNS
+    ))?;
 
     let feeds: Vec<Feed> = stmt
         .query_map([], row_to_feed)?
@@ -264,6 +982,55 @@ pub fn list_by_bucket(conn: &Connection, bucket: u8) -> AppResult<Vec<Feed>> {
     Ok(feeds)
 }
 
+/// Weight of a read or star that happened `age_days` ago
+fn engagement_decay(age_days: f64) -> f64 {
+    0.5_f64.powf(age_days.max(0.0) / ENGAGEMENT_HALF_LIFE_DAYS)
+}
+
+/// Recompute every feed's engagement score from the reads and stars of its
+/// entries, each worth less the longer ago it happened. Returns the number
+/// of feeds with a non-zero score.
+pub fn refresh_engagement_scores(conn: &Connection) -> AppResult<usize> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT feed_id,
+               julianday('now') - julianday(read_at),
+               julianday('now') - julianday(starred_at)
+        FROM entry
+        WHERE read_at >= datetime('now', ?1) OR starred_at >= datetime('now', ?1)
+        "#,
+    )?;
+    let window = format!("-{} days", ENGAGEMENT_WINDOW_DAYS);
+    let rows = stmt
+        .query_map(params![window], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, Option<f64>>(1)?,
+                row.get::<_, Option<f64>>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut scores: HashMap<i64, f64> = HashMap::new();
+    for (feed_id, read_age, starred_age) in rows {
+        let score = read_age.map_or(0.0, engagement_decay)
+            + starred_age.map_or(0.0, |age| STAR_WEIGHT * engagement_decay(age));
+        *scores.entry(feed_id).or_default() += score;
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("UPDATE feed SET engagement_score = 0", [])?;
+    for (feed_id, score) in &scores {
+        tx.execute(
+            "UPDATE feed SET engagement_score = ?1 WHERE id = ?2",
+            params![score, feed_id],
+        )?;
+    }
+    tx.commit()?;
+
+    Ok(scores.len())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -417,18 +1184,132 @@ mod tests {
     }
 
     #[test]
-    fn test_list_by_category() {
+    fn test_engagement_decay() {
+        assert_eq!(engagement_decay(0.0), 1.0);
+        assert_eq!(engagement_decay(ENGAGEMENT_HALF_LIFE_DAYS), 0.5);
+        assert!(engagement_decay(90.0) < 0.02);
+        // Clock skew never makes an event count more than once
+        assert_eq!(engagement_decay(-1.0), 1.0);
+    }
+
+    #[test]
+    fn test_list_by_user_ordered_by_engagement() {
         let conn = setup_db();
         let user_id = create_test_user(&conn, "testuser");
-        let cat1 = create_test_category(&conn, user_id, "Tech");
-        let cat2 = create_test_category(&conn, user_id, "News");
+        let category_id = create_test_category(&conn, user_id, "Tech");
 
-        create_feed(
-            &conn,
-            cat1,
-            "https://example1.com/feed.xml",
-            None,
-            None,
+        let mut feed_ids = Vec::new();
+        for title in ["Alpha", "Beta", "Gamma"] {
+            let url = format!("https://{}.example.com/feed.xml", title.to_lowercase());
+            let f = create_feed(
+                &conn,
+                category_id,
+                &url,
+                Some(title),
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+            feed_ids.push(f.id);
+        }
+
+        // Gamma: one recent star; Beta: two recent reads; Alpha: an old read
+        let entries = [
+            (feed_ids[2], "g1", None, Some("-1 hours")),
+            (feed_ids[1], "b1", Some("-1 hours"), None),
+            (feed_ids[1], "b2", Some("-2 hours"), None),
+            (feed_ids[0], "a1", Some("-60 days"), None),
+        ];
+        for (feed_id, guid, read, starred) in entries {
+            conn.execute(
+                "INSERT INTO entry (feed_id, guid, read_at, starred_at) \
+                 VALUES (?1, ?2, datetime('now', ?3), datetime('now', ?4))",
+                params![feed_id, guid, read, starred],
+            )
+            .unwrap();
+        }
+
+        assert_eq!(refresh_engagement_scores(&conn).unwrap(), 3);
+
+        let titles = |order: FeedOrder| -> Vec<String> {
+            list_by_user_ordered(&conn, user_id, order)
+                .unwrap()
+                .into_iter()
+                .filter_map(|f| f.title)
+                .collect()
+        };
+        assert_eq!(
+            titles(FeedOrder::Engagement),
+            vec!["Gamma", "Beta", "Alpha"]
+        );
+        assert_eq!(titles(FeedOrder::Title), vec!["Alpha", "Beta", "Gamma"]);
+
+        let gamma = find_by_id(&conn, feed_ids[2]).unwrap().unwrap();
+        assert!(gamma.engagement_score > 2.9);
+    }
+
+    #[test]
+    fn test_reorder() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+
+        let mut feed_ids = Vec::new();
+        for title in ["Alpha", "Beta", "Gamma"] {
+            let url = format!("https://{}.example.com/feed.xml", title.to_lowercase());
+            let f = create_feed(
+                &conn,
+                category_id,
+                &url,
+                Some(title),
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+            feed_ids.push(f.id);
+        }
+
+        reorder(&conn, user_id, &[feed_ids[2]]).unwrap();
+        let titles = |feeds: Vec<Feed>| -> Vec<String> {
+            feeds.into_iter().filter_map(|f| f.title).collect()
+        };
+        assert_eq!(
+            titles(list_by_user(&conn, user_id).unwrap()),
+            vec!["Gamma", "Alpha", "Beta"]
+        );
+        assert_eq!(
+            titles(list_by_category(&conn, category_id).unwrap()),
+            vec!["Gamma", "Alpha", "Beta"]
+        );
+        assert_eq!(
+            titles(list_by_user_ordered(&conn, user_id, FeedOrder::Title).unwrap()),
+            vec!["Alpha", "Beta", "Gamma"]
+        );
+
+        let other_id = create_test_user(&conn, "other");
+        assert!(matches!(
+            reorder(&conn, other_id, &[feed_ids[0]]),
+            Err(AppError::FeedNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_list_by_category() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let cat1 = create_test_category(&conn, user_id, "Tech");
+        let cat2 = create_test_category(&conn, user_id, "News");
+
+        create_feed(
+            &conn,
+            cat1,
+            "https://example1.com/feed.xml",
+            None,
+            None,
             None,
             None,
             None,
@@ -549,4 +1430,663 @@ mod tests {
         // Feed should be deleted too
         assert!(find_by_id(&conn, feed.id).unwrap().is_none());
     }
+
+    #[test]
+    fn test_trash_restore_and_purge_feed() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let other_id = create_test_user(&conn, "other");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let url = "https://example.com/feed.xml";
+        let feed = create_feed(&conn, category_id, url, None, None, None, None, None).unwrap();
+
+        // A feed restored on its own brings back its trashed category
+        category::delete_category(&conn, category_id, user_id).unwrap();
+        let trashed = list_trashed_by_user(&conn, user_id).unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert!(trashed[0].deleted_at.is_some());
+        assert!(matches!(
+            restore_feed(&conn, feed.id, other_id),
+            Err(AppError::FeedNotFound)
+        ));
+
+        let restored = restore_feed(&conn, feed.id, user_id).unwrap();
+        assert!(restored.deleted_at.is_none());
+        assert!(category::find_by_id(&conn, category_id).unwrap().is_some());
+
+        // Subscribing again restores the trashed feed
+        delete_feed(&conn, feed.id, category_id).unwrap();
+        let again = create_feed(
+            &conn,
+            category_id,
+            url,
+            Some("Tech"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(again.id, feed.id);
+        assert_eq!(again.title.as_deref(), Some("Tech"));
+        assert!(list_trashed_by_user(&conn, user_id).unwrap().is_empty());
+
+        // Moving another feed onto the trashed URL is refused, not purged
+        delete_feed(&conn, feed.id, category_id).unwrap();
+        let other_url = "https://example.com/other.xml";
+        let other =
+            create_feed(&conn, category_id, other_url, None, None, None, None, None).unwrap();
+        let moved = update_feed(
+            &conn,
+            other.id,
+            category_id,
+            category_id,
+            url,
+            None,
+            None,
+            None,
+            None,
+            false,
+        );
+        assert!(matches!(moved, Err(AppError::FeedExists)));
+        assert_eq!(list_trashed_by_user(&conn, user_id).unwrap().len(), 1);
+        let again = restore_feed(&conn, feed.id, user_id).unwrap();
+
+        delete_feed(&conn, again.id, category_id).unwrap();
+        assert_eq!(
+            purge_trashed(&conn, Utc::now() - chrono::Duration::days(1)).unwrap(),
+            0
+        );
+        assert_eq!(
+            purge_trashed(&conn, Utc::now() + chrono::Duration::days(1)).unwrap(),
+            1
+        );
+        assert!(list_trashed_by_user(&conn, user_id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_record_url_observation_migrates_after_threshold() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed = create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let new_url = "https://example.com/rss.xml";
+        for _ in 1..URL_MIGRATION_THRESHOLD {
+            let migrated = record_url_observation(
+                &conn,
+                feed.id,
+                Some((new_url, UrlSource::PermanentRedirect)),
+            )
+            .unwrap();
+            assert!(migrated.is_none());
+        }
+        let migrated = record_url_observation(
+            &conn,
+            feed.id,
+            Some((new_url, UrlSource::PermanentRedirect)),
+        )
+        .unwrap();
+        assert_eq!(migrated.as_deref(), Some(new_url));
+
+        let updated = find_by_id(&conn, feed.id).unwrap().unwrap();
+        assert_eq!(updated.url, new_url);
+
+        let events = feed_event::list_by_feed(&conn, feed.id, 10).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_type, feed_event::EVENT_URL_CHANGED);
+        assert_eq!(events[1].event_type, feed_event::EVENT_URL_CHANGE_DETECTED);
+    }
+
+    #[test]
+    fn test_record_url_observation_resets_when_not_persistent() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed = create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let new_url = "https://example.com/rss.xml";
+        for _ in 0..URL_MIGRATION_THRESHOLD {
+            record_url_observation(
+                &conn,
+                feed.id,
+                Some((new_url, UrlSource::PermanentRedirect)),
+            )
+            .unwrap();
+            // Original URL seen again resets the streak
+            record_url_observation(&conn, feed.id, None).unwrap();
+        }
+
+        let unchanged = find_by_id(&conn, feed.id).unwrap().unwrap();
+        assert_eq!(unchanged.url, "https://example.com/feed");
+    }
+
+    #[test]
+    fn test_record_url_observation_only_suggests() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed = create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        // Neither a self link nor a redirect to another host moves the feed
+        for observed in [
+            ("https://example.com/rss.xml", UrlSource::SelfLink),
+            (
+                "https://elsewhere.example.org/feed",
+                UrlSource::PermanentRedirect,
+            ),
+        ] {
+            for _ in 0..URL_MIGRATION_THRESHOLD * 2 {
+                let migrated = record_url_observation(&conn, feed.id, Some(observed)).unwrap();
+                assert!(migrated.is_none());
+            }
+        }
+
+        let unchanged = find_by_id(&conn, feed.id).unwrap().unwrap();
+        assert_eq!(unchanged.url, "https://example.com/feed");
+        // Each suggestion is logged once
+        let events = feed_event::list_by_feed(&conn, feed.id, 10).unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .all(|e| e.event_type == feed_event::EVENT_URL_CHANGE_DETECTED));
+    }
+
+    #[test]
+    fn test_record_url_observation_conflict() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed = create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        create_feed(
+            &conn,
+            category_id,
+            "https://example.com/rss.xml",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        for _ in 0..URL_MIGRATION_THRESHOLD {
+            let migrated = record_url_observation(
+                &conn,
+                feed.id,
+                Some(("https://example.com/rss.xml", UrlSource::PermanentRedirect)),
+            )
+            .unwrap();
+            assert!(migrated.is_none());
+        }
+
+        let events = feed_event::list_by_feed(&conn, feed.id, 10).unwrap();
+        assert_eq!(events[0].event_type, feed_event::EVENT_URL_CHANGE_CONFLICT);
+    }
+
+    #[test]
+    fn test_update_fetch_result_tracks_error_since() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed = create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed.xml",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let error_since = |conn: &Connection| -> Option<String> {
+            conn.query_row(
+                "SELECT error_since FROM feed WHERE id = ?1",
+                params![feed.id],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        let first = Utc::now() - chrono::Duration::days(2);
+        update_fetch_result(&conn, feed.id, first, Some("HTTP 500"), None, None).unwrap();
+        let since = error_since(&conn).unwrap();
+
+        // Later failures keep the original start of the streak
+        update_fetch_result(&conn, feed.id, Utc::now(), Some("HTTP 500"), None, None).unwrap();
+        assert_eq!(error_since(&conn).unwrap(), since);
+
+        // Success clears it
+        update_fetch_result(&conn, feed.id, Utc::now(), None, None, None).unwrap();
+        assert!(error_since(&conn).is_none());
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        assert_eq!(backoff_delay(1), chrono::Duration::hours(1));
+        assert_eq!(backoff_delay(2), chrono::Duration::hours(2));
+        assert_eq!(backoff_delay(4), chrono::Duration::hours(8));
+        assert_eq!(
+            backoff_delay(10),
+            chrono::Duration::hours(MAX_BACKOFF_HOURS)
+        );
+        assert_eq!(
+            backoff_delay(1000),
+            chrono::Duration::hours(MAX_BACKOFF_HOURS)
+        );
+    }
+
+    #[test]
+    fn test_failing_feed_backs_off_and_is_quarantined() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed = create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed.xml",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let bucket = url_to_bucket(&feed.url);
+
+        let quarantined =
+            update_fetch_result(&conn, feed.id, Utc::now(), Some("HTTP 500"), None, None).unwrap();
+        assert!(!quarantined);
+        let failing = find_by_id(&conn, feed.id).unwrap().unwrap();
+        assert_eq!(failing.failure_count, 1);
+        assert!(failing.next_fetch_at.is_some());
+        assert!(list_by_bucket(&conn, bucket).unwrap().is_empty());
+
+        // A week into the streak the feed is quarantined, once
+        conn.execute(
+            "UPDATE feed SET error_since = datetime('now', '-8 days') WHERE id = ?1",
+            params![feed.id],
+        )
+        .unwrap();
+        assert!(
+            update_fetch_result(&conn, feed.id, Utc::now(), Some("HTTP 500"), None, None).unwrap()
+        );
+        assert!(
+            !update_fetch_result(&conn, feed.id, Utc::now(), Some("HTTP 500"), None, None).unwrap()
+        );
+        let events = feed_event::list_by_feed(&conn, feed.id, 10).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, feed_event::EVENT_QUARANTINED);
+        let notices = notification::list_by_user(&conn, user_id, true, 10).unwrap();
+        assert_eq!(notices.len(), 1);
+        assert_eq!(notices[0].kind, notification::KIND_FEED_QUARANTINED);
+
+        clear_backoff(&conn, feed.id).unwrap();
+        assert_eq!(list_by_bucket(&conn, bucket).unwrap().len(), 1);
+
+        update_fetch_result(&conn, feed.id, Utc::now(), None, None, None).unwrap();
+        let recovered = find_by_id(&conn, feed.id).unwrap().unwrap();
+        assert_eq!(recovered.failure_count, 0);
+        assert!(recovered.quarantined_at.is_none());
+    }
+
+    #[test]
+    fn test_list_stale_by_user() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+
+        let failing = create_feed(
+            &conn,
+            category_id,
+            "https://failing.com/feed.xml",
+            Some("Failing"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let empty = create_feed(
+            &conn,
+            category_id,
+            "https://empty.com/feed.xml",
+            Some("Empty"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let healthy = create_feed(
+            &conn,
+            category_id,
+            "https://healthy.com/feed.xml",
+            Some("Healthy"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        conn.execute(
+            "UPDATE feed SET created_at = datetime('now', '-60 days')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE feed SET error_since = datetime('now', '-45 days') WHERE id = ?1",
+            params![failing.id],
+        )
+        .unwrap();
+        for f in [failing.id, healthy.id] {
+            conn.execute(
+                "INSERT INTO entry (feed_id, guid) VALUES (?1, 'g')",
+                params![f],
+            )
+            .unwrap();
+        }
+
+        let stale = list_stale_by_user(&conn, user_id, STALE_FEED_DAYS).unwrap();
+        assert_eq!(stale.len(), 2);
+        assert_eq!(stale[0].feed.id, empty.id);
+        assert_eq!(stale[0].reason, StaleReason::NoEntries);
+        assert_eq!(stale[0].entry_count, 0);
+        assert_eq!(stale[1].feed.id, failing.id);
+        assert_eq!(stale[1].reason, StaleReason::Failing);
+        assert!(stale[1].failing_since.is_some());
+    }
+
+    #[test]
+    fn test_set_date_strategy() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed = create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed.xml",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(feed.date_strategy, DateStrategy::Clamp);
+
+        set_date_strategy(&conn, feed.id, DateStrategy::Trust).unwrap();
+        let updated = find_by_id(&conn, feed.id).unwrap().unwrap();
+        assert_eq!(updated.date_strategy, DateStrategy::Trust);
+
+        let result = set_date_strategy(&conn, 9999, DateStrategy::Trust);
+        assert!(matches!(result, Err(AppError::FeedNotFound)));
+    }
+
+    #[test]
+    fn test_set_custom_headers() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed = create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed.xml",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(feed.custom_headers.is_empty());
+
+        let headers = BTreeMap::from([("Referer".to_string(), "https://example.com/".to_string())]);
+        set_custom_headers(&conn, feed.id, &headers).unwrap();
+        let updated = find_by_id(&conn, feed.id).unwrap().unwrap();
+        assert_eq!(updated.custom_headers, headers);
+
+        let listed = list_by_user(&conn, user_id).unwrap();
+        assert_eq!(listed[0].custom_headers, headers);
+
+        set_custom_headers(&conn, feed.id, &BTreeMap::new()).unwrap();
+        let cleared = find_by_id(&conn, feed.id).unwrap().unwrap();
+        assert!(cleared.custom_headers.is_empty());
+    }
+
+    #[test]
+    fn test_canonical_url() {
+        assert_eq!(
+            canonical_url("http://www.Example.com/feed/"),
+            canonical_url("https://example.com/feed")
+        );
+        assert_eq!(
+            canonical_url("https://example.com:443/feed#top"),
+            canonical_url("https://example.com/feed")
+        );
+        assert_eq!(
+            canonical_url("http://feedproxy.google.com/SomeBlog"),
+            canonical_url("https://feeds.feedburner.com/someblog?format=xml")
+        );
+        assert_ne!(
+            canonical_url("https://example.com/feed?tag=rust"),
+            canonical_url("https://example.com/feed?tag=go")
+        );
+        assert_ne!(
+            canonical_url("https://example.com:8080/feed"),
+            canonical_url("https://example.com/feed")
+        );
+    }
+
+    #[test]
+    fn test_find_duplicate_by_user_across_categories() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let other_id = create_test_user(&conn, "other");
+        let tech = create_test_category(&conn, user_id, "Tech");
+        create_test_category(&conn, user_id, "News");
+        let existing = create_feed(
+            &conn,
+            tech,
+            "https://example.com/feed.xml",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let found = find_duplicate_by_user(&conn, user_id, &["http://www.example.com/feed.xml/"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, existing.id);
+        assert!(
+            find_duplicate_by_user(&conn, user_id, &["https://example.com/other.xml"])
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            find_duplicate_by_user(&conn, other_id, &["https://example.com/feed.xml"])
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_synthetic_feed_is_never_fetched() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Imported");
+        let feed = create_synthetic_feed(&conn, category_id, "rdrs:imported", "Imported").unwrap();
+        assert!(feed.synthetic);
+        assert_eq!(feed.title.as_deref(), Some("Imported"));
+
+        let bucket = url_to_bucket(&feed.url);
+        assert!(list_by_bucket(&conn, bucket).unwrap().is_empty());
+        assert!(list_by_user(&conn, user_id).unwrap()[0].synthetic);
+
+        let found =
+            find_or_create_synthetic_feed(&conn, user_id, "rdrs:imported", "Imported").unwrap();
+        assert_eq!(found.id, feed.id);
+        let other =
+            find_or_create_synthetic_feed(&conn, user_id, "rdrs:saved", "Saved pages").unwrap();
+        assert_ne!(other.id, feed.id);
+        assert!(other.synthetic);
+    }
+
+    #[test]
+    fn test_set_update_threshold() {
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed = create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed.xml",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(feed.update_threshold, None);
+
+        set_update_threshold(&conn, feed.id, Some(20)).unwrap();
+        let listed = list_by_user(&conn, user_id).unwrap();
+        assert_eq!(listed[0].update_threshold, Some(20));
+
+        assert_eq!(parse_update_threshold(0).unwrap(), None);
+        assert_eq!(parse_update_threshold(100).unwrap(), Some(100));
+        assert!(parse_update_threshold(101).is_err());
+    }
+
+    #[test]
+    fn test_entry_counters() {
+        use crate::models::entry;
+        use chrono::TimeZone;
+
+        let conn = setup_db();
+        let user_id = create_test_user(&conn, "testuser");
+        let category_id = create_test_category(&conn, user_id, "Tech");
+        let feed = create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed.xml",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(feed.entry_count, 0);
+        assert_eq!(feed.unread_count, 0);
+        assert!(feed.last_entry_at.is_none());
+
+        let older = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let newer = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+        let (first, _) = entry::upsert_entry(
+            &conn,
+            feed.id,
+            "guid-1",
+            Some("First"),
+            None,
+            None,
+            None,
+            None,
+            Some(newer),
+        )
+        .unwrap();
+        entry::upsert_entry(
+            &conn,
+            feed.id,
+            "guid-2",
+            Some("Second"),
+            None,
+            None,
+            None,
+            None,
+            Some(older),
+        )
+        .unwrap();
+        // Re-fetching an existing entry does not count it twice
+        entry::upsert_entry(
+            &conn,
+            feed.id,
+            "guid-1",
+            Some("First (edited)"),
+            None,
+            None,
+            None,
+            None,
+            Some(newer),
+        )
+        .unwrap();
+
+        let feed = find_by_id(&conn, feed.id).unwrap().unwrap();
+        assert_eq!(feed.entry_count, 2);
+        assert_eq!(feed.unread_count, 2);
+        assert_eq!(feed.last_entry_at, Some(newer));
+
+        entry::mark_as_read(&conn, first.id).unwrap();
+        entry::mark_as_read(&conn, first.id).unwrap();
+        let feed = find_by_id(&conn, feed.id).unwrap().unwrap();
+        assert_eq!(feed.unread_count, 1);
+
+        entry::mark_as_unread(&conn, first.id).unwrap();
+        let feed = find_by_id(&conn, feed.id).unwrap().unwrap();
+        assert_eq!(feed.unread_count, 2);
+
+        conn.execute("DELETE FROM entry WHERE id = ?1", params![first.id])
+            .unwrap();
+        let feed = find_by_id(&conn, feed.id).unwrap().unwrap();
+        assert_eq!(feed.entry_count, 1);
+        assert_eq!(feed.unread_count, 1);
+        assert_eq!(feed.last_entry_at, Some(older));
+    }
 }
@@ -0,0 +1,84 @@
+//! Cookies sent when fetching a feed that sits behind a login. Only the
+//! encrypted form is stored; see `services::feed_cookies`.
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppResult;
+
+/// Store the encrypted cookie jar of a feed, replacing any previous one
+pub fn set_cookies(conn: &Connection, feed_id: i64, ciphertext: &[u8]) -> AppResult<()> {
+    conn.execute(
+        "INSERT INTO feed_cookie (feed_id, ciphertext) VALUES (?1, ?2) \
+         ON CONFLICT(feed_id) DO UPDATE SET ciphertext = excluded.ciphertext, \
+         updated_at = datetime('now')",
+        params![feed_id, ciphertext],
+    )?;
+    Ok(())
+}
+
+/// The encrypted cookie jar of a feed, if one is set
+pub fn find_cookies(conn: &Connection, feed_id: i64) -> AppResult<Option<Vec<u8>>> {
+    let ciphertext = conn
+        .query_row(
+            "SELECT ciphertext FROM feed_cookie WHERE feed_id = ?1",
+            params![feed_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(ciphertext)
+}
+
+pub fn delete_cookies(conn: &Connection, feed_id: i64) -> AppResult<()> {
+    conn.execute(
+        "DELETE FROM feed_cookie WHERE feed_id = ?1",
+        params![feed_id],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, feed, user};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_set_find_and_delete_cookies() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", user::Role::User).unwrap();
+        let cat = category::create_category(&conn, user.id, "Tech").unwrap();
+        let f = feed::create_feed(
+            &conn,
+            cat.id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(find_cookies(&conn, f.id).unwrap(), None);
+
+        set_cookies(&conn, f.id, b"first").unwrap();
+        set_cookies(&conn, f.id, b"second").unwrap();
+        assert_eq!(find_cookies(&conn, f.id).unwrap(), Some(b"second".to_vec()));
+
+        delete_cookies(&conn, f.id).unwrap();
+        assert_eq!(find_cookies(&conn, f.id).unwrap(), None);
+
+        // A trashed feed keeps its cookies for a restore; purging removes them
+        set_cookies(&conn, f.id, b"third").unwrap();
+        feed::delete_feed(&conn, f.id, cat.id).unwrap();
+        assert_eq!(find_cookies(&conn, f.id).unwrap(), Some(b"third".to_vec()));
+        feed::purge_trashed(&conn, chrono::Utc::now() + chrono::Duration::days(1)).unwrap();
+        assert_eq!(find_cookies(&conn, f.id).unwrap(), None);
+    }
+}
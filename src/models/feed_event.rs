@@ -0,0 +1,114 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::AppResult;
+
+/// A new canonical URL was observed for the feed
+pub const EVENT_URL_CHANGE_DETECTED: &str = "url_change_detected";
+
+/// The feed URL was migrated to a new canonical URL
+pub const EVENT_URL_CHANGED: &str = "url_changed";
+
+/// Migration was skipped because the user already subscribes to the new URL
+pub const EVENT_URL_CHANGE_CONFLICT: &str = "url_change_conflict";
+
+/// The feed kept failing and is no longer fetched automatically
+pub const EVENT_QUARANTINED: &str = "quarantined";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedEvent {
+    pub id: i64,
+    pub feed_id: i64,
+    pub event_type: String,
+    pub message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").map(|dt| dt.and_utc())
+        })
+        .or_else(|_| dateparser::parse(s).map(|dt| dt.with_timezone(&Utc)))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_event(row: &rusqlite::Row) -> rusqlite::Result<FeedEvent> {
+    let created_at: String = row.get(4)?;
+
+    Ok(FeedEvent {
+        id: row.get(0)?,
+        feed_id: row.get(1)?,
+        event_type: row.get(2)?,
+        message: row.get(3)?,
+        created_at: parse_datetime(&created_at),
+    })
+}
+
+pub fn create_event(
+    conn: &Connection,
+    feed_id: i64,
+    event_type: &str,
+    message: Option<&str>,
+) -> AppResult<()> {
+    conn.execute(
+        "INSERT INTO feed_event (feed_id, event_type, message) VALUES (?1, ?2, ?3)",
+        params![feed_id, event_type, message],
+    )?;
+    Ok(())
+}
+
+pub fn list_by_feed(conn: &Connection, feed_id: i64, limit: i64) -> AppResult<Vec<FeedEvent>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, feed_id, event_type, message, created_at FROM feed_event WHERE feed_id = ?1 ORDER BY id DESC LIMIT ?2",
+    )?;
+    let events = stmt
+        .query_map(params![feed_id, limit], row_to_event)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, feed, user};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_create_and_list_events() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", user::Role::User).unwrap();
+        let cat = category::create_category(&conn, user.id, "Tech").unwrap();
+        let f = feed::create_feed(
+            &conn,
+            cat.id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        create_event(&conn, f.id, EVENT_URL_CHANGE_DETECTED, Some("first")).unwrap();
+        create_event(&conn, f.id, EVENT_URL_CHANGED, Some("second")).unwrap();
+
+        let events = list_by_feed(&conn, f.id, 10).unwrap();
+        assert_eq!(events.len(), 2);
+        // Newest first
+        assert_eq!(events[0].event_type, EVENT_URL_CHANGED);
+        assert_eq!(events[1].message.as_deref(), Some("first"));
+
+        let limited = list_by_feed(&conn, f.id, 1).unwrap();
+        assert_eq!(limited.len(), 1);
+    }
+}
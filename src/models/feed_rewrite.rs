@@ -0,0 +1,208 @@
+//! Pattern → replacement rules applied to a feed's entry titles or content
+//! when they are stored; see `services::rewrite`.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+
+/// The part of an entry a rule rewrites
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RewriteField {
+    Title,
+    Content,
+}
+
+impl RewriteField {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RewriteField::Title => "title",
+            RewriteField::Content => "content",
+        }
+    }
+}
+
+impl FromStr for RewriteField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "title" => Ok(RewriteField::Title),
+            "content" => Ok(RewriteField::Content),
+            _ => Err(format!("Unknown rewrite field: {}", s)),
+        }
+    }
+}
+
+/// A rule rewriting a feed's entries, applied in ID order
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedRewrite {
+    pub id: i64,
+    pub feed_id: i64,
+    pub field: RewriteField,
+    /// Regular expression, in the syntax of the `regex` crate
+    pub pattern: String,
+    /// Replacement text; `$1` or `${name}` insert capture groups
+    pub replacement: String,
+    pub created_at: DateTime<Utc>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_rewrite(row: &rusqlite::Row) -> rusqlite::Result<FeedRewrite> {
+    let field: String = row.get(2)?;
+    let created_at: String = row.get(5)?;
+
+    Ok(FeedRewrite {
+        id: row.get(0)?,
+        feed_id: row.get(1)?,
+        field: field.parse().unwrap_or(RewriteField::Title),
+        pattern: row.get(3)?,
+        replacement: row.get(4)?,
+        created_at: parse_datetime(&created_at),
+    })
+}
+
+const SELECT_COLUMNS: &str = "id, feed_id, field, pattern, replacement, created_at";
+
+pub fn create_rewrite(
+    conn: &Connection,
+    feed_id: i64,
+    field: RewriteField,
+    pattern: &str,
+    replacement: &str,
+) -> AppResult<FeedRewrite> {
+    conn.execute(
+        "INSERT INTO feed_rewrite (feed_id, field, pattern, replacement) VALUES (?1, ?2, ?3, ?4)",
+        params![feed_id, field.as_str(), pattern, replacement],
+    )?;
+
+    let id = conn.last_insert_rowid();
+    find_by_id(conn, id, feed_id)?
+        .ok_or_else(|| AppError::Internal("Failed to create rewrite rule".to_string()))
+}
+
+pub fn find_by_id(conn: &Connection, id: i64, feed_id: i64) -> AppResult<Option<FeedRewrite>> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM feed_rewrite WHERE id = ?1 AND feed_id = ?2",
+            SELECT_COLUMNS
+        ),
+        params![id, feed_id],
+        row_to_rewrite,
+    )
+    .optional()
+    .map_err(AppError::Database)
+}
+
+pub fn list_by_feed(conn: &Connection, feed_id: i64) -> AppResult<Vec<FeedRewrite>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM feed_rewrite WHERE feed_id = ?1 ORDER BY id ASC",
+        SELECT_COLUMNS
+    ))?;
+    let rewrites = stmt
+        .query_map(params![feed_id], row_to_rewrite)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rewrites)
+}
+
+pub fn update_rewrite(
+    conn: &Connection,
+    id: i64,
+    feed_id: i64,
+    field: RewriteField,
+    pattern: &str,
+    replacement: &str,
+) -> AppResult<FeedRewrite> {
+    let updated = conn.execute(
+        "UPDATE feed_rewrite SET field = ?1, pattern = ?2, replacement = ?3 \
+         WHERE id = ?4 AND feed_id = ?5",
+        params![field.as_str(), pattern, replacement, id, feed_id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound("Rewrite rule not found".to_string()));
+    }
+    find_by_id(conn, id, feed_id)?
+        .ok_or_else(|| AppError::NotFound("Rewrite rule not found".to_string()))
+}
+
+pub fn delete_rewrite(conn: &Connection, id: i64, feed_id: i64) -> AppResult<()> {
+    let deleted = conn.execute(
+        "DELETE FROM feed_rewrite WHERE id = ?1 AND feed_id = ?2",
+        params![id, feed_id],
+    )?;
+    if deleted == 0 {
+        return Err(AppError::NotFound("Rewrite rule not found".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, feed, user};
+
+    fn setup_db() -> (Connection, i64) {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user = user::create_user(&conn, "testuser", "hash", user::Role::User).unwrap();
+        let cat = category::create_category(&conn, user.id, "Tech").unwrap();
+        let f = feed::create_feed(
+            &conn,
+            cat.id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        (conn, f.id)
+    }
+
+    #[test]
+    fn test_rewrite_crud() {
+        let (conn, feed_id) = setup_db();
+
+        let first = create_rewrite(&conn, feed_id, RewriteField::Title, r"^\[Ad\] ", "").unwrap();
+        let second =
+            create_rewrite(&conn, feed_id, RewriteField::Content, "http:", "https:").unwrap();
+        let listed = list_by_feed(&conn, feed_id).unwrap();
+        assert_eq!(
+            listed.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![first.id, second.id]
+        );
+        assert_eq!(listed[1].field, RewriteField::Content);
+
+        let updated =
+            update_rewrite(&conn, first.id, feed_id, RewriteField::Title, "^Ad: ", "").unwrap();
+        assert_eq!(updated.pattern, "^Ad: ");
+
+        // Rules are only reachable through their own feed
+        assert!(
+            update_rewrite(&conn, first.id, feed_id + 1, RewriteField::Title, "x", "").is_err()
+        );
+        assert!(delete_rewrite(&conn, first.id, feed_id + 1).is_err());
+
+        delete_rewrite(&conn, first.id, feed_id).unwrap();
+        assert_eq!(list_by_feed(&conn, feed_id).unwrap().len(), 1);
+        assert!(find_by_id(&conn, first.id, feed_id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_field() {
+        assert_eq!("title".parse::<RewriteField>(), Ok(RewriteField::Title));
+        assert_eq!("content".parse::<RewriteField>(), Ok(RewriteField::Content));
+        assert!("summary".parse::<RewriteField>().is_err());
+    }
+}
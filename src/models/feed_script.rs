@@ -0,0 +1,75 @@
+//! Rhai scripts run on a feed's entries during sync; see
+//! `services::scripting`.
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppResult;
+
+/// Store the script of a feed, replacing any previous one
+pub fn set_script(conn: &Connection, feed_id: i64, source: &str) -> AppResult<()> {
+    conn.execute(
+        "INSERT INTO feed_script (feed_id, source) VALUES (?1, ?2) \
+         ON CONFLICT(feed_id) DO UPDATE SET source = excluded.source, \
+         updated_at = datetime('now')",
+        params![feed_id, source],
+    )?;
+    Ok(())
+}
+
+/// The script of a feed, if one is set
+pub fn find_script(conn: &Connection, feed_id: i64) -> AppResult<Option<String>> {
+    let source = conn
+        .query_row(
+            "SELECT source FROM feed_script WHERE feed_id = ?1",
+            params![feed_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(source)
+}
+
+pub fn delete_script(conn: &Connection, feed_id: i64) -> AppResult<()> {
+    conn.execute(
+        "DELETE FROM feed_script WHERE feed_id = ?1",
+        params![feed_id],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, feed, user};
+
+    #[test]
+    fn test_set_find_and_delete_script() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user = user::create_user(&conn, "testuser", "hash", user::Role::User).unwrap();
+        let cat = category::create_category(&conn, user.id, "Tech").unwrap();
+        let f = feed::create_feed(
+            &conn,
+            cat.id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(find_script(&conn, f.id).unwrap(), None);
+
+        set_script(&conn, f.id, "fn on_entry(e) { e }").unwrap();
+        set_script(&conn, f.id, "fn on_entry(e) { () }").unwrap();
+        assert_eq!(
+            find_script(&conn, f.id).unwrap().as_deref(),
+            Some("fn on_entry(e) { () }")
+        );
+
+        delete_script(&conn, f.id).unwrap();
+        assert_eq!(find_script(&conn, f.id).unwrap(), None);
+    }
+}
@@ -0,0 +1,256 @@
+//! Per-feed reading statistics. Ratios come from live aggregates over the
+//! entry table; the weekly posting history is materialized into
+//! `feed_weekly_stats` by a periodic job, since grouping every entry by week
+//! on each request gets slow for large feeds.
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::AppResult;
+
+/// How many days of posting history are materialized (52 weeks)
+pub const HISTORY_DAYS: i64 = 364;
+
+/// Entries published in one week, starting on `week` (a Monday)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WeeklyCount {
+    pub week: String,
+    pub entries: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeedStats {
+    pub feed_id: i64,
+    pub entry_count: i64,
+    pub read_count: i64,
+    pub starred_count: i64,
+    /// Share of entries read, from 0 to 100
+    pub read_percent: f64,
+    /// Share of entries starred, from 0 to 100
+    pub starred_percent: f64,
+    /// Average seconds between an entry arriving and being read
+    pub avg_seconds_to_read: Option<i64>,
+    /// Average entries per week over the materialized history
+    pub entries_per_week: f64,
+    pub weekly: Vec<WeeklyCount>,
+    /// When the weekly history was last materialized
+    pub weekly_refreshed_at: Option<String>,
+}
+
+fn percent(part: i64, total: i64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    (part as f64 * 1000.0 / total as f64).round() / 10.0
+}
+
+/// Rebuild the weekly posting history of every feed. Returns the number of
+/// feed-week rows written.
+pub fn refresh_weekly(conn: &Connection) -> AppResult<usize> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM feed_weekly_stats", [])?;
+    // 'weekday 0' moves forward to Sunday, so six days back is that week's Monday
+    let rows = tx.execute(
+        r#"
+        INSERT INTO feed_weekly_stats (feed_id, week, entry_count)
+        SELECT feed_id,
+               date(COALESCE(published_at, created_at), 'weekday 0', '-6 days') AS week,
+               COUNT(*)
+        FROM entry
+        GROUP BY feed_id, week
+        HAVING week IS NOT NULL AND week >= date('now', ?1)
+        "#,
+        params![format!("-{} days", HISTORY_DAYS)],
+    )?;
+    tx.commit()?;
+    Ok(rows)
+}
+
+pub fn list_weekly(conn: &Connection, feed_id: i64) -> AppResult<Vec<WeeklyCount>> {
+    let mut stmt = conn.prepare(
+        "SELECT week, entry_count FROM feed_weekly_stats WHERE feed_id = ?1 ORDER BY week",
+    )?;
+    let weeks = stmt
+        .query_map(params![feed_id], |row| {
+            Ok(WeeklyCount {
+                week: row.get(0)?,
+                entries: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(weeks)
+}
+
+pub fn find_by_feed(conn: &Connection, feed_id: i64) -> AppResult<FeedStats> {
+    let (entry_count, read_count, starred_count, avg_seconds_to_read) = conn.query_row(
+        r#"
+        SELECT COUNT(*),
+               COALESCE(SUM(read_at IS NOT NULL), 0),
+               COALESCE(SUM(starred_at IS NOT NULL), 0),
+               AVG(CASE WHEN read_at >= created_at
+                   THEN (julianday(read_at) - julianday(created_at)) * 86400 END)
+        FROM entry
+        WHERE feed_id = ?1
+        "#,
+        params![feed_id],
+        |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, Option<f64>>(3)?,
+            ))
+        },
+    )?;
+
+    let (weeks_covered, weekly_refreshed_at): (Option<f64>, Option<String>) = conn.query_row(
+        r#"
+        SELECT (julianday('now') - julianday(MIN(week))) / 7, MAX(refreshed_at)
+        FROM feed_weekly_stats
+        WHERE feed_id = ?1
+        "#,
+        params![feed_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let weekly = list_weekly(conn, feed_id)?;
+    let recent: i64 = weekly.iter().map(|w| w.entries).sum();
+    // The current week counts as one even though it is not over yet
+    let weeks = weeks_covered.map_or(1.0, |w| w.floor() + 1.0);
+    let entries_per_week = (recent as f64 * 10.0 / weeks).round() / 10.0;
+
+    Ok(FeedStats {
+        feed_id,
+        entry_count,
+        read_count,
+        starred_count,
+        read_percent: percent(read_count, entry_count),
+        starred_percent: percent(starred_count, entry_count),
+        avg_seconds_to_read: avg_seconds_to_read.map(|s| s.round() as i64),
+        entries_per_week,
+        weekly,
+        weekly_refreshed_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, feed, user};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn create_feed(conn: &Connection) -> i64 {
+        let user = user::create_user(conn, "testuser", "hash", user::Role::User).unwrap();
+        let cat = category::create_category(conn, user.id, "Tech").unwrap();
+        feed::create_feed(
+            conn,
+            cat.id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .id
+    }
+
+    fn insert_entry(
+        conn: &Connection,
+        feed_id: i64,
+        guid: &str,
+        published: &str,
+        read_at: Option<&str>,
+        starred: bool,
+    ) {
+        conn.execute(
+            r#"
+            INSERT INTO entry (feed_id, guid, published_at, created_at, read_at, starred_at)
+            VALUES (?1, ?2, ?3, ?3, ?4, CASE WHEN ?5 THEN datetime('now') END)
+            "#,
+            params![feed_id, guid, published, read_at, starred],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_find_by_feed_ratios() {
+        let conn = setup_db();
+        let feed_id = create_feed(&conn);
+
+        let empty = find_by_feed(&conn, feed_id).unwrap();
+        assert_eq!(empty.entry_count, 0);
+        assert_eq!(empty.read_percent, 0.0);
+        assert_eq!(empty.avg_seconds_to_read, None);
+        assert!(empty.weekly.is_empty());
+
+        let published = "2024-03-04 10:00:00";
+        insert_entry(
+            &conn,
+            feed_id,
+            "a",
+            published,
+            Some("2024-03-04 11:00:00"),
+            true,
+        );
+        insert_entry(
+            &conn,
+            feed_id,
+            "b",
+            published,
+            Some("2024-03-04 13:00:00"),
+            false,
+        );
+        insert_entry(&conn, feed_id, "c", published, None, false);
+
+        let stats = find_by_feed(&conn, feed_id).unwrap();
+        assert_eq!(stats.entry_count, 3);
+        assert_eq!(stats.read_count, 2);
+        assert_eq!(stats.read_percent, 66.7);
+        assert_eq!(stats.starred_percent, 33.3);
+        assert_eq!(stats.avg_seconds_to_read, Some(2 * 3600));
+    }
+
+    #[test]
+    fn test_refresh_weekly() {
+        let conn = setup_db();
+        let feed_id = create_feed(&conn);
+
+        let monday: String = conn
+            .query_row("SELECT date('now', 'weekday 0', '-6 days')", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        let this_week = format!("{} 09:00:00", monday);
+        insert_entry(&conn, feed_id, "a", &this_week, None, false);
+        insert_entry(&conn, feed_id, "b", &this_week, None, false);
+        // Older than the materialized history
+        insert_entry(&conn, feed_id, "c", "2000-01-03 09:00:00", None, false);
+
+        // Nothing is materialized until the job runs
+        assert!(find_by_feed(&conn, feed_id).unwrap().weekly.is_empty());
+
+        assert_eq!(refresh_weekly(&conn).unwrap(), 1);
+        let stats = find_by_feed(&conn, feed_id).unwrap();
+        assert_eq!(
+            stats.weekly,
+            vec![WeeklyCount {
+                week: monday,
+                entries: 2
+            }]
+        );
+        assert_eq!(stats.entries_per_week, 2.0);
+        assert!(stats.weekly_refreshed_at.is_some());
+
+        // Refreshing again replaces rather than adds
+        assert_eq!(refresh_weekly(&conn).unwrap(), 1);
+        assert_eq!(list_weekly(&conn, feed_id).unwrap()[0].entries, 2);
+    }
+}
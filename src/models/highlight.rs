@@ -0,0 +1,377 @@
+//! Passages a user marked in an entry, each with an optional note.
+//!
+//! The quoted text is what identifies a highlight; the position selector
+//! (character offsets into the entry's text) only helps a client find it
+//! again, and may go stale when a feed rewrites the entry.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+
+pub const MAX_QUOTE_CHARS: usize = 5000;
+pub const MAX_NOTE_CHARS: usize = 5000;
+
+/// Where the quote starts and ends in the entry's text, in characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionSelector {
+    pub start: i64,
+    pub end: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Highlight {
+    pub id: i64,
+    pub entry_id: i64,
+    pub quote: String,
+    pub selector: Option<PositionSelector>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .unwrap_or_else(|_| Utc::now())
+}
+
+const COLUMNS: &str =
+    "id, entry_id, quote, position_start, position_end, note, created_at, updated_at";
+
+fn row_to_highlight(row: &rusqlite::Row) -> rusqlite::Result<Highlight> {
+    let start: Option<i64> = row.get(3)?;
+    let end: Option<i64> = row.get(4)?;
+    let created_at: String = row.get(6)?;
+    let updated_at: String = row.get(7)?;
+    Ok(Highlight {
+        id: row.get(0)?,
+        entry_id: row.get(1)?,
+        quote: row.get(2)?,
+        selector: start
+            .zip(end)
+            .map(|(start, end)| PositionSelector { start, end }),
+        note: row.get(5)?,
+        created_at: parse_datetime(&created_at),
+        updated_at: parse_datetime(&updated_at),
+    })
+}
+
+/// Check a quote, selector and note before they are stored
+pub fn validate(
+    quote: &str,
+    selector: Option<PositionSelector>,
+    note: Option<&str>,
+) -> AppResult<()> {
+    if quote.trim().is_empty() {
+        return Err(AppError::Validation("Quote must not be empty".to_string()));
+    }
+    if quote.chars().count() > MAX_QUOTE_CHARS {
+        return Err(AppError::Validation(format!(
+            "Quote must be at most {} characters",
+            MAX_QUOTE_CHARS
+        )));
+    }
+    if selector.is_some_and(|s| s.start < 0 || s.end <= s.start) {
+        return Err(AppError::Validation(
+            "Selector must have 0 <= start < end".to_string(),
+        ));
+    }
+    validate_note(note)
+}
+
+pub fn validate_note(note: Option<&str>) -> AppResult<()> {
+    if note.is_some_and(|n| n.chars().count() > MAX_NOTE_CHARS) {
+        return Err(AppError::Validation(format!(
+            "Note must be at most {} characters",
+            MAX_NOTE_CHARS
+        )));
+    }
+    Ok(())
+}
+
+/// The user's highlights in an entry, in reading order where known
+pub fn list_by_entry(conn: &Connection, user_id: i64, entry_id: i64) -> AppResult<Vec<Highlight>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT {} FROM highlight
+        WHERE user_id = ?1 AND entry_id = ?2
+        ORDER BY position_start IS NULL, position_start, id
+        "#,
+        COLUMNS
+    ))?;
+    let highlights = stmt
+        .query_map(params![user_id, entry_id], row_to_highlight)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(highlights)
+}
+
+/// The user's highlights in entries with IDs after `after_id` up to
+/// `last_id`, for a page of a backup
+pub fn list_by_entry_range(
+    conn: &Connection,
+    user_id: i64,
+    after_id: i64,
+    last_id: i64,
+) -> AppResult<Vec<Highlight>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT {} FROM highlight
+        WHERE user_id = ?1 AND entry_id > ?2 AND entry_id <= ?3
+        ORDER BY entry_id, position_start IS NULL, position_start, id
+        "#,
+        COLUMNS
+    ))?;
+    let highlights = stmt
+        .query_map(params![user_id, after_id, last_id], row_to_highlight)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(highlights)
+}
+
+pub fn create_highlight(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    quote: &str,
+    selector: Option<PositionSelector>,
+    note: Option<&str>,
+) -> AppResult<Highlight> {
+    let highlight = conn.query_row(
+        &format!(
+            r#"
+            INSERT INTO highlight (user_id, entry_id, quote, position_start, position_end, note)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            RETURNING {}
+            "#,
+            COLUMNS
+        ),
+        params![
+            user_id,
+            entry_id,
+            quote,
+            selector.map(|s| s.start),
+            selector.map(|s| s.end),
+            note
+        ],
+        row_to_highlight,
+    )?;
+    Ok(highlight)
+}
+
+/// Add a highlight restored from a backup, unless the entry already has the
+/// same one. Returns whether it was added.
+pub fn restore_highlight(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    quote: &str,
+    selector: Option<PositionSelector>,
+    note: Option<&str>,
+) -> AppResult<bool> {
+    let exists = conn
+        .query_row(
+            r#"
+            SELECT 1 FROM highlight
+            WHERE user_id = ?1 AND entry_id = ?2 AND quote = ?3
+              AND position_start IS ?4 AND position_end IS ?5
+            "#,
+            params![
+                user_id,
+                entry_id,
+                quote,
+                selector.map(|s| s.start),
+                selector.map(|s| s.end)
+            ],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    if exists {
+        return Ok(false);
+    }
+    create_highlight(conn, user_id, entry_id, quote, selector, note)?;
+    Ok(true)
+}
+
+/// Replace the note of a highlight; None clears it
+pub fn update_note(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    id: i64,
+    note: Option<&str>,
+) -> AppResult<Highlight> {
+    conn.query_row(
+        &format!(
+            r#"
+            UPDATE highlight SET note = ?4, updated_at = datetime('now')
+            WHERE id = ?1 AND user_id = ?2 AND entry_id = ?3
+            RETURNING {}
+            "#,
+            COLUMNS
+        ),
+        params![id, user_id, entry_id, note],
+        row_to_highlight,
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound("Highlight not found".to_string()))
+}
+
+pub fn delete_highlight(conn: &Connection, user_id: i64, entry_id: i64, id: i64) -> AppResult<()> {
+    let rows = conn.execute(
+        "DELETE FROM highlight WHERE id = ?1 AND user_id = ?2 AND entry_id = ?3",
+        params![id, user_id, entry_id],
+    )?;
+    if rows == 0 {
+        return Err(AppError::NotFound("Highlight not found".to_string()));
+    }
+    Ok(())
+}
+
+/// Highlights as Markdown quotes, each followed by its note, for bookmark
+/// notes in other services
+pub fn to_markdown(highlights: &[Highlight]) -> String {
+    highlights
+        .iter()
+        .map(|h| {
+            let quote = h
+                .quote
+                .lines()
+                .map(|l| format!("> {}", l.trim()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            match h.note.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+                Some(note) => format!("{}\n\n{}", quote, note),
+                None => quote,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::Role;
+    use crate::models::{category, entry, feed, user};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn setup_entry(conn: &Connection) -> (i64, i64) {
+        let user = user::create_user(conn, "testuser", "hash123", Role::User).unwrap();
+        let cat = category::create_category(conn, user.id, "Tech").unwrap();
+        let f = feed::create_feed(
+            conn,
+            cat.id,
+            "https://example.com/feed.xml",
+            Some("Test Feed"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let (e, _) = entry::upsert_entry(
+            conn,
+            f.id,
+            "guid-1",
+            Some("Entry"),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        (user.id, e.id)
+    }
+
+    #[test]
+    fn test_highlight_crud() {
+        let conn = setup_db();
+        let (user_id, entry_id) = setup_entry(&conn);
+
+        let later = create_highlight(
+            &conn,
+            user_id,
+            entry_id,
+            "second",
+            Some(PositionSelector { start: 50, end: 56 }),
+            None,
+        )
+        .unwrap();
+        let loose = create_highlight(&conn, user_id, entry_id, "anywhere", None, None).unwrap();
+        let first = create_highlight(
+            &conn,
+            user_id,
+            entry_id,
+            "first",
+            Some(PositionSelector { start: 0, end: 5 }),
+            Some("Good point"),
+        )
+        .unwrap();
+
+        let ids: Vec<i64> = list_by_entry(&conn, user_id, entry_id)
+            .unwrap()
+            .iter()
+            .map(|h| h.id)
+            .collect();
+        assert_eq!(ids, vec![first.id, later.id, loose.id]);
+
+        let updated = update_note(&conn, user_id, entry_id, later.id, Some("Why?")).unwrap();
+        assert_eq!(updated.note.as_deref(), Some("Why?"));
+        assert!(matches!(
+            update_note(&conn, user_id + 1, entry_id, later.id, None),
+            Err(AppError::NotFound(_))
+        ));
+
+        delete_highlight(&conn, user_id, entry_id, loose.id).unwrap();
+        assert!(delete_highlight(&conn, user_id, entry_id, loose.id).is_err());
+        assert_eq!(list_by_entry(&conn, user_id, entry_id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_restore_highlight_skips_duplicates() {
+        let conn = setup_db();
+        let (user_id, entry_id) = setup_entry(&conn);
+        let selector = Some(PositionSelector { start: 3, end: 9 });
+
+        assert!(restore_highlight(&conn, user_id, entry_id, "quote", selector, None).unwrap());
+        assert!(!restore_highlight(&conn, user_id, entry_id, "quote", selector, None).unwrap());
+        assert!(restore_highlight(&conn, user_id, entry_id, "quote", None, None).unwrap());
+        assert!(!restore_highlight(&conn, user_id, entry_id, "quote", None, None).unwrap());
+        assert_eq!(list_by_entry(&conn, user_id, entry_id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_validate_and_markdown() {
+        assert!(validate("text", None, None).is_ok());
+        assert!(validate("  ", None, None).is_err());
+        assert!(validate("text", Some(PositionSelector { start: 5, end: 5 }), None).is_err());
+        assert!(validate("text", None, Some(&"x".repeat(MAX_NOTE_CHARS + 1))).is_err());
+
+        let conn = setup_db();
+        let (user_id, entry_id) = setup_entry(&conn);
+        create_highlight(
+            &conn,
+            user_id,
+            entry_id,
+            "Line one\nline two",
+            None,
+            Some("Mine"),
+        )
+        .unwrap();
+        create_highlight(&conn, user_id, entry_id, "Other", None, None).unwrap();
+        let highlights = list_by_entry(&conn, user_id, entry_id).unwrap();
+        assert_eq!(
+            to_markdown(&highlights),
+            "> Line one\n> line two\n\nMine\n\n> Other"
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection, OptionalExtension};
 
@@ -85,6 +87,15 @@ pub fn exists(conn: &Connection, entity_type: &str, entity_id: i64) -> AppResult
     Ok(count > 0)
 }
 
+/// IDs of all entities of a type that have a stored image
+pub fn list_entity_ids(conn: &Connection, entity_type: &str) -> AppResult<HashSet<i64>> {
+    let mut stmt = conn.prepare("SELECT entity_id FROM image WHERE entity_type = ?1")?;
+    let ids = stmt
+        .query_map(params![entity_type], |row| row.get(0))?
+        .collect::<Result<HashSet<i64>, _>>()?;
+    Ok(ids)
+}
+
 pub fn needs_refresh(
     conn: &Connection,
     entity_type: &str,
@@ -106,6 +117,49 @@ pub fn needs_refresh(
     Ok(result.is_none())
 }
 
+/// Feeds whose icon is missing or older than `max_age_days` and that were
+/// not tried in the last `retry_after_days`, least recently tried first.
+/// Synthetic and trashed feeds have no site to ask.
+pub fn stale_feed_icons(
+    conn: &Connection,
+    max_age_days: i64,
+    retry_after_days: i64,
+    limit: i64,
+) -> AppResult<Vec<i64>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT f.id FROM feed f
+        LEFT JOIN image i ON i.entity_type = ?1 AND i.entity_id = f.id
+        WHERE f.synthetic = 0 AND f.deleted_at IS NULL
+          AND (i.id IS NULL OR datetime(i.fetched_at) <= datetime('now', ?2))
+          AND (f.icon_checked_at IS NULL OR datetime(f.icon_checked_at) <= datetime('now', ?3))
+        ORDER BY f.icon_checked_at, f.id
+        LIMIT ?4
+        "#,
+    )?;
+    let ids = stmt
+        .query_map(
+            params![
+                ENTITY_FEED,
+                format!("-{} days", max_age_days),
+                format!("-{} days", retry_after_days),
+                limit
+            ],
+            |row| row.get(0),
+        )?
+        .collect::<Result<Vec<i64>, _>>()?;
+    Ok(ids)
+}
+
+/// Remember that the feed's icon was just tried, found or not
+pub fn mark_feed_icon_checked(conn: &Connection, feed_id: i64) -> AppResult<()> {
+    conn.execute(
+        "UPDATE feed SET icon_checked_at = datetime('now') WHERE id = ?1",
+        params![feed_id],
+    )?;
+    Ok(())
+}
+
 pub fn delete_by_entity(conn: &Connection, entity_type: &str, entity_id: i64) -> AppResult<()> {
     conn.execute(
         "DELETE FROM image WHERE entity_type = ?1 AND entity_id = ?2",
@@ -185,6 +239,19 @@ mod tests {
         assert!(exists(&conn, ENTITY_FEED, 1).unwrap());
     }
 
+    #[test]
+    fn test_list_entity_ids() {
+        let conn = setup_db();
+
+        assert!(list_entity_ids(&conn, ENTITY_FEED).unwrap().is_empty());
+
+        upsert(&conn, ENTITY_FEED, 1, &[1, 2, 3], "image/png", None).unwrap();
+        upsert(&conn, ENTITY_FEED, 3, &[1, 2, 3], "image/png", None).unwrap();
+
+        let ids = list_entity_ids(&conn, ENTITY_FEED).unwrap();
+        assert_eq!(ids, HashSet::from([1, 3]));
+    }
+
     #[test]
     fn test_delete_by_entity() {
         let conn = setup_db();
@@ -196,6 +263,47 @@ mod tests {
         assert!(!exists(&conn, ENTITY_FEED, 1).unwrap());
     }
 
+    #[test]
+    fn test_stale_feed_icons() {
+        use crate::models::{category, feed, user};
+
+        let conn = setup_db();
+        let user_id = user::create_user(&conn, "testuser", "hash", user::Role::User)
+            .unwrap()
+            .id;
+        let category_id = category::create_category(&conn, user_id, "Tech")
+            .unwrap()
+            .id;
+        let mut ids = Vec::new();
+        for url in [
+            "https://a.example.com/feed.xml",
+            "https://b.example.com/feed.xml",
+            "https://c.example.com/feed.xml",
+        ] {
+            let f = feed::create_feed(&conn, category_id, url, None, None, None, None, None);
+            ids.push(f.unwrap().id);
+        }
+        feed::create_synthetic_feed(&conn, category_id, "rdrs:saved", "Saved pages").unwrap();
+
+        // Fresh, stale and missing
+        upsert(&conn, ENTITY_FEED, ids[0], &[1, 2, 3], "image/png", None).unwrap();
+        upsert(&conn, ENTITY_FEED, ids[1], &[1, 2, 3], "image/png", None).unwrap();
+        conn.execute(
+            "UPDATE image SET fetched_at = datetime('now', '-31 days') WHERE entity_id = ?1",
+            params![ids[1]],
+        )
+        .unwrap();
+        assert_eq!(
+            stale_feed_icons(&conn, 30, 1, 10).unwrap(),
+            vec![ids[1], ids[2]]
+        );
+        assert_eq!(stale_feed_icons(&conn, 30, 1, 1).unwrap(), vec![ids[1]]);
+
+        // Tried just now, so left alone until the next day
+        mark_feed_icon_checked(&conn, ids[2]).unwrap();
+        assert_eq!(stale_feed_icons(&conn, 30, 1, 10).unwrap(), vec![ids[1]]);
+    }
+
     #[test]
     fn test_needs_refresh() {
         let conn = setup_db();
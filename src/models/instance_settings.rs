@@ -0,0 +1,82 @@
+//! Instance-wide settings saved by admins at runtime; see
+//! `services::instance_settings`.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppResult;
+
+/// The settings admins have saved; `None` keeps the configured default
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredSettings {
+    pub signup_enabled: Option<bool>,
+    pub user_agent: Option<String>,
+    pub sync_interval_hours: Option<i64>,
+    pub trash_retention_days: Option<i64>,
+}
+
+pub fn get(conn: &Connection) -> AppResult<StoredSettings> {
+    let settings = conn
+        .query_row(
+            "SELECT signup_enabled, user_agent, sync_interval_hours, trash_retention_days \
+             FROM instance_settings WHERE id = 1",
+            [],
+            |row| {
+                Ok(StoredSettings {
+                    signup_enabled: row.get(0)?,
+                    user_agent: row.get(1)?,
+                    sync_interval_hours: row.get(2)?,
+                    trash_retention_days: row.get(3)?,
+                })
+            },
+        )
+        .optional()?;
+    Ok(settings.unwrap_or_default())
+}
+
+/// Replace the saved settings
+pub fn save(conn: &Connection, settings: &StoredSettings) -> AppResult<()> {
+    conn.execute(
+        "INSERT INTO instance_settings \
+         (id, signup_enabled, user_agent, sync_interval_hours, trash_retention_days) \
+         VALUES (1, ?1, ?2, ?3, ?4) \
+         ON CONFLICT(id) DO UPDATE SET signup_enabled = excluded.signup_enabled, \
+         user_agent = excluded.user_agent, \
+         sync_interval_hours = excluded.sync_interval_hours, \
+         trash_retention_days = excluded.trash_retention_days, \
+         updated_at = datetime('now')",
+        params![
+            settings.signup_enabled,
+            settings.user_agent,
+            settings.sync_interval_hours,
+            settings.trash_retention_days
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+
+    #[test]
+    fn test_save_and_get() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        assert_eq!(get(&conn).unwrap(), StoredSettings::default());
+
+        let settings = StoredSettings {
+            signup_enabled: Some(true),
+            user_agent: Some("MyReader/1.0".to_string()),
+            sync_interval_hours: Some(3),
+            trash_retention_days: None,
+        };
+        save(&conn, &settings).unwrap();
+        assert_eq!(get(&conn).unwrap(), settings);
+
+        // Saving again replaces every field, so None resets one
+        save(&conn, &StoredSettings::default()).unwrap();
+        assert_eq!(get(&conn).unwrap(), StoredSettings::default());
+    }
+}
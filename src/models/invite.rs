@@ -0,0 +1,178 @@
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+use crate::models::session::generate_token;
+
+/// Default lifetime of an invitation
+pub const DEFAULT_INVITE_EXPIRY_HOURS: i64 = 72;
+
+/// Longest lifetime an admin can give an invitation
+pub const MAX_INVITE_EXPIRY_HOURS: i64 = 30 * 24;
+
+/// A single-use registration token created by an admin
+#[derive(Debug, Clone, Serialize)]
+pub struct Invite {
+    pub id: i64,
+    pub token: String,
+    pub created_by: Option<i64>,
+    pub used_by: Option<i64>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Invite {
+    pub fn is_usable(&self) -> bool {
+        self.used_at.is_none() && Utc::now() < self.expires_at
+    }
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").map(|dt| dt.and_utc())
+        })
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_invite(row: &rusqlite::Row) -> rusqlite::Result<Invite> {
+    let expires_at: String = row.get(4)?;
+    let used_at: Option<String> = row.get(5)?;
+    let created_at: String = row.get(6)?;
+
+    Ok(Invite {
+        id: row.get(0)?,
+        token: row.get(1)?,
+        created_by: row.get(2)?,
+        used_by: row.get(3)?,
+        expires_at: parse_datetime(&expires_at),
+        used_at: used_at.as_deref().map(parse_datetime),
+        created_at: parse_datetime(&created_at),
+    })
+}
+
+const SELECT_COLUMNS: &str = "id, token, created_by, used_by, expires_at, used_at, created_at";
+
+pub fn create_invite(
+    conn: &Connection,
+    created_by: i64,
+    expires_in_hours: i64,
+) -> AppResult<Invite> {
+    let token = generate_token();
+    let expires_at = Utc::now() + Duration::hours(expires_in_hours);
+    let expires_at_str = expires_at.format("%Y-%m-%d %H:%M:%S").to_string();
+
+    conn.execute(
+        "INSERT INTO invite (token, created_by, expires_at) VALUES (?1, ?2, ?3)",
+        params![token, created_by, expires_at_str],
+    )?;
+
+    find_by_token(conn, &token)?.ok_or(AppError::Internal("Failed to create invite".to_string()))
+}
+
+pub fn find_by_token(conn: &Connection, token: &str) -> AppResult<Option<Invite>> {
+    conn.query_row(
+        &format!("SELECT {} FROM invite WHERE token = ?1", SELECT_COLUMNS),
+        params![token],
+        row_to_invite,
+    )
+    .optional()
+    .map_err(AppError::Database)
+}
+
+/// Find an invitation that has not been used and has not expired
+pub fn find_usable(conn: &Connection, token: &str) -> AppResult<Option<Invite>> {
+    Ok(find_by_token(conn, token)?.filter(Invite::is_usable))
+}
+
+pub fn list_all(conn: &Connection) -> AppResult<Vec<Invite>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM invite ORDER BY created_at DESC, id DESC",
+        SELECT_COLUMNS
+    ))?;
+    let invites = stmt
+        .query_map([], row_to_invite)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(invites)
+}
+
+/// Mark an invitation as used by a newly registered user. Returns false when
+/// the token was already used or has expired, so two registrations cannot
+/// share one invitation.
+pub fn redeem(conn: &Connection, token: &str, user_id: i64) -> AppResult<bool> {
+    let rows = conn.execute(
+        r#"
+        UPDATE invite SET used_by = ?2, used_at = datetime('now')
+        WHERE token = ?1 AND used_at IS NULL AND expires_at > datetime('now')
+        "#,
+        params![token, user_id],
+    )?;
+    Ok(rows > 0)
+}
+
+pub fn delete_invite(conn: &Connection, id: i64) -> AppResult<()> {
+    let rows = conn.execute("DELETE FROM invite WHERE id = ?1", params![id])?;
+    if rows == 0 {
+        return Err(AppError::NotFound("Invite not found".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::{self, Role};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_invite_is_single_use() {
+        let conn = setup_db();
+        let admin = user::create_user(&conn, "admin", "hash", Role::Admin).unwrap();
+        let invite = create_invite(&conn, admin.id, DEFAULT_INVITE_EXPIRY_HOURS).unwrap();
+        assert!(invite.is_usable());
+        assert!(find_usable(&conn, &invite.token).unwrap().is_some());
+
+        let guest = user::create_user(&conn, "guest", "hash", Role::User).unwrap();
+        assert!(redeem(&conn, &invite.token, guest.id).unwrap());
+        assert!(!redeem(&conn, &invite.token, guest.id).unwrap());
+
+        let used = find_by_token(&conn, &invite.token).unwrap().unwrap();
+        assert_eq!(used.used_by, Some(guest.id));
+        assert!(!used.is_usable());
+        assert!(find_usable(&conn, &invite.token).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_expired_invite() {
+        let conn = setup_db();
+        let admin = user::create_user(&conn, "admin", "hash", Role::Admin).unwrap();
+        let invite = create_invite(&conn, admin.id, -1).unwrap();
+
+        assert!(find_usable(&conn, &invite.token).unwrap().is_none());
+        assert!(!redeem(&conn, &invite.token, admin.id).unwrap());
+    }
+
+    #[test]
+    fn test_list_and_delete_invites() {
+        let conn = setup_db();
+        let admin = user::create_user(&conn, "admin", "hash", Role::Admin).unwrap();
+        let invite = create_invite(&conn, admin.id, 1).unwrap();
+        assert_eq!(list_all(&conn).unwrap().len(), 1);
+
+        delete_invite(&conn, invite.id).unwrap();
+        assert!(list_all(&conn).unwrap().is_empty());
+        assert!(matches!(
+            delete_invite(&conn, invite.id),
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,339 @@
+//! Persistent queue of background work.
+//!
+//! Rows move from `pending` to `running` when a worker claims them, then to
+//! `completed`, back to `pending` with a later `run_after` to be retried, or
+//! to `dead` once they run out of attempts. At most one pending or running
+//! job exists per `dedupe_key`.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+
+/// Finished and dead jobs are kept this long for inspection
+pub const JOB_RETENTION_DAYS: i64 = 7;
+
+pub const DEFAULT_JOB_LIMIT: i64 = 50;
+pub const MAX_JOB_LIMIT: i64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Completed,
+    /// Gave up after a permanent error or too many attempts
+    Dead,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Dead => "dead",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(JobStatus::Pending),
+            "running" => Some(JobStatus::Running),
+            "completed" => Some(JobStatus::Completed),
+            "dead" => Some(JobStatus::Dead),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: i64,
+    pub kind: String,
+    pub user_id: Option<i64>,
+    /// JSON describing the work, understood by the worker for `kind`
+    pub payload: String,
+    pub status: JobStatus,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    pub run_after: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A job to add to the queue
+#[derive(Debug, Clone)]
+pub struct NewJob<'a> {
+    pub kind: &'a str,
+    pub user_id: Option<i64>,
+    pub payload: String,
+    pub dedupe_key: Option<String>,
+    /// Lower runs first
+    pub priority: i64,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_job(row: &rusqlite::Row) -> rusqlite::Result<Job> {
+    let status: String = row.get(4)?;
+    let run_after: String = row.get(7)?;
+    let created_at: String = row.get(8)?;
+    let updated_at: String = row.get(9)?;
+    Ok(Job {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        user_id: row.get(2)?,
+        payload: row.get(3)?,
+        status: JobStatus::parse(&status).unwrap_or(JobStatus::Dead),
+        attempts: row.get(5)?,
+        last_error: row.get(6)?,
+        run_after: parse_datetime(&run_after),
+        created_at: parse_datetime(&created_at),
+        updated_at: parse_datetime(&updated_at),
+    })
+}
+
+const SELECT_COLUMNS: &str = "id, kind, user_id, payload, status, attempts, last_error, \
+                              run_after, created_at, updated_at";
+
+/// Queue a job. When a pending or running job already has the same
+/// `dedupe_key`, nothing is added and that job's ID is returned instead.
+pub fn enqueue(conn: &Connection, job: &NewJob) -> AppResult<i64> {
+    let rows = conn.execute(
+        r#"
+        INSERT OR IGNORE INTO job (kind, user_id, payload, dedupe_key, priority)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+        params![
+            job.kind,
+            job.user_id,
+            job.payload,
+            job.dedupe_key,
+            job.priority
+        ],
+    )?;
+    if rows > 0 {
+        return Ok(conn.last_insert_rowid());
+    }
+
+    conn.query_row(
+        "SELECT id FROM job WHERE dedupe_key = ?1 AND status IN ('pending', 'running')",
+        params![job.dedupe_key],
+        |row| row.get(0),
+    )
+    .optional()?
+    .ok_or_else(|| AppError::Internal("Job was neither queued nor found".to_string()))
+}
+
+/// Take the next job that is due, marking it running and counting the attempt
+pub fn claim_next(conn: &Connection) -> AppResult<Option<Job>> {
+    let job = conn
+        .query_row(
+            &format!(
+                r#"
+                UPDATE job
+                SET status = 'running', attempts = attempts + 1, updated_at = datetime('now')
+                WHERE id = (
+                    SELECT id FROM job
+                    WHERE status = 'pending' AND run_after <= datetime('now')
+                    ORDER BY priority, run_after, id
+                    LIMIT 1
+                )
+                RETURNING {}
+                "#,
+                SELECT_COLUMNS
+            ),
+            [],
+            row_to_job,
+        )
+        .optional()?;
+    Ok(job)
+}
+
+pub fn complete(conn: &Connection, id: i64) -> AppResult<()> {
+    conn.execute(
+        "UPDATE job SET status = 'completed', last_error = NULL, updated_at = datetime('now') WHERE id = ?1",
+        params![id],
+    )?;
+    Ok(())
+}
+
+/// Put a failed job back in the queue to run again in `delay_secs`
+pub fn retry(conn: &Connection, id: i64, error: &str, delay_secs: i64) -> AppResult<()> {
+    conn.execute(
+        r#"
+        UPDATE job
+        SET status = 'pending', last_error = ?2,
+            run_after = datetime('now', '+' || ?3 || ' seconds'),
+            updated_at = datetime('now')
+        WHERE id = ?1
+        "#,
+        params![id, error, delay_secs],
+    )?;
+    Ok(())
+}
+
+/// Give up on a job, leaving it in the dead-letter state
+pub fn kill(conn: &Connection, id: i64, error: &str) -> AppResult<()> {
+    conn.execute(
+        "UPDATE job SET status = 'dead', last_error = ?2, updated_at = datetime('now') WHERE id = ?1",
+        params![id, error],
+    )?;
+    Ok(())
+}
+
+/// Return jobs left running by a previous run to the queue
+pub fn requeue_running(conn: &Connection) -> AppResult<usize> {
+    let rows = conn.execute(
+        "UPDATE job SET status = 'pending', updated_at = datetime('now') WHERE status = 'running'",
+        [],
+    )?;
+    Ok(rows)
+}
+
+/// The latest `limit` jobs, newest first. Without a `user_id`, every user's
+/// jobs and jobs that belong to no user are listed.
+pub fn list(
+    conn: &Connection,
+    user_id: Option<i64>,
+    status: Option<JobStatus>,
+    limit: i64,
+) -> AppResult<Vec<Job>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT {} FROM job
+        WHERE (?1 IS NULL OR user_id = ?1) AND (?2 IS NULL OR status = ?2)
+        ORDER BY id DESC
+        LIMIT ?3
+        "#,
+        SELECT_COLUMNS
+    ))?;
+    let jobs = stmt
+        .query_map(
+            params![user_id, status.map(|s| s.as_str()), limit],
+            row_to_job,
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(jobs)
+}
+
+pub fn count_by_status(conn: &Connection, status: JobStatus) -> AppResult<i64> {
+    let count = conn.query_row(
+        "SELECT COUNT(*) FROM job WHERE status = ?1",
+        params![status.as_str()],
+        |row| row.get(0),
+    )?;
+    Ok(count)
+}
+
+/// Delete completed and dead jobs last touched more than `days` ago
+pub fn delete_finished(conn: &Connection, days: i64) -> AppResult<usize> {
+    let rows = conn.execute(
+        r#"
+        DELETE FROM job
+        WHERE status IN ('completed', 'dead')
+          AND updated_at < datetime('now', '-' || ?1 || ' days')
+        "#,
+        params![days],
+    )?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{user, Role};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn new_job(user_id: Option<i64>, key: Option<&str>, priority: i64) -> NewJob<'static> {
+        NewJob {
+            kind: "summary",
+            user_id,
+            payload: "{}".to_string(),
+            dedupe_key: key.map(str::to_string),
+            priority,
+        }
+    }
+
+    #[test]
+    fn test_enqueue_dedupes_active_jobs() {
+        let conn = setup_db();
+        let first = enqueue(&conn, &new_job(None, Some("k"), 0)).unwrap();
+        assert_eq!(enqueue(&conn, &new_job(None, Some("k"), 0)).unwrap(), first);
+        assert_ne!(enqueue(&conn, &new_job(None, None, 0)).unwrap(), first);
+
+        // Once finished, the key is free again
+        claim_next(&conn).unwrap().unwrap();
+        complete(&conn, first).unwrap();
+        assert_ne!(enqueue(&conn, &new_job(None, Some("k"), 0)).unwrap(), first);
+    }
+
+    #[test]
+    fn test_claim_order_and_retry() {
+        let conn = setup_db();
+        let low = enqueue(&conn, &new_job(None, None, 2)).unwrap();
+        let high = enqueue(&conn, &new_job(None, None, 0)).unwrap();
+
+        let claimed = claim_next(&conn).unwrap().unwrap();
+        assert_eq!(claimed.id, high);
+        assert_eq!(claimed.status, JobStatus::Running);
+        assert_eq!(claimed.attempts, 1);
+
+        // A retried job waits for its delay
+        retry(&conn, high, "timed out", 60).unwrap();
+        assert_eq!(claim_next(&conn).unwrap().unwrap().id, low);
+        assert!(claim_next(&conn).unwrap().is_none());
+
+        kill(&conn, low, "gone").unwrap();
+        let dead = list(&conn, None, Some(JobStatus::Dead), 10).unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].last_error.as_deref(), Some("gone"));
+    }
+
+    #[test]
+    fn test_requeue_running() {
+        let conn = setup_db();
+        let id = enqueue(&conn, &new_job(None, None, 0)).unwrap();
+        claim_next(&conn).unwrap().unwrap();
+        assert_eq!(requeue_running(&conn).unwrap(), 1);
+
+        let job = claim_next(&conn).unwrap().unwrap();
+        assert_eq!(job.id, id);
+        assert_eq!(job.attempts, 2);
+    }
+
+    #[test]
+    fn test_list_by_user_and_prune() {
+        let conn = setup_db();
+        let owner = user::create_user(&conn, "owner", "hash", Role::User).unwrap();
+        let other = user::create_user(&conn, "other", "hash", Role::User).unwrap();
+        let mine = enqueue(&conn, &new_job(Some(owner.id), None, 0)).unwrap();
+        enqueue(&conn, &new_job(Some(other.id), None, 0)).unwrap();
+
+        let listed = list(&conn, Some(owner.id), None, 10).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, mine);
+        assert_eq!(list(&conn, None, None, 10).unwrap().len(), 2);
+
+        complete(&conn, mine).unwrap();
+        conn.execute(
+            "UPDATE job SET updated_at = datetime('now', '-8 days') WHERE id = ?1",
+            params![mine],
+        )
+        .unwrap();
+        assert_eq!(delete_finished(&conn, JOB_RETENTION_DAYS).unwrap(), 1);
+        assert_eq!(list(&conn, None, None, 10).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,125 @@
+//! Failed password logins, used to lock an account that is being guessed at.
+//!
+//! After `MAX_FAILED_LOGINS` failures within `FAILED_LOGIN_WINDOW_MINUTES` the
+//! account is locked. The lock lasts `LOCKOUT_BASE_MINUTES` and doubles with
+//! every further lock in the same day, up to `MAX_LOCKOUT_HOURS`.
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection};
+
+use crate::error::AppResult;
+use crate::models::user;
+
+pub const MAX_FAILED_LOGINS: i64 = 5;
+pub const FAILED_LOGIN_WINDOW_MINUTES: i64 = 15;
+pub const LOCKOUT_BASE_MINUTES: i64 = 15;
+pub const MAX_LOCKOUT_HOURS: i64 = 24;
+
+/// How long to lock an account for its `lockouts`-th lock of the day
+pub fn lockout_duration(lockouts: i64) -> Duration {
+    let exponent = (lockouts.max(1) - 1).min(16) as u32;
+    let minutes = LOCKOUT_BASE_MINUTES.saturating_mul(1 << exponent);
+    Duration::minutes(minutes).min(Duration::hours(MAX_LOCKOUT_HOURS))
+}
+
+/// Record a failed login. Returns the lock expiry when this failure locked
+/// the account.
+pub fn record_failure(conn: &Connection, user_id: i64) -> AppResult<Option<DateTime<Utc>>> {
+    conn.execute(
+        "DELETE FROM login_attempt WHERE user_id = ?1 AND created_at < datetime('now', '-1 day')",
+        params![user_id],
+    )?;
+    conn.execute(
+        "INSERT INTO login_attempt (user_id) VALUES (?1)",
+        params![user_id],
+    )?;
+
+    // Failures from before the last lock ended have already been punished
+    let recent: i64 = conn.query_row(
+        r#"
+        SELECT COUNT(*) FROM login_attempt
+        WHERE user_id = ?1
+          AND created_at > datetime('now', ?2)
+          AND created_at > COALESCE((SELECT locked_until FROM user WHERE id = ?1), '')
+        "#,
+        params![user_id, format!("-{} minutes", FAILED_LOGIN_WINDOW_MINUTES)],
+        |row| row.get(0),
+    )?;
+    if recent < MAX_FAILED_LOGINS {
+        return Ok(None);
+    }
+
+    let daily: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM login_attempt WHERE user_id = ?1",
+        params![user_id],
+        |row| row.get(0),
+    )?;
+    let until = Utc::now() + lockout_duration(daily / MAX_FAILED_LOGINS);
+    user::lock_user(conn, user_id, until)?;
+    Ok(Some(until))
+}
+
+/// Forget failures after a successful login
+pub fn clear(conn: &Connection, user_id: i64) -> AppResult<()> {
+    conn.execute(
+        "DELETE FROM login_attempt WHERE user_id = ?1",
+        params![user_id],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::Role;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_lockout_duration() {
+        assert_eq!(lockout_duration(1), Duration::minutes(15));
+        assert_eq!(lockout_duration(2), Duration::minutes(30));
+        assert_eq!(lockout_duration(3), Duration::minutes(60));
+        assert_eq!(lockout_duration(100), Duration::hours(MAX_LOCKOUT_HOURS));
+    }
+
+    #[test]
+    fn test_record_failure_locks_account() {
+        let conn = setup_db();
+        let user_id = user::create_user(&conn, "alice", "hash", Role::User)
+            .unwrap()
+            .id;
+
+        for _ in 1..MAX_FAILED_LOGINS {
+            assert!(record_failure(&conn, user_id).unwrap().is_none());
+        }
+        let until = record_failure(&conn, user_id).unwrap().unwrap();
+        assert!(until > Utc::now());
+
+        let alice = user::find_by_id(&conn, user_id).unwrap().unwrap();
+        assert!(alice.is_locked());
+
+        user::unlock_user(&conn, user_id).unwrap();
+        let alice = user::find_by_id(&conn, user_id).unwrap().unwrap();
+        assert!(!alice.is_locked());
+    }
+
+    #[test]
+    fn test_clear_resets_failures() {
+        let conn = setup_db();
+        let user_id = user::create_user(&conn, "bob", "hash", Role::User)
+            .unwrap()
+            .id;
+
+        for _ in 1..MAX_FAILED_LOGINS {
+            record_failure(&conn, user_id).unwrap();
+        }
+        clear(&conn, user_id).unwrap();
+        assert!(record_failure(&conn, user_id).unwrap().is_none());
+    }
+}
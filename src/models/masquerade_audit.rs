@@ -0,0 +1,127 @@
+//! Trail of what admins did while masquerading as another user.
+//!
+//! Every mutating request made from a masquerading session is recorded with
+//! both users, the request and its response status. Usernames are copied in
+//! so the trail still reads after either account is deleted.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::AppResult;
+
+pub const DEFAULT_AUDIT_LIMIT: i64 = 100;
+pub const MAX_AUDIT_LIMIT: i64 = 1000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MasqueradeAudit {
+    pub id: i64,
+    pub admin_id: Option<i64>,
+    pub admin_username: Option<String>,
+    pub user_id: Option<i64>,
+    pub username: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    pub created_at: DateTime<Utc>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_audit(row: &rusqlite::Row) -> rusqlite::Result<MasqueradeAudit> {
+    let created_at: String = row.get(8)?;
+    Ok(MasqueradeAudit {
+        id: row.get(0)?,
+        admin_id: row.get(1)?,
+        admin_username: row.get(2)?,
+        user_id: row.get(3)?,
+        username: row.get(4)?,
+        method: row.get(5)?,
+        path: row.get(6)?,
+        status: row.get(7)?,
+        created_at: parse_datetime(&created_at),
+    })
+}
+
+/// Record a request `admin_id` made as `user_id`
+pub fn record(
+    conn: &Connection,
+    admin_id: i64,
+    user_id: i64,
+    method: &str,
+    path: &str,
+    status: u16,
+) -> AppResult<()> {
+    conn.execute(
+        r#"
+        INSERT INTO masquerade_audit
+            (admin_id, admin_username, user_id, username, method, path, status)
+        VALUES (
+            ?1, (SELECT username FROM user WHERE id = ?1),
+            ?2, (SELECT username FROM user WHERE id = ?2),
+            ?3, ?4, ?5
+        )
+        "#,
+        params![admin_id, user_id, method, path, status],
+    )?;
+    Ok(())
+}
+
+/// The latest `limit` records, newest first, optionally only those of one
+/// admin
+pub fn list_recent(
+    conn: &Connection,
+    admin_id: Option<i64>,
+    limit: i64,
+) -> AppResult<Vec<MasqueradeAudit>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, admin_id, admin_username, user_id, username, method, path, status, created_at
+        FROM masquerade_audit
+        WHERE ?1 IS NULL OR admin_id = ?1
+        ORDER BY id DESC
+        LIMIT ?2
+        "#,
+    )?;
+    let records = stmt
+        .query_map(params![admin_id, limit], row_to_audit)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::{self, Role};
+
+    #[test]
+    fn test_record_and_list() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let admin = user::create_user(&conn, "admin", "hash", Role::Admin).unwrap();
+        let target = user::create_user(&conn, "target", "hash", Role::User).unwrap();
+
+        record(&conn, admin.id, target.id, "POST", "/api/feeds", 201).unwrap();
+        record(&conn, admin.id, target.id, "DELETE", "/api/feeds/1", 204).unwrap();
+
+        let records = list_recent(&conn, None, 10).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].method, "DELETE");
+        assert_eq!(records[0].status, 204);
+        assert_eq!(records[1].admin_username.as_deref(), Some("admin"));
+        assert_eq!(records[1].username.as_deref(), Some("target"));
+        assert!(list_recent(&conn, Some(target.id), 10).unwrap().is_empty());
+        assert_eq!(list_recent(&conn, Some(admin.id), 1).unwrap().len(), 1);
+
+        // The trail outlives the accounts
+        user::delete_user(&conn, target.id).unwrap();
+        let records = list_recent(&conn, None, 10).unwrap();
+        assert_eq!(records[0].user_id, None);
+        assert_eq!(records[0].username.as_deref(), Some("target"));
+    }
+}
@@ -1,10 +1,43 @@
+pub mod api_token;
+pub mod author;
+pub mod auto_read;
+pub mod blocklist;
 pub mod category;
+pub mod category_digest;
+pub mod discussion_cache;
 pub mod entry;
+pub mod entry_change;
+pub mod entry_cluster;
+pub mod entry_interest;
+pub mod entry_note;
+pub mod entry_question;
+pub mod entry_revision;
+pub mod entry_save;
+pub mod entry_search;
 pub mod entry_summary;
+pub mod entry_view;
 pub mod feed;
+pub mod feed_cookie;
+pub mod feed_event;
+pub mod feed_rewrite;
+pub mod feed_script;
+pub mod feed_stats;
+pub mod highlight;
 pub mod image;
+pub mod instance_settings;
+pub mod invite;
+pub mod job;
+pub mod login_attempt;
+pub mod masquerade_audit;
+pub mod notification;
+pub mod notification_rule;
 pub mod passkey;
+pub mod push_subscription;
+pub mod related_cache;
 pub mod session;
+pub mod shared_summary;
+pub mod tag;
+pub mod undo_action;
 pub mod user;
 pub mod user_settings;
 pub mod webauthn_challenge;
@@ -0,0 +1,310 @@
+//! In-app messages about things that happened in the background.
+//!
+//! Background work that a user would otherwise only learn about from the
+//! server logs leaves a notification here, shown in the navigation bar. A
+//! user keeps at most `MAX_NOTIFICATIONS`; the oldest are dropped first.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+use crate::models::Role;
+
+/// A feed kept failing and is no longer fetched automatically
+pub const KIND_FEED_QUARANTINED: &str = "feed_quarantined";
+
+/// An OPML or full backup import finished
+pub const KIND_IMPORT_FINISHED: &str = "import_finished";
+
+/// A category digest was built, or failed to build
+pub const KIND_DIGEST_FINISHED: &str = "digest_finished";
+
+/// A newer release of rdrs is out; sent to admins once per version
+pub const KIND_UPDATE_AVAILABLE: &str = "update_available";
+
+pub const MAX_NOTIFICATIONS: i64 = 200;
+pub const DEFAULT_NOTIFICATION_LIMIT: i64 = 50;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub id: i64,
+    pub kind: String,
+    pub title: String,
+    pub body: Option<String>,
+    /// Where to go for details; a path in the app or a release page
+    pub link: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+/// What a new notification says
+#[derive(Debug, Clone, Default)]
+pub struct Message {
+    pub title: String,
+    pub body: Option<String>,
+    pub link: Option<String>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_notification(row: &rusqlite::Row) -> rusqlite::Result<Notification> {
+    let created_at: String = row.get(5)?;
+    let read_at: Option<String> = row.get(6)?;
+    Ok(Notification {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        title: row.get(2)?,
+        body: row.get(3)?,
+        link: row.get(4)?,
+        created_at: parse_datetime(&created_at),
+        read_at: read_at.map(|s| parse_datetime(&s)),
+    })
+}
+
+const SELECT_COLUMNS: &str = "id, kind, title, body, link, created_at, read_at";
+
+/// Notify `user_id`. With a `dedupe_key`, a notification the user already
+/// got under the same key is not repeated. Returns whether one was added.
+pub fn create(
+    conn: &Connection,
+    user_id: i64,
+    kind: &str,
+    dedupe_key: Option<&str>,
+    message: &Message,
+) -> AppResult<bool> {
+    let rows = conn.execute(
+        r#"
+        INSERT OR IGNORE INTO notification (user_id, kind, title, body, link, dedupe_key)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        "#,
+        params![
+            user_id,
+            kind,
+            message.title,
+            message.body,
+            message.link,
+            dedupe_key
+        ],
+    )?;
+    if rows == 0 {
+        return Ok(false);
+    }
+
+    conn.execute(
+        r#"
+        DELETE FROM notification
+        WHERE user_id = ?1
+          AND id NOT IN (
+              SELECT id FROM notification WHERE user_id = ?1 ORDER BY id DESC LIMIT ?2
+          )
+        "#,
+        params![user_id, MAX_NOTIFICATIONS],
+    )?;
+    Ok(true)
+}
+
+/// Notify the owner of `feed_id`
+pub fn notify_feed_owner(
+    conn: &Connection,
+    feed_id: i64,
+    kind: &str,
+    message: &Message,
+) -> AppResult<()> {
+    let owner: Option<i64> = conn
+        .query_row(
+            r#"
+            SELECT c.user_id FROM feed f
+            INNER JOIN category c ON f.category_id = c.id
+            WHERE f.id = ?1
+            "#,
+            params![feed_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(user_id) = owner {
+        create(conn, user_id, kind, None, message)?;
+    }
+    Ok(())
+}
+
+/// Notify every admin, once per `dedupe_key`
+pub fn notify_admins(
+    conn: &Connection,
+    kind: &str,
+    dedupe_key: &str,
+    message: &Message,
+) -> AppResult<()> {
+    let mut stmt = conn.prepare("SELECT id FROM user WHERE role = ?1")?;
+    let admins = stmt
+        .query_map(params![Role::Admin.as_str()], |row| row.get::<_, i64>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    for user_id in admins {
+        create(conn, user_id, kind, Some(dedupe_key), message)?;
+    }
+    Ok(())
+}
+
+/// The user's latest `limit` notifications, newest first
+pub fn list_by_user(
+    conn: &Connection,
+    user_id: i64,
+    unread_only: bool,
+    limit: i64,
+) -> AppResult<Vec<Notification>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM notification WHERE user_id = ?1 AND (?2 = 0 OR read_at IS NULL) ORDER BY id DESC LIMIT ?3",
+        SELECT_COLUMNS
+    ))?;
+    let notifications = stmt
+        .query_map(params![user_id, unread_only, limit], row_to_notification)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(notifications)
+}
+
+pub fn count_unread(conn: &Connection, user_id: i64) -> AppResult<i64> {
+    let count = conn.query_row(
+        "SELECT COUNT(*) FROM notification WHERE user_id = ?1 AND read_at IS NULL",
+        params![user_id],
+        |row| row.get(0),
+    )?;
+    Ok(count)
+}
+
+pub fn mark_read(conn: &Connection, id: i64, user_id: i64) -> AppResult<Notification> {
+    conn.execute(
+        "UPDATE notification SET read_at = datetime('now') WHERE id = ?1 AND user_id = ?2 AND read_at IS NULL",
+        params![id, user_id],
+    )?;
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM notification WHERE id = ?1 AND user_id = ?2",
+            SELECT_COLUMNS
+        ),
+        params![id, user_id],
+        row_to_notification,
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound("Notification not found".to_string()))
+}
+
+/// Mark all of the user's notifications read; returns how many were unread
+pub fn mark_all_read(conn: &Connection, user_id: i64) -> AppResult<i64> {
+    let rows = conn.execute(
+        "UPDATE notification SET read_at = datetime('now') WHERE user_id = ?1 AND read_at IS NULL",
+        params![user_id],
+    )?;
+    Ok(rows as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, feed, user};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn message(title: &str) -> Message {
+        Message {
+            title: title.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_create_list_and_mark_read() {
+        let conn = setup_db();
+        let owner = user::create_user(&conn, "owner", "hash", Role::User).unwrap();
+        let other = user::create_user(&conn, "other", "hash", Role::User).unwrap();
+
+        create(
+            &conn,
+            owner.id,
+            KIND_IMPORT_FINISHED,
+            None,
+            &message("first"),
+        )
+        .unwrap();
+        create(
+            &conn,
+            owner.id,
+            KIND_IMPORT_FINISHED,
+            None,
+            &message("second"),
+        )
+        .unwrap();
+        assert_eq!(count_unread(&conn, owner.id).unwrap(), 2);
+
+        let listed = list_by_user(&conn, owner.id, false, 10).unwrap();
+        assert_eq!(listed[0].title, "second");
+        assert_eq!(listed.len(), 2);
+
+        let read = mark_read(&conn, listed[0].id, owner.id).unwrap();
+        assert!(read.read_at.is_some());
+        assert_eq!(list_by_user(&conn, owner.id, true, 10).unwrap().len(), 1);
+        assert!(matches!(
+            mark_read(&conn, listed[1].id, other.id),
+            Err(AppError::NotFound(_))
+        ));
+
+        assert_eq!(mark_all_read(&conn, owner.id).unwrap(), 1);
+        assert_eq!(count_unread(&conn, owner.id).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_dedupe_and_admins() {
+        let conn = setup_db();
+        let admin = user::create_user(&conn, "admin", "hash", Role::Admin).unwrap();
+        let reader = user::create_user(&conn, "reader", "hash", Role::User).unwrap();
+
+        let update = message("rdrs 9.0.0 is available");
+        notify_admins(&conn, KIND_UPDATE_AVAILABLE, "9.0.0", &update).unwrap();
+        notify_admins(&conn, KIND_UPDATE_AVAILABLE, "9.0.0", &update).unwrap();
+        assert_eq!(count_unread(&conn, admin.id).unwrap(), 1);
+        assert_eq!(count_unread(&conn, reader.id).unwrap(), 0);
+
+        let cat = category::create_category(&conn, reader.id, "Tech").unwrap();
+        let f = feed::create_feed(
+            &conn,
+            cat.id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        notify_feed_owner(&conn, f.id, KIND_FEED_QUARANTINED, &message("down")).unwrap();
+        let listed = list_by_user(&conn, reader.id, true, 10).unwrap();
+        assert_eq!(listed[0].kind, KIND_FEED_QUARANTINED);
+    }
+
+    #[test]
+    fn test_oldest_dropped_past_limit() {
+        let conn = setup_db();
+        let owner = user::create_user(&conn, "owner", "hash", Role::User).unwrap();
+        for i in 0..=MAX_NOTIFICATIONS {
+            create(
+                &conn,
+                owner.id,
+                KIND_IMPORT_FINISHED,
+                None,
+                &message(&i.to_string()),
+            )
+            .unwrap();
+        }
+        let listed = list_by_user(&conn, owner.id, false, MAX_NOTIFICATIONS + 1).unwrap();
+        assert_eq!(listed.len() as i64, MAX_NOTIFICATIONS);
+        assert_eq!(listed.last().unwrap().title, "1");
+    }
+}
@@ -0,0 +1,170 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+
+/// A rule that triggers a push notification for new entries.
+///
+/// Every set field must match; a rule with no fields matches everything.
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationRule {
+    pub id: i64,
+    pub user_id: i64,
+    pub feed_id: Option<i64>,
+    pub category_id: Option<i64>,
+    pub keyword: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl NotificationRule {
+    pub fn matches(&self, feed_id: i64, category_id: i64, title: Option<&str>) -> bool {
+        if self.feed_id.is_some_and(|id| id != feed_id) {
+            return false;
+        }
+        if self.category_id.is_some_and(|id| id != category_id) {
+            return false;
+        }
+        match &self.keyword {
+            Some(keyword) => title
+                .map(|t| t.to_lowercase().contains(&keyword.to_lowercase()))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").map(|dt| dt.and_utc())
+        })
+        .or_else(|_| dateparser::parse(s).map(|dt| dt.with_timezone(&Utc)))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_rule(row: &rusqlite::Row) -> rusqlite::Result<NotificationRule> {
+    let created_at: String = row.get(5)?;
+
+    Ok(NotificationRule {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        feed_id: row.get(2)?,
+        category_id: row.get(3)?,
+        keyword: row.get(4)?,
+        created_at: parse_datetime(&created_at),
+    })
+}
+
+pub fn create_rule(
+    conn: &Connection,
+    user_id: i64,
+    feed_id: Option<i64>,
+    category_id: Option<i64>,
+    keyword: Option<&str>,
+) -> AppResult<NotificationRule> {
+    conn.execute(
+        "INSERT INTO notification_rule (user_id, feed_id, category_id, keyword) VALUES (?1, ?2, ?3, ?4)",
+        params![user_id, feed_id, category_id, keyword],
+    )?;
+
+    let id = conn.last_insert_rowid();
+    find_by_id(conn, id)?.ok_or(AppError::Internal(
+        "Failed to create notification rule".to_string(),
+    ))
+}
+
+pub fn find_by_id(conn: &Connection, id: i64) -> AppResult<Option<NotificationRule>> {
+    conn.query_row(
+        "SELECT id, user_id, feed_id, category_id, keyword, created_at FROM notification_rule WHERE id = ?1",
+        params![id],
+        row_to_rule,
+    )
+    .optional()
+    .map_err(AppError::Database)
+}
+
+pub fn list_by_user(conn: &Connection, user_id: i64) -> AppResult<Vec<NotificationRule>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, user_id, feed_id, category_id, keyword, created_at FROM notification_rule WHERE user_id = ?1 ORDER BY id ASC",
+    )?;
+    let rules = stmt
+        .query_map(params![user_id], row_to_rule)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rules)
+}
+
+pub fn delete_rule(conn: &Connection, id: i64, user_id: i64) -> AppResult<()> {
+    let deleted = conn.execute(
+        "DELETE FROM notification_rule WHERE id = ?1 AND user_id = ?2",
+        params![id, user_id],
+    )?;
+    if deleted == 0 {
+        return Err(AppError::NotFound(
+            "Notification rule not found".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::{self, Role};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn rule(
+        feed_id: Option<i64>,
+        category_id: Option<i64>,
+        keyword: Option<&str>,
+    ) -> NotificationRule {
+        NotificationRule {
+            id: 1,
+            user_id: 1,
+            feed_id,
+            category_id,
+            keyword: keyword.map(String::from),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        assert!(rule(None, None, None).matches(1, 1, None));
+        assert!(rule(Some(1), None, None).matches(1, 2, None));
+        assert!(!rule(Some(1), None, None).matches(2, 2, None));
+        assert!(rule(None, Some(2), None).matches(1, 2, None));
+        assert!(!rule(None, Some(3), None).matches(1, 2, None));
+        assert!(rule(None, None, Some("rust")).matches(1, 1, Some("Rust 2.0 released")));
+        assert!(!rule(None, None, Some("rust")).matches(1, 1, Some("Go 2.0 released")));
+        assert!(!rule(None, None, Some("rust")).matches(1, 1, None));
+        assert!(!rule(Some(2), None, Some("rust")).matches(1, 1, Some("Rust")));
+    }
+
+    #[test]
+    fn test_create_list_delete_rule() {
+        let conn = setup_db();
+        let user1 = user::create_user(&conn, "user1", "hash", Role::User).unwrap();
+        let user2 = user::create_user(&conn, "user2", "hash", Role::User).unwrap();
+
+        let created = create_rule(&conn, user1.id, None, None, Some("breaking")).unwrap();
+        assert_eq!(created.keyword.as_deref(), Some("breaking"));
+
+        let rules = list_by_user(&conn, user1.id).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert!(list_by_user(&conn, user2.id).unwrap().is_empty());
+
+        let result = delete_rule(&conn, created.id, user2.id);
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+
+        delete_rule(&conn, created.id, user1.id).unwrap();
+        assert!(list_by_user(&conn, user1.id).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PushSubscription {
+    pub id: i64,
+    pub user_id: i64,
+    pub endpoint: String,
+    #[serde(skip_serializing)]
+    pub p256dh: String,
+    #[serde(skip_serializing)]
+    pub auth: String,
+    pub created_at: DateTime<Utc>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").map(|dt| dt.and_utc())
+        })
+        .or_else(|_| dateparser::parse(s).map(|dt| dt.with_timezone(&Utc)))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_subscription(row: &rusqlite::Row) -> rusqlite::Result<PushSubscription> {
+    let created_at: String = row.get(5)?;
+
+    Ok(PushSubscription {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        endpoint: row.get(2)?,
+        p256dh: row.get(3)?,
+        auth: row.get(4)?,
+        created_at: parse_datetime(&created_at),
+    })
+}
+
+/// Register a subscription. Re-subscribing the same endpoint moves it to the
+/// current user and replaces its keys.
+pub fn upsert_subscription(
+    conn: &Connection,
+    user_id: i64,
+    endpoint: &str,
+    p256dh: &str,
+    auth: &str,
+) -> AppResult<PushSubscription> {
+    conn.execute(
+        r#"
+        INSERT INTO push_subscription (user_id, endpoint, p256dh, auth)
+        VALUES (?1, ?2, ?3, ?4)
+        ON CONFLICT(endpoint) DO UPDATE SET
+            user_id = excluded.user_id,
+            p256dh = excluded.p256dh,
+            auth = excluded.auth
+        "#,
+        params![user_id, endpoint, p256dh, auth],
+    )?;
+
+    find_by_endpoint(conn, endpoint)?.ok_or(AppError::Internal(
+        "Failed to create push subscription".to_string(),
+    ))
+}
+
+pub fn find_by_endpoint(conn: &Connection, endpoint: &str) -> AppResult<Option<PushSubscription>> {
+    conn.query_row(
+        "SELECT id, user_id, endpoint, p256dh, auth, created_at FROM push_subscription WHERE endpoint = ?1",
+        params![endpoint],
+        row_to_subscription,
+    )
+    .optional()
+    .map_err(AppError::Database)
+}
+
+pub fn list_by_user(conn: &Connection, user_id: i64) -> AppResult<Vec<PushSubscription>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, user_id, endpoint, p256dh, auth, created_at FROM push_subscription WHERE user_id = ?1 ORDER BY created_at DESC",
+    )?;
+    let subscriptions = stmt
+        .query_map(params![user_id], row_to_subscription)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(subscriptions)
+}
+
+pub fn delete_subscription(conn: &Connection, user_id: i64, endpoint: &str) -> AppResult<bool> {
+    let deleted = conn.execute(
+        "DELETE FROM push_subscription WHERE user_id = ?1 AND endpoint = ?2",
+        params![user_id, endpoint],
+    )?;
+    Ok(deleted > 0)
+}
+
+/// Remove a subscription the push service reported as gone
+pub fn delete_by_id(conn: &Connection, id: i64) -> AppResult<()> {
+    conn.execute("DELETE FROM push_subscription WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::{self, Role};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_upsert_and_list_subscriptions() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+
+        let sub = upsert_subscription(&conn, user.id, "https://push.example.com/1", "key", "auth")
+            .unwrap();
+        assert_eq!(sub.user_id, user.id);
+
+        // Same endpoint replaces keys instead of duplicating
+        let sub2 = upsert_subscription(
+            &conn,
+            user.id,
+            "https://push.example.com/1",
+            "key2",
+            "auth2",
+        )
+        .unwrap();
+        assert_eq!(sub.id, sub2.id);
+        assert_eq!(sub2.p256dh, "key2");
+
+        let subs = list_by_user(&conn, user.id).unwrap();
+        assert_eq!(subs.len(), 1);
+    }
+
+    #[test]
+    fn test_delete_subscription() {
+        let conn = setup_db();
+        let user1 = user::create_user(&conn, "user1", "hash", Role::User).unwrap();
+        let user2 = user::create_user(&conn, "user2", "hash", Role::User).unwrap();
+
+        upsert_subscription(&conn, user1.id, "https://push.example.com/1", "k", "a").unwrap();
+
+        assert!(!delete_subscription(&conn, user2.id, "https://push.example.com/1").unwrap());
+        assert!(delete_subscription(&conn, user1.id, "https://push.example.com/1").unwrap());
+        assert!(list_by_user(&conn, user1.id).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+//! Cached related entries, keyed by the entry they were ranked for. Entries
+//! belong to a single user's feed, so the IDs never cross users.
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppResult;
+
+/// Related entry IDs for `entry_id` ranked less than `max_age_secs` ago
+pub fn find_fresh(
+    conn: &Connection,
+    entry_id: i64,
+    max_age_secs: i64,
+) -> AppResult<Option<Vec<i64>>> {
+    let cached: Option<String> = conn
+        .query_row(
+            "SELECT related_ids FROM related_cache \
+             WHERE entry_id = ?1 AND computed_at > datetime('now', ?2)",
+            params![entry_id, format!("-{} seconds", max_age_secs)],
+            |row| row.get(0),
+        )
+        .optional()?;
+    // A row that no longer parses is treated as a miss and ranked again
+    Ok(cached.and_then(|ids| serde_json::from_str(&ids).ok()))
+}
+
+pub fn store(conn: &Connection, entry_id: i64, related_ids: &[i64]) -> AppResult<()> {
+    let ids = serde_json::to_string(related_ids).unwrap_or_else(|_| "[]".to_string());
+    conn.execute(
+        "INSERT INTO related_cache (entry_id, related_ids) VALUES (?1, ?2) \
+         ON CONFLICT(entry_id) DO UPDATE SET related_ids = excluded.related_ids, \
+         computed_at = datetime('now')",
+        params![entry_id, ids],
+    )?;
+    Ok(())
+}
+
+/// Drop rankings older than `max_age_secs`
+pub fn delete_expired(conn: &Connection, max_age_secs: i64) -> AppResult<usize> {
+    let rows = conn.execute(
+        "DELETE FROM related_cache WHERE computed_at <= datetime('now', ?1)",
+        params![format!("-{} seconds", max_age_secs)],
+    )?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, entry, feed, user};
+
+    fn setup_db() -> (Connection, i64) {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user = user::create_user(&conn, "testuser", "hash", user::Role::User).unwrap();
+        let cat = category::create_category(&conn, user.id, "Tech").unwrap();
+        let feed = feed::create_feed(
+            &conn,
+            cat.id,
+            "https://example.com/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let (entry, _) = entry::upsert_entry(
+            &conn,
+            feed.id,
+            "guid",
+            Some("Post"),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        (conn, entry.id)
+    }
+
+    #[test]
+    fn test_store_and_expire() {
+        let (conn, entry_id) = setup_db();
+        assert!(find_fresh(&conn, entry_id, 3600).unwrap().is_none());
+
+        store(&conn, entry_id, &[]).unwrap();
+        store(&conn, entry_id, &[3, 2]).unwrap();
+        assert_eq!(find_fresh(&conn, entry_id, 3600).unwrap(), Some(vec![3, 2]));
+
+        conn.execute(
+            "UPDATE related_cache SET computed_at = datetime('now', '-2 hours')",
+            [],
+        )
+        .unwrap();
+        assert!(find_fresh(&conn, entry_id, 3600).unwrap().is_none());
+
+        assert_eq!(delete_expired(&conn, 3600).unwrap(), 1);
+        assert!(find_fresh(&conn, entry_id, 3 * 3600).unwrap().is_none());
+    }
+}
@@ -4,9 +4,12 @@ use rusqlite::{params, Connection, OptionalExtension};
 
 use crate::error::{AppError, AppResult};
 
-pub const SESSION_EXPIRY_DAYS: i64 = 7;
 const TOKEN_LENGTH: usize = 32;
 
+/// Longest a session goes without its expiry being pushed forward; limits
+/// sliding renewal to one write per interval rather than one per request
+const MAX_REFRESH_INTERVAL_MINUTES: i64 = 60;
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct Session {
@@ -16,6 +19,10 @@ pub struct Session {
     pub original_user_id: Option<i64>,
     pub created_at: DateTime<Utc>,
     pub expires_at: DateTime<Utc>,
+    /// Remembered sessions get a long lifetime and a persistent cookie;
+    /// others end with the browser session or after a short idle time
+    pub persistent: bool,
+    pub user_agent: Option<String>,
 }
 
 impl Session {
@@ -26,9 +33,16 @@ impl Session {
     pub fn is_expired(&self) -> bool {
         Utc::now() > self.expires_at
     }
+
+    /// Whether activity now should renew the session for another `ttl`
+    pub fn needs_refresh(&self, ttl: Duration) -> bool {
+        let interval = (ttl / 10).min(Duration::minutes(MAX_REFRESH_INTERVAL_MINUTES));
+        self.expires_at < Utc::now() + ttl - interval
+    }
 }
 
-fn generate_token() -> String {
+/// Random URL-safe token, also used for invitations
+pub(crate) fn generate_token() -> String {
     let mut rng = rand::thread_rng();
     let bytes: Vec<u8> = (0..TOKEN_LENGTH).map(|_| rng.gen()).collect();
     base64_encode(&bytes)
@@ -78,17 +92,31 @@ fn row_to_session(row: &rusqlite::Row) -> rusqlite::Result<Session> {
         original_user_id: row.get(3)?,
         created_at: parse_datetime(&created_at),
         expires_at: parse_datetime(&expires_at),
+        persistent: row.get(6)?,
+        user_agent: row.get(7)?,
     })
 }
 
-pub fn create_session(conn: &Connection, user_id: i64) -> AppResult<Session> {
+const SELECT_COLUMNS: &str =
+    "id, user_id, session_token, original_user_id, created_at, expires_at, persistent, user_agent";
+
+pub fn create_session(
+    conn: &Connection,
+    user_id: i64,
+    ttl: Duration,
+    persistent: bool,
+    user_agent: Option<&str>,
+) -> AppResult<Session> {
     let token = generate_token();
-    let expires_at = Utc::now() + Duration::days(SESSION_EXPIRY_DAYS);
+    let expires_at = Utc::now() + ttl;
     let expires_at_str = expires_at.format("%Y-%m-%d %H:%M:%S").to_string();
 
     conn.execute(
-        "INSERT INTO session (user_id, session_token, expires_at) VALUES (?1, ?2, ?3)",
-        params![user_id, token, expires_at_str],
+        r#"
+        INSERT INTO session (user_id, session_token, expires_at, persistent, user_agent)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+        params![user_id, token, expires_at_str, persistent, user_agent],
     )?;
 
     let id = conn.last_insert_rowid();
@@ -97,7 +125,7 @@ pub fn create_session(conn: &Connection, user_id: i64) -> AppResult<Session> {
 
 fn find_by_id(conn: &Connection, id: i64) -> AppResult<Option<Session>> {
     conn.query_row(
-        "SELECT id, user_id, session_token, original_user_id, created_at, expires_at FROM session WHERE id = ?1",
+        &format!("SELECT {} FROM session WHERE id = ?1", SELECT_COLUMNS),
         params![id],
         row_to_session,
     )
@@ -107,7 +135,10 @@ fn find_by_id(conn: &Connection, id: i64) -> AppResult<Option<Session>> {
 
 pub fn find_by_token(conn: &Connection, token: &str) -> AppResult<Option<Session>> {
     conn.query_row(
-        "SELECT id, user_id, session_token, original_user_id, created_at, expires_at FROM session WHERE session_token = ?1",
+        &format!(
+            "SELECT {} FROM session WHERE session_token = ?1",
+            SELECT_COLUMNS
+        ),
         params![token],
         row_to_session,
     )
@@ -123,12 +154,85 @@ pub fn delete_session(conn: &Connection, token: &str) -> AppResult<()> {
     Ok(())
 }
 
+/// Unexpired sessions of a user, most recently created first
+pub fn list_by_user(conn: &Connection, user_id: i64) -> AppResult<Vec<Session>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM session WHERE user_id = ?1 AND expires_at > datetime('now') \
+         ORDER BY created_at DESC, id DESC",
+        SELECT_COLUMNS
+    ))?;
+    let sessions = stmt
+        .query_map(params![user_id], row_to_session)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(sessions)
+}
+
+/// Sign out one of the user's sessions
+pub fn delete_user_session(conn: &Connection, user_id: i64, id: i64) -> AppResult<bool> {
+    let rows = conn.execute(
+        "DELETE FROM session WHERE id = ?1 AND user_id = ?2",
+        params![id, user_id],
+    )?;
+    Ok(rows > 0)
+}
+
+/// Short description of the device behind a User-Agent, such as
+/// "Mobile · Safari on iOS"
+pub fn describe_device(user_agent: &str) -> String {
+    let ua = user_agent.to_ascii_lowercase();
+
+    let kind = if ua.contains("ipad") || ua.contains("tablet") {
+        "Tablet"
+    } else if ua.contains("mobi") || ua.contains("iphone") || ua.contains("android") {
+        "Mobile"
+    } else {
+        "Desktop"
+    };
+
+    let browser = if ua.contains("edg/") {
+        "Edge"
+    } else if ua.contains("firefox/") {
+        "Firefox"
+    } else if ua.contains("chrome/") || ua.contains("crios/") {
+        "Chrome"
+    } else if ua.contains("safari/") {
+        "Safari"
+    } else {
+        "Unknown browser"
+    };
+
+    let os = if ua.contains("iphone") || ua.contains("ipad") {
+        "iOS"
+    } else if ua.contains("android") {
+        "Android"
+    } else if ua.contains("mac os") {
+        "macOS"
+    } else if ua.contains("windows") {
+        "Windows"
+    } else if ua.contains("linux") {
+        "Linux"
+    } else {
+        "unknown OS"
+    };
+
+    format!("{} · {} on {}", kind, browser, os)
+}
+
 pub fn delete_user_sessions(conn: &Connection, user_id: i64) -> AppResult<()> {
     conn.execute("DELETE FROM session WHERE user_id = ?1", params![user_id])?;
     Ok(())
 }
 
-#[allow(dead_code)]
+/// Push the session's expiry to `ttl` from now
+pub fn extend_session(conn: &Connection, token: &str, ttl: Duration) -> AppResult<()> {
+    let expires_at = (Utc::now() + ttl).format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "UPDATE session SET expires_at = ?1 WHERE session_token = ?2",
+        params![expires_at, token],
+    )?;
+    Ok(())
+}
+
 pub fn cleanup_expired(conn: &Connection) -> AppResult<usize> {
     let deleted = conn.execute("DELETE FROM session WHERE expires_at < datetime('now')", [])?;
     Ok(deleted)
@@ -176,12 +280,16 @@ mod tests {
         conn
     }
 
+    fn ttl() -> Duration {
+        Duration::days(7)
+    }
+
     #[test]
     fn test_create_and_find_session() {
         let conn = setup_db();
         let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
 
-        let session = create_session(&conn, user.id).unwrap();
+        let session = create_session(&conn, user.id, ttl(), true, None).unwrap();
         assert_eq!(session.user_id, user.id);
         assert!(!session.is_masquerading());
         assert!(!session.is_expired());
@@ -197,20 +305,112 @@ mod tests {
         let conn = setup_db();
         let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
 
-        let session = create_session(&conn, user.id).unwrap();
+        let session = create_session(&conn, user.id, ttl(), true, None).unwrap();
         delete_session(&conn, &session.session_token).unwrap();
 
         let found = find_by_token(&conn, &session.session_token).unwrap();
         assert!(found.is_none());
     }
 
+    #[test]
+    fn test_sliding_expiry() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+
+        let session = create_session(&conn, user.id, ttl(), true, None).unwrap();
+        assert!(!session.needs_refresh(ttl()));
+
+        // An hour of inactivity later the session is due for renewal
+        conn.execute(
+            "UPDATE session SET expires_at = datetime('now', '+6 days', '+22 hours')",
+            [],
+        )
+        .unwrap();
+        let stale = find_by_token(&conn, &session.session_token)
+            .unwrap()
+            .unwrap();
+        assert!(stale.needs_refresh(ttl()));
+
+        extend_session(&conn, &session.session_token, ttl()).unwrap();
+        let renewed = find_by_token(&conn, &session.session_token)
+            .unwrap()
+            .unwrap();
+        assert!(!renewed.needs_refresh(ttl()));
+        assert!(renewed.expires_at > stale.expires_at);
+    }
+
+    #[test]
+    fn test_cleanup_expired() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+
+        let active = create_session(&conn, user.id, ttl(), true, None).unwrap();
+        let expired = create_session(&conn, user.id, Duration::seconds(-1), true, None).unwrap();
+        assert!(expired.is_expired());
+
+        assert_eq!(cleanup_expired(&conn).unwrap(), 1);
+        assert!(find_by_token(&conn, &active.session_token)
+            .unwrap()
+            .is_some());
+        assert!(find_by_token(&conn, &expired.session_token)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_list_and_delete_user_sessions() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+        let other = user::create_user(&conn, "other", "hash", Role::User).unwrap();
+
+        let laptop = create_session(&conn, user.id, ttl(), true, Some("Firefox/120")).unwrap();
+        create_session(&conn, user.id, Duration::hours(2), false, None).unwrap();
+        create_session(&conn, other.id, ttl(), true, None).unwrap();
+
+        let sessions = list_by_user(&conn, user.id).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.iter().any(|s| !s.persistent));
+
+        assert!(!delete_user_session(&conn, other.id, laptop.id).unwrap());
+        assert!(delete_user_session(&conn, user.id, laptop.id).unwrap());
+        assert_eq!(list_by_user(&conn, user.id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_describe_device() {
+        assert_eq!(
+            describe_device(
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 \
+                 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1"
+            ),
+            "Mobile · Safari on iOS"
+        );
+        assert_eq!(
+            describe_device(
+                "Mozilla/5.0 (X11; Linux x86_64; rv:120.0) Gecko/20100101 Firefox/120.0"
+            ),
+            "Desktop · Firefox on Linux"
+        );
+        assert_eq!(
+            describe_device(
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+                 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.0.0"
+            ),
+            "Desktop · Edge on Windows"
+        );
+        assert_eq!(
+            describe_device("curl/8.0"),
+            "Desktop · Unknown browser on unknown OS"
+        );
+    }
+
     #[test]
     fn test_masquerade() {
         let conn = setup_db();
         let admin = user::create_user(&conn, "admin", "hash", Role::Admin).unwrap();
         let target = user::create_user(&conn, "target", "hash", Role::User).unwrap();
 
-        let session = create_session(&conn, admin.id).unwrap();
+        let session = create_session(&conn, admin.id, ttl(), true, None).unwrap();
         assert!(!session.is_masquerading());
 
         start_masquerade(&conn, &session.session_token, target.id).unwrap();
@@ -237,7 +437,7 @@ mod tests {
         let admin = user::create_user(&conn, "admin", "hash", Role::Admin).unwrap();
         let target = user::create_user(&conn, "target", "hash", Role::User).unwrap();
 
-        let session = create_session(&conn, admin.id).unwrap();
+        let session = create_session(&conn, admin.id, ttl(), true, None).unwrap();
         start_masquerade(&conn, &session.session_token, target.id).unwrap();
 
         let result = start_masquerade(&conn, &session.session_token, target.id);
@@ -249,7 +449,7 @@ mod tests {
         let conn = setup_db();
         let user = user::create_user(&conn, "user", "hash", Role::User).unwrap();
 
-        let session = create_session(&conn, user.id).unwrap();
+        let session = create_session(&conn, user.id, ttl(), true, None).unwrap();
 
         let result = stop_masquerade(&conn, &session.session_token);
         assert!(matches!(result, Err(AppError::NotMasquerading)));
@@ -0,0 +1,111 @@
+//! Completed summaries shared between users.
+//!
+//! Summaries are made from an entry's link, so users subscribed to the same
+//! feed would otherwise have the same article summarized once each. Finished
+//! summaries are kept here as Kagi returned them, under a hash of the
+//! normalized link and the kind of summary. A user only sees one through
+//! their own `entry_summary` row, made in their style and length when they
+//! ask for a summary of an entry with that link.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+use crate::error::AppResult;
+use crate::models::feed::canonical_url;
+use crate::services::sanitize::without_tracking_params;
+
+/// Key for `link` and Kagi's `summary_type`: the same article reached
+/// through another scheme, with `www.`, a trailing slash, a fragment or
+/// tracking parameters shares it
+pub fn link_key(link: &str, summary_type: &str) -> String {
+    let link = without_tracking_params(link).unwrap_or_else(|| link.to_string());
+    let digest = Sha256::digest(format!("{}\n{}", summary_type, canonical_url(&link)).as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The summary stored for `key`, if any
+pub fn find_text(conn: &Connection, key: &str) -> AppResult<Option<String>> {
+    let text = conn
+        .query_row(
+            "SELECT summary_text FROM shared_summary WHERE link_key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(text)
+}
+
+/// Store a finished summary, replacing an older one for the same key
+pub fn store(conn: &Connection, key: &str, summary_text: &str) -> AppResult<()> {
+    conn.execute(
+        r#"
+        INSERT INTO shared_summary (link_key, summary_text)
+        VALUES (?1, ?2)
+        ON CONFLICT(link_key) DO UPDATE SET
+            summary_text = excluded.summary_text,
+            created_at = datetime('now')
+        "#,
+        params![key, summary_text],
+    )?;
+    Ok(())
+}
+
+/// Delete shared summaries older than `hours`
+pub fn delete_expired(conn: &Connection, hours: i64) -> AppResult<usize> {
+    let rows = conn.execute(
+        "DELETE FROM shared_summary WHERE created_at < datetime('now', '-' || ?1 || ' hours')",
+        params![hours],
+    )?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_link_key_normalizes() {
+        let key = link_key("https://example.com/post?id=1", "summary");
+        assert_eq!(key.len(), 64);
+        assert_eq!(
+            link_key("http://www.Example.com/post/?id=1#comments", "summary"),
+            key
+        );
+        assert_eq!(
+            link_key(
+                "https://example.com/post?id=1&utm_source=rss&fbclid=abc",
+                "summary"
+            ),
+            key
+        );
+        assert_ne!(link_key("https://example.com/post?id=2", "summary"), key);
+        assert_ne!(link_key("https://example.com/post?id=1", "takeaway"), key);
+    }
+
+    #[test]
+    fn test_store_find_and_expire() {
+        let conn = setup_db();
+        let key = link_key("https://example.com/post", "summary");
+        assert!(find_text(&conn, &key).unwrap().is_none());
+
+        store(&conn, &key, "first").unwrap();
+        store(&conn, &key, "second").unwrap();
+        assert_eq!(find_text(&conn, &key).unwrap().as_deref(), Some("second"));
+
+        assert_eq!(delete_expired(&conn, 24).unwrap(), 0);
+        conn.execute(
+            "UPDATE shared_summary SET created_at = datetime('now', '-25 hours')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(delete_expired(&conn, 24).unwrap(), 1);
+        assert!(find_text(&conn, &key).unwrap().is_none());
+    }
+}
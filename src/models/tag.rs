@@ -0,0 +1,204 @@
+//! A user's topic labels and the entries they are put on.
+//!
+//! Tags are the label set the summarizer backend picks from when a user has
+//! automatic tagging turned on; entries can then be listed by tag.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+
+/// Longest tag name accepted, in characters
+pub const MAX_NAME_CHARS: usize = 50;
+
+/// Longest description accepted, in characters
+pub const MAX_DESCRIPTION_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    /// What the tag covers, to help the backend tell tags apart
+    pub description: Option<String>,
+}
+
+fn row_to_tag(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+    })
+}
+
+/// The user's tags, by name
+pub fn list_by_user(conn: &Connection, user_id: i64) -> AppResult<Vec<Tag>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, description FROM tag WHERE user_id = ?1 ORDER BY name COLLATE NOCASE",
+    )?;
+    let tags = stmt
+        .query_map(params![user_id], row_to_tag)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(tags)
+}
+
+pub fn create_tag(
+    conn: &Connection,
+    user_id: i64,
+    name: &str,
+    description: Option<&str>,
+) -> AppResult<Tag> {
+    let result = conn.query_row(
+        r#"
+        INSERT INTO tag (user_id, name, description) VALUES (?1, ?2, ?3)
+        RETURNING id, name, description
+        "#,
+        params![user_id, name, description],
+        row_to_tag,
+    );
+
+    match result {
+        Ok(tag) => Ok(tag),
+        Err(rusqlite::Error::SqliteFailure(err, _))
+            if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            Err(AppError::Validation("Tag already exists".to_string()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn delete_tag(conn: &Connection, id: i64, user_id: i64) -> AppResult<()> {
+    let deleted = conn.execute(
+        "DELETE FROM tag WHERE id = ?1 AND user_id = ?2",
+        params![id, user_id],
+    )?;
+    if deleted == 0 {
+        return Err(AppError::NotFound("Tag not found".to_string()));
+    }
+    Ok(())
+}
+
+/// Put tags on an entry; tags it already has are kept
+pub fn add_entry_tags(conn: &Connection, entry_id: i64, tag_ids: &[i64]) -> AppResult<()> {
+    let mut stmt =
+        conn.prepare("INSERT OR IGNORE INTO entry_tag (entry_id, tag_id) VALUES (?1, ?2)")?;
+    for tag_id in tag_ids {
+        stmt.execute(params![entry_id, tag_id])?;
+    }
+    Ok(())
+}
+
+/// The tags on an entry, by name
+pub fn list_for_entry(conn: &Connection, entry_id: i64) -> AppResult<Vec<Tag>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT t.id, t.name, t.description FROM tag t
+        INNER JOIN entry_tag et ON et.tag_id = t.id
+        WHERE et.entry_id = ?1
+        ORDER BY t.name COLLATE NOCASE
+        "#,
+    )?;
+    let tags = stmt
+        .query_map(params![entry_id], row_to_tag)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(tags)
+}
+
+/// Whether the entry has any tag yet
+pub fn is_tagged(conn: &Connection, entry_id: i64) -> AppResult<bool> {
+    let found = conn
+        .query_row(
+            "SELECT 1 FROM entry_tag WHERE entry_id = ?1 LIMIT 1",
+            params![entry_id],
+            |_| Ok(()),
+        )
+        .optional()?;
+    Ok(found.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::Role;
+    use crate::models::{category, entry, feed, user};
+
+    fn setup() -> (Connection, i64, i64) {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "tagger", "hash123", Role::User)
+            .unwrap()
+            .id;
+        let category_id = category::create_category(&conn, user_id, "Tech")
+            .unwrap()
+            .id;
+        let feed_id = feed::create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed.xml",
+            Some("Test Feed"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .id;
+        let (entry, _) = entry::upsert_entry(
+            &conn,
+            feed_id,
+            "guid-123",
+            Some("Test Entry"),
+            Some("https://example.com/entry"),
+            Some("Content"),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        (conn, user_id, entry.id)
+    }
+
+    #[test]
+    fn test_create_list_and_delete() {
+        let (conn, user_id, _) = setup();
+        let rust = create_tag(&conn, user_id, "rust", Some("The language")).unwrap();
+        create_tag(&conn, user_id, "AI", None).unwrap();
+
+        assert!(matches!(
+            create_tag(&conn, user_id, "Rust", None),
+            Err(AppError::Validation(_))
+        ));
+
+        let names: Vec<String> = list_by_user(&conn, user_id)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, vec!["AI", "rust"]);
+
+        delete_tag(&conn, rust.id, user_id).unwrap();
+        assert!(matches!(
+            delete_tag(&conn, rust.id, user_id),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_entry_tags() {
+        let (conn, user_id, entry_id) = setup();
+        let rust = create_tag(&conn, user_id, "rust", None).unwrap();
+        let ai = create_tag(&conn, user_id, "ai", None).unwrap();
+        assert!(!is_tagged(&conn, entry_id).unwrap());
+
+        add_entry_tags(&conn, entry_id, &[rust.id]).unwrap();
+        add_entry_tags(&conn, entry_id, &[rust.id, ai.id]).unwrap();
+        let tags = list_for_entry(&conn, entry_id).unwrap();
+        assert_eq!(tags.len(), 2);
+        assert!(is_tagged(&conn, entry_id).unwrap());
+
+        // Deleting a tag takes it off its entries
+        delete_tag(&conn, ai.id, user_id).unwrap();
+        assert_eq!(list_for_entry(&conn, entry_id).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,143 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::{AppError, AppResult};
+use crate::models::session::generate_token;
+
+/// A short-lived record of what a bulk action changed, kept so the action
+/// can be reverted. `payload` is interpreted by `services::undo`.
+#[derive(Debug, Clone)]
+pub struct UndoAction {
+    pub id: i64,
+    pub user_id: i64,
+    pub token: String,
+    pub payload: String,
+    pub created_at: DateTime<Utc>,
+}
+
+fn parse_datetime(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").map(|dt| dt.and_utc())
+        })
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn row_to_action(row: &rusqlite::Row) -> rusqlite::Result<UndoAction> {
+    let created_at: String = row.get(4)?;
+
+    Ok(UndoAction {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        token: row.get(2)?,
+        payload: row.get(3)?,
+        created_at: parse_datetime(&created_at),
+    })
+}
+
+const SELECT_COLUMNS: &str = "id, user_id, token, payload, created_at";
+
+pub fn create(conn: &Connection, user_id: i64, payload: &str) -> AppResult<UndoAction> {
+    let token = generate_token();
+    conn.execute(
+        "INSERT INTO undo_action (user_id, token, payload) VALUES (?1, ?2, ?3)",
+        params![user_id, token, payload],
+    )?;
+
+    let id = conn.last_insert_rowid();
+    conn.query_row(
+        &format!("SELECT {} FROM undo_action WHERE id = ?1", SELECT_COLUMNS),
+        params![id],
+        row_to_action,
+    )
+    .map_err(AppError::Database)
+}
+
+/// Remove and return the user's action for `token` if it was recorded at or
+/// after `since`. An action can only be taken once.
+pub fn take(
+    conn: &Connection,
+    user_id: i64,
+    token: &str,
+    since: DateTime<Utc>,
+) -> AppResult<Option<UndoAction>> {
+    let action = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM undo_action WHERE token = ?1 AND user_id = ?2 AND created_at >= ?3",
+                SELECT_COLUMNS
+            ),
+            params![
+                token,
+                user_id,
+                since.format("%Y-%m-%d %H:%M:%S").to_string()
+            ],
+            row_to_action,
+        )
+        .optional()?;
+
+    if let Some(action) = &action {
+        conn.execute("DELETE FROM undo_action WHERE id = ?1", params![action.id])?;
+    }
+    Ok(action)
+}
+
+/// Drop actions recorded before `before`
+pub fn delete_expired(conn: &Connection, before: DateTime<Utc>) -> AppResult<i64> {
+    let rows = conn.execute(
+        "DELETE FROM undo_action WHERE created_at < ?1",
+        params![before.format("%Y-%m-%d %H:%M:%S").to_string()],
+    )?;
+    Ok(rows as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::{self, Role};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_take_once_within_window() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+        let other = user::create_user(&conn, "other", "hash", Role::User).unwrap();
+        let since = Utc::now() - chrono::Duration::minutes(5);
+
+        let action = create(&conn, user.id, "{}").unwrap();
+        assert!(take(&conn, other.id, &action.token, since)
+            .unwrap()
+            .is_none());
+
+        let taken = take(&conn, user.id, &action.token, since).unwrap().unwrap();
+        assert_eq!(taken.payload, "{}");
+        assert!(take(&conn, user.id, &action.token, since)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_expired_actions() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+        let action = create(&conn, user.id, "{}").unwrap();
+        conn.execute(
+            "UPDATE undo_action SET created_at = datetime('now', '-1 hour') WHERE id = ?1",
+            params![action.id],
+        )
+        .unwrap();
+
+        let since = Utc::now() - chrono::Duration::minutes(5);
+        assert!(take(&conn, user.id, &action.token, since)
+            .unwrap()
+            .is_none());
+        assert_eq!(delete_expired(&conn, since).unwrap(), 1);
+    }
+}
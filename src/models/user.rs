@@ -43,6 +43,11 @@ pub struct User {
     pub role: Role,
     pub disabled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    /// Set after repeated failed logins; password login is refused until then
+    pub locked_until: Option<DateTime<Utc>>,
+    /// Signed in with a temporary password that must be replaced before
+    /// anything else can be done
+    pub must_change_password: bool,
 }
 
 impl User {
@@ -53,6 +58,10 @@ impl User {
     pub fn is_admin(&self) -> bool {
         self.role == Role::Admin
     }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked_until.is_some_and(|until| Utc::now() < until)
+    }
 }
 
 fn parse_datetime(s: &str) -> DateTime<Utc> {
@@ -69,6 +78,7 @@ fn row_to_user(row: &rusqlite::Row) -> rusqlite::Result<User> {
     let role_str: String = row.get(3)?;
     let disabled_at: Option<String> = row.get(4)?;
     let created_at: String = row.get(5)?;
+    let locked_until: Option<String> = row.get(6)?;
 
     Ok(User {
         id: row.get(0)?,
@@ -77,6 +87,8 @@ fn row_to_user(row: &rusqlite::Row) -> rusqlite::Result<User> {
         role: Role::from_str(&role_str).unwrap_or(Role::User),
         disabled_at: disabled_at.map(|s| parse_datetime(&s)),
         created_at: parse_datetime(&created_at),
+        locked_until: locked_until.map(|s| parse_datetime(&s)),
+        must_change_password: row.get(7)?,
     })
 }
 
@@ -107,7 +119,7 @@ pub fn create_user(
 
 pub fn find_by_username(conn: &Connection, username: &str) -> AppResult<Option<User>> {
     conn.query_row(
-        "SELECT id, username, password_hash, role, disabled_at, created_at FROM user WHERE username = ?1",
+        "SELECT id, username, password_hash, role, disabled_at, created_at, locked_until, must_change_password FROM user WHERE username = ?1",
         params![username],
         row_to_user,
     )
@@ -117,7 +129,7 @@ pub fn find_by_username(conn: &Connection, username: &str) -> AppResult<Option<U
 
 pub fn find_by_id(conn: &Connection, id: i64) -> AppResult<Option<User>> {
     conn.query_row(
-        "SELECT id, username, password_hash, role, disabled_at, created_at FROM user WHERE id = ?1",
+        "SELECT id, username, password_hash, role, disabled_at, created_at, locked_until, must_change_password FROM user WHERE id = ?1",
         params![id],
         row_to_user,
     )
@@ -127,7 +139,7 @@ pub fn find_by_id(conn: &Connection, id: i64) -> AppResult<Option<User>> {
 
 pub fn list_all(conn: &Connection) -> AppResult<Vec<User>> {
     let mut stmt = conn.prepare(
-        "SELECT id, username, password_hash, role, disabled_at, created_at FROM user ORDER BY id",
+        "SELECT id, username, password_hash, role, disabled_at, created_at, locked_until, must_change_password FROM user ORDER BY id",
     )?;
 
     let users = stmt
@@ -138,9 +150,10 @@ pub fn list_all(conn: &Connection) -> AppResult<Vec<User>> {
     Ok(users)
 }
 
+/// Replace the password; this also satisfies a pending `must_change_password`
 pub fn update_password(conn: &Connection, user_id: i64, new_password_hash: &str) -> AppResult<()> {
     let rows = conn.execute(
-        "UPDATE user SET password_hash = ?1 WHERE id = ?2",
+        "UPDATE user SET password_hash = ?1, must_change_password = 0 WHERE id = ?2",
         params![new_password_hash, user_id],
     )?;
 
@@ -186,6 +199,35 @@ pub fn enable_user(conn: &Connection, user_id: i64) -> AppResult<()> {
     Ok(())
 }
 
+/// Require the user to pick a new password at the next sign-in
+pub fn require_password_change(conn: &Connection, user_id: i64) -> AppResult<()> {
+    conn.execute(
+        "UPDATE user SET must_change_password = 1 WHERE id = ?1",
+        params![user_id],
+    )?;
+    Ok(())
+}
+
+pub fn lock_user(conn: &Connection, user_id: i64, until: DateTime<Utc>) -> AppResult<()> {
+    conn.execute(
+        "UPDATE user SET locked_until = ?1 WHERE id = ?2",
+        params![until.format("%Y-%m-%d %H:%M:%S").to_string(), user_id],
+    )?;
+    Ok(())
+}
+
+pub fn unlock_user(conn: &Connection, user_id: i64) -> AppResult<()> {
+    let rows = conn.execute(
+        "UPDATE user SET locked_until = NULL WHERE id = ?1",
+        params![user_id],
+    )?;
+
+    if rows == 0 {
+        return Err(AppError::UserNotFound);
+    }
+    Ok(())
+}
+
 pub fn delete_user(conn: &Connection, user_id: i64) -> AppResult<()> {
     let rows = conn.execute("DELETE FROM user WHERE id = ?1", params![user_id])?;
 
@@ -200,6 +242,15 @@ pub fn count(conn: &Connection) -> AppResult<i64> {
         .map_err(AppError::Database)
 }
 
+pub fn count_admins(conn: &Connection) -> AppResult<i64> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM user WHERE role = ?1",
+        params![Role::Admin.as_str()],
+        |row| row.get(0),
+    )
+    .map_err(AppError::Database)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -264,6 +315,22 @@ mod tests {
         assert_eq!(admin.role, Role::Admin);
     }
 
+    #[test]
+    fn test_require_password_change() {
+        let conn = setup_db();
+
+        let user = create_user(&conn, "testuser", "hash123", Role::User).unwrap();
+        assert!(!user.must_change_password);
+
+        require_password_change(&conn, user.id).unwrap();
+        let user = find_by_id(&conn, user.id).unwrap().unwrap();
+        assert!(user.must_change_password);
+
+        update_password(&conn, user.id, "hash456").unwrap();
+        let user = find_by_id(&conn, user.id).unwrap().unwrap();
+        assert!(!user.must_change_password);
+    }
+
     #[test]
     fn test_delete_user() {
         let conn = setup_db();
@@ -301,4 +368,13 @@ mod tests {
         create_user(&conn, "user2", "hash2", Role::User).unwrap();
         assert_eq!(count(&conn).unwrap(), 2);
     }
+
+    #[test]
+    fn test_count_admins() {
+        let conn = setup_db();
+
+        create_user(&conn, "admin", "hash1", Role::Admin).unwrap();
+        create_user(&conn, "user", "hash2", Role::User).unwrap();
+        assert_eq!(count_admins(&conn).unwrap(), 1);
+    }
 }
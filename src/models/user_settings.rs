@@ -3,12 +3,20 @@ use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 
 use crate::error::{AppError, AppResult};
+use crate::models::category;
+use crate::services::sanitize::SanitizePolicy;
 use crate::services::save::SaveServicesConfig;
+use crate::timezone::{self, Tz};
 
 pub const DEFAULT_ENTRIES_PER_PAGE: i64 = 30;
 pub const MIN_ENTRIES_PER_PAGE: i64 = 10;
 pub const MAX_ENTRIES_PER_PAGE: i64 = 100;
 
+pub const DEFAULT_SCROLL_DWELL_MS: i64 = 1000;
+pub const MAX_SCROLL_DWELL_MS: i64 = 10_000;
+
+pub const MAX_UNREAD_COUNT_CAP: i64 = 99_999;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserSettings {
     pub id: i64,
@@ -165,6 +173,431 @@ pub fn update_theme(conn: &Connection, user_id: i64, theme: Option<String>) -> A
     Ok(())
 }
 
+/// Get UI language preference for a user, `None` to follow the browser
+pub fn get_locale(conn: &Connection, user_id: i64) -> AppResult<Option<String>> {
+    let locale = conn
+        .query_row(
+            "SELECT locale FROM user_settings WHERE user_id = ?1",
+            params![user_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(locale.flatten())
+}
+
+/// UI language preference of the user signed in with a session token
+pub fn find_locale_by_session(conn: &Connection, token: &str) -> AppResult<Option<String>> {
+    let locale = conn
+        .query_row(
+            r#"
+            SELECT us.locale FROM session s
+            INNER JOIN user_settings us ON us.user_id = s.user_id
+            WHERE s.session_token = ?1
+            "#,
+            params![token],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(locale.flatten())
+}
+
+/// Update UI language preference for a user
+pub fn update_locale(conn: &Connection, user_id: i64, locale: Option<&str>) -> AppResult<()> {
+    // First ensure user_settings row exists
+    conn.execute(
+        "INSERT INTO user_settings (user_id, entries_per_page) VALUES (?1, ?2)
+         ON CONFLICT(user_id) DO NOTHING",
+        params![user_id, DEFAULT_ENTRIES_PER_PAGE],
+    )?;
+
+    conn.execute(
+        "UPDATE user_settings SET locale = ?1, updated_at = datetime('now') WHERE user_id = ?2",
+        params![locale, user_id],
+    )?;
+
+    Ok(())
+}
+
+/// Get time zone preference for a user. Unknown stored names count as unset.
+pub fn get_timezone(conn: &Connection, user_id: i64) -> AppResult<Option<Tz>> {
+    let name: Option<String> = conn
+        .query_row(
+            "SELECT timezone FROM user_settings WHERE user_id = ?1",
+            params![user_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+    Ok(name.as_deref().and_then(timezone::parse))
+}
+
+/// Update time zone preference for a user
+pub fn update_timezone(conn: &Connection, user_id: i64, tz: Option<Tz>) -> AppResult<()> {
+    // First ensure user_settings row exists
+    conn.execute(
+        "INSERT INTO user_settings (user_id, entries_per_page) VALUES (?1, ?2)
+         ON CONFLICT(user_id) DO NOTHING",
+        params![user_id, DEFAULT_ENTRIES_PER_PAGE],
+    )?;
+
+    conn.execute(
+        "UPDATE user_settings SET timezone = ?1, updated_at = datetime('now') WHERE user_id = ?2",
+        params![tz.map(|tz| tz.name()), user_id],
+    )?;
+
+    Ok(())
+}
+
+/// Get the sanitizer policy for a user. A missing or unreadable policy is
+/// the default one.
+pub fn get_sanitize_policy(conn: &Connection, user_id: i64) -> AppResult<SanitizePolicy> {
+    let json: Option<String> = conn
+        .query_row(
+            "SELECT sanitize_policy FROM user_settings WHERE user_id = ?1",
+            params![user_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+
+    Ok(json
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+/// Update the sanitizer policy for a user, returning it normalized
+pub fn update_sanitize_policy(
+    conn: &Connection,
+    user_id: i64,
+    policy: SanitizePolicy,
+) -> AppResult<SanitizePolicy> {
+    let policy = policy.normalized()?;
+    let json = serde_json::to_string(&policy)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize sanitize_policy: {}", e)))?;
+
+    // First ensure user_settings row exists
+    conn.execute(
+        "INSERT INTO user_settings (user_id, entries_per_page) VALUES (?1, ?2)
+         ON CONFLICT(user_id) DO NOTHING",
+        params![user_id, DEFAULT_ENTRIES_PER_PAGE],
+    )?;
+
+    conn.execute(
+        "UPDATE user_settings SET sanitize_policy = ?1, updated_at = datetime('now') WHERE user_id = ?2",
+        params![json, user_id],
+    )?;
+
+    Ok(policy)
+}
+
+/// Mark-as-read-on-scroll preference
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ScrollReadSettings {
+    pub enabled: bool,
+    /// Minimum time an entry must have been visible before it counts as read
+    pub dwell_ms: i64,
+}
+
+impl Default for ScrollReadSettings {
+    fn default() -> Self {
+        ScrollReadSettings {
+            enabled: false,
+            dwell_ms: DEFAULT_SCROLL_DWELL_MS,
+        }
+    }
+}
+
+/// Get mark-as-read-on-scroll preference for a user
+pub fn get_scroll_read(conn: &Connection, user_id: i64) -> AppResult<ScrollReadSettings> {
+    let settings = conn
+        .query_row(
+            "SELECT mark_read_on_scroll, scroll_dwell_ms FROM user_settings WHERE user_id = ?1",
+            params![user_id],
+            |row| {
+                let enabled: i64 = row.get(0)?;
+                Ok(ScrollReadSettings {
+                    enabled: enabled != 0,
+                    dwell_ms: row.get(1)?,
+                })
+            },
+        )
+        .optional()?;
+
+    Ok(settings.unwrap_or_default())
+}
+
+/// Update mark-as-read-on-scroll preference for a user
+pub fn update_scroll_read(
+    conn: &Connection,
+    user_id: i64,
+    settings: ScrollReadSettings,
+) -> AppResult<()> {
+    if !(0..=MAX_SCROLL_DWELL_MS).contains(&settings.dwell_ms) {
+        return Err(AppError::Validation(format!(
+            "dwell_ms must be between 0 and {}",
+            MAX_SCROLL_DWELL_MS
+        )));
+    }
+
+    // First ensure user_settings row exists
+    conn.execute(
+        "INSERT INTO user_settings (user_id, entries_per_page) VALUES (?1, ?2)
+         ON CONFLICT(user_id) DO NOTHING",
+        params![user_id, DEFAULT_ENTRIES_PER_PAGE],
+    )?;
+
+    conn.execute(
+        "UPDATE user_settings SET mark_read_on_scroll = ?1, scroll_dwell_ms = ?2, updated_at = datetime('now') WHERE user_id = ?3",
+        params![settings.enabled as i64, settings.dwell_ms, user_id],
+    )?;
+
+    Ok(())
+}
+
+/// What happens when a feed changes an entry the user already has
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct RevisionSettings {
+    /// Mark entries unread again when their title or content changes
+    pub unread_on_update: bool,
+}
+
+/// Get the entry revision preference for a user
+pub fn get_revision_settings(conn: &Connection, user_id: i64) -> AppResult<RevisionSettings> {
+    let unread_on_update: Option<i64> = conn
+        .query_row(
+            "SELECT unread_on_update FROM user_settings WHERE user_id = ?1",
+            params![user_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(RevisionSettings {
+        unread_on_update: unread_on_update.unwrap_or(0) != 0,
+    })
+}
+
+/// Update the entry revision preference for a user
+pub fn update_revision_settings(
+    conn: &Connection,
+    user_id: i64,
+    settings: RevisionSettings,
+) -> AppResult<()> {
+    // First ensure user_settings row exists
+    conn.execute(
+        "INSERT INTO user_settings (user_id, entries_per_page) VALUES (?1, ?2)
+         ON CONFLICT(user_id) DO NOTHING",
+        params![user_id, DEFAULT_ENTRIES_PER_PAGE],
+    )?;
+
+    conn.execute(
+        "UPDATE user_settings SET unread_on_update = ?1, updated_at = datetime('now') WHERE user_id = ?2",
+        params![settings.unread_on_update as i64, user_id],
+    )?;
+
+    Ok(())
+}
+
+/// Whether new entries are tagged automatically
+pub fn get_auto_tag(conn: &Connection, user_id: i64) -> AppResult<bool> {
+    let auto_tag: Option<i64> = conn
+        .query_row(
+            "SELECT auto_tag FROM user_settings WHERE user_id = ?1",
+            params![user_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(auto_tag.unwrap_or(0) != 0)
+}
+
+/// Turn automatic tagging of new entries on or off
+pub fn update_auto_tag(conn: &Connection, user_id: i64, enabled: bool) -> AppResult<()> {
+    conn.execute(
+        "INSERT INTO user_settings (user_id, entries_per_page) VALUES (?1, ?2)
+         ON CONFLICT(user_id) DO NOTHING",
+        params![user_id, DEFAULT_ENTRIES_PER_PAGE],
+    )?;
+
+    conn.execute(
+        "UPDATE user_settings SET auto_tag = ?1, updated_at = datetime('now') WHERE user_id = ?2",
+        params![enabled as i64, user_id],
+    )?;
+
+    Ok(())
+}
+
+/// Whether starred entries are saved to the save services automatically
+pub fn get_auto_save_starred(conn: &Connection, user_id: i64) -> AppResult<bool> {
+    let enabled: Option<i64> = conn
+        .query_row(
+            "SELECT auto_save_starred FROM user_settings WHERE user_id = ?1",
+            params![user_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(enabled.unwrap_or(0) != 0)
+}
+
+/// Turn automatic saving of starred entries on or off
+pub fn update_auto_save_starred(conn: &Connection, user_id: i64, enabled: bool) -> AppResult<()> {
+    conn.execute(
+        "INSERT INTO user_settings (user_id, entries_per_page) VALUES (?1, ?2)
+         ON CONFLICT(user_id) DO NOTHING",
+        params![user_id, DEFAULT_ENTRIES_PER_PAGE],
+    )?;
+
+    conn.execute(
+        "UPDATE user_settings SET auto_save_starred = ?1, updated_at = datetime('now') WHERE user_id = ?2",
+        params![enabled as i64, user_id],
+    )?;
+
+    Ok(())
+}
+
+/// The page `/` shows
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StartPage {
+    #[default]
+    Unread,
+    All,
+    Starred,
+    /// The entries of `HomeSettings::start_category_id`
+    Category,
+}
+
+impl StartPage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StartPage::Unread => "unread",
+            StartPage::All => "all",
+            StartPage::Starred => "starred",
+            StartPage::Category => "category",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "all" => StartPage::All,
+            "starred" => StartPage::Starred,
+            "category" => StartPage::Category,
+            _ => StartPage::Unread,
+        }
+    }
+}
+
+/// Start page and unread count display preferences
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct HomeSettings {
+    #[serde(default)]
+    pub start_page: StartPage,
+    pub start_category_id: Option<i64>,
+    /// Unread counts above this are shown as `<cap>+`
+    pub unread_count_cap: Option<i64>,
+}
+
+impl HomeSettings {
+    /// Where `/` sends the user, or `None` to show the unread page
+    pub fn start_path(&self) -> Option<String> {
+        match (self.start_page, self.start_category_id) {
+            (StartPage::Unread, _) => None,
+            (StartPage::All, _) => Some("/entries".to_string()),
+            (StartPage::Starred, _) => Some("/entries/starred".to_string()),
+            (StartPage::Category, Some(id)) => Some(format!("/categories/{}/entries", id)),
+            (StartPage::Category, None) => None,
+        }
+    }
+
+    /// An unread count as shown, `<cap>+` past the cap
+    pub fn format_count(&self, count: i64) -> String {
+        match self.unread_count_cap {
+            Some(cap) if count > cap => format!("{}+", cap),
+            _ => count.to_string(),
+        }
+    }
+}
+
+/// Get the start page and unread count preferences for a user. A start
+/// category that has since been deleted falls back to the unread page.
+pub fn get_home_settings(conn: &Connection, user_id: i64) -> AppResult<HomeSettings> {
+    let row: Option<(Option<String>, Option<i64>, Option<i64>)> = conn
+        .query_row(
+            r#"
+            SELECT s.start_page, c.id, s.unread_count_cap
+            FROM user_settings s
+            LEFT JOIN category c
+              ON c.id = s.start_category_id AND c.user_id = s.user_id AND c.deleted_at IS NULL
+            WHERE s.user_id = ?1
+            "#,
+            params![user_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+
+    let Some((start_page, start_category_id, unread_count_cap)) = row else {
+        return Ok(HomeSettings::default());
+    };
+    let mut start_page = start_page
+        .as_deref()
+        .map(StartPage::parse)
+        .unwrap_or_default();
+    if start_page == StartPage::Category && start_category_id.is_none() {
+        start_page = StartPage::Unread;
+    }
+
+    Ok(HomeSettings {
+        start_page,
+        start_category_id: start_category_id.filter(|_| start_page == StartPage::Category),
+        unread_count_cap,
+    })
+}
+
+/// Update the start page and unread count preferences for a user
+pub fn update_home_settings(
+    conn: &Connection,
+    user_id: i64,
+    settings: HomeSettings,
+) -> AppResult<()> {
+    let start_category_id = match settings.start_page {
+        StartPage::Category => {
+            let id = settings.start_category_id.ok_or_else(|| {
+                AppError::Validation("Choose the category to start on".to_string())
+            })?;
+            category::find_by_id_and_user(conn, id, user_id)?.ok_or(AppError::CategoryNotFound)?;
+            Some(id)
+        }
+        _ => None,
+    };
+    if let Some(cap) = settings.unread_count_cap {
+        if !(1..=MAX_UNREAD_COUNT_CAP).contains(&cap) {
+            return Err(AppError::Validation(format!(
+                "Unread count limit must be between 1 and {}",
+                MAX_UNREAD_COUNT_CAP
+            )));
+        }
+    }
+
+    // First ensure user_settings row exists
+    conn.execute(
+        "INSERT INTO user_settings (user_id, entries_per_page) VALUES (?1, ?2)
+         ON CONFLICT(user_id) DO NOTHING",
+        params![user_id, DEFAULT_ENTRIES_PER_PAGE],
+    )?;
+
+    conn.execute(
+        "UPDATE user_settings SET start_page = ?1, start_category_id = ?2, unread_count_cap = ?3, updated_at = datetime('now') WHERE user_id = ?4",
+        params![
+            settings.start_page.as_str(),
+            start_category_id,
+            settings.unread_count_cap,
+            user_id
+        ],
+    )?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -261,6 +694,56 @@ mod tests {
         assert_eq!(theme, None);
     }
 
+    #[test]
+    fn test_update_and_get_locale() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+        assert_eq!(get_locale(&conn, user.id).unwrap(), None);
+
+        update_locale(&conn, user.id, Some("zh-TW")).unwrap();
+        assert_eq!(
+            get_locale(&conn, user.id).unwrap(),
+            Some("zh-TW".to_string())
+        );
+
+        update_locale(&conn, user.id, None).unwrap();
+        assert_eq!(get_locale(&conn, user.id).unwrap(), None);
+    }
+
+    #[test]
+    fn test_update_and_get_timezone() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+        assert_eq!(get_timezone(&conn, user.id).unwrap(), None);
+
+        update_timezone(&conn, user.id, Some(Tz::Asia__Taipei)).unwrap();
+        assert_eq!(
+            get_timezone(&conn, user.id).unwrap(),
+            Some(Tz::Asia__Taipei)
+        );
+
+        update_timezone(&conn, user.id, None).unwrap();
+        assert_eq!(get_timezone(&conn, user.id).unwrap(), None);
+    }
+
+    #[test]
+    fn test_find_locale_by_session() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+        let ttl = chrono::Duration::days(1);
+        let session =
+            crate::models::session::create_session(&conn, user.id, ttl, false, None).unwrap();
+
+        let token = &session.session_token;
+        assert_eq!(find_locale_by_session(&conn, token).unwrap(), None);
+        update_locale(&conn, user.id, Some("zh-TW")).unwrap();
+        assert_eq!(
+            find_locale_by_session(&conn, token).unwrap(),
+            Some("zh-TW".to_string())
+        );
+        assert_eq!(find_locale_by_session(&conn, "unknown").unwrap(), None);
+    }
+
     #[test]
     fn test_theme_with_existing_settings() {
         let conn = setup_db();
@@ -279,4 +762,148 @@ mod tests {
         assert_eq!(settings.entries_per_page, 50);
         assert_eq!(settings.theme, Some("dark".to_string()));
     }
+
+    #[test]
+    fn test_scroll_read_settings() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+
+        // Disabled by default
+        let settings = get_scroll_read(&conn, user.id).unwrap();
+        assert_eq!(settings, ScrollReadSettings::default());
+        assert!(!settings.enabled);
+
+        let new_settings = ScrollReadSettings {
+            enabled: true,
+            dwell_ms: 2500,
+        };
+        update_scroll_read(&conn, user.id, new_settings).unwrap();
+        assert_eq!(get_scroll_read(&conn, user.id).unwrap(), new_settings);
+
+        // Other settings are untouched
+        assert_eq!(
+            get_entries_per_page(&conn, user.id).unwrap(),
+            DEFAULT_ENTRIES_PER_PAGE
+        );
+
+        let result = update_scroll_read(
+            &conn,
+            user.id,
+            ScrollReadSettings {
+                enabled: true,
+                dwell_ms: MAX_SCROLL_DWELL_MS + 1,
+            },
+        );
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn test_sanitize_policy() {
+        use crate::services::sanitize::VideoEmbeds;
+
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+
+        assert_eq!(
+            get_sanitize_policy(&conn, user.id).unwrap(),
+            SanitizePolicy::default()
+        );
+
+        let policy = SanitizePolicy {
+            iframe_hosts: vec!["Player.Vimeo.com".to_string()],
+            keep_inline_styles: true,
+            proxy_images: false,
+            video_embeds: VideoEmbeds::Remove,
+        };
+        let saved = update_sanitize_policy(&conn, user.id, policy).unwrap();
+        assert_eq!(saved.iframe_hosts, vec!["player.vimeo.com"]);
+        assert_eq!(get_sanitize_policy(&conn, user.id).unwrap(), saved);
+
+        let invalid = SanitizePolicy {
+            iframe_hosts: vec!["not a host".to_string()],
+            ..SanitizePolicy::default()
+        };
+        assert!(update_sanitize_policy(&conn, user.id, invalid).is_err());
+        assert_eq!(get_sanitize_policy(&conn, user.id).unwrap(), saved);
+    }
+
+    #[test]
+    fn test_revision_settings() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+
+        assert!(
+            !get_revision_settings(&conn, user.id)
+                .unwrap()
+                .unread_on_update
+        );
+
+        let settings = RevisionSettings {
+            unread_on_update: true,
+        };
+        update_revision_settings(&conn, user.id, settings).unwrap();
+        assert_eq!(get_revision_settings(&conn, user.id).unwrap(), settings);
+    }
+
+    #[test]
+    fn test_auto_tag() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+
+        assert!(!get_auto_tag(&conn, user.id).unwrap());
+        update_auto_tag(&conn, user.id, true).unwrap();
+        assert!(get_auto_tag(&conn, user.id).unwrap());
+    }
+
+    #[test]
+    fn test_auto_save_starred() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+
+        assert!(!get_auto_save_starred(&conn, user.id).unwrap());
+        update_auto_save_starred(&conn, user.id, true).unwrap();
+        assert!(get_auto_save_starred(&conn, user.id).unwrap());
+    }
+
+    #[test]
+    fn test_home_settings() {
+        let conn = setup_db();
+        let user = user::create_user(&conn, "testuser", "hash", Role::User).unwrap();
+        let other = user::create_user(&conn, "other", "hash", Role::User).unwrap();
+        let news = category::create_category(&conn, user.id, "News").unwrap();
+
+        let home = get_home_settings(&conn, user.id).unwrap();
+        assert_eq!(home, HomeSettings::default());
+        assert_eq!(home.start_path(), None);
+        assert_eq!(home.format_count(1500), "1500");
+
+        let settings = HomeSettings {
+            start_page: StartPage::Category,
+            start_category_id: Some(news.id),
+            unread_count_cap: Some(999),
+        };
+        update_home_settings(&conn, user.id, settings).unwrap();
+        let home = get_home_settings(&conn, user.id).unwrap();
+        assert_eq!(home, settings);
+        assert_eq!(
+            home.start_path(),
+            Some(format!("/categories/{}/entries", news.id))
+        );
+        assert_eq!(home.format_count(1500), "999+");
+        assert_eq!(home.format_count(999), "999");
+
+        // Someone else's category, or none, cannot be the start page
+        assert!(update_home_settings(&conn, other.id, settings).is_err());
+        let no_category = HomeSettings {
+            start_category_id: None,
+            ..settings
+        };
+        assert!(update_home_settings(&conn, user.id, no_category).is_err());
+
+        // Deleting the category falls back to the unread page
+        category::delete_category(&conn, news.id, user.id).unwrap();
+        let home = get_home_settings(&conn, user.id).unwrap();
+        assert_eq!(home.start_page, StartPage::Unread);
+        assert_eq!(home.start_path(), None);
+    }
 }
@@ -9,6 +9,8 @@ const CHALLENGE_EXPIRY_MINUTES: i64 = 5;
 pub enum ChallengeType {
     Registration,
     Authentication,
+    /// Usernameless sign-in offered through the browser's autofill
+    DiscoverableAuthentication,
 }
 
 impl ChallengeType {
@@ -16,6 +18,7 @@ impl ChallengeType {
         match self {
             ChallengeType::Registration => "registration",
             ChallengeType::Authentication => "authentication",
+            ChallengeType::DiscoverableAuthentication => "discoverable_authentication",
         }
     }
 
@@ -23,6 +26,7 @@ impl ChallengeType {
         match s {
             "registration" => Some(ChallengeType::Registration),
             "authentication" => Some(ChallengeType::Authentication),
+            "discoverable_authentication" => Some(ChallengeType::DiscoverableAuthentication),
             _ => None,
         }
     }
@@ -125,6 +129,30 @@ pub fn find_and_delete_challenge(
     Ok(challenge)
 }
 
+/// Find and consume the unexpired challenge with the given bytes, as echoed
+/// back by the browser in the credential's client data
+pub fn find_and_delete_by_challenge(
+    conn: &Connection,
+    challenge: &[u8],
+) -> AppResult<WebauthnChallenge> {
+    let found = conn
+        .query_row(
+            "SELECT id, challenge, user_id, challenge_type, state_data, created_at, expires_at \
+             FROM webauthn_challenge WHERE challenge = ?1 AND expires_at > datetime('now')",
+            params![challenge],
+            row_to_challenge,
+        )
+        .optional()?
+        .ok_or(AppError::ChallengeNotFound)?;
+
+    conn.execute(
+        "DELETE FROM webauthn_challenge WHERE id = ?1",
+        params![found.id],
+    )?;
+
+    Ok(found)
+}
+
 pub fn cleanup_expired(conn: &Connection) -> AppResult<usize> {
     let deleted = conn.execute(
         "DELETE FROM webauthn_challenge WHERE expires_at < datetime('now')",
@@ -211,6 +239,33 @@ mod tests {
         assert!(found.user_id.is_none());
     }
 
+    #[test]
+    fn test_find_and_delete_by_challenge() {
+        let conn = setup_db();
+
+        create_challenge(&conn, &[1, 1, 1], None, ChallengeType::Authentication, "{}").unwrap();
+        create_challenge(
+            &conn,
+            &[2, 2, 2],
+            None,
+            ChallengeType::DiscoverableAuthentication,
+            "{}",
+        )
+        .unwrap();
+
+        // Concurrent sign-ins each get their own challenge back
+        let found = find_and_delete_by_challenge(&conn, &[1, 1, 1]).unwrap();
+        assert_eq!(found.challenge_type, ChallengeType::Authentication);
+        let found = find_and_delete_by_challenge(&conn, &[2, 2, 2]).unwrap();
+        assert_eq!(
+            found.challenge_type,
+            ChallengeType::DiscoverableAuthentication
+        );
+
+        let result = find_and_delete_by_challenge(&conn, &[1, 1, 1]);
+        assert!(matches!(result, Err(AppError::ChallengeNotFound)));
+    }
+
     #[test]
     fn test_challenge_type_conversion() {
         assert_eq!(ChallengeType::Registration.as_str(), "registration");
@@ -223,6 +278,10 @@ mod tests {
             ChallengeType::from_str("authentication"),
             Some(ChallengeType::Authentication)
         );
+        assert_eq!(
+            ChallengeType::from_str("discoverable_authentication"),
+            Some(ChallengeType::DiscoverableAuthentication)
+        );
         assert_eq!(ChallengeType::from_str("invalid"), None);
     }
 
@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::heartbeat::{Heartbeat, HEARTBEAT_INTERVAL};
+use crate::db::DbPool;
+use crate::models::auto_read;
+
+/// How often auto-mark-read rules are applied
+const AUTO_READ_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Start the worker that applies the feed and category auto-mark-read rules
+/// every hour
+///
+/// # Arguments
+/// * `db` - Database connection
+/// * `heartbeat` - Reported to while the worker is running
+/// * `cancel_token` - Token to signal graceful shutdown
+pub fn start_auto_read_worker(
+    db: DbPool,
+    heartbeat: Heartbeat,
+    cancel_token: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        tracing::info!("Auto-mark-read worker started");
+
+        let mut interval = tokio::time::interval(AUTO_READ_INTERVAL);
+        let mut beat = tokio::time::interval(HEARTBEAT_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    tracing::info!("Auto-mark-read worker stopping...");
+                    break;
+                }
+                _ = beat.tick() => {
+                    heartbeat.beat();
+                }
+                _ = interval.tick() => {
+                    match db.background(auto_read::apply_rules).await {
+                        Ok(Ok(count)) if count > 0 => {
+                            tracing::info!("Auto-marked {} entries as read", count);
+                        }
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) => tracing::error!("Failed to apply auto-read rules: {}", e),
+                        Err(e) => tracing::error!("Failed to access DB for auto-mark-read: {}", e),
+                    }
+                }
+            }
+        }
+
+        tracing::info!("Auto-mark-read worker stopped");
+    })
+}
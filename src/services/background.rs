@@ -1,20 +1,40 @@
+use std::sync::Arc;
+
 use chrono::Utc;
+use tokio::sync::watch;
 use tokio::task::JoinHandle;
 use tokio::time::{interval, Duration};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info};
 
 use super::feed_sync;
+use super::heartbeat::Heartbeat;
+use super::instance_settings::InstanceSettings;
+use super::notifier;
+use super::refresh_registry::RefreshRegistry;
+use super::web_push::{self, VapidKeys};
 use crate::db::DbPool;
 
 pub fn start_background_sync(
     db: DbPool,
-    user_agent: String,
+    settings: watch::Receiver<Arc<InstanceSettings>>,
+    cookie_secret: Option<Vec<u8>>,
+    vapid: Option<Arc<VapidKeys>>,
+    refreshes: RefreshRegistry,
+    heartbeat: Heartbeat,
     cancel_token: CancellationToken,
 ) -> JoinHandle<()> {
     tokio::spawn(async move {
         info!("Background sync task started");
 
+        // One client carries every push this task sends
+        let push_client = match vapid {
+            Some(_) => web_push::push_client()
+                .map_err(|e| error!("Failed to build push client: {}", e))
+                .ok(),
+            None => None,
+        };
+
         let mut ticker = interval(Duration::from_secs(60));
 
         loop {
@@ -24,12 +44,24 @@ pub fn start_background_sync(
                     break;
                 }
                 _ = ticker.tick() => {
+                    heartbeat.beat();
                     let now = Utc::now();
+                    let current = settings.borrow().clone();
+                    if !current.syncs_in_hour(now) {
+                        continue;
+                    }
                     let bucket = (now.timestamp() / 60 % 60) as u8;
 
                     debug!("Running background sync for bucket {}", bucket);
 
-                    let results = feed_sync::refresh_bucket(db.clone(), bucket, &user_agent).await;
+                    let results = feed_sync::refresh_bucket(
+                        db.clone(),
+                        bucket,
+                        &current.user_agent,
+                        cookie_secret.as_deref(),
+                        &refreshes,
+                    )
+                    .await;
 
                     let success_count = results.iter().filter(|(_, r)| r.is_ok()).count();
                     let fail_count = results.iter().filter(|(_, r)| r.is_err()).count();
@@ -46,6 +78,46 @@ pub fn start_background_sync(
                             error!("Background sync feed {} failed: {}", feed_id, e);
                         }
                     }
+
+                    // Pushes go out on their own task so a slow push service
+                    // never holds up the next sync
+                    let db = db.clone();
+                    let push = push_client.clone().zip(vapid.clone());
+                    tokio::spawn(async move {
+                        let push = push.as_ref().map(|(client, vapid)| (client, vapid.as_ref()));
+
+                        // Tell owners about feeds this run put into quarantine
+                        let failed: Vec<i64> = results
+                            .iter()
+                            .filter(|(_, r)| r.is_err())
+                            .map(|(id, _)| *id)
+                            .collect();
+                        if !failed.is_empty() {
+                            let since = now - chrono::Duration::seconds(1);
+                            notifier::notify_quarantined(&db, push, failed, since).await;
+                        }
+
+                        // Push notifications for new entries matching user rules
+                        let Some((client, vapid)) = push else {
+                            return;
+                        };
+                        for (feed_id, result) in results {
+                            if let Ok(sync) = result {
+                                let sent = notifier::notify_new_entries(
+                                    &db,
+                                    client,
+                                    vapid,
+                                    feed_id,
+                                    sync.new_entry_ids,
+                                    sync.first_fetch,
+                                )
+                                .await;
+                                if sent > 0 {
+                                    debug!("Sent {} notifications for feed {}", sent, feed_id);
+                                }
+                            }
+                        }
+                    });
                 }
             }
         }
@@ -58,6 +130,7 @@ pub fn start_background_sync(
 mod tests {
     use super::*;
     use crate::db::init_db;
+    use crate::services::instance_settings::LiveSettings;
     use rusqlite::Connection;
 
     fn setup_db_pool() -> DbPool {
@@ -72,7 +145,15 @@ mod tests {
         let db = setup_db_pool();
         let cancel_token = CancellationToken::new();
 
-        let handle = start_background_sync(db, "Test-Agent/1.0".to_string(), cancel_token.clone());
+        let handle = start_background_sync(
+            db,
+            LiveSettings::new(InstanceSettings::default()).subscribe(),
+            None,
+            None,
+            RefreshRegistry::default(),
+            Heartbeat::detached("background_sync"),
+            cancel_token.clone(),
+        );
 
         // Cancel immediately
         cancel_token.cancel();
@@ -90,7 +171,15 @@ mod tests {
         let db = setup_db_pool();
         let cancel_token = CancellationToken::new();
 
-        let handle = start_background_sync(db, "Test-Agent/1.0".to_string(), cancel_token.clone());
+        let handle = start_background_sync(
+            db,
+            LiveSettings::new(InstanceSettings::default()).subscribe(),
+            None,
+            None,
+            RefreshRegistry::default(),
+            Heartbeat::detached("background_sync"),
+            cancel_token.clone(),
+        );
 
         // Give worker time to run one tick (interval starts immediately with first tick)
         tokio::time::sleep(std::time::Duration::from_millis(100)).await;
@@ -0,0 +1,849 @@
+//! Full-state JSON backup and restore.
+//!
+//! A backup is a single JSON object. Export writes it incrementally, paging
+//! entries out of the database, and import parses it with a streaming visitor
+//! that writes entries in batches, so neither side holds the whole archive in
+//! memory. `format` and `version` must come first and `feeds` must precede
+//! `entries`, which is the order the exporter produces.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::{BufReader, Read};
+
+use axum::body::Body;
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, StreamExt};
+use futures_util::TryStreamExt;
+use rusqlite::Connection;
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use tokio::io::{AsyncWrite, AsyncWriteExt, DuplexStream};
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+use tokio_util::io::{ReaderStream, StreamReader, SyncIoBridge};
+use tracing::warn;
+
+use crate::db::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::i18n::Locale;
+use crate::models::entry_note;
+use crate::models::feed::DateStrategy;
+use crate::models::highlight::{self, PositionSelector};
+use crate::models::{
+    category, category_digest, entry, entry_summary, feed, notification_rule, passkey,
+    push_subscription, user, user_settings,
+};
+use crate::services::sanitize::SanitizePolicy;
+use crate::services::save::SaveServicesConfig;
+use crate::timezone;
+
+pub const BACKUP_FORMAT: &str = "rdrs-backup";
+pub const BACKUP_VERSION: u32 = 1;
+
+pub const PERSONAL_DATA_FORMAT: &str = "rdrs-personal-data";
+pub const PERSONAL_DATA_VERSION: u32 = 1;
+
+/// Entries read from the database per export page
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Entries written to the database per import transaction
+const IMPORT_BATCH_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSettings {
+    pub entries_per_page: i64,
+    pub theme: Option<String>,
+    pub save_services: Option<String>,
+    #[serde(default)]
+    pub locale: Option<String>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub sanitize_policy: SanitizePolicy,
+    #[serde(default)]
+    pub mark_read_on_scroll: bool,
+    #[serde(default = "default_scroll_dwell_ms")]
+    pub scroll_dwell_ms: i64,
+}
+
+fn default_scroll_dwell_ms() -> i64 {
+    user_settings::DEFAULT_SCROLL_DWELL_MS
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupCategory {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFeed {
+    pub id: i64,
+    pub category_id: i64,
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub site_url: Option<String>,
+    pub custom_user_agent: Option<String>,
+    #[serde(default)]
+    pub http2_disabled: bool,
+    #[serde(default)]
+    pub date_strategy: DateStrategy,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom_headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub synthetic: bool,
+}
+
+impl From<feed::Feed> for BackupFeed {
+    fn from(f: feed::Feed) -> Self {
+        BackupFeed {
+            id: f.id,
+            category_id: f.category_id,
+            url: f.url,
+            title: f.title,
+            description: f.description,
+            site_url: f.site_url,
+            custom_user_agent: f.custom_user_agent,
+            http2_disabled: f.http2_disabled,
+            date_strategy: f.date_strategy,
+            custom_headers: f.custom_headers,
+            synthetic: f.synthetic,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupEntry {
+    pub feed_id: i64,
+    pub guid: String,
+    pub title: Option<String>,
+    pub link: Option<String>,
+    pub content: Option<String>,
+    pub summary: Option<String>,
+    pub author: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub read_at: Option<DateTime<Utc>>,
+    pub starred_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<BackupHighlight>,
+    /// The user's Markdown note on the entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupHighlight {
+    pub quote: String,
+    #[serde(default)]
+    pub selector: Option<PositionSelector>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl From<highlight::Highlight> for BackupHighlight {
+    fn from(h: highlight::Highlight) -> Self {
+        BackupHighlight {
+            quote: h.quote,
+            selector: h.selector,
+            note: h.note,
+        }
+    }
+}
+
+impl From<entry::Entry> for BackupEntry {
+    fn from(e: entry::Entry) -> Self {
+        BackupEntry {
+            feed_id: e.feed_id,
+            guid: e.guid,
+            title: e.title,
+            link: e.link,
+            content: e.content,
+            summary: e.summary,
+            author: e.author,
+            published_at: e.published_at,
+            read_at: e.read_at,
+            starred_at: e.starred_at,
+            highlights: Vec::new(),
+            note: None,
+        }
+    }
+}
+
+/// Everything in a backup except entries
+#[derive(Debug, Serialize)]
+struct BackupHeader {
+    format: &'static str,
+    version: u32,
+    exported_at: DateTime<Utc>,
+    settings: BackupSettings,
+    categories: Vec<BackupCategory>,
+    feeds: Vec<BackupFeed>,
+}
+
+/// Account data that is not part of a restorable backup. The personal data
+/// export writes this followed by the full backup under `backup`.
+#[derive(Debug, Serialize)]
+struct PersonalDataHeader {
+    format: &'static str,
+    version: u32,
+    exported_at: DateTime<Utc>,
+    account: user::User,
+    passkeys: Vec<passkey::Passkey>,
+    push_subscriptions: Vec<push_subscription::PushSubscription>,
+    notification_rules: Vec<notification_rule::NotificationRule>,
+    entry_summaries: Vec<entry_summary::EntrySummary>,
+    category_digests: Vec<category_digest::CategoryDigest>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportStats {
+    pub categories_created: i64,
+    pub feeds_created: i64,
+    pub feeds_existing: i64,
+    pub entries_created: i64,
+    pub entries_merged: i64,
+    pub entries_skipped: i64,
+    pub highlights_created: i64,
+    pub notes_created: i64,
+}
+
+fn internal(e: impl fmt::Display) -> AppError {
+    AppError::Internal(e.to_string())
+}
+
+fn load_header(conn: &Connection, user_id: i64) -> AppResult<BackupHeader> {
+    let stored = user_settings::find_by_user_id(conn, user_id)?;
+    let scroll_read = user_settings::get_scroll_read(conn, user_id)?;
+    let settings = BackupSettings {
+        entries_per_page: stored
+            .as_ref()
+            .map(|s| s.entries_per_page)
+            .unwrap_or(user_settings::DEFAULT_ENTRIES_PER_PAGE),
+        theme: stored.as_ref().and_then(|s| s.theme.clone()),
+        save_services: stored.and_then(|s| s.save_services),
+        locale: user_settings::get_locale(conn, user_id)?,
+        timezone: user_settings::get_timezone(conn, user_id)?.map(|tz| tz.name().to_string()),
+        sanitize_policy: user_settings::get_sanitize_policy(conn, user_id)?,
+        mark_read_on_scroll: scroll_read.enabled,
+        scroll_dwell_ms: scroll_read.dwell_ms,
+    };
+
+    let categories = category::list_by_user(conn, user_id)?
+        .into_iter()
+        .map(|c| BackupCategory {
+            id: c.id,
+            name: c.name,
+        })
+        .collect();
+
+    let feeds = feed::list_by_user(conn, user_id)?
+        .into_iter()
+        .map(BackupFeed::from)
+        .collect();
+
+    Ok(BackupHeader {
+        format: BACKUP_FORMAT,
+        version: BACKUP_VERSION,
+        exported_at: Utc::now(),
+        settings,
+        categories,
+        feeds,
+    })
+}
+
+/// Build a streaming response body containing the user's full backup.
+/// Categories, feeds and settings are loaded up front so that failures surface
+/// as an error response; entries are then paged out in the background.
+pub async fn export_body(db: DbPool, user_id: i64) -> AppResult<Body> {
+    let header = db.read(move |conn| load_header(conn, user_id)).await??;
+
+    let (writer, reader) = tokio::io::duplex(64 * 1024);
+    let task = tokio::spawn(write_backup(db, user_id, header, writer));
+    Ok(streamed_body(reader, task, "Full export", user_id))
+}
+
+/// Body streaming what a background writer produces. The status is sent by
+/// the time the entries are read, so a failure ends the body with an error,
+/// which aborts the response rather than leave truncated JSON looking like a
+/// complete export.
+fn streamed_body(
+    reader: DuplexStream,
+    task: JoinHandle<AppResult<()>>,
+    what: &'static str,
+    user_id: i64,
+) -> Body {
+    let outcome = stream::once(async move {
+        let error = match task.await {
+            Ok(Ok(())) => return None,
+            Ok(Err(e)) => e.to_string(),
+            Err(e) => e.to_string(),
+        };
+        warn!("{} for user {} failed: {}", what, user_id, error);
+        Some(Err(std::io::Error::other(error)))
+    })
+    .filter_map(std::future::ready);
+
+    Body::from_stream(ReaderStream::new(reader).chain(outcome))
+}
+
+fn load_personal_data_header(conn: &Connection, user_id: i64) -> AppResult<PersonalDataHeader> {
+    Ok(PersonalDataHeader {
+        format: PERSONAL_DATA_FORMAT,
+        version: PERSONAL_DATA_VERSION,
+        exported_at: Utc::now(),
+        account: user::find_by_id(conn, user_id)?.ok_or(AppError::UserNotFound)?,
+        passkeys: passkey::list_by_user(conn, user_id)?,
+        push_subscriptions: push_subscription::list_by_user(conn, user_id)?,
+        notification_rules: notification_rule::list_by_user(conn, user_id)?,
+        entry_summaries: entry_summary::list_by_user(conn, user_id)?,
+        category_digests: category_digest::list_by_user(conn, user_id)?,
+    })
+}
+
+/// Build a streaming response body with everything stored for the user:
+/// account details, passkeys, push subscriptions, notification rules and
+/// summaries, with the full backup embedded under `backup`. Secrets such as
+/// the password hash and passkey public keys are left out.
+pub async fn export_personal_data_body(db: DbPool, user_id: i64) -> AppResult<Body> {
+    let (personal, header) = db
+        .read(move |conn| {
+            let personal = load_personal_data_header(conn, user_id)?;
+            let header = load_header(conn, user_id)?;
+            Ok::<_, AppError>((personal, header))
+        })
+        .await??;
+
+    let (writer, reader) = tokio::io::duplex(64 * 1024);
+    let task = tokio::spawn(write_personal_data(db, user_id, personal, header, writer));
+    Ok(streamed_body(reader, task, "Personal data export", user_id))
+}
+
+async fn write_personal_data<W>(
+    db: DbPool,
+    user_id: i64,
+    personal: PersonalDataHeader,
+    header: BackupHeader,
+    mut out: W,
+) -> AppResult<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = serde_json::to_vec(&personal).map_err(internal)?;
+    buf.pop();
+    buf.extend_from_slice(b",\"backup\":");
+    out.write_all(&buf).await.map_err(internal)?;
+
+    write_backup_object(&db, user_id, header, &mut out).await?;
+    out.write_all(b"}").await.map_err(internal)?;
+    out.shutdown().await.map_err(internal)?;
+    Ok(())
+}
+
+async fn write_backup<W>(
+    db: DbPool,
+    user_id: i64,
+    header: BackupHeader,
+    mut out: W,
+) -> AppResult<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    write_backup_object(&db, user_id, header, &mut out).await?;
+    out.shutdown().await.map_err(internal)?;
+    Ok(())
+}
+
+/// Write the backup JSON object without closing the writer, so it can be
+/// embedded in a larger document
+async fn write_backup_object<W>(
+    db: &DbPool,
+    user_id: i64,
+    header: BackupHeader,
+    out: &mut W,
+) -> AppResult<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = serde_json::to_vec(&header).map_err(internal)?;
+    // Reopen the header object to append the entries array
+    buf.pop();
+    buf.extend_from_slice(b",\"entries\":[");
+    out.write_all(&buf).await.map_err(internal)?;
+
+    let mut after_id = 0;
+    let mut first = true;
+    loop {
+        let (entries, highlights, notes) = db
+            .read(move |conn| {
+                let entries =
+                    entry::list_by_user_after_id(conn, user_id, after_id, EXPORT_PAGE_SIZE)?;
+                let (highlights, notes) = match entries.last() {
+                    Some(last) => (
+                        highlight::list_by_entry_range(conn, user_id, after_id, last.id)?,
+                        entry_note::list_by_entry_range(conn, user_id, after_id, last.id)?,
+                    ),
+                    None => (Vec::new(), Vec::new()),
+                };
+                Ok::<_, AppError>((entries, highlights, notes))
+            })
+            .await??;
+        let count = entries.len() as i64;
+        let Some(last) = entries.last() else {
+            break;
+        };
+        after_id = last.id;
+
+        let mut highlights_by_entry: HashMap<i64, Vec<BackupHighlight>> = HashMap::new();
+        for h in highlights {
+            highlights_by_entry
+                .entry(h.entry_id)
+                .or_default()
+                .push(h.into());
+        }
+        let mut notes_by_entry: HashMap<i64, String> =
+            notes.into_iter().map(|n| (n.entry_id, n.content)).collect();
+
+        buf.clear();
+        for e in entries {
+            if !first {
+                buf.push(b',');
+            }
+            first = false;
+            let highlights = highlights_by_entry.remove(&e.id).unwrap_or_default();
+            let note = notes_by_entry.remove(&e.id);
+            let backup_entry = BackupEntry {
+                highlights,
+                note,
+                ..BackupEntry::from(e)
+            };
+            serde_json::to_writer(&mut buf, &backup_entry).map_err(internal)?;
+        }
+        out.write_all(&buf).await.map_err(internal)?;
+
+        if count < EXPORT_PAGE_SIZE {
+            break;
+        }
+    }
+
+    out.write_all(b"]}").await.map_err(internal)?;
+    Ok(())
+}
+
+/// Recreate a feed with its settings in a local category, or find the one
+/// already subscribed there. Returns the local feed id and whether it was
+/// created.
+fn restore_feed(conn: &Connection, category_id: i64, f: &BackupFeed) -> AppResult<(i64, bool)> {
+    if let Some(found) = feed::find_by_url_and_category(conn, &f.url, category_id)? {
+        return Ok((found.id, false));
+    }
+
+    if f.synthetic {
+        let title = f.title.as_deref().unwrap_or(&f.url);
+        let created = feed::create_synthetic_feed(conn, category_id, &f.url, title)?;
+        return Ok((created.id, true));
+    }
+
+    let new_feed = feed::create_feed(
+        conn,
+        category_id,
+        &f.url,
+        f.title.as_deref(),
+        f.description.as_deref(),
+        f.site_url.as_deref(),
+        f.custom_user_agent.as_deref(),
+        Some(f.http2_disabled),
+    )?;
+    if f.date_strategy != new_feed.date_strategy {
+        feed::set_date_strategy(conn, new_feed.id, f.date_strategy)?;
+    }
+    if !f.custom_headers.is_empty() {
+        feed::set_custom_headers(conn, new_feed.id, &f.custom_headers)?;
+    }
+    Ok((new_feed.id, true))
+}
+
+/// Restore a backup streamed in a request body into the user's account.
+/// Existing categories and feeds are matched by name and URL; existing entries
+/// only have their read and star state merged in.
+pub async fn import_body(db: DbPool, user_id: i64, body: Body) -> AppResult<ImportStats> {
+    let stream = body.into_data_stream().map_err(std::io::Error::other);
+    let reader = SyncIoBridge::new(StreamReader::new(stream));
+    let handle = Handle::current();
+
+    tokio::task::spawn_blocking(move || import_reader(handle, db, user_id, reader))
+        .await
+        .map_err(internal)?
+}
+
+/// Restore a backup from a blocking reader. Must run outside the async
+/// runtime, since database calls are driven through `handle`.
+pub fn import_reader<R: Read>(
+    handle: Handle,
+    db: DbPool,
+    user_id: i64,
+    reader: R,
+) -> AppResult<ImportStats> {
+    let mut importer = Importer {
+        handle,
+        db,
+        user_id,
+        categories: HashMap::new(),
+        feeds: HashMap::new(),
+        pending: Vec::new(),
+        stats: ImportStats::default(),
+        error: None,
+    };
+
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+    let result = BackupSeed {
+        importer: &mut importer,
+    }
+    .deserialize(&mut deserializer)
+    .and_then(|_| deserializer.end());
+
+    // Database errors raised inside the visitor take precedence over the
+    // generic parse error they were wrapped in
+    if let Some(e) = importer.error.take() {
+        return Err(e);
+    }
+    result.map_err(|e| AppError::Validation(format!("Invalid backup: {}", e)))?;
+
+    Ok(importer.stats)
+}
+
+struct Importer {
+    handle: Handle,
+    db: DbPool,
+    user_id: i64,
+    /// Backup category id -> local category id
+    categories: HashMap<i64, i64>,
+    /// Backup feed id -> local feed id
+    feeds: HashMap<i64, i64>,
+    pending: Vec<BackupEntry>,
+    stats: ImportStats,
+    error: Option<AppError>,
+}
+
+impl Importer {
+    fn run<F, T>(&self, f: F) -> AppResult<T>
+    where
+        F: FnOnce(&Connection) -> AppResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.handle.block_on(self.db.background(f))?
+    }
+
+    /// Keep the application error and hand the deserializer a generic one
+    fn check<T, E: de::Error>(&mut self, result: AppResult<T>) -> Result<T, E> {
+        result.map_err(|e| {
+            let message = e.to_string();
+            self.error = Some(e);
+            E::custom(message)
+        })
+    }
+
+    fn restore_settings(&mut self, settings: BackupSettings) -> AppResult<()> {
+        let user_id = self.user_id;
+        self.run(move |conn| {
+            let entries_per_page = settings.entries_per_page.clamp(
+                user_settings::MIN_ENTRIES_PER_PAGE,
+                user_settings::MAX_ENTRIES_PER_PAGE,
+            );
+            user_settings::upsert(conn, user_id, entries_per_page)?;
+
+            let theme = settings
+                .theme
+                .filter(|t| t.as_str() == "dark" || t.as_str() == "light");
+            user_settings::update_theme(conn, user_id, theme)?;
+
+            let locale = settings.locale.filter(|l| Locale::parse(l).is_some());
+            user_settings::update_locale(conn, user_id, locale.as_deref())?;
+
+            let tz = settings.timezone.as_deref().and_then(timezone::parse);
+            user_settings::update_timezone(conn, user_id, tz)?;
+
+            let policy = settings.sanitize_policy.normalized().unwrap_or_default();
+            user_settings::update_sanitize_policy(conn, user_id, policy)?;
+
+            if let Some(config) = settings
+                .save_services
+                .as_deref()
+                .and_then(|json| SaveServicesConfig::from_json(json).ok())
+            {
+                user_settings::update_save_services(conn, user_id, &config)?;
+            }
+
+            user_settings::update_scroll_read(
+                conn,
+                user_id,
+                user_settings::ScrollReadSettings {
+                    enabled: settings.mark_read_on_scroll,
+                    dwell_ms: settings
+                        .scroll_dwell_ms
+                        .clamp(0, user_settings::MAX_SCROLL_DWELL_MS),
+                },
+            )
+        })
+    }
+
+    fn restore_categories(&mut self, categories: Vec<BackupCategory>) -> AppResult<()> {
+        let user_id = self.user_id;
+        let (mapping, created) = self.run(move |conn| {
+            let mut mapping = Vec::with_capacity(categories.len());
+            let mut created = 0;
+            for cat in categories {
+                let local = match category::find_by_name_and_user(conn, &cat.name, user_id)? {
+                    Some(existing) => existing,
+                    None => {
+                        created += 1;
+                        category::create_category(conn, user_id, &cat.name)?
+                    }
+                };
+                mapping.push((cat.id, local.id));
+            }
+            Ok((mapping, created))
+        })?;
+
+        self.categories.extend(mapping);
+        self.stats.categories_created += created;
+        Ok(())
+    }
+
+    fn restore_feeds(&mut self, feeds: Vec<BackupFeed>) -> AppResult<()> {
+        let categories = self.categories.clone();
+        let (mapping, created, existing) = self.run(move |conn| {
+            let mut mapping = Vec::with_capacity(feeds.len());
+            let mut created = 0;
+            let mut existing = 0;
+            for f in feeds {
+                // Feeds whose category is missing from the backup are dropped
+                let Some(&category_id) = categories.get(&f.category_id) else {
+                    continue;
+                };
+
+                let (local_id, is_new) = restore_feed(conn, category_id, &f)?;
+                if is_new {
+                    created += 1;
+                } else {
+                    existing += 1;
+                }
+                mapping.push((f.id, local_id));
+            }
+            Ok((mapping, created, existing))
+        })?;
+
+        self.feeds.extend(mapping);
+        self.stats.feeds_created += created;
+        self.stats.feeds_existing += existing;
+        Ok(())
+    }
+
+    fn push_entry(&mut self, e: BackupEntry) -> AppResult<()> {
+        if !self.feeds.contains_key(&e.feed_id) {
+            self.stats.entries_skipped += 1;
+            return Ok(());
+        }
+
+        self.pending.push(e);
+        if self.pending.len() >= IMPORT_BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> AppResult<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let batch: Vec<(i64, BackupEntry)> = std::mem::take(&mut self.pending)
+            .into_iter()
+            .map(|e| (self.feeds[&e.feed_id], e))
+            .collect();
+
+        let user_id = self.user_id;
+        let (created, merged, highlights_created, notes_created) = self.run(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            let mut created = 0;
+            let mut merged = 0;
+            let mut highlights_created = 0;
+            let mut notes_created = 0;
+            for (feed_id, e) in batch {
+                let is_new = entry::restore_entry(
+                    &tx,
+                    feed_id,
+                    &e.guid,
+                    e.title.as_deref(),
+                    e.link.as_deref(),
+                    e.content.as_deref(),
+                    e.summary.as_deref(),
+                    e.author.as_deref(),
+                    e.published_at,
+                    e.read_at,
+                    e.starred_at,
+                )?;
+                if is_new {
+                    created += 1;
+                } else {
+                    merged += 1;
+                }
+
+                if e.highlights.is_empty() && e.note.is_none() {
+                    continue;
+                }
+                let Some(local) = entry::find_by_guid_and_feed(&tx, &e.guid, feed_id)? else {
+                    continue;
+                };
+                for h in &e.highlights {
+                    // Highlights the API would reject are dropped
+                    if highlight::validate(&h.quote, h.selector, h.note.as_deref()).is_err() {
+                        continue;
+                    }
+                    if highlight::restore_highlight(
+                        &tx,
+                        user_id,
+                        local.id,
+                        &h.quote,
+                        h.selector,
+                        h.note.as_deref(),
+                    )? {
+                        highlights_created += 1;
+                    }
+                }
+                // An entry that already has a note keeps it
+                if let Some(note) = e.note.as_deref().map(str::trim) {
+                    if !note.is_empty()
+                        && entry_note::validate(note).is_ok()
+                        && entry_note::restore_note(&tx, user_id, local.id, note)?
+                    {
+                        notes_created += 1;
+                    }
+                }
+            }
+            tx.commit()?;
+            Ok((created, merged, highlights_created, notes_created))
+        })?;
+
+        self.stats.entries_created += created;
+        self.stats.entries_merged += merged;
+        self.stats.highlights_created += highlights_created;
+        self.stats.notes_created += notes_created;
+        Ok(())
+    }
+}
+
+struct BackupSeed<'a> {
+    importer: &'a mut Importer,
+}
+
+impl<'de> DeserializeSeed<'de> for BackupSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for BackupSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an rdrs backup object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let importer = self.importer;
+        let mut format_seen = false;
+        let mut version_seen = false;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "format" => {
+                    let format: String = map.next_value()?;
+                    if format != BACKUP_FORMAT {
+                        return Err(de::Error::custom(format!(
+                            "unknown backup format {:?}",
+                            format
+                        )));
+                    }
+                    format_seen = true;
+                }
+                "version" => {
+                    let version: u32 = map.next_value()?;
+                    if version == 0 || version > BACKUP_VERSION {
+                        return Err(de::Error::custom(format!(
+                            "unsupported backup version {}",
+                            version
+                        )));
+                    }
+                    version_seen = true;
+                }
+                _ if !(format_seen && version_seen) => {
+                    return Err(de::Error::custom(
+                        "format and version must precede backup data",
+                    ));
+                }
+                "settings" => {
+                    let settings: BackupSettings = map.next_value()?;
+                    let result = importer.restore_settings(settings);
+                    importer.check(result)?;
+                }
+                "categories" => {
+                    let categories: Vec<BackupCategory> = map.next_value()?;
+                    let result = importer.restore_categories(categories);
+                    importer.check(result)?;
+                }
+                "feeds" => {
+                    let feeds: Vec<BackupFeed> = map.next_value()?;
+                    let result = importer.restore_feeds(feeds);
+                    importer.check(result)?;
+                }
+                "entries" => {
+                    map.next_value_seed(EntriesSeed {
+                        importer: &mut *importer,
+                    })?;
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        if !(format_seen && version_seen) {
+            return Err(de::Error::custom("missing backup format or version"));
+        }
+
+        Ok(())
+    }
+}
+
+struct EntriesSeed<'a> {
+    importer: &'a mut Importer,
+}
+
+impl<'de> DeserializeSeed<'de> for EntriesSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for EntriesSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of entries")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(e) = seq.next_element::<BackupEntry>()? {
+            let result = self.importer.push_entry(e);
+            self.importer.check(result)?;
+        }
+
+        let result = self.importer.flush();
+        self.importer.check(result)
+    }
+}
@@ -0,0 +1,394 @@
+//! Instance-wide blocklist of hosts and URL prefixes.
+//!
+//! On top of the sanitizer's built-in tracking rules, admins can block
+//! hosts such as ad and analytics domains. Images, media and iframes from a
+//! blocked host are removed from content, links to one are unwrapped, and
+//! the image proxy refuses to fetch from it. Patterns are either a host,
+//! which also blocks its subdomains, or a host followed by a path prefix.
+//! Besides the patterns admins enter, a hosts file can be imported from a
+//! URL, and is imported again every day.
+
+use std::collections::BTreeSet;
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+use url::Url;
+
+use super::heartbeat::{Heartbeat, HEARTBEAT_INTERVAL};
+use super::http::{client_builder, send_with_retry, Outbound, RetryConfig, DEFAULT_TIMEOUT};
+use super::instance_settings::InstanceSettings;
+use crate::db::DbPool;
+use crate::error::AppResult;
+use crate::models::blocklist;
+
+/// Longest pattern accepted
+pub const MAX_PATTERN_LEN: usize = 500;
+
+/// Most patterns an admin can enter by hand
+pub const MAX_MANUAL_PATTERNS: usize = 10_000;
+
+/// Most hosts kept from an imported hosts file
+const MAX_IMPORTED_HOSTS: usize = 300_000;
+
+/// Largest hosts file imported
+const MAX_HOSTS_FILE_BYTES: usize = 20 * 1024 * 1024;
+
+/// How often the hosts file is imported again
+const IMPORT_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+/// Names hosts files map to a local address for the machine itself
+const LOCAL_NAMES: &[&str] = &[
+    "localhost",
+    "localhost.localdomain",
+    "local",
+    "broadcasthost",
+    "ip6-localhost",
+    "ip6-loopback",
+    "ip6-localnet",
+    "ip6-mcastprefix",
+    "ip6-allnodes",
+    "ip6-allrouters",
+    "ip6-allhosts",
+];
+
+/// Validate a pattern and bring it to the form it is stored in: lowercase,
+/// without scheme or leading `*.`, and with a path only when it narrows the
+/// host down
+pub fn normalize_pattern(pattern: &str) -> Result<String, String> {
+    let lower = pattern.trim().to_ascii_lowercase();
+    if lower.len() > MAX_PATTERN_LEN {
+        return Err(format!(
+            "Pattern must be {} characters or less",
+            MAX_PATTERN_LEN
+        ));
+    }
+    let rest = lower
+        .strip_prefix("https://")
+        .or_else(|| lower.strip_prefix("http://"))
+        .unwrap_or(&lower);
+    let rest = rest.strip_prefix("*.").unwrap_or(rest);
+    let rest = rest.strip_prefix('.').unwrap_or(rest);
+    let (host, path) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, ""),
+    };
+
+    let is_host = !host.is_empty()
+        && Url::parse(&format!("https://{}/", host)).is_ok_and(|url| url.host_str() == Some(host));
+    if !is_host {
+        return Err(format!("Invalid host: {}", pattern.trim()));
+    }
+    Ok(match path {
+        "" | "/" => host.to_string(),
+        _ => format!("{}{}", host, path),
+    })
+}
+
+/// Hosts listed in a hosts file, such as `0.0.0.0 ads.example.com`. Files
+/// listing one domain per line are read as well.
+pub fn parse_hosts(text: &str) -> Vec<String> {
+    let mut hosts = Vec::new();
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace().peekable();
+        if tokens
+            .peek()
+            .is_some_and(|token| token.parse::<IpAddr>().is_ok())
+        {
+            tokens.next();
+        }
+        for token in tokens {
+            if LOCAL_NAMES.contains(&token) || token.contains('/') {
+                continue;
+            }
+            if let Ok(host) = normalize_pattern(token) {
+                hosts.push(host);
+            }
+        }
+        if hosts.len() >= MAX_IMPORTED_HOSTS {
+            hosts.truncate(MAX_IMPORTED_HOSTS);
+            break;
+        }
+    }
+    hosts
+}
+
+/// A set of blocked patterns, ready to check URLs against
+#[derive(Debug, Default)]
+pub struct BlockedHosts {
+    hosts: BTreeSet<String>,
+    /// Host and path prefix of patterns with a path
+    prefixes: Vec<(String, String)>,
+}
+
+impl BlockedHosts {
+    pub const fn new() -> Self {
+        BlockedHosts {
+            hosts: BTreeSet::new(),
+            prefixes: Vec::new(),
+        }
+    }
+
+    /// Build the set from normalized patterns
+    pub fn from_patterns<I: IntoIterator<Item = String>>(patterns: I) -> Self {
+        let mut blocked = BlockedHosts::new();
+        for pattern in patterns {
+            match pattern.find('/') {
+                Some(i) => {
+                    let (host, path) = pattern.split_at(i);
+                    blocked.prefixes.push((host.to_string(), path.to_string()));
+                }
+                None => {
+                    blocked.hosts.insert(pattern);
+                }
+            }
+        }
+        blocked
+    }
+
+    pub fn len(&self) -> usize {
+        self.hosts.len() + self.prefixes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether `url` is on a blocked host, or under a blocked path of one
+    pub fn blocks(&self, url: &str) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let Ok(url) = Url::parse(url) else {
+            return false;
+        };
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.trim_end_matches('.');
+
+        let in_domain = |domain: &str| {
+            host == domain
+                || host
+                    .strip_suffix(domain)
+                    .is_some_and(|sub| sub.ends_with('.'))
+        };
+        let mut domain = host;
+        loop {
+            if self.hosts.contains(domain) {
+                return true;
+            }
+            match domain.split_once('.') {
+                Some((_, parent)) => domain = parent,
+                None => break,
+            }
+        }
+        self.prefixes
+            .iter()
+            .any(|(prefix_host, path)| in_domain(prefix_host) && url.path().starts_with(path))
+    }
+}
+
+/// The blocklist in effect, shared through `AppState` with the sanitizer
+/// and the image proxy
+#[derive(Debug, Clone, Default)]
+pub struct Blocklist {
+    current: Arc<RwLock<Arc<BlockedHosts>>>,
+}
+
+impl Blocklist {
+    /// The blocked patterns as of now; later changes do not affect it
+    pub fn snapshot(&self) -> Arc<BlockedHosts> {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn replace(&self, blocked: BlockedHosts) {
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(blocked);
+    }
+
+    /// Load the stored patterns, replacing those in effect
+    pub async fn reload(&self, db: &DbPool) -> AppResult<()> {
+        let patterns = db.background(blocklist::list_all).await??;
+        self.replace(BlockedHosts::from_patterns(patterns));
+        Ok(())
+    }
+}
+
+/// Download the hosts file at `url`
+async fn fetch_hosts(url: &str, user_agent: &str) -> Result<Vec<String>, String> {
+    let client = client_builder(Outbound::Configured)
+        .map_err(|e| e.to_string())?
+        .timeout(DEFAULT_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = send_with_retry(&RetryConfig::default(), || {
+        client.get(url).header("User-Agent", user_agent)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    let too_large = format!(
+        "Hosts file is larger than {} MiB",
+        MAX_HOSTS_FILE_BYTES >> 20
+    );
+    if response
+        .content_length()
+        .is_some_and(|len| len > MAX_HOSTS_FILE_BYTES as u64)
+    {
+        return Err(too_large);
+    }
+    let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+    if bytes.len() > MAX_HOSTS_FILE_BYTES {
+        return Err(too_large);
+    }
+    Ok(parse_hosts(&String::from_utf8_lossy(&bytes)))
+}
+
+/// Import the hosts file, if an import URL is set, and load the blocklist.
+/// A failed download is recorded and keeps the hosts imported before.
+pub async fn refresh(db: &DbPool, blocked: &Blocklist, user_agent: &str) -> AppResult<()> {
+    let status = db.background(blocklist::import_status).await??;
+    if let Some(url) = status.url {
+        let result = fetch_hosts(&url, user_agent).await;
+        match &result {
+            Ok(hosts) => info!("Imported {} blocked hosts from {}", hosts.len(), url),
+            Err(e) => warn!("Failed to import blocklist from {}: {}", url, e),
+        }
+        db.background(move |conn| {
+            blocklist::record_import(conn, result.as_deref().map_err(String::as_str))
+        })
+        .await??;
+    }
+    blocked.reload(db).await
+}
+
+/// Start the worker that loads the blocklist at startup and imports the
+/// hosts file every day
+///
+/// # Arguments
+/// * `db` - Database connection
+/// * `blocked` - Blocklist to keep up to date
+/// * `settings` - Instance settings, for the user agent sent with downloads
+/// * `heartbeat` - Reported to while the worker is running
+/// * `cancel_token` - Token to signal graceful shutdown
+pub fn start_blocklist_worker(
+    db: DbPool,
+    blocked: Blocklist,
+    settings: watch::Receiver<Arc<InstanceSettings>>,
+    heartbeat: Heartbeat,
+    cancel_token: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Blocklist worker started");
+
+        let mut interval = tokio::time::interval(IMPORT_INTERVAL);
+        let mut beat = tokio::time::interval(HEARTBEAT_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    info!("Blocklist worker stopping...");
+                    break;
+                }
+                _ = beat.tick() => {
+                    heartbeat.beat();
+                }
+                _ = interval.tick() => {
+                    let user_agent = settings.borrow().user_agent.clone();
+                    if let Err(e) = refresh(&db, &blocked, &user_agent).await {
+                        error!("Failed to refresh blocklist: {}", e);
+                    }
+                }
+            }
+        }
+
+        info!("Blocklist worker stopped");
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_pattern() {
+        assert_eq!(
+            normalize_pattern(" Ads.Example.com ").unwrap(),
+            "ads.example.com"
+        );
+        assert_eq!(normalize_pattern("*.example.com").unwrap(), "example.com");
+        assert_eq!(
+            normalize_pattern("https://example.com/").unwrap(),
+            "example.com"
+        );
+        assert_eq!(
+            normalize_pattern("http://example.com/ads/").unwrap(),
+            "example.com/ads/"
+        );
+
+        assert!(normalize_pattern("").is_err());
+        assert!(normalize_pattern("exa mple.com").is_err());
+        assert!(normalize_pattern("example.com:8080").is_err());
+        assert!(normalize_pattern(&"a".repeat(MAX_PATTERN_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_parse_hosts() {
+        let file = "\
+            # Ad servers\n\
+            127.0.0.1 localhost\n\
+            ::1 ip6-localhost ip6-loopback\n\
+            0.0.0.0 ads.example.com track.example.net # inline comment\n\
+            \n\
+            metrics.example.org\n\
+            0.0.0.0 example.com/path\n";
+        assert_eq!(
+            parse_hosts(file),
+            vec![
+                "ads.example.com",
+                "track.example.net",
+                "metrics.example.org",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        let patterns = ["ads.example.com", "example.org/track/"].map(String::from);
+        let blocked = BlockedHosts::from_patterns(patterns);
+
+        assert!(blocked.blocks("https://ads.example.com/banner.png"));
+        assert!(blocked.blocks("https://cdn.ads.example.com/banner.png"));
+        assert!(!blocked.blocks("https://example.com/photo.png"));
+        assert!(!blocked.blocks("https://badads.example.com/photo.png"));
+
+        assert!(blocked.blocks("https://example.org/track/pixel.gif"));
+        assert!(blocked.blocks("https://www.example.org/track/pixel.gif"));
+        assert!(!blocked.blocks("https://example.org/photo.png"));
+        assert!(!blocked.blocks("https://myexample.org/track/pixel.gif"));
+
+        assert!(!blocked.blocks("not a url"));
+        assert!(!BlockedHosts::new().blocks("https://ads.example.com/"));
+    }
+
+    #[test]
+    fn test_snapshot_is_unaffected_by_replace() {
+        let blocklist = Blocklist::default();
+        let before = blocklist.snapshot();
+        blocklist.replace(BlockedHosts::from_patterns(["ads.example.com".to_string()]));
+
+        assert!(before.is_empty());
+        assert!(blocklist.snapshot().blocks("https://ads.example.com/"));
+    }
+}
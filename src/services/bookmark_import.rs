@@ -0,0 +1,340 @@
+//! Import saved articles from Pocket and Instapaper exports.
+//!
+//! Bookmarks become starred entries of a synthetic "Imported" feed, so they
+//! sit alongside starred feed entries without anything being fetched. Both
+//! services' HTML exports and their CSV exports are understood; items filed
+//! under an archive are imported as read.
+
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use scraper::{Html, Selector};
+use serde::Serialize;
+use url::Url;
+
+use crate::error::{AppError, AppResult};
+use crate::models::{entry, feed};
+
+/// Name of the category and synthetic feed that hold imported bookmarks
+pub const IMPORTED_NAME: &str = "Imported";
+
+/// Identifies the synthetic feed; never fetched
+pub const IMPORTED_FEED_URL: &str = "rdrs:imported";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub url: String,
+    pub title: Option<String>,
+    pub added_at: Option<DateTime<Utc>>,
+    pub archived: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BookmarkImportResult {
+    pub entries_created: i64,
+    pub entries_existing: i64,
+}
+
+/// Parse a Pocket or Instapaper export, detecting HTML or CSV from the content.
+/// Items without an http(s) URL are dropped.
+pub fn parse_bookmarks(content: &str) -> AppResult<Vec<Bookmark>> {
+    let bookmarks = if content.trim_start().starts_with('<') {
+        parse_html(content)?
+    } else {
+        parse_csv(content)?
+    };
+
+    let bookmarks: Vec<Bookmark> = bookmarks
+        .into_iter()
+        .filter(|b| {
+            Url::parse(&b.url)
+                .map(|u| matches!(u.scheme(), "http" | "https"))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    if bookmarks.is_empty() {
+        return Err(AppError::Validation(
+            "No bookmarks found in export".to_string(),
+        ));
+    }
+
+    Ok(bookmarks)
+}
+
+/// Both services export HTML as links grouped under `<h1>` section headings,
+/// such as "Unread" and "Read Archive". Pocket adds a `time_added` attribute.
+fn parse_html(content: &str) -> AppResult<Vec<Bookmark>> {
+    let document = Html::parse_document(content);
+    let selector = Selector::parse("h1, a[href]")
+        .map_err(|_| AppError::Internal("Failed to parse selector".to_string()))?;
+
+    let mut archived = false;
+    let mut bookmarks = Vec::new();
+    for element in document.select(&selector) {
+        let text = element.text().collect::<String>().trim().to_string();
+        if element.value().name() == "h1" {
+            archived = text.to_lowercase().contains("archive");
+            continue;
+        }
+
+        let Some(href) = element.value().attr("href") else {
+            continue;
+        };
+        bookmarks.push(Bookmark {
+            url: href.trim().to_string(),
+            title: Some(text).filter(|t| !t.is_empty()),
+            added_at: element.value().attr("time_added").and_then(parse_timestamp),
+            archived,
+        });
+    }
+
+    Ok(bookmarks)
+}
+
+/// Pocket CSV has `title,url,time_added,tags,status` columns and Instapaper
+/// CSV has `URL,Title,Selection,Folder,Timestamp`; columns are found by name.
+fn parse_csv(content: &str) -> AppResult<Vec<Bookmark>> {
+    let mut rows = parse_csv_rows(content).into_iter();
+    let header: Vec<String> = rows
+        .next()
+        .unwrap_or_default()
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+    let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+
+    let url_col = column(&["url"])
+        .ok_or_else(|| AppError::Validation("Unrecognized bookmark export format".to_string()))?;
+    let title_col = column(&["title"]);
+    let time_col = column(&["time_added", "timestamp"]);
+    let status_col = column(&["status", "folder"]);
+
+    let field = |row: &[String], col: Option<usize>| {
+        col.and_then(|c| row.get(c))
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+
+    Ok(rows
+        .filter_map(|row| {
+            let url = field(&row, Some(url_col))?;
+            Some(Bookmark {
+                url,
+                title: field(&row, title_col),
+                added_at: field(&row, time_col).as_deref().and_then(parse_timestamp),
+                archived: field(&row, status_col)
+                    .is_some_and(|s| s.eq_ignore_ascii_case("archive")),
+            })
+        })
+        .collect())
+}
+
+/// Split CSV into rows of fields, honouring quoted fields with `""` escapes
+/// and embedded newlines
+fn parse_csv_rows(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    rows
+}
+
+/// Both services record when an item was saved as Unix seconds
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    value
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+}
+
+/// Store bookmarks as starred entries of the user's "Imported" feed. A
+/// bookmark already imported keeps its entry and only gains missing read and
+/// star state.
+pub fn import_bookmarks(
+    conn: &Connection,
+    user_id: i64,
+    bookmarks: Vec<Bookmark>,
+) -> AppResult<BookmarkImportResult> {
+    let tx = conn.unchecked_transaction()?;
+    let imported =
+        feed::find_or_create_synthetic_feed(&tx, user_id, IMPORTED_FEED_URL, IMPORTED_NAME)?;
+
+    let now = Utc::now();
+    let mut entries_created = 0;
+    let mut entries_existing = 0;
+    for b in bookmarks {
+        let saved_at = b.added_at.unwrap_or(now);
+        let created = entry::restore_entry(
+            &tx,
+            imported.id,
+            &b.url,
+            b.title.as_deref(),
+            Some(&b.url),
+            None,
+            None,
+            None,
+            b.added_at,
+            b.archived.then_some(now),
+            Some(saved_at),
+        )?;
+        if created {
+            entries_created += 1;
+        } else {
+            entries_existing += 1;
+        }
+    }
+    tx.commit()?;
+
+    Ok(BookmarkImportResult {
+        entries_created,
+        entries_existing,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::{self, Role};
+
+    fn setup_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_parse_pocket_html() {
+        let html = r#"<!DOCTYPE html>
+<html><head><title>Pocket Export</title></head><body>
+<h1>Unread</h1>
+<ul>
+<li><a href="https://example.com/a" time_added="1500000000" tags="">First</a></li>
+</ul>
+<h1>Read Archive</h1>
+<ul>
+<li><a href="https://example.com/b" time_added="1600000000" tags="rust">Second</a></li>
+<li><a href="javascript:alert(1)">Bad</a></li>
+</ul>
+</body></html>"#;
+
+        let bookmarks = parse_bookmarks(html).unwrap();
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(bookmarks[0].url, "https://example.com/a");
+        assert_eq!(bookmarks[0].title.as_deref(), Some("First"));
+        assert_eq!(bookmarks[0].added_at.unwrap().timestamp(), 1500000000);
+        assert!(!bookmarks[0].archived);
+        assert!(bookmarks[1].archived);
+    }
+
+    #[test]
+    fn test_parse_instapaper_csv() {
+        let csv = "URL,Title,Selection,Folder,Timestamp\r\n\
+https://example.com/a,\"Quoted, \"\"title\"\"\",,Unread,1500000000\r\n\
+https://example.com/b,Plain,\"multi\nline\",Archive,1600000000\r\n";
+
+        let bookmarks = parse_bookmarks(csv).unwrap();
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(bookmarks[0].title.as_deref(), Some("Quoted, \"title\""));
+        assert!(!bookmarks[0].archived);
+        assert_eq!(bookmarks[1].url, "https://example.com/b");
+        assert!(bookmarks[1].archived);
+        assert_eq!(bookmarks[1].added_at.unwrap().timestamp(), 1600000000);
+    }
+
+    #[test]
+    fn test_parse_pocket_csv() {
+        let csv = "title,url,time_added,tags,status\n\
+Post,https://example.com/a,1500000000,,archive\n";
+
+        let bookmarks = parse_bookmarks(csv).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].title.as_deref(), Some("Post"));
+        assert!(bookmarks[0].archived);
+    }
+
+    #[test]
+    fn test_parse_unrecognized_export() {
+        assert!(parse_bookmarks("name,link\nfoo,bar\n").is_err());
+        assert!(parse_bookmarks("<html><body></body></html>").is_err());
+    }
+
+    #[test]
+    fn test_import_bookmarks_creates_starred_entries_once() {
+        let conn = setup_db();
+        let user_id = user::create_user(&conn, "testuser", "hash123", Role::User)
+            .unwrap()
+            .id;
+        let bookmarks = vec![
+            Bookmark {
+                url: "https://example.com/a".to_string(),
+                title: Some("A".to_string()),
+                added_at: DateTime::from_timestamp(1500000000, 0),
+                archived: false,
+            },
+            Bookmark {
+                url: "https://example.com/b".to_string(),
+                title: None,
+                added_at: None,
+                archived: true,
+            },
+        ];
+
+        let result = import_bookmarks(&conn, user_id, bookmarks.clone()).unwrap();
+        assert_eq!(result.entries_created, 2);
+
+        let again = import_bookmarks(&conn, user_id, bookmarks).unwrap();
+        assert_eq!(again.entries_created, 0);
+        assert_eq!(again.entries_existing, 2);
+
+        let feeds = feed::list_by_user(&conn, user_id).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert!(feeds[0].synthetic);
+
+        let a = entry::find_by_guid_and_feed(&conn, "https://example.com/a", feeds[0].id)
+            .unwrap()
+            .unwrap();
+        assert!(a.starred_at.is_some());
+        assert!(a.read_at.is_none());
+        let b = entry::find_by_guid_and_feed(&conn, "https://example.com/b", feeds[0].id)
+            .unwrap()
+            .unwrap();
+        assert!(b.starred_at.is_some());
+        assert!(b.read_at.is_some());
+    }
+}
@@ -0,0 +1,172 @@
+//! Suggest a category for a new subscription.
+//!
+//! A user category scores when its name shows up among the feed's declared
+//! categories or in its title, and for every title keyword it shares with
+//! feeds already filed under it. The highest score wins; a feed with nothing
+//! in common with any category gets no suggestion.
+
+use std::collections::HashSet;
+
+use rusqlite::Connection;
+
+use crate::error::AppResult;
+use crate::models::{category, category::Category, feed, feed::Feed};
+use crate::services::feed_discovery::DiscoveredFeed;
+
+/// Score for a category name matching a declared category or title word,
+/// worth more than a single shared keyword with an existing feed
+const NAME_MATCH_WEIGHT: usize = 3;
+
+/// Words too common in feed titles to say anything about the topic
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "at", "blog", "by", "com", "daily", "feed", "for", "from", "in", "is",
+    "news", "of", "on", "or", "posts", "rss", "the", "to", "weekly", "with", "www",
+];
+
+fn keywords(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 2)
+        .map(str::to_lowercase)
+        .filter(|w| !STOPWORDS.contains(&w.as_str()))
+        .collect()
+}
+
+fn suggest(
+    categories: &[Category],
+    feeds: &[Feed],
+    title: Option<&str>,
+    declared: &[String],
+) -> Option<i64> {
+    let title_words = keywords(title.unwrap_or_default());
+    let declared_words: HashSet<String> = declared.iter().flat_map(|d| keywords(d)).collect();
+
+    let mut best: Option<(i64, usize)> = None;
+    for cat in categories {
+        let name_words = keywords(&cat.name);
+        let mut score = 0;
+        if name_words.iter().any(|w| declared_words.contains(w)) {
+            score += NAME_MATCH_WEIGHT;
+        }
+        if name_words.iter().any(|w| title_words.contains(w)) {
+            score += NAME_MATCH_WEIGHT;
+        }
+
+        for f in feeds
+            .iter()
+            .filter(|f| f.category_id == cat.id && !f.synthetic)
+        {
+            let words = keywords(f.title.as_deref().unwrap_or_default());
+            score += words.intersection(&title_words).count();
+            score += words.intersection(&declared_words).count();
+        }
+
+        let better = match best {
+            Some((_, best_score)) => score > best_score,
+            None => score > 0,
+        };
+        if better {
+            best = Some((cat.id, score));
+        }
+    }
+
+    best.map(|(id, _)| id)
+}
+
+/// Pick the user's category that best fits a discovered feed, if any does
+pub fn suggest_category(
+    conn: &Connection,
+    user_id: i64,
+    discovered: &DiscoveredFeed,
+) -> AppResult<Option<i64>> {
+    let categories = category::list_by_user(conn, user_id)?;
+    let feeds = feed::list_by_user(conn, user_id)?;
+    Ok(suggest(
+        &categories,
+        &feeds,
+        discovered.title.as_deref(),
+        &discovered.categories,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::{self, Role};
+
+    fn setup() -> (Connection, i64) {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "testuser", "hash", Role::User)
+            .unwrap()
+            .id;
+        (conn, user_id)
+    }
+
+    fn discovered(title: &str, categories: &[&str]) -> DiscoveredFeed {
+        DiscoveredFeed {
+            feed_url: "https://example.com/feed.xml".to_string(),
+            title: Some(title.to_string()),
+            description: None,
+            site_url: None,
+            categories: categories.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_suggests_category_named_by_declared_tags() {
+        let (conn, user_id) = setup();
+        category::create_category(&conn, user_id, "Cooking").unwrap();
+        let programming = category::create_category(&conn, user_id, "Programming").unwrap();
+
+        let found = discovered("Someone's Notes", &["programming", "career"]);
+        assert_eq!(
+            suggest_category(&conn, user_id, &found).unwrap(),
+            Some(programming.id)
+        );
+    }
+
+    #[test]
+    fn test_suggests_category_of_similar_feeds() {
+        let (conn, user_id) = setup();
+        let tech = category::create_category(&conn, user_id, "Tech").unwrap();
+        let misc = category::create_category(&conn, user_id, "Misc").unwrap();
+        feed::create_feed(
+            &conn,
+            tech.id,
+            "https://blog.rust-lang.org/feed.xml",
+            Some("Rust Blog"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        feed::create_feed(
+            &conn,
+            misc.id,
+            "https://example.com/garden.xml",
+            Some("Garden Diary"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let found = discovered("Inside Rust Blog", &[]);
+        assert_eq!(
+            suggest_category(&conn, user_id, &found).unwrap(),
+            Some(tech.id)
+        );
+    }
+
+    #[test]
+    fn test_no_suggestion_without_overlap() {
+        let (conn, user_id) = setup();
+        category::create_category(&conn, user_id, "Cooking").unwrap();
+
+        let found = discovered("The Daily Blog", &[]);
+        assert_eq!(suggest_category(&conn, user_id, &found).unwrap(), None);
+    }
+}
@@ -0,0 +1,290 @@
+//! Category digests: one combined summary of a category's unread entries.
+//!
+//! Entries are packed into chunks that fit a single summarizer request (map),
+//! then the chunk summaries are packed and summarized again until one text is
+//! left (reduce). Entries that already have a completed summary contribute
+//! that instead of their raw content.
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use super::jobs::JobError;
+use super::summarize::kagi::{self, KagiConfig};
+use crate::db::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::entry::{self, EntryFilter, EntrySortOrder};
+use crate::models::{
+    category, category_digest, entry_summary, notification, user_settings, SummaryStatus,
+};
+
+/// Maximum number of unread entries folded into one digest (newest first)
+pub const MAX_DIGEST_ENTRIES: i64 = 300;
+
+/// Maximum characters sent to the summarizer per request
+const CHUNK_CHARS: usize = 12_000;
+
+/// Maximum characters taken from a single entry
+const ENTRY_CHARS: usize = 1_500;
+
+/// A job to build a digest for a category
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestJob {
+    pub user_id: i64,
+    pub category_id: i64,
+}
+
+/// Reduce entry HTML to whitespace-normalized plain text
+pub(crate) fn html_to_text(html: &str) -> String {
+    let fragment = scraper::Html::parse_fragment(html);
+    let text: String = fragment.root_element().text().collect::<Vec<_>>().join(" ");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Format one entry as a digest input block
+pub fn entry_text(title: Option<&str>, source: Option<&str>, body: &str) -> String {
+    let body: String = body.chars().take(ENTRY_CHARS).collect();
+    match source {
+        Some(source) => format!("## {} ({})\n{}", title.unwrap_or("Untitled"), source, body),
+        None => format!("## {}\n{}", title.unwrap_or("Untitled"), body),
+    }
+}
+
+/// Pack texts into chunks of at most `max_chars` characters, keeping each
+/// text whole unless it alone exceeds the limit
+pub fn chunk_texts(texts: Vec<String>, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for text in texts {
+        let text: String = text.chars().take(max_chars).collect();
+        if !current.is_empty() && current.chars().count() + text.chars().count() + 2 > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(&text);
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+async fn summarize_chunk(config: &KagiConfig, text: &str) -> Result<String, String> {
+    match kagi::summarize_text(config, text).await {
+        Ok(result) if result.success => result
+            .output_text
+            .ok_or_else(|| "No summary text returned".to_string()),
+        Ok(result) => Err(result.error.unwrap_or_else(|| "Unknown error".to_string())),
+        Err(e) => Err(format!("Kagi API error: {}", e)),
+    }
+}
+
+/// Map-reduce texts into a single summary
+async fn build_digest(config: &KagiConfig, texts: Vec<String>) -> Result<String, String> {
+    let mut chunks = chunk_texts(texts, CHUNK_CHARS);
+
+    loop {
+        let mut summaries = Vec::with_capacity(chunks.len());
+        for chunk in &chunks {
+            summaries.push(summarize_chunk(config, chunk).await?);
+        }
+
+        if summaries.len() == 1 {
+            return Ok(summaries.remove(0));
+        }
+
+        let count = summaries.len();
+        chunks = chunk_texts(summaries, CHUNK_CHARS);
+        // Summaries too long to pack any tighter are merged in one truncated
+        // request so the reduction always terminates
+        if chunks.len() >= count {
+            chunks = vec![chunks.join("\n\n").chars().take(CHUNK_CHARS).collect()];
+        }
+    }
+}
+
+/// Build a digest, recording progress in `category_digest`. Failures are
+/// left for the queue to retry or hand to `fail_digest_job`.
+pub(crate) async fn run_digest_job(db: &DbPool, job: &DigestJob) -> Result<(), JobError> {
+    let user_id = job.user_id;
+    let category_id = job.category_id;
+    tracing::debug!(
+        "Processing digest job: user={}, category={}",
+        user_id,
+        category_id
+    );
+
+    let _ = db
+        .background(move |conn| category_digest::set_processing(conn, user_id, category_id))
+        .await;
+
+    let (config, texts) = load_inputs(db, job).await?;
+    let (text, count) = if texts.is_empty() {
+        (String::new(), 0)
+    } else {
+        let count = texts.len() as i64;
+        let text = build_digest(&config, texts)
+            .await
+            .map_err(JobError::Retry)?;
+        (text, count)
+    };
+
+    tracing::debug!(
+        "Digest completed for category {}: {} entries, {} chars",
+        category_id,
+        count,
+        text.len()
+    );
+    let _ = db
+        .background(move |conn| {
+            category_digest::set_completed(conn, user_id, category_id, &text, count)?;
+            notify_finished(conn, user_id, category_id, None)
+        })
+        .await;
+    Ok(())
+}
+
+/// Record a digest the queue gave up on as failed
+pub(crate) async fn fail_digest_job(db: &DbPool, job: &DigestJob, error: &str) {
+    let user_id = job.user_id;
+    let category_id = job.category_id;
+    tracing::warn!("Digest failed for category {}: {}", category_id, error);
+    let error = error.to_string();
+    let _ = db
+        .background(move |conn| {
+            category_digest::set_failed(conn, user_id, category_id, &error)?;
+            notify_finished(conn, user_id, category_id, Some(&error))
+        })
+        .await;
+}
+
+/// Let the user know the digest is ready, or why it could not be built
+fn notify_finished(
+    conn: &Connection,
+    user_id: i64,
+    category_id: i64,
+    error: Option<&str>,
+) -> AppResult<()> {
+    let Some(cat) = category::find_by_id_and_user(conn, category_id, user_id)? else {
+        return Ok(());
+    };
+    let title = match error {
+        None => format!("Digest of {} is ready", cat.name),
+        Some(_) => format!("Digest of {} failed", cat.name),
+    };
+    let message = notification::Message {
+        title,
+        body: error.map(str::to_string),
+        link: Some(format!("/categories/{}/digest", category_id)),
+    };
+    notification::create(
+        conn,
+        user_id,
+        notification::KIND_DIGEST_FINISHED,
+        None,
+        &message,
+    )?;
+    Ok(())
+}
+
+/// Load the summarizer config and the text of every unread entry in the
+/// category. A missing config is not worth retrying.
+async fn load_inputs(db: &DbPool, job: &DigestJob) -> Result<(KagiConfig, Vec<String>), JobError> {
+    let user_id = job.user_id;
+    let category_id = job.category_id;
+
+    db.background(move |conn| {
+        let config = user_settings::get_save_services_config(conn, user_id)?
+            .kagi
+            .filter(|k| k.is_configured())
+            .ok_or_else(|| AppError::Validation("Kagi is not configured".to_string()))?;
+
+        let filter = EntryFilter {
+            category_id: Some(category_id),
+            unread_only: true,
+            ..Default::default()
+        };
+        let entries = entry::list_by_user(
+            conn,
+            user_id,
+            &filter,
+            EntrySortOrder::default(),
+            MAX_DIGEST_ENTRIES,
+            0,
+        )?;
+
+        let mut texts = Vec::with_capacity(entries.len());
+        for e in entries {
+            let summarized = entry_summary::find_by_user_and_entry(conn, user_id, e.entry.id)?
+                .filter(|s| s.status == SummaryStatus::Completed)
+                .and_then(|s| s.summary_text);
+            let body = match summarized {
+                Some(text) => text,
+                None => html_to_text(
+                    e.entry
+                        .content
+                        .as_deref()
+                        .or(e.entry.summary.as_deref())
+                        .unwrap_or(""),
+                ),
+            };
+            texts.push(entry_text(
+                e.entry.title.as_deref(),
+                e.feed_title.as_deref(),
+                &body,
+            ));
+        }
+
+        Ok::<_, AppError>((config, texts))
+    })
+    .await
+    .map_err(|e| JobError::Retry(format!("Internal error: {}", e)))?
+    .map_err(|e| match e {
+        AppError::Validation(msg) => JobError::Fail(msg),
+        e => JobError::Retry(e.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_text() {
+        assert_eq!(
+            html_to_text("<p>Hello <b>world</b></p>\n<p>again</p>"),
+            "Hello world again"
+        );
+    }
+
+    #[test]
+    fn test_entry_text_truncates() {
+        let long = "a".repeat(ENTRY_CHARS + 100);
+        let text = entry_text(Some("Title"), Some("Feed"), &long);
+        assert!(text.starts_with("## Title (Feed)\n"));
+        assert_eq!(text.matches('a').count(), ENTRY_CHARS);
+
+        assert_eq!(entry_text(None, None, "body"), "## Untitled\nbody");
+    }
+
+    #[test]
+    fn test_chunk_texts() {
+        let texts = vec!["a".repeat(40), "b".repeat(40), "c".repeat(40)];
+        let chunks = chunk_texts(texts, 100);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(
+            chunks[0],
+            format!("{}\n\n{}", "a".repeat(40), "b".repeat(40))
+        );
+        assert_eq!(chunks[1], "c".repeat(40));
+
+        // Oversized texts are truncated rather than split
+        let chunks = chunk_texts(vec!["x".repeat(150)], 100);
+        assert_eq!(chunks, vec!["x".repeat(100)]);
+
+        assert!(chunk_texts(Vec::new(), 100).is_empty());
+    }
+}
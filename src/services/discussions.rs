@@ -0,0 +1,300 @@
+//! Finds discussion threads about an article on Hacker News, Lobsters and
+//! Reddit. Lookups only happen when a user asks for them, since they send the
+//! article URL to those sites.
+
+use chrono::{DateTime, TimeZone, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::error::{AppError, AppResult};
+use crate::services::http::{
+    client_builder, send_with_retry, Outbound, RetryConfig, DEFAULT_TIMEOUT,
+};
+
+/// How long lookup results are reused before asking the sites again (6 hours)
+pub const CACHE_TTL_SECS: i64 = 6 * 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscussionSource {
+    HackerNews,
+    Lobsters,
+    Reddit,
+}
+
+/// A thread discussing an article
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Discussion {
+    pub source: DiscussionSource,
+    pub title: String,
+    /// Link to the thread itself
+    pub url: String,
+    pub points: Option<i64>,
+    pub comments: Option<i64>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Compare article URLs ignoring scheme, `www.`, trailing slashes and fragments
+fn normalize_url(url: &str) -> String {
+    let url = url.split('#').next().unwrap_or(url);
+    let url = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+    let url = url.strip_prefix("www.").unwrap_or(url);
+    url.trim_end_matches('/').to_lowercase()
+}
+
+#[derive(Debug, Deserialize)]
+struct HnSearchResponse {
+    hits: Vec<HnHit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HnHit {
+    #[serde(rename = "objectID")]
+    object_id: String,
+    title: Option<String>,
+    url: Option<String>,
+    points: Option<i64>,
+    num_comments: Option<i64>,
+    created_at_i: Option<i64>,
+}
+
+fn parse_hacker_news(body: &str, article_url: &str) -> Vec<Discussion> {
+    let Ok(response) = serde_json::from_str::<HnSearchResponse>(body) else {
+        return vec![];
+    };
+    let wanted = normalize_url(article_url);
+    response
+        .hits
+        .into_iter()
+        .filter(|hit| hit.url.as_deref().map(normalize_url).as_ref() == Some(&wanted))
+        .map(|hit| Discussion {
+            source: DiscussionSource::HackerNews,
+            title: hit.title.unwrap_or_default(),
+            url: format!("https://news.ycombinator.com/item?id={}", hit.object_id),
+            points: hit.points,
+            comments: hit.num_comments,
+            created_at: hit
+                .created_at_i
+                .and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct LobstersStory {
+    title: String,
+    url: Option<String>,
+    comments_url: String,
+    score: Option<i64>,
+    comment_count: Option<i64>,
+    created_at: Option<DateTime<Utc>>,
+}
+
+fn parse_lobsters(body: &str, article_url: &str) -> Vec<Discussion> {
+    let Ok(stories) = serde_json::from_str::<Vec<LobstersStory>>(body) else {
+        return vec![];
+    };
+    let wanted = normalize_url(article_url);
+    stories
+        .into_iter()
+        .filter(|story| story.url.as_deref().map(normalize_url).as_ref() == Some(&wanted))
+        .map(|story| Discussion {
+            source: DiscussionSource::Lobsters,
+            title: story.title,
+            url: story.comments_url,
+            points: story.score,
+            comments: story.comment_count,
+            created_at: story.created_at,
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct RedditListing {
+    data: RedditListingData,
+}
+
+#[derive(Debug, Deserialize)]
+struct RedditListingData {
+    children: Vec<RedditChild>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RedditChild {
+    data: RedditPost,
+}
+
+#[derive(Debug, Deserialize)]
+struct RedditPost {
+    title: String,
+    subreddit: String,
+    permalink: String,
+    score: Option<i64>,
+    num_comments: Option<i64>,
+    created_utc: Option<f64>,
+}
+
+fn parse_reddit(body: &str) -> Vec<Discussion> {
+    let Ok(listing) = serde_json::from_str::<RedditListing>(body) else {
+        return vec![];
+    };
+    listing
+        .data
+        .children
+        .into_iter()
+        .map(|child| child.data)
+        .map(|post| Discussion {
+            source: DiscussionSource::Reddit,
+            title: format!("r/{}: {}", post.subreddit, post.title),
+            url: format!("https://www.reddit.com{}", post.permalink),
+            points: post.score,
+            comments: post.num_comments,
+            created_at: post
+                .created_utc
+                .and_then(|ts| Utc.timestamp_opt(ts as i64, 0).single()),
+        })
+        .collect()
+}
+
+async fn fetch(client: &Client, api_url: &str, user_agent: &str) -> Option<String> {
+    let response = send_with_retry(&RetryConfig::icon(), || {
+        client.get(api_url).header("User-Agent", user_agent)
+    })
+    .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => response.text().await.ok(),
+        Ok(response) => {
+            warn!(
+                "Discussion lookup {} returned HTTP {}",
+                api_url,
+                response.status()
+            );
+            None
+        }
+        Err(e) => {
+            warn!("Discussion lookup {} failed: {}", api_url, e);
+            None
+        }
+    }
+}
+
+fn lookup_url(base: &str, param: &str, article_url: &str) -> AppResult<String> {
+    let mut url = url::Url::parse(base)
+        .map_err(|e| AppError::Internal(format!("Failed to parse discussion API URL: {}", e)))?;
+    url.query_pairs_mut().append_pair(param, article_url);
+    Ok(url.to_string())
+}
+
+/// Look up discussions of `article_url` on every supported site. A site that
+/// fails contributes no results, so a partial answer is still returned.
+pub async fn find_discussions(article_url: &str, user_agent: &str) -> AppResult<Vec<Discussion>> {
+    let client = client_builder(Outbound::Discussions)?
+        .timeout(DEFAULT_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to build HTTP client: {}", e)))?;
+
+    let hn_url = lookup_url(
+        "https://hn.algolia.com/api/v1/search?restrictSearchableAttributes=url",
+        "query",
+        article_url,
+    )?;
+    let lobsters_url = lookup_url("https://lobste.rs/stories/url/all.json", "url", article_url)?;
+    let reddit_url = lookup_url("https://www.reddit.com/api/info.json", "url", article_url)?;
+
+    let (hn, lobsters, reddit) = tokio::join!(
+        fetch(&client, &hn_url, user_agent),
+        fetch(&client, &lobsters_url, user_agent),
+        fetch(&client, &reddit_url, user_agent),
+    );
+
+    let mut discussions = Vec::new();
+    if let Some(body) = hn {
+        discussions.extend(parse_hacker_news(&body, article_url));
+    }
+    if let Some(body) = lobsters {
+        discussions.extend(parse_lobsters(&body, article_url));
+    }
+    if let Some(body) = reddit {
+        discussions.extend(parse_reddit(&body));
+    }
+
+    // Busiest threads first
+    discussions.sort_by_key(|d| std::cmp::Reverse(d.comments.unwrap_or(0)));
+    Ok(discussions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARTICLE: &str = "https://example.com/post/";
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(
+            normalize_url("https://www.Example.com/post/#top"),
+            normalize_url("http://example.com/post")
+        );
+        assert_ne!(
+            normalize_url("https://example.com/post"),
+            normalize_url("https://example.com/other")
+        );
+    }
+
+    #[test]
+    fn test_parse_hacker_news() {
+        let body = r#"{"hits": [
+            {"objectID": "42", "title": "Post", "url": "https://example.com/post",
+             "points": 120, "num_comments": 45, "created_at_i": 1700000000},
+            {"objectID": "43", "title": "Other", "url": "https://example.com/post-2",
+             "points": 1, "num_comments": 0, "created_at_i": 1700000000}
+        ]}"#;
+
+        let discussions = parse_hacker_news(body, ARTICLE);
+        assert_eq!(discussions.len(), 1);
+        assert_eq!(discussions[0].source, DiscussionSource::HackerNews);
+        assert_eq!(
+            discussions[0].url,
+            "https://news.ycombinator.com/item?id=42"
+        );
+        assert_eq!(discussions[0].points, Some(120));
+        assert_eq!(discussions[0].comments, Some(45));
+        assert!(discussions[0].created_at.is_some());
+
+        assert!(parse_hacker_news("not json", ARTICLE).is_empty());
+    }
+
+    #[test]
+    fn test_parse_lobsters() {
+        let body = r#"[{"short_id": "abc", "title": "Post", "url": "http://www.example.com/post",
+            "comments_url": "https://lobste.rs/s/abc/post", "score": 30, "comment_count": 8,
+            "created_at": "2024-03-01T12:00:00.000-06:00"}]"#;
+
+        let discussions = parse_lobsters(body, ARTICLE);
+        assert_eq!(discussions.len(), 1);
+        assert_eq!(discussions[0].url, "https://lobste.rs/s/abc/post");
+        assert_eq!(discussions[0].comments, Some(8));
+        assert!(discussions[0].created_at.is_some());
+    }
+
+    #[test]
+    fn test_parse_reddit() {
+        let body = r#"{"kind": "Listing", "data": {"children": [{"kind": "t3", "data": {
+            "title": "Post", "subreddit": "rust", "permalink": "/r/rust/comments/xyz/post/",
+            "score": 250, "num_comments": 60, "created_utc": 1700000000.0}}]}}"#;
+
+        let discussions = parse_reddit(body);
+        assert_eq!(discussions.len(), 1);
+        assert_eq!(discussions[0].title, "r/rust: Post");
+        assert_eq!(
+            discussions[0].url,
+            "https://www.reddit.com/r/rust/comments/xyz/post/"
+        );
+        assert_eq!(discussions[0].points, Some(250));
+    }
+}
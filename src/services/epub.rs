@@ -0,0 +1,459 @@
+//! EPUB books of entries, for reading on e-readers.
+//!
+//! Each entry becomes a chapter. Chapters are built from sanitized HTML and
+//! rewritten as XHTML, which EPUB requires; images the caller fetched are
+//! packed into the book and the rest are left out, so a book never loads
+//! anything when opened.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+
+use chrono::{DateTime, Utc};
+use scraper::{ElementRef, Html, Node, Selector};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::error::{AppError, AppResult};
+
+/// Most images packed into one book
+pub const MAX_IMAGES: usize = 200;
+
+/// Most bytes of images packed into one book (30 MiB)
+pub const MAX_IMAGE_BYTES: usize = 30 * 1024 * 1024;
+
+const MATHML_NS: &str = "http://www.w3.org/1998/Math/MathML";
+
+/// Elements written as `<name/>`
+const VOID_ELEMENTS: &[&str] = &["area", "br", "col", "hr", "img", "wbr"];
+
+/// Image attributes that make no sense once the image is in the book
+const DROPPED_IMAGE_ATTRIBUTES: &[&str] = &["srcset", "sizes", "loading", "decoding"];
+
+pub struct Chapter {
+    pub title: String,
+    pub feed_title: Option<String>,
+    pub author: Option<String>,
+    pub link: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    /// Sanitized content with absolute image URLs
+    pub html: String,
+}
+
+pub struct Image {
+    pub media_type: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+impl Image {
+    /// An image of a type every EPUB reader shows, recognized by its
+    /// content rather than what the server claimed it was
+    pub fn sniff(bytes: Vec<u8>) -> Option<Image> {
+        let media_type = if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            "image/jpeg"
+        } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            "image/png"
+        } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+            "image/gif"
+        } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            "image/webp"
+        } else {
+            return None;
+        };
+        Some(Image { media_type, bytes })
+    }
+
+    fn extension(&self) -> &'static str {
+        match self.media_type {
+            "image/jpeg" => "jpg",
+            "image/png" => "png",
+            "image/gif" => "gif",
+            _ => "webp",
+        }
+    }
+}
+
+/// Image URLs in `chapters` in reading order, each once
+pub fn image_urls(chapters: &[Chapter]) -> Vec<String> {
+    let selector = Selector::parse("img[src]").expect("valid selector");
+    let mut urls: Vec<String> = Vec::new();
+    for chapter in chapters {
+        let fragment = Html::parse_fragment(&chapter.html);
+        for img in fragment.select(&selector) {
+            let Some(src) = img.value().attr("src") else {
+                continue;
+            };
+            if (src.starts_with("http://") || src.starts_with("https://"))
+                && !urls.iter().any(|u| u == src)
+            {
+                urls.push(src.to_string());
+            }
+        }
+    }
+    urls
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            // Not allowed in XML 1.0
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Write the children of `el` as XHTML, pointing images at their paths in
+/// the book
+fn write_children(el: ElementRef<'_>, images: &HashMap<&str, String>, out: &mut String) {
+    for child in el.children() {
+        match child.value() {
+            Node::Text(text) => out.push_str(&escape(text)),
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    write_element(child, images, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn write_element(el: ElementRef<'_>, images: &HashMap<&str, String>, out: &mut String) {
+    let name = el.value().name();
+    match name {
+        // The book holds one version of each image
+        "picture" => return write_children(el, images, out),
+        "source" => return,
+        _ => {}
+    }
+
+    let mut attrs: Vec<(&str, String)> = Vec::new();
+    if name == "img" {
+        let alt = el.value().attr("alt");
+        let Some(path) = el.value().attr("src").and_then(|src| images.get(src)) else {
+            // Not packed into the book; keep its description
+            if let Some(alt) = alt.filter(|a| !a.trim().is_empty()) {
+                out.push_str(&escape(alt));
+            }
+            return;
+        };
+        attrs.push(("src", path.clone()));
+        attrs.push(("alt", alt.unwrap_or_default().to_string()));
+    }
+    for (attr, value) in el.value().attrs() {
+        let skip = (name == "img" && matches!(attr, "src" | "alt"))
+            || DROPPED_IMAGE_ATTRIBUTES.contains(&attr)
+            || attr == "xmlns";
+        if !skip {
+            attrs.push((attr, value.to_string()));
+        }
+    }
+    if name == "math" {
+        attrs.push(("xmlns", MATHML_NS.to_string()));
+    }
+
+    out.push('<');
+    out.push_str(name);
+    for (attr, value) in attrs {
+        out.push_str(&format!(" {}=\"{}\"", attr, escape(&value)));
+    }
+    if VOID_ELEMENTS.contains(&name) {
+        out.push_str("/>");
+        return;
+    }
+    out.push('>');
+    write_children(el, images, out);
+    out.push_str(&format!("</{}>", name));
+}
+
+/// `html` as the body of an XHTML document
+fn to_xhtml(html: &str, images: &HashMap<&str, String>) -> String {
+    let fragment = Html::parse_fragment(html);
+    let mut out = String::new();
+    write_children(fragment.root_element(), images, &mut out);
+    out
+}
+
+fn chapter_xhtml(chapter: &Chapter, images: &HashMap<&str, String>) -> String {
+    let mut byline: Vec<String> = Vec::new();
+    if let Some(feed_title) = &chapter.feed_title {
+        byline.push(escape(feed_title));
+    }
+    if let Some(author) = &chapter.author {
+        byline.push(escape(author));
+    }
+    if let Some(published_at) = chapter.published_at {
+        byline.push(published_at.format("%Y-%m-%d").to_string());
+    }
+    let link = chapter
+        .link
+        .as_deref()
+        .map(|link| format!("<p><a href=\"{}\">{}</a></p>\n", escape(link), escape(link)))
+        .unwrap_or_default();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head><title>{title}</title></head>
+<body>
+<h1>{title}</h1>
+<p><em>{byline}</em></p>
+{link}<hr/>
+{content}
+</body>
+</html>
+"#,
+        title = escape(&chapter.title),
+        byline = byline.join(" &#183; "),
+        link = link,
+        content = to_xhtml(&chapter.html, images),
+    )
+}
+
+fn nav_xhtml(title: &str, chapters: &[Chapter]) -> String {
+    let items: String = chapters
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            format!(
+                "<li><a href=\"chapter-{}.xhtml\">{}</a></li>\n",
+                i + 1,
+                escape(&c.title)
+            )
+        })
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head><title>{title}</title></head>
+<body>
+<nav epub:type="toc" id="toc">
+<h1>{title}</h1>
+<ol>
+{items}</ol>
+</nav>
+</body>
+</html>
+"#,
+        title = escape(title),
+        items = items,
+    )
+}
+
+fn package_opf(title: &str, chapters: &[Chapter], image_files: &[(String, &Image)]) -> String {
+    let mut manifest = String::from(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n",
+    );
+    let mut spine = String::new();
+    for (i, chapter) in chapters.iter().enumerate() {
+        let properties = if chapter.html.contains("<math") {
+            " properties=\"mathml\""
+        } else {
+            ""
+        };
+        manifest.push_str(&format!(
+            "<item id=\"chapter-{n}\" href=\"chapter-{n}.xhtml\" media-type=\"application/xhtml+xml\"{}/>\n",
+            properties,
+            n = i + 1
+        ));
+        spine.push_str(&format!("<itemref idref=\"chapter-{}\"/>\n", i + 1));
+    }
+    for (i, (path, image)) in image_files.iter().enumerate() {
+        manifest.push_str(&format!(
+            "<item id=\"image-{}\" href=\"{}\" media-type=\"{}\"/>\n",
+            i + 1,
+            path,
+            image.media_type
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id">
+<metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+<dc:identifier id="book-id">urn:uuid:{id}</dc:identifier>
+<dc:title>{title}</dc:title>
+<dc:language>en</dc:language>
+<dc:creator>rdrs</dc:creator>
+<meta property="dcterms:modified">{modified}</meta>
+</metadata>
+<manifest>
+{manifest}</manifest>
+<spine>
+{spine}</spine>
+</package>
+"#,
+        id = uuid::Uuid::new_v4(),
+        title = escape(title),
+        modified = Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+        manifest = manifest,
+        spine = spine,
+    )
+}
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+<rootfiles>
+<rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+</rootfiles>
+</container>
+"#;
+
+fn zip_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Internal(format!("Failed to build EPUB: {}", e))
+}
+
+/// Build an EPUB titled `title` with one chapter per entry. `images` maps
+/// image URLs in the chapters to the images to pack; other images are
+/// left out.
+pub fn build(
+    title: &str,
+    chapters: &[Chapter],
+    images: &HashMap<String, Image>,
+) -> AppResult<Vec<u8>> {
+    // Number images in reading order so books come out the same each time
+    let mut image_files: Vec<(String, &Image)> = Vec::new();
+    let mut image_paths: HashMap<&str, String> = HashMap::new();
+    for url in image_urls(chapters) {
+        let Some((url, image)) = images.get_key_value(&url) else {
+            continue;
+        };
+        let path = format!("images/{}.{}", image_files.len() + 1, image.extension());
+        image_paths.insert(url.as_str(), path.clone());
+        image_files.push((path, image));
+    }
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // The mimetype must come first and uncompressed
+    zip.start_file("mimetype", stored).map_err(zip_error)?;
+    zip.write_all(b"application/epub+zip").map_err(zip_error)?;
+    zip.start_file("META-INF/container.xml", deflated)
+        .map_err(zip_error)?;
+    zip.write_all(CONTAINER_XML.as_bytes()).map_err(zip_error)?;
+    zip.start_file("OEBPS/content.opf", deflated)
+        .map_err(zip_error)?;
+    zip.write_all(package_opf(title, chapters, &image_files).as_bytes())
+        .map_err(zip_error)?;
+    zip.start_file("OEBPS/nav.xhtml", deflated)
+        .map_err(zip_error)?;
+    zip.write_all(nav_xhtml(title, chapters).as_bytes())
+        .map_err(zip_error)?;
+    for (i, chapter) in chapters.iter().enumerate() {
+        zip.start_file(format!("OEBPS/chapter-{}.xhtml", i + 1), deflated)
+            .map_err(zip_error)?;
+        zip.write_all(chapter_xhtml(chapter, &image_paths).as_bytes())
+            .map_err(zip_error)?;
+    }
+    // Images are compressed already
+    for (path, image) in &image_files {
+        zip.start_file(format!("OEBPS/{}", path), stored)
+            .map_err(zip_error)?;
+        zip.write_all(&image.bytes).map_err(zip_error)?;
+    }
+
+    let cursor = zip.finish().map_err(zip_error)?;
+    Ok(cursor.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn chapter(html: &str) -> Chapter {
+        Chapter {
+            title: "A & B".to_string(),
+            feed_title: Some("Feed".to_string()),
+            author: None,
+            link: Some("https://example.com/a?x=1&y=2".to_string()),
+            published_at: None,
+            html: html.to_string(),
+        }
+    }
+
+    fn png() -> Vec<u8> {
+        b"\x89PNG\r\n\x1a\nrest".to_vec()
+    }
+
+    #[test]
+    fn test_to_xhtml() {
+        let images = HashMap::from([("https://example.com/a.png", "images/1.png".to_string())]);
+        let xhtml = to_xhtml(
+            r#"<p>One<br>two &amp; <img src="https://example.com/a.png" loading="lazy"> <img src="https://example.com/b.png" alt="Chart"></p><picture><source srcset="x"><img src="https://example.com/a.png"></picture>"#,
+            &images,
+        );
+        assert_eq!(
+            xhtml,
+            r#"<p>One<br/>two &amp; <img src="images/1.png" alt=""/> Chart</p><img src="images/1.png" alt=""/>"#
+        );
+    }
+
+    #[test]
+    fn test_sniff_image() {
+        assert_eq!(Image::sniff(png()).unwrap().media_type, "image/png");
+        assert_eq!(
+            Image::sniff(b"RIFF\0\0\0\0WEBPVP8 ".to_vec())
+                .unwrap()
+                .media_type,
+            "image/webp"
+        );
+        assert!(Image::sniff(b"<svg></svg>".to_vec()).is_none());
+    }
+
+    #[test]
+    fn test_build() {
+        let chapters = vec![
+            chapter(r#"<p><img src="https://example.com/a.png"></p>"#),
+            chapter(r#"<p><img src="https://example.com/missing.png">Text</p>"#),
+        ];
+        assert_eq!(
+            image_urls(&chapters),
+            vec![
+                "https://example.com/a.png".to_string(),
+                "https://example.com/missing.png".to_string()
+            ]
+        );
+        let images = HashMap::from([(
+            "https://example.com/a.png".to_string(),
+            Image::sniff(png()).unwrap(),
+        )]);
+
+        let book = build("Starred", &chapters, &images).unwrap();
+        // The mimetype is the first file, stored as is
+        assert_eq!(&book[30..38], b"mimetype");
+        let data = 38 + u16::from_le_bytes([book[28], book[29]]) as usize;
+        assert_eq!(&book[data..data + 20], b"application/epub+zip");
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(book)).unwrap();
+        let mut opf = String::new();
+        archive
+            .by_name("OEBPS/content.opf")
+            .unwrap()
+            .read_to_string(&mut opf)
+            .unwrap();
+        assert!(opf.contains(r#"href="images/1.png" media-type="image/png""#));
+        assert!(opf.contains(r#"<itemref idref="chapter-2"/>"#));
+
+        let mut second = String::new();
+        archive
+            .by_name("OEBPS/chapter-2.xhtml")
+            .unwrap()
+            .read_to_string(&mut second)
+            .unwrap();
+        assert!(second.contains("<title>A &amp; B</title>"));
+        assert!(second.contains("https://example.com/a?x=1&amp;y=2"));
+        assert!(second.contains("<p>Text</p>"));
+        assert!(archive.by_name("OEBPS/images/1.png").is_ok());
+    }
+}
@@ -0,0 +1,230 @@
+//! Encryption of per-feed cookie jars.
+//!
+//! Cookies are sealed with AES-256-GCM under a key derived from
+//! `FEED_COOKIE_SECRET`. The stored layout is `nonce (12) || ciphertext ||
+//! tag (16)`. Cookie values are never logged or returned by the API; only
+//! their names are shown.
+
+use hmac::{Hmac, Mac};
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use rand::RngCore;
+use reqwest::header::HeaderValue;
+use sha2::Sha256;
+use tracing::warn;
+use url::Url;
+
+use crate::db::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::feed_cookie;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// Maximum length of a feed's cookie header
+pub const MAX_COOKIE_LEN: usize = 8192;
+
+fn derive_key(secret: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC can take key of any size");
+    mac.update(b"rdrs feed cookies v1");
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn crypto_err(e: openssl::error::ErrorStack) -> AppError {
+    AppError::Internal(format!("Crypto error: {}", e))
+}
+
+/// Normalize a `Cookie` header value such as `"a=1; b=2"` as copied from a
+/// browser, rejecting pairs without a name
+pub fn parse_cookie_header(input: &str) -> AppResult<String> {
+    if input.len() > MAX_COOKIE_LEN {
+        return Err(AppError::Validation(format!(
+            "Cookies must be at most {} characters",
+            MAX_COOKIE_LEN
+        )));
+    }
+
+    let mut pairs = Vec::new();
+    for pair in input.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+        match pair.split_once('=') {
+            Some((name, value)) if !name.trim().is_empty() => {
+                pairs.push(format!("{}={}", name.trim(), value.trim()));
+            }
+            _ => {
+                return Err(AppError::Validation(
+                    "Cookies must be name=value pairs separated by semicolons".to_string(),
+                ))
+            }
+        }
+    }
+
+    let header = pairs.join("; ");
+    HeaderValue::from_str(&header)
+        .map_err(|_| AppError::Validation("Cookies contain invalid characters".to_string()))?;
+    Ok(header)
+}
+
+/// Names of the cookies in a normalized header, for display
+pub fn cookie_names(header: &str) -> Vec<String> {
+    header
+        .split("; ")
+        .filter_map(|pair| pair.split_once('=').map(|(name, _)| name.to_string()))
+        .collect()
+}
+
+/// Encrypt a cookie header for storage
+pub fn encrypt(secret: &[u8], header: &str) -> AppResult<Vec<u8>> {
+    let key = derive_key(secret);
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let mut tag = [0u8; TAG_LEN];
+    let ciphertext = encrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key,
+        Some(&nonce),
+        &[],
+        header.as_bytes(),
+        &mut tag,
+    )
+    .map_err(crypto_err)?;
+
+    let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len() + TAG_LEN);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    sealed.extend_from_slice(&tag);
+    Ok(sealed)
+}
+
+/// Decrypt a stored cookie header. Fails when the secret has changed since
+/// the cookies were saved.
+pub fn decrypt(secret: &[u8], sealed: &[u8]) -> AppResult<String> {
+    if sealed.len() < NONCE_LEN + TAG_LEN {
+        return Err(AppError::Internal("Stored cookies are corrupt".to_string()));
+    }
+    let (nonce, rest) = sealed.split_at(NONCE_LEN);
+    let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+
+    let key = derive_key(secret);
+    let plaintext = decrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key,
+        Some(nonce),
+        &[],
+        ciphertext,
+        tag,
+    )
+    .map_err(crypto_err)?;
+    String::from_utf8(plaintext)
+        .map_err(|_| AppError::Internal("Stored cookies are corrupt".to_string()))
+}
+
+/// `Cookie` header value marked sensitive so it is left out of HTTP client
+/// debug output
+pub fn cookie_header_value(header: &str) -> Option<HeaderValue> {
+    let mut value = HeaderValue::from_str(header).ok()?;
+    value.set_sensitive(true);
+    Some(value)
+}
+
+/// Whether a feed's cookies may go with a request to `url`. Entry links can
+/// point anywhere, so only the host the feed itself is fetched from gets
+/// them.
+pub fn sends_to(feed_url: &str, url: &str) -> bool {
+    let host = |u: &str| Url::parse(u).ok()?.host_str().map(str::to_string);
+    matches!((host(feed_url), host(url)), (Some(a), Some(b)) if a == b)
+}
+
+/// Load and decrypt the `Cookie` header for a feed. Failures are logged
+/// without the cookie values and the feed is fetched without cookies.
+pub async fn load_cookie_header(
+    db: &DbPool,
+    feed_id: i64,
+    secret: Option<&[u8]>,
+) -> Option<HeaderValue> {
+    let secret = secret?;
+    let sealed = match db
+        .background(move |conn| feed_cookie::find_cookies(conn, feed_id))
+        .await
+    {
+        Ok(Ok(sealed)) => sealed?,
+        Ok(Err(e)) => {
+            warn!("Failed to load cookies for feed {}: {}", feed_id, e);
+            return None;
+        }
+        Err(e) => {
+            warn!("Failed to access DB for feed {} cookies: {}", feed_id, e);
+            return None;
+        }
+    };
+
+    match decrypt(secret, &sealed) {
+        Ok(header) => cookie_header_value(&header),
+        Err(_) => {
+            warn!(
+                "Cookies for feed {} could not be decrypted; was FEED_COOKIE_SECRET changed?",
+                feed_id
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn test_parse_cookie_header() {
+        let header = parse_cookie_header(" session=abc ;theme=dark; ").unwrap();
+        assert_eq!(header, "session=abc; theme=dark");
+        assert_eq!(cookie_names(&header), vec!["session", "theme"]);
+
+        assert_eq!(parse_cookie_header("").unwrap(), "");
+        assert!(parse_cookie_header("novalue").is_err());
+        assert!(parse_cookie_header("=value").is_err());
+        assert!(parse_cookie_header("a=line\nbreak").is_err());
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        let sealed = encrypt(SECRET, "session=abc").unwrap();
+        assert!(!sealed.windows(3).any(|w| w == b"abc"));
+        assert_eq!(decrypt(SECRET, &sealed).unwrap(), "session=abc");
+
+        // Every encryption uses a fresh nonce
+        assert_ne!(sealed, encrypt(SECRET, "session=abc").unwrap());
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_secret() {
+        let sealed = encrypt(SECRET, "session=abc").unwrap();
+        assert!(decrypt(b"another-secret-of-some-length", &sealed).is_err());
+        assert!(decrypt(SECRET, &sealed[..10]).is_err());
+    }
+
+    #[test]
+    fn test_sends_to_feed_host_only() {
+        let feed_url = "https://members.example.com/feed.xml";
+        assert!(sends_to(feed_url, "https://members.example.com/posts/1"));
+        assert!(sends_to(
+            feed_url,
+            "http://MEMBERS.example.com:8080/posts/1"
+        ));
+        // Aggregator feeds link to other sites, which must not get the cookies
+        assert!(!sends_to(feed_url, "https://other.org/article"));
+        assert!(!sends_to(feed_url, "https://example.com/posts/1"));
+        assert!(!sends_to(feed_url, "https://members.example.com.evil.org/"));
+        assert!(!sends_to(feed_url, "not a url"));
+    }
+
+    #[test]
+    fn test_cookie_header_value_is_sensitive() {
+        let value = cookie_header_value("session=abc").unwrap();
+        assert!(value.is_sensitive());
+        assert_eq!(format!("{:?}", value), "Sensitive");
+    }
+}
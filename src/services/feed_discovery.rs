@@ -1,8 +1,15 @@
+use std::time::Duration;
+
 use scraper::{Html, Selector};
+use serde::Serialize;
+use tokio::time::{timeout_at, Instant};
 use url::Url;
 
 use crate::error::{AppError, AppResult};
-use crate::services::http::{send_with_retry, RetryConfig, DEFAULT_TIMEOUT};
+use crate::services::http::{
+    client_builder, send_with_retry, Outbound, RetryConfig, DEFAULT_TIMEOUT,
+};
+use crate::services::icon_fetcher;
 
 #[derive(Debug, Clone)]
 pub struct DiscoveredFeed {
@@ -10,18 +17,98 @@ pub struct DiscoveredFeed {
     pub title: Option<String>,
     pub description: Option<String>,
     pub site_url: Option<String>,
+    /// Categories and tags the feed declares for itself and its entries,
+    /// most common first
+    pub categories: Vec<String>,
 }
 
-pub async fn discover_feed(url: &str, user_agent: &str) -> AppResult<DiscoveredFeed> {
-    // Validate URL
+/// Declared categories kept for category suggestion
+const MAX_DECLARED_CATEGORIES: usize = 20;
+
+/// How long `discover_metadata` waits for all of its steps together
+pub const METADATA_BUDGET: Duration = Duration::from_secs(15);
+
+/// The parts of `discover_metadata` that can fail on their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataStep {
+    Feed,
+    Icon,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepError {
+    pub step: MetadataStep,
+    pub error: String,
+}
+
+/// What could be learned about a URL in time
+#[derive(Debug, Clone, Default)]
+pub struct FeedMetadata {
+    pub feed: Option<DiscoveredFeed>,
+    /// Where the site's icon was found
+    pub icon_url: Option<String>,
+    /// Steps that failed or ran out of time
+    pub errors: Vec<StepError>,
+}
+
+/// Find the feed behind `url` and the site's icon at the same time, giving
+/// up on whatever has not finished within `budget`. Only an unusable URL is
+/// an error; anything else is reported per step next to what was found.
+pub async fn discover_metadata(
+    url: &str,
+    user_agent: &str,
+    budget: Duration,
+) -> AppResult<FeedMetadata> {
+    parse_url(url)?;
+
+    let deadline = Instant::now() + budget;
+    let (feed, icon) = tokio::join!(
+        timeout_at(deadline, discover_feed(url, user_agent)),
+        timeout_at(deadline, icon_fetcher::fetch_favicon(url, user_agent)),
+    );
+
+    let timed_out = || format!("Timed out after {}s", budget.as_secs());
+    let mut metadata = FeedMetadata::default();
+    match feed {
+        Ok(Ok(feed)) => metadata.feed = Some(feed),
+        Ok(Err(e)) => metadata.errors.push(StepError {
+            step: MetadataStep::Feed,
+            error: e.to_string(),
+        }),
+        Err(_) => metadata.errors.push(StepError {
+            step: MetadataStep::Feed,
+            error: timed_out(),
+        }),
+    }
+    match icon {
+        Ok(Ok(icon)) => metadata.icon_url = icon.map(|i| i.source_url),
+        Ok(Err(e)) => metadata.errors.push(StepError {
+            step: MetadataStep::Icon,
+            error: e.to_string(),
+        }),
+        Err(_) => metadata.errors.push(StepError {
+            step: MetadataStep::Icon,
+            error: timed_out(),
+        }),
+    }
+    Ok(metadata)
+}
+
+fn parse_url(url: &str) -> AppResult<Url> {
     let parsed_url = Url::parse(url).map_err(|_| AppError::InvalidUrl)?;
 
     if parsed_url.scheme() != "http" && parsed_url.scheme() != "https" {
         return Err(AppError::InvalidUrl);
     }
+    Ok(parsed_url)
+}
+
+pub async fn discover_feed(url: &str, user_agent: &str) -> AppResult<DiscoveredFeed> {
+    let parsed_url = parse_url(url)?;
 
     // Fetch the URL
-    let client = reqwest::Client::builder()
+    let client = client_builder(Outbound::Content)?
         .timeout(DEFAULT_TIMEOUT)
         .user_agent(user_agent)
         .build()
@@ -130,10 +217,64 @@ fn parse_feed_content(feed_url: &str, content: &str) -> AppResult<DiscoveredFeed
         .find(|link| link.rel.as_deref() == Some("alternate") || link.rel.is_none())
         .map(|link| link.href.clone());
 
+    // Feed-level categories count once per entry so they outrank entry tags
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    let feed_weight = feed.entries.len().max(1);
+    let entry_tags = feed.entries.iter().flat_map(|e| &e.categories);
+    let declared = feed
+        .categories
+        .iter()
+        .map(|c| (c, feed_weight))
+        .chain(entry_tags.map(|c| (c, 1)));
+    for (c, weight) in declared {
+        let name = c.label.as_deref().unwrap_or(&c.term).trim();
+        if name.is_empty() {
+            continue;
+        }
+        let existing = counts
+            .iter_mut()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name));
+        match existing {
+            Some((_, count)) => *count += weight,
+            None => counts.push((name.to_string(), weight)),
+        }
+    }
+    counts.sort_by_key(|c| std::cmp::Reverse(c.1));
+    let categories = counts
+        .into_iter()
+        .take(MAX_DECLARED_CATEGORIES)
+        .map(|(name, _)| name)
+        .collect();
+
     Ok(DiscoveredFeed {
         feed_url: feed_url.to_string(),
         title,
         description,
         site_url,
+        categories,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_discover_metadata_rejects_bad_url() {
+        let result = discover_metadata("ftp://example.com/feed", "rdrs", METADATA_BUDGET).await;
+        assert!(matches!(result, Err(AppError::InvalidUrl)));
+    }
+
+    #[tokio::test]
+    async fn test_discover_metadata_reports_each_step() {
+        // Nothing can finish in no time, so both steps report an error
+        // instead of failing the whole lookup
+        let metadata = discover_metadata("https://example.invalid/", "rdrs", Duration::ZERO)
+            .await
+            .unwrap();
+        assert!(metadata.feed.is_none());
+        assert!(metadata.icon_url.is_none());
+        let steps: Vec<_> = metadata.errors.iter().map(|e| e.step).collect();
+        assert_eq!(steps, vec![MetadataStep::Feed, MetadataStep::Icon]);
+    }
+}
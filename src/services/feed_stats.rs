@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::heartbeat::{Heartbeat, HEARTBEAT_INTERVAL};
+use crate::db::DbPool;
+use crate::models::{feed, feed_stats};
+
+/// Start the worker that periodically rebuilds the weekly posting history
+/// behind `GET /api/feeds/{id}/stats` and the engagement scores behind
+/// `GET /api/feeds?order=engagement`. It runs once right away so both are
+/// available soon after startup.
+///
+/// # Arguments
+/// * `db` - Database connection
+/// * `interval_hours` - How often to rebuild (in hours)
+/// * `heartbeat` - Reported to while the worker is running
+/// * `cancel_token` - Token to signal graceful shutdown
+pub fn start_feed_stats_worker(
+    db: DbPool,
+    interval_hours: u64,
+    heartbeat: Heartbeat,
+    cancel_token: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        tracing::info!("Feed stats worker started: interval={}h", interval_hours);
+
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_hours * 3600));
+        let mut beat = tokio::time::interval(HEARTBEAT_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    tracing::info!("Feed stats worker stopping...");
+                    break;
+                }
+                _ = beat.tick() => {
+                    heartbeat.beat();
+                }
+                _ = interval.tick() => {
+                    match db.background(feed_stats::refresh_weekly).await {
+                        Ok(Ok(rows)) => tracing::debug!("Refreshed {} weekly feed stats", rows),
+                        Ok(Err(e)) => tracing::error!("Failed to refresh feed stats: {}", e),
+                        Err(e) => tracing::error!("Failed to access DB for feed stats: {}", e),
+                    }
+
+                    match db.background(feed::refresh_engagement_scores).await {
+                        Ok(Ok(feeds)) => {
+                            tracing::debug!("Refreshed engagement scores of {} feeds", feeds);
+                        }
+                        Ok(Err(e)) => tracing::error!("Failed to refresh engagement scores: {}", e),
+                        Err(e) => tracing::error!("Failed to access DB for feed stats: {}", e),
+                    }
+                }
+            }
+        }
+
+        tracing::info!("Feed stats worker stopped");
+    })
+}
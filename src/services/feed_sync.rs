@@ -1,13 +1,30 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
 use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
-use reqwest::header::{HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::header::{HeaderMap, HeaderValue, COOKIE, IF_MODIFIED_SINCE, IF_NONE_MATCH};
 use serde::Serialize;
 use tracing::{debug, error, info, warn};
 
 use crate::db::DbPool;
 use crate::error::{AppError, AppResult};
-use crate::models::{entry, feed, image};
-use crate::services::http::{send_with_retry, RetryConfig, DEFAULT_TIMEOUT};
+use crate::models::author::{self, AuthorInput};
+use crate::models::feed::{DateStrategy, UrlSource};
+use crate::models::feed_rewrite::{self, RewriteField};
+use crate::models::{entry, feed, feed_script, image};
+use crate::services::feed_cookies;
+use crate::services::http::{
+    allows, client_builder, custom_header_map, send_with_retry, Outbound, RetryConfig,
+    DEFAULT_TIMEOUT,
+};
 use crate::services::icon_fetcher;
+use crate::services::interest;
+use crate::services::jobs;
+use crate::services::refresh_registry::RefreshRegistry;
+use crate::services::rewrite::Rewriter;
+use crate::services::scripting::{EntryHook, ScriptEntry};
 
 /// Parse Chinese month names to month number
 fn parse_chinese_month(s: &str) -> Option<u32> {
@@ -140,39 +157,436 @@ fn normalize_timezone_format(text: &str) -> String {
     text.to_string()
 }
 
+/// Clock skew allowed before a published date counts as being in the future
+const FUTURE_DATE_TOLERANCE_MINUTES: i64 = 10;
+
+/// Apply a feed's date strategy to the publication date declared by an entry
+pub fn sanitize_published_at(
+    strategy: DateStrategy,
+    declared: Option<DateTime<Utc>>,
+    feed_timestamp: Option<DateTime<Utc>>,
+    fetched_at: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    match strategy {
+        DateStrategy::Trust => declared.or(feed_timestamp),
+        DateStrategy::FetchTime => Some(fetched_at),
+        DateStrategy::Clamp => {
+            let limit = fetched_at + chrono::Duration::minutes(FUTURE_DATE_TOLERANCE_MINUTES);
+            let date = declared.or(feed_timestamp).unwrap_or(fetched_at);
+            Some(if date > limit { fetched_at } else { date })
+        }
+    }
+}
+
+/// Collect the date string of each item exactly as written in the feed,
+/// keyed by guid/id and by link. Published dates win over updated dates.
+fn extract_raw_dates(body: &str) -> HashMap<String, String> {
+    let mut reader = Reader::from_str(body);
+    reader.config_mut().trim_text(true);
+
+    let mut raw_dates = HashMap::new();
+    let mut in_item = false;
+    let mut current_tag: Vec<u8> = Vec::new();
+    let mut keys: Vec<String> = Vec::new();
+    // (priority, value): lower priority wins
+    let mut date: Option<(u8, String)> = None;
+
+    loop {
+        let text = match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = e.local_name().as_ref().to_vec();
+                if name == b"item" || name == b"entry" {
+                    in_item = true;
+                    keys.clear();
+                    date = None;
+                } else if in_item {
+                    current_tag = name;
+                }
+                continue;
+            }
+            Ok(Event::Empty(e)) => {
+                // Atom links carry the URL in the href attribute
+                if in_item && e.local_name().as_ref() == b"link" {
+                    for attr in e.attributes().flatten() {
+                        if attr.key.as_ref() == b"href" {
+                            keys.push(String::from_utf8_lossy(&attr.value).trim().to_string());
+                        }
+                    }
+                }
+                continue;
+            }
+            Ok(Event::Text(t)) => String::from_utf8_lossy(&t).trim().to_string(),
+            Ok(Event::CData(t)) => String::from_utf8_lossy(&t).trim().to_string(),
+            Ok(Event::End(e)) => {
+                let name = e.local_name();
+                if name.as_ref() == b"item" || name.as_ref() == b"entry" {
+                    in_item = false;
+                    if let Some((_, raw)) = date.take() {
+                        for key in keys.drain(..) {
+                            raw_dates.insert(key, raw.clone());
+                        }
+                    }
+                }
+                current_tag.clear();
+                continue;
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => continue,
+        };
+
+        if !in_item || text.is_empty() {
+            continue;
+        }
+
+        let priority = match current_tag.as_slice() {
+            b"guid" | b"id" | b"link" => {
+                keys.push(text);
+                continue;
+            }
+            b"pubDate" | b"published" | b"date" | b"issued" => 0,
+            b"updated" | b"modified" => 1,
+            _ => continue,
+        };
+
+        if date.as_ref().is_none_or(|(p, _)| priority < *p) {
+            date = Some((priority, text));
+        }
+    }
+
+    raw_dates
+}
+
+/// Discussion link and comment count of a feed item
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct RawComments {
+    url: Option<String>,
+    count: Option<i64>,
+}
+
+/// `href`, `rel`, `type` and `thr:count` of a link element
+fn link_attrs(
+    e: &quick_xml::events::BytesStart,
+) -> (Option<String>, Option<String>, Option<String>, Option<i64>) {
+    let (mut href, mut rel, mut kind, mut count) = (None, None, None, None);
+    for attr in e.attributes().flatten() {
+        let value = String::from_utf8_lossy(&attr.value).trim().to_string();
+        match attr.key.as_ref() {
+            b"href" => href = Some(value),
+            b"rel" => rel = Some(value),
+            b"type" => kind = Some(value),
+            b"thr:count" => count = value.parse().ok(),
+            _ => {}
+        }
+    }
+    (href, rel, kind, count)
+}
+
+/// Comment links and counts feed_rs does not expose, keyed by the item's guid
+/// and link. Reads RSS `<comments>`, `wfw:commentRss` and `slash:comments`,
+/// and Atom `<link rel="replies">` with `thr:count` or `thr:total`. A
+/// discussion page is preferred over a comment feed.
+fn extract_raw_comments(body: &str) -> HashMap<String, RawComments> {
+    let mut reader = Reader::from_str(body);
+    reader.config_mut().trim_text(true);
+
+    let mut raw_comments = HashMap::new();
+    let mut in_item = false;
+    let mut current_tag: Vec<u8> = Vec::new();
+    let mut keys: Vec<String> = Vec::new();
+    let mut page_url: Option<String> = None;
+    let mut feed_url: Option<String> = None;
+    let mut count: Option<i64> = None;
+
+    loop {
+        let link = match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = e.local_name().as_ref().to_vec();
+                if name == b"item" || name == b"entry" {
+                    in_item = true;
+                    keys.clear();
+                    page_url = None;
+                    feed_url = None;
+                    count = None;
+                }
+                current_tag = e.name().as_ref().to_vec();
+                (in_item && name == b"link").then(|| link_attrs(&e))
+            }
+            Ok(Event::Empty(e)) => {
+                (in_item && e.local_name().as_ref() == b"link").then(|| link_attrs(&e))
+            }
+            Ok(Event::Text(t)) => {
+                let text = String::from_utf8_lossy(&t).trim().to_string();
+                if in_item && !text.is_empty() {
+                    match current_tag.as_slice() {
+                        b"slash:comments" | b"thr:total" => count = text.parse().ok().or(count),
+                        b"comments" if text.starts_with("http") => page_url = Some(text),
+                        b"wfw:commentRss" | b"wfw:commentRSS" => feed_url = Some(text),
+                        b"guid" | b"id" | b"link" => keys.push(text),
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            Ok(Event::End(e)) => {
+                let name = e.local_name();
+                if name.as_ref() == b"item" || name.as_ref() == b"entry" {
+                    in_item = false;
+                    let url = page_url.take().or(feed_url.take());
+                    if url.is_some() || count.is_some() {
+                        let comments = RawComments { url, count };
+                        for key in keys.drain(..) {
+                            raw_comments.insert(key, comments.clone());
+                        }
+                    }
+                }
+                current_tag.clear();
+                continue;
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => continue,
+        };
+
+        let Some((Some(href), rel, kind, thr_count)) = link else {
+            continue;
+        };
+        match rel.as_deref() {
+            Some("replies") => {
+                if kind.as_deref() == Some("text/html") {
+                    page_url = Some(href);
+                } else {
+                    feed_url = Some(href);
+                }
+                count = thr_count.or(count);
+            }
+            None | Some("alternate") => keys.push(href),
+            _ => {}
+        }
+    }
+
+    raw_comments
+}
+
+/// Author details feed_rs does not expose
+#[derive(Debug, Default)]
+struct RawAuthors {
+    /// `itunes:author` of each item, keyed by the item's guid and link
+    itunes: HashMap<String, String>,
+    /// Avatar URL by author name, from images nested in `<author>` elements
+    /// such as Blogger's `gd:image` or `media:thumbnail`
+    avatars: HashMap<String, String>,
+}
+
+/// Absolute http(s) URL of an avatar image element, if `e` is one
+fn avatar_attr(e: &quick_xml::events::BytesStart) -> Option<String> {
+    if !matches!(e.local_name().as_ref(), b"image" | b"thumbnail" | b"avatar") {
+        return None;
+    }
+    e.attributes()
+        .flatten()
+        .filter(|attr| matches!(attr.key.local_name().as_ref(), b"src" | b"url" | b"href"))
+        .map(|attr| String::from_utf8_lossy(&attr.value).trim().to_string())
+        .find(|url| url.starts_with("https://") || url.starts_with("http://"))
+}
+
+fn extract_raw_authors(body: &str) -> RawAuthors {
+    let mut reader = Reader::from_str(body);
+    reader.config_mut().trim_text(true);
+
+    let mut raw = RawAuthors::default();
+    let mut in_item = false;
+    let mut in_author = false;
+    let mut current_tag: Vec<u8> = Vec::new();
+    let mut keys: Vec<String> = Vec::new();
+    let mut itunes_author: Option<String> = None;
+    let mut author_name: Option<String> = None;
+    let mut avatar: Option<String> = None;
+
+    loop {
+        let text = match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = e.local_name().as_ref().to_vec();
+                let is_itunes = e.name().as_ref() == b"itunes:author";
+                if name == b"item" || name == b"entry" {
+                    in_item = true;
+                    keys.clear();
+                    itunes_author = None;
+                } else if name == b"author" && !is_itunes {
+                    in_author = true;
+                    author_name = None;
+                    avatar = None;
+                } else if in_author {
+                    avatar = avatar_attr(&e).or(avatar);
+                }
+                current_tag = if is_itunes {
+                    b"itunes:author".to_vec()
+                } else {
+                    name
+                };
+                continue;
+            }
+            Ok(Event::Empty(e)) => {
+                if in_author {
+                    avatar = avatar_attr(&e).or(avatar);
+                } else if in_item && e.local_name().as_ref() == b"link" {
+                    for attr in e.attributes().flatten() {
+                        if attr.key.as_ref() == b"href" {
+                            keys.push(String::from_utf8_lossy(&attr.value).trim().to_string());
+                        }
+                    }
+                }
+                continue;
+            }
+            Ok(Event::Text(t)) => String::from_utf8_lossy(&t).trim().to_string(),
+            Ok(Event::CData(t)) => String::from_utf8_lossy(&t).trim().to_string(),
+            Ok(Event::End(e)) => {
+                let name = e.local_name();
+                if in_author && name.as_ref() == b"author" && e.name().as_ref() != b"itunes:author"
+                {
+                    in_author = false;
+                    if let (Some(author), Some(url)) = (author_name.take(), avatar.take()) {
+                        raw.avatars.insert(author, url);
+                    }
+                } else if name.as_ref() == b"item" || name.as_ref() == b"entry" {
+                    in_item = false;
+                    if let Some(author) = itunes_author.take() {
+                        for key in keys.drain(..) {
+                            raw.itunes.insert(key, author.clone());
+                        }
+                    }
+                }
+                current_tag.clear();
+                continue;
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => continue,
+        };
+
+        if text.is_empty() {
+            continue;
+        }
+
+        match current_tag.as_slice() {
+            b"itunes:author" if in_item => itunes_author = Some(text),
+            b"name" if in_author => author_name = Some(text),
+            b"guid" | b"id" | b"link" if in_item && !in_author => keys.push(text),
+            _ => {}
+        }
+    }
+
+    raw
+}
+
+/// Normalize a feed author. RSS `<author>` holds `email (Name)`, which is
+/// split into its parts; a bare email is used as the name.
+fn author_input(name: &str, email: Option<&str>, uri: Option<&str>) -> Option<AuthorInput> {
+    let name = name.trim();
+    let mut email = email
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(String::from);
+
+    let name = match name.split_once(" (") {
+        Some((address, display)) if address.contains('@') && display.ends_with(')') => {
+            email.get_or_insert_with(|| address.to_string());
+            display.trim_end_matches(')').trim().to_string()
+        }
+        _ => name.to_string(),
+    };
+    let name = if name.is_empty() {
+        email.clone()?
+    } else {
+        name
+    };
+
+    Some(AuthorInput {
+        name,
+        email,
+        uri: uri
+            .map(str::trim)
+            .filter(|u| !u.is_empty())
+            .map(String::from),
+        avatar_url: None,
+    })
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct SyncResult {
     pub new_entries: i64,
     pub updated_entries: i64,
+    /// IDs of entries inserted by this sync, used for notifications
+    #[serde(skip)]
+    pub new_entry_ids: Vec<i64>,
+    /// Whether the feed had no entries before this sync, so everything in it
+    /// is backlog rather than news
+    #[serde(skip)]
+    pub first_fetch: bool,
 }
 
 pub async fn refresh_feed(
     db: DbPool,
     feed_id: i64,
     default_user_agent: &str,
+    cookie_secret: Option<&[u8]>,
 ) -> AppResult<SyncResult> {
     let feed_data = db
         .background(move |conn| feed::find_by_id(conn, feed_id))
         .await??
         .ok_or(AppError::FeedNotFound)?;
 
+    if feed_data.synthetic {
+        return Err(AppError::Validation(
+            "This feed holds imported entries and cannot be refreshed".to_string(),
+        ));
+    }
+
     // Use per-feed custom user agent if set, otherwise use global default
     let effective_user_agent = feed_data
         .custom_user_agent
         .as_deref()
         .unwrap_or(default_user_agent);
 
+    let mut default_headers = custom_header_map(&feed_data.custom_headers);
+    if let Some(cookie) = feed_cookies::load_cookie_header(&db, feed_id, cookie_secret).await {
+        default_headers.insert(COOKIE, cookie);
+    }
+
+    // Track the leading run of permanent redirects so a moved feed can be
+    // migrated to its new URL
+    let redirect_state: Arc<Mutex<(Option<String>, bool)>> = Arc::new(Mutex::new((None, false)));
+    let policy_state = redirect_state.clone();
+    let redirect_policy = reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() > 10 {
+            return attempt.error("too many redirects");
+        }
+        let permanent = matches!(
+            attempt.status(),
+            reqwest::StatusCode::MOVED_PERMANENTLY | reqwest::StatusCode::PERMANENT_REDIRECT
+        );
+        if let Ok(mut state) = policy_state.lock() {
+            if !state.1 {
+                if permanent {
+                    state.0 = Some(attempt.url().to_string());
+                } else {
+                    state.1 = true;
+                }
+            }
+        }
+        attempt.follow()
+    });
+
     // Build HTTP client with per-feed settings
-    let mut client_builder = reqwest::Client::builder()
+    let mut builder = client_builder(Outbound::Content)?
         .timeout(DEFAULT_TIMEOUT)
-        .user_agent(effective_user_agent);
+        .user_agent(effective_user_agent)
+        .default_headers(default_headers)
+        .redirect(redirect_policy);
 
     // Disable HTTP/2 if configured for this feed
     if feed_data.http2_disabled {
-        client_builder = client_builder.http1_only();
+        builder = builder.http1_only();
     }
 
-    let client = client_builder
+    let client = builder
         .build()
         .map_err(|e| AppError::FetchError(e.to_string()))?;
 
@@ -237,6 +651,8 @@ pub async fn refresh_feed(
         return Ok(SyncResult {
             new_entries: 0,
             updated_entries: 0,
+            new_entry_ids: Vec::new(),
+            first_fetch: false,
         });
     }
 
@@ -314,6 +730,23 @@ pub async fn refresh_feed(
         }
     };
 
+    // New URL candidate: a permanent redirect wins over <link rel="self">,
+    // which is only ever a suggestion
+    let redirected_url = redirect_state.lock().ok().and_then(|state| state.0.clone());
+    let self_url = parsed_feed
+        .links
+        .iter()
+        .find(|l| l.rel.as_deref() == Some("self"))
+        .map(|l| l.href.clone())
+        .filter(|href| {
+            url::Url::parse(href)
+                .map(|u| u.scheme() == "http" || u.scheme() == "https")
+                .unwrap_or(false)
+        });
+    let observed_url = redirected_url
+        .map(|u| (u, UrlSource::PermanentRedirect))
+        .or(self_url.map(|u| (u, UrlSource::SelfLink)));
+
     // Extract icon URLs before consuming parsed_feed
     let icon_url = parsed_feed.icon.as_ref().map(|i| i.uri.clone());
     let logo_url = parsed_feed.logo.as_ref().map(|l| l.uri.clone());
@@ -323,8 +756,8 @@ pub async fn refresh_feed(
         .background(move |conn| image::needs_refresh(conn, image::ENTITY_FEED, feed_id, 7))
         .await??;
 
-    // Fetch icon if needed (every 7 days)
-    if needs_icon_refresh {
+    // Fetch icon if needed (every 7 days); privacy mode fetches none
+    if needs_icon_refresh && allows(Outbound::Icon) {
         match icon_fetcher::fetch_feed_icon(
             icon_url.as_deref(),
             logo_url.as_deref(),
@@ -368,16 +801,33 @@ pub async fn refresh_feed(
         }
     }
 
+    // Original date strings, kept for debugging sanitized dates
+    let raw_dates = extract_raw_dates(&body);
+    let raw_authors = extract_raw_authors(&body);
+    let raw_comments = extract_raw_comments(&body);
+    let date_strategy = feed_data.date_strategy;
+    let fetched_at = Utc::now();
+
     // Extract feed-level timestamp as fallback for entries without dates
     let feed_timestamp = parsed_feed
         .updated
         .or(parsed_feed.published)
         .map(|dt| dt.with_timezone(&Utc));
 
-    let (new_entries, updated_entries) = db
+    let (new_entries, updated_entries, new_entry_ids) = db
         .background(move |conn| {
             let mut new_entries = 0i64;
             let mut updated_entries = 0i64;
+            let mut new_entry_ids = Vec::new();
+
+            let rewriter = Rewriter::new(&feed_rewrite::list_by_feed(conn, feed_id)?);
+            // A script that no longer compiles is ignored so the feed keeps syncing
+            let hook = match feed_script::find_script(conn, feed_id)? {
+                Some(source) => EntryHook::compile(&source)
+                    .map_err(|e| warn!("Script of feed {} does not compile: {}", feed_id, e))
+                    .ok(),
+                None => None,
+            };
 
             for item in parsed_feed.entries {
                 let guid = item.id;
@@ -393,17 +843,78 @@ pub async fn refresh_feed(
 
                 let summary = item.summary.map(|s| s.content);
 
-                let author = item.authors.first().map(|a| a.name.clone());
+                let mut authors: Vec<AuthorInput> = item
+                    .authors
+                    .iter()
+                    .filter_map(|p| author_input(&p.name, p.email.as_deref(), p.uri.as_deref()))
+                    .collect();
+                if authors.is_empty() {
+                    let itunes = raw_authors
+                        .itunes
+                        .get(&guid)
+                        .or_else(|| link.as_ref().and_then(|l| raw_authors.itunes.get(l)));
+                    authors.extend(itunes.and_then(|name| author_input(name, None, None)));
+                }
+                for a in &mut authors {
+                    if a.avatar_url.is_none() {
+                        a.avatar_url = raw_authors.avatars.get(&a.name).cloned();
+                    }
+                }
+                let author = authors.first().map(|a| a.name.clone());
 
-                // Use published date, fall back to updated date, then feed timestamp
-                // If no date is available, use None so sorting falls back to created_at
-                let published_at = item
+                // Use published date, fall back to updated date, then let the
+                // feed's date strategy fill in missing or future dates
+                let declared = item
                     .published
                     .or(item.updated)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .or(feed_timestamp);
+                    .map(|dt| dt.with_timezone(&Utc));
+                let published_at =
+                    sanitize_published_at(date_strategy, declared, feed_timestamp, fetched_at);
+
+                let published_raw = raw_dates
+                    .get(&guid)
+                    .or_else(|| link.as_ref().and_then(|l| raw_dates.get(l)));
+                let comments = raw_comments
+                    .get(&guid)
+                    .or_else(|| link.as_ref().and_then(|l| raw_comments.get(l)));
+
+                let title = rewriter.apply(RewriteField::Title, title);
+                let content = rewriter.apply(RewriteField::Content, content);
+
+                // The script sees the entry after the rewrite rules and can change
+                // or drop it. Entries it fails on are stored as they are.
+                let (title, link, content, summary, author) = match &hook {
+                    Some(hook) => {
+                        let input = ScriptEntry {
+                            title,
+                            link,
+                            content,
+                            summary,
+                            author,
+                        };
+                        match hook.run(input.clone()) {
+                            Ok(Some(e)) => (e.title, e.link, e.content, e.summary, e.author),
+                            Ok(None) => {
+                                debug!("Script of feed {} skipped entry {}", feed_id, guid);
+                                continue;
+                            }
+                            Err(e) => {
+                                warn!("Script of feed {} failed on {}: {}", feed_id, guid, e);
+                                let ScriptEntry {
+                                    title,
+                                    link,
+                                    content,
+                                    summary,
+                                    author,
+                                } = input;
+                                (title, link, content, summary, author)
+                            }
+                        }
+                    }
+                    None => (title, link, content, summary, author),
+                };
 
-                let (_, is_new) = entry::upsert_entry(
+                let (saved, is_new) = entry::upsert_entry(
                     conn,
                     feed_id,
                     &guid,
@@ -415,8 +926,21 @@ pub async fn refresh_feed(
                     published_at,
                 )?;
 
+                let author_ids = authors
+                    .iter()
+                    .map(|a| author::upsert_author(conn, feed_id, a).map(|a| a.id))
+                    .collect::<AppResult<Vec<_>>>()?;
+                author::set_entry_authors(conn, saved.id, &author_ids)?;
+                if let Some(comments) = comments {
+                    entry::set_comments(conn, saved.id, comments.url.as_deref(), comments.count)?;
+                }
+
                 if is_new {
+                    if let Some(raw) = published_raw {
+                        entry::set_published_raw(conn, saved.id, Some(raw))?;
+                    }
                     new_entries += 1;
+                    new_entry_ids.push(saved.id);
                 } else {
                     updated_entries += 1;
                 }
@@ -426,13 +950,26 @@ pub async fn refresh_feed(
             feed::update_fetch_result(
                 conn,
                 feed_id,
-                Utc::now(),
+                fetched_at,
                 None,
                 new_etag.as_deref(),
                 new_last_modified.as_deref(),
             )?;
 
-            Ok::<_, AppError>((new_entries, updated_entries))
+            if let Some(new_url) = feed::record_url_observation(
+                conn,
+                feed_id,
+                observed_url
+                    .as_ref()
+                    .map(|(u, source)| (u.as_str(), *source)),
+            )? {
+                info!("Feed {} moved to {}", feed_id, new_url);
+            }
+
+            jobs::tag::queue_new_entries(conn, feed_id, &new_entry_ids)?;
+            interest::score_new_entries(conn, feed_id, &new_entry_ids)?;
+
+            Ok::<_, AppError>((new_entries, updated_entries, new_entry_ids))
         })
         .await??;
 
@@ -444,6 +981,8 @@ pub async fn refresh_feed(
     Ok(SyncResult {
         new_entries,
         updated_entries,
+        new_entry_ids,
+        first_fetch: feed_data.entry_count == 0,
     })
 }
 
@@ -451,6 +990,8 @@ pub async fn refresh_bucket(
     db: DbPool,
     bucket: u8,
     user_agent: &str,
+    cookie_secret: Option<&[u8]>,
+    refreshes: &RefreshRegistry,
 ) -> Vec<(i64, Result<SyncResult, String>)> {
     let feeds = match db
         .background(move |conn| feed::list_by_bucket(conn, bucket))
@@ -477,7 +1018,11 @@ pub async fn refresh_bucket(
     let mut results = Vec::new();
 
     for feed_data in feeds {
-        let result = refresh_feed(db.clone(), feed_data.id, user_agent).await;
+        let result = refreshes
+            .run(feed_data.id, || {
+                refresh_feed(db.clone(), feed_data.id, user_agent, cookie_secret)
+            })
+            .await;
         match &result {
             Ok(sync) => {
                 debug!(
@@ -556,4 +1101,232 @@ mod tests {
         // Chinese format
         assert!(parse_timestamp("週四, 22 一月 2026 15:09:47 +0800").is_some());
     }
+
+    #[test]
+    fn test_sanitize_published_at_clamp() {
+        let now = Utc::now();
+        let past = now - chrono::Duration::days(1);
+        let future = now + chrono::Duration::days(1);
+
+        // Valid dates are kept
+        assert_eq!(
+            sanitize_published_at(DateStrategy::Clamp, Some(past), None, now),
+            Some(past)
+        );
+        // Future dates are clamped to fetch time
+        assert_eq!(
+            sanitize_published_at(DateStrategy::Clamp, Some(future), None, now),
+            Some(now)
+        );
+        // Missing dates fall back to the feed date, then fetch time
+        assert_eq!(
+            sanitize_published_at(DateStrategy::Clamp, None, Some(past), now),
+            Some(past)
+        );
+        assert_eq!(
+            sanitize_published_at(DateStrategy::Clamp, None, None, now),
+            Some(now)
+        );
+    }
+
+    #[test]
+    fn test_sanitize_published_at_other_strategies() {
+        let now = Utc::now();
+        let future = now + chrono::Duration::days(1);
+
+        assert_eq!(
+            sanitize_published_at(DateStrategy::Trust, Some(future), None, now),
+            Some(future)
+        );
+        assert_eq!(
+            sanitize_published_at(DateStrategy::Trust, None, None, now),
+            None
+        );
+        assert_eq!(
+            sanitize_published_at(DateStrategy::FetchTime, Some(future), None, now),
+            Some(now)
+        );
+    }
+
+    #[test]
+    fn test_extract_raw_dates() {
+        let rss = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <channel>
+    <title>Test</title>
+    <pubDate>Mon, 01 Jan 2024 00:00:00 +0000</pubDate>
+    <item>
+      <guid>item-1</guid>
+      <link>https://example.com/1</link>
+      <pubDate>Thu, 01 Jan 2099 00:00:00 +0000</pubDate>
+    </item>
+    <item>
+      <link>https://example.com/2</link>
+      <dc:date>2024-02-03</dc:date>
+    </item>
+    <item>
+      <guid>item-3</guid>
+    </item>
+  </channel>
+</rss>"#;
+
+        let raw = extract_raw_dates(rss);
+        assert_eq!(
+            raw.get("item-1").map(String::as_str),
+            Some("Thu, 01 Jan 2099 00:00:00 +0000")
+        );
+        assert_eq!(
+            raw.get("https://example.com/1").map(String::as_str),
+            Some("Thu, 01 Jan 2099 00:00:00 +0000")
+        );
+        assert_eq!(
+            raw.get("https://example.com/2").map(String::as_str),
+            Some("2024-02-03")
+        );
+        assert!(!raw.contains_key("item-3"));
+    }
+
+    #[test]
+    fn test_extract_raw_dates_atom_prefers_published() {
+        let atom = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Test</title>
+  <entry>
+    <id>urn:uuid:1</id>
+    <link href="https://example.com/a"/>
+    <updated>2024-03-02T00:00:00Z</updated>
+    <published>2024-03-01T00:00:00Z</published>
+  </entry>
+</feed>"#;
+
+        let raw = extract_raw_dates(atom);
+        assert_eq!(
+            raw.get("urn:uuid:1").map(String::as_str),
+            Some("2024-03-01T00:00:00Z")
+        );
+        assert_eq!(
+            raw.get("https://example.com/a").map(String::as_str),
+            Some("2024-03-01T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_extract_raw_comments_rss() {
+        let rss = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:wfw="http://wellformedweb.org/CommentAPI/"
+     xmlns:slash="http://purl.org/rss/1.0/modules/slash/">
+  <channel>
+    <item>
+      <guid>post-1</guid>
+      <link>https://example.com/post-1</link>
+      <comments>https://news.ycombinator.com/item?id=1</comments>
+      <wfw:commentRss>https://example.com/post-1/feed</wfw:commentRss>
+      <slash:comments>12</slash:comments>
+    </item>
+    <item>
+      <guid>post-2</guid>
+      <wfw:commentRss>https://example.com/post-2/feed</wfw:commentRss>
+    </item>
+    <item>
+      <guid>post-3</guid>
+    </item>
+  </channel>
+</rss>"#;
+
+        let raw = extract_raw_comments(rss);
+        let expected = RawComments {
+            url: Some("https://news.ycombinator.com/item?id=1".to_string()),
+            count: Some(12),
+        };
+        assert_eq!(raw.get("post-1"), Some(&expected));
+        assert_eq!(raw.get("https://example.com/post-1"), Some(&expected));
+        assert_eq!(
+            raw.get("post-2").and_then(|c| c.url.as_deref()),
+            Some("https://example.com/post-2/feed")
+        );
+        assert!(!raw.contains_key("post-3"));
+    }
+
+    #[test]
+    fn test_extract_raw_comments_atom() {
+        let atom = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xmlns:thr="http://purl.org/syndication/thread/1.0">
+  <entry>
+    <id>urn:uuid:1</id>
+    <link rel="alternate" href="https://example.com/a"/>
+    <link rel="replies" type="application/atom+xml" href="https://example.com/a/comments.xml"/>
+    <link rel="replies" type="text/html" href="https://example.com/a#comments" thr:count="3"/>
+  </entry>
+</feed>"#;
+
+        let raw = extract_raw_comments(atom);
+        let comments = raw.get("urn:uuid:1").unwrap();
+        assert_eq!(
+            comments.url.as_deref(),
+            Some("https://example.com/a#comments")
+        );
+        assert_eq!(comments.count, Some(3));
+        assert!(raw.contains_key("https://example.com/a"));
+    }
+
+    #[test]
+    fn test_extract_raw_authors() {
+        let atom = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xmlns:gd="http://schemas.google.com/g/2005">
+  <title>Group Blog</title>
+  <entry>
+    <id>urn:uuid:1</id>
+    <author>
+      <name>Alice</name>
+      <gd:image width="32" height="32" src="https://example.com/alice.png"/>
+    </author>
+  </entry>
+  <entry>
+    <id>urn:uuid:2</id>
+    <author><name>Bob</name></author>
+  </entry>
+</feed>"#;
+
+        let raw = extract_raw_authors(atom);
+        assert_eq!(
+            raw.avatars.get("Alice").map(String::as_str),
+            Some("https://example.com/alice.png")
+        );
+        assert!(!raw.avatars.contains_key("Bob"));
+
+        let rss = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd">
+  <channel>
+    <itunes:author>The Network</itunes:author>
+    <item>
+      <guid>episode-1</guid>
+      <itunes:author>Carol</itunes:author>
+    </item>
+  </channel>
+</rss>"#;
+
+        let raw = extract_raw_authors(rss);
+        assert_eq!(
+            raw.itunes.get("episode-1").map(String::as_str),
+            Some("Carol")
+        );
+        assert_eq!(raw.itunes.len(), 1);
+    }
+
+    #[test]
+    fn test_author_input() {
+        let author = author_input("alice@example.com (Alice Smith)", None, None).unwrap();
+        assert_eq!(author.name, "Alice Smith");
+        assert_eq!(author.email.as_deref(), Some("alice@example.com"));
+
+        let author =
+            author_input(" Bob ", Some("bob@example.com"), Some("https://bob.dev")).unwrap();
+        assert_eq!(author.name, "Bob");
+        assert_eq!(author.uri.as_deref(), Some("https://bob.dev"));
+
+        let author = author_input("", Some("carol@example.com"), None).unwrap();
+        assert_eq!(author.name, "carol@example.com");
+
+        assert!(author_input("  ", None, None).is_none());
+    }
 }
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// How often idle workers report in
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy)]
+struct Beat {
+    last: DateTime<Utc>,
+    max_silence: Duration,
+}
+
+/// Last-seen timestamps of the background workers, shared through `AppState`
+/// so the readiness check can tell a stalled worker from a busy one
+#[derive(Debug, Clone, Default)]
+pub struct Heartbeats {
+    workers: Arc<Mutex<BTreeMap<&'static str, Beat>>>,
+}
+
+/// Handle a worker uses to report that it is still running
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    name: &'static str,
+    heartbeats: Heartbeats,
+}
+
+/// Status of one worker as reported by `/health/ready`
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerStatus {
+    pub name: &'static str,
+    pub last_beat: DateTime<Utc>,
+    pub alive: bool,
+}
+
+impl Heartbeats {
+    /// Register a worker that is considered dead after `max_silence`
+    /// without a beat
+    pub fn register(&self, name: &'static str, max_silence: Duration) -> Heartbeat {
+        let beat = Beat {
+            last: Utc::now(),
+            max_silence,
+        };
+        self.workers.lock().unwrap().insert(name, beat);
+        Heartbeat {
+            name,
+            heartbeats: self.clone(),
+        }
+    }
+
+    /// Status of every registered worker, ordered by name
+    pub fn statuses(&self) -> Vec<WorkerStatus> {
+        let now = Utc::now();
+        self.workers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, beat)| {
+                let silence = (now - beat.last).to_std().unwrap_or_default();
+                WorkerStatus {
+                    name,
+                    last_beat: beat.last,
+                    alive: silence <= beat.max_silence,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Heartbeat {
+    /// A heartbeat that is not registered anywhere, for tests and tools
+    pub fn detached(name: &'static str) -> Self {
+        Heartbeats::default().register(name, Duration::MAX)
+    }
+
+    pub fn beat(&self) {
+        if let Some(beat) = self.heartbeats.workers.lock().unwrap().get_mut(self.name) {
+            beat.last = Utc::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_statuses() {
+        let heartbeats = Heartbeats::default();
+        let sync = heartbeats.register("sync", Duration::from_secs(60));
+        heartbeats.register("stalled", Duration::ZERO);
+
+        std::thread::sleep(Duration::from_millis(5));
+        sync.beat();
+
+        let statuses = heartbeats.statuses();
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[0].name, "stalled");
+        assert!(!statuses[0].alive);
+        assert_eq!(statuses[1].name, "sync");
+        assert!(statuses[1].alive);
+        assert!(statuses[1].last_beat > statuses[0].last_beat);
+    }
+}
@@ -0,0 +1,121 @@
+//! Syntax highlighting of code blocks in entry content. Tokens are marked
+//! with `hl-` classes instead of inline styles, so their colors come from
+//! `stylesheet()` and highlighted content needs no `style` attributes.
+
+use std::sync::LazyLock;
+
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+/// Code blocks longer than this are left plain. Content is sanitized on
+/// every view, and highlighting time grows with the input.
+pub const MAX_CODE_LEN: usize = 64 * 1024;
+
+const LIGHT_THEME: &str = "InspiredGitHub";
+const DARK_THEME: &str = "base16-ocean.dark";
+
+static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+
+static STYLESHEET: LazyLock<String> = LazyLock::new(build_stylesheet);
+
+/// Syntax for the language of a `language-*` class, by name or file
+/// extension (`rust` or `rs`)
+pub fn find_syntax(lang: &str) -> Option<&'static SyntaxReference> {
+    SYNTAX_SET.find_syntax_by_token(lang)
+}
+
+/// Highlight `code` as HTML. The text is escaped; tokens are wrapped in
+/// `hl-` classed spans.
+pub fn highlight(code: &str, syntax: &SyntaxReference) -> Option<String> {
+    let mut generator =
+        ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAX_SET, CLASS_STYLE);
+    for line in LinesWithEndings::from(code) {
+        generator
+            .parse_html_for_line_which_includes_newline(line)
+            .ok()?;
+    }
+    Some(generator.finalize())
+}
+
+/// CSS for the highlighting classes, following the light/dark theme the
+/// same way the base template does
+pub fn stylesheet() -> &'static str {
+    &STYLESHEET
+}
+
+fn build_stylesheet() -> String {
+    let themes = ThemeSet::load_defaults();
+    let css = |name: &str| {
+        themes
+            .themes
+            .get(name)
+            .and_then(|theme| css_for_theme_with_class_style(theme, CLASS_STYLE).ok())
+            .unwrap_or_default()
+    };
+    let dark = css(DARK_THEME);
+    format!(
+        "{}\n{}\n@media (prefers-color-scheme: dark) {{\n{}\n}}\n",
+        css(LIGHT_THEME),
+        scope_rules(&dark, "[data-theme=\"dark\"]"),
+        scope_rules(&dark, ":root:not([data-theme])")
+    )
+}
+
+/// Prefix every selector of generated CSS with `scope`
+fn scope_rules(css: &str, scope: &str) -> String {
+    css.lines()
+        .map(|line| match line.strip_suffix('{') {
+            Some(selectors) => {
+                let scoped: Vec<String> = selectors
+                    .split(',')
+                    .map(|selector| format!("{} {}", scope, selector.trim()))
+                    .collect();
+                format!("{} {{", scoped.join(", "))
+            }
+            None => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_syntax() {
+        assert_eq!(find_syntax("rust").unwrap().name, "Rust");
+        assert_eq!(find_syntax("rs").unwrap().name, "Rust");
+        assert_eq!(find_syntax("python").unwrap().name, "Python");
+        assert!(find_syntax("no-such-language").is_none());
+    }
+
+    #[test]
+    fn test_highlight_escapes_and_classes() {
+        let html = highlight("fn main() { a < b }\n", find_syntax("rust").unwrap()).unwrap();
+        assert!(html.contains("<span class=\"hl-"));
+        assert!(html.contains("&lt;"));
+        assert!(!html.contains("style="));
+    }
+
+    #[test]
+    fn test_scope_rules() {
+        let css = ".hl-comment, .hl-string {\n color: #fff;\n}";
+        assert_eq!(
+            scope_rules(css, ".dark"),
+            ".dark .hl-comment, .dark .hl-string {\n color: #fff;\n}"
+        );
+    }
+
+    #[test]
+    fn test_stylesheet_has_both_themes() {
+        let css = stylesheet();
+        assert!(css.contains(".hl-"));
+        assert!(css.contains("[data-theme=\"dark\"] .hl-"));
+        assert!(css.contains("@media (prefers-color-scheme: dark)"));
+    }
+}
@@ -1,9 +1,14 @@
+use std::collections::BTreeMap;
 use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
-use reqwest::{Response, StatusCode};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{ClientBuilder, Response, StatusCode};
 use tracing::warn;
 
+use crate::error::{AppError, AppResult};
+
 /// Default timeout for general HTTP requests (30s)
 pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 
@@ -13,6 +18,65 @@ pub const ICON_TIMEOUT: Duration = Duration::from_secs(10);
 /// Timeout for external API calls like Linkding, Kagi (60s)
 pub const EXTERNAL_API_TIMEOUT: Duration = Duration::from_secs(60);
 
+/// What an outbound request is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outbound {
+    /// Feeds, feed discovery, full article content and proxied images
+    Content,
+    /// Services a user or admin set up: Linkding, Web Push and the
+    /// blocklist's hosts file
+    Configured,
+    /// Site icons of feeds
+    Icon,
+    /// Hacker News, Lobsters and Reddit threads about an entry
+    Discussions,
+    /// Kagi summaries of entries and category digests
+    Summary,
+    /// The latest release of rdrs on GitHub
+    UpdateCheck,
+}
+
+impl Outbound {
+    /// Requests that only add to what feeds provide, which privacy mode
+    /// refuses
+    pub fn is_enrichment(&self) -> bool {
+        matches!(
+            self,
+            Outbound::Icon | Outbound::Discussions | Outbound::Summary | Outbound::UpdateCheck
+        )
+    }
+}
+
+static PRIVACY_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Turn privacy mode on or off for the whole process, from `PRIVACY_MODE`
+/// at startup
+pub fn set_privacy_mode(enabled: bool) {
+    PRIVACY_MODE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn privacy_mode() -> bool {
+    PRIVACY_MODE.load(Ordering::Relaxed)
+}
+
+fn allowed(purpose: Outbound, privacy_mode: bool) -> bool {
+    !(privacy_mode && purpose.is_enrichment())
+}
+
+/// Whether requests for `purpose` may be made
+pub fn allows(purpose: Outbound) -> bool {
+    allowed(purpose, privacy_mode())
+}
+
+/// Builder for the client of requests made for `purpose`. Every outbound
+/// client is built here, so privacy mode holds wherever a request is made.
+pub fn client_builder(purpose: Outbound) -> AppResult<ClientBuilder> {
+    if !allows(purpose) {
+        return Err(AppError::PrivacyMode);
+    }
+    Ok(reqwest::Client::builder())
+}
+
 /// Configuration for retry behavior
 #[derive(Debug, Clone)]
 pub struct RetryConfig {
@@ -193,12 +257,73 @@ where
     unreachable!("retry loop should return before reaching here")
 }
 
+/// Maximum number of extra request headers per feed
+pub const MAX_CUSTOM_HEADERS: usize = 20;
+
+/// Headers the HTTP client manages itself and a feed may not override
+const RESERVED_HEADERS: &[&str] = &["host", "content-length", "transfer-encoding", "connection"];
+
+/// Check a feed's extra request headers, describing the first one that
+/// cannot be sent
+pub fn validate_custom_headers(headers: &BTreeMap<String, String>) -> Result<(), String> {
+    if headers.len() > MAX_CUSTOM_HEADERS {
+        return Err(format!(
+            "At most {} custom headers are allowed",
+            MAX_CUSTOM_HEADERS
+        ));
+    }
+    for (name, value) in headers {
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("Invalid header name: {}", name))?;
+        if RESERVED_HEADERS.contains(&header_name.as_str()) {
+            return Err(format!("Header cannot be overridden: {}", name));
+        }
+        HeaderValue::from_str(value).map_err(|_| format!("Invalid value for header {}", name))?;
+    }
+    Ok(())
+}
+
+/// Header map for a feed's extra request headers. Invalid entries are
+/// skipped; `validate_custom_headers` rejects them when they are saved.
+pub fn custom_header_map(headers: &BTreeMap<String, String>) -> HeaderMap {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let name = HeaderName::from_bytes(name.as_bytes()).ok()?;
+            let value = HeaderValue::from_str(value).ok()?;
+            Some((name, value))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::sync::atomic::{AtomicU32, Ordering};
     use std::sync::Arc;
 
+    #[test]
+    fn test_validate_custom_headers() {
+        let valid = BTreeMap::from([
+            ("Referer".to_string(), "https://example.com/".to_string()),
+            ("X-Api-Key".to_string(), "secret".to_string()),
+        ]);
+        assert!(validate_custom_headers(&valid).is_ok());
+
+        let map = custom_header_map(&valid);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["referer"], "https://example.com/");
+
+        let bad_name = BTreeMap::from([("Bad Header".to_string(), "x".to_string())]);
+        assert!(validate_custom_headers(&bad_name).is_err());
+
+        let bad_value = BTreeMap::from([("X-Test".to_string(), "line\nbreak".to_string())]);
+        assert!(validate_custom_headers(&bad_value).is_err());
+
+        let reserved = BTreeMap::from([("Host".to_string(), "example.com".to_string())]);
+        assert!(validate_custom_headers(&reserved).is_err());
+    }
+
     #[test]
     fn test_is_transient_status() {
         assert!(is_transient_status(StatusCode::INTERNAL_SERVER_ERROR));
@@ -332,6 +457,22 @@ mod tests {
         assert_eq!(attempts.load(Ordering::SeqCst), 1);
     }
 
+    #[test]
+    fn test_privacy_mode_refuses_enrichment() {
+        for purpose in [Outbound::Content, Outbound::Configured] {
+            assert!(allowed(purpose, true));
+        }
+        for purpose in [
+            Outbound::Icon,
+            Outbound::Discussions,
+            Outbound::Summary,
+            Outbound::UpdateCheck,
+        ] {
+            assert!(!allowed(purpose, true));
+            assert!(allowed(purpose, false));
+        }
+    }
+
     #[test]
     fn test_timeout_constants() {
         assert_eq!(DEFAULT_TIMEOUT, Duration::from_secs(30));
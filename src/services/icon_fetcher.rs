@@ -3,7 +3,7 @@ use tracing::debug;
 use url::Url;
 
 use crate::error::{AppError, AppResult};
-use crate::services::http::{send_with_retry, RetryConfig, ICON_TIMEOUT};
+use crate::services::http::{client_builder, send_with_retry, Outbound, RetryConfig, ICON_TIMEOUT};
 
 const MAX_ICON_SIZE: usize = 256 * 1024; // 256KB
 
@@ -46,7 +46,7 @@ pub async fn fetch_feed_icon(
 }
 
 async fn fetch_image(url: &str, user_agent: &str) -> AppResult<Option<FetchedImage>> {
-    let client = reqwest::Client::builder()
+    let client = client_builder(Outbound::Icon)?
         .timeout(ICON_TIMEOUT)
         .user_agent(user_agent)
         .build()
@@ -106,7 +106,8 @@ async fn fetch_image(url: &str, user_agent: &str) -> AppResult<Option<FetchedIma
     }))
 }
 
-async fn fetch_favicon(site_url: &str, user_agent: &str) -> AppResult<Option<FetchedImage>> {
+/// The site's favicon, from `/favicon.ico` or the icon its page links to
+pub async fn fetch_favicon(site_url: &str, user_agent: &str) -> AppResult<Option<FetchedImage>> {
     let base_url = match Url::parse(site_url) {
         Ok(u) => u,
         Err(_) => return Ok(None),
@@ -124,7 +125,7 @@ async fn fetch_favicon(site_url: &str, user_agent: &str) -> AppResult<Option<Fet
     }
 
     // Try parsing HTML for link rel="icon"
-    let client = reqwest::Client::builder()
+    let client = client_builder(Outbound::Icon)?
         .timeout(ICON_TIMEOUT)
         .user_agent(user_agent)
         .build()
@@ -0,0 +1,147 @@
+//! Keeps feed icons current. Feed refreshes only look at the icon when the
+//! feed itself changed, so feeds answering 304 keep an old icon and feeds
+//! whose icon failed once may never get one. A daily worker refetches icons
+//! older than 30 days and retries feeds without one, and
+//! `POST /api/feeds/{id}/icon/refresh` does the same for one feed at once.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::heartbeat::{Heartbeat, HEARTBEAT_INTERVAL};
+use super::http::{allows, Outbound};
+use super::icon_fetcher;
+use super::instance_settings::InstanceSettings;
+use crate::db::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::{feed, image};
+
+/// Icons older than this are fetched again
+pub const STALE_ICON_DAYS: i64 = 30;
+
+/// How often the worker looks for stale and missing icons
+const REFRESH_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+/// Feeds tried per pass, so a large instance spreads the work over days
+const BATCH_SIZE: i64 = 50;
+
+/// Fetch the feed's icon again and store it. The icon found last time is
+/// tried first, then the site's favicon. Returns where the icon came from,
+/// or None if nothing was found, in which case the old icon is kept.
+pub async fn refresh_feed_icon(
+    db: &DbPool,
+    feed_id: i64,
+    default_user_agent: &str,
+) -> AppResult<Option<String>> {
+    let (f, current) = db
+        .background(move |conn| {
+            let f = feed::find_by_id(conn, feed_id)?.ok_or(AppError::FeedNotFound)?;
+            let current = image::find(conn, image::ENTITY_FEED, feed_id)?;
+            image::mark_feed_icon_checked(conn, feed_id)?;
+            Ok::<_, AppError>((f, current))
+        })
+        .await??;
+
+    let user_agent = f.custom_user_agent.as_deref().unwrap_or(default_user_agent);
+    let site_url = f.site_url.as_deref().unwrap_or(&f.url);
+    let source_url = current.and_then(|img| img.source_url);
+    let Some(fetched) =
+        icon_fetcher::fetch_feed_icon(source_url.as_deref(), None, Some(site_url), user_agent)
+            .await?
+    else {
+        return Ok(None);
+    };
+
+    let source_url = fetched.source_url.clone();
+    db.background(move |conn| {
+        image::upsert(
+            conn,
+            image::ENTITY_FEED,
+            feed_id,
+            &fetched.data,
+            &fetched.content_type,
+            Some(&fetched.source_url),
+        )
+    })
+    .await??;
+    Ok(Some(source_url))
+}
+
+/// Refresh one batch of stale and missing icons
+async fn refresh_stale_icons(db: &DbPool, user_agent: &str) {
+    let feed_ids = match db
+        .background(|conn| image::stale_feed_icons(conn, STALE_ICON_DAYS, 1, BATCH_SIZE))
+        .await
+    {
+        Ok(Ok(ids)) => ids,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to list stale feed icons: {}", e);
+            return;
+        }
+        Err(e) => {
+            tracing::error!("Failed to access DB for stale feed icons: {}", e);
+            return;
+        }
+    };
+
+    let mut refreshed = 0;
+    for feed_id in &feed_ids {
+        match refresh_feed_icon(db, *feed_id, user_agent).await {
+            Ok(Some(_)) => refreshed += 1,
+            Ok(None) => tracing::debug!("No icon found for feed {}", feed_id),
+            Err(e) => tracing::warn!("Failed to refresh icon of feed {}: {}", feed_id, e),
+        }
+    }
+    if !feed_ids.is_empty() {
+        tracing::info!(
+            "Refreshed {} of {} stale or missing feed icons",
+            refreshed,
+            feed_ids.len()
+        );
+    }
+}
+
+/// Start the worker that refreshes stale and missing feed icons once a day
+///
+/// # Arguments
+/// * `db` - Database connection
+/// * `settings` - Instance settings, for the user agent
+/// * `heartbeat` - Reported to while the worker is running
+/// * `cancel_token` - Token to signal graceful shutdown
+pub fn start_icon_refresh_worker(
+    db: DbPool,
+    settings: watch::Receiver<Arc<InstanceSettings>>,
+    heartbeat: Heartbeat,
+    cancel_token: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        tracing::info!("Icon refresh worker started");
+
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        let mut beat = tokio::time::interval(HEARTBEAT_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    tracing::info!("Icon refresh worker stopping...");
+                    break;
+                }
+                _ = beat.tick() => {
+                    heartbeat.beat();
+                }
+                _ = interval.tick() => {
+                    // Privacy mode fetches no icons
+                    if allows(Outbound::Icon) {
+                        let user_agent = settings.borrow().user_agent.clone();
+                        refresh_stale_icons(&db, &user_agent).await;
+                    }
+                }
+            }
+        }
+
+        tracing::info!("Icon refresh worker stopped");
+    })
+}
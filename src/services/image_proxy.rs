@@ -1,31 +1,83 @@
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
 
 type HmacSha256 = Hmac<Sha256>;
 
-/// Signs a URL using HMAC-SHA256 and returns a truncated base64-encoded signature.
+/// How long a proxy URL stays valid after it is generated (7 days)
+pub const URL_TTL_SECS: i64 = 7 * 24 * 3600;
+
+/// Expiry timestamps are rounded up to this step so the same image keeps the
+/// same URL, and stays cacheable, for a while
+const EXPIRY_STEP_SECS: i64 = 3600;
+
+/// Short identifier of a secret, embedded in proxy URLs so the verifier knows
+/// which of the active secrets signed them.
+pub fn key_id(secret: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC can take key of any size");
+    mac.update(b"rdrs image proxy key id");
+    let result = mac.finalize().into_bytes();
+    URL_SAFE_NO_PAD.encode(&result[..3])
+}
+
+/// Signs a URL and its expiration timestamp using HMAC-SHA256 and returns a
+/// truncated base64-encoded signature.
 /// The signature is truncated to 8 bytes (64 bits) for URL brevity.
-pub fn sign_url(url: &str, secret: &[u8]) -> String {
+pub fn sign_url(url: &str, expires: i64, secret: &[u8]) -> String {
     let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC can take key of any size");
+    mac.update(expires.to_string().as_bytes());
+    mac.update(b"\n");
     mac.update(url.as_bytes());
     let result = mac.finalize().into_bytes();
     // Truncate to 8 bytes and base64 encode
     URL_SAFE_NO_PAD.encode(&result[..8])
 }
 
-/// Verifies a signature for a given URL.
-pub fn verify_signature(url: &str, signature: &str, secret: &[u8]) -> bool {
-    let expected = sign_url(url, secret);
-    // Use constant-time comparison to prevent timing attacks
-    constant_time_eq(expected.as_bytes(), signature.as_bytes())
+/// Verifies a signature for a given URL. The URL must not have expired and
+/// `kid` must name one of `secrets`; the first secret signs new URLs and the
+/// rest are kept around so URLs signed before a rotation keep working.
+pub fn verify_signature(
+    url: &str,
+    expires: i64,
+    kid: &str,
+    signature: &str,
+    secrets: &[&[u8]],
+    now: i64,
+) -> bool {
+    if expires < now {
+        return false;
+    }
+    secrets
+        .iter()
+        .filter(|secret| key_id(secret) == kid)
+        .any(|secret| {
+            let expected = sign_url(url, expires, secret);
+            // Use constant-time comparison to prevent timing attacks
+            constant_time_eq(expected.as_bytes(), signature.as_bytes())
+        })
+}
+
+/// Expiration timestamp for a URL generated at `now`
+fn expires_at(now: i64) -> i64 {
+    let deadline = now + URL_TTL_SECS;
+    deadline + (EXPIRY_STEP_SECS - deadline.rem_euclid(EXPIRY_STEP_SECS)) % EXPIRY_STEP_SECS
 }
 
-/// Creates a proxy URL with signature for an image URL.
+/// Creates a proxy URL with expiry, key ID and signature for an image URL.
+/// URLs are generated whenever content is served, so they always carry the
+/// current key.
 pub fn create_proxy_url(original_url: &str, secret: &[u8]) -> String {
     let encoded = URL_SAFE_NO_PAD.encode(original_url);
-    let signature = sign_url(original_url, secret);
-    format!("/api/proxy/image?url={}&s={}", encoded, signature)
+    let expires = expires_at(Utc::now().timestamp());
+    let signature = sign_url(original_url, expires, secret);
+    format!(
+        "/api/proxy/image?url={}&e={}&k={}&s={}",
+        encoded,
+        expires,
+        key_id(secret),
+        signature
+    )
 }
 
 /// Constant-time equality comparison to prevent timing attacks.
@@ -44,68 +96,164 @@ fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
 mod tests {
     use super::*;
 
+    const SECRET: &[u8] = b"test_secret_key_32_bytes_long!!!";
+    const OTHER_SECRET: &[u8] = b"other_secret_key_32_bytes_long!!";
+    const URL: &str = "https://example.com/image.jpg";
+    const NOW: i64 = 1_700_000_000;
+    const EXPIRES: i64 = NOW + 3600;
+
+    fn query_param<'a>(proxy_url: &'a str, name: &str) -> &'a str {
+        let query = proxy_url.split_once('?').unwrap().1;
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix(&format!("{}=", name)))
+            .unwrap()
+    }
+
     #[test]
     fn test_sign_url() {
-        let secret = b"test_secret_key_32_bytes_long!!!";
-        let url = "https://example.com/image.jpg";
-
-        let signature = sign_url(url, secret);
+        let signature = sign_url(URL, EXPIRES, SECRET);
         // Signature should be 11 characters (8 bytes base64 encoded without padding)
         assert_eq!(signature.len(), 11);
+        assert_ne!(signature, sign_url(URL, EXPIRES + 1, SECRET));
     }
 
     #[test]
-    fn test_verify_signature_valid() {
-        let secret = b"test_secret_key_32_bytes_long!!!";
-        let url = "https://example.com/image.jpg";
+    fn test_key_id() {
+        assert_eq!(key_id(SECRET).len(), 4);
+        assert_eq!(key_id(SECRET), key_id(SECRET));
+        assert_ne!(key_id(SECRET), key_id(OTHER_SECRET));
+    }
 
-        let signature = sign_url(url, secret);
-        assert!(verify_signature(url, &signature, secret));
+    #[test]
+    fn test_verify_signature_valid() {
+        let signature = sign_url(URL, EXPIRES, SECRET);
+        assert!(verify_signature(
+            URL,
+            EXPIRES,
+            &key_id(SECRET),
+            &signature,
+            &[SECRET],
+            NOW
+        ));
     }
 
     #[test]
     fn test_verify_signature_invalid() {
-        let secret = b"test_secret_key_32_bytes_long!!!";
-        let url = "https://example.com/image.jpg";
-
-        assert!(!verify_signature(url, "invalid_sig", secret));
+        assert!(!verify_signature(
+            URL,
+            EXPIRES,
+            &key_id(SECRET),
+            "invalid_sig",
+            &[SECRET],
+            NOW
+        ));
     }
 
     #[test]
     fn test_verify_signature_wrong_url() {
-        let secret = b"test_secret_key_32_bytes_long!!!";
-        let url = "https://example.com/image.jpg";
         let other_url = "https://example.com/other.jpg";
 
-        let signature = sign_url(url, secret);
-        assert!(!verify_signature(other_url, &signature, secret));
+        let signature = sign_url(URL, EXPIRES, SECRET);
+        let kid = key_id(SECRET);
+        assert!(!verify_signature(
+            other_url,
+            EXPIRES,
+            &kid,
+            &signature,
+            &[SECRET],
+            NOW
+        ));
     }
 
     #[test]
     fn test_verify_signature_wrong_secret() {
-        let secret1 = b"test_secret_key_32_bytes_long!!!";
-        let secret2 = b"other_secret_key_32_bytes_long!!";
-        let url = "https://example.com/image.jpg";
+        let signature = sign_url(URL, EXPIRES, SECRET);
+        let kid = key_id(SECRET);
+        assert!(!verify_signature(
+            URL,
+            EXPIRES,
+            &kid,
+            &signature,
+            &[OTHER_SECRET],
+            NOW
+        ));
+    }
+
+    #[test]
+    fn test_verify_signature_expired() {
+        let signature = sign_url(URL, EXPIRES, SECRET);
+        let kid = key_id(SECRET);
+        assert!(!verify_signature(
+            URL,
+            EXPIRES,
+            &kid,
+            &signature,
+            &[SECRET],
+            EXPIRES + 1
+        ));
+        // Extending the expiry invalidates the signature
+        assert!(!verify_signature(
+            URL,
+            EXPIRES + 60,
+            &kid,
+            &signature,
+            &[SECRET],
+            NOW
+        ));
+    }
 
-        let signature = sign_url(url, secret1);
-        assert!(!verify_signature(url, &signature, secret2));
+    #[test]
+    fn test_verify_signature_after_rotation() {
+        let signature = sign_url(URL, EXPIRES, OTHER_SECRET);
+        let kid = key_id(OTHER_SECRET);
+        // The old secret is still accepted while it is listed
+        assert!(verify_signature(
+            URL,
+            EXPIRES,
+            &kid,
+            &signature,
+            &[SECRET, OTHER_SECRET],
+            NOW
+        ));
+        assert!(!verify_signature(
+            URL,
+            EXPIRES,
+            &kid,
+            &signature,
+            &[SECRET],
+            NOW
+        ));
     }
 
     #[test]
-    fn test_create_proxy_url() {
-        let secret = b"test_secret_key_32_bytes_long!!!";
-        let url = "https://example.com/image.jpg";
+    fn test_expires_at() {
+        let expires = expires_at(NOW);
+        assert!(expires >= NOW + URL_TTL_SECS);
+        assert!(expires < NOW + URL_TTL_SECS + EXPIRY_STEP_SECS);
+        assert_eq!(expires % EXPIRY_STEP_SECS, 0);
+        assert_eq!(expires_at(NOW + 1), expires);
+    }
 
-        let proxy_url = create_proxy_url(url, secret);
+    #[test]
+    fn test_create_proxy_url() {
+        let proxy_url = create_proxy_url(URL, SECRET);
 
         assert!(proxy_url.starts_with("/api/proxy/image?url="));
-        assert!(proxy_url.contains("&s="));
+        assert_eq!(query_param(&proxy_url, "k"), key_id(SECRET));
 
         // Verify the signature part
-        let parts: Vec<&str> = proxy_url.split("&s=").collect();
-        assert_eq!(parts.len(), 2);
-        let signature = parts[1];
-        assert!(verify_signature(url, signature, secret));
+        let expires: i64 = query_param(&proxy_url, "e").parse().unwrap();
+        let signature = query_param(&proxy_url, "s");
+        let now = Utc::now().timestamp();
+        assert!(verify_signature(
+            URL,
+            expires,
+            &key_id(SECRET),
+            signature,
+            &[SECRET],
+            now
+        ));
     }
 
     #[test]
@@ -0,0 +1,221 @@
+//! Settings admins can change without a restart.
+//!
+//! Each setting starts from the configuration (`SIGNUP_ENABLED`,
+//! `USER_AGENT`) or a built-in default, and admins can override it from the
+//! Admin Panel. The settings in effect are published on a watch channel, so
+//! handlers and workers pick up a change as soon as it is saved.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::watch;
+
+use super::trash::TRASH_RETENTION_DAYS;
+use crate::config::{Config, DEFAULT_USER_AGENT};
+use crate::db::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::instance_settings::{self, StoredSettings};
+
+/// Feeds are refreshed in the background every hour unless set otherwise
+pub const DEFAULT_SYNC_INTERVAL_HOURS: i64 = 1;
+pub const MAX_SYNC_INTERVAL_HOURS: i64 = 24;
+pub const MAX_TRASH_RETENTION_DAYS: i64 = 365;
+const MAX_USER_AGENT_LEN: usize = 500;
+
+/// The settings in effect
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InstanceSettings {
+    pub signup_enabled: bool,
+    /// Default user agent for outbound requests; feeds can override it
+    pub user_agent: String,
+    /// Each feed is refreshed in the background once every this many hours
+    pub sync_interval_hours: i64,
+    /// Days deleted feeds and categories stay in the trash
+    pub trash_retention_days: i64,
+    /// From `MULTI_USER_ENABLED`, which cannot be changed at runtime
+    #[serde(skip)]
+    pub multi_user_enabled: bool,
+}
+
+impl Default for InstanceSettings {
+    fn default() -> Self {
+        InstanceSettings {
+            signup_enabled: false,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            sync_interval_hours: DEFAULT_SYNC_INTERVAL_HOURS,
+            trash_retention_days: TRASH_RETENTION_DAYS,
+            multi_user_enabled: false,
+        }
+    }
+}
+
+impl InstanceSettings {
+    /// The saved settings, falling back to `config` for those never saved
+    pub fn resolve(config: &Config, stored: &StoredSettings) -> Self {
+        InstanceSettings {
+            signup_enabled: stored.signup_enabled.unwrap_or(config.signup_enabled),
+            user_agent: stored
+                .user_agent
+                .clone()
+                .unwrap_or_else(|| config.user_agent.clone()),
+            sync_interval_hours: stored
+                .sync_interval_hours
+                .unwrap_or(DEFAULT_SYNC_INTERVAL_HOURS),
+            trash_retention_days: stored.trash_retention_days.unwrap_or(TRASH_RETENTION_DAYS),
+            multi_user_enabled: config.multi_user_enabled,
+        }
+    }
+
+    pub fn can_register(&self, user_count: i64) -> bool {
+        self.signup_enabled && (self.multi_user_enabled || user_count == 0)
+    }
+
+    /// Whether background sync refreshes feeds in the hour that `now` falls in
+    pub fn syncs_in_hour(&self, now: DateTime<Utc>) -> bool {
+        (now.timestamp() / 3600) % self.sync_interval_hours.max(1) == 0
+    }
+}
+
+/// Check settings an admin is about to save
+pub fn validate(stored: &StoredSettings) -> AppResult<()> {
+    if let Some(user_agent) = &stored.user_agent {
+        if user_agent.trim().is_empty() || user_agent.len() > MAX_USER_AGENT_LEN {
+            return Err(AppError::Validation(format!(
+                "User agent must be 1 to {} characters",
+                MAX_USER_AGENT_LEN
+            )));
+        }
+    }
+    if let Some(hours) = stored.sync_interval_hours {
+        if !(1..=MAX_SYNC_INTERVAL_HOURS).contains(&hours) {
+            return Err(AppError::Validation(format!(
+                "Sync interval must be between 1 and {} hours",
+                MAX_SYNC_INTERVAL_HOURS
+            )));
+        }
+    }
+    if let Some(days) = stored.trash_retention_days {
+        if !(1..=MAX_TRASH_RETENTION_DAYS).contains(&days) {
+            return Err(AppError::Validation(format!(
+                "Trash retention must be between 1 and {} days",
+                MAX_TRASH_RETENTION_DAYS
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The settings in effect, shared through `AppState`; workers subscribe to
+/// changes
+#[derive(Debug, Clone)]
+pub struct LiveSettings {
+    tx: Arc<watch::Sender<Arc<InstanceSettings>>>,
+}
+
+impl LiveSettings {
+    pub fn new(settings: InstanceSettings) -> Self {
+        let (tx, _) = watch::channel(Arc::new(settings));
+        LiveSettings { tx: Arc::new(tx) }
+    }
+
+    /// Settings from the configuration alone, before any are loaded
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(InstanceSettings::resolve(
+            config,
+            &StoredSettings::default(),
+        ))
+    }
+
+    pub fn current(&self) -> Arc<InstanceSettings> {
+        self.tx.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Arc<InstanceSettings>> {
+        self.tx.subscribe()
+    }
+
+    /// Load the saved settings and publish them
+    pub async fn reload(&self, db: &DbPool, config: &Config) -> AppResult<()> {
+        let stored = db.background(instance_settings::get).await??;
+        self.tx
+            .send_replace(Arc::new(InstanceSettings::resolve(config, &stored)));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_can_register() {
+        let settings = InstanceSettings {
+            signup_enabled: true,
+            ..Default::default()
+        };
+        assert!(settings.can_register(0));
+        assert!(!settings.can_register(1));
+
+        let multi_user = InstanceSettings {
+            multi_user_enabled: true,
+            ..settings.clone()
+        };
+        assert!(multi_user.can_register(0));
+        assert!(multi_user.can_register(5));
+
+        let disabled = InstanceSettings {
+            signup_enabled: false,
+            ..multi_user
+        };
+        assert!(!disabled.can_register(0));
+    }
+
+    #[test]
+    fn test_syncs_in_hour() {
+        let every_third = InstanceSettings {
+            sync_interval_hours: 3,
+            ..Default::default()
+        };
+        let at = |hour| Utc.with_ymd_and_hms(2024, 1, 1, hour, 30, 0).unwrap();
+        assert!(every_third.syncs_in_hour(at(0)));
+        assert!(!every_third.syncs_in_hour(at(1)));
+        assert!(every_third.syncs_in_hour(at(3)));
+        assert!(InstanceSettings::default().syncs_in_hour(at(1)));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&StoredSettings::default()).is_ok());
+        let invalid = [
+            StoredSettings {
+                user_agent: Some(" ".to_string()),
+                ..Default::default()
+            },
+            StoredSettings {
+                sync_interval_hours: Some(0),
+                ..Default::default()
+            },
+            StoredSettings {
+                trash_retention_days: Some(MAX_TRASH_RETENTION_DAYS + 1),
+                ..Default::default()
+            },
+        ];
+        for settings in invalid {
+            assert!(validate(&settings).is_err());
+        }
+    }
+
+    #[test]
+    fn test_subscribers_see_changes() {
+        let live = LiveSettings::new(InstanceSettings::default());
+        let rx = live.subscribe();
+        live.tx.send_replace(Arc::new(InstanceSettings {
+            signup_enabled: true,
+            ..Default::default()
+        }));
+        assert!(rx.borrow().signup_enabled);
+        assert!(live.current().signup_enabled);
+    }
+}
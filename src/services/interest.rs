@@ -0,0 +1,322 @@
+//! Per-user interest model behind `sort=interest`.
+//!
+//! A logistic regression over words of the title, the feed and the author,
+//! trained on which recent entries the user starred, read or skipped. It is
+//! retrained daily; entries arriving in between are scored with the model
+//! from the last training.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::heartbeat::{Heartbeat, HEARTBEAT_INTERVAL};
+use crate::db::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::entry_interest::{self, EntryFeatures, Outcome};
+use crate::models::{category, feed, user};
+
+/// How often models are retrained
+const TRAINING_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+/// Days of history a model learns from
+const TRAINING_DAYS: i64 = 90;
+
+/// Most entries a model learns from
+const MAX_EXAMPLES: i64 = 5000;
+
+/// Fewest skipped and fewest read entries needed to train at all
+const MIN_EXAMPLES_PER_CLASS: usize = 20;
+
+const EPOCHS: usize = 30;
+const LEARNING_RATE: f64 = 0.05;
+const L2: f64 = 0.001;
+
+/// Starring says more than opening, so it counts for this many reads
+const STAR_WEIGHT: f64 = 3.0;
+
+/// Weights closer to zero than this are dropped when the model is stored
+const MIN_WEIGHT: f64 = 0.001;
+
+/// Words of a title, lowercased. Scripts written without spaces, like
+/// Chinese and Japanese, are split into overlapping pairs of characters.
+fn title_tokens(title: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for word in title.split(|c: char| !c.is_alphanumeric()) {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.iter().any(|&c| is_cjk(c)) {
+            if chars.len() == 1 {
+                tokens.push(word.to_string());
+            }
+            tokens.extend(chars.windows(2).map(|pair| pair.iter().collect::<String>()));
+        } else if chars.len() >= 3 && !chars.iter().all(|c| c.is_ascii_digit()) {
+            tokens.push(word.to_lowercase());
+        }
+    }
+    tokens
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{3040}'..='\u{30ff}' | '\u{3400}'..='\u{9fff}' | '\u{ac00}'..='\u{d7af}')
+}
+
+/// Features of an entry, each present or not
+fn features(entry: &EntryFeatures) -> Vec<String> {
+    let mut features = vec![format!("feed:{}", entry.feed_id)];
+    if let Some(author) = entry.author.as_deref().map(str::trim) {
+        if !author.is_empty() {
+            features.push(format!("author:{}", author.to_lowercase()));
+        }
+    }
+    if let Some(title) = &entry.title {
+        features.extend(
+            title_tokens(title)
+                .into_iter()
+                .map(|t| format!("word:{}", t)),
+        );
+    }
+    features.sort_unstable();
+    features.dedup();
+    features
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InterestModel {
+    bias: f64,
+    weights: HashMap<String, f64>,
+}
+
+impl InterestModel {
+    /// Fit a model to entries and how they were treated. Returns None while
+    /// there are too few skipped or too few read entries to learn from.
+    pub fn train(examples: &[(EntryFeatures, Outcome)]) -> Option<Self> {
+        let skipped = examples
+            .iter()
+            .filter(|(_, o)| *o == Outcome::Skipped)
+            .count();
+        if skipped < MIN_EXAMPLES_PER_CLASS || examples.len() - skipped < MIN_EXAMPLES_PER_CLASS {
+            return None;
+        }
+
+        let rows: Vec<(Vec<String>, f64, f64)> = examples
+            .iter()
+            .map(|(entry, outcome)| {
+                let (label, weight) = match outcome {
+                    Outcome::Skipped => (0.0, 1.0),
+                    Outcome::Read => (1.0, 1.0),
+                    Outcome::Starred => (1.0, STAR_WEIGHT),
+                };
+                (features(entry), label, weight)
+            })
+            .collect();
+
+        let mut model = InterestModel::default();
+        for _ in 0..EPOCHS {
+            for (features, label, weight) in &rows {
+                let gradient = (model.predict(features) - label) * weight;
+                model.bias -= LEARNING_RATE * gradient;
+                for feature in features {
+                    let w = model.weights.entry(feature.clone()).or_default();
+                    *w -= LEARNING_RATE * (gradient + L2 * *w);
+                }
+            }
+        }
+        model.weights.retain(|_, w| w.abs() >= MIN_WEIGHT);
+        Some(model)
+    }
+
+    fn predict(&self, features: &[String]) -> f64 {
+        let sum: f64 = features
+            .iter()
+            .filter_map(|f| self.weights.get(f))
+            .sum::<f64>()
+            + self.bias;
+        sigmoid(sum)
+    }
+
+    /// Predicted chance the user wants to read the entry
+    pub fn score(&self, entry: &EntryFeatures) -> f64 {
+        self.predict(&features(entry))
+    }
+}
+
+fn load_model(conn: &Connection, user_id: i64) -> AppResult<Option<InterestModel>> {
+    let Some(json) = entry_interest::find_model(conn, user_id)? else {
+        return Ok(None);
+    };
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| AppError::Internal(format!("Invalid interest model: {}", e)))
+}
+
+/// Train the user's model and score their unread entries with it. Returns
+/// the number of entries scored, or None if there is too little history.
+pub fn retrain_user(conn: &Connection, user_id: i64) -> AppResult<Option<usize>> {
+    let examples = entry_interest::training_entries(conn, user_id, TRAINING_DAYS, MAX_EXAMPLES)?;
+    let Some(model) = InterestModel::train(&examples) else {
+        return Ok(None);
+    };
+    let json = serde_json::to_string(&model)
+        .map_err(|e| AppError::Internal(format!("Failed to encode interest model: {}", e)))?;
+    entry_interest::save_model(conn, user_id, &json, examples.len() as i64)?;
+
+    let scores: Vec<(i64, f64)> = entry_interest::unread_entries(conn, user_id)?
+        .iter()
+        .map(|e| (e.entry_id, model.score(e)))
+        .collect();
+    entry_interest::set_scores(conn, &scores)?;
+    Ok(Some(scores.len()))
+}
+
+/// Score entries a refresh of `feed_id` just added with the owner's model,
+/// if they have one
+pub fn score_new_entries(conn: &Connection, feed_id: i64, entry_ids: &[i64]) -> AppResult<()> {
+    if entry_ids.is_empty() {
+        return Ok(());
+    }
+    let f = feed::find_by_id(conn, feed_id)?.ok_or(AppError::FeedNotFound)?;
+    let user_id = category::find_by_id(conn, f.category_id)?
+        .ok_or(AppError::CategoryNotFound)?
+        .user_id;
+    let Some(model) = load_model(conn, user_id)? else {
+        return Ok(());
+    };
+
+    let scores: Vec<(i64, f64)> = entry_interest::entries_by_ids(conn, entry_ids)?
+        .iter()
+        .map(|e| (e.entry_id, model.score(e)))
+        .collect();
+    entry_interest::set_scores(conn, &scores)
+}
+
+/// Retrain every user's model, one user at a time so other writes are not
+/// held up
+async fn retrain_all(db: &DbPool) {
+    let users = match db.background(user::list_all).await {
+        Ok(Ok(users)) => users,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to list users for interest models: {}", e);
+            return;
+        }
+        Err(e) => {
+            tracing::error!("Failed to access DB for interest models: {}", e);
+            return;
+        }
+    };
+
+    for u in users {
+        let user_id = u.id;
+        match db.background(move |conn| retrain_user(conn, user_id)).await {
+            Ok(Ok(Some(scored))) => tracing::debug!(
+                "Trained interest model of user {}, scored {} entries",
+                user_id,
+                scored
+            ),
+            Ok(Ok(None)) => {}
+            Ok(Err(e)) => {
+                tracing::error!("Failed to train interest model of user {}: {}", user_id, e)
+            }
+            Err(e) => tracing::error!("Failed to access DB for interest models: {}", e),
+        }
+    }
+}
+
+/// Start the worker that retrains interest models once a day, and right
+/// away so `sort=interest` works soon after startup
+///
+/// # Arguments
+/// * `db` - Database connection
+/// * `heartbeat` - Reported to while the worker is running
+/// * `cancel_token` - Token to signal graceful shutdown
+pub fn start_interest_worker(
+    db: DbPool,
+    heartbeat: Heartbeat,
+    cancel_token: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        tracing::info!("Interest model worker started");
+
+        let mut interval = tokio::time::interval(TRAINING_INTERVAL);
+        let mut beat = tokio::time::interval(HEARTBEAT_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    tracing::info!("Interest model worker stopping...");
+                    break;
+                }
+                _ = beat.tick() => {
+                    heartbeat.beat();
+                }
+                _ = interval.tick() => {
+                    retrain_all(&db).await;
+                }
+            }
+        }
+
+        tracing::info!("Interest model worker stopped");
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: i64, feed_id: i64, title: &str) -> EntryFeatures {
+        EntryFeatures {
+            entry_id: id,
+            feed_id,
+            title: Some(title.to_string()),
+            author: None,
+        }
+    }
+
+    #[test]
+    fn test_title_tokens() {
+        assert_eq!(
+            title_tokens("Rust 1.80 is out: faster builds!"),
+            vec!["rust", "out", "faster", "builds"]
+        );
+        assert_eq!(title_tokens("台灣新聞"), vec!["台灣", "灣新", "新聞"]);
+    }
+
+    #[test]
+    fn test_train_learns_topics() {
+        let mut examples = Vec::new();
+        for i in 0..30 {
+            examples.push((
+                entry(i, 1, &format!("Rust compiler release {}", i)),
+                Outcome::Read,
+            ));
+            examples.push((
+                entry(100 + i, 2, &format!("Celebrity gossip roundup {}", i)),
+                Outcome::Skipped,
+            ));
+        }
+        let model = InterestModel::train(&examples).unwrap();
+
+        let liked = model.score(&entry(1000, 3, "New rust compiler features"));
+        let disliked = model.score(&entry(1001, 3, "More celebrity gossip"));
+        assert!(liked > 0.5, "{}", liked);
+        assert!(disliked < 0.5, "{}", disliked);
+        // The feed a user reads counts on its own
+        assert!(
+            model.score(&entry(1002, 1, "Untitled")) > model.score(&entry(1003, 2, "Untitled"))
+        );
+    }
+
+    #[test]
+    fn test_train_needs_both_classes() {
+        let examples: Vec<_> = (0..50)
+            .map(|i| (entry(i, 1, "Rust"), Outcome::Read))
+            .collect();
+        assert!(InterestModel::train(&examples).is_none());
+    }
+}
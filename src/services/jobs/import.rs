@@ -0,0 +1,163 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{enqueue, JobError, RefreshJob, Task};
+use crate::db::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::notification;
+use crate::services::opml::{self, OpmlOutline};
+
+/// A job to import parsed OPML outlines, so a large file does not hold the
+/// request open
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportJob {
+    pub user_id: i64,
+    pub outlines: Vec<OpmlOutline>,
+}
+
+impl ImportJob {
+    /// The same outlines queued again before the first import ran are the
+    /// same job
+    pub(super) fn digest(&self) -> String {
+        let payload = serde_json::to_vec(&self.outlines).unwrap_or_default();
+        Sha256::digest(payload)[..8]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+/// Tell the user an import finished, linking to the feeds page
+pub fn notify_import(
+    conn: &Connection,
+    user_id: i64,
+    title: &str,
+    summary: String,
+) -> AppResult<()> {
+    let message = notification::Message {
+        title: title.to_string(),
+        body: Some(summary),
+        link: Some("/feeds".to_string()),
+    };
+    notification::create(
+        conn,
+        user_id,
+        notification::KIND_IMPORT_FINISHED,
+        None,
+        &message,
+    )?;
+    Ok(())
+}
+
+/// Create the categories and feeds, queue the new feeds to be fetched, and
+/// notify the user, all in one transaction. Returns what was imported.
+pub fn import_now(
+    conn: &Connection,
+    user_id: i64,
+    outlines: Vec<OpmlOutline>,
+) -> AppResult<opml::ImportResult> {
+    let tx = conn.unchecked_transaction()?;
+    let result = opml::import_outlines(&tx, user_id, outlines)?;
+    for &feed_id in &result.feed_ids {
+        enqueue(&tx, &Task::Refresh(RefreshJob { user_id, feed_id }))?;
+    }
+    let summary = format!(
+        "{} feeds added, {} skipped",
+        result.feeds_created, result.feeds_skipped
+    );
+    notify_import(&tx, user_id, "OPML import finished", summary)?;
+    tx.commit()?;
+    Ok(result)
+}
+
+/// Run a queued import. The refreshes it queues are picked up by the same
+/// worker once it finishes.
+pub(crate) async fn run_import_job(db: &DbPool, job: &ImportJob) -> Result<(), JobError> {
+    let user_id = job.user_id;
+    let outlines = job.outlines.clone();
+    match db
+        .background(move |conn| import_now(conn, user_id, outlines))
+        .await
+    {
+        Ok(Ok(result)) => {
+            tracing::debug!(
+                "Imported OPML for user {}: {} feeds added",
+                user_id,
+                result.feeds_created
+            );
+            Ok(())
+        }
+        Ok(Err(e @ AppError::Validation(_))) => Err(JobError::Fail(e.to_string())),
+        Ok(Err(e)) => Err(JobError::Retry(e.to_string())),
+        Err(e) => Err(JobError::Retry(format!("Internal error: {}", e))),
+    }
+}
+
+/// Let the user know the import will not happen
+pub(crate) async fn fail_import_job(db: &DbPool, job: &ImportJob, error: &str) {
+    let user_id = job.user_id;
+    let summary = error.to_string();
+    match db
+        .background(move |conn| notify_import(conn, user_id, "OPML import failed", summary))
+        .await
+    {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::error!("Failed to notify user {} of failed import: {}", user_id, e),
+        Err(e) => tracing::error!("Failed to access DB to notify of failed import: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::{self, Role};
+    use crate::models::{feed, job};
+    use crate::services::opml::OpmlFeed;
+
+    #[tokio::test]
+    async fn test_run_import_job() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "importer", "hash", Role::User)
+            .unwrap()
+            .id;
+        let (db, _handle) = DbPool::new(conn);
+
+        let job = ImportJob {
+            user_id,
+            outlines: vec![OpmlOutline {
+                category_name: "Tech".to_string(),
+                feeds: vec![OpmlFeed {
+                    title: Some("Example".to_string()),
+                    xml_url: "https://example.com/feed.xml".to_string(),
+                    html_url: None,
+                }],
+            }],
+        };
+        run_import_job(&db, &job).await.unwrap();
+        // Running it again skips the feed already there
+        run_import_job(&db, &job).await.unwrap();
+
+        let (feeds, jobs, notifications) = db
+            .user(move |conn| {
+                Ok::<_, AppError>((
+                    feed::list_by_user(conn, user_id)?,
+                    job::list(conn, Some(user_id), None, 10)?,
+                    notification::list_by_user(conn, user_id, false, 10)?,
+                ))
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].kind, "refresh");
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(
+            notifications[0].body.as_deref(),
+            Some("0 feeds added, 1 skipped")
+        );
+    }
+}
@@ -0,0 +1,441 @@
+//! Background work kept in the `job` table so it survives restarts.
+//!
+//! Handlers queue a `Task` inside their own transaction and wake the worker
+//! through `JobQueue`. The worker runs due jobs one at a time, retrying
+//! transient failures with backoff according to each kind's `RetryPolicy`
+//! and leaving the rest dead for `GET /api/jobs` to show.
+
+pub mod import;
+pub mod refresh;
+pub mod save;
+pub mod summary;
+pub mod tag;
+mod worker;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rusqlite::Connection;
+use serde::Serialize;
+use tokio::sync::Notify;
+
+pub use import::ImportJob;
+pub use refresh::RefreshJob;
+pub use save::SaveJob;
+pub use summary::SummaryJob;
+pub use tag::TagJob;
+pub use worker::{start_job_worker, JobContext};
+
+use super::digest::DigestJob;
+use super::summary_cache::SummaryCache;
+use crate::db::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::{category_digest, entry_summary, job};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    Summary,
+    Digest,
+    Refresh,
+    Tag,
+    Save,
+    Import,
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::Summary => "summary",
+            JobKind::Digest => "digest",
+            JobKind::Refresh => "refresh",
+            JobKind::Tag => "tag",
+            JobKind::Save => "save",
+            JobKind::Import => "import",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "summary" => Some(JobKind::Summary),
+            "digest" => Some(JobKind::Digest),
+            "refresh" => Some(JobKind::Refresh),
+            "tag" => Some(JobKind::Tag),
+            "save" => Some(JobKind::Save),
+            "import" => Some(JobKind::Import),
+            _ => None,
+        }
+    }
+
+    /// Someone is waiting on a summary; a bulk refresh can queue hundreds of
+    /// feeds, and tagging and saving wait on nobody, so they go last. An
+    /// import goes before the refreshes it queues.
+    fn priority(&self) -> i64 {
+        match self {
+            JobKind::Summary => 0,
+            JobKind::Digest | JobKind::Import => 1,
+            JobKind::Refresh => 2,
+            JobKind::Tag | JobKind::Save => 3,
+        }
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        match self {
+            JobKind::Summary => RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::from_secs(30),
+            },
+            JobKind::Digest => RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::from_secs(60),
+            },
+            JobKind::Refresh => RetryPolicy {
+                max_attempts: 2,
+                base_delay: Duration::from_secs(300),
+            },
+            JobKind::Tag => RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::from_secs(120),
+            },
+            // Bookmark services are often self-hosted and down for a while
+            JobKind::Save => RetryPolicy {
+                max_attempts: 5,
+                base_delay: Duration::from_secs(60),
+            },
+            // Only the database can fail it, and briefly at that
+            JobKind::Import => RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::from_secs(30),
+            },
+        }
+    }
+}
+
+/// How often a job is tried before it is left dead, and how long to wait
+/// between tries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: i64,
+    pub base_delay: Duration,
+}
+
+/// Retries never wait longer than this
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(3600);
+
+impl RetryPolicy {
+    /// Delay before the try following `attempt`, doubling each time
+    pub fn delay(&self, attempt: i64) -> Duration {
+        let exponent = attempt.saturating_sub(1).clamp(0, 16) as u32;
+        self.base_delay
+            .saturating_mul(2u32.pow(exponent))
+            .min(MAX_RETRY_DELAY)
+    }
+}
+
+/// Why a job did not finish
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobError {
+    /// Might work later, e.g. the summarizer could not be reached
+    Retry(String),
+    /// Will never work, e.g. the summarizer is not configured
+    Fail(String),
+}
+
+/// Work the queue knows how to run
+#[derive(Debug, Clone)]
+pub enum Task {
+    Summary(SummaryJob),
+    Digest(DigestJob),
+    Refresh(RefreshJob),
+    Tag(TagJob),
+    Save(SaveJob),
+    Import(ImportJob),
+}
+
+impl Task {
+    pub fn kind(&self) -> JobKind {
+        match self {
+            Task::Summary(_) => JobKind::Summary,
+            Task::Digest(_) => JobKind::Digest,
+            Task::Refresh(_) => JobKind::Refresh,
+            Task::Tag(_) => JobKind::Tag,
+            Task::Save(_) => JobKind::Save,
+            Task::Import(_) => JobKind::Import,
+        }
+    }
+
+    fn user_id(&self) -> i64 {
+        match self {
+            Task::Summary(job) => job.user_id,
+            Task::Digest(job) => job.user_id,
+            Task::Refresh(job) => job.user_id,
+            Task::Tag(job) => job.user_id,
+            Task::Save(job) => job.user_id,
+            Task::Import(job) => job.user_id,
+        }
+    }
+
+    /// Queuing the same work twice while the first is still waiting or
+    /// running adds nothing
+    fn dedupe_key(&self) -> String {
+        match self {
+            Task::Summary(job) => format!("summary:{}:{}", job.user_id, job.entry_id),
+            Task::Digest(job) => format!("digest:{}:{}", job.user_id, job.category_id),
+            Task::Refresh(job) => format!("refresh:{}", job.feed_id),
+            // Entries are new to one refresh, so the first tells jobs apart
+            Task::Tag(job) => format!(
+                "tag:{}:{}",
+                job.user_id,
+                job.entry_ids.first().copied().unwrap_or_default()
+            ),
+            Task::Save(job) => format!("save:{}:{}", job.user_id, job.entry_id),
+            Task::Import(job) => format!("import:{}:{}", job.user_id, job.digest()),
+        }
+    }
+
+    fn payload(&self) -> AppResult<String> {
+        let payload = match self {
+            Task::Summary(job) => serde_json::to_string(job),
+            Task::Digest(job) => serde_json::to_string(job),
+            Task::Refresh(job) => serde_json::to_string(job),
+            Task::Tag(job) => serde_json::to_string(job),
+            Task::Save(job) => serde_json::to_string(job),
+            Task::Import(job) => serde_json::to_string(job),
+        };
+        payload.map_err(|e| AppError::Internal(format!("Failed to encode job: {}", e)))
+    }
+
+    /// Read a task back from a `job` row
+    pub fn parse(kind: &str, payload: &str) -> Result<Self, String> {
+        let task = match JobKind::parse(kind) {
+            Some(JobKind::Summary) => serde_json::from_str(payload).map(Task::Summary),
+            Some(JobKind::Digest) => serde_json::from_str(payload).map(Task::Digest),
+            Some(JobKind::Refresh) => serde_json::from_str(payload).map(Task::Refresh),
+            Some(JobKind::Tag) => serde_json::from_str(payload).map(Task::Tag),
+            Some(JobKind::Save) => serde_json::from_str(payload).map(Task::Save),
+            Some(JobKind::Import) => serde_json::from_str(payload).map(Task::Import),
+            None => return Err(format!("Unknown job kind: {}", kind)),
+        };
+        task.map_err(|e| format!("Invalid {} job: {}", kind, e))
+    }
+}
+
+/// Queue a task, returning its job ID. Call `JobQueue::wake` once the
+/// transaction is done so it starts right away.
+pub fn enqueue(conn: &Connection, task: &Task) -> AppResult<i64> {
+    let kind = task.kind();
+    job::enqueue(
+        conn,
+        &job::NewJob {
+            kind: kind.as_str(),
+            user_id: Some(task.user_id()),
+            payload: task.payload()?,
+            dedupe_key: Some(task.dedupe_key()),
+            priority: kind.priority(),
+        },
+    )
+}
+
+/// Wakes the job worker when work is queued, shared through `AppState`
+#[derive(Debug, Clone, Default)]
+pub struct JobQueue {
+    wake: Arc<Notify>,
+}
+
+impl JobQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tell the worker there is work to do
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    async fn woken(&self) {
+        self.wake.notified().await;
+    }
+}
+
+/// Return interrupted jobs to the queue and queue summaries and digests left
+/// unfinished without a job, e.g. by a version of rdrs that had no queue.
+/// Returns the number of jobs waiting to run.
+pub async fn recover(db: &DbPool, cache: &SummaryCache) -> usize {
+    let recovered = db
+        .background(|conn| {
+            job::requeue_running(conn)?;
+
+            let mut summaries = Vec::new();
+            for (user_id, entry_id, entry_link, options) in entry_summary::find_incomplete(conn)? {
+                let task = Task::Summary(SummaryJob {
+                    user_id,
+                    entry_id,
+                    entry_link,
+                    options,
+                });
+                enqueue(conn, &task)?;
+                summaries.push((user_id, entry_id));
+            }
+
+            for (user_id, category_id) in category_digest::find_incomplete(conn)? {
+                let task = Task::Digest(DigestJob {
+                    user_id,
+                    category_id,
+                });
+                enqueue(conn, &task)?;
+            }
+
+            let pending = job::count_by_status(conn, job::JobStatus::Pending)?;
+            Ok::<_, AppError>((summaries, pending as usize))
+        })
+        .await;
+
+    match recovered {
+        Ok(Ok((summaries, pending))) => {
+            // Requests for the summary show it waiting rather than missing
+            for (user_id, entry_id) in summaries {
+                cache.set_pending(user_id, entry_id);
+            }
+            pending
+        }
+        Ok(Err(e)) => {
+            tracing::error!("Failed to recover jobs: {}", e);
+            0
+        }
+        Err(e) => {
+            tracing::error!("Failed to access DB for job recovery: {}", e);
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::Role;
+    use crate::models::{category, entry, feed, user};
+    use crate::services::SummaryOptions;
+
+    fn setup_test_db() -> DbPool {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let (pool, _handle) = DbPool::new(conn);
+        pool
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_cap() {
+        let policy = JobKind::Summary.retry_policy();
+        assert_eq!(policy.delay(1), Duration::from_secs(30));
+        assert_eq!(policy.delay(2), Duration::from_secs(60));
+        assert_eq!(policy.delay(3), Duration::from_secs(120));
+        assert_eq!(policy.delay(100), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_task_round_trip() {
+        let task = Task::Refresh(RefreshJob {
+            user_id: 1,
+            feed_id: 7,
+        });
+        let parsed = Task::parse(task.kind().as_str(), &task.payload().unwrap()).unwrap();
+        assert!(matches!(parsed, Task::Refresh(job) if job.feed_id == 7));
+
+        assert!(Task::parse("mystery", "{}").is_err());
+        assert!(Task::parse("summary", "{}").is_err());
+    }
+
+    #[test]
+    fn test_enqueue_dedupes() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "testuser", "hash", Role::User)
+            .unwrap()
+            .id;
+        let task = Task::Digest(DigestJob {
+            user_id,
+            category_id: 3,
+        });
+
+        let id = enqueue(&conn, &task).unwrap();
+        assert_eq!(enqueue(&conn, &task).unwrap(), id);
+        let listed = job::list(&conn, Some(user_id), None, 10).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].kind, "digest");
+    }
+
+    #[tokio::test]
+    async fn test_recover_incomplete_summaries() {
+        let db = setup_test_db();
+
+        // A summary left processing by a crashed worker that had no queue
+        let (user_id, entry_id) = db
+            .user(|conn| {
+                let user_id = user::create_user(conn, "testuser", "hash", Role::User)
+                    .unwrap()
+                    .id;
+                let category_id = category::create_category(conn, user_id, "Tech").unwrap().id;
+                let feed_id = feed::create_feed(
+                    conn,
+                    category_id,
+                    "https://example.com/feed.xml",
+                    Some("Feed"),
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap()
+                .id;
+                let (entry_obj, _) = entry::upsert_entry(
+                    conn,
+                    feed_id,
+                    "guid-1",
+                    Some("Entry"),
+                    Some("https://example.com/article"),
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap();
+                entry_summary::upsert_pending(
+                    conn,
+                    user_id,
+                    entry_obj.id,
+                    SummaryOptions::default(),
+                )
+                .unwrap();
+                entry_summary::set_processing(conn, user_id, entry_obj.id).unwrap();
+                (user_id, entry_obj.id)
+            })
+            .await
+            .unwrap();
+
+        let cache = SummaryCache::new(100, 24);
+        assert_eq!(recover(&db, &cache).await, 1);
+        assert!(cache.get(user_id, entry_id).is_some());
+
+        // Recovering again finds the job already queued
+        assert_eq!(recover(&db, &cache).await, 1);
+
+        let claimed = db
+            .background(job::claim_next)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        match Task::parse(&claimed.kind, &claimed.payload).unwrap() {
+            Task::Summary(job) => assert_eq!(job.entry_link, "https://example.com/article"),
+            other => panic!("unexpected task {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recover_empty() {
+        let db = setup_test_db();
+        let cache = SummaryCache::new(100, 24);
+        assert_eq!(recover(&db, &cache).await, 0);
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use super::{JobContext, JobError};
+use crate::error::AppError;
+use crate::services::feed_sync::refresh_feed;
+
+/// A job to fetch one feed now rather than at its next scheduled sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshJob {
+    pub user_id: i64,
+    pub feed_id: i64,
+}
+
+/// Fetch the feed. A feed that is gone or cannot be refreshed is not tried
+/// again; fetch and parse errors are.
+pub(crate) async fn run_refresh_job(ctx: &JobContext, job: &RefreshJob) -> Result<(), JobError> {
+    let feed_id = job.feed_id;
+    let settings = ctx.settings.current();
+    let result = ctx
+        .refreshes
+        .run(feed_id, || {
+            refresh_feed(
+                ctx.db.clone(),
+                feed_id,
+                &settings.user_agent,
+                ctx.feed_cookie_secret.as_deref(),
+            )
+        })
+        .await;
+
+    match result {
+        Ok(result) => {
+            tracing::debug!(
+                "Refreshed feed {}: {} new entries",
+                feed_id,
+                result.new_entries
+            );
+            Ok(())
+        }
+        Err(e @ (AppError::FeedNotFound | AppError::Validation(_))) => {
+            Err(JobError::Fail(e.to_string()))
+        }
+        Err(e) => Err(JobError::Retry(e.to_string())),
+    }
+}
@@ -0,0 +1,202 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use super::{enqueue, JobError, Task};
+use crate::db::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::{entry, entry_save, user_settings};
+use crate::services::save;
+
+/// A job to save a starred entry to the user's save services
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveJob {
+    pub user_id: i64,
+    pub entry_id: i64,
+}
+
+/// Queue saving an entry the user just starred, if they turned automatic
+/// saving on and have somewhere to save it. Returns whether a job was
+/// queued.
+pub fn queue_starred(conn: &Connection, user_id: i64, entry_id: i64) -> AppResult<bool> {
+    if !user_settings::get_auto_save_starred(conn, user_id)? {
+        return Ok(false);
+    }
+    let Some(e) = entry::find_by_id(conn, entry_id)? else {
+        return Ok(false);
+    };
+    if e.link.is_none() {
+        return Ok(false);
+    }
+
+    let config = user_settings::get_save_services_config(conn, user_id)?;
+    let mut pending = false;
+    for service in config.configured_services() {
+        pending |= entry_save::mark_pending(conn, user_id, entry_id, service)?;
+    }
+    if !pending {
+        return Ok(false);
+    }
+
+    enqueue(conn, &Task::Save(SaveJob { user_id, entry_id }))?;
+    Ok(true)
+}
+
+/// Save the entry to each service it is still waiting on. A service that
+/// answers with an error is recorded as failed and not tried again; one that
+/// cannot be reached is retried with the job.
+pub(crate) async fn run_save_job(db: &DbPool, job: &SaveJob) -> Result<(), JobError> {
+    let SaveJob { user_id, entry_id } = *job;
+    let loaded = db
+        .background(move |conn| {
+            let e = entry::find_by_id_with_feed(conn, entry_id)?;
+            // Unstarred before its turn came
+            let Some(e) = e.filter(|e| e.entry.starred_at.is_some()) else {
+                entry_save::clear_pending(conn, user_id, entry_id)?;
+                return Ok(None);
+            };
+            let config = user_settings::get_save_services_config(conn, user_id)?;
+            let services = entry_save::pending_services(conn, user_id, entry_id)?;
+            let bookmark = save::bookmark_for_entry(conn, user_id, &e, &config)?;
+            Ok::<_, AppError>(Some((config, services, bookmark)))
+        })
+        .await;
+
+    let (config, services, bookmark) = match loaded {
+        Ok(Ok(Some(loaded))) => loaded,
+        Ok(Ok(None)) => return Ok(()),
+        Ok(Err(e)) => return Err(JobError::Retry(format!("Failed to load entry: {}", e))),
+        Err(e) => return Err(JobError::Retry(format!("Internal error: {}", e))),
+    };
+
+    let mut unreachable = None;
+    for service in services {
+        let result = match save::save_to(&config, &service, &bookmark).await {
+            Ok(result) => result,
+            Err(AppError::Validation(message)) => save::SaveResult {
+                success: false,
+                service,
+                message,
+                bookmark_url: None,
+                already_saved: false,
+            },
+            // Left pending for the next try
+            Err(e) => {
+                unreachable = Some(e.to_string());
+                continue;
+            }
+        };
+        match db
+            .background(move |conn| entry_save::record(conn, user_id, entry_id, &result))
+            .await
+        {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => return Err(JobError::Retry(format!("Failed to record save: {}", e))),
+            Err(e) => return Err(JobError::Retry(format!("Internal error: {}", e))),
+        }
+    }
+
+    match unreachable {
+        Some(error) => Err(JobError::Retry(error)),
+        None => Ok(()),
+    }
+}
+
+/// Mark the saves the job was still waiting on as failed
+pub(crate) async fn fail_save_job(db: &DbPool, job: &SaveJob, error: &str) {
+    let SaveJob { user_id, entry_id } = *job;
+    let error = error.to_string();
+    match db
+        .background(move |conn| entry_save::fail_pending(conn, user_id, entry_id, &error))
+        .await
+    {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::error!("Failed to record failed save of entry {}: {}", entry_id, e),
+        Err(e) => tracing::error!("Failed to access DB to record failed save: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::{self, Role};
+    use crate::models::{category, feed, job};
+    use crate::services::save::{LinkdingConfig, SaveServicesConfig};
+
+    #[test]
+    fn test_queue_starred_only_when_opted_in() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "saver", "hash", Role::User)
+            .unwrap()
+            .id;
+        let category_id = category::create_category(&conn, user_id, "Tech")
+            .unwrap()
+            .id;
+        let feed_id = feed::create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed.xml",
+            Some("Feed"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .id;
+        let (e, _) = entry::upsert_entry(
+            &conn,
+            feed_id,
+            "guid-1",
+            Some("Entry"),
+            Some("https://example.com/article"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let queued = |conn: &Connection| job::list(conn, Some(user_id), None, 10).unwrap().len();
+
+        user_settings::update_save_services(
+            &conn,
+            user_id,
+            &SaveServicesConfig {
+                linkding: Some(LinkdingConfig {
+                    api_url: "https://ld.example.com".to_string(),
+                    api_token: "token".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(!queue_starred(&conn, user_id, e.id).unwrap());
+        assert_eq!(queued(&conn), 0);
+
+        user_settings::update_auto_save_starred(&conn, user_id, true).unwrap();
+        assert!(queue_starred(&conn, user_id, e.id).unwrap());
+        assert_eq!(queued(&conn), 1);
+        assert_eq!(
+            entry_save::pending_services(&conn, user_id, e.id).unwrap(),
+            vec!["linkding"]
+        );
+
+        // Nothing left to save once it is saved
+        entry_save::record(
+            &conn,
+            user_id,
+            e.id,
+            &save::SaveResult {
+                success: true,
+                service: "linkding".to_string(),
+                message: "Saved to Linkding".to_string(),
+                bookmark_url: None,
+                already_saved: false,
+            },
+        )
+        .unwrap();
+        assert!(!queue_starred(&conn, user_id, e.id).unwrap());
+    }
+}
@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+
+use super::JobError;
+use crate::db::DbPool;
+use crate::error::AppError;
+use crate::models::{entry, entry_summary, shared_summary, user_settings};
+use crate::services::digest::html_to_text;
+use crate::services::summarize::kagi::{self, KagiConfig};
+use crate::services::summarize::openai::{self, OpenAiConfig};
+use crate::services::summarize::{SummaryOptions, SummaryStyle};
+use crate::services::summary_cache::SummaryCache;
+
+/// A job to summarize an entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummaryJob {
+    pub user_id: i64,
+    pub entry_id: i64,
+    pub entry_link: String,
+    /// Style and length to make the summary in
+    #[serde(default)]
+    pub options: SummaryOptions,
+}
+
+/// Summarize the entry, recording progress in the cache and `entry_summary`.
+/// Failures are left for the queue to retry or hand to `fail_summary_job`.
+pub(crate) async fn run_summary_job(
+    job: &SummaryJob,
+    cache: &SummaryCache,
+    db: &DbPool,
+) -> Result<(), JobError> {
+    tracing::debug!(
+        "Processing summary job: user={}, entry={}, link={}",
+        job.user_id,
+        job.entry_id,
+        job.entry_link
+    );
+
+    // Mark as processing in both cache and DB
+    cache.set_processing(job.user_id, job.entry_id);
+    let user_id = job.user_id;
+    let entry_id = job.entry_id;
+    let _ = db
+        .background(move |conn| entry_summary::set_processing(conn, user_id, entry_id))
+        .await;
+
+    // Get summarizer config for the user
+    let config = match db
+        .background(move |conn| user_settings::get_save_services_config(conn, user_id))
+        .await
+    {
+        Ok(Ok(config)) => config,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to get user settings: {}", e);
+            return Err(JobError::Retry(
+                "Failed to load summarizer settings".to_string(),
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Failed to access DB: {}", e);
+            return Err(JobError::Retry(
+                "Internal error: DB access failed".to_string(),
+            ));
+        }
+    };
+
+    // An OpenAI-compatible backend is preferred and follows the style itself;
+    // only Kagi's unstyled output is shared with other users
+    let (summary_text, shared) = match config.openai().cloned() {
+        Some(openai) => (summarize_with_openai(job, &openai, cache, db).await?, None),
+        None => {
+            let kagi_config = match config.kagi {
+                Some(c) if c.is_configured() => c,
+                _ => return Err(JobError::Fail("Kagi is not configured".to_string())),
+            };
+            let (key, raw_text) = kagi_summary(job, &kagi_config, db).await?;
+            (job.options.shape(&raw_text), Some((key, raw_text)))
+        }
+    };
+    tracing::debug!(
+        "Summary completed for entry {}: {} chars",
+        job.entry_id,
+        summary_text.len()
+    );
+    cache.set_completed(user_id, entry_id, summary_text.clone());
+    let _ = db
+        .background(move |conn| {
+            entry_summary::set_completed(conn, user_id, entry_id, &summary_text)?;
+            if let Some((key, raw_text)) = shared {
+                shared_summary::store(conn, &key, &raw_text)?;
+            }
+            Ok::<_, AppError>(())
+        })
+        .await;
+    Ok(())
+}
+
+/// Kagi's summary of the entry's link, or one already made for another user.
+/// Returns the shared summary key with the text.
+async fn kagi_summary(
+    job: &SummaryJob,
+    config: &KagiConfig,
+    db: &DbPool,
+) -> Result<(String, String), JobError> {
+    // Another user may have had the same article summarized meanwhile
+    let style = job.options.style;
+    let key = shared_summary::link_key(&job.entry_link, style.summary_type());
+    let lookup_key = key.clone();
+    let shared = match db
+        .background(move |conn| shared_summary::find_text(conn, &lookup_key))
+        .await
+    {
+        Ok(Ok(text)) => text,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to look up shared summary: {}", e);
+            None
+        }
+        Err(e) => {
+            tracing::error!("Failed to access DB: {}", e);
+            None
+        }
+    };
+
+    // Call Kagi API
+    let raw_text = match shared {
+        Some(text) => text,
+        None => summarize_with_kagi(config, &job.entry_link, style).await?,
+    };
+    Ok((key, raw_text))
+}
+
+/// Summarize the entry's text with an OpenAI-compatible backend, publishing
+/// the text to the cache as it is written
+async fn summarize_with_openai(
+    job: &SummaryJob,
+    config: &OpenAiConfig,
+    cache: &SummaryCache,
+    db: &DbPool,
+) -> Result<String, JobError> {
+    let user_id = job.user_id;
+    let entry_id = job.entry_id;
+    let html = match db
+        .background(move |conn| entry::find_article_html(conn, entry_id))
+        .await
+    {
+        Ok(Ok(html)) => html.unwrap_or_default(),
+        Ok(Err(AppError::EntryNotFound)) => {
+            return Err(JobError::Fail("Entry not found".to_string()))
+        }
+        Ok(Err(e)) => return Err(JobError::Retry(format!("Failed to load entry: {}", e))),
+        Err(e) => return Err(JobError::Retry(format!("Internal error: {}", e))),
+    };
+    let article = html_to_text(&html);
+    if article.is_empty() {
+        return Err(JobError::Fail("Entry has no text to summarize".to_string()));
+    }
+
+    let result = openai::summarize_stream(config, &article, job.options, |text| {
+        cache.set_partial(user_id, entry_id, text)
+    })
+    .await;
+    match result {
+        Ok(result) if result.success => result
+            .output_text
+            .ok_or_else(|| JobError::Fail("No summary text returned".to_string())),
+        Ok(result) => Err(JobError::Fail(
+            result.error.unwrap_or_else(|| "Unknown error".to_string()),
+        )),
+        Err(e) => Err(JobError::Retry(format!("Summarizer error: {}", e))),
+    }
+}
+
+/// Record a summary the queue gave up on as failed
+pub(crate) async fn fail_summary_job(
+    job: &SummaryJob,
+    cache: &SummaryCache,
+    db: &DbPool,
+    error: &str,
+) {
+    tracing::warn!("Summary failed for entry {}: {}", job.entry_id, error);
+    let user_id = job.user_id;
+    let entry_id = job.entry_id;
+    let error = error.to_string();
+    cache.set_failed(user_id, entry_id, error.clone());
+    let _ = db
+        .background(move |conn| entry_summary::set_failed(conn, user_id, entry_id, &error))
+        .await;
+}
+
+/// Call Kagi API to get a summary. Kagi refusing the URL is final; not
+/// reaching Kagi is worth another try.
+async fn summarize_with_kagi(
+    config: &KagiConfig,
+    url: &str,
+    style: SummaryStyle,
+) -> Result<String, JobError> {
+    match kagi::summarize_url(config, url, style).await {
+        Ok(result) => {
+            if result.success {
+                result
+                    .output_text
+                    .ok_or_else(|| JobError::Fail("No summary text returned".to_string()))
+            } else {
+                Err(JobError::Fail(
+                    result.error.unwrap_or_else(|| "Unknown error".to_string()),
+                ))
+            }
+        }
+        Err(e) => Err(JobError::Retry(format!("Kagi API error: {}", e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::{self, Role};
+    use crate::services::SummaryStatus;
+    use rusqlite::Connection;
+
+    fn setup_test_db() -> DbPool {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let (pool, _handle) = DbPool::new(conn);
+        pool
+    }
+
+    #[test]
+    fn test_summary_job_round_trip() {
+        let job = SummaryJob {
+            user_id: 1,
+            entry_id: 100,
+            entry_link: "https://example.com/article".to_string(),
+            options: SummaryOptions::default(),
+        };
+
+        let json = serde_json::to_string(&job).unwrap();
+        let parsed: SummaryJob = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.user_id, 1);
+        assert_eq!(parsed.entry_id, 100);
+        assert_eq!(parsed.entry_link, "https://example.com/article");
+
+        // Jobs queued before summaries had styles still parse
+        let queued: SummaryJob =
+            serde_json::from_str(r#"{"user_id": 1, "entry_id": 2, "entry_link": "x"}"#).unwrap();
+        assert_eq!(queued.options, SummaryOptions::default());
+    }
+
+    #[tokio::test]
+    async fn test_run_without_kagi_fails_for_good() {
+        let db = setup_test_db();
+        let user_id = db
+            .user(|conn| {
+                user::create_user(conn, "testuser", "hash", Role::User)
+                    .unwrap()
+                    .id
+            })
+            .await
+            .unwrap();
+        let cache = SummaryCache::new(100, 24);
+        let job = SummaryJob {
+            user_id,
+            entry_id: 1,
+            entry_link: "https://example.com".to_string(),
+            options: SummaryOptions::default(),
+        };
+
+        let result = run_summary_job(&job, &cache, &db).await;
+        assert!(matches!(result, Err(JobError::Fail(ref e)) if e == "Kagi is not configured"));
+        assert_eq!(
+            cache.get(user_id, 1).unwrap().status,
+            SummaryStatus::Processing
+        );
+
+        fail_summary_job(&job, &cache, &db, "Kagi is not configured").await;
+        let cached = cache.get(user_id, 1).unwrap();
+        assert_eq!(cached.status, SummaryStatus::Failed);
+        assert_eq!(
+            cached.error_message.as_deref(),
+            Some("Kagi is not configured")
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use super::{enqueue, JobError, Task};
+use crate::db::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::{category, entry, feed, tag, user_settings};
+use crate::services::digest::html_to_text;
+use crate::services::summarize::openai::{self, Label};
+
+/// A job to tag new entries of one feed from the user's tags
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagJob {
+    pub user_id: i64,
+    pub entry_ids: Vec<i64>,
+}
+
+/// Queue tagging of entries a refresh of `feed_id` just added, if the feed's
+/// owner turned automatic tagging on, has tags and has a backend to ask
+pub fn queue_new_entries(conn: &Connection, feed_id: i64, entry_ids: &[i64]) -> AppResult<()> {
+    if entry_ids.is_empty() {
+        return Ok(());
+    }
+    let f = feed::find_by_id(conn, feed_id)?.ok_or(AppError::FeedNotFound)?;
+    let user_id = category::find_by_id(conn, f.category_id)?
+        .ok_or(AppError::CategoryNotFound)?
+        .user_id;
+
+    if !user_settings::get_auto_tag(conn, user_id)?
+        || user_settings::get_save_services_config(conn, user_id)?
+            .openai()
+            .is_none()
+        || tag::list_by_user(conn, user_id)?.is_empty()
+    {
+        return Ok(());
+    }
+
+    enqueue(
+        conn,
+        &Task::Tag(TagJob {
+            user_id,
+            entry_ids: entry_ids.to_vec(),
+        }),
+    )?;
+    Ok(())
+}
+
+/// What tagging needs to know about one entry
+struct Article {
+    id: i64,
+    title: String,
+    text: String,
+}
+
+/// Ask the backend which of the user's tags fit each entry. Entries already
+/// tagged, e.g. by an earlier try of the same job, are skipped.
+pub(crate) async fn run_tag_job(db: &DbPool, job: &TagJob) -> Result<(), JobError> {
+    let user_id = job.user_id;
+    let entry_ids = job.entry_ids.clone();
+    let loaded = db
+        .background(move |conn| {
+            let config = user_settings::get_save_services_config(conn, user_id)?
+                .openai()
+                .cloned();
+            let tags = tag::list_by_user(conn, user_id)?;
+
+            let mut articles = Vec::new();
+            for id in entry_ids {
+                if tag::is_tagged(conn, id)? {
+                    continue;
+                }
+                let Some(e) = entry::find_by_id(conn, id)? else {
+                    continue;
+                };
+                let html = entry::find_article_html(conn, id)?.unwrap_or_default();
+                articles.push(Article {
+                    id,
+                    title: e.title.unwrap_or_default(),
+                    text: html_to_text(&html),
+                });
+            }
+            Ok::<_, AppError>((config, tags, articles))
+        })
+        .await;
+
+    let (config, tags, articles) = match loaded {
+        Ok(Ok(loaded)) => loaded,
+        Ok(Err(e)) => return Err(JobError::Retry(format!("Failed to load entries: {}", e))),
+        Err(e) => return Err(JobError::Retry(format!("Internal error: {}", e))),
+    };
+    let Some(config) = config else {
+        return Err(JobError::Fail(
+            "No OpenAI-compatible backend is configured".to_string(),
+        ));
+    };
+    if tags.is_empty() {
+        return Ok(());
+    }
+    let labels: Vec<Label> = tags
+        .iter()
+        .map(|t| Label {
+            name: t.name.clone(),
+            description: t.description.clone(),
+        })
+        .collect();
+
+    for article in articles {
+        if article.title.is_empty() && article.text.is_empty() {
+            continue;
+        }
+        let names = match openai::pick_labels(&config, &labels, &article.title, &article.text).await
+        {
+            Ok(Ok(names)) => names,
+            Ok(Err(message)) => return Err(JobError::Fail(message)),
+            Err(e) => return Err(JobError::Retry(format!("Summarizer error: {}", e))),
+        };
+        if names.is_empty() {
+            continue;
+        }
+
+        let tag_ids: Vec<i64> = tags
+            .iter()
+            .filter(|t| names.contains(&t.name))
+            .map(|t| t.id)
+            .collect();
+        let entry_id = article.id;
+        match db
+            .background(move |conn| tag::add_entry_tags(conn, entry_id, &tag_ids))
+            .await
+        {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => return Err(JobError::Retry(format!("Failed to save tags: {}", e))),
+            Err(e) => return Err(JobError::Retry(format!("Internal error: {}", e))),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::job;
+    use crate::models::user::{self, Role};
+    use crate::services::save::SaveServicesConfig;
+    use crate::services::OpenAiConfig;
+
+    #[test]
+    fn test_queue_new_entries_only_when_opted_in() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "tagger", "hash", Role::User)
+            .unwrap()
+            .id;
+        let category_id = category::create_category(&conn, user_id, "Tech")
+            .unwrap()
+            .id;
+        let feed_id = feed::create_feed(
+            &conn,
+            category_id,
+            "https://example.com/feed.xml",
+            Some("Feed"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .id;
+        let queued = |conn: &Connection| job::list(conn, Some(user_id), None, 10).unwrap().len();
+
+        tag::create_tag(&conn, user_id, "rust", None).unwrap();
+        user_settings::update_save_services(
+            &conn,
+            user_id,
+            &SaveServicesConfig {
+                openai: Some(OpenAiConfig {
+                    base_url: "http://localhost:11434/v1".to_string(),
+                    api_key: String::new(),
+                    model: "llama3".to_string(),
+                }),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        queue_new_entries(&conn, feed_id, &[1, 2]).unwrap();
+        assert_eq!(queued(&conn), 0);
+
+        user_settings::update_auto_tag(&conn, user_id, true).unwrap();
+        queue_new_entries(&conn, feed_id, &[]).unwrap();
+        assert_eq!(queued(&conn), 0);
+        queue_new_entries(&conn, feed_id, &[1, 2]).unwrap();
+        assert_eq!(queued(&conn), 1);
+    }
+}
@@ -0,0 +1,280 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::import::{fail_import_job, run_import_job};
+use super::refresh::run_refresh_job;
+use super::save::{fail_save_job, run_save_job};
+use super::summary::{fail_summary_job, run_summary_job};
+use super::tag::run_tag_job;
+use super::{JobError, JobQueue, Task};
+use crate::db::DbPool;
+use crate::models::job::{self, Job, JOB_RETENTION_DAYS};
+use crate::services::digest::{fail_digest_job, run_digest_job};
+use crate::services::heartbeat::{Heartbeat, HEARTBEAT_INTERVAL};
+use crate::services::{LiveSettings, RefreshRegistry, SummaryCache};
+
+/// How often the queue is checked when nothing wakes the worker, which is
+/// how retries are picked up once their delay has passed
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often finished jobs past `JOB_RETENTION_DAYS` are deleted
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// What jobs need to run
+#[derive(Clone)]
+pub struct JobContext {
+    pub db: DbPool,
+    pub summary_cache: Arc<SummaryCache>,
+    pub refreshes: RefreshRegistry,
+    pub settings: LiveSettings,
+    pub feed_cookie_secret: Option<Vec<u8>>,
+}
+
+/// How a run ended, as recorded on the job
+enum Outcome {
+    Completed,
+    Retry(String, Duration),
+    Dead(String),
+}
+
+/// Start the worker that runs queued jobs until cancelled. A job still
+/// running at shutdown is finished first; jobs left waiting stay queued for
+/// the next start.
+pub fn start_job_worker(
+    ctx: JobContext,
+    queue: JobQueue,
+    heartbeat: Heartbeat,
+    cancel_token: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        tracing::info!("Job worker started");
+
+        let mut beat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        let mut poll = tokio::time::interval(POLL_INTERVAL);
+        let mut prune = tokio::time::interval(PRUNE_INTERVAL);
+
+        loop {
+            // Work through everything due before waiting again
+            while !cancel_token.is_cancelled() {
+                let Some(job) = claim_next(&ctx.db).await else {
+                    break;
+                };
+                run_job(&ctx, job).await;
+                heartbeat.beat();
+            }
+
+            tokio::select! {
+                _ = cancel_token.cancelled() => break,
+                _ = queue.woken() => {}
+                _ = poll.tick() => {}
+                _ = beat.tick() => heartbeat.beat(),
+                _ = prune.tick() => prune_finished(&ctx.db).await,
+            }
+        }
+
+        tracing::info!("Job worker stopped");
+    })
+}
+
+async fn claim_next(db: &DbPool) -> Option<Job> {
+    match db.background(job::claim_next).await {
+        Ok(Ok(job)) => job,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to claim job: {}", e);
+            None
+        }
+        Err(e) => {
+            tracing::error!("Failed to access DB to claim job: {}", e);
+            None
+        }
+    }
+}
+
+async fn run_job(ctx: &JobContext, job: Job) {
+    tracing::debug!(
+        "Running {} job {} (attempt {})",
+        job.kind,
+        job.id,
+        job.attempts
+    );
+
+    let outcome = match Task::parse(&job.kind, &job.payload) {
+        Ok(task) => {
+            let policy = task.kind().retry_policy();
+            match run_task(ctx, &task).await {
+                Ok(()) => Outcome::Completed,
+                Err(JobError::Retry(error)) if job.attempts < policy.max_attempts => {
+                    Outcome::Retry(error, policy.delay(job.attempts))
+                }
+                Err(JobError::Retry(error)) | Err(JobError::Fail(error)) => {
+                    give_up(ctx, &task, &error).await;
+                    Outcome::Dead(error)
+                }
+            }
+        }
+        Err(error) => Outcome::Dead(error),
+    };
+
+    match &outcome {
+        Outcome::Completed => {}
+        Outcome::Retry(error, delay) => tracing::warn!(
+            "{} job {} failed, retrying in {}s: {}",
+            job.kind,
+            job.id,
+            delay.as_secs(),
+            error
+        ),
+        Outcome::Dead(error) => tracing::warn!("{} job {} gave up: {}", job.kind, job.id, error),
+    }
+
+    let id = job.id;
+    let recorded = ctx
+        .db
+        .background(move |conn| match outcome {
+            Outcome::Completed => job::complete(conn, id),
+            Outcome::Retry(error, delay) => job::retry(conn, id, &error, delay.as_secs() as i64),
+            Outcome::Dead(error) => job::kill(conn, id, &error),
+        })
+        .await;
+    match recorded {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::error!("Failed to record job {}: {}", id, e),
+        Err(e) => tracing::error!("Failed to access DB to record job {}: {}", id, e),
+    }
+}
+
+async fn run_task(ctx: &JobContext, task: &Task) -> Result<(), JobError> {
+    match task {
+        Task::Summary(job) => run_summary_job(job, &ctx.summary_cache, &ctx.db).await,
+        Task::Digest(job) => run_digest_job(&ctx.db, job).await,
+        Task::Refresh(job) => run_refresh_job(ctx, job).await,
+        Task::Tag(job) => run_tag_job(&ctx.db, job).await,
+        Task::Save(job) => run_save_job(&ctx.db, job).await,
+        Task::Import(job) => run_import_job(&ctx.db, job).await,
+    }
+}
+
+/// Let whatever waits on the task know it will not finish
+async fn give_up(ctx: &JobContext, task: &Task, error: &str) {
+    match task {
+        Task::Summary(job) => fail_summary_job(job, &ctx.summary_cache, &ctx.db, error).await,
+        Task::Digest(job) => fail_digest_job(&ctx.db, job, error).await,
+        Task::Save(job) => fail_save_job(&ctx.db, job, error).await,
+        Task::Import(job) => fail_import_job(&ctx.db, job, error).await,
+        // The feed records its own fetch errors; untagged entries stay so
+        Task::Refresh(_) | Task::Tag(_) => {}
+    }
+}
+
+async fn prune_finished(db: &DbPool) {
+    match db
+        .background(|conn| job::delete_finished(conn, JOB_RETENTION_DAYS))
+        .await
+    {
+        Ok(Ok(count)) if count > 0 => tracing::debug!("Deleted {} finished jobs", count),
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => tracing::error!("Failed to delete finished jobs: {}", e),
+        Err(e) => tracing::error!("Failed to access DB to delete finished jobs: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::job::JobStatus;
+    use crate::models::user::{self, Role};
+    use crate::services::jobs::{enqueue, SummaryJob};
+    use crate::services::InstanceSettings;
+    use rusqlite::Connection;
+
+    fn setup_context() -> JobContext {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let (db, _handle) = DbPool::new(conn);
+        JobContext {
+            db,
+            summary_cache: Arc::new(SummaryCache::new(100, 24)),
+            refreshes: RefreshRegistry::default(),
+            settings: LiveSettings::new(InstanceSettings::default()),
+            feed_cookie_secret: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_worker_gives_up_on_permanent_failure() {
+        let ctx = setup_context();
+        let queue = JobQueue::new();
+        let cancel_token = CancellationToken::new();
+
+        // Without Kagi configured the summary can never succeed
+        let user_id = ctx
+            .db
+            .user(|conn| {
+                let user_id = user::create_user(conn, "testuser", "hash", Role::User)
+                    .unwrap()
+                    .id;
+                let task = Task::Summary(SummaryJob {
+                    user_id,
+                    entry_id: 1,
+                    entry_link: "https://example.com".to_string(),
+                    options: Default::default(),
+                });
+                enqueue(conn, &task).unwrap();
+                user_id
+            })
+            .await
+            .unwrap();
+
+        let handle = start_job_worker(
+            ctx.clone(),
+            queue.clone(),
+            Heartbeat::detached("job_worker"),
+            cancel_token.clone(),
+        );
+        queue.wake();
+
+        let mut dead = Vec::new();
+        for _ in 0..50 {
+            dead = ctx
+                .db
+                .read(move |conn| job::list(conn, Some(user_id), Some(JobStatus::Dead), 10))
+                .await
+                .unwrap()
+                .unwrap();
+            if !dead.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].attempts, 1);
+        assert_eq!(
+            dead[0].last_error.as_deref(),
+            Some("Kagi is not configured")
+        );
+
+        cancel_token.cancel();
+        let result = tokio::time::timeout(Duration::from_secs(5), handle).await;
+        assert!(result.is_ok(), "Worker should stop after cancellation");
+    }
+
+    #[tokio::test]
+    async fn test_worker_stops_on_cancellation() {
+        let ctx = setup_context();
+        let cancel_token = CancellationToken::new();
+        let handle = start_job_worker(
+            ctx,
+            JobQueue::new(),
+            Heartbeat::detached("job_worker"),
+            cancel_token.clone(),
+        );
+
+        cancel_token.cancel();
+        let result = tokio::time::timeout(Duration::from_secs(5), handle).await;
+        assert!(result.is_ok(), "Worker should stop after cancellation");
+    }
+}
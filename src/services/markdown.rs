@@ -0,0 +1,50 @@
+//! Markdown the user writes themselves, such as entry notes, rendered to
+//! HTML. Raw HTML in the source is shown as text rather than markup, and the
+//! result is sanitized as well. Images are dropped, so opening a note never
+//! loads anything from another site.
+
+use ammonia::Builder;
+use pulldown_cmark::{html, Event, Options, Parser};
+
+/// Render `markdown` as safe HTML
+pub fn render(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+    let parser = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        event => event,
+    });
+    let mut out = String::new();
+    html::push_html(&mut out, parser);
+
+    Builder::default()
+        .rm_tags(&["img"])
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .clean(&out)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_markdown() {
+        let html = render("**Why** I saved this:\n\n- [source](https://example.com)\n- ~~old~~");
+        assert!(html.contains("<strong>Why</strong>"));
+        assert!(html.contains(
+            r#"<a href="https://example.com" rel="noopener noreferrer nofollow">source</a>"#
+        ));
+        assert!(html.contains("<del>old</del>"));
+    }
+
+    #[test]
+    fn test_render_drops_unsafe_markup() {
+        let html = render(
+            "<script>alert(1)</script>\n\n[x](javascript:alert(1)) ![i](https://example.com/a.png)",
+        );
+        assert!(!html.contains("<script"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("javascript:"));
+        assert!(!html.contains("<img"));
+    }
+}
@@ -0,0 +1,145 @@
+//! LaTeX math in entry content, rendered to MathML so browsers display it
+//! natively. Recognizes `\(...\)` and `$...$` as inline math, and `\[...\]`
+//! and `$$...$$` as display math. A `$` only opens inline math when followed
+//! by a non-space, and only closes it when preceded by a non-space and not
+//! followed by a digit, so prices such as "$5 and $10" stay text.
+
+use latex2mathml::{latex_to_mathml, DisplayStyle};
+
+use super::sanitize::decode_entities;
+
+/// Formulas longer than this are left as written
+const MAX_FORMULA_LEN: usize = 4096;
+
+/// Render the math in `text`, an HTML text node with its entities still
+/// encoded. Returns `None` when there is no math to render, otherwise the
+/// text as HTML with each formula replaced by MathML. Formulas that fail to
+/// convert are kept as written.
+pub fn render_math(text: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut rendered = false;
+    let mut rest = text;
+
+    while let Some(start) = rest.find(['\\', '$']) {
+        let (before, from) = rest.split_at(start);
+        out.push_str(before);
+
+        let Some((tex, display, len)) = formula_at(from) else {
+            // Not a formula, or an escaped `\$`; skip the delimiter
+            let skip = if from.starts_with("\\$") { 2 } else { 1 };
+            out.push_str(&from[..skip]);
+            rest = &from[skip..];
+            continue;
+        };
+
+        match to_mathml(tex, display) {
+            Some(mathml) => {
+                out.push_str(&mathml);
+                rendered = true;
+            }
+            None => out.push_str(&from[..len]),
+        }
+        rest = &from[len..];
+    }
+    out.push_str(rest);
+
+    rendered.then_some(out)
+}
+
+/// Formula starting at the beginning of `s`: its TeX, whether it is display
+/// math and the length of the whole delimited formula
+fn formula_at(s: &str) -> Option<(&str, bool, usize)> {
+    let delimited = |open: &str, close: &str, display: bool| {
+        let body = s.strip_prefix(open)?;
+        let end = body.find(close)?;
+        Some((&body[..end], display, open.len() + end + close.len()))
+    };
+
+    if s.starts_with("\\(") {
+        delimited("\\(", "\\)", false)
+    } else if s.starts_with("\\[") {
+        delimited("\\[", "\\]", true)
+    } else if s.starts_with("$$") {
+        delimited("$$", "$$", true)
+    } else if let Some(body) = s.strip_prefix('$') {
+        if body.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let end = closing_dollar(body)?;
+        Some((&body[..end], false, end + 2))
+    } else {
+        None
+    }
+}
+
+/// Position of the `$` closing inline math in `body`
+fn closing_dollar(body: &str) -> Option<usize> {
+    body.match_indices('$').map(|(i, _)| i).find(|&i| {
+        let escaped = body[..i].ends_with('\\');
+        let after_space = body[..i].ends_with(char::is_whitespace);
+        let before_digit = body[i + 1..].starts_with(|c: char| c.is_ascii_digit());
+        i > 0 && !escaped && !after_space && !before_digit
+    })
+}
+
+fn to_mathml(tex: &str, display: bool) -> Option<String> {
+    let tex = decode_entities(tex);
+    let tex = tex.trim();
+    if tex.is_empty() || tex.len() > MAX_FORMULA_LEN {
+        return None;
+    }
+    let style = if display {
+        DisplayStyle::Block
+    } else {
+        DisplayStyle::Inline
+    };
+    latex_to_mathml(tex, style).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_math() {
+        assert_eq!(render_math("Plain text"), None);
+        assert_eq!(render_math("It costs $5 and $10 now"), None);
+        assert_eq!(render_math("Escaped \\$x\\$ signs"), None);
+        assert_eq!(render_math("A lone $ sign"), None);
+    }
+
+    #[test]
+    fn test_inline_math() {
+        let html = render_math("Let $x^2$ be").unwrap();
+        assert!(html.starts_with("Let <math"));
+        assert!(html.contains("<msup>"));
+        assert!(html.ends_with("</math> be"));
+
+        let html = render_math("Let \\(a + b\\) be").unwrap();
+        assert!(html.contains("<math"));
+        assert!(!html.contains("\\("));
+    }
+
+    #[test]
+    fn test_display_math() {
+        for text in ["$$\\frac{1}{2}$$", "\\[\\frac{1}{2}\\]"] {
+            let html = render_math(text).unwrap();
+            assert!(html.contains("display=\"block\""), "{}", text);
+            assert!(html.contains("<mfrac>"), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_entities_in_formula() {
+        let html = render_math("where $a &lt; b$ holds").unwrap();
+        assert!(html.contains("<math"));
+        assert!(!html.contains("&amp;lt;"));
+    }
+
+    #[test]
+    fn test_invalid_formula_kept() {
+        assert_eq!(render_math("bad $\\frac{1$ here"), None);
+        let html = render_math("bad $\\frac{1$ and good $y$").unwrap();
+        assert!(html.starts_with("bad $\\frac{1$ and good <math"));
+    }
+}
@@ -1,29 +1,71 @@
+pub mod auto_read;
 pub mod background;
+pub mod backup;
+pub mod blocklist;
+pub mod bookmark_import;
+pub mod category_suggest;
+pub mod digest;
+pub mod discussions;
+pub mod epub;
+pub mod feed_cookies;
 pub mod feed_discovery;
+pub mod feed_stats;
 pub mod feed_sync;
+pub mod heartbeat;
+pub mod highlight;
 pub mod http;
 pub mod icon_fetcher;
+pub mod icon_refresh;
 pub mod image_proxy;
+pub mod instance_settings;
+pub mod interest;
+pub mod jobs;
+pub mod markdown;
+pub mod math;
+pub mod notifier;
+pub mod offline_bundle;
 pub mod opml;
+pub mod pdf;
 pub mod readability;
+pub mod reading_stats;
+pub mod refresh_registry;
+pub mod related;
+pub mod revision_diff;
+pub mod rewrite;
 pub mod sanitize;
 pub mod save;
+pub mod saved_pages;
+pub mod scripting;
 pub mod summarize;
 pub mod summary_cache;
 pub mod summary_cleanup;
-pub mod summary_worker;
+pub mod trash;
+pub mod undo;
+pub mod update_check;
+pub mod video_embed;
+pub mod web_push;
 
+pub use auto_read::start_auto_read_worker;
 pub use background::start_background_sync;
+pub use blocklist::{start_blocklist_worker, BlockedHosts, Blocklist};
 pub use feed_discovery::{discover_feed, DiscoveredFeed};
+pub use feed_stats::start_feed_stats_worker;
 pub use feed_sync::{refresh_feed, SyncResult};
-pub use image_proxy::{create_proxy_url, sign_url, verify_signature};
+pub use heartbeat::{Heartbeat, Heartbeats, WorkerStatus};
+pub use icon_refresh::start_icon_refresh_worker;
+pub use image_proxy::{create_proxy_url, key_id, sign_url, verify_signature};
+pub use instance_settings::{InstanceSettings, LiveSettings};
+pub use interest::start_interest_worker;
+pub use jobs::{start_job_worker, JobContext, JobQueue, Task};
+pub use offline_bundle::{build_bundle, OfflineBundle, OfflineEntry};
 pub use opml::{export_opml, parse_opml, OpmlFeed, OpmlOutline};
 pub use readability::{fetch_and_extract, ExtractedContent};
-pub use sanitize::sanitize_html;
+pub use refresh_registry::RefreshRegistry;
+pub use sanitize::{sanitize_html, sanitize_html_with_policy, SanitizePolicy};
 pub use save::{BookmarkData, LinkdingConfig, SaveResult, SaveServicesConfig};
-pub use summarize::KagiConfig;
+pub use summarize::{KagiConfig, OpenAiConfig, SummaryLength, SummaryOptions, SummaryStyle};
 pub use summary_cache::{create_summary_cache, SummaryCache, SummaryCacheEntry, SummaryStatus};
 pub use summary_cleanup::start_cleanup_worker;
-pub use summary_worker::{
-    create_summary_channel, recover_incomplete_jobs, start_summary_worker, SummaryJob,
-};
+pub use trash::start_trash_purge_worker;
+pub use update_check::{start_update_checker, UpdateChecker, UpdateStatus};
+pub use web_push::{PushMessage, VapidKeys};
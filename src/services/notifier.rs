@@ -0,0 +1,247 @@
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use tracing::{debug, warn};
+
+use crate::db::DbPool;
+use crate::error::AppError;
+use crate::models::{category, entry, feed, notification_rule, push_subscription};
+use crate::services::web_push::{send_push, PushMessage, PushOutcome, VapidKeys};
+
+/// Body of the push sent when a feed is quarantined
+const QUARANTINE_NOTICE: &str =
+    "This feed keeps failing and is no longer refreshed. Retry it from the feeds page.";
+
+/// Most pushes sent for one feed in one sync; more matches collapse into a
+/// single summary
+const MAX_PUSHES_PER_FEED: usize = 3;
+
+/// Evaluate the feed owner's notification rules against newly fetched entries
+/// and push a notification to every registered subscription for each match.
+/// Nothing is sent for a feed's first fetch, whose entries are all backlog.
+/// Returns the number of notifications delivered.
+pub async fn notify_new_entries(
+    db: &DbPool,
+    client: &Client,
+    vapid: &VapidKeys,
+    feed_id: i64,
+    entry_ids: Vec<i64>,
+    first_fetch: bool,
+) -> usize {
+    if entry_ids.is_empty() || first_fetch {
+        return 0;
+    }
+
+    let lookup = db
+        .background(move |conn| {
+            let f = feed::find_by_id(conn, feed_id)?.ok_or(AppError::FeedNotFound)?;
+            let cat =
+                category::find_by_id(conn, f.category_id)?.ok_or(AppError::CategoryNotFound)?;
+
+            let rules = notification_rule::list_by_user(conn, cat.user_id)?;
+            if rules.is_empty() {
+                return Ok::<_, AppError>(None);
+            }
+
+            let subscriptions = push_subscription::list_by_user(conn, cat.user_id)?;
+            if subscriptions.is_empty() {
+                return Ok(None);
+            }
+
+            let name = f.title.clone().unwrap_or_else(|| f.url.clone());
+            let mut messages = Vec::new();
+            for id in entry_ids {
+                let Some(e) = entry::find_by_id(conn, id)? else {
+                    continue;
+                };
+                if rules
+                    .iter()
+                    .any(|r| r.matches(f.id, f.category_id, e.title.as_deref()))
+                {
+                    messages.push(PushMessage {
+                        title: name.clone(),
+                        body: e.title.unwrap_or_else(|| "New entry".to_string()),
+                        url: format!("/entries/{}", e.id),
+                    });
+                }
+            }
+
+            Ok(Some((subscriptions, cap_messages(f.id, &name, messages))))
+        })
+        .await;
+
+    let (subscriptions, messages) = match lookup {
+        Ok(Ok(Some(found))) => found,
+        Ok(Ok(None)) => return 0,
+        Ok(Err(e)) => {
+            warn!(
+                "Failed to evaluate notification rules for feed {}: {}",
+                feed_id, e
+            );
+            return 0;
+        }
+        Err(e) => {
+            warn!(
+                "Failed to access DB for notifications of feed {}: {}",
+                feed_id, e
+            );
+            return 0;
+        }
+    };
+
+    deliver(db, client, vapid, &subscriptions, &messages).await
+}
+
+/// Keep up to `MAX_PUSHES_PER_FEED` messages, replacing a longer run with one
+/// pointing at the feed's entries
+fn cap_messages(feed_id: i64, name: &str, messages: Vec<PushMessage>) -> Vec<PushMessage> {
+    if messages.len() <= MAX_PUSHES_PER_FEED {
+        return messages;
+    }
+    vec![PushMessage {
+        title: name.to_string(),
+        body: format!("{} new entries", messages.len()),
+        url: format!("/feeds/{}/entries", feed_id),
+    }]
+}
+
+/// Push every message to every subscription, dropping subscriptions the push
+/// service reports as gone. Returns the number of notifications delivered.
+async fn deliver(
+    db: &DbPool,
+    client: &Client,
+    vapid: &VapidKeys,
+    subscriptions: &[push_subscription::PushSubscription],
+    messages: &[PushMessage],
+) -> usize {
+    let mut delivered = 0;
+    for sub in subscriptions {
+        for message in messages {
+            match send_push(
+                client,
+                vapid,
+                &sub.endpoint,
+                &sub.p256dh,
+                &sub.auth,
+                message,
+            )
+            .await
+            {
+                Ok(PushOutcome::Delivered) => delivered += 1,
+                Ok(PushOutcome::Gone) => {
+                    debug!("Removing expired push subscription {}", sub.id);
+                    let sub_id = sub.id;
+                    let _ = db
+                        .background(move |conn| push_subscription::delete_by_id(conn, sub_id))
+                        .await;
+                    break;
+                }
+                Ok(PushOutcome::Failed(e)) => {
+                    warn!("Push to subscription {} failed: {}", sub.id, e);
+                }
+                Err(e) => {
+                    warn!("Push to subscription {} failed: {}", sub.id, e);
+                    break;
+                }
+            }
+        }
+    }
+
+    delivered
+}
+
+/// Warn about feeds among `feed_ids` that were quarantined at or after
+/// `since`, and push a notice to their owners when push is configured.
+/// Returns the number of quarantined feeds found.
+pub async fn notify_quarantined(
+    db: &DbPool,
+    push: Option<(&Client, &VapidKeys)>,
+    feed_ids: Vec<i64>,
+    since: DateTime<Utc>,
+) -> usize {
+    let lookup = db
+        .background(move |conn| {
+            let mut found = Vec::new();
+            for feed_id in feed_ids {
+                let Some(f) = feed::find_by_id(conn, feed_id)? else {
+                    continue;
+                };
+                if f.quarantined_at.is_none_or(|at| at < since) {
+                    continue;
+                }
+                let cat =
+                    category::find_by_id(conn, f.category_id)?.ok_or(AppError::CategoryNotFound)?;
+                let subscriptions = push_subscription::list_by_user(conn, cat.user_id)?;
+                found.push((f, subscriptions));
+            }
+            Ok::<_, AppError>(found)
+        })
+        .await;
+
+    let found = match lookup {
+        Ok(Ok(found)) => found,
+        Ok(Err(e)) => {
+            warn!("Failed to look up quarantined feeds: {}", e);
+            return 0;
+        }
+        Err(e) => {
+            warn!("Failed to access DB for quarantined feeds: {}", e);
+            return 0;
+        }
+    };
+
+    for (f, subscriptions) in &found {
+        let name = f.title.clone().unwrap_or_else(|| f.url.clone());
+        warn!(
+            "Feed {} ({}) quarantined after {} failures: {}",
+            f.id,
+            f.url,
+            f.failure_count,
+            f.fetch_error.as_deref().unwrap_or_default()
+        );
+        if let Some((client, vapid)) = push {
+            let message = PushMessage {
+                title: name,
+                body: QUARANTINE_NOTICE.to_string(),
+                url: "/feeds".to_string(),
+            };
+            deliver(
+                db,
+                client,
+                vapid,
+                subscriptions,
+                std::slice::from_ref(&message),
+            )
+            .await;
+        }
+    }
+
+    found.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(count: i64) -> Vec<PushMessage> {
+        (1..=count)
+            .map(|id| PushMessage {
+                title: "Feed".to_string(),
+                body: format!("Entry {}", id),
+                url: format!("/entries/{}", id),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cap_messages() {
+        let kept = cap_messages(7, "Feed", messages(3));
+        assert_eq!(kept.len(), 3);
+        assert_eq!(kept[2].url, "/entries/3");
+
+        let collapsed = cap_messages(7, "Feed", messages(4));
+        assert_eq!(collapsed.len(), 1);
+        assert_eq!(collapsed[0].title, "Feed");
+        assert_eq!(collapsed[0].body, "4 new entries");
+        assert_eq!(collapsed[0].url, "/feeds/7/entries");
+    }
+}
@@ -0,0 +1,163 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::blocklist::BlockedHosts;
+use super::sanitize::{sanitize_html_with_policy, SanitizePolicy};
+use crate::models::entry::EntryWithFeed;
+
+/// Default byte budget for an offline bundle (2 MiB)
+pub const DEFAULT_BUNDLE_BYTES: usize = 2 * 1024 * 1024;
+
+/// Upper bound for a requested byte budget (20 MiB)
+pub const MAX_BUNDLE_BYTES: usize = 20 * 1024 * 1024;
+
+/// Compact entry representation cached by the service worker
+#[derive(Debug, Clone, Serialize)]
+pub struct OfflineEntry {
+    pub id: i64,
+    pub feed_id: i64,
+    pub feed_title: Option<String>,
+    pub title: Option<String>,
+    pub link: Option<String>,
+    pub author: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OfflineBundle {
+    pub entries: Vec<OfflineEntry>,
+    pub bytes: usize,
+    pub truncated: bool,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Build an offline bundle from entries, newest first, stopping once the
+/// byte budget would be exceeded. Content is sanitized the same way as the
+/// entry API so cached pages render identically offline.
+pub fn build_bundle(
+    entries: Vec<EntryWithFeed>,
+    secret: &[u8],
+    blocked: &BlockedHosts,
+    max_bytes: usize,
+) -> OfflineBundle {
+    let policy = SanitizePolicy::default();
+    let mut bundled = Vec::new();
+    let mut bytes = 0usize;
+    let mut truncated = false;
+
+    for e in entries {
+        let base_url = e.entry.link.as_deref();
+        let content = e
+            .entry
+            .content
+            .as_deref()
+            .or(e.entry.summary.as_deref())
+            .map(|c| sanitize_html_with_policy(c, secret, base_url, &policy, blocked));
+
+        let offline = OfflineEntry {
+            id: e.entry.id,
+            feed_id: e.entry.feed_id,
+            feed_title: e.feed_title,
+            title: e.entry.title,
+            link: e.entry.link,
+            author: e.entry.author,
+            published_at: e.entry.published_at,
+            content,
+        };
+
+        let size = serde_json::to_vec(&offline).map(|v| v.len()).unwrap_or(0);
+        if bytes + size > max_bytes {
+            truncated = true;
+            break;
+        }
+
+        bytes += size;
+        bundled.push(offline);
+    }
+
+    OfflineBundle {
+        entries: bundled,
+        bytes,
+        truncated,
+        generated_at: Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::entry::Entry;
+
+    const TEST_SECRET: &[u8] = b"test_secret_key_32_bytes_long!!!";
+
+    fn make_entry(id: i64, content: &str) -> EntryWithFeed {
+        EntryWithFeed {
+            entry: Entry {
+                id,
+                feed_id: 1,
+                guid: format!("guid-{}", id),
+                title: Some(format!("Entry {}", id)),
+                link: Some(format!("https://example.com/{}", id)),
+                content: Some(content.to_string()),
+                summary: None,
+                author: None,
+                published_at: None,
+                read_at: None,
+                starred_at: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                comments_url: None,
+                comments_count: None,
+                content_updated_at: None,
+            },
+            feed_title: Some("Feed".to_string()),
+            feed_url: "https://example.com/feed.xml".to_string(),
+            category_id: 1,
+            category_name: "Cat".to_string(),
+            feed_has_icon: false,
+        }
+    }
+
+    #[test]
+    fn test_build_bundle_sanitizes_content() {
+        let entries = vec![make_entry(1, "<p>Hi</p><script>alert(1)</script>")];
+        let bundle = build_bundle(
+            entries,
+            TEST_SECRET,
+            &BlockedHosts::new(),
+            DEFAULT_BUNDLE_BYTES,
+        );
+
+        assert_eq!(bundle.entries.len(), 1);
+        assert!(!bundle.truncated);
+        let content = bundle.entries[0].content.as_deref().unwrap();
+        assert!(content.contains("<p>Hi</p>"));
+        assert!(!content.contains("script"));
+    }
+
+    #[test]
+    fn test_build_bundle_respects_budget() {
+        let body = "x".repeat(1000);
+        let entries = (1..=10).map(|i| make_entry(i, &body)).collect();
+        let bundle = build_bundle(entries, TEST_SECRET, &BlockedHosts::new(), 3500);
+
+        assert!(bundle.truncated);
+        assert!(bundle.bytes <= 3500);
+        assert_eq!(bundle.entries.len(), 3);
+        assert_eq!(bundle.entries[0].id, 1);
+    }
+
+    #[test]
+    fn test_build_bundle_empty() {
+        let bundle = build_bundle(
+            Vec::new(),
+            TEST_SECRET,
+            &BlockedHosts::new(),
+            DEFAULT_BUNDLE_BYTES,
+        );
+        assert!(bundle.entries.is_empty());
+        assert_eq!(bundle.bytes, 0);
+        assert!(!bundle.truncated);
+    }
+}
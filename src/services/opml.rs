@@ -1,10 +1,20 @@
+use quick_xml::encoding::Decoder;
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::reader::Reader;
 use quick_xml::writer::Writer;
-use std::io::Cursor;
+use std::collections::HashSet;
+use std::io::{self, BufRead, Cursor};
+use std::pin::pin;
 
-use crate::error::{AppError, AppResult};
-use crate::models::{category::Category, feed::Feed};
+use axum::body::Bytes;
+use axum::BoxError;
+use futures_util::{Stream, TryStreamExt};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tokio_util::io::StreamReader;
+
+use crate::error::{body_too_large, AppError, AppResult};
+use crate::models::{category, category::Category, feed, feed::Feed};
 
 /// Decode HTML entities in a string (e.g., &amp; -> &)
 fn decode_html_entities(s: &str) -> String {
@@ -16,14 +26,14 @@ fn decode_html_entities(s: &str) -> String {
         .replace("&apos;", "'")
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpmlFeed {
     pub title: Option<String>,
     pub xml_url: String,
     pub html_url: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpmlOutline {
     pub category_name: String,
     pub feeds: Vec<OpmlFeed>,
@@ -84,22 +94,32 @@ pub fn export_opml(categories: &[Category], feeds: &[Feed]) -> String {
 
     // Group feeds by category
     for cat in categories {
-        let cat_feeds: Vec<&Feed> = feeds.iter().filter(|f| f.category_id == cat.id).collect();
+        // Synthetic feeds have nothing to subscribe to
+        let cat_feeds: Vec<&Feed> = feeds
+            .iter()
+            .filter(|f| f.category_id == cat.id && !f.synthetic)
+            .collect();
 
         // Skip empty categories
         if cat_feeds.is_empty() {
             continue;
         }
 
+        // Uncategorized feeds are written as top-level outlines, as they
+        // were most likely imported
+        let top_level = cat.name == category::UNCATEGORIZED;
+
         // Category outline
-        let mut cat_outline = BytesStart::new("outline");
-        let decoded_cat_name = decode_html_entities(&cat.name);
-        cat_outline.push_attribute(("text", decoded_cat_name.as_str()));
-        cat_outline.push_attribute(("title", decoded_cat_name.as_str()));
-        writer.write_event(Event::Start(cat_outline)).unwrap();
-        writer
-            .write_event(Event::Text(BytesText::new("\n")))
-            .unwrap();
+        if !top_level {
+            let mut cat_outline = BytesStart::new("outline");
+            let decoded_cat_name = decode_html_entities(&cat.name);
+            cat_outline.push_attribute(("text", decoded_cat_name.as_str()));
+            cat_outline.push_attribute(("title", decoded_cat_name.as_str()));
+            writer.write_event(Event::Start(cat_outline)).unwrap();
+            writer
+                .write_event(Event::Text(BytesText::new("\n")))
+                .unwrap();
+        }
 
         // Feed outlines
         for feed in cat_feeds {
@@ -125,12 +145,14 @@ pub fn export_opml(categories: &[Category], feeds: &[Feed]) -> String {
                 .unwrap();
         }
 
-        writer
-            .write_event(Event::End(BytesEnd::new("outline")))
-            .unwrap();
-        writer
-            .write_event(Event::Text(BytesText::new("\n")))
-            .unwrap();
+        if !top_level {
+            writer
+                .write_event(Event::End(BytesEnd::new("outline")))
+                .unwrap();
+            writer
+                .write_event(Event::Text(BytesText::new("\n")))
+                .unwrap();
+        }
     }
 
     writer
@@ -147,180 +169,286 @@ pub fn export_opml(categories: &[Category], feeds: &[Feed]) -> String {
     String::from_utf8(result).unwrap_or_default()
 }
 
-pub fn parse_opml(content: &str) -> AppResult<Vec<OpmlOutline>> {
-    let mut reader = Reader::from_str(content);
-    reader.config_mut().trim_text(true);
+/// The attributes of an `<outline>` that matter for import
+struct OutlineAttrs {
+    text: Option<String>,
+    title: Option<String>,
+    xml_url: Option<String>,
+    html_url: Option<String>,
+}
 
-    let mut outlines: Vec<OpmlOutline> = Vec::new();
-    let mut current_category: Option<String> = None;
-    let mut current_feeds: Vec<OpmlFeed> = Vec::new();
-    let mut in_body = false;
-    let mut depth = 0;
+impl OutlineAttrs {
+    fn read(e: &BytesStart, decoder: Decoder) -> Self {
+        let mut attrs = OutlineAttrs {
+            text: None,
+            title: None,
+            xml_url: None,
+            html_url: None,
+        };
+
+        for attr in e.attributes().flatten() {
+            let key = String::from_utf8_lossy(attr.key.as_ref()).to_lowercase();
+            let value = attr
+                .decode_and_unescape_value(decoder)
+                .map(|v| v.to_string())
+                .unwrap_or_else(|_| String::from_utf8_lossy(&attr.value).to_string());
+
+            match key.as_str() {
+                "text" => attrs.text = Some(value),
+                "title" => attrs.title = Some(value),
+                "xmlurl" => attrs.xml_url = Some(value),
+                "htmlurl" => attrs.html_url = Some(value),
+                _ => {}
+            }
+        }
 
-    loop {
-        match reader.read_event() {
-            Ok(Event::Start(e)) => {
+        attrs
+    }
+}
+
+/// Builds outlines from OPML events one at a time, so a document can be
+/// parsed as it is read
+#[derive(Default)]
+struct OutlineCollector {
+    outlines: Vec<OpmlOutline>,
+    current_category: Option<String>,
+    current_feeds: Vec<OpmlFeed>,
+    in_body: bool,
+    depth: usize,
+}
+
+impl OutlineCollector {
+    fn event(&mut self, event: Event, decoder: Decoder) {
+        match event {
+            Event::Start(e) => {
                 let tag_name = e.name();
 
                 if tag_name.as_ref() == b"body" {
-                    in_body = true;
-                    continue;
+                    self.in_body = true;
+                    return;
                 }
 
-                if !in_body || tag_name.as_ref() != b"outline" {
-                    continue;
+                if !self.in_body || tag_name.as_ref() != b"outline" {
+                    return;
                 }
 
-                // Parse attributes
-                let mut text: Option<String> = None;
-                let mut title: Option<String> = None;
-                let mut xml_url: Option<String> = None;
-                let mut html_url: Option<String> = None;
-
-                for attr in e.attributes().flatten() {
-                    let key = String::from_utf8_lossy(attr.key.as_ref()).to_lowercase();
-                    let value = attr
-                        .decode_and_unescape_value(reader.decoder())
-                        .map(|v| v.to_string())
-                        .unwrap_or_else(|_| String::from_utf8_lossy(&attr.value).to_string());
-
-                    match key.as_str() {
-                        "text" => text = Some(value),
-                        "title" => title = Some(value),
-                        "xmlurl" => xml_url = Some(value),
-                        "htmlurl" => html_url = Some(value),
-                        _ => {}
-                    }
-                }
+                let attrs = OutlineAttrs::read(&e, decoder);
 
                 // Determine if this is a feed or category
-                if let Some(url) = xml_url {
+                if let Some(url) = attrs.xml_url {
                     // This is a feed (Start element with xmlUrl - unusual but handle it)
-                    let feed = OpmlFeed {
-                        title: title.or(text),
+                    self.push_feed(OpmlFeed {
+                        title: attrs.title.or(attrs.text),
                         xml_url: url,
-                        html_url,
-                    };
-
-                    if current_category.is_some() {
-                        current_feeds.push(feed);
-                    } else {
-                        outlines.push(OpmlOutline {
-                            category_name: "Uncategorized".to_string(),
-                            feeds: vec![feed],
-                        });
-                    }
+                        html_url: attrs.html_url,
+                    });
                 } else {
                     // This is a category (Start outline without xmlUrl)
                     // Save previous category if exists
-                    if let Some(cat_name) = current_category.take() {
-                        if !current_feeds.is_empty() {
-                            outlines.push(OpmlOutline {
-                                category_name: cat_name,
-                                feeds: std::mem::take(&mut current_feeds),
-                            });
-                        }
-                    }
-
-                    current_category = text.or(title);
-                    depth += 1;
+                    self.close_category();
+                    self.current_category = attrs.text.or(attrs.title);
+                    self.depth += 1;
                 }
             }
-            Ok(Event::Empty(e)) => {
-                let tag_name = e.name();
-
-                if !in_body || tag_name.as_ref() != b"outline" {
-                    continue;
+            Event::Empty(e) => {
+                if !self.in_body || e.name().as_ref() != b"outline" {
+                    return;
                 }
 
-                // Parse attributes
-                let mut text: Option<String> = None;
-                let mut title: Option<String> = None;
-                let mut xml_url: Option<String> = None;
-                let mut html_url: Option<String> = None;
-
-                for attr in e.attributes().flatten() {
-                    let key = String::from_utf8_lossy(attr.key.as_ref()).to_lowercase();
-                    let value = attr
-                        .decode_and_unescape_value(reader.decoder())
-                        .map(|v| v.to_string())
-                        .unwrap_or_else(|_| String::from_utf8_lossy(&attr.value).to_string());
-
-                    match key.as_str() {
-                        "text" => text = Some(value),
-                        "title" => title = Some(value),
-                        "xmlurl" => xml_url = Some(value),
-                        "htmlurl" => html_url = Some(value),
-                        _ => {}
-                    }
-                }
+                let attrs = OutlineAttrs::read(&e, decoder);
 
                 // Empty outline - must be a feed (self-closing tag)
-                if let Some(url) = xml_url {
-                    let feed = OpmlFeed {
-                        title: title.or(text),
+                if let Some(url) = attrs.xml_url {
+                    self.push_feed(OpmlFeed {
+                        title: attrs.title.or(attrs.text),
                         xml_url: url,
-                        html_url,
-                    };
-
-                    if current_category.is_some() {
-                        current_feeds.push(feed);
-                    } else {
-                        outlines.push(OpmlOutline {
-                            category_name: "Uncategorized".to_string(),
-                            feeds: vec![feed],
-                        });
-                    }
+                        html_url: attrs.html_url,
+                    });
                 }
                 // Empty outline without xmlUrl is ignored (empty category)
             }
-            Ok(Event::End(e)) => {
+            Event::End(e) => {
                 if e.name().as_ref() == b"body" {
-                    in_body = false;
-                } else if e.name().as_ref() == b"outline" && depth > 0 {
-                    depth -= 1;
-                    if depth == 0 {
+                    self.in_body = false;
+                } else if e.name().as_ref() == b"outline" && self.depth > 0 {
+                    self.depth -= 1;
+                    if self.depth == 0 {
                         // End of category
-                        if let Some(cat_name) = current_category.take() {
-                            if !current_feeds.is_empty() {
-                                outlines.push(OpmlOutline {
-                                    category_name: cat_name,
-                                    feeds: std::mem::take(&mut current_feeds),
-                                });
-                            }
-                        }
+                        self.close_category();
                     }
                 }
             }
-            Ok(Event::Eof) => break,
-            Err(e) => {
-                return Err(AppError::OpmlParseError(format!(
-                    "Error at position {}: {:?}",
-                    reader.error_position(),
-                    e
-                )));
-            }
             _ => {}
         }
     }
 
-    // Handle any remaining category
-    if let Some(cat_name) = current_category {
-        if !current_feeds.is_empty() {
-            outlines.push(OpmlOutline {
-                category_name: cat_name,
-                feeds: current_feeds,
+    fn push_feed(&mut self, feed: OpmlFeed) {
+        if self.current_category.is_some() {
+            self.current_feeds.push(feed);
+        } else {
+            self.outlines.push(OpmlOutline {
+                category_name: category::UNCATEGORIZED.to_string(),
+                feeds: vec![feed],
             });
         }
     }
 
-    if outlines.is_empty() {
-        return Err(AppError::OpmlParseError(
-            "No feeds found in OPML".to_string(),
-        ));
+    fn close_category(&mut self) {
+        if let Some(cat_name) = self.current_category.take() {
+            if !self.current_feeds.is_empty() {
+                self.outlines.push(OpmlOutline {
+                    category_name: cat_name,
+                    feeds: std::mem::take(&mut self.current_feeds),
+                });
+            }
+        }
+    }
+
+    fn finish(mut self) -> AppResult<Vec<OpmlOutline>> {
+        // Handle any remaining category
+        self.close_category();
+
+        if self.outlines.is_empty() {
+            return Err(AppError::OpmlParseError(
+                "No feeds found in OPML".to_string(),
+            ));
+        }
+
+        Ok(self.outlines)
+    }
+}
+
+fn read_error(e: quick_xml::Error, position: u64) -> AppError {
+    if let quick_xml::Error::Io(io) = &e {
+        if body_too_large(io.as_ref()) {
+            return AppError::PayloadTooLarge;
+        }
+    }
+    AppError::OpmlParseError(format!("Error at position {}: {:?}", position, e))
+}
+
+pub fn parse_opml(content: &str) -> AppResult<Vec<OpmlOutline>> {
+    parse_opml_reader(content.as_bytes())
+}
+
+/// Parse OPML from a reader, such as a file, a piece at a time
+pub fn parse_opml_reader<R: BufRead>(reader: R) -> AppResult<Vec<OpmlOutline>> {
+    let mut reader = Reader::from_reader(reader);
+    reader.config_mut().trim_text(true);
+
+    let mut collector = OutlineCollector::default();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Eof) => break,
+            Ok(event) => collector.event(event, reader.decoder()),
+            Err(e) => return Err(read_error(e, reader.error_position())),
+        }
+        buf.clear();
+    }
+
+    collector.finish()
+}
+
+/// Parse OPML as it streams in, such as an upload, without holding the whole
+/// document in memory
+pub async fn parse_opml_stream<S, E>(stream: S) -> AppResult<Vec<OpmlOutline>>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+    E: Into<BoxError>,
+{
+    let stream = pin!(stream.map_err(io::Error::other));
+    let mut reader = Reader::from_reader(StreamReader::new(stream));
+    reader.config_mut().trim_text(true);
+
+    let mut collector = OutlineCollector::default();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into_async(&mut buf).await {
+            Ok(Event::Eof) => break,
+            Ok(event) => collector.event(event, reader.decoder()),
+            Err(e) => return Err(read_error(e, reader.error_position())),
+        }
+        buf.clear();
+    }
+
+    collector.finish()
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResult {
+    pub categories_created: i32,
+    pub feeds_created: i32,
+    pub feeds_skipped: i32,
+    /// IDs of the feeds created, queued to be fetched right away
+    #[serde(skip)]
+    pub feed_ids: Vec<i64>,
+}
+
+/// Create the categories and feeds described by parsed OPML outlines for a
+/// user. Feeds the user already subscribes to in any category, compared by
+/// canonical URL, are skipped, as are repeats within the OPML.
+pub fn import_outlines(
+    conn: &Connection,
+    user_id: i64,
+    outlines: Vec<OpmlOutline>,
+) -> AppResult<ImportResult> {
+    let mut categories_created = 0;
+    let mut feeds_created = 0;
+    let mut feeds_skipped = 0;
+    let mut feed_ids = Vec::new();
+
+    let mut subscribed: HashSet<String> = feed::list_by_user(conn, user_id)?
+        .iter()
+        .map(|f| feed::canonical_url(&f.url))
+        .collect();
+
+    for outline in outlines {
+        // Find or create category
+        let cat = match category::find_by_name_and_user(conn, &outline.category_name, user_id)? {
+            Some(cat) => cat,
+            None => {
+                let new_cat = category::create_category(conn, user_id, &outline.category_name)?;
+                categories_created += 1;
+                // A trashed category of that name comes back with its feeds
+                subscribed.extend(
+                    feed::list_by_category(conn, new_cat.id)?
+                        .iter()
+                        .map(|f| feed::canonical_url(&f.url)),
+                );
+                new_cat
+            }
+        };
+
+        // Create feeds
+        for opml_feed in outline.feeds {
+            if !subscribed.insert(feed::canonical_url(&opml_feed.xml_url)) {
+                feeds_skipped += 1;
+                continue;
+            }
+
+            // Create the feed
+            let created = feed::create_feed(
+                conn,
+                cat.id,
+                &opml_feed.xml_url,
+                opml_feed.title.as_deref(),
+                None,
+                opml_feed.html_url.as_deref(),
+                None,
+                None,
+            )?;
+            feed_ids.push(created.id);
+            feeds_created += 1;
+        }
     }
 
-    Ok(outlines)
+    Ok(ImportResult {
+        categories_created,
+        feeds_created,
+        feeds_skipped,
+        feed_ids,
+    })
 }
 
 #[cfg(test)]
@@ -335,6 +463,10 @@ mod tests {
             user_id: 1,
             name: "Tech".to_string(),
             created_at: Utc::now(),
+            auto_read_days: None,
+            deleted_at: None,
+            color: None,
+            icon: None,
         }];
 
         let feeds = vec![Feed {
@@ -353,6 +485,19 @@ mod tests {
             http2_disabled: false,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            date_strategy: Default::default(),
+            entry_count: 0,
+            unread_count: 0,
+            last_entry_at: None,
+            custom_headers: Default::default(),
+            engagement_score: 0.0,
+            auto_read_days: None,
+            update_threshold: None,
+            synthetic: false,
+            deleted_at: None,
+            failure_count: 0,
+            next_fetch_at: None,
+            quarantined_at: None,
         }];
 
         let opml = export_opml(&categories, &feeds);
@@ -420,4 +565,128 @@ mod tests {
         let result = parse_opml(opml);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_import_skips_near_duplicates() {
+        use crate::db::init_db;
+        use crate::models::user::{self, Role};
+
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "testuser", "hash", Role::User)
+            .unwrap()
+            .id;
+        let news = category::create_category(&conn, user_id, "News").unwrap();
+        feed::create_feed(
+            &conn,
+            news.id,
+            "https://example.com/feed.xml",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let feed = |url: &str| OpmlFeed {
+            title: None,
+            xml_url: url.to_string(),
+            html_url: None,
+        };
+        let outlines = vec![OpmlOutline {
+            category_name: "Tech".to_string(),
+            feeds: vec![
+                feed("http://www.example.com/feed.xml"),
+                feed("https://rust.example.com/feed.xml"),
+                feed("https://rust.example.com/feed.xml/"),
+            ],
+        }];
+
+        let result = import_outlines(&conn, user_id, outlines).unwrap();
+        assert_eq!(result.feeds_created, 1);
+        assert_eq!(result.feeds_skipped, 2);
+    }
+
+    #[test]
+    fn test_import_into_trashed_category() {
+        use crate::db::init_db;
+        use crate::models::user::{self, Role};
+
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "testuser", "hash", Role::User)
+            .unwrap()
+            .id;
+        let tech = category::create_category(&conn, user_id, "Tech").unwrap();
+        feed::create_feed(
+            &conn,
+            tech.id,
+            "https://example.com/feed.xml",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        category::delete_category(&conn, tech.id, user_id).unwrap();
+
+        let outlines = vec![OpmlOutline {
+            category_name: "Tech".to_string(),
+            feeds: vec![
+                OpmlFeed {
+                    title: None,
+                    xml_url: "https://example.com/feed.xml".to_string(),
+                    html_url: None,
+                },
+                OpmlFeed {
+                    title: None,
+                    xml_url: "https://rust.example.com/feed.xml".to_string(),
+                    html_url: None,
+                },
+            ],
+        }];
+
+        // The trashed category is restored with its feed, which is skipped
+        let result = import_outlines(&conn, user_id, outlines).unwrap();
+        assert_eq!(result.feeds_created, 1);
+        assert_eq!(result.feeds_skipped, 1);
+        let feeds = feed::list_by_category(&conn, tech.id).unwrap();
+        assert_eq!(feeds.len(), 2);
+    }
+
+    #[test]
+    fn test_top_level_feeds_round_trip() {
+        use crate::db::init_db;
+        use crate::models::user::{self, Role};
+
+        let opml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+  <body>
+    <outline type="rss" text="Flat" xmlUrl="https://flat.example.com/feed.xml"/>
+  </body>
+</opml>"#;
+
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "testuser", "hash", Role::User)
+            .unwrap()
+            .id;
+        import_outlines(&conn, user_id, parse_opml(opml).unwrap()).unwrap();
+
+        let categories = category::list_by_user(&conn, user_id).unwrap();
+        assert_eq!(categories.len(), 1);
+        assert_eq!(categories[0].name, category::UNCATEGORIZED);
+
+        let feeds = feed::list_by_user(&conn, user_id).unwrap();
+        let exported = export_opml(&categories, &feeds);
+        assert!(!exported.contains("text=\"Uncategorized\""));
+        let outlines = parse_opml(&exported).unwrap();
+        assert_eq!(outlines[0].category_name, category::UNCATEGORIZED);
+        assert_eq!(
+            outlines[0].feeds[0].xml_url,
+            "https://flat.example.com/feed.xml"
+        );
+    }
 }
@@ -0,0 +1,772 @@
+//! PDF copies of entries, for archiving articles as they were.
+//!
+//! Written directly rather than through a layout engine: pages are A4, text
+//! is set in the PDF base font Courier, which every viewer has, so nothing
+//! is embedded and every character is the same width, which keeps line
+//! breaking simple. Base fonts only cover Western European text (the
+//! WinAnsi encoding); other characters come out as `?`. Images are replaced
+//! by their descriptions.
+
+use chrono::{DateTime, Utc};
+use scraper::{ElementRef, Html, Node};
+
+pub const DEFAULT_FONT_SIZE: f32 = 10.0;
+pub const MIN_FONT_SIZE: f32 = 6.0;
+pub const MAX_FONT_SIZE: f32 = 24.0;
+
+/// A4 in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+
+/// Width of every Courier glyph, in em
+const GLYPH_WIDTH: f32 = 0.6;
+const LINE_HEIGHT: f32 = 1.4;
+
+/// Characters a block is indented by per level of lists and quotes
+const INDENT_CHARS: usize = 2;
+
+pub struct Article {
+    pub title: String,
+    pub feed_title: Option<String>,
+    pub author: Option<String>,
+    pub link: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    /// Sanitized content
+    pub html: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Style {
+    bold: bool,
+    italic: bool,
+}
+
+impl Style {
+    /// Resource name of the base font in this style
+    fn font(&self) -> &'static str {
+        match (self.bold, self.italic) {
+            (false, false) => "F1",
+            (true, false) => "F2",
+            (false, true) => "F3",
+            (true, true) => "F4",
+        }
+    }
+}
+
+const FONTS: [(&str, &str); 4] = [
+    ("F1", "Courier"),
+    ("F2", "Courier-Bold"),
+    ("F3", "Courier-Oblique"),
+    ("F4", "Courier-BoldOblique"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BlockKind {
+    /// Size relative to the body text
+    Heading(f32),
+    Paragraph,
+    /// Whitespace kept, lines broken only where they overflow
+    Preformatted,
+    Rule,
+}
+
+#[derive(Debug)]
+struct Block {
+    kind: BlockKind,
+    indent: usize,
+    /// Put before the first line and hung off the rest, such as a bullet
+    marker: String,
+    text: Vec<(Style, char)>,
+}
+
+/// Splits HTML into blocks of styled text
+#[derive(Default)]
+struct Collector {
+    blocks: Vec<Block>,
+    current: Vec<(Style, char)>,
+    style: Style,
+    indent: usize,
+    marker: String,
+    preformatted: bool,
+    heading: Option<f32>,
+}
+
+impl Collector {
+    fn flush(&mut self) {
+        let text = std::mem::take(&mut self.current);
+        let marker = std::mem::take(&mut self.marker);
+        let empty = text.iter().all(|(_, c)| c.is_whitespace());
+        if empty && marker.is_empty() {
+            return;
+        }
+        let kind = match self.heading {
+            Some(scale) => BlockKind::Heading(scale),
+            None if self.preformatted => BlockKind::Preformatted,
+            None => BlockKind::Paragraph,
+        };
+        self.blocks.push(Block {
+            kind,
+            indent: self.indent,
+            marker,
+            text,
+        });
+    }
+
+    fn push_text(&mut self, text: &str) {
+        let style = self.style;
+        if self.preformatted {
+            self.current.extend(text.chars().map(|c| (style, c)));
+            return;
+        }
+        for c in text.chars() {
+            let c = if c.is_whitespace() { ' ' } else { c };
+            let after_space = self.current.last().is_none_or(|(_, last)| *last == ' ');
+            if c == ' ' && after_space {
+                continue;
+            }
+            self.current.push((style, c));
+        }
+    }
+
+    fn children(&mut self, el: ElementRef<'_>) {
+        for child in el.children() {
+            match child.value() {
+                Node::Text(text) => self.push_text(text),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.element(child);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn element(&mut self, el: ElementRef<'_>) {
+        let name = el.value().name();
+        match name {
+            "br" => {
+                if self.preformatted {
+                    self.current.push((self.style, '\n'));
+                } else {
+                    self.flush();
+                }
+            }
+            "hr" => {
+                self.flush();
+                self.blocks.push(Block {
+                    kind: BlockKind::Rule,
+                    indent: self.indent,
+                    marker: String::new(),
+                    text: Vec::new(),
+                });
+            }
+            "img" => {
+                if let Some(alt) = el.value().attr("alt").filter(|a| !a.trim().is_empty()) {
+                    self.push_text(&format!("[{}]", alt.trim()));
+                }
+            }
+            "strong" | "b" | "em" | "i" => {
+                let saved = self.style;
+                if name == "em" || name == "i" {
+                    self.style.italic = true;
+                } else {
+                    self.style.bold = true;
+                }
+                self.children(el);
+                self.style = saved;
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.flush();
+                let saved = self.style;
+                self.style.bold = true;
+                self.heading = Some(if matches!(name, "h1" | "h2") {
+                    1.2
+                } else {
+                    1.0
+                });
+                self.children(el);
+                self.flush();
+                self.heading = None;
+                self.style = saved;
+            }
+            "pre" => {
+                self.flush();
+                self.preformatted = true;
+                self.children(el);
+                self.flush();
+                self.preformatted = false;
+            }
+            "ul" | "ol" => {
+                self.flush();
+                self.indent += 1;
+                let mut number = 0;
+                for item in el.children().filter_map(ElementRef::wrap) {
+                    if item.value().name() != "li" {
+                        self.element(item);
+                        continue;
+                    }
+                    number += 1;
+                    self.marker = if name == "ol" {
+                        format!("{}.", number)
+                    } else {
+                        "-".to_string()
+                    };
+                    self.children(item);
+                    self.flush();
+                }
+                self.indent -= 1;
+            }
+            "blockquote" => {
+                self.flush();
+                self.indent += 1;
+                self.children(el);
+                self.flush();
+                self.indent -= 1;
+            }
+            "td" | "th" => {
+                let saved = self.style;
+                self.style.bold |= name == "th";
+                self.children(el);
+                self.style = saved;
+                self.push_text(" | ");
+            }
+            "p" | "div" | "figure" | "figcaption" | "li" | "tr" | "table" | "thead" | "tbody"
+            | "section" | "article" => {
+                self.flush();
+                self.children(el);
+                self.flush();
+            }
+            // Players and scripts have nothing to print
+            "iframe" | "video" | "audio" | "script" | "style" | "button" => {}
+            _ => self.children(el),
+        }
+    }
+}
+
+fn blocks(html: &str) -> Vec<Block> {
+    let fragment = Html::parse_fragment(html);
+    let mut collector = Collector::default();
+    collector.children(fragment.root_element());
+    collector.flush();
+    collector.blocks
+}
+
+/// Break `text` into lines of at most `width` characters, between words
+/// where possible
+fn wrap(text: &[(Style, char)], width: usize, preformatted: bool) -> Vec<Vec<(Style, char)>> {
+    let width = width.max(1);
+    let mut lines: Vec<Vec<(Style, char)>> = Vec::new();
+    if preformatted {
+        for source in text.split(|(_, c)| *c == '\n') {
+            let source: Vec<(Style, char)> = source
+                .iter()
+                .flat_map(|&(s, c)| {
+                    let n = if c == '\t' { 4 } else { 1 };
+                    std::iter::repeat_n((s, if c == '\t' { ' ' } else { c }), n)
+                })
+                .collect();
+            if source.is_empty() {
+                lines.push(Vec::new());
+            }
+            lines.extend(source.chunks(width).map(<[_]>::to_vec));
+        }
+        return lines;
+    }
+
+    let mut line: Vec<(Style, char)> = Vec::new();
+    for word in text
+        .split(|(_, c)| *c == ' ')
+        .filter(|word| !word.is_empty())
+    {
+        let needed = if line.is_empty() {
+            word.len()
+        } else {
+            line.len() + 1 + word.len()
+        };
+        if needed > width && !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        // The space keeps the style before it, so a bold run stays one string
+        if let Some(&(style, _)) = line.last() {
+            line.push((style, ' '));
+        }
+        let mut rest = word;
+        while line.len() + rest.len() > width {
+            let take = width - line.len();
+            line.extend_from_slice(&rest[..take]);
+            lines.push(std::mem::take(&mut line));
+            rest = &rest[take..];
+        }
+        line.extend_from_slice(rest);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// A line of text placed on a page
+struct Line {
+    x: f32,
+    y: f32,
+    size: f32,
+    text: Vec<(Style, char)>,
+}
+
+enum Mark {
+    Text(Line),
+    Rule { x: f32, y: f32 },
+}
+
+struct Layout {
+    pages: Vec<Vec<Mark>>,
+    y: f32,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Layout {
+            pages: vec![Vec::new()],
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    /// Move down by `height`, starting a new page if it does not fit
+    fn advance(&mut self, height: f32) -> f32 {
+        if self.y - height < MARGIN {
+            self.pages.push(Vec::new());
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+        self.y -= height;
+        self.y
+    }
+
+    fn skip(&mut self, height: f32) {
+        // Space at the top of a page is dropped
+        if self.y < PAGE_HEIGHT - MARGIN {
+            self.y -= height;
+        }
+    }
+
+    fn mark(&mut self, mark: Mark) {
+        self.pages.last_mut().expect("at least one page").push(mark);
+    }
+
+    fn text(&mut self, x: f32, size: f32, text: Vec<(Style, char)>) {
+        let y = self.advance(size * LINE_HEIGHT);
+        self.mark(Mark::Text(Line { x, y, size, text }));
+    }
+
+    fn block(&mut self, block: &Block, body_size: f32) {
+        let size = match block.kind {
+            BlockKind::Heading(scale) => body_size * scale,
+            _ => body_size,
+        };
+        let char_width = size * GLYPH_WIDTH;
+        let columns = ((PAGE_WIDTH - 2.0 * MARGIN) / char_width) as usize;
+        let indent = block.indent.saturating_sub(1) * INDENT_CHARS
+            + if block.marker.is_empty() {
+                block.indent.min(1) * INDENT_CHARS
+            } else {
+                block.marker.chars().count() + 1
+            };
+        let x = MARGIN + indent as f32 * char_width;
+
+        if block.kind == BlockKind::Rule {
+            self.skip(size * 0.5);
+            let y = self.advance(size * 0.5);
+            self.mark(Mark::Rule { x, y });
+            self.skip(size * 0.5);
+            return;
+        }
+        if matches!(block.kind, BlockKind::Heading(_)) {
+            self.skip(size * 0.6);
+        }
+
+        let lines = wrap(
+            &block.text,
+            columns.saturating_sub(indent),
+            block.kind == BlockKind::Preformatted,
+        );
+        for (i, line) in lines.into_iter().enumerate() {
+            if i == 0 && !block.marker.is_empty() {
+                let marker_x = x - (block.marker.chars().count() + 1) as f32 * char_width;
+                let mut text: Vec<(Style, char)> = block
+                    .marker
+                    .chars()
+                    .map(|c| (Style::default(), c))
+                    .collect();
+                text.push((Style::default(), ' '));
+                text.extend(line);
+                self.text(marker_x, size, text);
+            } else {
+                self.text(x, size, line);
+            }
+        }
+        self.skip(size * 0.6);
+    }
+}
+
+/// A character in the WinAnsi encoding of the base fonts
+fn win_ansi(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8,
+        '\u{A0}'..='\u{FF}' => c as u32 as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        'ƒ' => 0x83,
+        '„' => 0x84,
+        '…' => 0x85,
+        '†' => 0x86,
+        '‡' => 0x87,
+        'ˆ' => 0x88,
+        '‰' => 0x89,
+        'Š' => 0x8A,
+        '‹' => 0x8B,
+        'Œ' => 0x8C,
+        'Ž' => 0x8E,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '˜' => 0x98,
+        '™' => 0x99,
+        'š' => 0x9A,
+        '›' => 0x9B,
+        'œ' => 0x9C,
+        'ž' => 0x9E,
+        'Ÿ' => 0x9F,
+        _ => b'?',
+    }
+}
+
+/// A PDF literal string of `text` in WinAnsi
+fn literal(text: impl Iterator<Item = char>) -> String {
+    let mut out = String::from("(");
+    for byte in text.map(win_ansi) {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                out.push('\\');
+                out.push(byte as char);
+            }
+            0x20..=0x7E => out.push(byte as char),
+            _ => out.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    out.push(')');
+    out
+}
+
+/// A PDF text string, in UTF-16 so any title survives in the metadata
+fn text_string(text: &str) -> String {
+    let mut out = String::from("<FEFF");
+    for unit in text.encode_utf16() {
+        out.push_str(&format!("{:04X}", unit));
+    }
+    out.push('>');
+    out
+}
+
+fn content_stream(marks: &[Mark], footer: &str) -> String {
+    let mut out = String::new();
+    for mark in marks {
+        match mark {
+            Mark::Text(line) => {
+                out.push_str(&format!("BT {:.2} {:.2} Td ", line.x, line.y));
+                let mut start = 0;
+                while start < line.text.len() {
+                    let style = line.text[start].0;
+                    let end = line.text[start..]
+                        .iter()
+                        .position(|(s, _)| *s != style)
+                        .map_or(line.text.len(), |n| start + n);
+                    out.push_str(&format!(
+                        "/{} {:.2} Tf {} Tj ",
+                        style.font(),
+                        line.size,
+                        literal(line.text[start..end].iter().map(|(_, c)| *c))
+                    ));
+                    start = end;
+                }
+                out.push_str("ET\n");
+            }
+            Mark::Rule { x, y } => {
+                out.push_str(&format!(
+                    "0.5 w {:.2} {:.2} m {:.2} {:.2} l S\n",
+                    x,
+                    y,
+                    PAGE_WIDTH - MARGIN,
+                    y
+                ));
+            }
+        }
+    }
+    let footer_size = 8.0;
+    let footer_x = (PAGE_WIDTH - footer.chars().count() as f32 * footer_size * GLYPH_WIDTH) / 2.0;
+    out.push_str(&format!(
+        "BT {:.2} {:.2} Td /F1 {:.2} Tf {} Tj ET\n",
+        footer_x,
+        MARGIN / 2.0,
+        footer_size,
+        literal(footer.chars())
+    ));
+    out
+}
+
+/// Assemble the PDF file: catalog, page tree, fonts, then each page and its
+/// content, followed by the cross-reference table
+fn write_pdf(title: &str, pages: &[String]) -> Vec<u8> {
+    let mut objects: Vec<String> = Vec::new();
+    let font_base = 3;
+    let page_base = font_base + FONTS.len() + 1;
+    let info_id = font_base + FONTS.len();
+
+    objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", page_base + 2 * i))
+        .collect();
+    objects.push(format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        pages.len()
+    ));
+    for (_, base_font) in FONTS {
+        objects.push(format!(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+            base_font
+        ));
+    }
+    objects.push(format!(
+        "<< /Title {} /Producer (rdrs) /CreationDate (D:{}Z) >>",
+        text_string(title),
+        Utc::now().format("%Y%m%d%H%M%S")
+    ));
+    let fonts: Vec<String> = FONTS
+        .iter()
+        .enumerate()
+        .map(|(i, (name, _))| format!("/{} {} 0 R", name, font_base + i))
+        .collect();
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << {} >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            fonts.join(" "),
+            page_base + 2 * i + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+    let xref = pdf.len();
+    pdf.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            info_id,
+            xref
+        )
+        .as_bytes(),
+    );
+    pdf
+}
+
+/// Typeset `article` as a PDF with body text of `font_size` points
+pub fn render(article: &Article, font_size: f32) -> Vec<u8> {
+    let size = font_size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+    let plain = |text: &str| -> Vec<(Style, char)> {
+        text.chars().map(|c| (Style::default(), c)).collect()
+    };
+
+    let mut header = vec![Block {
+        kind: BlockKind::Heading(1.4),
+        indent: 0,
+        marker: String::new(),
+        text: article
+            .title
+            .chars()
+            .map(|c| {
+                (
+                    Style {
+                        bold: true,
+                        italic: false,
+                    },
+                    c,
+                )
+            })
+            .collect(),
+    }];
+    let byline: Vec<String> = [
+        article.feed_title.clone(),
+        article.author.clone(),
+        article
+            .published_at
+            .map(|d| d.format("%Y-%m-%d").to_string()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !byline.is_empty() {
+        header.push(Block {
+            kind: BlockKind::Paragraph,
+            indent: 0,
+            marker: String::new(),
+            text: byline
+                .join(" · ")
+                .chars()
+                .map(|c| {
+                    (
+                        Style {
+                            bold: false,
+                            italic: true,
+                        },
+                        c,
+                    )
+                })
+                .collect(),
+        });
+    }
+    if let Some(link) = &article.link {
+        header.push(Block {
+            kind: BlockKind::Paragraph,
+            indent: 0,
+            marker: String::new(),
+            text: plain(link),
+        });
+    }
+    header.push(Block {
+        kind: BlockKind::Rule,
+        indent: 0,
+        marker: String::new(),
+        text: Vec::new(),
+    });
+
+    let mut layout = Layout::new();
+    for block in header.iter().chain(blocks(&article.html).iter()) {
+        layout.block(block, size);
+    }
+
+    let count = layout.pages.len();
+    let pages: Vec<String> = layout
+        .pages
+        .iter()
+        .enumerate()
+        .map(|(i, marks)| content_stream(marks, &format!("{} / {}", i + 1, count)))
+        .collect();
+    write_pdf(&article.title, &pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(text: &str) -> Vec<(Style, char)> {
+        text.chars().map(|c| (Style::default(), c)).collect()
+    }
+
+    fn lines(wrapped: Vec<Vec<(Style, char)>>) -> Vec<String> {
+        wrapped
+            .into_iter()
+            .map(|l| l.into_iter().map(|(_, c)| c).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            lines(wrap(&plain("the quick brown fox"), 10, false)),
+            vec!["the quick", "brown fox"]
+        );
+        assert_eq!(
+            lines(wrap(&plain("abcdefghijkl xy"), 5, false)),
+            vec!["abcde", "fghij", "kl xy"]
+        );
+        assert_eq!(
+            lines(wrap(&plain("a  b\n\n\tc"), 10, true)),
+            vec!["a  b", "", "    c"]
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        let blocks = blocks(
+            "<h2>Title</h2><p>One <strong>two</strong>\n three</p><ul><li>A</li><li>B</li></ul><pre>x\n  y</pre>",
+        );
+        let text: Vec<String> = blocks
+            .iter()
+            .map(|b| b.text.iter().map(|(_, c)| c).collect())
+            .collect();
+        assert_eq!(text, vec!["Title", "One two three", "A", "B", "x\n  y"]);
+        assert_eq!(blocks[0].kind, BlockKind::Heading(1.2));
+        assert!(blocks[1].text[4].0.bold);
+        assert_eq!(blocks[3].marker, "-");
+        assert_eq!(blocks[3].indent, 1);
+        assert_eq!(blocks[4].kind, BlockKind::Preformatted);
+    }
+
+    #[test]
+    fn test_literal() {
+        assert_eq!(literal("a(b)\\".chars()), "(a\\(b\\)\\\\)");
+        assert_eq!(literal("café – 中".chars()), "(caf\\351 \\226 ?)");
+    }
+
+    #[test]
+    fn test_render() {
+        let article = Article {
+            title: "Ruling (2026)".to_string(),
+            feed_title: Some("Court News".to_string()),
+            author: None,
+            link: Some("https://example.com/ruling".to_string()),
+            published_at: None,
+            html: "<p>Long text. </p>".repeat(200),
+        };
+        let pdf = render(&article, DEFAULT_FONT_SIZE);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("(Ruling \\(2026\\)) Tj"));
+        assert!(text.contains("/BaseFont /Courier-Bold"));
+        // Each paragraph takes a line and a gap, so 200 need several pages
+        let pages = text.matches("/Type /Page ").count();
+        assert!(pages > 1);
+        assert!(text.contains(&format!("/Count {}", pages)));
+        assert!(text.contains(&format!("({} / {}) Tj", pages, pages)));
+
+        // The cross-reference table points at the objects. The header has a
+        // binary comment, so offsets are checked against the raw bytes.
+        let xref: usize = text
+            .rsplit("startxref\n")
+            .next()
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(pdf[xref..].starts_with(b"xref"));
+        let table = String::from_utf8_lossy(&pdf[xref..]);
+        let first = table.lines().nth(3).unwrap();
+        let offset: usize = first[..10].parse().unwrap();
+        assert!(pdf[offset..].starts_with(b"1 0 obj"));
+    }
+}
@@ -1,10 +1,13 @@
 use std::net::IpAddr;
 
 use readability::extractor;
+use reqwest::header::HeaderMap;
 use url::Url;
 
 use crate::error::{AppError, AppResult};
-use crate::services::http::{send_with_retry, RetryConfig, DEFAULT_TIMEOUT};
+use crate::services::http::{
+    client_builder, send_with_retry, Outbound, RetryConfig, DEFAULT_TIMEOUT,
+};
 
 pub struct ExtractedContent {
     pub title: Option<String>,
@@ -12,15 +15,21 @@ pub struct ExtractedContent {
 }
 
 /// Fetches HTML from URL and extracts readable content using readability crate.
-pub async fn fetch_and_extract(url: &str, user_agent: &str) -> AppResult<ExtractedContent> {
+/// `headers` carries the extra request headers of the entry's feed.
+pub async fn fetch_and_extract(
+    url: &str,
+    user_agent: &str,
+    headers: HeaderMap,
+) -> AppResult<ExtractedContent> {
     // Parse and validate URL (SSRF protection)
     let parsed_url = Url::parse(url).map_err(|_| AppError::InvalidUrl)?;
     validate_url(&parsed_url)?;
 
     // Fetch HTML using existing reqwest (rustls-tls)
-    let client = reqwest::Client::builder()
+    let client = client_builder(Outbound::Content)?
         .timeout(DEFAULT_TIMEOUT)
         .user_agent(user_agent)
+        .default_headers(headers)
         .build()
         .map_err(|e| AppError::FetchError(e.to_string()))?;
 
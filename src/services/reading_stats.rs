@@ -0,0 +1,295 @@
+//! Reading statistics behind `GET /api/user/stats` and the stats page.
+//!
+//! Built from entry views in the user's time zone: what a day is, and so
+//! which days make a streak, depends on where they read.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::error::AppResult;
+use crate::models::entry_view::{self, EntryView};
+use crate::models::user_settings;
+use crate::timezone::{self, Tz};
+
+/// Feeds listed under `top_feeds`
+const TOP_FEEDS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PeriodStats {
+    /// First day of the period: the day itself, or the Monday of a week
+    pub date: NaiveDate,
+    pub entries_read: i64,
+    pub seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FeedReadingStats {
+    /// None once the feed has been deleted
+    pub feed_id: Option<i64>,
+    pub title: Option<String>,
+    pub entries_read: i64,
+    pub seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadingStats {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Different entries opened in the period
+    pub entries_read: i64,
+    pub seconds: i64,
+    /// Every day of the period, including days without reading
+    pub days: Vec<PeriodStats>,
+    pub weeks: Vec<PeriodStats>,
+    pub top_feeds: Vec<FeedReadingStats>,
+    /// Days in a row with reading, up to today, or up to yesterday if
+    /// nothing was read yet today
+    pub current_streak: i64,
+    pub longest_streak: i64,
+}
+
+/// Same entry opened twice counts once; views of deleted entries each count
+fn entry_key(view: &EntryView) -> i64 {
+    view.entry_id.unwrap_or(-view.id)
+}
+
+fn monday_of(date: NaiveDate) -> NaiveDate {
+    date - Days::new(date.weekday().num_days_from_monday() as u64)
+}
+
+#[derive(Default)]
+struct Tally {
+    entries: HashSet<i64>,
+    seconds: i64,
+}
+
+impl Tally {
+    fn add(&mut self, view: &EntryView) {
+        self.entries.insert(entry_key(view));
+        self.seconds += view.seconds;
+    }
+
+    fn period(&self, date: NaiveDate) -> PeriodStats {
+        PeriodStats {
+            date,
+            entries_read: self.entries.len() as i64,
+            seconds: self.seconds,
+        }
+    }
+}
+
+/// Current and longest run of consecutive days in `days`, which are sorted
+fn streaks(days: &[NaiveDate], today: NaiveDate) -> (i64, i64) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut last: Option<NaiveDate> = None;
+    for &day in days {
+        run = match last {
+            Some(prev) if prev.succ_opt() == Some(day) => run + 1,
+            Some(prev) if prev == day => run,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        last = Some(day);
+    }
+    let current = match last {
+        Some(day) if day == today || day.succ_opt() == Some(today) => run,
+        _ => 0,
+    };
+    (current, longest)
+}
+
+/// Statistics of `views` between `from` and `to`, inclusive. `opened` is
+/// every time the user opened an entry, for streaks.
+pub fn summarize(
+    views: &[EntryView],
+    opened: &[DateTime<Utc>],
+    tz: Option<Tz>,
+    from: NaiveDate,
+    to: NaiveDate,
+    today: NaiveDate,
+) -> ReadingStats {
+    let local_date = |dt: DateTime<Utc>| timezone::local(dt, tz).date_naive();
+
+    let mut total = Tally::default();
+    let mut days: BTreeMap<NaiveDate, Tally> = BTreeMap::new();
+    let mut weeks: BTreeMap<NaiveDate, Tally> = BTreeMap::new();
+    let mut feeds: HashMap<Option<i64>, (Option<String>, Tally)> = HashMap::new();
+    for view in views {
+        let date = local_date(view.opened_at);
+        if date < from || date > to {
+            continue;
+        }
+        total.add(view);
+        days.entry(date).or_default().add(view);
+        weeks.entry(monday_of(date)).or_default().add(view);
+        let (title, tally) = feeds
+            .entry(view.feed_id)
+            .or_insert_with(|| (view.feed_title.clone(), Tally::default()));
+        if title.is_none() {
+            title.clone_from(&view.feed_title);
+        }
+        tally.add(view);
+    }
+
+    let empty = Tally::default();
+    let day_stats = from
+        .iter_days()
+        .take_while(|d| *d <= to)
+        .map(|d| days.get(&d).unwrap_or(&empty).period(d))
+        .collect();
+    let week_stats = monday_of(from)
+        .iter_weeks()
+        .take_while(|w| *w <= to)
+        .map(|w| weeks.get(&w).unwrap_or(&empty).period(w))
+        .collect();
+
+    let mut top_feeds: Vec<FeedReadingStats> = feeds
+        .into_iter()
+        .map(|(feed_id, (title, tally))| FeedReadingStats {
+            feed_id,
+            title,
+            entries_read: tally.entries.len() as i64,
+            seconds: tally.seconds,
+        })
+        .collect();
+    top_feeds.sort_by(|a, b| {
+        b.entries_read
+            .cmp(&a.entries_read)
+            .then(b.seconds.cmp(&a.seconds))
+            .then(a.feed_id.cmp(&b.feed_id))
+    });
+    top_feeds.truncate(TOP_FEEDS);
+
+    let mut reading_days: Vec<NaiveDate> = opened.iter().map(|&t| local_date(t)).collect();
+    reading_days.sort_unstable();
+    reading_days.dedup();
+    let (current_streak, longest_streak) = streaks(&reading_days, today);
+
+    ReadingStats {
+        from,
+        to,
+        entries_read: total.entries.len() as i64,
+        seconds: total.seconds,
+        days: day_stats,
+        weeks: week_stats,
+        top_feeds,
+        current_streak,
+        longest_streak,
+    }
+}
+
+/// The user's reading statistics between `from` and `to` in their time
+/// zone, inclusive
+pub fn user_stats(
+    conn: &Connection,
+    user_id: i64,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> AppResult<ReadingStats> {
+    let tz = user_settings::get_timezone(conn, user_id)?;
+    // Local days start up to a day before UTC ones; extra views are dropped
+    let since = (from - Days::new(1))
+        .and_time(chrono::NaiveTime::MIN)
+        .and_utc();
+    let views = entry_view::list_since(conn, user_id, since)?;
+    let opened = entry_view::opened_times(conn, user_id)?;
+    let today = timezone::local(Utc::now(), tz).date_naive();
+    Ok(summarize(&views, &opened, tz, from, to, today))
+}
+
+/// Today in the user's time zone
+pub fn user_today(conn: &Connection, user_id: i64) -> AppResult<NaiveDate> {
+    let tz = user_settings::get_timezone(conn, user_id)?;
+    Ok(timezone::local(Utc::now(), tz).date_naive())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn view(id: i64, entry_id: i64, feed_id: i64, opened_at: &str, seconds: i64) -> EntryView {
+        EntryView {
+            id,
+            entry_id: Some(entry_id),
+            feed_id: Some(feed_id),
+            feed_title: Some(format!("Feed {}", feed_id)),
+            opened_at: chrono::NaiveDateTime::parse_from_str(opened_at, "%Y-%m-%d %H:%M:%S")
+                .unwrap()
+                .and_utc(),
+            seconds,
+        }
+    }
+
+    #[test]
+    fn test_streaks() {
+        let days = [
+            date("2026-01-01"),
+            date("2026-01-02"),
+            date("2026-01-03"),
+            date("2026-01-10"),
+            date("2026-01-11"),
+        ];
+        assert_eq!(streaks(&days, date("2026-01-11")), (2, 3));
+        assert_eq!(streaks(&days, date("2026-01-12")), (2, 3));
+        assert_eq!(streaks(&days, date("2026-01-13")), (0, 3));
+        assert_eq!(streaks(&[], date("2026-01-13")), (0, 0));
+    }
+
+    #[test]
+    fn test_summarize() {
+        let views = vec![
+            view(1, 10, 1, "2026-01-05 08:00:00", 60),
+            // The same entry again the same day counts once
+            view(2, 10, 1, "2026-01-05 09:00:00", 30),
+            view(3, 11, 2, "2026-01-06 08:00:00", 120),
+            view(4, 12, 1, "2026-01-12 08:00:00", 10),
+        ];
+        let opened: Vec<_> = views.iter().map(|v| v.opened_at).collect();
+        let stats = summarize(
+            &views,
+            &opened,
+            None,
+            date("2026-01-05"),
+            date("2026-01-12"),
+            date("2026-01-12"),
+        );
+
+        assert_eq!(stats.entries_read, 3);
+        assert_eq!(stats.seconds, 220);
+        assert_eq!(stats.days.len(), 8);
+        assert_eq!(stats.days[0].entries_read, 1);
+        assert_eq!(stats.days[0].seconds, 90);
+        assert_eq!(stats.days[2].entries_read, 0);
+        assert_eq!(stats.weeks.len(), 2);
+        assert_eq!(stats.weeks[0].date, date("2026-01-05"));
+        assert_eq!(stats.weeks[0].entries_read, 2);
+        assert_eq!(stats.top_feeds[0].feed_id, Some(1));
+        assert_eq!(stats.top_feeds[0].entries_read, 2);
+        assert_eq!(stats.current_streak, 1);
+        assert_eq!(stats.longest_streak, 2);
+    }
+
+    #[test]
+    fn test_summarize_uses_time_zone() {
+        let views = vec![view(1, 10, 1, "2026-01-05 20:00:00", 60)];
+        let tz = timezone::parse("Asia/Taipei");
+        let stats = summarize(
+            &views,
+            &[],
+            tz,
+            date("2026-01-05"),
+            date("2026-01-06"),
+            date("2026-01-06"),
+        );
+        assert_eq!(stats.days[0].entries_read, 0);
+        assert_eq!(stats.days[1].entries_read, 1);
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::watch;
+
+use crate::error::{AppError, AppResult};
+use crate::services::feed_sync::SyncResult;
+
+/// Outcome of a refresh as seen by requests that waited on it
+type SharedResult = Option<Result<SyncResult, SharedError>>;
+
+/// A refresh error that can be handed to every waiting request
+#[derive(Debug, Clone)]
+enum SharedError {
+    FeedNotFound,
+    Fetch(String),
+    Parse(String),
+    Validation(String),
+    Other(String),
+}
+
+impl From<&AppError> for SharedError {
+    fn from(e: &AppError) -> Self {
+        match e {
+            AppError::FeedNotFound => SharedError::FeedNotFound,
+            AppError::FetchError(msg) => SharedError::Fetch(msg.clone()),
+            AppError::FeedParseError(msg) => SharedError::Parse(msg.clone()),
+            AppError::Validation(msg) => SharedError::Validation(msg.clone()),
+            other => SharedError::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<SharedError> for AppError {
+    fn from(e: SharedError) -> Self {
+        match e {
+            SharedError::FeedNotFound => AppError::FeedNotFound,
+            SharedError::Fetch(msg) => AppError::FetchError(msg),
+            SharedError::Parse(msg) => AppError::FeedParseError(msg),
+            SharedError::Validation(msg) => AppError::Validation(msg),
+            SharedError::Other(msg) => AppError::Internal(msg),
+        }
+    }
+}
+
+/// Feeds being refreshed right now, shared through `AppState` and with the
+/// background sync so the same feed is never fetched twice at once
+#[derive(Debug, Clone, Default)]
+pub struct RefreshRegistry {
+    in_flight: Arc<Mutex<HashMap<i64, watch::Receiver<SharedResult>>>>,
+}
+
+enum Slot {
+    /// This caller runs the refresh and publishes its result
+    Leader(watch::Sender<SharedResult>),
+    /// Another caller is already refreshing the feed
+    Follower(watch::Receiver<SharedResult>),
+}
+
+/// Removes the feed from the registry when the leader finishes or is
+/// dropped mid-refresh, so a cancelled request does not block the feed
+struct LeaderGuard<'a> {
+    registry: &'a RefreshRegistry,
+    feed_id: i64,
+}
+
+impl Drop for LeaderGuard<'_> {
+    fn drop(&mut self) {
+        self.registry
+            .in_flight
+            .lock()
+            .unwrap()
+            .remove(&self.feed_id);
+    }
+}
+
+impl RefreshRegistry {
+    fn claim(&self, feed_id: i64) -> Slot {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(rx) = in_flight.get(&feed_id) {
+            return Slot::Follower(rx.clone());
+        }
+        let (tx, rx) = watch::channel(None);
+        in_flight.insert(feed_id, rx);
+        Slot::Leader(tx)
+    }
+
+    /// Run `refresh` for the feed unless a refresh of it is already in
+    /// flight, in which case wait for that one and return its result
+    pub async fn run<F, Fut>(&self, feed_id: i64, refresh: F) -> AppResult<SyncResult>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = AppResult<SyncResult>>,
+    {
+        let mut refresh = Some(refresh);
+        loop {
+            match self.claim(feed_id) {
+                Slot::Leader(tx) => {
+                    let _guard = LeaderGuard {
+                        registry: self,
+                        feed_id,
+                    };
+                    let refresh = refresh.take().expect("refresh already run");
+                    let result = refresh().await;
+                    let shared = match &result {
+                        Ok(sync) => Ok(sync.clone()),
+                        Err(e) => Err(SharedError::from(e)),
+                    };
+                    let _ = tx.send(Some(shared));
+                    return result;
+                }
+                Slot::Follower(mut rx) => {
+                    let outcome = rx
+                        .wait_for(Option::is_some)
+                        .await
+                        .ok()
+                        .and_then(|r| r.clone());
+                    match outcome {
+                        Some(result) => return result.map_err(AppError::from),
+                        // The leader was cancelled before finishing; try again
+                        None => continue,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether a refresh of the feed is in flight
+    pub fn is_refreshing(&self, feed_id: i64) -> bool {
+        self.in_flight.lock().unwrap().contains_key(&feed_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn sync_result(new_entries: i64) -> SyncResult {
+        SyncResult {
+            new_entries,
+            updated_entries: 0,
+            new_entry_ids: Vec::new(),
+            first_fetch: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_refreshes_share_one_run() {
+        let registry = RefreshRegistry::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let refresh = |registry: RefreshRegistry, runs: Arc<AtomicUsize>| async move {
+            registry
+                .run(1, || async {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok(sync_result(3))
+                })
+                .await
+        };
+
+        let (a, b) = tokio::join!(
+            refresh(registry.clone(), runs.clone()),
+            refresh(registry.clone(), runs.clone())
+        );
+        assert_eq!(a.unwrap().new_entries, 3);
+        assert_eq!(b.unwrap().new_entries, 3);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(!registry.is_refreshing(1));
+    }
+
+    #[tokio::test]
+    async fn test_waiters_get_the_error() {
+        let registry = RefreshRegistry::default();
+
+        let leader = registry.run(1, || async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Err(AppError::FetchError("HTTP 503".to_string()))
+        });
+        let follower = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            registry.run(1, || async { Ok(sync_result(1)) }).await
+        };
+
+        let (a, b) = tokio::join!(leader, follower);
+        assert!(matches!(a, Err(AppError::FetchError(_))));
+        assert!(matches!(b, Err(AppError::FetchError(msg)) if msg == "HTTP 503"));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_leader_releases_feed() {
+        let registry = RefreshRegistry::default();
+
+        let leader = registry.run(1, || async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(sync_result(0))
+        });
+        let _ = tokio::time::timeout(Duration::from_millis(10), leader).await;
+        assert!(!registry.is_refreshing(1));
+
+        let result = registry.run(1, || async { Ok(sync_result(2)) }).await;
+        assert_eq!(result.unwrap().new_entries, 2);
+    }
+}
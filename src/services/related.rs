@@ -0,0 +1,215 @@
+//! Finds entries on the same topic as another one in the user's archive.
+//!
+//! The most frequent words of the entry, title words counting extra, are
+//! looked up in the search index and candidates ranked with FTS5's `bm25`,
+//! so words that are rare across the archive weigh the most. Results are
+//! cached in `related_cache` since ranking scans much of the index.
+
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppResult;
+use crate::services::digest::html_to_text;
+
+/// How long related entries are reused before being ranked again (6 hours)
+pub const CACHE_TTL_SECS: i64 = 6 * 3600;
+
+/// Number of related entries returned
+pub const RELATED_LIMIT: i64 = 10;
+
+/// Words of the entry looked up in the index
+const MAX_TERMS: usize = 12;
+
+/// How much more a word in the title counts than one in the body
+const TITLE_WEIGHT: usize = 3;
+
+/// Words shorter than this are skipped, along with `STOP_WORDS`
+const MIN_WORD_CHARS: usize = 4;
+
+/// Words longer than this are usually URLs or runs of text without spaces
+const MAX_WORD_CHARS: usize = 24;
+
+/// Common English words that say nothing about the topic
+const STOP_WORDS: &[&str] = &[
+    "about", "after", "also", "because", "been", "before", "being", "could", "does", "each",
+    "from", "have", "here", "href", "http", "https", "into", "just", "like", "made", "make",
+    "many", "more", "most", "much", "only", "other", "over", "should", "some", "such", "than",
+    "that", "their", "them", "then", "there", "these", "they", "this", "those", "through", "very",
+    "were", "what", "when", "where", "which", "while", "will", "with", "would", "your",
+];
+
+/// The words that best describe `title` and `body`, most frequent first
+pub fn key_terms(title: &str, body: &str) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (text, weight) in [(title, TITLE_WEIGHT), (body, 1)] {
+        for word in text.split(|c: char| !c.is_alphanumeric()) {
+            let len = word.chars().count();
+            if !(MIN_WORD_CHARS..=MAX_WORD_CHARS).contains(&len)
+                || word.chars().all(|c| c.is_numeric())
+            {
+                continue;
+            }
+            let word = word.to_lowercase();
+            if STOP_WORDS.contains(&word.as_str()) {
+                continue;
+            }
+            *counts.entry(word).or_default() += weight;
+        }
+    }
+
+    let mut terms: Vec<(String, usize)> = counts.into_iter().collect();
+    // Ties broken alphabetically so the same entry always gives the same terms
+    terms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    terms
+        .into_iter()
+        .take(MAX_TERMS)
+        .map(|(word, _)| word)
+        .collect()
+}
+
+/// IDs of up to `limit` of the user's entries most similar to `entry_id`,
+/// best match first
+pub fn find_related(
+    conn: &Connection,
+    user_id: i64,
+    entry_id: i64,
+    limit: i64,
+) -> AppResult<Vec<i64>> {
+    let texts: Option<(Option<String>, Option<String>, Option<String>)> = conn
+        .query_row(
+            "SELECT title, content, full_content FROM entry_fts WHERE rowid = ?1",
+            params![entry_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    let Some((title, content, full_content)) = texts else {
+        return Ok(Vec::new());
+    };
+
+    let body = format!(
+        "{} {}",
+        html_to_text(content.as_deref().unwrap_or_default()),
+        html_to_text(full_content.as_deref().unwrap_or_default())
+    );
+    let terms = key_terms(title.as_deref().unwrap_or_default(), &body);
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let query = terms
+        .iter()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT e.id
+        FROM entry_fts
+        INNER JOIN entry e ON e.id = entry_fts.rowid
+        INNER JOIN feed f ON e.feed_id = f.id
+        INNER JOIN category c ON f.category_id = c.id
+        WHERE entry_fts MATCH ?1 AND c.user_id = ?2 AND f.deleted_at IS NULL AND e.id != ?3
+        ORDER BY bm25(entry_fts, 2.0, 1.0, 1.0, 1.0)
+        LIMIT ?4
+        "#,
+    )?;
+    let ids = stmt
+        .query_map(params![query, user_id, entry_id, limit], |row| row.get(0))?
+        .filter_map(Result::ok)
+        .collect();
+
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{category, entry, feed, user};
+
+    #[test]
+    fn test_key_terms() {
+        let terms = key_terms(
+            "Rust ownership",
+            "<p>Ownership in Rust: what the borrow checker does with ownership and 2024</p>",
+        );
+        assert_eq!(terms[0], "ownership");
+        assert_eq!(terms[1], "rust");
+        assert!(terms.contains(&"borrow".to_string()));
+        assert!(!terms.contains(&"what".to_string()));
+        assert!(!terms.contains(&"2024".to_string()));
+        assert!(key_terms("", "a b c").is_empty());
+    }
+
+    fn add_feed(conn: &Connection, user_id: i64) -> i64 {
+        let cat = category::create_category(conn, user_id, "Tech").unwrap();
+        feed::create_feed(
+            conn,
+            cat.id,
+            "https://a.test/feed",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .id
+    }
+
+    fn add_entry(conn: &Connection, feed_id: i64, guid: &str, title: &str, content: &str) -> i64 {
+        entry::upsert_entry(
+            conn,
+            feed_id,
+            guid,
+            Some(title),
+            None,
+            Some(content),
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .0
+        .id
+    }
+
+    #[test]
+    fn test_find_related() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "testuser", "hash", user::Role::User)
+            .unwrap()
+            .id;
+        let other_id = user::create_user(&conn, "other", "hash", user::Role::User)
+            .unwrap()
+            .id;
+        let feed_id = add_feed(&conn, user_id);
+        let other_feed_id = add_feed(&conn, other_id);
+
+        let source = add_entry(
+            &conn,
+            feed_id,
+            "a",
+            "Borrow checker tips",
+            "Lifetimes and the borrow checker",
+        );
+        let close = add_entry(
+            &conn,
+            feed_id,
+            "b",
+            "Fighting the borrow checker",
+            "More lifetimes",
+        );
+        let loose = add_entry(&conn, feed_id, "c", "Weekly links", "A note on lifetimes");
+        add_entry(&conn, feed_id, "d", "Gardening", "Tomatoes and basil");
+        add_entry(&conn, other_feed_id, "e", "Borrow checker", "Lifetimes");
+
+        let related = find_related(&conn, user_id, source, RELATED_LIMIT).unwrap();
+        assert_eq!(related, vec![close, loose]);
+        assert!(find_related(&conn, user_id, 999, RELATED_LIMIT)
+            .unwrap()
+            .is_empty());
+    }
+}
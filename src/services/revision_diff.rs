@@ -0,0 +1,158 @@
+//! Word-level diffs between revisions of an entry. Content is compared as
+//! text, the same way feed refreshes decide whether an entry changed.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+
+use super::digest::html_to_text;
+
+/// Unchanged text kept on each side of a change; the rest is elided
+pub const CONTEXT_CHARS: usize = 80;
+
+/// Diffing gives up refining after this and reports coarser changes
+const DIFF_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffChunk {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// Word diff from `old` to `new` HTML content. Returns no chunks when the
+/// text is the same.
+pub fn diff_content(old: Option<&str>, new: Option<&str>) -> Vec<DiffChunk> {
+    let old = html_to_text(old.unwrap_or_default());
+    let new = html_to_text(new.unwrap_or_default());
+    if old == new {
+        return Vec::new();
+    }
+
+    let diff = TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_words(&old, &new);
+
+    let mut chunks: Vec<DiffChunk> = Vec::new();
+    for change in diff.iter_all_changes() {
+        let op = match change.tag() {
+            ChangeTag::Equal => DiffOp::Equal,
+            ChangeTag::Insert => DiffOp::Insert,
+            ChangeTag::Delete => DiffOp::Delete,
+        };
+        match chunks.last_mut() {
+            Some(last) if last.op == op => last.text.push_str(change.value()),
+            _ => chunks.push(DiffChunk {
+                op,
+                text: change.value().to_string(),
+            }),
+        }
+    }
+
+    let last = chunks.len() - 1;
+    for (i, chunk) in chunks.iter_mut().enumerate() {
+        if chunk.op == DiffOp::Equal {
+            let head = if i == 0 { 0 } else { CONTEXT_CHARS };
+            let tail = if i == last { 0 } else { CONTEXT_CHARS };
+            chunk.text = elide(&chunk.text, head, tail);
+        }
+    }
+    chunks
+}
+
+/// Share of the words in `old` and `new` text that differ, in percent
+pub fn changed_percent(old: &str, new: &str) -> i64 {
+    if old == new {
+        return 0;
+    }
+    let ratio = TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_words(old, new)
+        .ratio();
+    // Any change counts as at least 1%, however long the text
+    (((1.0 - ratio) * 100.0).round() as i64).max(1)
+}
+
+/// Keep `head` chars at the start of `text` and `tail` at the end
+fn elide(text: &str, head: usize, tail: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= head + tail + 1 {
+        return text.to_string();
+    }
+    let start: String = chars[..head].iter().collect();
+    let end: String = chars[chars.len() - tail..].iter().collect();
+    format!("{}…{}", start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(op: DiffOp, text: &str) -> DiffChunk {
+        DiffChunk {
+            op,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_diff_content() {
+        let chunks = diff_content(
+            Some("<p>The minister <b>denied</b> the report.</p>"),
+            Some("<p>The minister <b>confirmed</b> the report.</p>"),
+        );
+        assert_eq!(
+            chunks,
+            vec![
+                chunk(DiffOp::Equal, "The minister "),
+                chunk(DiffOp::Delete, "denied"),
+                chunk(DiffOp::Insert, "confirmed"),
+                chunk(DiffOp::Equal, " the report."),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_changed_percent() {
+        assert_eq!(changed_percent("same words", "same words"), 0);
+        assert_eq!(changed_percent("", "all new"), 100);
+
+        let old = "word ".repeat(99) + "old";
+        let new = "word ".repeat(99) + "new";
+        let small = changed_percent(&old, &new);
+        assert!((1..=5).contains(&small), "{}", small);
+
+        let rewritten = changed_percent("the service is operational", "major outage in progress");
+        assert!(rewritten > 50, "{}", rewritten);
+    }
+
+    #[test]
+    fn test_markup_only_change_is_no_diff() {
+        let chunks = diff_content(
+            Some("<p>Same text</p>"),
+            Some("<div class=\"x\">Same text</div>"),
+        );
+        assert!(chunks.is_empty());
+    }
+
+    #[test]
+    fn test_long_unchanged_text_is_elided() {
+        let intro = "word ".repeat(100);
+        let chunks = diff_content(
+            Some(&format!("{}old", intro)),
+            Some(&format!("{}new", intro)),
+        );
+        assert_eq!(chunks[0].op, DiffOp::Equal);
+        assert!(chunks[0].text.starts_with('…'));
+        assert_eq!(chunks[0].text.chars().count(), CONTEXT_CHARS + 1);
+        assert_eq!(chunks.last().unwrap().text, "new");
+    }
+}
@@ -0,0 +1,144 @@
+//! Applies a feed's rewrite rules to entries as they are stored.
+
+use std::borrow::Cow;
+
+use regex::{Regex, RegexBuilder};
+use tracing::warn;
+
+use crate::models::feed_rewrite::{FeedRewrite, RewriteField};
+
+/// Longest pattern or replacement accepted
+pub const MAX_RULE_LEN: usize = 500;
+
+/// Compiled size limit, keeping pathological patterns from eating memory
+const REGEX_SIZE_LIMIT: usize = 1024 * 1024;
+
+/// Number of recent entries a rule is previewed against
+pub const PREVIEW_ENTRIES: i64 = 10;
+
+/// Compile a rule's pattern, with an error fit for showing the user
+pub fn compile(pattern: &str) -> Result<Regex, String> {
+    if pattern.is_empty() {
+        return Err("Pattern cannot be empty".to_string());
+    }
+    if pattern.len() > MAX_RULE_LEN {
+        return Err(format!(
+            "Pattern must be {} characters or less",
+            MAX_RULE_LEN
+        ));
+    }
+    RegexBuilder::new(pattern)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| format!("Invalid pattern: {}", e))
+}
+
+/// A feed's rules, compiled once per sync
+pub struct Rewriter {
+    rules: Vec<(RewriteField, Regex, String)>,
+}
+
+impl Rewriter {
+    /// Rules saved before a stricter check was added may no longer compile;
+    /// those are logged and skipped
+    pub fn new(rewrites: &[FeedRewrite]) -> Self {
+        let rules = rewrites
+            .iter()
+            .filter_map(|r| match compile(&r.pattern) {
+                Ok(regex) => Some((r.field, regex, r.replacement.clone())),
+                Err(e) => {
+                    warn!("Skipping rewrite rule {}: {}", r.id, e);
+                    None
+                }
+            })
+            .collect();
+        Rewriter { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// `text` with every rule for `field` applied in order. Rewritten titles
+    /// are trimmed, since rules usually strip a prefix or suffix.
+    pub fn apply(&self, field: RewriteField, text: Option<String>) -> Option<String> {
+        let mut text = text?;
+        let mut changed = false;
+        for (rule_field, regex, replacement) in &self.rules {
+            if *rule_field != field {
+                continue;
+            }
+            let rewritten = match regex.replace_all(&text, replacement.as_str()) {
+                Cow::Owned(rewritten) => rewritten,
+                Cow::Borrowed(_) => continue,
+            };
+            text = rewritten;
+            changed = true;
+        }
+        if changed && field == RewriteField::Title {
+            text = text.trim().to_string();
+        }
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn rule(id: i64, field: RewriteField, pattern: &str, replacement: &str) -> FeedRewrite {
+        FeedRewrite {
+            id,
+            feed_id: 1,
+            field,
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_apply_in_order() {
+        let rewriter = Rewriter::new(&[
+            rule(1, RewriteField::Title, r"^\[Sponsored\]\s*", ""),
+            rule(2, RewriteField::Title, r"\|[^|]*\|", ""),
+            rule(3, RewriteField::Content, r"utm_\w+=\w+&?", ""),
+            rule(4, RewriteField::Title, r"(\d+) ways", "$1 tips"),
+        ]);
+
+        assert_eq!(
+            rewriter.apply(
+                RewriteField::Title,
+                Some("[Sponsored] |SiteName| 5 ways to cook".to_string())
+            ),
+            Some("5 tips to cook".to_string())
+        );
+        assert_eq!(
+            rewriter.apply(
+                RewriteField::Content,
+                Some("<a href=\"/a?utm_source=rss\">[Sponsored]</a>".to_string())
+            ),
+            Some("<a href=\"/a?\">[Sponsored]</a>".to_string())
+        );
+        assert_eq!(rewriter.apply(RewriteField::Title, None), None);
+
+        // Untouched titles keep their whitespace
+        assert_eq!(
+            rewriter.apply(RewriteField::Title, Some(" Plain ".to_string())),
+            Some(" Plain ".to_string())
+        );
+    }
+
+    #[test]
+    fn test_compile() {
+        assert!(compile(r"^\[Ad\]").is_ok());
+        assert!(compile("").is_err());
+        assert!(compile("(unclosed").is_err());
+        assert!(compile(&"a".repeat(MAX_RULE_LEN + 1)).is_err());
+
+        // Invalid stored rules are skipped rather than failing the sync
+        let rewriter = Rewriter::new(&[rule(1, RewriteField::Title, "(", "")]);
+        assert!(rewriter.is_empty());
+    }
+}
@@ -1,9 +1,20 @@
 use ammonia::Builder;
-use scraper::{Html, Selector};
+use lol_html::html_content::{ContentType, Element, EndTag, TextChunk};
+use lol_html::{doc_text, element, rewrite_str, text, HandlerResult, RewriteStrSettings};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::collections::HashSet;
+use std::rc::Rc;
+use syntect::parsing::SyntaxReference;
 use url::Url;
 
+use super::blocklist::BlockedHosts;
+use super::highlight;
 use super::image_proxy::create_proxy_url;
+use super::math::render_math;
+use super::video_embed::Video;
+use crate::error::{AppError, AppResult};
 
 /// Known tracking domains (subdomains that indicate tracking)
 const TRACKING_DOMAINS: &[&str] = &["pixel.", "beacon.", "track.", "analytics."];
@@ -67,93 +78,425 @@ const TRACKING_PARAMS: &[&str] = &[
     "ref",
 ];
 
+/// MathML elements allowed in content. `annotation-xml` is left out, as it
+/// can switch back to parsing HTML inside math.
+const MATHML_TAGS: &[&str] = &[
+    "math",
+    "semantics",
+    "annotation",
+    "mrow",
+    "mi",
+    "mn",
+    "mo",
+    "ms",
+    "mtext",
+    "mspace",
+    "msub",
+    "msup",
+    "msubsup",
+    "munder",
+    "mover",
+    "munderover",
+    "mmultiscripts",
+    "mprescripts",
+    "none",
+    "mfrac",
+    "msqrt",
+    "mroot",
+    "mstyle",
+    "mpadded",
+    "mphantom",
+    "menclose",
+    "mtable",
+    "mtr",
+    "mtd",
+];
+
+/// Presentation attributes allowed on MathML elements
+const MATHML_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("math", &["display"]),
+    ("mi", &["mathvariant"]),
+    (
+        "mo",
+        &["stretchy", "fence", "separator", "lspace", "rspace"],
+    ),
+    ("mtext", &["mathvariant"]),
+    ("mspace", &["width"]),
+    ("mover", &["accent"]),
+    ("munder", &["accentunder"]),
+    ("mfrac", &["linethickness"]),
+    ("mstyle", &["displaystyle", "scriptlevel", "mathvariant"]),
+    ("mpadded", &["width", "lspace", "voffset"]),
+    ("menclose", &["notation"]),
+    ("mtable", &["columnalign", "rowspacing", "columnspacing"]),
+    ("mtd", &["columnspan", "rowspan"]),
+    ("annotation", &["encoding"]),
+];
+
 /// Tracking query parameter prefixes
 const TRACKING_PARAM_PREFIXES: &[&str] = &["utm_", "mtm_"];
 
-/// Remove tracking pixels (1x1 images, zero-dimension images, and images from tracking domains)
-fn remove_tracking_pixels(html: &str) -> String {
-    // Build a list of src URLs to remove based on tracking criteria
-    let document = Html::parse_fragment(html);
-    let img_selector = Selector::parse("img").unwrap();
+/// Maximum number of hosts in a policy's iframe allowlist
+pub const MAX_IFRAME_HOSTS: usize = 50;
+
+/// Embed hosts offered on the settings page. YouTube and Vimeo are handled
+/// by `SanitizePolicy::video_embeds` instead.
+pub const SUGGESTED_IFRAME_HOSTS: &[&str] = &["open.spotify.com", "w.soundcloud.com", "codepen.io"];
+
+/// CSS properties kept when a policy keeps inline styles. None of them can
+/// load a URL or lift an element out of the content.
+const ALLOWED_STYLE_PROPERTIES: &[&str] = &[
+    "color",
+    "background-color",
+    "font-family",
+    "font-size",
+    "font-style",
+    "font-weight",
+    "text-align",
+    "text-decoration",
+    "text-indent",
+    "line-height",
+    "vertical-align",
+    "white-space",
+    "margin",
+    "margin-top",
+    "margin-right",
+    "margin-bottom",
+    "margin-left",
+    "padding",
+    "padding-top",
+    "padding-right",
+    "padding-bottom",
+    "padding-left",
+    "border",
+    "border-collapse",
+    "border-radius",
+    "width",
+    "max-width",
+    "height",
+    "float",
+    "clear",
+    "display",
+    "list-style-type",
+];
 
-    let mut urls_to_remove: Vec<String> = Vec::new();
+/// Iframe permissions an allowed embed gets; enough to play video
+const IFRAME_SANDBOX: &str = "allow-scripts allow-same-origin allow-presentation allow-popups";
+
+/// What becomes of YouTube and Vimeo embeds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoEmbeds {
+    /// Drop them like any other iframe
+    Remove,
+    /// Show the thumbnail, loading the privacy-enhanced player on click
+    #[default]
+    Facade,
+    /// Embed the privacy-enhanced player right away
+    Embed,
+}
 
-    for element in document.select(&img_selector) {
-        let attrs = element.value();
+impl VideoEmbeds {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VideoEmbeds::Remove => "remove",
+            VideoEmbeds::Facade => "facade",
+            VideoEmbeds::Embed => "embed",
+        }
+    }
+}
 
-        // Check for 1x1 pixel or zero dimensions
-        let width = attrs.attr("width");
-        let height = attrs.attr("height");
+/// What a user lets through the sanitizer on top of the fixed rules
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SanitizePolicy {
+    /// Hosts whose iframes are kept, such as `www.youtube.com` for YouTube
+    /// embeds. Hosts must match exactly, subdomains are not included.
+    pub iframe_hosts: Vec<String>,
+    /// Keep `style` attributes, limited to harmless properties
+    pub keep_inline_styles: bool,
+    /// Load images through the image proxy. Without it, images come straight
+    /// from their origin, which then sees the reader's IP address.
+    pub proxy_images: bool,
+    pub video_embeds: VideoEmbeds,
+}
 
-        let is_tracking_size = match (width, height) {
-            (Some(w), Some(h)) => w == "1" && h == "1",
-            (Some(w), None) => w == "0",
-            (None, Some(h)) => h == "0",
-            _ => false,
-        };
+static DEFAULT_POLICY: SanitizePolicy = SanitizePolicy {
+    iframe_hosts: Vec::new(),
+    keep_inline_styles: false,
+    proxy_images: true,
+    video_embeds: VideoEmbeds::Facade,
+};
 
-        // Check for tracking domain or path in src
-        let is_tracking_url = if let Some(src) = attrs.attr("src") {
-            let src_lower = src.to_lowercase();
-            TRACKING_DOMAINS.iter().any(|d| src_lower.contains(d))
-                || TRACKING_PATHS.iter().any(|p| src_lower.contains(p))
-        } else {
-            false
-        };
+impl Default for SanitizePolicy {
+    fn default() -> Self {
+        DEFAULT_POLICY.clone()
+    }
+}
 
-        if is_tracking_size || is_tracking_url {
-            if let Some(src) = attrs.attr("src") {
-                urls_to_remove.push(src.to_string());
+impl SanitizePolicy {
+    /// Validate the policy, lowercasing its hosts and dropping blank and
+    /// repeated ones
+    pub fn normalized(mut self) -> AppResult<Self> {
+        let mut hosts: Vec<String> = Vec::new();
+        for host in &self.iframe_hosts {
+            let host = host.trim().to_ascii_lowercase();
+            if host.is_empty() || hosts.contains(&host) {
+                continue;
             }
+            let is_host = Url::parse(&format!("https://{}/", host))
+                .is_ok_and(|url| url.host_str() == Some(host.as_str()));
+            if !is_host {
+                return Err(AppError::Validation(format!(
+                    "Invalid embed host: {}",
+                    host
+                )));
+            }
+            hosts.push(host);
+        }
+        if hosts.len() > MAX_IFRAME_HOSTS {
+            return Err(AppError::Validation(format!(
+                "At most {} embed hosts are allowed",
+                MAX_IFRAME_HOSTS
+            )));
         }
+        self.iframe_hosts = hosts;
+        Ok(self)
     }
 
-    // Remove img tags with matching src URLs
-    let mut result = html.to_string();
-    for url in &urls_to_remove {
-        // Find img tags containing this URL and remove them
-        result = remove_img_tag_with_src(&result, url);
+    /// Whether an iframe may load `src`: https, from an allowlisted host
+    fn allows_iframe(&self, src: &str) -> bool {
+        Url::parse(src).is_ok_and(|url| {
+            url.scheme() == "https"
+                && url
+                    .host_str()
+                    .is_some_and(|host| self.iframe_hosts.iter().any(|h| h == host))
+        })
     }
+}
 
-    result
+/// Blocklist for callers without one, which blocks nothing
+static NO_BLOCKED_HOSTS: BlockedHosts = BlockedHosts::new();
+
+/// Code block being collected for highlighting
+struct CodeBlock {
+    syntax: &'static SyntaxReference,
+    text: String,
 }
 
-/// Remove an img tag that contains the specified src URL
-fn remove_img_tag_with_src(html: &str, src_url: &str) -> String {
-    let mut result = String::new();
-    let mut i = 0;
+/// Shared state for one run of the transformers over a document
+struct Context<'a> {
+    secret: &'a [u8],
+    base: Option<Url>,
+    policy: &'a SanitizePolicy,
+    /// Hosts whose media is removed and whose links are unwrapped
+    blocked: &'a BlockedHosts,
+    /// Set from the start to the end tag of a code block to highlight.
+    /// Shared with the end tag handler, which must be `'static`.
+    code: Rc<RefCell<Option<CodeBlock>>>,
+    /// Text of the current text node, when it arrives in several chunks
+    text: RefCell<String>,
+    /// Number of open `pre` and `code` elements, whose text is never math
+    raw_depth: Rc<Cell<usize>>,
+}
+
+impl<'a> Context<'a> {
+    fn new(secret: &'a [u8], base_url: Option<&str>) -> Self {
+        Self {
+            secret,
+            base: base_url.and_then(|u| Url::parse(u).ok()),
+            policy: &DEFAULT_POLICY,
+            blocked: &NO_BLOCKED_HOSTS,
+            code: Rc::default(),
+            text: RefCell::default(),
+            raw_depth: Rc::default(),
+        }
+    }
+}
+
+type ElementFn = fn(&mut Element<'_, '_>, &Context<'_>) -> HandlerResult;
+type TextFn = fn(&mut TextChunk<'_>, &Context<'_>) -> HandlerResult;
+
+/// A rewrite of the elements matching a selector, of their text, or of all
+/// text in the document. Transformers are combined into one streaming pass,
+/// so the document is parsed and serialized once however many of them run.
+enum Transformer {
+    Element(&'static str, ElementFn),
+    Text(&'static str, TextFn),
+    DocumentText(TextFn),
+}
+
+/// Drop tracking pixels. Runs first, so later transformers never see them.
+const REMOVE_TRACKING_PIXELS: Transformer = Transformer::Element("img", remove_tracking_pixel);
+
+/// Drop media from blocked hosts before any of it is proxied
+const REMOVE_BLOCKED_IMAGES: Transformer = Transformer::Element("img[src]", remove_blocked_media);
+
+const REMOVE_BLOCKED_SOURCES: Transformer =
+    Transformer::Element("source[src]", remove_blocked_media);
+
+const REMOVE_BLOCKED_IFRAMES: Transformer =
+    Transformer::Element("iframe[src]", remove_blocked_media);
+
+const UNLINK_BLOCKED: Transformer = Transformer::Element("a[href]", unlink_blocked);
 
-    while i < html.len() {
-        // Check if we're at the start of an img tag
-        if html[i..].starts_with("<img") {
-            // Find the end of this tag
-            if let Some(end_pos) = html[i..].find('>') {
-                let tag_end = i + end_pos + 1;
-                let tag = &html[i..tag_end];
+const STRIP_TRACKING_PARAMS: Transformer =
+    Transformer::Element("a[href]", strip_link_tracking_params);
 
-                // Check if this img tag contains our target src URL
-                let src_patterns = [format!("src=\"{}\"", src_url), format!("src='{}'", src_url)];
+const FILTER_IFRAMES: Transformer = Transformer::Element("iframe", filter_iframe);
 
-                let should_remove = src_patterns.iter().any(|p| tag.contains(p));
+const PROXY_IMAGES: Transformer = Transformer::Element("img[src]", proxy_image_src);
 
-                if should_remove {
-                    // Skip this tag entirely
-                    i = tag_end;
-                    continue;
-                }
+const PROXY_SOURCES: Transformer = Transformer::Element("source[src]", proxy_image_src);
+
+const PRIVACY_ATTRS: Transformer = Transformer::Element("a[href]", add_link_privacy_attrs);
+
+/// Start collecting a code block with a known language
+const HIGHLIGHT_CODE: Transformer = Transformer::Element("pre > code[class]", start_code_block);
+
+/// Unwrap markup inside a collected code block, such as the spans of a
+/// highlighter the feed already ran
+const FLATTEN_CODE: Transformer = Transformer::Element("pre > code[class] *", flatten_code_markup);
+
+const COLLECT_CODE: Transformer = Transformer::Text("pre > code[class]", collect_code_text);
+
+const COLLECT_NESTED_CODE: Transformer =
+    Transformer::Text("pre > code[class] *", collect_code_text);
+
+const SKIP_MATH_IN_PRE: Transformer = Transformer::Element("pre", enter_raw_text);
+
+const SKIP_MATH_IN_CODE: Transformer = Transformer::Element("code", enter_raw_text);
+
+const RENDER_MATH: Transformer = Transformer::DocumentText(render_text_math);
+
+/// Transformers `sanitize_html` runs over the content before ammonia, so the
+/// markup they add is sanitized like the rest
+const PRE_SANITIZE: &[Transformer] = &[SKIP_MATH_IN_PRE, SKIP_MATH_IN_CODE, RENDER_MATH];
+
+/// Transformers `sanitize_html` runs over ammonia's output, in order
+const PIPELINE: &[Transformer] = &[
+    REMOVE_TRACKING_PIXELS,
+    REMOVE_BLOCKED_IMAGES,
+    REMOVE_BLOCKED_SOURCES,
+    REMOVE_BLOCKED_IFRAMES,
+    FILTER_IFRAMES,
+    UNLINK_BLOCKED,
+    STRIP_TRACKING_PARAMS,
+    PROXY_IMAGES,
+    PROXY_SOURCES,
+    PRIVACY_ATTRS,
+    HIGHLIGHT_CODE,
+    FLATTEN_CODE,
+    COLLECT_CODE,
+    COLLECT_NESTED_CODE,
+];
+
+/// Parse `html` once, run `transformers` over the matching elements and
+/// serialize the result. Handlers of the same element or text run in the
+/// order the transformers are given, and skip what an earlier one removed.
+fn transform(html: &str, ctx: &Context<'_>, transformers: &[Transformer]) -> String {
+    let mut element_content_handlers = Vec::new();
+    let mut document_content_handlers = Vec::new();
+    for t in transformers {
+        match *t {
+            Transformer::Element(selector, apply) => {
+                element_content_handlers.push(element!(selector, move |el| {
+                    if el.removed() {
+                        return Ok(());
+                    }
+                    apply(el, ctx)
+                }));
+            }
+            Transformer::Text(selector, apply) => {
+                element_content_handlers.push(text!(selector, move |chunk| {
+                    if chunk.removed() {
+                        return Ok(());
+                    }
+                    apply(chunk, ctx)
+                }));
+            }
+            Transformer::DocumentText(apply) => {
+                document_content_handlers.push(doc_text!(move |chunk| {
+                    if chunk.removed() {
+                        return Ok(());
+                    }
+                    apply(chunk, ctx)
+                }));
             }
         }
+    }
 
-        // Add current character to result
-        if let Some(c) = html[i..].chars().next() {
-            result.push(c);
-            i += c.len_utf8();
-        } else {
-            break;
-        }
+    let settings = RewriteStrSettings {
+        element_content_handlers,
+        document_content_handlers,
+        ..RewriteStrSettings::new()
+    };
+    rewrite_str(html, settings).unwrap_or_else(|e| {
+        tracing::warn!("Failed to transform HTML: {}", e);
+        html.to_string()
+    })
+}
+
+/// Attribute values and text are read back as written, entities and all.
+/// Ammonia's serializer only ever emits these.
+pub(crate) fn decode_entities(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn encode_attr(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;")
+}
+
+/// Remove tracking pixels (1x1 images, zero-dimension images, and images from tracking domains)
+fn remove_tracking_pixel(el: &mut Element<'_, '_>, _ctx: &Context<'_>) -> HandlerResult {
+    let width = el.get_attribute("width");
+    let height = el.get_attribute("height");
+
+    let is_tracking_size = match (width.as_deref(), height.as_deref()) {
+        (Some(w), Some(h)) => w == "1" && h == "1",
+        (Some(w), None) => w == "0",
+        (None, Some(h)) => h == "0",
+        _ => false,
+    };
+
+    // Check for tracking domain or path in src
+    let is_tracking_url = el.get_attribute("src").is_some_and(|src| {
+        let src_lower = src.to_lowercase();
+        TRACKING_DOMAINS.iter().any(|d| src_lower.contains(d))
+            || TRACKING_PATHS.iter().any(|p| src_lower.contains(p))
+    });
+
+    if is_tracking_size || is_tracking_url {
+        el.remove();
+    }
+    Ok(())
+}
+
+/// Remove an image, source or iframe whose `src` is on a blocked host
+fn remove_blocked_media(el: &mut Element<'_, '_>, ctx: &Context<'_>) -> HandlerResult {
+    if el
+        .get_attribute("src")
+        .is_some_and(|src| ctx.blocked.blocks(&decode_entities(&src)))
+    {
+        el.remove();
     }
+    Ok(())
+}
 
-    result
+/// Keep the text of a link to a blocked host, but not the link
+fn unlink_blocked(el: &mut Element<'_, '_>, ctx: &Context<'_>) -> HandlerResult {
+    if el
+        .get_attribute("href")
+        .is_some_and(|href| ctx.blocked.blocks(&decode_entities(&href)))
+    {
+        el.remove_and_keep_content();
+    }
+    Ok(())
 }
 
 /// Check if a parameter name is a tracking parameter
@@ -165,77 +508,323 @@ fn is_tracking_param(name: &str) -> bool {
             .any(|p| name_lower.starts_with(p))
 }
 
-/// Strip tracking parameters from all URLs in anchor tags
-fn strip_tracking_params(html: &str) -> String {
-    let document = Html::parse_fragment(html);
-    let a_selector = Selector::parse("a[href]").unwrap();
+/// `href` without its tracking parameters, or `None` when there are none to
+/// remove
+pub(crate) fn without_tracking_params(href: &str) -> Option<String> {
+    // Only process http/https URLs
+    if !href.starts_with("http://") && !href.starts_with("https://") {
+        return None;
+    }
 
-    let mut result = html.to_string();
+    let mut url = Url::parse(href).ok()?;
+    let original_query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
 
-    for element in document.select(&a_selector) {
-        if let Some(href) = element.value().attr("href") {
-            // Only process http/https URLs
-            if !href.starts_with("http://") && !href.starts_with("https://") {
-                continue;
-            }
+    // Filter out tracking parameters
+    let filtered_query: Vec<&(String, String)> = original_query
+        .iter()
+        .filter(|(k, _)| !is_tracking_param(k))
+        .collect();
 
-            if let Ok(mut url) = Url::parse(href) {
-                let original_query: Vec<(String, String)> = url
-                    .query_pairs()
-                    .map(|(k, v)| (k.to_string(), v.to_string()))
-                    .collect();
-
-                // Filter out tracking parameters
-                let filtered_query: Vec<(String, String)> = original_query
-                    .iter()
-                    .filter(|(k, _)| !is_tracking_param(k))
-                    .cloned()
-                    .collect();
-
-                // Only modify if we actually removed something
-                if filtered_query.len() < original_query.len() {
-                    // Clear and rebuild query string
-                    url.set_query(None);
-                    if !filtered_query.is_empty() {
-                        let query_string: String = filtered_query
-                            .iter()
-                            .map(|(k, v)| {
-                                format!(
-                                    "{}={}",
-                                    url::form_urlencoded::byte_serialize(k.as_bytes())
-                                        .collect::<String>(),
-                                    url::form_urlencoded::byte_serialize(v.as_bytes())
-                                        .collect::<String>()
-                                )
-                            })
-                            .collect::<Vec<_>>()
-                            .join("&");
-                        url.set_query(Some(&query_string));
-                    }
+    if filtered_query.len() == original_query.len() {
+        return None;
+    }
 
-                    // Build new URL with &amp; for HTML context
-                    let new_url = url.as_str().replace('&', "&amp;");
+    // Clear and rebuild query string
+    url.set_query(None);
+    if !filtered_query.is_empty() {
+        url.query_pairs_mut().extend_pairs(filtered_query);
+    }
+    Some(url.into())
+}
 
-                    // Try both & and &amp; versions for matching (ammonia encodes & to &amp;)
-                    let old_attr_amp = format!("href=\"{}\"", href.replace('&', "&amp;"));
-                    let old_attr_raw = format!("href=\"{}\"", href);
-                    let new_attr = format!("href=\"{}\"", new_url);
+/// Strip tracking parameters from the URL of a link
+fn strip_link_tracking_params(el: &mut Element<'_, '_>, _ctx: &Context<'_>) -> HandlerResult {
+    let Some(href) = el.get_attribute("href") else {
+        return Ok(());
+    };
+    if let Some(stripped) = without_tracking_params(&decode_entities(&href)) {
+        el.set_attribute("href", &encode_attr(&stripped))?;
+    }
+    Ok(())
+}
 
-                    // Try the &amp; version first (more common after ammonia)
-                    if result.contains(&old_attr_amp) {
-                        result = result.replacen(&old_attr_amp, &new_attr, 1);
-                    } else {
-                        result = result.replacen(&old_attr_raw, &new_attr, 1);
-                    }
-                }
+/// Point an image or picture source at the image proxy, resolving relative
+/// URLs against the entry's link. Images are also lazy-loaded.
+fn proxy_image_src(el: &mut Element<'_, '_>, ctx: &Context<'_>) -> HandlerResult {
+    let Some(src) = el.get_attribute("src").map(|src| decode_entities(&src)) else {
+        return Ok(());
+    };
+
+    // Skip data: URLs
+    if src.starts_with("data:") {
+        return Ok(());
+    }
+
+    // Resolve the URL (handles absolute, relative and protocol-relative URLs)
+    let absolute_url = resolve_url(&src, ctx.base.as_ref()).unwrap_or(src);
+
+    if let Some(url) = image_url(&absolute_url, ctx.secret, ctx.policy.proxy_images) {
+        // Proxy URLs only carry base64 and hex values, so need no escaping
+        let value = if ctx.policy.proxy_images {
+            url
+        } else {
+            encode_attr(&url)
+        };
+        el.set_attribute("src", &value)?;
+        if el.tag_name() == "img" {
+            el.set_attribute("loading", "lazy")?;
+            el.set_attribute("decoding", "async")?;
+        }
+    }
+    Ok(())
+}
+
+/// Rewrite YouTube and Vimeo embeds as the policy says, keep other iframes
+/// the policy allows, and drop the rest
+fn filter_iframe(el: &mut Element<'_, '_>, ctx: &Context<'_>) -> HandlerResult {
+    let src = el.get_attribute("src").map(|src| decode_entities(&src));
+
+    if let Some(video) = src.as_deref().and_then(Video::from_embed_url) {
+        match ctx.policy.video_embeds {
+            VideoEmbeds::Remove => el.remove(),
+            VideoEmbeds::Facade => el.replace(&video_facade(&video, ctx), ContentType::Html),
+            VideoEmbeds::Embed => {
+                el.set_attribute("src", &encode_attr(&video.embed_url()))?;
+                el.set_attribute("allowfullscreen", "")?;
+                sandbox_iframe(el)?;
             }
         }
+        return Ok(());
+    }
+
+    if !src.is_some_and(|src| ctx.policy.allows_iframe(&src)) {
+        el.remove();
+        return Ok(());
+    }
+    sandbox_iframe(el)
+}
+
+/// Click-to-load stand-in for a video: a link to the video showing its
+/// thumbnail, which the entry page swaps for the player in `data-embed`
+fn video_facade(video: &Video, ctx: &Context<'_>) -> String {
+    let thumbnail = video
+        .thumbnail_url()
+        .and_then(|url| image_url(&url, ctx.secret, ctx.policy.proxy_images))
+        .map(|src| {
+            format!(
+                r#"<img src="{}" alt="" loading="lazy" decoding="async">"#,
+                encode_attr(&src)
+            )
+        })
+        .unwrap_or_default();
+    format!(
+        r#"<a class="video-facade" href="{}" data-embed="{}" target="_blank" rel="noopener noreferrer" referrerpolicy="no-referrer">{}<span class="video-facade-label">&#9654; {}</span></a>"#,
+        encode_attr(&video.watch_url()),
+        encode_attr(&video.embed_url()),
+        thumbnail,
+        video.provider()
+    )
+}
+
+fn sandbox_iframe(el: &mut Element<'_, '_>) -> HandlerResult {
+    el.set_attribute("sandbox", IFRAME_SANDBOX)?;
+    // Video hosts refuse to play embeds that send no referrer
+    el.set_attribute("referrerpolicy", "strict-origin-when-cross-origin")?;
+    el.set_attribute("loading", "lazy")?;
+    Ok(())
+}
+
+/// Add target="_blank" and referrerpolicy="no-referrer" to external links
+fn add_link_privacy_attrs(el: &mut Element<'_, '_>, _ctx: &Context<'_>) -> HandlerResult {
+    let is_external = el
+        .get_attribute("href")
+        .is_some_and(|href| href.starts_with("http://") || href.starts_with("https://"));
+    if is_external {
+        el.set_attribute("target", "_blank")?;
+        el.set_attribute("referrerpolicy", "no-referrer")?;
+    }
+    Ok(())
+}
+
+/// The `language-*` class of a code block, also accepting the `lang-*`
+/// spelling some generators use. Other classes are dropped, so content
+/// cannot pick up the page's own styles.
+fn language_class(class: &str) -> Option<String> {
+    class
+        .split_whitespace()
+        .find_map(|c| {
+            c.strip_prefix("language-")
+                .or_else(|| c.strip_prefix("lang-"))
+        })
+        .filter(|lang| !lang.is_empty())
+        .map(|lang| format!("language-{}", lang))
+}
+
+/// Language of a code block, from the `language-*` class `sanitize_html`
+/// leaves on it
+fn code_language(class: &str) -> Option<&str> {
+    class
+        .split_whitespace()
+        .find_map(|c| c.strip_prefix("language-"))
+}
+
+fn start_code_block(el: &mut Element<'_, '_>, ctx: &Context<'_>) -> HandlerResult {
+    let syntax = el
+        .get_attribute("class")
+        .and_then(|class| code_language(&class).and_then(highlight::find_syntax));
+    let (Some(syntax), Some(end_tag_handlers)) = (syntax, el.end_tag_handlers()) else {
+        return Ok(());
+    };
+
+    *ctx.code.borrow_mut() = Some(CodeBlock {
+        syntax,
+        text: String::new(),
+    });
+    let code = Rc::clone(&ctx.code);
+    end_tag_handlers.push(Box::new(move |end: &mut EndTag<'_>| {
+        if let Some(block) = code.borrow_mut().take() {
+            let html = (block.text.len() <= highlight::MAX_CODE_LEN)
+                .then(|| highlight::highlight(&block.text, block.syntax))
+                .flatten()
+                .unwrap_or_else(|| encode_text(&block.text));
+            end.before(&html, ContentType::Html);
+        }
+        Ok(())
+    }));
+    Ok(())
+}
+
+fn flatten_code_markup(el: &mut Element<'_, '_>, ctx: &Context<'_>) -> HandlerResult {
+    if ctx.code.borrow().is_some() {
+        el.remove_and_keep_content();
     }
+    Ok(())
+}
+
+/// Move the text of a code block into the block being collected; it is
+/// written back, highlighted, before the end tag
+fn collect_code_text(chunk: &mut TextChunk<'_>, ctx: &Context<'_>) -> HandlerResult {
+    if let Some(block) = ctx.code.borrow_mut().as_mut() {
+        block.text.push_str(&decode_entities(chunk.as_str()));
+        chunk.remove();
+    }
+    Ok(())
+}
+
+/// Count an open `pre` or `code` element until its end tag
+fn enter_raw_text(el: &mut Element<'_, '_>, ctx: &Context<'_>) -> HandlerResult {
+    let Some(end_tag_handlers) = el.end_tag_handlers() else {
+        return Ok(());
+    };
+    ctx.raw_depth.set(ctx.raw_depth.get() + 1);
+    let depth = Rc::clone(&ctx.raw_depth);
+    end_tag_handlers.push(Box::new(move |_: &mut EndTag<'_>| {
+        depth.set(depth.get().saturating_sub(1));
+        Ok(())
+    }));
+    Ok(())
+}
+
+/// Render the LaTeX math of each text node as MathML. A node can arrive in
+/// several chunks, so they are collected and written out with the last one.
+fn render_text_math(chunk: &mut TextChunk<'_>, ctx: &Context<'_>) -> HandlerResult {
+    if ctx.raw_depth.get() > 0 {
+        return Ok(());
+    }
+
+    let mut text = ctx.text.borrow_mut();
+    text.push_str(chunk.as_str());
+    if !chunk.last_in_text_node() {
+        chunk.remove();
+        return Ok(());
+    }
+
+    let text = std::mem::take(&mut *text);
+    let html = render_math(&text).unwrap_or(text);
+    chunk.replace(&html, ContentType::Html);
+    Ok(())
+}
 
-    result
+fn encode_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
 }
 
+pub fn rewrite_image_urls(html: &str, secret: &[u8], base_url: Option<&str>) -> String {
+    let ctx = Context::new(secret, base_url);
+    transform(html, &ctx, &[PROXY_IMAGES, PROXY_SOURCES])
+}
+
+/// Resolve a URL found in content against the entry's link. Returns `None`
+/// when it should stay as is: already absolute, or an in-page anchor.
+/// Protocol-relative URLs (`//cdn.example.com/...`) take the base's scheme,
+/// or `https` without a base.
+fn resolve_url(url: &str, base: Option<&Url>) -> Option<String> {
+    let url = url.trim();
+    if url.is_empty() || url.starts_with('#') || Url::parse(url).is_ok() {
+        return None;
+    }
+    match base {
+        Some(base) => base.join(url).ok().map(String::from),
+        None => url.starts_with("//").then(|| format!("https:{}", url)),
+    }
+}
+
+/// Rewrite each candidate URL of a `srcset` attribute, keeping its width or
+/// density descriptor. Candidates `rewrite` rejects are dropped.
+fn map_srcset(srcset: &str, rewrite: impl Fn(&str) -> Option<String>) -> String {
+    srcset
+        .split(',')
+        .filter_map(|candidate| {
+            let mut parts = candidate.split_whitespace();
+            let mut rewritten = rewrite(parts.next()?)?;
+            for descriptor in parts {
+                rewritten.push(' ');
+                rewritten.push_str(descriptor);
+            }
+            Some(rewritten)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Image URL as served to the reader: absolute http(s) URLs, through the
+/// image proxy when `proxy` is set. Anything else is dropped.
+fn image_url(url: &str, secret: &[u8], proxy: bool) -> Option<String> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return None;
+    }
+    Some(if proxy {
+        create_proxy_url(url, secret)
+    } else {
+        url.to_string()
+    })
+}
+
+/// Sanitize entry content for display with the default policy
 pub fn sanitize_html(content: &str, secret: &[u8], base_url: Option<&str>) -> String {
+    sanitize_html_with_policy(
+        content,
+        secret,
+        base_url,
+        &DEFAULT_POLICY,
+        &NO_BLOCKED_HOSTS,
+    )
+}
+
+/// Sanitize entry content for display, letting through what `policy` allows
+/// and removing what `blocked` lists
+pub fn sanitize_html_with_policy(
+    content: &str,
+    secret: &[u8],
+    base_url: Option<&str>,
+    policy: &SanitizePolicy,
+    blocked: &BlockedHosts,
+) -> String {
     let allowed_tags: HashSet<&str> = [
         "p",
         "br",
@@ -267,104 +856,89 @@ pub fn sanitize_html(content: &str, secret: &[u8], base_url: Option<&str>) -> St
         "tr",
         "th",
         "td",
+        "picture",
+        "source",
+        // Dropped or rewritten by the transformers unless the policy allows them
+        "iframe",
     ]
     .iter()
+    .chain(MATHML_TAGS)
     .copied()
     .collect();
 
     let url_schemes: HashSet<&str> = ["http", "https"].iter().copied().collect();
 
-    // Step 1: Ammonia sanitization (already adds rel="noopener noreferrer")
-    let sanitized = Builder::default()
+    let base = base_url.and_then(|u| Url::parse(u).ok());
+    let srcset_secret = secret.to_vec();
+    let proxy_images = policy.proxy_images;
+    let ctx = || Context {
+        policy,
+        blocked,
+        ..Context::new(secret, base_url)
+    };
+
+    // Step 1: Render LaTeX math as MathML
+    let content = transform(content, &ctx(), PRE_SANITIZE);
+
+    // Step 2: Ammonia sanitization (already adds rel="noopener noreferrer").
+    // Relative URLs are resolved against the entry's link as attributes are
+    // visited, so links and media work when opened from the reader. `srcset`
+    // is not checked by ammonia, so its candidates are proxied here as well.
+    let mut builder = Builder::default();
+    builder
         .tags(allowed_tags)
+        .add_tag_attributes("img", &["srcset", "sizes"])
+        .add_tag_attributes("source", &["src", "srcset", "sizes", "media", "type"])
+        .add_tag_attributes("code", &["class"])
+        .add_tag_attributes(
+            "iframe",
+            &["src", "width", "height", "title", "allowfullscreen"],
+        )
         .link_rel(Some("noopener noreferrer"))
         .url_schemes(url_schemes)
-        .clean(content)
-        .to_string();
-
-    // Step 2: Remove tracking pixels
-    let without_pixels = remove_tracking_pixels(&sanitized);
-
-    // Step 3: Strip tracking parameters from URLs
-    let without_tracking = strip_tracking_params(&without_pixels);
-
-    // Step 4: Rewrite image URLs to proxy (resolve relative URLs using base_url)
-    let with_images = rewrite_image_urls(&without_tracking, secret, base_url);
-
-    // Step 5: Add privacy attributes to links
-    add_privacy_attrs_to_links(&with_images)
-}
-
-pub fn rewrite_image_urls(html: &str, secret: &[u8], base_url: Option<&str>) -> String {
-    let document = Html::parse_fragment(html);
-    let img_selector = Selector::parse("img[src]").unwrap();
-
-    // Parse base URL if provided
-    let parsed_base = base_url.and_then(|u| Url::parse(u).ok());
-
-    let mut result = html.to_string();
-
-    for element in document.select(&img_selector) {
-        if let Some(src) = element.value().attr("src") {
-            // Skip data: URLs
-            if src.starts_with("data:") {
-                continue;
-            }
-
-            // Resolve the URL (handles both absolute and relative URLs)
-            let absolute_url = if src.starts_with("http://") || src.starts_with("https://") {
-                Some(src.to_string())
-            } else if let Some(ref base) = parsed_base {
-                // Try to resolve relative URL against base URL
-                base.join(src).ok().map(|u| u.to_string())
-            } else {
-                None
-            };
-
-            if let Some(url) = absolute_url {
-                let proxy_url = create_proxy_url(&url, secret);
-
-                // Replace the original src with the proxy URL and add lazy loading
-                let old_attr = format!("src=\"{}\"", src);
-                let new_attr = format!("src=\"{}\" loading=\"lazy\" decoding=\"async\"", proxy_url);
-                result = result.replacen(&old_attr, &new_attr, 1);
+        .attribute_filter(move |_element, attribute, value| match attribute {
+            "href" | "src" => {
+                Some(resolve_url(value, base.as_ref()).map_or(Cow::Borrowed(value), Cow::Owned))
             }
-        }
+            "srcset" => Some(Cow::Owned(map_srcset(value, |url| {
+                let url = resolve_url(url, base.as_ref()).unwrap_or_else(|| url.to_string());
+                image_url(&url, &srcset_secret, proxy_images)
+            }))),
+            "class" => language_class(value).map(Cow::Owned),
+            _ => Some(Cow::Borrowed(value)),
+        });
+    for (tag, attributes) in MATHML_ATTRIBUTES {
+        builder.add_tag_attributes(*tag, *attributes);
     }
-
-    result
-}
-
-/// Add target="_blank" and referrerpolicy="no-referrer" to all external links
-fn add_privacy_attrs_to_links(html: &str) -> String {
-    let document = Html::parse_fragment(html);
-    let a_selector = Selector::parse("a[href]").unwrap();
-
-    let mut result = html.to_string();
-
-    for element in document.select(&a_selector) {
-        if let Some(href) = element.value().attr("href") {
-            // Only process http/https links (external links)
-            if href.starts_with("http://") || href.starts_with("https://") {
-                let old_attr = format!("href=\"{}\"", href);
-                let new_attr = format!(
-                    "href=\"{}\" target=\"_blank\" referrerpolicy=\"no-referrer\"",
-                    href
-                );
-                result = result.replacen(&old_attr, &new_attr, 1);
-            }
-        }
+    if policy.keep_inline_styles {
+        builder
+            .add_generic_attributes(&["style"])
+            .filter_style_properties(ALLOWED_STYLE_PROPERTIES.iter().copied().collect());
     }
+    let sanitized = builder.clean(&content).to_string();
 
-    result
+    // Step 3: Remove tracking pixels, strip tracking parameters, proxy
+    // images, add privacy attributes to links and highlight code blocks,
+    // in one pass
+    transform(&sanitized, &ctx(), PIPELINE)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 
     const TEST_SECRET: &[u8] = b"test_secret_key_32_bytes_long!!!";
 
+    // Each transformer on its own
+    fn remove_tracking_pixels(html: &str) -> String {
+        transform(html, &Context::new(&[], None), &[REMOVE_TRACKING_PIXELS])
+    }
+
+    fn strip_tracking_params(html: &str) -> String {
+        transform(html, &Context::new(&[], None), &[STRIP_TRACKING_PARAMS])
+    }
+
     #[test]
     fn test_sanitize_basic_html() {
         let input = "<p>Hello <strong>world</strong></p>";
@@ -684,4 +1258,349 @@ mod tests {
         assert!(output.contains("/api/proxy/image?url="));
         assert!(!output.contains("src=\"/images/photo.jpg\""));
     }
+
+    fn proxied(url: &str) -> String {
+        format!("/api/proxy/image?url={}", URL_SAFE_NO_PAD.encode(url))
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        let base = Url::parse("https://example.com/blog/post").unwrap();
+        let cases = [
+            ("/about", Some("https://example.com/about")),
+            ("next", Some("https://example.com/blog/next")),
+            (
+                "//cdn.example.net/a.png",
+                Some("https://cdn.example.net/a.png"),
+            ),
+            ("https://other.com/x", None),
+            ("mailto:me@example.com", None),
+            ("#section", None),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(
+                resolve_url(url, Some(&base)).as_deref(),
+                expected,
+                "{}",
+                url
+            );
+        }
+
+        let http_base = Url::parse("http://example.com/").unwrap();
+        assert_eq!(
+            resolve_url("//cdn.example.net/a.png", Some(&http_base)).as_deref(),
+            Some("http://cdn.example.net/a.png")
+        );
+        assert_eq!(
+            resolve_url("//cdn.example.net/a.png", None).as_deref(),
+            Some("https://cdn.example.net/a.png")
+        );
+        assert_eq!(resolve_url("/about", None), None);
+    }
+
+    #[test]
+    fn test_map_srcset() {
+        let srcset = "small.jpg 480w, /large.jpg 1080w,huge.jpg";
+        let output = map_srcset(srcset, |url| Some(format!("[{}]", url)));
+        assert_eq!(output, "[small.jpg] 480w, [/large.jpg] 1080w, [huge.jpg]");
+
+        let output = map_srcset(srcset, |url| url.starts_with('/').then(|| url.to_string()));
+        assert_eq!(output, "/large.jpg 1080w");
+    }
+
+    #[test]
+    fn test_sanitize_resolves_relative_links() {
+        let input = r##"<a href="/2024/next-post">Next</a> <a href="#notes">Notes</a>"##;
+        let output = sanitize_html(input, TEST_SECRET, Some("https://example.com/2024/post"));
+        assert!(output.contains(r#"href="https://example.com/2024/next-post""#));
+        // Resolved links point off-site, so they open in a new tab like other external links
+        assert!(output.contains(r#"target="_blank""#));
+        assert!(output.contains(r##"href="#notes""##));
+    }
+
+    #[test]
+    fn test_sanitize_protocol_relative_urls() {
+        let input =
+            r#"<a href="//news.example.net/story">Story</a><img src="//cdn.example.net/a.png">"#;
+        let output = sanitize_html(input, TEST_SECRET, None);
+        assert!(output.contains(r#"href="https://news.example.net/story""#));
+        assert!(output.contains(&proxied("https://cdn.example.net/a.png")));
+        assert!(!output.contains("src=\"//"));
+    }
+
+    #[test]
+    fn test_sanitize_srcset_and_picture_sources() {
+        let input = r#"<picture><source srcset="/hero.webp 1x, /hero@2x.webp 2x" type="image/webp"><source src="hero.avif" type="image/avif"><img src="/hero.jpg" srcset="/hero.jpg 800w, javascript:alert(1) 1600w" sizes="100vw" alt="Hero"></picture>"#;
+        let output = sanitize_html(input, TEST_SECRET, Some("https://example.com/posts/1"));
+
+        assert!(output.contains("<picture>"));
+        assert!(output.contains(r#"type="image/webp""#));
+        assert!(output.contains(r#"sizes="100vw""#));
+        // Every candidate goes through the proxy, resolved against the entry link
+        for url in [
+            "https://example.com/hero.webp",
+            "https://example.com/hero@2x.webp",
+            "https://example.com/posts/hero.avif",
+            "https://example.com/hero.jpg",
+        ] {
+            assert!(output.contains(&proxied(url)), "{}", url);
+        }
+        assert!(output.contains(" 2x"));
+        assert!(!output.contains("javascript"));
+        assert!(!output.contains("\"/hero"));
+    }
+
+    #[test]
+    fn test_transform_preserves_untouched_markup() {
+        let input = r#"<p class="x">Hi &amp; <em>bye</em></p><img src="data:image/png;base64,AA">"#;
+        let ctx = Context::new(TEST_SECRET, None);
+        assert_eq!(transform(input, &ctx, PIPELINE), input);
+    }
+
+    #[test]
+    fn test_pipeline_skips_removed_pixels() {
+        let input =
+            r#"<img src="https://example.com/pixel/a.gif"><img src="https://example.com/b.jpg">"#;
+        let output = transform(input, &Context::new(TEST_SECRET, None), PIPELINE);
+        // The pixel is dropped before the proxy transformer would rewrite it
+        assert_eq!(output.matches("<img").count(), 1);
+        assert!(output.contains(&proxied("https://example.com/b.jpg")));
+        assert!(!output.contains(&proxied("https://example.com/pixel/a.gif")));
+    }
+
+    #[test]
+    fn test_strip_tracking_params_keeps_entities() {
+        // `&copy` must stay escaped, or browsers would read it as ©
+        let input = r#"<a href="https://example.com/?a=1&amp;copy=2&amp;utm_source=x">Link</a>"#;
+        let output = strip_tracking_params(input);
+        assert!(output.contains(r#"href="https://example.com/?a=1&amp;copy=2""#));
+    }
+
+    #[test]
+    fn test_sanitize_links_in_one_pass() {
+        let input = r#"<a href="https://example.com/?id=1&utm_medium=rss">Link</a>"#;
+        let output = sanitize_html(input, TEST_SECRET, None);
+        assert_eq!(
+            output,
+            r#"<a href="https://example.com/?id=1" rel="noopener noreferrer" target="_blank" referrerpolicy="no-referrer">Link</a>"#
+        );
+    }
+
+    #[test]
+    fn test_language_class() {
+        assert_eq!(
+            language_class("language-rust").as_deref(),
+            Some("language-rust")
+        );
+        assert_eq!(
+            language_class("hljs lang-py").as_deref(),
+            Some("language-py")
+        );
+        assert_eq!(language_class("sidebar"), None);
+        assert_eq!(language_class("language-"), None);
+    }
+
+    #[test]
+    fn test_highlight_code_blocks() {
+        let input =
+            r#"<pre><code class="language-rust hljs">fn main() { let x = 1 &lt; 2; }</code></pre>"#;
+        let output = sanitize_html(input, TEST_SECRET, None);
+        assert!(output.starts_with(r#"<pre><code class="language-rust">"#));
+        assert!(output.contains("<span class=\"hl-"));
+        assert!(output.contains("&lt;"));
+        assert!(!output.contains("hljs"));
+        assert!(!output.contains("style="));
+    }
+
+    #[test]
+    fn test_highlight_flattens_existing_markup() {
+        let input =
+            r#"<pre><code class="language-python"><span>print</span>(<b>"hi"</b>)</code></pre>"#;
+        let output = sanitize_html(input, TEST_SECRET, None);
+        assert!(!output.contains("<b>"));
+        assert!(output.contains("print"));
+        assert!(output.contains("hi"));
+        // Text keeps its order once the old markup is gone
+        assert!(output.find("print").unwrap() < output.find("hi").unwrap());
+    }
+
+    #[test]
+    fn test_unknown_language_and_inline_code_unchanged() {
+        let input = r#"<pre><code class="language-nosuchlang">a &lt; b</code></pre><p><code class="language-rust">x</code></p>"#;
+        let output = sanitize_html(input, TEST_SECRET, None);
+        assert!(output.contains(r#"<code class="language-nosuchlang">a &lt; b</code>"#));
+        assert!(output.contains(r#"<code class="language-rust">x</code>"#));
+        assert!(!output.contains("hl-"));
+    }
+
+    #[test]
+    fn test_render_latex_math() {
+        let input = r#"<p>Energy $E = mc^2$ and</p><p>$$\sum_{i=1}^n i$$</p><pre><code>echo $x$</code></pre>"#;
+        let output = sanitize_html(input, TEST_SECRET, None);
+        assert!(output.contains("<p>Energy <math"));
+        assert!(output.contains("<msup>"));
+        assert!(output.contains(r#"display="block""#));
+        assert!(output.contains("<munderover>") || output.contains("<msubsup>"));
+        // Math in code is left alone
+        assert!(output.contains("<code>echo $x$</code>"));
+    }
+
+    #[test]
+    fn test_mathml_passthrough() {
+        let input = r#"<math display="block" onclick="x()"><mfrac><mi mathvariant="bold">a</mi><mn>2</mn></mfrac><annotation-xml encoding="text/html"><img src=x></annotation-xml></math>"#;
+        let output = sanitize_html(input, TEST_SECRET, None);
+        assert!(output.contains(r#"<math display="block">"#));
+        assert!(output.contains(r#"<mi mathvariant="bold">a</mi>"#));
+        assert!(output.contains("<mfrac>"));
+        assert!(!output.contains("onclick"));
+        assert!(!output.contains("annotation-xml"));
+    }
+
+    #[test]
+    fn test_rendered_math_is_sanitized() {
+        let input = r#"<p>$\text{&lt;img src=x onerror=alert(1)&gt;}$</p>"#;
+        let output = sanitize_html(input, TEST_SECRET, None);
+        // Whether the converter escapes it or not, no handler attribute survives
+        assert!(!output.contains("onerror=\""));
+    }
+
+    fn policy(
+        iframe_hosts: &[&str],
+        keep_inline_styles: bool,
+        proxy_images: bool,
+    ) -> SanitizePolicy {
+        SanitizePolicy {
+            iframe_hosts: iframe_hosts.iter().map(|h| h.to_string()).collect(),
+            keep_inline_styles,
+            proxy_images,
+            video_embeds: VideoEmbeds::Facade,
+        }
+    }
+
+    #[test]
+    fn test_policy_normalized() {
+        let hosts = [
+            " WWW.YouTube.com ",
+            "",
+            "www.youtube.com",
+            "player.vimeo.com",
+        ];
+        let normalized = policy(&hosts, false, true).normalized().unwrap();
+        assert_eq!(
+            normalized.iframe_hosts,
+            vec!["www.youtube.com", "player.vimeo.com"]
+        );
+
+        for host in [
+            "youtube.com/embed",
+            "evil host",
+            "example.com:8080",
+            "user@example.com",
+        ] {
+            assert!(
+                policy(&[host], false, true).normalized().is_err(),
+                "{}",
+                host
+            );
+        }
+    }
+
+    #[test]
+    fn test_default_policy_strips_iframes_and_styles() {
+        let input = r#"<p style="color: red">Hi</p><iframe src="https://www.youtube.com/embed/x"></iframe>"#;
+        let output = sanitize_html(input, TEST_SECRET, None);
+        assert!(!output.contains("iframe"));
+        assert!(!output.contains("style="));
+    }
+
+    #[test]
+    fn test_policy_allows_listed_iframes() {
+        let input = r#"<iframe src="https://embed.example.net/abc" width="560" allow="camera"></iframe><iframe src="https://evil.example.com/x"></iframe><iframe src="http://embed.example.net/def"></iframe>"#;
+        let p = policy(&["embed.example.net"], false, true);
+        let output = sanitize_html_with_policy(input, TEST_SECRET, None, &p, &NO_BLOCKED_HOSTS);
+        assert_eq!(output.matches("<iframe").count(), 1);
+        assert!(output.contains(r#"src="https://embed.example.net/abc""#));
+        assert!(output.contains(r#"width="560""#));
+        assert!(output.contains(&format!(r#"sandbox="{}""#, IFRAME_SANDBOX)));
+        assert!(!output.contains("camera"));
+        assert!(!output.contains("evil.example.com"));
+    }
+
+    #[test]
+    fn test_policy_keeps_safe_inline_styles() {
+        let input = r#"<p style="color: red; position: fixed; background-image: url(https://t.example.com/a.gif)">Hi</p>"#;
+        let p = policy(&[], true, true);
+        let output = sanitize_html_with_policy(input, TEST_SECRET, None, &p, &NO_BLOCKED_HOSTS);
+        assert!(output.contains("color"));
+        assert!(!output.contains("position"));
+        assert!(!output.contains("url("));
+    }
+
+    #[test]
+    fn test_policy_without_image_proxy() {
+        let input = r#"<img src="/a.jpg?x=1&amp;y=2" srcset="/b.jpg 2x, javascript:alert(1) 3x">"#;
+        let p = policy(&[], false, false);
+        let base = Some("https://example.com/post");
+        let output = sanitize_html_with_policy(input, TEST_SECRET, base, &p, &NO_BLOCKED_HOSTS);
+        assert!(!output.contains("/api/proxy/image"));
+        assert!(output.contains(r#"src="https://example.com/a.jpg?x=1&amp;y=2""#));
+        assert!(output.contains("https://example.com/b.jpg 2x"));
+        assert!(!output.contains("javascript"));
+        assert!(output.contains(r#"loading="lazy""#));
+    }
+
+    #[test]
+    fn test_blocked_hosts_are_removed() {
+        let input = r#"<p>See <a href="https://ads.example.com/click?id=1">the offer</a></p><img src="https://ads.example.com/banner.png"><img src="https://cdn.example.com/a.png"><iframe src="https://ads.example.com/frame"></iframe>"#;
+        let blocked = BlockedHosts::from_patterns(["ads.example.com".to_string()]);
+        let output = sanitize_html_with_policy(input, TEST_SECRET, None, &DEFAULT_POLICY, &blocked);
+        assert!(output.contains("<p>See the offer</p>"));
+        assert!(!output.contains("ads.example.com"));
+        assert!(output.contains(&proxied("https://cdn.example.com/a.png")));
+    }
+
+    const YOUTUBE_EMBED: &str = r#"<p>Watch:</p><iframe src="https://www.youtube.com/embed/dQw4w9WgXcQ" width="560"></iframe>"#;
+
+    fn with_videos(video_embeds: VideoEmbeds) -> SanitizePolicy {
+        SanitizePolicy {
+            video_embeds,
+            ..SanitizePolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_video_embed_facade() {
+        let output = sanitize_html(YOUTUBE_EMBED, TEST_SECRET, None);
+        assert!(!output.contains("<iframe"));
+        assert!(output.contains(r#"class="video-facade""#));
+        assert!(output.contains(r#"href="https://www.youtube.com/watch?v=dQw4w9WgXcQ""#));
+        assert!(
+            output.contains(r#"data-embed="https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ""#)
+        );
+        // The thumbnail comes through the proxy, so YouTube sees nothing until a click
+        assert!(output.contains(&proxied("https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg")));
+        assert!(output.contains("<p>Watch:</p>"));
+    }
+
+    #[test]
+    fn test_video_embed_privacy_player() {
+        let p = with_videos(VideoEmbeds::Embed);
+        let output =
+            sanitize_html_with_policy(YOUTUBE_EMBED, TEST_SECRET, None, &p, &NO_BLOCKED_HOSTS);
+        assert!(output.contains(r#"src="https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ""#));
+        assert!(output.contains(r#"width="560""#));
+        assert!(output.contains("sandbox="));
+
+        let vimeo = r#"<iframe src="https://player.vimeo.com/video/76979871"></iframe>"#;
+        let output = sanitize_html_with_policy(vimeo, TEST_SECRET, None, &p, &NO_BLOCKED_HOSTS);
+        assert!(output.contains(r#"src="https://player.vimeo.com/video/76979871?dnt=1""#));
+    }
+
+    #[test]
+    fn test_video_embed_removed() {
+        let p = with_videos(VideoEmbeds::Remove);
+        let output =
+            sanitize_html_with_policy(YOUTUBE_EMBED, TEST_SECRET, None, &p, &NO_BLOCKED_HOSTS);
+        assert_eq!(output, "<p>Watch:</p>");
+    }
 }
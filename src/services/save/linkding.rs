@@ -1,15 +1,38 @@
+use std::collections::BTreeMap;
+
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use url::Url;
 
 use super::{BookmarkData, SaveResult};
 use crate::error::{AppError, AppResult};
-use crate::services::http::{send_with_retry, RetryConfig, EXTERNAL_API_TIMEOUT};
+use crate::services::http::{
+    client_builder, send_with_retry, Outbound, RetryConfig, EXTERNAL_API_TIMEOUT,
+};
 
 /// Linkding service configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LinkdingConfig {
     pub api_url: String,
     pub api_token: String,
+    /// Put the entry's highlights in the bookmark's notes when saving
+    #[serde(default)]
+    pub send_highlights: bool,
+    /// Tags every saved bookmark gets
+    #[serde(default)]
+    pub default_tags: Vec<String>,
+    /// Tags for bookmarks of entries in a category, by category name
+    #[serde(default)]
+    pub category_tags: BTreeMap<String, Vec<String>>,
+    /// Tags for bookmarks of entries of a feed, by feed title
+    #[serde(default)]
+    pub feed_tags: BTreeMap<String, Vec<String>>,
+    /// Save bookmarks as unread, for Linkding's reading list
+    #[serde(default)]
+    pub unread: bool,
+    /// Share saved bookmarks with other Linkding users
+    #[serde(default)]
+    pub shared: bool,
 }
 
 impl LinkdingConfig {
@@ -17,6 +40,47 @@ impl LinkdingConfig {
     pub fn is_configured(&self) -> bool {
         !self.api_url.is_empty() && !self.api_token.is_empty()
     }
+
+    /// Tags for a bookmark: its own, the default ones, then those mapped from
+    /// its category and feed. Names match case-insensitively and each tag is
+    /// given once.
+    pub fn tags_for(&self, bookmark: &BookmarkData) -> Vec<String> {
+        let mapped = |map: &BTreeMap<String, Vec<String>>, name: Option<&str>| {
+            map.iter()
+                .filter(|(key, _)| name.is_some_and(|n| key.eq_ignore_ascii_case(n.trim())))
+                .flat_map(|(_, tags)| tags.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let mut tags: Vec<String> = Vec::new();
+        for tag in bookmark
+            .tags
+            .iter()
+            .cloned()
+            .chain(self.default_tags.iter().cloned())
+            .chain(mapped(&self.category_tags, bookmark.category.as_deref()))
+            .chain(mapped(&self.feed_tags, bookmark.feed_title.as_deref()))
+        {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+}
+
+/// Check tag names before they are stored. Linkding separates tags with
+/// whitespace, so a name cannot contain any.
+pub fn validate_tags<'a>(tags: impl IntoIterator<Item = &'a String>) -> AppResult<()> {
+    for tag in tags {
+        if tag.is_empty() || tag.chars().any(char::is_whitespace) {
+            return Err(AppError::Validation(format!(
+                "Invalid Linkding tag \"{}\": tags cannot be empty or contain spaces",
+                tag
+            )));
+        }
+    }
+    Ok(())
 }
 
 /// Request body for Linkding API
@@ -29,6 +93,10 @@ struct LinkdingBookmarkRequest {
     description: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     tag_names: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+    unread: bool,
+    shared: bool,
 }
 
 /// Response from Linkding API
@@ -41,6 +109,44 @@ struct LinkdingBookmarkResponse {
     title: Option<String>,
 }
 
+/// Response from Linkding's URL check
+#[derive(Debug, Deserialize)]
+struct LinkdingCheckResponse {
+    bookmark: Option<LinkdingBookmarkResponse>,
+}
+
+/// The ID of the bookmark Linkding already has for `url`. Any failure, such
+/// as a Linkding version without the check endpoint, counts as none, and the
+/// bookmark is created as before.
+async fn find_existing(client: &Client, api_url: &str, token: &str, url: &str) -> Option<i64> {
+    let check_url = Url::parse_with_params(&format!("{}check/", api_url), &[("url", url)]).ok()?;
+    let response = send_with_retry(&RetryConfig::default(), || {
+        client.get(check_url.clone()).header("Authorization", token)
+    })
+    .await
+    .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let body: LinkdingCheckResponse = response.json().await.ok()?;
+    body.bookmark.map(|b| b.id)
+}
+
+/// Replace the notes of an existing bookmark, so highlights added since it
+/// was saved reach Linkding
+async fn update_notes(client: &Client, api_url: &str, token: &str, id: i64, notes: &str) {
+    let result = client
+        .patch(format!("{}{}/", api_url, id))
+        .header("Authorization", token)
+        .json(&serde_json::json!({ "notes": notes }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status());
+    if let Err(e) = result {
+        tracing::warn!("Failed to update notes of Linkding bookmark {}: {}", id, e);
+    }
+}
+
 /// Save a bookmark to Linkding
 pub async fn save_to_linkding(
     config: &LinkdingConfig,
@@ -52,10 +158,11 @@ pub async fn save_to_linkding(
             service: "linkding".to_string(),
             message: "Linkding is not configured".to_string(),
             bookmark_url: None,
+            already_saved: false,
         });
     }
 
-    let client = Client::builder()
+    let client = client_builder(Outbound::Configured)?
         .timeout(EXTERNAL_API_TIMEOUT)
         .build()
         .map_err(|e| AppError::Internal(format!("Failed to build HTTP client: {}", e)))?;
@@ -67,10 +174,28 @@ pub async fn save_to_linkding(
         url: bookmark.url.clone(),
         title: bookmark.title.clone(),
         description: bookmark.description.clone(),
-        tag_names: bookmark.tags.clone(),
+        tag_names: config.tags_for(bookmark),
+        notes: bookmark.notes.clone(),
+        unread: config.unread,
+        shared: config.shared,
     };
 
     let token = format!("Token {}", config.api_token);
+
+    // Saving twice would add a second bookmark for the same URL
+    if let Some(id) = find_existing(&client, &api_url, &token, &bookmark.url).await {
+        if let Some(notes) = &bookmark.notes {
+            update_notes(&client, &api_url, &token, id, notes).await;
+        }
+        return Ok(SaveResult {
+            success: true,
+            service: "linkding".to_string(),
+            message: "Already saved to Linkding".to_string(),
+            bookmark_url: Some(construct_bookmark_url(&config.api_url, id)),
+            already_saved: true,
+        });
+    }
+
     let response = send_with_retry(&RetryConfig::default(), || {
         client
             .post(&api_url)
@@ -97,6 +222,7 @@ pub async fn save_to_linkding(
             service: "linkding".to_string(),
             message: "Saved to Linkding".to_string(),
             bookmark_url: Some(bookmark_url),
+            already_saved: false,
         })
     } else {
         let error_text = response
@@ -125,6 +251,7 @@ pub async fn save_to_linkding(
             service: "linkding".to_string(),
             message,
             bookmark_url: None,
+            already_saved: false,
         })
     }
 }
@@ -219,19 +346,72 @@ mod tests {
         let config = LinkdingConfig {
             api_url: "https://linkding.example.com".to_string(),
             api_token: "abc123".to_string(),
+            ..Default::default()
         };
         assert!(config.is_configured());
 
         let empty_url = LinkdingConfig {
             api_url: "".to_string(),
             api_token: "abc123".to_string(),
+            ..Default::default()
         };
         assert!(!empty_url.is_configured());
 
         let empty_token = LinkdingConfig {
             api_url: "https://linkding.example.com".to_string(),
             api_token: "".to_string(),
+            ..Default::default()
         };
         assert!(!empty_token.is_configured());
     }
+
+    #[test]
+    fn test_tags_for() {
+        let config = LinkdingConfig {
+            default_tags: vec!["rdrs".to_string()],
+            category_tags: BTreeMap::from([("Tech".to_string(), vec!["tech".to_string()])]),
+            feed_tags: BTreeMap::from([(
+                "Hacker News".to_string(),
+                vec!["hn".to_string(), "Tech".to_string()],
+            )]),
+            ..Default::default()
+        };
+        let bookmark = BookmarkData {
+            url: "https://example.com".to_string(),
+            title: None,
+            description: None,
+            tags: vec![],
+            notes: None,
+            category: Some("tech".to_string()),
+            feed_title: Some("Hacker News".to_string()),
+        };
+        assert_eq!(config.tags_for(&bookmark), vec!["rdrs", "tech", "hn"]);
+
+        let other = BookmarkData {
+            category: Some("News".to_string()),
+            feed_title: None,
+            ..bookmark
+        };
+        assert_eq!(config.tags_for(&other), vec!["rdrs"]);
+    }
+
+    #[test]
+    fn test_validate_tags() {
+        assert!(validate_tags(&["rust".to_string(), "web-dev".to_string()]).is_ok());
+        assert!(validate_tags(&["two words".to_string()]).is_err());
+        assert!(validate_tags(&[String::new()]).is_err());
+    }
+
+    #[test]
+    fn test_parse_check_response() {
+        let found: LinkdingCheckResponse = serde_json::from_str(
+            r#"{"bookmark": {"id": 42, "url": "https://example.com", "title": "Example"}, "metadata": {}, "auto_tags": []}"#,
+        )
+        .unwrap();
+        assert_eq!(found.bookmark.map(|b| b.id), Some(42));
+
+        let missing: LinkdingCheckResponse =
+            serde_json::from_str(r#"{"bookmark": null, "metadata": {}, "auto_tags": []}"#).unwrap();
+        assert!(missing.bookmark.is_none());
+    }
 }
@@ -1,10 +1,14 @@
 pub mod linkding;
 
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 
 pub use linkding::LinkdingConfig;
 
-use super::summarize::KagiConfig;
+use super::summarize::{KagiConfig, OpenAiConfig};
+use crate::error::{AppError, AppResult};
+use crate::models::entry::EntryWithFeed;
+use crate::models::highlight;
 
 /// Bookmark data to save to external services
 #[derive(Debug, Clone)]
@@ -13,6 +17,11 @@ pub struct BookmarkData {
     pub title: Option<String>,
     pub description: Option<String>,
     pub tags: Vec<String>,
+    /// Markdown notes, for services that keep them
+    pub notes: Option<String>,
+    /// Where the entry came from, for services that tag by it
+    pub category: Option<String>,
+    pub feed_title: Option<String>,
 }
 
 /// Result of saving to a single service
@@ -22,6 +31,9 @@ pub struct SaveResult {
     pub service: String,
     pub message: String,
     pub bookmark_url: Option<String>,
+    /// The service had the URL already, and `bookmark_url` is the existing
+    /// bookmark
+    pub already_saved: bool,
 }
 
 /// Configuration for all save services (stored as JSON in database)
@@ -31,6 +43,9 @@ pub struct SaveServicesConfig {
     pub linkding: Option<LinkdingConfig>,
     #[serde(default)]
     pub kagi: Option<KagiConfig>,
+    /// OpenAI-compatible backend, used for summaries instead of Kagi
+    #[serde(default)]
+    pub openai: Option<OpenAiConfig>,
     // Future services can be added here:
     // pub pocket: Option<PocketConfig>,
     // pub wallabag: Option<WallabagConfig>,
@@ -66,4 +81,66 @@ impl SaveServicesConfig {
     pub fn has_any_service(&self) -> bool {
         !self.configured_services().is_empty()
     }
+
+    /// The OpenAI-compatible backend, if it is set up
+    pub fn openai(&self) -> Option<&OpenAiConfig> {
+        self.openai.as_ref().filter(|c| c.is_configured())
+    }
+
+    /// Check if entries can be summarized, with Kagi or an OpenAI-compatible
+    /// backend
+    pub fn has_summarizer(&self) -> bool {
+        self.openai().is_some() || self.kagi.as_ref().is_some_and(|k| k.is_configured())
+    }
+}
+
+/// The bookmark to save for an entry, with its highlights as notes when the
+/// user asked for them
+pub fn bookmark_for_entry(
+    conn: &Connection,
+    user_id: i64,
+    entry_with_feed: &EntryWithFeed,
+    config: &SaveServicesConfig,
+) -> AppResult<BookmarkData> {
+    let entry = &entry_with_feed.entry;
+    let link = entry
+        .link
+        .clone()
+        .ok_or_else(|| AppError::Validation("Entry has no link to save".to_string()))?;
+
+    let send_highlights = config.linkding.as_ref().is_some_and(|c| c.send_highlights);
+    let notes = if send_highlights {
+        let highlights = highlight::list_by_entry(conn, user_id, entry.id)?;
+        Some(highlight::to_markdown(&highlights)).filter(|n| !n.is_empty())
+    } else {
+        None
+    };
+
+    Ok(BookmarkData {
+        url: link,
+        title: entry.title.clone(),
+        description: entry.summary.clone(),
+        tags: vec![],
+        notes,
+        category: Some(entry_with_feed.category_name.clone()),
+        feed_title: entry_with_feed.feed_title.clone(),
+    })
+}
+
+/// Save a bookmark to one of the services named by `configured_services`
+pub async fn save_to(
+    config: &SaveServicesConfig,
+    service: &str,
+    bookmark: &BookmarkData,
+) -> AppResult<SaveResult> {
+    match (service, &config.linkding) {
+        ("linkding", Some(linkding_config)) => {
+            linkding::save_to_linkding(linkding_config, bookmark).await
+        }
+        // Future services can be added here
+        _ => Err(AppError::Validation(format!(
+            "{} is not configured",
+            service
+        ))),
+    }
 }
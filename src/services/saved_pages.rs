@@ -0,0 +1,158 @@
+//! Pages saved by URL rather than delivered by a feed.
+//!
+//! Each user has a synthetic "Saved pages" feed. Saving a URL stores its
+//! extracted article as an entry there, so it can be read, starred and
+//! summarized like any feed entry. Starred entries of an unsubscribed feed
+//! can be moved there too, so they outlive the feed.
+
+use chrono::Utc;
+use rusqlite::{params, Connection};
+
+use crate::error::AppResult;
+use crate::models::{entry, feed};
+
+/// Name of the category and synthetic feed that hold saved pages
+pub const SAVED_PAGES_NAME: &str = "Saved pages";
+
+/// Identifies the synthetic feed; never fetched
+pub const SAVED_PAGES_FEED_URL: &str = "rdrs:saved";
+
+/// Store an extracted page in the user's "Saved pages" feed. Saving a URL
+/// again replaces its content. Returns the entry and whether it was created.
+pub fn save_page(
+    conn: &Connection,
+    user_id: i64,
+    url: &str,
+    title: Option<&str>,
+    content: &str,
+) -> AppResult<(entry::Entry, bool)> {
+    let saved =
+        feed::find_or_create_synthetic_feed(conn, user_id, SAVED_PAGES_FEED_URL, SAVED_PAGES_NAME)?;
+
+    entry::upsert_entry(
+        conn,
+        saved.id,
+        url,
+        Some(title.unwrap_or(url)),
+        Some(url),
+        Some(content),
+        None,
+        None,
+        Some(Utc::now()),
+    )
+}
+
+/// Move the starred entries of a feed into the user's "Saved pages" feed,
+/// keyed by their link like a saved page. An entry whose link is already
+/// saved stays behind. Returns the number of entries moved.
+pub fn keep_starred(conn: &Connection, user_id: i64, feed_id: i64) -> AppResult<usize> {
+    let saved =
+        feed::find_or_create_synthetic_feed(conn, user_id, SAVED_PAGES_FEED_URL, SAVED_PAGES_NAME)?;
+    if saved.id == feed_id {
+        return Ok(0);
+    }
+
+    let moved = conn.execute(
+        r#"
+        UPDATE OR IGNORE entry
+        SET feed_id = ?1, guid = COALESCE(link, guid), updated_at = datetime('now')
+        WHERE feed_id = ?2 AND starred_at IS NOT NULL
+        "#,
+        params![saved.id, feed_id],
+    )?;
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::category;
+    use crate::models::user::{self, Role};
+
+    #[test]
+    fn test_save_page_reuses_feed_and_entry() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "testuser", "hash123", Role::User)
+            .unwrap()
+            .id;
+
+        let (first, created) = save_page(
+            &conn,
+            user_id,
+            "https://example.com/post",
+            None,
+            "<p>Hello</p>",
+        )
+        .unwrap();
+        assert!(created);
+        assert_eq!(first.title.as_deref(), Some("https://example.com/post"));
+        assert!(first.read_at.is_none());
+
+        let (second, created) = save_page(
+            &conn,
+            user_id,
+            "https://example.com/post",
+            Some("Post"),
+            "<p>Hello again</p>",
+        )
+        .unwrap();
+        assert!(!created);
+        assert_eq!(second.id, first.id);
+        assert_eq!(second.title.as_deref(), Some("Post"));
+
+        let feeds = feed::list_by_user(&conn, user_id).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert!(feeds[0].synthetic);
+        assert_eq!(feeds[0].title.as_deref(), Some(SAVED_PAGES_NAME));
+    }
+
+    #[test]
+    fn test_keep_starred_moves_entries_to_saved_pages() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "testuser", "hash123", Role::User)
+            .unwrap()
+            .id;
+        let cat = category::create_category(&conn, user_id, "Tech").unwrap();
+        let f = feed::create_feed(
+            &conn,
+            cat.id,
+            "https://example.com/feed.xml",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        for guid in ["a", "b"] {
+            let link = format!("https://example.com/{}", guid);
+            entry::upsert_entry(&conn, f.id, guid, None, Some(&link), None, None, None, None)
+                .unwrap();
+        }
+        let starred = entry::find_by_guid_and_feed(&conn, "a", f.id)
+            .unwrap()
+            .unwrap();
+        entry::toggle_star(&conn, starred.id).unwrap();
+
+        assert_eq!(keep_starred(&conn, user_id, f.id).unwrap(), 1);
+
+        let moved = entry::find_by_id(&conn, starred.id).unwrap().unwrap();
+        assert_ne!(moved.feed_id, f.id);
+        assert_eq!(moved.guid, "https://example.com/a");
+        assert!(moved.starred_at.is_some());
+        assert_eq!(
+            feed::find_by_id(&conn, f.id).unwrap().unwrap().entry_count,
+            1
+        );
+        assert_eq!(
+            feed::find_by_id(&conn, moved.feed_id)
+                .unwrap()
+                .unwrap()
+                .entry_count,
+            1
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Per-feed entry hooks written in Rhai.
+//!
+//! A feed's script defines `on_entry(entry)`, called during sync for every
+//! entry of the feed with a map of its `title`, `link`, `content`, `summary`
+//! and `author`. It returns the map, changed or not, to store the entry, or
+//! `()` to skip it. Scripts have no access to files or the network, and each
+//! call is stopped once it exceeds `MAX_OPERATIONS` or `MAX_RUN_TIME`.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+
+/// Longest script accepted, in bytes
+pub const MAX_SCRIPT_BYTES: usize = 16 * 1024;
+
+/// Operations one call may run before it is stopped
+const MAX_OPERATIONS: u64 = 200_000;
+
+/// Wall-clock time one call may take before it is stopped
+const MAX_RUN_TIME: Duration = Duration::from_millis(250);
+
+/// Longest string a script may build; generous for article content
+const MAX_STRING_BYTES: usize = 4 * 1024 * 1024;
+
+const HOOK_NAME: &str = "on_entry";
+
+/// The fields of an entry a hook can read and rewrite
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptEntry {
+    pub title: Option<String>,
+    pub link: Option<String>,
+    pub content: Option<String>,
+    pub summary: Option<String>,
+    pub author: Option<String>,
+}
+
+impl ScriptEntry {
+    fn into_map(self) -> Map {
+        let mut map = Map::new();
+        for (name, value) in [
+            ("title", self.title),
+            ("link", self.link),
+            ("content", self.content),
+            ("summary", self.summary),
+            ("author", self.author),
+        ] {
+            map.insert(
+                name.into(),
+                value.map(Dynamic::from).unwrap_or(Dynamic::UNIT),
+            );
+        }
+        map
+    }
+
+    fn from_map(mut map: Map) -> Result<Self, String> {
+        let mut field = |name: &str| match map.remove(name) {
+            None => Ok(None),
+            Some(value) if value.is_unit() => Ok(None),
+            Some(value) => value
+                .into_string()
+                .map(Some)
+                .map_err(|_| format!("`{}` must be a string or ()", name)),
+        };
+        Ok(ScriptEntry {
+            title: field("title")?,
+            link: field("link")?,
+            content: field("content")?,
+            summary: field("summary")?,
+            author: field("author")?,
+        })
+    }
+}
+
+/// A compiled feed script, ready to run on the entries of one sync
+pub struct EntryHook {
+    engine: Engine,
+    ast: AST,
+    started: Arc<Mutex<Instant>>,
+}
+
+impl EntryHook {
+    /// Compile `source`, which must define `on_entry(entry)`
+    pub fn compile(source: &str) -> Result<Self, String> {
+        if source.len() > MAX_SCRIPT_BYTES {
+            return Err(format!(
+                "Script is too long (at most {} KiB)",
+                MAX_SCRIPT_BYTES / 1024
+            ));
+        }
+
+        let started = Arc::new(Mutex::new(Instant::now()));
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_call_levels(32)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(MAX_STRING_BYTES)
+            .set_max_array_size(10_000)
+            .set_max_map_size(1_000);
+        engine.on_print(|_| {});
+        engine.on_debug(|_, _, _| {});
+        let clock = started.clone();
+        engine.on_progress(move |_| {
+            let started = *clock.lock().unwrap_or_else(|e| e.into_inner());
+            (started.elapsed() > MAX_RUN_TIME).then_some(Dynamic::UNIT)
+        });
+
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+        let has_hook = ast
+            .iter_functions()
+            .any(|f| f.name == HOOK_NAME && f.params.len() == 1);
+        if !has_hook {
+            return Err(format!("Script must define `fn {}(entry)`", HOOK_NAME));
+        }
+
+        Ok(EntryHook {
+            engine,
+            ast,
+            started,
+        })
+    }
+
+    /// Run the hook on `entry`: the entry to store, or `None` to skip it
+    pub fn run(&self, entry: ScriptEntry) -> Result<Option<ScriptEntry>, String> {
+        *self.started.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+        let result: Dynamic = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, HOOK_NAME, (entry.into_map(),))
+            .map_err(|e| e.to_string())?;
+
+        if result.is_unit() {
+            return Ok(None);
+        }
+        match result.try_cast::<Map>() {
+            Some(map) => ScriptEntry::from_map(map).map(Some),
+            None => Err(format!("`{}` must return the entry map or ()", HOOK_NAME)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str) -> ScriptEntry {
+        ScriptEntry {
+            title: Some(title.to_string()),
+            content: Some("<p>Body</p>".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rewrite_and_skip() {
+        let hook = EntryHook::compile(
+            r#"
+            fn on_entry(entry) {
+                if entry.title.contains("Sponsored") {
+                    return ();
+                }
+                entry.title.replace("[Blog] ", "");
+                entry.author = "Staff";
+                entry
+            }
+            "#,
+        )
+        .unwrap();
+
+        let rewritten = hook.run(entry("[Blog] Release notes")).unwrap().unwrap();
+        assert_eq!(rewritten.title.as_deref(), Some("Release notes"));
+        assert_eq!(rewritten.author.as_deref(), Some("Staff"));
+        assert_eq!(rewritten.content.as_deref(), Some("<p>Body</p>"));
+        assert_eq!(rewritten.link, None);
+
+        assert_eq!(hook.run(entry("Sponsored: VPN deal")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_invalid_scripts() {
+        assert!(EntryHook::compile("fn on_entry(entry) {").is_err());
+        assert!(EntryHook::compile("fn other(entry) { entry }").is_err());
+        assert!(EntryHook::compile(&"x".repeat(MAX_SCRIPT_BYTES + 1)).is_err());
+
+        let hook = EntryHook::compile("fn on_entry(entry) { 42 }").unwrap();
+        assert!(hook.run(entry("Post")).is_err());
+
+        let hook = EntryHook::compile("fn on_entry(entry) { entry.title = 1; entry }").unwrap();
+        assert!(hook.run(entry("Post")).is_err());
+    }
+
+    #[test]
+    fn test_runaway_script_is_stopped() {
+        let hook = EntryHook::compile("fn on_entry(entry) { loop {} }").unwrap();
+        assert!(hook.run(entry("Post")).is_err());
+
+        // Each call gets its own budget
+        let hook = EntryHook::compile("fn on_entry(e) { for n in 0..1000 {} e }").unwrap();
+        for _ in 0..3 {
+            assert!(hook.run(entry("Post")).unwrap().is_some());
+        }
+    }
+}
@@ -1,8 +1,10 @@
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
+use super::style::{SummaryLength, SummaryOptions, SummaryStyle};
 use crate::error::{AppError, AppResult};
-use crate::services::http::{send_with_retry, RetryConfig, EXTERNAL_API_TIMEOUT};
+use crate::services::http::{
+    client_builder, send_with_retry, Outbound, RetryConfig, EXTERNAL_API_TIMEOUT,
+};
 
 /// Kagi Universal Summarizer configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +14,12 @@ pub struct KagiConfig {
     /// Target language for summary (optional, e.g., "ZH-HANT", "EN")
     #[serde(default)]
     pub language: Option<String>,
+    /// Style preset used unless a request asks for another
+    #[serde(default)]
+    pub style: SummaryStyle,
+    /// Target length used unless a request asks for another
+    #[serde(default)]
+    pub length: SummaryLength,
 }
 
 impl KagiConfig {
@@ -19,6 +27,14 @@ impl KagiConfig {
     pub fn is_configured(&self) -> bool {
         !self.session_token.is_empty()
     }
+
+    /// The user's default style and length
+    pub fn options(&self) -> SummaryOptions {
+        SummaryOptions {
+            style: self.style,
+            length: self.length,
+        }
+    }
 }
 
 /// Output data from Kagi Summary API
@@ -42,8 +58,13 @@ pub struct SummarizeResult {
     pub error: Option<String>,
 }
 
-/// Summarize a URL using Kagi Universal Summarizer
-pub async fn summarize_url(config: &KagiConfig, url: &str) -> AppResult<SummarizeResult> {
+/// Summarize a URL using Kagi Universal Summarizer, asking for the kind of
+/// summary closest to `style`
+pub async fn summarize_url(
+    config: &KagiConfig,
+    url: &str,
+    style: SummaryStyle,
+) -> AppResult<SummarizeResult> {
     if !config.is_configured() {
         return Ok(SummarizeResult {
             success: false,
@@ -52,7 +73,7 @@ pub async fn summarize_url(config: &KagiConfig, url: &str) -> AppResult<Summariz
         });
     }
 
-    let client = Client::builder()
+    let client = client_builder(Outbound::Summary)?
         .timeout(EXTERNAL_API_TIMEOUT)
         .build()
         .map_err(|e| AppError::Internal(format!("Failed to build HTTP client: {}", e)))?;
@@ -63,7 +84,7 @@ pub async fn summarize_url(config: &KagiConfig, url: &str) -> AppResult<Summariz
 
     {
         let mut query = api_url.query_pairs_mut();
-        query.append_pair("summary_type", "summary");
+        query.append_pair("summary_type", style.summary_type());
         query.append_pair("url", url);
 
         if let Some(lang) = &config.language {
@@ -84,6 +105,47 @@ pub async fn summarize_url(config: &KagiConfig, url: &str) -> AppResult<Summariz
     .await
     .map_err(|e| AppError::Internal(format!("Failed to connect to Kagi: {}", e)))?;
 
+    parse_summary_response(response).await
+}
+
+/// Summarize plain text using Kagi Universal Summarizer. Used to combine
+/// several articles into one digest, where there is no single URL to pass.
+pub async fn summarize_text(config: &KagiConfig, text: &str) -> AppResult<SummarizeResult> {
+    if !config.is_configured() {
+        return Ok(SummarizeResult {
+            success: false,
+            output_text: None,
+            error: Some("Kagi is not configured".to_string()),
+        });
+    }
+
+    let client = client_builder(Outbound::Summary)?
+        .timeout(EXTERNAL_API_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to build HTTP client: {}", e)))?;
+
+    let mut payload = serde_json::json!({
+        "summary_type": "summary",
+        "text": text,
+    });
+    if let Some(lang) = config.language.as_deref().filter(|l| !l.is_empty()) {
+        payload["target_language"] = serde_json::Value::String(lang.to_string());
+    }
+
+    let session_token = config.session_token.clone();
+    let response = send_with_retry(&RetryConfig::default(), || {
+        client
+            .post("https://kagi.com/mother/summary_labs")
+            .header("Authorization", &session_token)
+            .json(&payload)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to connect to Kagi: {}", e)))?;
+
+    parse_summary_response(response).await
+}
+
+async fn parse_summary_response(response: reqwest::Response) -> AppResult<SummarizeResult> {
     let status = response.status();
 
     if status.is_success() {
@@ -150,12 +212,16 @@ mod tests {
         let config = KagiConfig {
             session_token: "some_token".to_string(),
             language: Some("ZH-HANT".to_string()),
+            style: SummaryStyle::default(),
+            length: SummaryLength::default(),
         };
         assert!(config.is_configured());
 
         let empty_token = KagiConfig {
             session_token: "".to_string(),
             language: None,
+            style: SummaryStyle::default(),
+            length: SummaryLength::default(),
         };
         assert!(!empty_token.is_configured());
     }
@@ -165,6 +231,8 @@ mod tests {
         let config = KagiConfig {
             session_token: "test_token".to_string(),
             language: Some("EN".to_string()),
+            style: SummaryStyle::Bullets,
+            length: SummaryLength::Short,
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -172,6 +240,7 @@ mod tests {
 
         assert_eq!(parsed.session_token, config.session_token);
         assert_eq!(parsed.language, config.language);
+        assert_eq!(parsed.options(), config.options());
     }
 
     #[test]
@@ -181,5 +250,6 @@ mod tests {
 
         assert_eq!(config.session_token, "test");
         assert!(config.language.is_none());
+        assert_eq!(config.options(), SummaryOptions::default());
     }
 }
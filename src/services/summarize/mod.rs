@@ -1,3 +1,7 @@
 pub mod kagi;
+pub mod openai;
+pub mod style;
 
 pub use kagi::KagiConfig;
+pub use openai::OpenAiConfig;
+pub use style::{SummaryLength, SummaryOptions, SummaryStyle};
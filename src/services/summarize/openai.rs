@@ -0,0 +1,484 @@
+use serde::{Deserialize, Serialize};
+
+use super::kagi::SummarizeResult;
+use super::style::{SummaryOptions, SummaryStyle};
+use crate::error::{AppError, AppResult};
+use crate::services::http::{
+    client_builder, send_with_retry, Outbound, RetryConfig, EXTERNAL_API_TIMEOUT,
+};
+
+/// Maximum characters of article text sent with a request
+const MAX_ARTICLE_CHARS: usize = 24_000;
+
+/// OpenAI-compatible chat completions backend (OpenAI, Ollama, llama.cpp,
+/// vLLM and the like)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAiConfig {
+    /// API root the `/chat/completions` path is added to, e.g.
+    /// `https://api.openai.com/v1`
+    pub base_url: String,
+    /// Sent as a bearer token; local servers often need none
+    #[serde(default)]
+    pub api_key: String,
+    pub model: String,
+}
+
+impl OpenAiConfig {
+    /// Check if the configuration is valid
+    pub fn is_configured(&self) -> bool {
+        !self.base_url.is_empty() && !self.model.is_empty()
+    }
+
+    fn completions_url(&self) -> String {
+        format!("{}/chat/completions", self.base_url.trim_end_matches('/'))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkDelta {
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    delta: ChunkDelta,
+}
+
+/// One `data:` event of a streamed chat completion
+#[derive(Debug, Deserialize)]
+struct CompletionChunk {
+    choices: Vec<ChunkChoice>,
+}
+
+/// Collects text from the server-sent events of a streamed completion,
+/// which may be split anywhere across network chunks
+#[derive(Debug, Default)]
+struct CompletionStream {
+    pending: Vec<u8>,
+    text: String,
+    done: bool,
+}
+
+impl CompletionStream {
+    /// Add received bytes; returns whether any text was added
+    fn push(&mut self, bytes: &[u8]) -> bool {
+        self.pending.extend_from_slice(bytes);
+        let mut added = false;
+        while let Some(pos) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                self.done = true;
+                continue;
+            }
+            let Ok(chunk) = serde_json::from_str::<CompletionChunk>(data) else {
+                continue;
+            };
+            for choice in chunk.choices {
+                if let Some(content) = choice.delta.content.filter(|c| !c.is_empty()) {
+                    self.text.push_str(&content);
+                    added = true;
+                }
+            }
+        }
+        added
+    }
+}
+
+/// Instructions for the style and length asked for
+fn instructions(options: SummaryOptions) -> String {
+    let style = match options.style {
+        SummaryStyle::Paragraph => "Summarize the article in one paragraph.",
+        SummaryStyle::Bullets => {
+            "Summarize the article as bullet points, one per line, each starting with \"- \"."
+        }
+        SummaryStyle::Eli5 => {
+            "Explain what the article says in a few short, plain sentences a child could follow."
+        }
+        SummaryStyle::KeyQuotes => {
+            "List the article's most important quotations word for word as bullet points, one \
+             per line, each starting with \"- \"."
+        }
+    };
+    let length = match options.length.max_words() {
+        Some(words) => format!(" Use at most about {} words.", words),
+        None => String::new(),
+    };
+    format!(
+        "{}{} Write in the language of the article. Reply with the summary only.",
+        style, length
+    )
+}
+
+/// A question asked earlier about the same article, with its answer
+#[derive(Debug, Clone)]
+pub struct Turn {
+    pub question: String,
+    pub answer: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplyMessage {
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplyChoice {
+    message: ReplyMessage,
+}
+
+/// A chat completion returned whole
+#[derive(Debug, Deserialize)]
+struct Completion {
+    choices: Vec<ReplyChoice>,
+}
+
+fn failed(error: String) -> SummarizeResult {
+    SummarizeResult {
+        success: false,
+        output_text: None,
+        error: Some(error),
+    }
+}
+
+/// Post a chat completion request. A response the server turned down is
+/// returned as the message to show the user.
+async fn post_completion(
+    config: &OpenAiConfig,
+    payload: &serde_json::Value,
+) -> AppResult<Result<reqwest::Response, String>> {
+    if !config.is_configured() {
+        return Ok(Err(
+            "OpenAI-compatible backend is not configured".to_string()
+        ));
+    }
+
+    let client = client_builder(Outbound::Summary)?
+        .timeout(EXTERNAL_API_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to build HTTP client: {}", e)))?;
+
+    let url = config.completions_url();
+    let api_key = config.api_key.clone();
+    let response = send_with_retry(&RetryConfig::default(), || {
+        let request = client.post(&url).json(payload);
+        if api_key.is_empty() {
+            request
+        } else {
+            request.bearer_auth(&api_key)
+        }
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to connect to summarizer: {}", e)))?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(Ok(response));
+    }
+    let error_text = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Unknown error".to_string());
+    let message = match status.as_u16() {
+        401 => "Invalid API key".to_string(),
+        404 => "Model or endpoint not found".to_string(),
+        429 => "Rate limit exceeded - please try again later".to_string(),
+        _ => format!("Summarizer error ({}): {}", status, error_text),
+    };
+    Ok(Err(message))
+}
+
+/// Summarize article text with a streamed chat completion, calling
+/// `on_text` with the text so far each time more arrives
+pub async fn summarize_stream(
+    config: &OpenAiConfig,
+    article: &str,
+    options: SummaryOptions,
+    mut on_text: impl FnMut(&str),
+) -> AppResult<SummarizeResult> {
+    let article: String = article.chars().take(MAX_ARTICLE_CHARS).collect();
+    let payload = serde_json::json!({
+        "model": config.model,
+        "stream": true,
+        "messages": [
+            {"role": "system", "content": instructions(options)},
+            {"role": "user", "content": article},
+        ],
+    });
+
+    let mut response = match post_completion(config, &payload).await? {
+        Ok(response) => response,
+        Err(message) => return Ok(failed(message)),
+    };
+
+    let mut stream = CompletionStream::default();
+    while let Some(bytes) = response
+        .chunk()
+        .await
+        .map_err(|e| AppError::Internal(format!("Summarizer stream failed: {}", e)))?
+    {
+        if stream.push(&bytes) {
+            on_text(&stream.text);
+        }
+        if stream.done {
+            break;
+        }
+    }
+
+    let text = stream.text.trim().to_string();
+    if text.is_empty() {
+        return Ok(failed("No summary returned".to_string()));
+    }
+    Ok(SummarizeResult {
+        success: true,
+        output_text: Some(text),
+        error: None,
+    })
+}
+
+/// Messages asking `question` about the article, after the earlier turns
+fn question_messages(article: &str, history: &[Turn], question: &str) -> Vec<serde_json::Value> {
+    let article: String = article.chars().take(MAX_ARTICLE_CHARS).collect();
+    let mut messages = vec![serde_json::json!({
+        "role": "system",
+        "content": format!(
+            "Answer questions about the article below using only what it says. If it does \
+             not say, answer that it does not. Be brief and answer in the language of the \
+             question.\n\n{}",
+            article
+        ),
+    })];
+    for turn in history {
+        messages.push(serde_json::json!({"role": "user", "content": turn.question}));
+        messages.push(serde_json::json!({"role": "assistant", "content": turn.answer}));
+    }
+    messages.push(serde_json::json!({"role": "user", "content": question}));
+    messages
+}
+
+/// Text of a chat completion returned whole, or the message to show when
+/// the backend gave none
+async fn reply(
+    config: &OpenAiConfig,
+    messages: Vec<serde_json::Value>,
+) -> AppResult<Result<String, String>> {
+    let payload = serde_json::json!({
+        "model": config.model,
+        "messages": messages,
+    });
+
+    let response = match post_completion(config, &payload).await? {
+        Ok(response) => response,
+        Err(message) => return Ok(Err(message)),
+    };
+    let completion: Completion = response
+        .json()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to parse reply: {}", e)))?;
+
+    Ok(completion
+        .choices
+        .into_iter()
+        .find_map(|c| c.message.content)
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .ok_or_else(|| "No reply returned".to_string()))
+}
+
+/// Answer a question about article text, following on from `history`
+pub async fn ask(
+    config: &OpenAiConfig,
+    article: &str,
+    history: &[Turn],
+    question: &str,
+) -> AppResult<SummarizeResult> {
+    match reply(config, question_messages(article, history, question)).await? {
+        Ok(answer) => Ok(SummarizeResult {
+            success: true,
+            output_text: Some(answer),
+            error: None,
+        }),
+        Err(message) => Ok(failed(message)),
+    }
+}
+
+/// Characters of article text sent when picking tags; the opening is
+/// usually enough to tell the topic
+const MAX_TAGGING_CHARS: usize = 4_000;
+
+/// A tag the backend may pick, with what it covers
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+fn label_messages(labels: &[Label], title: &str, article: &str) -> Vec<serde_json::Value> {
+    let list = labels
+        .iter()
+        .map(|l| match &l.description {
+            Some(description) => format!("- {}: {}", l.name, description),
+            None => format!("- {}", l.name),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let article: String = article.chars().take(MAX_TAGGING_CHARS).collect();
+    vec![
+        serde_json::json!({
+            "role": "system",
+            "content": format!(
+                "Pick the labels below that describe the topic of the article. Use only \
+                 these labels:\n{}\n\nReply with the chosen label names separated by \
+                 commas, or with none if no label fits.",
+                list
+            ),
+        }),
+        serde_json::json!({"role": "user", "content": format!("{}\n\n{}", title, article)}),
+    ]
+}
+
+/// Names from `labels` found in the reply, in the order of `labels`;
+/// anything else the backend made up is left out
+fn chosen_labels(labels: &[Label], reply: &str) -> Vec<String> {
+    let picked: Vec<String> = reply
+        .split([',', '\n'])
+        .map(|part| {
+            part.trim()
+                .trim_start_matches(['-', '*'])
+                .trim()
+                .trim_matches(['"', '\'', '`', '.'])
+                .to_lowercase()
+        })
+        .filter(|part| !part.is_empty())
+        .collect();
+    labels
+        .iter()
+        .filter(|l| picked.contains(&l.name.to_lowercase()))
+        .map(|l| l.name.clone())
+        .collect()
+}
+
+/// Pick the labels that fit an article. The outer error is for failures
+/// worth retrying; the inner one is the backend turning the request down.
+pub async fn pick_labels(
+    config: &OpenAiConfig,
+    labels: &[Label],
+    title: &str,
+    article: &str,
+) -> AppResult<Result<Vec<String>, String>> {
+    let reply = reply(config, label_messages(labels, title, article)).await?;
+    Ok(reply.map(|text| chosen_labels(labels, &text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::summarize::SummaryLength;
+
+    #[test]
+    fn test_completion_stream_joins_split_events() {
+        let mut stream = CompletionStream::default();
+        let events = concat!(
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\" wörld\"}}]}\n\n",
+            ": keep-alive\n\n",
+            "data: [DONE]\n\n",
+        )
+        .as_bytes();
+
+        // Split inside an event and inside a multi-byte character
+        let split = events.iter().position(|&b| b == 0xc3).unwrap() + 1;
+        assert!(!stream.push(&events[..20]));
+        assert!(stream.push(&events[20..split]));
+        assert_eq!(stream.text, "Hello");
+        assert!(stream.push(&events[split..]));
+        assert_eq!(stream.text, "Hello wörld");
+        assert!(stream.done);
+    }
+
+    #[test]
+    fn test_instructions_follow_options() {
+        let prompt = instructions(SummaryOptions {
+            style: SummaryStyle::Bullets,
+            length: SummaryLength::Short,
+        });
+        assert!(prompt.contains("bullet points"));
+        assert!(prompt.contains("60 words"));
+
+        let prompt = instructions(SummaryOptions::default());
+        assert!(prompt.contains("one paragraph"));
+        assert!(!prompt.contains("words."));
+    }
+
+    #[test]
+    fn test_question_messages_follow_history() {
+        let history = vec![Turn {
+            question: "Who wrote it?".to_string(),
+            answer: "Ada.".to_string(),
+        }];
+        let messages = question_messages("The article.", &history, "When?");
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0]["role"], "system");
+        assert!(messages[0]["content"]
+            .as_str()
+            .unwrap()
+            .ends_with("The article."));
+        assert_eq!(messages[1]["content"], "Who wrote it?");
+        assert_eq!(messages[2]["role"], "assistant");
+        assert_eq!(messages[3]["content"], "When?");
+    }
+
+    #[test]
+    fn test_chosen_labels_keep_known_names() {
+        let labels = vec![
+            Label {
+                name: "Rust".to_string(),
+                description: None,
+            },
+            Label {
+                name: "Machine Learning".to_string(),
+                description: Some("AI and models".to_string()),
+            },
+            Label {
+                name: "Cooking".to_string(),
+                description: None,
+            },
+        ];
+        assert_eq!(
+            chosen_labels(&labels, "machine learning, \"rust\", Politics"),
+            vec!["Rust", "Machine Learning"]
+        );
+        assert_eq!(chosen_labels(&labels, "- Cooking.\n"), vec!["Cooking"]);
+        assert!(chosen_labels(&labels, "none").is_empty());
+
+        let messages = label_messages(&labels, "Title", "Body");
+        let prompt = messages[0]["content"].as_str().unwrap();
+        assert!(prompt.contains("- Machine Learning: AI and models"));
+        assert_eq!(messages[1]["content"], "Title\n\nBody");
+    }
+
+    #[test]
+    fn test_openai_config_is_configured() {
+        let config = OpenAiConfig {
+            base_url: "http://localhost:11434/v1/".to_string(),
+            api_key: String::new(),
+            model: "llama3".to_string(),
+        };
+        assert!(config.is_configured());
+        assert_eq!(
+            config.completions_url(),
+            "http://localhost:11434/v1/chat/completions"
+        );
+
+        let no_model = OpenAiConfig {
+            model: String::new(),
+            ..config
+        };
+        assert!(!no_model.is_configured());
+    }
+}
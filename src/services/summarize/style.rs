@@ -0,0 +1,275 @@
+//! How a summary reads: its style preset and target length.
+//!
+//! Kagi takes a kind of summary rather than a free-form prompt, so each style
+//! asks for the closest kind (prose or takeaways) and the result is shaped
+//! to the preset and cut to the target length at sentence or line bounds.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryStyle {
+    /// One paragraph of prose
+    #[default]
+    Paragraph,
+    /// A list of takeaways
+    Bullets,
+    /// The plain opening of the summary, kept short
+    Eli5,
+    /// Takeaways that quote the article, or all of them if none do
+    KeyQuotes,
+}
+
+impl SummaryStyle {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SummaryStyle::Paragraph => "paragraph",
+            SummaryStyle::Bullets => "bullets",
+            SummaryStyle::Eli5 => "eli5",
+            SummaryStyle::KeyQuotes => "key_quotes",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "paragraph" => Some(SummaryStyle::Paragraph),
+            "bullets" => Some(SummaryStyle::Bullets),
+            "eli5" => Some(SummaryStyle::Eli5),
+            "key_quotes" => Some(SummaryStyle::KeyQuotes),
+            _ => None,
+        }
+    }
+
+    /// Kagi `summary_type` to ask for
+    pub fn summary_type(&self) -> &'static str {
+        match self {
+            SummaryStyle::Paragraph | SummaryStyle::Eli5 => "summary",
+            SummaryStyle::Bullets | SummaryStyle::KeyQuotes => "takeaway",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryLength {
+    Short,
+    Medium,
+    /// As long as Kagi makes it
+    #[default]
+    Long,
+}
+
+impl SummaryLength {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SummaryLength::Short => "short",
+            SummaryLength::Medium => "medium",
+            SummaryLength::Long => "long",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "short" => Some(SummaryLength::Short),
+            "medium" => Some(SummaryLength::Medium),
+            "long" => Some(SummaryLength::Long),
+            _ => None,
+        }
+    }
+
+    /// Words to aim for; the first sentence or line is always kept
+    pub(crate) fn max_words(&self) -> Option<usize> {
+        match self {
+            SummaryLength::Short => Some(60),
+            SummaryLength::Medium => Some(150),
+            SummaryLength::Long => None,
+        }
+    }
+}
+
+/// Style and length of one summary
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SummaryOptions {
+    #[serde(default)]
+    pub style: SummaryStyle,
+    #[serde(default)]
+    pub length: SummaryLength,
+}
+
+impl SummaryOptions {
+    /// Shape Kagi's markdown to the style and cut it to the length
+    pub fn shape(&self, text: &str) -> String {
+        let max_words = self.length.max_words();
+        match self.style {
+            SummaryStyle::Paragraph => {
+                let paragraph = text
+                    .lines()
+                    .map(strip_marker)
+                    .filter(|l| !l.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                fit(sentences(&paragraph), max_words, " ")
+            }
+            SummaryStyle::Eli5 => {
+                let first = text
+                    .split("\n\n")
+                    .map(|p| p.lines().map(strip_marker).collect::<Vec<_>>().join(" "))
+                    .find(|p| !p.trim().is_empty())
+                    .unwrap_or_default();
+                fit(sentences(&first), max_words.or(Some(60)), " ")
+            }
+            SummaryStyle::Bullets => fit(bullets(text).collect(), max_words, "\n"),
+            SummaryStyle::KeyQuotes => {
+                let all: Vec<String> = bullets(text).collect();
+                let quoted: Vec<String> = all.iter().filter(|l| quotes(l)).cloned().collect();
+                let lines = if quoted.is_empty() { all } else { quoted };
+                fit(lines, max_words, "\n")
+            }
+        }
+    }
+}
+
+/// Drop a list marker (`-`, `*`, `•` or `1.`) from the start of a line
+fn strip_marker(line: &str) -> &str {
+    let line = line.trim();
+    for marker in ["- ", "* ", "• "] {
+        if let Some(rest) = line.strip_prefix(marker) {
+            return rest.trim_start();
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        if let Some(rest) = line[digits..].strip_prefix(". ") {
+            return rest.trim_start();
+        }
+    }
+    line
+}
+
+fn bullets(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.lines()
+        .map(strip_marker)
+        .filter(|l| !l.is_empty())
+        .map(|l| format!("- {}", l))
+}
+
+fn quotes(line: &str) -> bool {
+    line.contains('"') || line.contains('“') || line.contains('「')
+}
+
+/// Split prose after sentence-ending punctuation
+fn sentences(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        current.push(c);
+        let ends = matches!(c, '。' | '！' | '？')
+            || (matches!(c, '.' | '!' | '?') && chars.peek().is_none_or(|n| n.is_whitespace()));
+        if ends {
+            out.push(current.trim().to_string());
+            current.clear();
+        }
+    }
+    if !current.trim().is_empty() {
+        out.push(current.trim().to_string());
+    }
+    out
+}
+
+/// Keep whole pieces while they fit in `max_words`, always at least one
+fn fit(pieces: Vec<String>, max_words: Option<usize>, sep: &str) -> String {
+    let mut kept = Vec::new();
+    let mut words = 0;
+    for piece in pieces {
+        // Chinese and Japanese have no spaces; two characters count as a word
+        let cjk = piece
+            .chars()
+            .filter(|&c| matches!(c, '\u{3040}'..='\u{30ff}' | '\u{3400}'..='\u{9fff}'))
+            .count();
+        let count = piece.split_whitespace().count() + cjk / 2;
+        if max_words.is_some_and(|max| words + count > max) && !kept.is_empty() {
+            break;
+        }
+        words += count;
+        kept.push(piece);
+    }
+    kept.join(sep)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TAKEAWAYS: &str = "- The council approved the budget.\n- The mayor said \"we kept our promise\".\n- Work starts in May.";
+
+    #[test]
+    fn test_style_parse_round_trip() {
+        for style in [
+            SummaryStyle::Paragraph,
+            SummaryStyle::Bullets,
+            SummaryStyle::Eli5,
+            SummaryStyle::KeyQuotes,
+        ] {
+            assert_eq!(SummaryStyle::parse(style.as_str()), Some(style));
+        }
+        assert_eq!(SummaryStyle::parse("haiku"), None);
+        assert_eq!(SummaryLength::parse("short"), Some(SummaryLength::Short));
+    }
+
+    #[test]
+    fn test_shape_paragraph_joins_lines() {
+        let options = SummaryOptions::default();
+        assert_eq!(
+            options.shape("First point.\n\n- Second point."),
+            "First point. Second point."
+        );
+    }
+
+    #[test]
+    fn test_shape_bullets_and_quotes() {
+        let bullets = SummaryOptions {
+            style: SummaryStyle::Bullets,
+            length: SummaryLength::Long,
+        };
+        assert_eq!(bullets.shape("* One\n2. Two"), "- One\n- Two");
+
+        let quotes = SummaryOptions {
+            style: SummaryStyle::KeyQuotes,
+            length: SummaryLength::Long,
+        };
+        assert_eq!(
+            quotes.shape(TAKEAWAYS),
+            "- The mayor said \"we kept our promise\"."
+        );
+        assert_eq!(quotes.shape("- No quotes here"), "- No quotes here");
+    }
+
+    #[test]
+    fn test_shape_short_keeps_whole_sentences() {
+        let sentence = "This sentence has exactly ten words in it, you see.";
+        let text = [sentence; 10].join(" ");
+        let short = SummaryOptions {
+            style: SummaryStyle::Paragraph,
+            length: SummaryLength::Short,
+        };
+        let shaped = short.shape(&text);
+        assert_eq!(shaped, [sentence; 6].join(" "));
+
+        // A single long sentence is kept rather than cut
+        let long_sentence = ["word"; 100].join(" ");
+        assert_eq!(short.shape(&long_sentence), long_sentence);
+    }
+
+    #[test]
+    fn test_shape_eli5_keeps_first_paragraph() {
+        let eli5 = SummaryOptions {
+            style: SummaryStyle::Eli5,
+            length: SummaryLength::Long,
+        };
+        assert_eq!(
+            eli5.shape("Plain start. More.\n\nDetails nobody needs."),
+            "Plain start. More."
+        );
+    }
+}
@@ -1,8 +1,10 @@
 use chrono::{DateTime, Utc};
 use moka::sync::Cache;
 use serde::Serialize;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::sync::watch;
 
 // Re-export SummaryStatus from models for backward compatibility
 pub use crate::models::entry_summary::SummaryStatus;
@@ -61,6 +63,9 @@ type CacheKey = (i64, i64);
 #[derive(Clone)]
 pub struct SummaryCache {
     cache: Cache<CacheKey, SummaryCacheEntry>,
+    /// Text written so far for summaries being streamed in; closed when the
+    /// summary completes or fails
+    streams: Arc<Mutex<HashMap<CacheKey, watch::Sender<String>>>>,
 }
 
 impl SummaryCache {
@@ -75,7 +80,10 @@ impl SummaryCache {
             .time_to_live(Duration::from_secs(ttl_hours * 3600))
             .build();
 
-        Self { cache }
+        Self {
+            cache,
+            streams: Arc::default(),
+        }
     }
 
     /// Get a summary from the cache
@@ -99,17 +107,48 @@ impl SummaryCache {
     pub fn set_completed(&self, user_id: i64, entry_id: i64, text: String) {
         self.cache
             .insert((user_id, entry_id), SummaryCacheEntry::new_completed(text));
+        self.close_stream(user_id, entry_id);
     }
 
     /// Set a failed status with error message
     pub fn set_failed(&self, user_id: i64, entry_id: i64, error: String) {
         self.cache
             .insert((user_id, entry_id), SummaryCacheEntry::new_failed(error));
+        self.close_stream(user_id, entry_id);
     }
 
     /// Remove a summary from the cache
     pub fn remove(&self, user_id: i64, entry_id: i64) {
         self.cache.invalidate(&(user_id, entry_id));
+        self.close_stream(user_id, entry_id);
+    }
+
+    /// Publish the text written so far for a summary in progress
+    pub fn set_partial(&self, user_id: i64, entry_id: i64, text: &str) {
+        let mut streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        streams
+            .entry((user_id, entry_id))
+            .or_insert_with(|| watch::channel(String::new()).0)
+            .send_replace(text.to_string());
+    }
+
+    /// Follow the text of a summary in progress. Returns `None` unless it is
+    /// pending or processing; the receiver closes once it completes or fails.
+    pub fn subscribe(&self, user_id: i64, entry_id: i64) -> Option<watch::Receiver<String>> {
+        let mut streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        // Checked under the lock so completing cannot slip in between
+        if !self.is_in_flight(user_id, entry_id) {
+            return None;
+        }
+        let sender = streams
+            .entry((user_id, entry_id))
+            .or_insert_with(|| watch::channel(String::new()).0);
+        Some(sender.subscribe())
+    }
+
+    fn close_stream(&self, user_id: i64, entry_id: i64) {
+        let mut streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        streams.remove(&(user_id, entry_id));
     }
 
     /// Check if an entry has a summary (completed or in progress)
@@ -242,6 +281,23 @@ mod tests {
         assert!(user2_entries.contains(&200));
     }
 
+    #[tokio::test]
+    async fn test_stream_partial_text() {
+        let cache = SummaryCache::new(100, 24);
+        assert!(cache.subscribe(1, 100).is_none());
+
+        cache.set_processing(1, 100);
+        let mut rx = cache.subscribe(1, 100).unwrap();
+        cache.set_partial(1, 100, "Hel");
+        cache.set_partial(1, 100, "Hello");
+        rx.changed().await.unwrap();
+        assert_eq!(*rx.borrow_and_update(), "Hello");
+
+        cache.set_completed(1, 100, "Hello world".to_string());
+        assert!(rx.changed().await.is_err());
+        assert!(cache.subscribe(1, 100).is_none());
+    }
+
     #[test]
     fn test_count_by_status() {
         let cache = SummaryCache::new(100, 24);
@@ -3,20 +3,25 @@ use std::time::Duration;
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 
+use super::heartbeat::{Heartbeat, HEARTBEAT_INTERVAL};
 use crate::db::DbPool;
-use crate::models::entry_summary;
+use crate::models::{discussion_cache, entry_summary, related_cache, session, shared_summary};
+use crate::services::{discussions, related};
 
-/// Start the summary cleanup worker that periodically removes expired summaries
+/// Start the cleanup worker that periodically removes expired summaries,
+/// shared summaries, sessions, discussion lookups and related entry rankings
 ///
 /// # Arguments
 /// * `db` - Database connection
 /// * `interval_hours` - How often to run cleanup (in hours)
 /// * `ttl_hours` - Delete summaries older than this many hours
+/// * `heartbeat` - Reported to while the worker is running
 /// * `cancel_token` - Token to signal graceful shutdown
 pub fn start_cleanup_worker(
     db: DbPool,
     interval_hours: u64,
     ttl_hours: i64,
+    heartbeat: Heartbeat,
     cancel_token: CancellationToken,
 ) -> JoinHandle<()> {
     tokio::spawn(async move {
@@ -27,6 +32,7 @@ pub fn start_cleanup_worker(
         );
 
         let mut interval = tokio::time::interval(Duration::from_secs(interval_hours * 3600));
+        let mut beat = tokio::time::interval(HEARTBEAT_INTERVAL);
 
         loop {
             tokio::select! {
@@ -34,6 +40,9 @@ pub fn start_cleanup_worker(
                     tracing::info!("Summary cleanup worker stopping...");
                     break;
                 }
+                _ = beat.tick() => {
+                    heartbeat.beat();
+                }
                 _ = interval.tick() => {
                     tracing::debug!("Running summary cleanup...");
 
@@ -55,6 +64,53 @@ pub fn start_cleanup_worker(
                     if deleted > 0 {
                         tracing::info!("Cleaned up {} expired summaries", deleted);
                     }
+
+                    let expired = db
+                        .background(move |conn| shared_summary::delete_expired(conn, ttl_hours))
+                        .await;
+                    match expired {
+                        Ok(Ok(count)) if count > 0 => {
+                            tracing::debug!("Cleaned up {} expired shared summaries", count);
+                        }
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) => tracing::error!("Failed to cleanup shared summaries: {}", e),
+                        Err(e) => tracing::error!("Failed to access DB for cleanup: {}", e),
+                    }
+
+                    match db.background(session::cleanup_expired).await {
+                        Ok(Ok(count)) if count > 0 => {
+                            tracing::info!("Cleaned up {} expired sessions", count);
+                        }
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) => tracing::error!("Failed to cleanup expired sessions: {}", e),
+                        Err(e) => tracing::error!("Failed to access DB for cleanup: {}", e),
+                    }
+
+                    let ttl = discussions::CACHE_TTL_SECS;
+                    let expired = db
+                        .background(move |conn| discussion_cache::delete_expired(conn, ttl))
+                        .await;
+                    match expired {
+                        Ok(Ok(count)) if count > 0 => {
+                            tracing::debug!("Cleaned up {} expired discussion lookups", count);
+                        }
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) => tracing::error!("Failed to cleanup discussion lookups: {}", e),
+                        Err(e) => tracing::error!("Failed to access DB for cleanup: {}", e),
+                    }
+
+                    let ttl = related::CACHE_TTL_SECS;
+                    let expired = db
+                        .background(move |conn| related_cache::delete_expired(conn, ttl))
+                        .await;
+                    match expired {
+                        Ok(Ok(count)) if count > 0 => {
+                            tracing::debug!("Cleaned up {} expired related rankings", count);
+                        }
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) => tracing::error!("Failed to cleanup related rankings: {}", e),
+                        Err(e) => tracing::error!("Failed to access DB for cleanup: {}", e),
+                    }
                 }
             }
         }
@@ -69,6 +125,7 @@ mod tests {
     use crate::db::init_db;
     use crate::models::user::Role;
     use crate::models::{category, entry, feed, user};
+    use crate::services::SummaryOptions;
     use rusqlite::Connection;
 
     fn setup_db() -> Connection {
@@ -119,7 +176,7 @@ mod tests {
         .unwrap();
 
         // Create a summary
-        entry_summary::upsert_pending(&conn, user_id, entry.id).unwrap();
+        entry_summary::upsert_pending(&conn, user_id, entry.id, SummaryOptions::default()).unwrap();
         entry_summary::set_completed(&conn, user_id, entry.id, "Summary text").unwrap();
 
         // Verify it exists
@@ -146,7 +203,13 @@ mod tests {
         let cancel_token = CancellationToken::new();
 
         // Start cleanup worker with a long interval (won't trigger during test)
-        let handle = start_cleanup_worker(db, 1000, 24, cancel_token.clone());
+        let handle = start_cleanup_worker(
+            db,
+            1000,
+            24,
+            Heartbeat::detached("summary_cleanup"),
+            cancel_token.clone(),
+        );
 
         // Cancel immediately
         cancel_token.cancel();
@@ -199,7 +262,7 @@ mod tests {
             .unwrap();
 
             // Create an expired summary (25 hours old)
-            entry_summary::upsert_pending(conn, user_id, entry_obj.id).unwrap();
+            entry_summary::upsert_pending(conn, user_id, entry_obj.id, SummaryOptions::default()).unwrap();
             entry_summary::set_completed(conn, user_id, entry_obj.id, "Summary text").unwrap();
 
             conn.execute(
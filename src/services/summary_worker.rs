@@ -1,489 +0,0 @@
-use std::sync::Arc;
-
-use tokio::sync::mpsc;
-use tokio::task::JoinHandle;
-use tokio_util::sync::CancellationToken;
-
-use super::summarize::kagi::{self, KagiConfig};
-use super::summary_cache::SummaryCache;
-use crate::db::DbPool;
-use crate::models::{entry_summary, user_settings};
-
-/// A job to summarize an entry
-#[derive(Debug, Clone)]
-pub struct SummaryJob {
-    pub user_id: i64,
-    pub entry_id: i64,
-    pub entry_link: String,
-}
-
-/// Start the summary worker that processes jobs from the queue
-pub fn start_summary_worker(
-    mut rx: mpsc::Receiver<SummaryJob>,
-    cache: Arc<SummaryCache>,
-    db: DbPool,
-    cancel_token: CancellationToken,
-) -> JoinHandle<()> {
-    tokio::spawn(async move {
-        tracing::info!("Summary worker started");
-
-        loop {
-            let job = tokio::select! {
-                _ = cancel_token.cancelled() => {
-                    tracing::info!("Summary worker stopping, draining remaining jobs...");
-                    // Drain remaining jobs before exiting
-                    while let Ok(job) = rx.try_recv() {
-                        process_summary_job(&job, &cache, &db).await;
-                    }
-                    break;
-                }
-                job = rx.recv() => {
-                    match job {
-                        Some(job) => job,
-                        None => break,
-                    }
-                }
-            };
-
-            process_summary_job(&job, &cache, &db).await;
-        }
-
-        tracing::info!("Summary worker stopped");
-    })
-}
-
-async fn process_summary_job(job: &SummaryJob, cache: &Arc<SummaryCache>, db: &DbPool) {
-    tracing::debug!(
-        "Processing summary job: user={}, entry={}, link={}",
-        job.user_id,
-        job.entry_id,
-        job.entry_link
-    );
-
-    // Mark as processing in both cache and DB
-    cache.set_processing(job.user_id, job.entry_id);
-    {
-        let user_id = job.user_id;
-        let entry_id = job.entry_id;
-        let _ = db
-            .background(move |conn| entry_summary::set_processing(conn, user_id, entry_id))
-            .await;
-    }
-
-    // Get Kagi config for the user
-    let user_id = job.user_id;
-    let entry_id = job.entry_id;
-    let kagi_config = match db
-        .background(move |conn| user_settings::get_save_services_config(conn, user_id))
-        .await
-    {
-        Ok(Ok(config)) => config.kagi,
-        Ok(Err(e)) => {
-            tracing::error!("Failed to get user settings: {}", e);
-            let error_msg = "Failed to load Kagi settings".to_string();
-            cache.set_failed(job.user_id, job.entry_id, error_msg.clone());
-            let _ = db
-                .background(move |conn| {
-                    entry_summary::set_failed(conn, user_id, entry_id, &error_msg)
-                })
-                .await;
-            return;
-        }
-        Err(e) => {
-            tracing::error!("Failed to access DB: {}", e);
-            let error_msg = "Internal error: DB access failed".to_string();
-            cache.set_failed(job.user_id, job.entry_id, error_msg);
-            return;
-        }
-    };
-
-    let kagi_config = match kagi_config {
-        Some(c) if c.is_configured() => c,
-        _ => {
-            let error_msg = "Kagi is not configured".to_string();
-            cache.set_failed(job.user_id, job.entry_id, error_msg.clone());
-            let user_id = job.user_id;
-            let entry_id = job.entry_id;
-            let _ = db
-                .background(move |conn| {
-                    entry_summary::set_failed(conn, user_id, entry_id, &error_msg)
-                })
-                .await;
-            return;
-        }
-    };
-
-    // Call Kagi API
-    match summarize_with_kagi(&kagi_config, &job.entry_link).await {
-        Ok(summary_text) => {
-            tracing::debug!(
-                "Summary completed for entry {}: {} chars",
-                job.entry_id,
-                summary_text.len()
-            );
-            // Update cache
-            cache.set_completed(job.user_id, job.entry_id, summary_text.clone());
-            // Update DB
-            let user_id = job.user_id;
-            let entry_id = job.entry_id;
-            let _ = db
-                .background(move |conn| {
-                    entry_summary::set_completed(conn, user_id, entry_id, &summary_text)
-                })
-                .await;
-        }
-        Err(error) => {
-            tracing::warn!("Summary failed for entry {}: {}", job.entry_id, error);
-            cache.set_failed(job.user_id, job.entry_id, error.clone());
-            // Update DB
-            let user_id = job.user_id;
-            let entry_id = job.entry_id;
-            let _ = db
-                .background(move |conn| entry_summary::set_failed(conn, user_id, entry_id, &error))
-                .await;
-        }
-    }
-}
-
-/// Call Kagi API to get a summary
-async fn summarize_with_kagi(config: &KagiConfig, url: &str) -> Result<String, String> {
-    match kagi::summarize_url(config, url).await {
-        Ok(result) => {
-            if result.success {
-                result
-                    .output_text
-                    .ok_or_else(|| "No summary text returned".to_string())
-            } else {
-                Err(result.error.unwrap_or_else(|| "Unknown error".to_string()))
-            }
-        }
-        Err(e) => Err(format!("Kagi API error: {}", e)),
-    }
-}
-
-/// Create a summary job queue channel
-pub fn create_summary_channel(
-    buffer_size: usize,
-) -> (mpsc::Sender<SummaryJob>, mpsc::Receiver<SummaryJob>) {
-    mpsc::channel(buffer_size)
-}
-
-/// Recover incomplete summary jobs on startup
-/// Returns the number of jobs re-queued
-pub async fn recover_incomplete_jobs(
-    db: DbPool,
-    tx: mpsc::Sender<SummaryJob>,
-    cache: Arc<SummaryCache>,
-) -> usize {
-    let incomplete = match db.background(entry_summary::find_incomplete).await {
-        Ok(Ok(jobs)) => jobs,
-        Ok(Err(e)) => {
-            tracing::error!("Failed to find incomplete jobs: {}", e);
-            return 0;
-        }
-        Err(e) => {
-            tracing::error!("Failed to access DB for recovery: {}", e);
-            return 0;
-        }
-    };
-
-    let count = incomplete.len();
-    if count > 0 {
-        tracing::info!("Recovering {} incomplete summary jobs", count);
-    }
-
-    for (user_id, entry_id, entry_link) in incomplete {
-        // Set pending in cache to track the job
-        cache.set_pending(user_id, entry_id);
-
-        let job = SummaryJob {
-            user_id,
-            entry_id,
-            entry_link,
-        };
-
-        if let Err(e) = tx.send(job).await {
-            tracing::error!("Failed to re-queue job: {}", e);
-        }
-    }
-
-    count
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::db::init_db;
-    use crate::models::user::Role;
-    use crate::models::{category, entry, feed, user};
-    use rusqlite::Connection;
-
-    fn setup_test_db() -> DbPool {
-        let conn = Connection::open_in_memory().unwrap();
-        init_db(&conn).unwrap();
-        let (pool, _handle) = DbPool::new(conn);
-        pool
-    }
-
-    #[test]
-    fn test_summary_job_creation() {
-        let job = SummaryJob {
-            user_id: 1,
-            entry_id: 100,
-            entry_link: "https://example.com/article".to_string(),
-        };
-
-        assert_eq!(job.user_id, 1);
-        assert_eq!(job.entry_id, 100);
-        assert_eq!(job.entry_link, "https://example.com/article");
-    }
-
-    #[test]
-    fn test_summary_job_clone() {
-        let job = SummaryJob {
-            user_id: 1,
-            entry_id: 100,
-            entry_link: "https://example.com/article".to_string(),
-        };
-
-        let cloned = job.clone();
-        assert_eq!(cloned.user_id, job.user_id);
-        assert_eq!(cloned.entry_id, job.entry_id);
-        assert_eq!(cloned.entry_link, job.entry_link);
-    }
-
-    #[test]
-    fn test_summary_job_debug() {
-        let job = SummaryJob {
-            user_id: 1,
-            entry_id: 100,
-            entry_link: "https://example.com/article".to_string(),
-        };
-
-        let debug_str = format!("{:?}", job);
-        assert!(debug_str.contains("SummaryJob"));
-        assert!(debug_str.contains("user_id: 1"));
-        assert!(debug_str.contains("entry_id: 100"));
-    }
-
-    #[tokio::test]
-    async fn test_channel_creation() {
-        let (tx, mut rx) = create_summary_channel(10);
-
-        let job = SummaryJob {
-            user_id: 1,
-            entry_id: 100,
-            entry_link: "https://example.com".to_string(),
-        };
-
-        tx.send(job.clone()).await.unwrap();
-        let received = rx.recv().await.unwrap();
-
-        assert_eq!(received.user_id, job.user_id);
-        assert_eq!(received.entry_id, job.entry_id);
-        assert_eq!(received.entry_link, job.entry_link);
-    }
-
-    #[tokio::test]
-    async fn test_worker_stops_on_cancellation() {
-        let (tx, rx) = create_summary_channel(10);
-        let cache = Arc::new(SummaryCache::new(100, 24));
-        let db = setup_test_db();
-        let cancel_token = CancellationToken::new();
-
-        let handle = start_summary_worker(rx, cache, db, cancel_token.clone());
-
-        // Send a job (it won't be processed properly without Kagi config, but that's OK)
-        let _ = tx
-            .send(SummaryJob {
-                user_id: 1,
-                entry_id: 1,
-                entry_link: "https://example.com".to_string(),
-            })
-            .await;
-
-        // Cancel the worker
-        cancel_token.cancel();
-
-        // Worker should stop
-        let result = tokio::time::timeout(std::time::Duration::from_secs(5), handle).await;
-        assert!(result.is_ok(), "Worker should stop after cancellation");
-    }
-
-    #[tokio::test]
-    async fn test_worker_stops_when_channel_closed() {
-        let (tx, rx) = create_summary_channel(10);
-        let cache = Arc::new(SummaryCache::new(100, 24));
-        let db = setup_test_db();
-        let cancel_token = CancellationToken::new();
-
-        let handle = start_summary_worker(rx, cache, db, cancel_token);
-
-        // Drop the sender to close the channel
-        drop(tx);
-
-        // Worker should stop
-        let result = tokio::time::timeout(std::time::Duration::from_secs(5), handle).await;
-        assert!(result.is_ok(), "Worker should stop when channel closes");
-    }
-
-    #[tokio::test]
-    async fn test_recover_incomplete_jobs_empty() {
-        let db = setup_test_db();
-        let (tx, _rx) = create_summary_channel(10);
-        let cache = Arc::new(SummaryCache::new(100, 24));
-
-        // No incomplete jobs to recover
-        let count = recover_incomplete_jobs(db, tx, cache).await;
-        assert_eq!(count, 0);
-    }
-
-    #[tokio::test]
-    async fn test_recover_incomplete_jobs_with_pending() {
-        let db = setup_test_db();
-
-        // Create test data with a pending summary
-        db.user(|conn| {
-            let user_id = user::create_user(conn, "testuser", "hash", Role::User)
-                .unwrap()
-                .id;
-            let category_id = category::create_category(conn, user_id, "Tech").unwrap().id;
-            let feed_id = feed::create_feed(
-                conn,
-                category_id,
-                "https://example.com/feed.xml",
-                Some("Feed"),
-                None,
-                None,
-                None,
-                None,
-            )
-            .unwrap()
-            .id;
-
-            let (entry_obj, _) = entry::upsert_entry(
-                conn,
-                feed_id,
-                "guid-1",
-                Some("Entry"),
-                Some("https://example.com/article"),
-                None,
-                None,
-                None,
-                None,
-            )
-            .unwrap();
-
-            // Create a pending summary
-            entry_summary::upsert_pending(conn, user_id, entry_obj.id).unwrap();
-        })
-        .await
-        .unwrap();
-
-        let (tx, mut rx) = create_summary_channel(10);
-        let cache = Arc::new(SummaryCache::new(100, 24));
-
-        let count = recover_incomplete_jobs(db, tx, cache.clone()).await;
-        assert_eq!(count, 1);
-
-        // Verify job was queued
-        let job = rx.try_recv().unwrap();
-        assert_eq!(job.entry_link, "https://example.com/article");
-
-        // Verify cache was updated
-        let status = cache.get(job.user_id, job.entry_id);
-        assert!(status.is_some());
-    }
-
-    #[tokio::test]
-    async fn test_recover_incomplete_jobs_with_processing() {
-        let db = setup_test_db();
-
-        // Create test data with a processing (stuck) summary
-        db.user(|conn| {
-            let user_id = user::create_user(conn, "testuser", "hash", Role::User)
-                .unwrap()
-                .id;
-            let category_id = category::create_category(conn, user_id, "Tech").unwrap().id;
-            let feed_id = feed::create_feed(
-                conn,
-                category_id,
-                "https://example.com/feed.xml",
-                Some("Feed"),
-                None,
-                None,
-                None,
-                None,
-            )
-            .unwrap()
-            .id;
-
-            let (entry_obj, _) = entry::upsert_entry(
-                conn,
-                feed_id,
-                "guid-2",
-                Some("Entry 2"),
-                Some("https://example.com/article2"),
-                None,
-                None,
-                None,
-                None,
-            )
-            .unwrap();
-
-            // Create a processing summary (simulates a crashed worker)
-            entry_summary::upsert_pending(conn, user_id, entry_obj.id).unwrap();
-            entry_summary::set_processing(conn, user_id, entry_obj.id).unwrap();
-        })
-        .await
-        .unwrap();
-
-        let (tx, mut rx) = create_summary_channel(10);
-        let cache = Arc::new(SummaryCache::new(100, 24));
-
-        let count = recover_incomplete_jobs(db, tx, cache).await;
-        assert_eq!(count, 1);
-
-        // Verify job was queued
-        let job = rx.try_recv().unwrap();
-        assert_eq!(job.entry_link, "https://example.com/article2");
-    }
-
-    #[tokio::test]
-    async fn test_worker_drains_jobs_on_cancellation() {
-        let (tx, rx) = create_summary_channel(10);
-        let cache = Arc::new(SummaryCache::new(100, 24));
-        let db = setup_test_db();
-        let cancel_token = CancellationToken::new();
-
-        // Create test user for the jobs
-        db.user(|conn| {
-            user::create_user(conn, "testuser", "hash", Role::User).unwrap();
-        })
-        .await
-        .unwrap();
-
-        let handle = start_summary_worker(rx, cache.clone(), db, cancel_token.clone());
-
-        // Send multiple jobs
-        for i in 1..=3 {
-            tx.send(SummaryJob {
-                user_id: 1,
-                entry_id: i,
-                entry_link: format!("https://example.com/{}", i),
-            })
-            .await
-            .unwrap();
-        }
-
-        // Give worker a moment to start processing
-        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
-
-        // Cancel the worker
-        cancel_token.cancel();
-
-        // Worker should stop after draining
-        let result = tokio::time::timeout(std::time::Duration::from_secs(5), handle).await;
-        assert!(result.is_ok(), "Worker should stop after draining jobs");
-    }
-}
@@ -0,0 +1,148 @@
+//! Trash for deleted feeds and categories.
+//!
+//! Deleting a feed or category only stamps `deleted_at`; it stays in the
+//! trash, hidden from every listing, until it is restored or the retention
+//! period passes and the purge worker removes it for good.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use super::heartbeat::{Heartbeat, HEARTBEAT_INTERVAL};
+use super::instance_settings::InstanceSettings;
+use crate::db::DbPool;
+use crate::error::AppResult;
+use crate::models::entry_change::{self, TOMBSTONE_RETENTION_DAYS};
+use crate::models::{category, feed};
+
+/// How long deleted feeds and categories stay in the trash, unless an
+/// admin sets otherwise
+pub const TRASH_RETENTION_DAYS: i64 = 30;
+
+/// How often the trash is purged
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// When something deleted at `deleted_at` will be purged
+pub fn purge_at(deleted_at: DateTime<Utc>, retention_days: i64) -> DateTime<Utc> {
+    deleted_at + chrono::Duration::days(retention_days)
+}
+
+/// Permanently delete everything that has been in the trash longer than
+/// `retention_days`, and drop sync tombstones past their own retention.
+/// Returns the number of feeds and categories removed.
+pub fn purge_expired(conn: &Connection, retention_days: i64) -> AppResult<usize> {
+    let before = Utc::now() - chrono::Duration::days(retention_days);
+    let tx = conn.unchecked_transaction()?;
+    let feeds = feed::purge_trashed(&tx, before)?;
+    let categories = category::purge_trashed(&tx, before)?;
+    entry_change::prune_tombstones(
+        &tx,
+        Utc::now() - chrono::Duration::days(TOMBSTONE_RETENTION_DAYS),
+    )?;
+    tx.commit()?;
+    Ok(feeds + categories)
+}
+
+/// Start the worker that empties expired items from the trash every hour
+///
+/// # Arguments
+/// * `db` - Database connection
+/// * `settings` - Instance settings, for the retention period
+/// * `heartbeat` - Reported to while the worker is running
+/// * `cancel_token` - Token to signal graceful shutdown
+pub fn start_trash_purge_worker(
+    db: DbPool,
+    settings: watch::Receiver<Arc<InstanceSettings>>,
+    heartbeat: Heartbeat,
+    cancel_token: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        tracing::info!("Trash purge worker started");
+
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+        let mut beat = tokio::time::interval(HEARTBEAT_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    tracing::info!("Trash purge worker stopping...");
+                    break;
+                }
+                _ = beat.tick() => {
+                    heartbeat.beat();
+                }
+                _ = interval.tick() => {
+                    let retention_days = settings.borrow().trash_retention_days;
+                    match db.background(move |c| purge_expired(c, retention_days)).await {
+                        Ok(Ok(count)) if count > 0 => {
+                            tracing::info!("Purged {} feeds and categories from the trash", count);
+                        }
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) => tracing::error!("Failed to purge trash: {}", e),
+                        Err(e) => tracing::error!("Failed to access DB for trash purge: {}", e),
+                    }
+                }
+            }
+        }
+
+        tracing::info!("Trash purge worker stopped");
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{entry, user};
+    use rusqlite::params;
+
+    #[test]
+    fn test_purge_expired() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "testuser", "hash", user::Role::User)
+            .unwrap()
+            .id;
+        let old = category::create_category(&conn, user_id, "Old").unwrap();
+        let recent = category::create_category(&conn, user_id, "Recent").unwrap();
+        let f = feed::create_feed(
+            &conn,
+            old.id,
+            "https://example.com/feed.xml",
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        entry::upsert_entry(&conn, f.id, "a", Some("A"), None, None, None, None, None).unwrap();
+
+        category::delete_category(&conn, old.id, user_id).unwrap();
+        category::delete_category(&conn, recent.id, user_id).unwrap();
+        conn.execute(
+            "UPDATE category SET deleted_at = datetime('now', '-31 days') WHERE id = ?1",
+            params![old.id],
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE feed SET deleted_at = datetime('now', '-31 days') WHERE id = ?1",
+            params![f.id],
+        )
+        .unwrap();
+
+        assert_eq!(purge_expired(&conn, TRASH_RETENTION_DAYS).unwrap(), 2);
+        let trashed = category::list_trashed_by_user(&conn, user_id).unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].id, recent.id);
+        let entries: i64 = conn
+            .query_row("SELECT COUNT(*) FROM entry", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(entries, 0);
+    }
+}
@@ -0,0 +1,171 @@
+//! Undo for destructive bulk actions.
+//!
+//! Marking entries read in bulk and deleting feeds or categories record what
+//! they changed and hand back a token. Redeeming the token within
+//! `UNDO_WINDOW_MINUTES` reverts the action: marked entries become unread
+//! again, and deleted feeds or categories come back out of the trash.
+
+use chrono::Utc;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::models::{category, entry, feed, undo_action};
+
+/// How long an action can be undone
+pub const UNDO_WINDOW_MINUTES: i64 = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum UndoPayload {
+    MarkRead { entry_ids: Vec<i64> },
+    RestoreFeed { feed_id: i64 },
+    RestoreCategory { category_id: i64 },
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct UndoResult {
+    pub entries_unread: i64,
+    pub feeds_restored: i64,
+    pub categories_restored: i64,
+}
+
+/// Store an undo record and return its token. Expired records are dropped
+/// on the way.
+pub fn record(conn: &Connection, user_id: i64, payload: &UndoPayload) -> AppResult<String> {
+    undo_action::delete_expired(conn, window_start())?;
+    let json = serde_json::to_string(payload).map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(undo_action::create(conn, user_id, &json)?.token)
+}
+
+/// Record marked entries for undo, unless nothing was marked
+pub fn record_mark_read(
+    conn: &Connection,
+    user_id: i64,
+    entry_ids: Vec<i64>,
+) -> AppResult<Option<String>> {
+    if entry_ids.is_empty() {
+        return Ok(None);
+    }
+    record(conn, user_id, &UndoPayload::MarkRead { entry_ids }).map(Some)
+}
+
+fn window_start() -> chrono::DateTime<Utc> {
+    Utc::now() - chrono::Duration::minutes(UNDO_WINDOW_MINUTES)
+}
+
+/// Revert the action recorded under `token`. Each token works once.
+pub fn undo(conn: &Connection, user_id: i64, token: &str) -> AppResult<UndoResult> {
+    let tx = conn.unchecked_transaction()?;
+    let action = undo_action::take(&tx, user_id, token, window_start())?.ok_or_else(|| {
+        AppError::NotFound("Nothing to undo, or the undo window has passed".to_string())
+    })?;
+    let payload: UndoPayload = serde_json::from_str(&action.payload)
+        .map_err(|e| AppError::Internal(format!("Invalid undo record: {}", e)))?;
+
+    let mut result = UndoResult::default();
+    match payload {
+        UndoPayload::MarkRead { entry_ids } => {
+            result.entries_unread = entry::mark_unread_by_ids(&tx, user_id, &entry_ids)?;
+        }
+        UndoPayload::RestoreFeed { feed_id } => {
+            feed::restore_feed(&tx, feed_id, user_id)?;
+            result.feeds_restored = 1;
+        }
+        UndoPayload::RestoreCategory { category_id } => {
+            category::restore_category(&tx, category_id, user_id)?;
+            result.categories_restored = 1;
+        }
+    }
+    tx.commit()?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::user::{self, Role};
+
+    fn setup() -> (Connection, i64, i64) {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let user_id = user::create_user(&conn, "testuser", "hash123", Role::User)
+            .unwrap()
+            .id;
+        let category_id = category::create_category(&conn, user_id, "Tech")
+            .unwrap()
+            .id;
+        (conn, user_id, category_id)
+    }
+
+    fn add_feed(conn: &Connection, category_id: i64) -> feed::Feed {
+        let f = feed::create_feed(
+            conn,
+            category_id,
+            "https://example.com/feed.xml",
+            Some("Example"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        for guid in ["a", "b"] {
+            entry::upsert_entry(conn, f.id, guid, Some(guid), None, None, None, None, None)
+                .unwrap();
+        }
+        f
+    }
+
+    #[test]
+    fn test_undo_mark_read() {
+        let (conn, user_id, category_id) = setup();
+        add_feed(&conn, category_id);
+
+        let marked = entry::mark_all_read_by_user(&conn, user_id, None).unwrap();
+        let token = record_mark_read(&conn, user_id, marked).unwrap().unwrap();
+        assert_eq!(entry::count_unread_by_user(&conn, user_id).unwrap(), 0);
+
+        let result = undo(&conn, user_id, &token).unwrap();
+        assert_eq!(result.entries_unread, 2);
+        assert_eq!(entry::count_unread_by_user(&conn, user_id).unwrap(), 2);
+
+        // A token works once
+        assert!(undo(&conn, user_id, &token).is_err());
+        assert!(record_mark_read(&conn, user_id, Vec::new())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_undo_delete_category() {
+        let (conn, user_id, category_id) = setup();
+        let f = add_feed(&conn, category_id);
+        let starred = entry::find_by_guid_and_feed(&conn, "a", f.id)
+            .unwrap()
+            .unwrap();
+        entry::toggle_star(&conn, starred.id).unwrap();
+
+        category::delete_category(&conn, category_id, user_id).unwrap();
+        let token = record(
+            &conn,
+            user_id,
+            &UndoPayload::RestoreCategory { category_id },
+        )
+        .unwrap();
+        assert!(feed::list_by_user(&conn, user_id).unwrap().is_empty());
+
+        let result = undo(&conn, user_id, &token).unwrap();
+        assert_eq!(result.categories_restored, 1);
+
+        let restored = feed::list_by_user(&conn, user_id).unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].id, f.id);
+        let a = entry::find_by_guid_and_feed(&conn, "a", f.id)
+            .unwrap()
+            .unwrap();
+        assert!(a.starred_at.is_some());
+    }
+}
@@ -0,0 +1,245 @@
+//! Opt-in check for newer releases of rdrs.
+//!
+//! With `UPDATE_CHECK_ENABLED`, the latest release is looked up on GitHub at
+//! startup and every day after. Only the request itself is sent; the result
+//! is kept in memory and shown to admins, who are also notified once of each
+//! newer release. Privacy mode refuses the request.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use super::heartbeat::{Heartbeat, HEARTBEAT_INTERVAL};
+use super::http::{client_builder, send_with_retry, Outbound, RetryConfig, DEFAULT_TIMEOUT};
+use super::instance_settings::InstanceSettings;
+use crate::db::DbPool;
+use crate::models::notification;
+use crate::PKG_VERSION;
+
+/// GitHub API endpoint for the latest published release
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/henry40408/rdrs/releases/latest";
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+/// What the last check found
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateStatus {
+    /// Version of the latest release, without the leading `v`
+    pub latest_version: Option<String>,
+    pub release_url: Option<String>,
+    pub checked_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl UpdateStatus {
+    /// Whether the latest release is newer than the running build
+    pub fn update_available(&self) -> bool {
+        self.latest_version
+            .as_deref()
+            .is_some_and(|latest| is_newer(latest, PKG_VERSION))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    html_url: String,
+}
+
+/// The result of the last check, shared through `AppState`
+#[derive(Debug, Clone, Default)]
+pub struct UpdateChecker {
+    status: Arc<RwLock<UpdateStatus>>,
+}
+
+impl UpdateChecker {
+    pub fn status(&self) -> UpdateStatus {
+        self.status
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Record a check; a failed one keeps the release found before
+    fn record(&self, result: Result<Release, String>) {
+        let mut status = self.status.write().unwrap_or_else(|e| e.into_inner());
+        status.checked_at = Some(Utc::now());
+        match result {
+            Ok(release) => {
+                status.latest_version = Some(release.tag_name.trim_start_matches('v').to_string());
+                status.release_url = Some(release.html_url);
+                status.last_error = None;
+            }
+            Err(e) => status.last_error = Some(e),
+        }
+    }
+}
+
+/// `major.minor.patch` of a version such as `v1.2.3` or `1.2.3-rc.1`;
+/// missing parts count as 0
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.trim().trim_start_matches('v');
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((major, minor, patch))
+}
+
+/// Whether `latest` is a higher version than `current`. Versions that do not
+/// parse are never newer.
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+async fn fetch_latest(user_agent: &str) -> Result<Release, String> {
+    let client = client_builder(Outbound::UpdateCheck)
+        .map_err(|e| e.to_string())?
+        .timeout(DEFAULT_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = send_with_retry(&RetryConfig::default(), || {
+        client
+            .get(LATEST_RELEASE_URL)
+            .header("User-Agent", user_agent)
+            .header("Accept", "application/vnd.github+json")
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
+
+/// Look up the latest release and record the result
+pub async fn check(checker: &UpdateChecker, user_agent: &str) {
+    let result = fetch_latest(user_agent).await;
+    match &result {
+        Ok(release) => info!("Latest rdrs release is {}", release.tag_name),
+        Err(e) => warn!("Failed to check for rdrs updates: {}", e),
+    }
+    checker.record(result);
+}
+
+/// Tell every admin about a newer release, once per version
+async fn notify_admins(db: &DbPool, status: UpdateStatus) {
+    let Some(version) = status
+        .latest_version
+        .clone()
+        .filter(|_| status.update_available())
+    else {
+        return;
+    };
+    let result = db
+        .background(move |conn| {
+            let message = notification::Message {
+                title: format!("rdrs {} is available", version),
+                body: Some(format!("This server runs {}", PKG_VERSION)),
+                link: status.release_url,
+            };
+            notification::notify_admins(
+                conn,
+                notification::KIND_UPDATE_AVAILABLE,
+                &version,
+                &message,
+            )
+        })
+        .await;
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => error!("Failed to notify admins of the update: {}", e),
+        Err(e) => error!("Failed to access DB to notify admins of the update: {}", e),
+    }
+}
+
+/// Start the worker that checks for a newer release at startup and every
+/// day
+///
+/// # Arguments
+/// * `db` - Database pool, for notifying admins of a newer release
+/// * `checker` - Where the result of each check is kept
+/// * `settings` - Instance settings, for the user agent GitHub requires
+/// * `heartbeat` - Reported to while the worker is running
+/// * `cancel_token` - Token to signal graceful shutdown
+pub fn start_update_checker(
+    db: DbPool,
+    checker: UpdateChecker,
+    settings: watch::Receiver<Arc<InstanceSettings>>,
+    heartbeat: Heartbeat,
+    cancel_token: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Update checker started");
+
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        let mut beat = tokio::time::interval(HEARTBEAT_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    info!("Update checker stopping...");
+                    break;
+                }
+                _ = beat.tick() => {
+                    heartbeat.beat();
+                }
+                _ = interval.tick() => {
+                    let user_agent = settings.borrow().user_agent.clone();
+                    check(&checker, &user_agent).await;
+                    notify_admins(&db, checker.status()).await;
+                }
+            }
+        }
+
+        info!("Update checker stopped");
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("v0.2.0", "0.1.9"));
+        assert!(is_newer("1.0", "0.9.9"));
+        assert!(is_newer("0.1.10", "0.1.9"));
+        assert!(!is_newer("v0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0-rc.1", "0.1.0"));
+        assert!(!is_newer("0.0.9", "0.1.0"));
+        assert!(!is_newer("nightly", "0.1.0"));
+        assert!(!is_newer("1.2.3.4", "0.1.0"));
+    }
+
+    #[test]
+    fn test_failed_check_keeps_last_release() {
+        let checker = UpdateChecker::default();
+        assert!(!checker.status().update_available());
+
+        checker.record(Ok(Release {
+            tag_name: "v999.0.0".to_string(),
+            html_url: "https://github.com/henry40408/rdrs/releases/tag/v999.0.0".to_string(),
+        }));
+        checker.record(Err("HTTP 403 Forbidden".to_string()));
+
+        let status = checker.status();
+        assert_eq!(status.latest_version.as_deref(), Some("999.0.0"));
+        assert_eq!(status.last_error.as_deref(), Some("HTTP 403 Forbidden"));
+        assert!(status.update_available());
+    }
+}
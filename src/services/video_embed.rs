@@ -0,0 +1,187 @@
+//! Recognizes YouTube and Vimeo embeds in entry content, so the sanitizer
+//! can swap them for privacy-enhanced players or click-to-load facades.
+
+use url::Url;
+
+/// A video embedded from a known host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Video {
+    YouTube {
+        id: String,
+        /// Start offset in seconds
+        start: Option<u32>,
+    },
+    Vimeo {
+        id: String,
+        /// Access hash of an unlisted video
+        hash: Option<String>,
+    },
+}
+
+/// Video IDs and hashes end up in URLs and attributes, so only plain ones
+/// are accepted
+fn is_id(s: &str) -> bool {
+    !s.is_empty()
+        && s.len() <= 64
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+impl Video {
+    /// Parse the `src` of an embed iframe
+    pub fn from_embed_url(src: &str) -> Option<Self> {
+        let url = Url::parse(src).ok()?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return None;
+        }
+        let mut segments = url.path_segments()?;
+        let query = |name: &str| {
+            url.query_pairs()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.into_owned())
+        };
+
+        match url.host_str()? {
+            "youtube.com"
+            | "www.youtube.com"
+            | "m.youtube.com"
+            | "youtube-nocookie.com"
+            | "www.youtube-nocookie.com" => {
+                let (Some("embed"), Some(id)) = (segments.next(), segments.next()) else {
+                    return None;
+                };
+                is_id(id).then(|| Video::YouTube {
+                    id: id.to_string(),
+                    start: query("start").and_then(|s| s.parse().ok()),
+                })
+            }
+            "player.vimeo.com" => {
+                let (Some("video"), Some(id)) = (segments.next(), segments.next()) else {
+                    return None;
+                };
+                (!id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())).then(|| Video::Vimeo {
+                    id: id.to_string(),
+                    hash: query("h").filter(|h| is_id(h)),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn provider(&self) -> &'static str {
+        match self {
+            Video::YouTube { .. } => "YouTube",
+            Video::Vimeo { .. } => "Vimeo",
+        }
+    }
+
+    /// Player URL on the host's privacy-enhanced mode: YouTube's
+    /// cookieless domain, or Vimeo with Do Not Track
+    pub fn embed_url(&self) -> String {
+        match self {
+            Video::YouTube { id, start: None } => {
+                format!("https://www.youtube-nocookie.com/embed/{}", id)
+            }
+            Video::YouTube {
+                id,
+                start: Some(start),
+            } => format!(
+                "https://www.youtube-nocookie.com/embed/{}?start={}",
+                id, start
+            ),
+            Video::Vimeo { id, hash: None } => {
+                format!("https://player.vimeo.com/video/{}?dnt=1", id)
+            }
+            Video::Vimeo {
+                id,
+                hash: Some(hash),
+            } => format!("https://player.vimeo.com/video/{}?h={}&dnt=1", id, hash),
+        }
+    }
+
+    /// Page of the video on its host, for readers without JavaScript
+    pub fn watch_url(&self) -> String {
+        match self {
+            Video::YouTube { id, start: None } => format!("https://www.youtube.com/watch?v={}", id),
+            Video::YouTube {
+                id,
+                start: Some(start),
+            } => format!("https://www.youtube.com/watch?v={}&t={}", id, start),
+            Video::Vimeo { id, hash: None } => format!("https://vimeo.com/{}", id),
+            Video::Vimeo {
+                id,
+                hash: Some(hash),
+            } => format!("https://vimeo.com/{}/{}", id, hash),
+        }
+    }
+
+    /// Still image for a facade. Vimeo has none without an API call.
+    pub fn thumbnail_url(&self) -> Option<String> {
+        match self {
+            Video::YouTube { id, .. } => {
+                Some(format!("https://i.ytimg.com/vi/{}/hqdefault.jpg", id))
+            }
+            Video::Vimeo { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_youtube() {
+        let src = "https://www.youtube.com/embed/dQw4w9WgXcQ?start=42&rel=0";
+        let video = Video::from_embed_url(src);
+        assert_eq!(
+            video,
+            Some(Video::YouTube {
+                id: "dQw4w9WgXcQ".to_string(),
+                start: Some(42)
+            })
+        );
+        let video = video.unwrap();
+        assert_eq!(
+            video.embed_url(),
+            "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ?start=42"
+        );
+        assert_eq!(
+            video.watch_url(),
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42"
+        );
+        assert_eq!(
+            video.thumbnail_url().as_deref(),
+            Some("https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg")
+        );
+
+        let nocookie = Video::from_embed_url("https://www.youtube-nocookie.com/embed/abc_-1");
+        assert!(matches!(nocookie, Some(Video::YouTube { .. })));
+    }
+
+    #[test]
+    fn test_parse_vimeo() {
+        let src = "https://player.vimeo.com/video/76979871?h=8272103f6e";
+        let video = Video::from_embed_url(src).unwrap();
+        assert_eq!(
+            video.embed_url(),
+            "https://player.vimeo.com/video/76979871?h=8272103f6e&dnt=1"
+        );
+        assert_eq!(video.watch_url(), "https://vimeo.com/76979871/8272103f6e");
+        assert_eq!(video.thumbnail_url(), None);
+    }
+
+    #[test]
+    fn test_parse_unknown_embeds() {
+        for src in [
+            "https://www.youtube.com/watch?v=abc",
+            "https://www.youtube.com/embed/",
+            "https://www.youtube.com/embed/a%22b",
+            "https://player.vimeo.com/video/abc",
+            "https://evil.example.com/embed/abc",
+            "javascript:alert(1)",
+        ] {
+            assert_eq!(Video::from_embed_url(src), None, "{}", src);
+        }
+    }
+}
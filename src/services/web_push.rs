@@ -0,0 +1,378 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::derive::Deriver;
+use openssl::ec::{EcGroup, EcKey, EcPoint, PointConversionForm};
+use openssl::ecdsa::EcdsaSig;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+use openssl::symm::{encrypt_aead, Cipher};
+use rand::RngCore;
+use reqwest::Client;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::error::{AppError, AppResult};
+use crate::services::http::{client_builder, Outbound, EXTERNAL_API_TIMEOUT};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Record size advertised in the aes128gcm header
+const RECORD_SIZE: u32 = 4096;
+
+/// How long the push service should keep an undelivered message (1 day)
+const PUSH_TTL_SECS: u32 = 86400;
+
+/// VAPID application server keys (RFC 8292)
+pub struct VapidKeys {
+    key: EcKey<Private>,
+    public_key: Vec<u8>,
+    subject: String,
+}
+
+impl std::fmt::Debug for VapidKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VapidKeys")
+            .field("public_key", &self.public_key_base64())
+            .field("subject", &self.subject)
+            .finish()
+    }
+}
+
+fn openssl_err(e: openssl::error::ErrorStack) -> AppError {
+    AppError::Internal(format!("Crypto error: {}", e))
+}
+
+impl VapidKeys {
+    /// Load keys from a base64url-encoded raw P-256 private scalar, the format
+    /// printed by common `generate-vapid-keys` tools.
+    pub fn from_private_key(private_key: &str, subject: &str) -> AppResult<Self> {
+        let raw = URL_SAFE_NO_PAD
+            .decode(private_key.trim().trim_end_matches('='))
+            .map_err(|_| AppError::Validation("Invalid VAPID private key".to_string()))?;
+        if raw.len() != 32 {
+            return Err(AppError::Validation(
+                "VAPID private key must be 32 bytes".to_string(),
+            ));
+        }
+
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).map_err(openssl_err)?;
+        let mut ctx = BigNumContext::new().map_err(openssl_err)?;
+        let d = BigNum::from_slice(&raw).map_err(openssl_err)?;
+        let mut point = EcPoint::new(&group).map_err(openssl_err)?;
+        point
+            .mul_generator2(&group, &d, &mut ctx)
+            .map_err(openssl_err)?;
+        let key = EcKey::from_private_components(&group, &d, &point).map_err(openssl_err)?;
+        let public_key = point
+            .to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut ctx)
+            .map_err(openssl_err)?;
+
+        Ok(Self {
+            key,
+            public_key,
+            subject: subject.to_string(),
+        })
+    }
+
+    /// Public key to pass as `applicationServerKey` when subscribing
+    pub fn public_key_base64(&self) -> String {
+        URL_SAFE_NO_PAD.encode(&self.public_key)
+    }
+
+    /// Build the `Authorization` header value for a push endpoint
+    fn authorization(&self, endpoint: &str) -> AppResult<String> {
+        let url = Url::parse(endpoint).map_err(|_| AppError::InvalidUrl)?;
+        let audience = url.origin().ascii_serialization();
+        let exp = Utc::now().timestamp() + 12 * 3600;
+
+        let header = URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = serde_json::json!({
+            "aud": audience,
+            "exp": exp,
+            "sub": self.subject,
+        });
+        let claims = URL_SAFE_NO_PAD.encode(claims.to_string());
+        let signing_input = format!("{}.{}", header, claims);
+
+        let digest = Sha256::digest(signing_input.as_bytes());
+        let sig = EcdsaSig::sign(&digest, &self.key).map_err(openssl_err)?;
+        let mut raw_sig = sig.r().to_vec_padded(32).map_err(openssl_err)?;
+        raw_sig.extend(sig.s().to_vec_padded(32).map_err(openssl_err)?);
+
+        Ok(format!(
+            "vapid t={}.{}, k={}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(raw_sig),
+            self.public_key_base64()
+        ))
+    }
+}
+
+fn hkdf_extract(salt: &[u8], ikm: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(salt).expect("HMAC can take key of any size");
+    mac.update(ikm);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// HKDF-Expand for outputs up to one hash length
+fn hkdf_expand(prk: &[u8], info: &[u8], len: usize) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(prk).expect("HMAC can take key of any size");
+    mac.update(info);
+    mac.update(&[1u8]);
+    mac.finalize().into_bytes()[..len].to_vec()
+}
+
+/// Encrypt a payload for a subscription using the aes128gcm content coding (RFC 8291)
+pub fn encrypt_payload(p256dh: &str, auth: &str, payload: &[u8]) -> AppResult<Vec<u8>> {
+    let ua_public = URL_SAFE_NO_PAD
+        .decode(p256dh.trim_end_matches('='))
+        .map_err(|_| AppError::Validation("Invalid subscription key".to_string()))?;
+    let auth_secret = URL_SAFE_NO_PAD
+        .decode(auth.trim_end_matches('='))
+        .map_err(|_| AppError::Validation("Invalid subscription auth secret".to_string()))?;
+
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).map_err(openssl_err)?;
+    let mut ctx = BigNumContext::new().map_err(openssl_err)?;
+
+    let peer_point = EcPoint::from_bytes(&group, &ua_public, &mut ctx)
+        .map_err(|_| AppError::Validation("Invalid subscription key".to_string()))?;
+    let peer_key = EcKey::from_public_key(&group, &peer_point).map_err(openssl_err)?;
+    let peer_pkey = PKey::from_ec_key(peer_key).map_err(openssl_err)?;
+
+    let local_key = EcKey::generate(&group).map_err(openssl_err)?;
+    let as_public = local_key
+        .public_key()
+        .to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut ctx)
+        .map_err(openssl_err)?;
+    let local_pkey = PKey::from_ec_key(local_key).map_err(openssl_err)?;
+
+    let mut deriver = Deriver::new(&local_pkey).map_err(openssl_err)?;
+    deriver.set_peer(&peer_pkey).map_err(openssl_err)?;
+    let ecdh_secret = deriver.derive_to_vec().map_err(openssl_err)?;
+
+    // IKM = HKDF(auth_secret, ecdh_secret, "WebPush: info" || 0x00 || ua_public || as_public)
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(&ua_public);
+    key_info.extend_from_slice(&as_public);
+    let prk_key = hkdf_extract(&auth_secret, &ecdh_secret);
+    let ikm = hkdf_expand(&prk_key, &key_info, 32);
+
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let prk = hkdf_extract(&salt, &ikm);
+    let cek = hkdf_expand(&prk, b"Content-Encoding: aes128gcm\0", 16);
+    let nonce = hkdf_expand(&prk, b"Content-Encoding: nonce\0", 12);
+
+    // Single record, terminated by the last-record delimiter
+    let mut plaintext = payload.to_vec();
+    plaintext.push(2);
+
+    let mut tag = [0u8; 16];
+    let ciphertext = encrypt_aead(
+        Cipher::aes_128_gcm(),
+        &cek,
+        Some(&nonce),
+        &[],
+        &plaintext,
+        &mut tag,
+    )
+    .map_err(openssl_err)?;
+
+    let mut body = Vec::with_capacity(16 + 4 + 1 + as_public.len() + ciphertext.len() + 16);
+    body.extend_from_slice(&salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(as_public.len() as u8);
+    body.extend_from_slice(&as_public);
+    body.extend_from_slice(&ciphertext);
+    body.extend_from_slice(&tag);
+    Ok(body)
+}
+
+/// Notification payload understood by the service worker
+#[derive(Debug, Clone, Serialize)]
+pub struct PushMessage {
+    pub title: String,
+    pub body: String,
+    pub url: String,
+}
+
+/// Result of delivering a push message
+#[derive(Debug, PartialEq)]
+pub enum PushOutcome {
+    Delivered,
+    /// The subscription has expired or been revoked and should be removed
+    Gone,
+    Failed(String),
+}
+
+/// Client for push deliveries, built once and shared by every send
+pub fn push_client() -> AppResult<Client> {
+    client_builder(Outbound::Configured)?
+        .timeout(EXTERNAL_API_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to build HTTP client: {}", e)))
+}
+
+pub async fn send_push(
+    client: &Client,
+    vapid: &VapidKeys,
+    endpoint: &str,
+    p256dh: &str,
+    auth: &str,
+    message: &PushMessage,
+) -> AppResult<PushOutcome> {
+    let payload = serde_json::to_vec(message)
+        .map_err(|e| AppError::Internal(format!("Failed to encode push message: {}", e)))?;
+    let body = encrypt_payload(p256dh, auth, &payload)?;
+    let authorization = vapid.authorization(endpoint)?;
+
+    let response = match client
+        .post(endpoint)
+        .header("Authorization", authorization)
+        .header("Content-Encoding", "aes128gcm")
+        .header("Content-Type", "application/octet-stream")
+        .header("TTL", PUSH_TTL_SECS.to_string())
+        .body(body)
+        .send()
+        .await
+    {
+        Ok(resp) => resp,
+        Err(e) => return Ok(PushOutcome::Failed(e.to_string())),
+    };
+
+    let status = response.status();
+    if status.is_success() {
+        Ok(PushOutcome::Delivered)
+    } else if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::GONE {
+        Ok(PushOutcome::Gone)
+    } else {
+        Ok(PushOutcome::Failed(format!("HTTP {}", status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    // Private scalar 1 is valid for tests; the public key is the curve generator
+    const TEST_PRIVATE_KEY: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAE";
+
+    fn client_keys() -> (String, String) {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let mut ctx = BigNumContext::new().unwrap();
+        let key = EcKey::generate(&group).unwrap();
+        let public = key
+            .public_key()
+            .to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut ctx)
+            .unwrap();
+        (
+            URL_SAFE_NO_PAD.encode(public),
+            URL_SAFE_NO_PAD.encode([7u8; 16]),
+        )
+    }
+
+    #[test]
+    fn test_vapid_keys_from_private_key() {
+        let keys =
+            VapidKeys::from_private_key(TEST_PRIVATE_KEY, "mailto:test@example.com").unwrap();
+        let public = URL_SAFE_NO_PAD.decode(keys.public_key_base64()).unwrap();
+        assert_eq!(public.len(), 65);
+        assert_eq!(public[0], 0x04);
+    }
+
+    #[test]
+    fn test_vapid_keys_invalid() {
+        assert!(VapidKeys::from_private_key("not base64!", "mailto:a@b.c").is_err());
+        assert!(VapidKeys::from_private_key("AAAA", "mailto:a@b.c").is_err());
+    }
+
+    #[test]
+    fn test_authorization_header_format() {
+        let keys =
+            VapidKeys::from_private_key(TEST_PRIVATE_KEY, "mailto:test@example.com").unwrap();
+        let value = keys
+            .authorization("https://push.example.com/send/abc")
+            .unwrap();
+        assert!(value.starts_with("vapid t="));
+        assert!(value.contains(&format!("k={}", keys.public_key_base64())));
+
+        let token = value
+            .trim_start_matches("vapid t=")
+            .split(',')
+            .next()
+            .unwrap();
+        assert_eq!(token.split('.').count(), 3);
+    }
+
+    #[test]
+    fn test_encrypt_payload_layout() {
+        let (p256dh, auth) = client_keys();
+        let body = encrypt_payload(&p256dh, &auth, b"hello").unwrap();
+
+        // salt(16) + rs(4) + idlen(1) + keyid(65) + ciphertext(5 + 1) + tag(16)
+        assert_eq!(body.len(), 16 + 4 + 1 + 65 + 6 + 16);
+        assert_eq!(&body[16..20], &RECORD_SIZE.to_be_bytes());
+        assert_eq!(body[20], 65);
+    }
+
+    #[test]
+    fn test_encrypt_payload_invalid_key() {
+        let (_, auth) = client_keys();
+        assert!(encrypt_payload("AAAA", &auth, b"hello").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_push_delivered_and_gone() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/ok"))
+            .and(header("Content-Encoding", "aes128gcm"))
+            .respond_with(ResponseTemplate::new(201))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/gone"))
+            .respond_with(ResponseTemplate::new(410))
+            .mount(&server)
+            .await;
+
+        let keys =
+            VapidKeys::from_private_key(TEST_PRIVATE_KEY, "mailto:test@example.com").unwrap();
+        let (p256dh, auth) = client_keys();
+        let client = push_client().unwrap();
+        let message = PushMessage {
+            title: "Feed".to_string(),
+            body: "Entry".to_string(),
+            url: "/entries/1".to_string(),
+        };
+
+        let outcome = send_push(
+            &client,
+            &keys,
+            &format!("{}/ok", server.uri()),
+            &p256dh,
+            &auth,
+            &message,
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome, PushOutcome::Delivered);
+
+        let outcome = send_push(
+            &client,
+            &keys,
+            &format!("{}/gone", server.uri()),
+            &p256dh,
+            &auth,
+            &message,
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome, PushOutcome::Gone);
+    }
+}
@@ -0,0 +1,165 @@
+//! Per-user time zones. Timestamps are stored and served in UTC; a user's
+//! zone is applied when rendering pages, when working out where "Today"
+//! starts, and to entry timestamps in API responses that ask for `?tz=local`.
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize, Serializer};
+
+pub use chrono_tz::Tz;
+
+/// Zone of the timestamps in an API response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampZone {
+    #[default]
+    Utc,
+    /// The user's configured zone, or UTC if none is set
+    Local,
+}
+
+/// Parse an IANA zone name such as `Asia/Taipei`
+pub fn parse(name: &str) -> Option<Tz> {
+    name.parse().ok()
+}
+
+/// Every supported zone name, for pickers
+pub fn names() -> impl Iterator<Item = &'static str> {
+    chrono_tz::TZ_VARIANTS.iter().map(|tz| tz.name())
+}
+
+/// `dt` as wall-clock time in `tz`, or UTC without one
+pub fn local(dt: DateTime<Utc>, tz: Option<Tz>) -> DateTime<FixedOffset> {
+    match tz {
+        Some(tz) => dt.with_timezone(&tz).fixed_offset(),
+        None => dt.fixed_offset(),
+    }
+}
+
+/// The most recent local midnight in `tz`, as a UTC instant
+pub fn start_of_day(now: DateTime<Utc>, tz: Tz) -> DateTime<Utc> {
+    let midnight = now.with_timezone(&tz).date_naive().and_time(NaiveTime::MIN);
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        // Midnight can fall in a DST gap; the day then starts an hour later
+        .or_else(|| {
+            tz.from_local_datetime(&(midnight + Duration::hours(1)))
+                .earliest()
+        })
+        .map_or(now, |dt| dt.with_timezone(&Utc))
+}
+
+tokio::task_local! {
+    static DISPLAY_ZONE: Tz;
+}
+
+/// Run `f` with entry timestamps serialized in `tz`
+pub async fn scope<F: std::future::Future>(tz: Tz, f: F) -> F::Output {
+    DISPLAY_ZONE.scope(tz, f).await
+}
+
+/// Serialize a timestamp in the zone set by `scope`, in UTC outside of one
+pub fn serialize<S: Serializer>(dt: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    match DISPLAY_ZONE.try_with(|tz| *tz) {
+        Ok(tz) => dt.with_timezone(&tz).to_rfc3339().serialize(serializer),
+        Err(_) => dt.serialize(serializer),
+    }
+}
+
+/// `serialize` for optional timestamps
+pub fn serialize_option<S: Serializer>(
+    dt: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match dt {
+        Some(dt) => serializer.serialize_some(&Zoned(dt)),
+        None => serializer.serialize_none(),
+    }
+}
+
+struct Zoned<'a>(&'a DateTime<Utc>);
+
+impl Serialize for Zoned<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(self.0, serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[derive(Serialize)]
+    struct Stamped {
+        #[serde(serialize_with = "serialize")]
+        at: DateTime<Utc>,
+        #[serde(serialize_with = "serialize_option")]
+        maybe: Option<DateTime<Utc>>,
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("Asia/Taipei"), Some(Tz::Asia__Taipei));
+        assert_eq!(parse("Mars/Olympus_Mons"), None);
+        assert!(names().any(|name| name == "Europe/Berlin"));
+    }
+
+    #[test]
+    fn test_local() {
+        let dt = utc("2024-03-04T20:30:00Z");
+        assert_eq!(local(dt, None).to_rfc3339(), "2024-03-04T20:30:00+00:00");
+        assert_eq!(
+            local(dt, parse("Asia/Taipei")).to_rfc3339(),
+            "2024-03-05T04:30:00+08:00"
+        );
+    }
+
+    #[test]
+    fn test_start_of_day() {
+        let taipei = Tz::Asia__Taipei;
+        assert_eq!(
+            start_of_day(utc("2024-03-04T20:30:00Z"), taipei),
+            utc("2024-03-04T16:00:00Z")
+        );
+        assert_eq!(
+            start_of_day(utc("2024-03-04T10:00:00Z"), taipei),
+            utc("2024-03-03T16:00:00Z")
+        );
+
+        // Follows daylight saving time: New York is UTC-4 in July
+        assert_eq!(
+            start_of_day(utc("2024-07-01T12:00:00Z"), Tz::America__New_York),
+            utc("2024-07-01T04:00:00Z")
+        );
+
+        // Santiago skipped midnight on 2024-09-08, so that day began at 01:00
+        assert_eq!(
+            start_of_day(utc("2024-09-08T12:00:00Z"), Tz::America__Santiago),
+            utc("2024-09-08T04:00:00Z")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_serialize_in_scope() {
+        let stamped = Stamped {
+            at: utc("2024-03-04T20:30:00Z"),
+            maybe: None,
+        };
+        let json = serde_json::to_value(&stamped).unwrap();
+        assert_eq!(json["at"], "2024-03-04T20:30:00Z");
+
+        let stamped = Stamped {
+            maybe: Some(stamped.at),
+            ..stamped
+        };
+        let json = scope(Tz::Asia__Taipei, async {
+            serde_json::to_value(&stamped).unwrap()
+        })
+        .await;
+        assert_eq!(json["at"], "2024-03-05T04:30:00+08:00");
+        assert_eq!(json["maybe"], "2024-03-05T04:30:00+08:00");
+    }
+}
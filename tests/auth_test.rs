@@ -10,15 +10,20 @@ fn create_test_server(config: Config) -> TestServer {
 
     let webauthn = auth::create_webauthn(&config).unwrap();
     let summary_cache = services::create_summary_cache(100, 24);
-    let (summary_tx, _summary_rx) = services::create_summary_channel(10);
 
     let (db, _handle) = DbPool::new(conn);
+    let settings = services::LiveSettings::from_config(&config);
     let state = AppState {
         db,
         config: Arc::new(config),
         webauthn: Arc::new(webauthn),
         summary_cache,
-        summary_tx,
+        jobs: services::JobQueue::default(),
+        heartbeats: services::Heartbeats::default(),
+        refreshes: services::RefreshRegistry::default(),
+        blocklist: services::Blocklist::default(),
+        updates: services::UpdateChecker::default(),
+        settings,
     };
 
     let app = create_router(state);
@@ -35,10 +40,27 @@ fn default_test_config() -> Config {
         multi_user_enabled: true,
         image_proxy_secret: vec![0u8; 32],
         image_proxy_secret_generated: false,
+        image_proxy_previous_secrets: vec![],
         user_agent: "RDRS-Test/1.0".to_string(),
         webauthn_rp_id: "localhost".to_string(),
         webauthn_rp_origin: "http://localhost:3000".to_string(),
         webauthn_rp_name: "rdrs-test".to_string(),
+        vapid_private_key: None,
+        vapid_subject: "mailto:admin@localhost".to_string(),
+        tls_cert_path: None,
+        tls_key_path: None,
+        unix_socket_path: None,
+        reuse_port: false,
+        shutdown_drain_timeout_secs: 30,
+        max_body_bytes: 2 * 1024 * 1024,
+        max_import_bytes: 256 * 1024 * 1024,
+        log_json: false,
+        session_ttl_secs: 7 * 24 * 3600,
+        session_short_ttl_secs: 2 * 3600,
+        feed_cookie_secret: None,
+        privacy_mode: false,
+        update_check_enabled: false,
+        db_reader_count: 0,
     }
 }
 
@@ -159,6 +181,122 @@ async fn test_register_multi_user_disabled() {
     response.assert_status(StatusCode::FORBIDDEN);
 }
 
+#[tokio::test]
+async fn test_register_with_invite() {
+    let config = Config {
+        signup_enabled: true,
+        multi_user_enabled: false,
+        ..default_test_config()
+    };
+    let server = create_test_server(config);
+
+    server
+        .post("/api/register")
+        .json(&json!({
+            "username": "admin",
+            "password": "password123"
+        }))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    server
+        .post("/api/session")
+        .json(&json!({
+            "username": "admin",
+            "password": "password123"
+        }))
+        .await
+        .assert_status_ok();
+
+    let response = server
+        .post("/api/admin/invites")
+        .json(&json!({ "expires_in_hours": 24 }))
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    let invite: serde_json::Value = response.json();
+    let token = invite["token"].as_str().unwrap().to_string();
+
+    let response = server.get(&format!("/register?invite={}", token)).await;
+    response.assert_status_ok();
+    assert!(response
+        .text()
+        .contains(&format!("data-invite=\"{}\"", token)));
+
+    let response = server
+        .post("/api/register")
+        .json(&json!({
+            "username": "user1",
+            "password": "password123",
+            "invite": "not-a-token"
+        }))
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+
+    let response = server
+        .post("/api/register")
+        .json(&json!({
+            "username": "user1",
+            "password": "password123",
+            "invite": token
+        }))
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["role"], "user");
+
+    // Invites are single-use
+    let response = server
+        .post("/api/register")
+        .json(&json!({
+            "username": "user2",
+            "password": "password123",
+            "invite": token
+        }))
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+
+    let response = server.get("/api/admin/invites").await;
+    response.assert_status_ok();
+    let invites: Vec<serde_json::Value> = response.json();
+    assert_eq!(invites.len(), 1);
+    assert!(invites[0]["used_at"].is_string());
+
+    let id = invites[0]["id"].as_i64().unwrap();
+    server
+        .delete(&format!("/api/admin/invites/{}", id))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn test_create_invite_validation() {
+    let server = create_test_server(default_test_config());
+
+    server
+        .post("/api/register")
+        .json(&json!({
+            "username": "admin",
+            "password": "password123"
+        }))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    server
+        .post("/api/session")
+        .json(&json!({
+            "username": "admin",
+            "password": "password123"
+        }))
+        .await
+        .assert_status_ok();
+
+    let response = server
+        .post("/api/admin/invites")
+        .json(&json!({ "expires_in_hours": 0 }))
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test]
 async fn test_login_success() {
     let server = create_test_server(default_test_config());
@@ -224,6 +362,154 @@ async fn test_login_nonexistent_user() {
     response.assert_status_unauthorized();
 }
 
+#[tokio::test]
+async fn test_login_lockout_and_admin_unlock() {
+    let server = create_test_server(default_test_config());
+
+    for username in ["admin", "user1"] {
+        server
+            .post("/api/register")
+            .json(&json!({
+                "username": username,
+                "password": "password123"
+            }))
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    for _ in 1..5 {
+        server
+            .post("/api/session")
+            .json(&json!({
+                "username": "user1",
+                "password": "wrongpassword"
+            }))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+    server
+        .post("/api/session")
+        .json(&json!({
+            "username": "user1",
+            "password": "wrongpassword"
+        }))
+        .await
+        .assert_status(StatusCode::LOCKED);
+
+    // The correct password is refused while locked
+    server
+        .post("/api/session")
+        .json(&json!({
+            "username": "user1",
+            "password": "password123"
+        }))
+        .await
+        .assert_status(StatusCode::LOCKED);
+
+    server
+        .post("/api/session")
+        .json(&json!({
+            "username": "admin",
+            "password": "password123"
+        }))
+        .await
+        .assert_status_ok();
+
+    let users: Vec<serde_json::Value> = server.get("/api/admin/users").await.json();
+    let user1 = users.iter().find(|u| u["username"] == "user1").unwrap();
+    assert!(user1["locked_until"].is_string());
+    let user1_id = user1["id"].as_i64().unwrap();
+
+    server
+        .post(&format!("/api/admin/users/{}/unlock", user1_id))
+        .await
+        .assert_status_ok();
+
+    server
+        .post("/api/session")
+        .json(&json!({
+            "username": "user1",
+            "password": "password123"
+        }))
+        .await
+        .assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_admin_create_user_with_temporary_password() {
+    let server = create_test_server(default_test_config());
+
+    server
+        .post("/api/register")
+        .json(&json!({
+            "username": "admin",
+            "password": "password123"
+        }))
+        .await
+        .assert_status(StatusCode::CREATED);
+    server
+        .post("/api/session")
+        .json(&json!({
+            "username": "admin",
+            "password": "password123"
+        }))
+        .await
+        .assert_status_ok();
+
+    let response = server
+        .post("/api/admin/users")
+        .json(&json!({ "username": "newbie" }))
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["username"], "newbie");
+    assert_eq!(body["role"], "user");
+    assert_eq!(body["must_change_password"], true);
+    let temporary_password = body["temporary_password"].as_str().unwrap().to_string();
+
+    server
+        .post("/api/admin/users")
+        .json(&json!({ "username": "newbie" }))
+        .await
+        .assert_status(StatusCode::CONFLICT);
+
+    server.delete("/api/session").await.assert_status_ok();
+    server
+        .post("/api/session")
+        .json(&json!({
+            "username": "newbie",
+            "password": temporary_password
+        }))
+        .await
+        .assert_status_ok();
+
+    // Everything but the account itself is off limits until the password changes
+    let response = server.get("/api/categories").await;
+    response.assert_status(StatusCode::FORBIDDEN);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["error"], "Password change required");
+    server.get("/api/user").await.assert_status_ok();
+
+    server
+        .put("/api/user/password")
+        .json(&json!({
+            "current_password": temporary_password,
+            "new_password": "newpassword456"
+        }))
+        .await
+        .assert_status_ok();
+
+    server
+        .post("/api/session")
+        .json(&json!({
+            "username": "newbie",
+            "password": "newpassword456"
+        }))
+        .await
+        .assert_status_ok();
+    server.get("/api/categories").await.assert_status_ok();
+}
+
 #[tokio::test]
 async fn test_get_current_user() {
     let server = create_test_server(default_test_config());
@@ -435,6 +721,155 @@ async fn test_admin_list_users_forbidden() {
     response.assert_status_forbidden();
 }
 
+#[tokio::test]
+async fn test_admin_blocklist() {
+    let server = create_test_server(default_test_config());
+
+    server
+        .post("/api/register")
+        .json(&json!({
+            "username": "admin",
+            "password": "password123"
+        }))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    server
+        .post("/api/session")
+        .json(&json!({
+            "username": "admin",
+            "password": "password123"
+        }))
+        .await
+        .assert_status_ok();
+
+    let response = server.get("/api/admin/blocklist").await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["entries"], json!([]));
+    assert!(body["import_url"].is_null());
+
+    let response = server
+        .put("/api/admin/blocklist")
+        .json(&json!({
+            "entries": ["*.Ads.example.com", "", "https://example.org/track/", "ads.example.com"]
+        }))
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(
+        body["entries"],
+        json!(["ads.example.com", "example.org/track/"])
+    );
+    assert_eq!(body["imported_count"], 0);
+
+    let body: serde_json::Value = server.get("/api/admin/blocklist").await.json();
+    assert_eq!(
+        body["entries"],
+        json!(["ads.example.com", "example.org/track/"])
+    );
+
+    server
+        .put("/api/admin/blocklist")
+        .json(&json!({ "entries": ["not a host"] }))
+        .await
+        .assert_status_bad_request();
+
+    server
+        .put("/api/admin/blocklist")
+        .json(&json!({ "entries": [], "import_url": "ftp://example.com/hosts" }))
+        .await
+        .assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_admin_settings() {
+    let server = create_test_server(default_test_config());
+
+    server
+        .post("/api/register")
+        .json(&json!({
+            "username": "admin",
+            "password": "password123"
+        }))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    server
+        .post("/api/session")
+        .json(&json!({
+            "username": "admin",
+            "password": "password123"
+        }))
+        .await
+        .assert_status_ok();
+
+    let response = server.get("/api/admin/settings").await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["signup_enabled"], true);
+    assert_eq!(body["sync_interval_hours"], 1);
+    assert!(body["saved"]["signup_enabled"].is_null());
+
+    let response = server
+        .put("/api/admin/settings")
+        .json(&json!({ "signup_enabled": false, "trash_retention_days": 7 }))
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["signup_enabled"], false);
+    assert_eq!(body["trash_retention_days"], 7);
+    assert_eq!(body["saved"]["trash_retention_days"], 7);
+
+    // Takes effect without a restart
+    server
+        .post("/api/register")
+        .json(&json!({
+            "username": "user1",
+            "password": "password123"
+        }))
+        .await
+        .assert_status_forbidden();
+
+    server
+        .put("/api/admin/settings")
+        .json(&json!({ "sync_interval_hours": 0 }))
+        .await
+        .assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_admin_version() {
+    let server = create_test_server(default_test_config());
+
+    server
+        .post("/api/register")
+        .json(&json!({
+            "username": "admin",
+            "password": "password123"
+        }))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    server
+        .post("/api/session")
+        .json(&json!({
+            "username": "admin",
+            "password": "password123"
+        }))
+        .await
+        .assert_status_ok();
+
+    let response = server.get("/api/admin/version").await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["current_version"], rdrs::PKG_VERSION);
+    assert_eq!(body["check_enabled"], false);
+    assert_eq!(body["update_available"], false);
+    assert!(body["latest_version"].is_null());
+    assert!(body["checked_at"].is_null());
+}
+
 #[tokio::test]
 async fn test_admin_disable_user() {
     let server = create_test_server(default_test_config());
@@ -748,6 +1183,84 @@ async fn test_unmasquerade_not_masquerading() {
     response.assert_status_bad_request();
 }
 
+#[tokio::test]
+async fn test_masquerade_audit() {
+    let server = create_test_server(default_test_config());
+
+    for username in ["admin", "user1"] {
+        server
+            .post("/api/register")
+            .json(&json!({
+                "username": username,
+                "password": "password123"
+            }))
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    server
+        .post("/api/session")
+        .json(&json!({
+            "username": "admin",
+            "password": "password123"
+        }))
+        .await
+        .assert_status_ok();
+
+    let response = server.get("/api/admin/masquerade/status").await;
+    response.assert_status_ok();
+    assert!(response.headers().get("x-masquerading-as").is_none());
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["masquerading"], false);
+
+    server
+        .post("/api/admin/masquerade/2")
+        .await
+        .assert_status_ok();
+
+    // Reads are labelled but not recorded; writes are both
+    let response = server.get("/api/admin/masquerade/status").await;
+    response.assert_status_ok();
+    assert_eq!(response.header("x-masquerading-as"), "user1");
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["masquerading"], true);
+    assert_eq!(body["user"]["username"], "user1");
+    assert_eq!(body["original_user"]["username"], "admin");
+
+    let response = server
+        .post("/api/categories")
+        .json(&json!({ "name": "News" }))
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    assert_eq!(response.header("x-masquerading-as"), "user1");
+
+    server
+        .post("/api/admin/unmasquerade")
+        .await
+        .assert_status_ok();
+
+    let response = server.get("/api/admin/masquerade/audit").await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    let records = body.as_array().unwrap();
+    let paths: Vec<_> = records
+        .iter()
+        .map(|r| r["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            "/api/admin/unmasquerade",
+            "/api/categories",
+            "/api/admin/masquerade/2"
+        ]
+    );
+    assert_eq!(records[1]["method"], "POST");
+    assert_eq!(records[1]["status"], 201);
+    assert_eq!(records[1]["admin_username"], "admin");
+    assert_eq!(records[1]["username"], "user1");
+}
+
 #[tokio::test]
 async fn test_login_page() {
     let server = create_test_server(default_test_config());
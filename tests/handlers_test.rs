@@ -9,7 +9,8 @@
 
 use std::sync::Arc;
 
-use axum::http::StatusCode;
+use axum::http::{header, HeaderValue, StatusCode};
+use axum_test::multipart::{MultipartForm, Part};
 use axum_test::TestServer;
 use rdrs::{auth, create_router, db, services, AppState, Config, DbPool, Role};
 use rusqlite::Connection;
@@ -27,14 +28,19 @@ fn create_test_server(config: Config) -> TestServer {
     let (db, _handle) = DbPool::new(conn);
     let webauthn = auth::create_webauthn(&config).unwrap();
     let summary_cache = services::create_summary_cache(100, 24);
-    let (summary_tx, _summary_rx) = services::create_summary_channel(10);
 
+    let settings = services::LiveSettings::from_config(&config);
     let state = AppState {
         db,
         config: Arc::new(config),
         webauthn: Arc::new(webauthn),
         summary_cache,
-        summary_tx,
+        jobs: services::JobQueue::default(),
+        heartbeats: services::Heartbeats::default(),
+        refreshes: services::RefreshRegistry::default(),
+        blocklist: services::Blocklist::default(),
+        updates: services::UpdateChecker::default(),
+        settings,
     };
 
     let app = create_router(state);
@@ -48,14 +54,19 @@ fn create_test_app(config: Config) -> TestApp {
     let (db, _handle) = DbPool::new(conn);
     let webauthn = auth::create_webauthn(&config).unwrap();
     let summary_cache = services::create_summary_cache(100, 24);
-    let (summary_tx, _summary_rx) = services::create_summary_channel(10);
 
+    let settings = services::LiveSettings::from_config(&config);
     let state = AppState {
         db: db.clone(),
         config: Arc::new(config),
         webauthn: Arc::new(webauthn),
         summary_cache,
-        summary_tx,
+        jobs: services::JobQueue::default(),
+        heartbeats: services::Heartbeats::default(),
+        refreshes: services::RefreshRegistry::default(),
+        blocklist: services::Blocklist::default(),
+        updates: services::UpdateChecker::default(),
+        settings,
     };
 
     let app = create_router(state);
@@ -72,10 +83,27 @@ fn default_test_config() -> Config {
         multi_user_enabled: true,
         image_proxy_secret: vec![0u8; 32],
         image_proxy_secret_generated: false,
+        image_proxy_previous_secrets: vec![],
         user_agent: "RDRS-Test/1.0".to_string(),
         webauthn_rp_id: "localhost".to_string(),
         webauthn_rp_origin: "http://localhost:3000".to_string(),
         webauthn_rp_name: "rdrs-test".to_string(),
+        vapid_private_key: None,
+        vapid_subject: "mailto:admin@localhost".to_string(),
+        tls_cert_path: None,
+        tls_key_path: None,
+        unix_socket_path: None,
+        reuse_port: false,
+        shutdown_drain_timeout_secs: 30,
+        max_body_bytes: 2 * 1024 * 1024,
+        max_import_bytes: 256 * 1024 * 1024,
+        log_json: false,
+        session_ttl_secs: 7 * 24 * 3600,
+        session_short_ttl_secs: 2 * 3600,
+        feed_cookie_secret: None,
+        privacy_mode: false,
+        update_check_enabled: false,
+        db_reader_count: 0,
     }
 }
 
@@ -175,6 +203,32 @@ async fn test_list_categories() {
     assert_eq!(body.len(), 3);
 }
 
+#[tokio::test]
+async fn test_reorder_categories() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let tech = create_category(&server, "Tech").await;
+    let news = create_category(&server, "News").await;
+    let sports = create_category(&server, "Sports").await;
+
+    let response = server
+        .put("/api/categories/reorder")
+        .json(&json!({ "ids": [sports, tech] }))
+        .await;
+    response.assert_status_ok();
+
+    let body: Vec<serde_json::Value> = server.get("/api/categories").await.json();
+    let ids: Vec<i64> = body.iter().map(|c| c["id"].as_i64().unwrap()).collect();
+    assert_eq!(ids, vec![sports, tech, news]);
+
+    let response = server
+        .put("/api/categories/reorder")
+        .json(&json!({ "ids": [9999] }))
+        .await;
+    response.assert_status_not_found();
+}
+
 #[tokio::test]
 async fn test_list_categories_unauthorized() {
     let server = create_test_server(default_test_config());
@@ -224,6 +278,78 @@ async fn test_update_category() {
     assert_eq!(body["name"], "New Name");
 }
 
+#[tokio::test]
+async fn test_update_category_if_match() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let cat_id = create_category(&server, "News").await;
+    let path = format!("/api/categories/{}", cat_id);
+    let seen = server.get(&path).await.header(header::ETAG);
+    assert_eq!(seen, "\"1\"");
+
+    let response = server
+        .put(&path)
+        .add_header(header::IF_MATCH, seen.clone())
+        .json(&json!({ "name": "World" }))
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.header(header::ETAG), "\"2\"");
+
+    // A second device still holding version 1 is refused
+    let response = server
+        .put(&path)
+        .add_header(header::IF_MATCH, seen)
+        .json(&json!({ "name": "Sports" }))
+        .await;
+    response.assert_status(StatusCode::PRECONDITION_FAILED);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["version"], 2);
+    assert_eq!(body["current"]["name"], "World");
+    assert_eq!(
+        server.get(&path).await.json::<serde_json::Value>()["name"],
+        "World"
+    );
+}
+
+#[tokio::test]
+async fn test_update_category_appearance() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let cat_id = create_category(&server, "News").await;
+
+    let response = server
+        .put(&format!("/api/categories/{}", cat_id))
+        .json(&json!({ "name": "News", "color": "#36C", "icon": "news" }))
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["color"], "#3366cc");
+    assert_eq!(body["icon"], "news");
+    assert_eq!(body["icon_symbol"], "📰");
+
+    // Omitted fields are kept; an empty one is cleared
+    let response = server
+        .put(&format!("/api/categories/{}", cat_id))
+        .json(&json!({ "name": "News", "icon": "" }))
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["color"], "#3366cc");
+    assert!(body["icon"].is_null());
+
+    let response = server
+        .put(&format!("/api/categories/{}", cat_id))
+        .json(&json!({ "name": "News", "color": "red" }))
+        .await;
+    response.assert_status_bad_request();
+
+    let response = server.get("/api/bootstrap").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["categories"][0]["color"], "#3366cc");
+}
+
 #[tokio::test]
 async fn test_update_category_empty_name() {
     let server = create_test_server(default_test_config());
@@ -247,7 +373,9 @@ async fn test_delete_category() {
     let cat_id = create_category(&server, "To Delete").await;
 
     let response = server.delete(&format!("/api/categories/{}", cat_id)).await;
-    response.assert_status(StatusCode::NO_CONTENT);
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert!(body["undo_token"].is_string());
 
     // Verify it's gone
     let response = server.get(&format!("/api/categories/{}", cat_id)).await;
@@ -270,6 +398,40 @@ async fn test_list_feeds_empty() {
     assert!(body.is_empty());
 }
 
+#[tokio::test]
+async fn test_error_response_carries_request_id() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let response = server.get("/api/feeds/99999").await;
+    response.assert_status_not_found();
+
+    let request_id = response.header("x-request-id");
+    let request_id = request_id.to_str().unwrap();
+    assert!(!request_id.is_empty());
+
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["request_id"], request_id);
+}
+
+#[tokio::test]
+async fn test_request_id_from_client_is_kept() {
+    let server = create_test_server(default_test_config());
+
+    let response = server
+        .get("/api/feeds")
+        .add_header(
+            header::HeaderName::from_static("x-request-id"),
+            HeaderValue::from_static("proxy-abc"),
+        )
+        .await;
+    response.assert_status_unauthorized();
+    assert_eq!(response.header("x-request-id"), "proxy-abc");
+
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["request_id"], "proxy-abc");
+}
+
 #[tokio::test]
 async fn test_list_feeds_unauthorized() {
     let server = create_test_server(default_test_config());
@@ -325,6 +487,23 @@ async fn test_get_feed_icon_not_found() {
     response.assert_status_not_found();
 }
 
+#[tokio::test]
+async fn test_refresh_feed_icon_not_found() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let response = server.post("/api/feeds/9999/icon/refresh").await;
+    response.assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_refresh_feed_icon_unauthorized() {
+    let server = create_test_server(default_test_config());
+
+    let response = server.post("/api/feeds/1/icon/refresh").await;
+    response.assert_status_unauthorized();
+}
+
 #[tokio::test]
 async fn test_export_opml_empty() {
     let server = create_test_server(default_test_config());
@@ -366,6 +545,107 @@ async fn test_import_opml_valid() {
     assert_eq!(body["feeds_skipped"], 0);
 }
 
+#[tokio::test]
+async fn test_notifications() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let response = server.get("/api/notifications").await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["unread_count"], 0);
+
+    let opml_content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+  <body>
+    <outline type="rss" text="Example Feed" xmlUrl="https://example.com/feed.xml"/>
+  </body>
+</opml>"#;
+    server
+        .post("/api/opml/import")
+        .json(&json!({ "content": opml_content }))
+        .await
+        .assert_status_ok();
+
+    let body: serde_json::Value = server.get("/api/notifications").await.json();
+    assert_eq!(body["unread_count"], 1);
+    let notification = &body["notifications"][0];
+    assert_eq!(notification["kind"], "import_finished");
+    assert_eq!(notification["body"], "1 feeds added, 0 skipped");
+    assert!(notification["read_at"].is_null());
+
+    let response = server
+        .put(&format!("/api/notifications/{}/read", notification["id"]))
+        .await;
+    response.assert_status_ok();
+    let read: serde_json::Value = response.json();
+    assert!(read["read_at"].is_string());
+
+    let body: serde_json::Value = server
+        .get("/api/notifications?unread_only=true")
+        .await
+        .json();
+    assert_eq!(body["unread_count"], 0);
+    assert_eq!(body["notifications"].as_array().unwrap().len(), 0);
+
+    let response = server.put("/api/notifications/read").await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["marked_count"], 0);
+
+    server
+        .put("/api/notifications/999999/read")
+        .await
+        .assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_imported_feeds_are_queued_for_refresh() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let opml_content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+  <body>
+    <outline text="Tech">
+      <outline type="rss" text="One" xmlUrl="https://example.com/one.xml"/>
+      <outline type="rss" text="Two" xmlUrl="https://example.com/two.xml"/>
+    </outline>
+  </body>
+</opml>"#;
+    server
+        .post("/api/opml/import")
+        .json(&json!({ "content": opml_content }))
+        .await
+        .assert_status_ok();
+
+    let response = server.get("/api/jobs").await;
+    response.assert_status_ok();
+    let jobs: Vec<serde_json::Value> = response.json();
+    assert_eq!(jobs.len(), 2);
+    assert_eq!(jobs[0]["kind"], "refresh");
+    assert_eq!(jobs[0]["status"], "pending");
+    assert_eq!(jobs[0]["attempts"], 0);
+    assert_eq!(jobs[0]["max_attempts"], 2);
+    assert!(jobs[0]["payload"]["feed_id"].is_i64());
+
+    // Feeds already waiting to be fetched are not queued twice
+    let response = server.post("/api/feeds/refresh").await;
+    response.assert_status(StatusCode::ACCEPTED);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["queued"], 2);
+    let jobs: Vec<serde_json::Value> = server.get("/api/jobs").await.json();
+    assert_eq!(jobs.len(), 2);
+
+    let jobs: Vec<serde_json::Value> = server.get("/api/jobs?status=dead").await.json();
+    assert!(jobs.is_empty());
+
+    server
+        .post("/api/feeds/refresh?category_id=999999")
+        .await
+        .assert_status_not_found();
+}
+
 #[tokio::test]
 async fn test_import_opml_invalid() {
     let server = create_test_server(default_test_config());
@@ -414,6 +694,83 @@ async fn test_import_opml_duplicate_feeds_skipped() {
     assert_eq!(body["feeds_skipped"], 1);
 }
 
+#[tokio::test]
+async fn test_import_opml_upload() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let opml_content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+  <body>
+    <outline text="Tech">
+      <outline type="rss" text="Feed 1" xmlUrl="https://example.com/1.xml"/>
+    </outline>
+    <outline type="rss" text="Feed 2" xmlUrl="https://example.com/2.xml"/>
+  </body>
+</opml>"#;
+
+    let form = MultipartForm::new().add_part(
+        "file",
+        Part::bytes(opml_content.as_bytes().to_vec())
+            .file_name("subscriptions.opml")
+            .mime_type("text/x-opml"),
+    );
+    let response = server.post("/api/opml/import").multipart(form).await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["feeds_created"], 2);
+
+    // The same document sent as the request body
+    let response = server
+        .post("/api/opml/import")
+        .content_type("text/xml")
+        .bytes(opml_content.as_bytes().to_vec().into())
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["feeds_skipped"], 2);
+
+    let form = MultipartForm::new().add_text("content", opml_content);
+    let response = server.post("/api/opml/import").multipart(form).await;
+    response.assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_body_size_limits() {
+    let mut config = default_test_config();
+    config.max_body_bytes = 1024;
+    config.max_import_bytes = 64 * 1024;
+    let server = create_test_server(config);
+    setup_authenticated_user(&server).await;
+
+    // Imports accept more than other requests...
+    let feeds: String = (0..200)
+        .map(|i| format!(r#"<outline text="Feed {i}" xmlUrl="https://example.com/{i}.xml"/>"#))
+        .collect();
+    let opml_content = format!(r#"<opml version="2.0"><body>{}</body></opml>"#, feeds);
+    assert!(opml_content.len() > 1024);
+    let response = server
+        .post("/api/opml/import")
+        .content_type("text/xml")
+        .bytes(opml_content.into_bytes().into())
+        .await;
+    response.assert_status_ok();
+
+    let response = server
+        .post("/api/categories")
+        .json(&json!({ "name": "x".repeat(2048) }))
+        .await;
+    response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+
+    // ...up to their own limit
+    let response = server
+        .post("/api/opml/import")
+        .content_type("text/xml")
+        .bytes(vec![b' '; 128 * 1024].into())
+        .await;
+    response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+}
+
 // ============================================================================
 // Entry Handler Tests
 // ============================================================================
@@ -578,6 +935,15 @@ async fn test_refresh_feed_not_found() {
     response.assert_status_not_found();
 }
 
+#[tokio::test]
+async fn test_retry_feed_not_found() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let response = server.post("/api/feeds/9999/retry").await;
+    response.assert_status_not_found();
+}
+
 #[tokio::test]
 async fn test_get_unread_stats() {
     let server = create_test_server(default_test_config());
@@ -693,6 +1059,70 @@ async fn test_update_linkding_settings() {
     let body: serde_json::Value = response.json();
     assert_eq!(body["configured"], true);
     assert_eq!(body["api_url"], "https://linkding.example.com");
+    assert_eq!(body["send_highlights"], false);
+
+    // Turning on highlights keeps the stored token
+    let response = server
+        .put("/api/user/settings/linkding")
+        .json(&json!({
+            "api_url": "https://linkding.example.com",
+            "send_highlights": true
+        }))
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["configured"], true);
+    assert_eq!(body["send_highlights"], true);
+}
+
+#[tokio::test]
+async fn test_update_linkding_tags() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let response = server
+        .put("/api/user/settings/linkding")
+        .json(&json!({
+            "api_url": "https://linkding.example.com",
+            "api_token": "secret-token",
+            "default_tags": ["rdrs", " "],
+            "category_tags": {"Tech": ["tech", "programming"], "Empty": []},
+            "feed_tags": {"Hacker News": ["hn"]},
+            "unread": true
+        }))
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["default_tags"], json!(["rdrs"]));
+    assert_eq!(
+        body["category_tags"],
+        json!({"Tech": ["tech", "programming"]})
+    );
+    assert_eq!(body["feed_tags"]["Hacker News"], json!(["hn"]));
+    assert_eq!(body["unread"], true);
+    assert_eq!(body["shared"], false);
+
+    // Fields left out keep their values
+    let response = server
+        .put("/api/user/settings/linkding")
+        .json(&json!({
+            "api_url": "https://linkding.example.com",
+            "shared": true
+        }))
+        .await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["default_tags"], json!(["rdrs"]));
+    assert_eq!(body["shared"], true);
+
+    // Linkding splits tags on whitespace
+    server
+        .put("/api/user/settings/linkding")
+        .json(&json!({
+            "api_url": "https://linkding.example.com",
+            "default_tags": ["two words"]
+        }))
+        .await
+        .assert_status_bad_request();
 }
 
 #[tokio::test]
@@ -753,6 +1183,36 @@ async fn test_update_kagi_settings() {
     let body: serde_json::Value = response.json();
     assert_eq!(body["configured"], true);
     assert_eq!(body["language"], "EN");
+    assert_eq!(body["style"], "paragraph");
+    assert_eq!(body["length"], "long");
+}
+
+#[tokio::test]
+async fn test_update_kagi_summary_style() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    server
+        .put("/api/user/settings/kagi")
+        .json(&json!({
+            "session_link": "https://kagi.com/summarizer/index.html?token=abc123",
+            "language": "EN"
+        }))
+        .await
+        .assert_status_ok();
+
+    // Style alone keeps the token and language
+    let response = server
+        .put("/api/user/settings/kagi")
+        .json(&json!({"style": "key_quotes", "length": "medium"}))
+        .await;
+    response.assert_status_ok();
+
+    let body: serde_json::Value = server.get("/api/user/settings/kagi").await.json();
+    assert_eq!(body["configured"], true);
+    assert_eq!(body["language"], "EN");
+    assert_eq!(body["style"], "key_quotes");
+    assert_eq!(body["length"], "medium");
 }
 
 #[tokio::test]
@@ -786,12 +1246,54 @@ async fn test_update_kagi_settings_no_token() {
 }
 
 #[tokio::test]
-async fn test_get_theme_default() {
+async fn test_update_openai_settings() {
     let server = create_test_server(default_test_config());
     setup_authenticated_user(&server).await;
 
-    let response = server.get("/api/user/settings/theme").await;
-    response.assert_status_ok();
+    let response = server
+        .put("/api/user/settings/openai")
+        .json(&json!({
+            "base_url": "http://localhost:11434/v1",
+            "api_key": "secret",
+            "model": "llama3"
+        }))
+        .await;
+    response.assert_status_ok();
+
+    // The key is kept when left out, and never returned
+    server
+        .put("/api/user/settings/openai")
+        .json(&json!({"model": "qwen2"}))
+        .await
+        .assert_status_ok();
+    let body: serde_json::Value = server.get("/api/user/settings/openai").await.json();
+    assert_eq!(body["configured"], true);
+    assert_eq!(body["base_url"], "http://localhost:11434/v1");
+    assert_eq!(body["model"], "qwen2");
+    assert!(body.get("api_key").is_none());
+
+    server
+        .put("/api/user/settings/openai")
+        .json(&json!({}))
+        .await
+        .assert_status_ok();
+    let body: serde_json::Value = server.get("/api/user/settings/openai").await.json();
+    assert_eq!(body["configured"], false);
+
+    server
+        .put("/api/user/settings/openai")
+        .json(&json!({"base_url": "not a url", "model": "llama3"}))
+        .await
+        .assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_get_theme_default() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let response = server.get("/api/user/settings/theme").await;
+    response.assert_status_ok();
 
     let body: serde_json::Value = response.json();
     assert_eq!(body["theme"], serde_json::Value::Null);
@@ -895,6 +1397,94 @@ async fn test_update_theme_unauthorized() {
     response.assert_status_unauthorized();
 }
 
+#[tokio::test]
+async fn test_locale_setting() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let response = server.get("/api/user/settings/locale").await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["locale"], serde_json::Value::Null);
+    assert_eq!(body["effective"], "en");
+
+    // Without a setting the browser language is used
+    let response = server
+        .get("/api/user/settings/locale")
+        .add_header(
+            header::ACCEPT_LANGUAGE,
+            HeaderValue::from_static("zh-TW,zh;q=0.9,en;q=0.8"),
+        )
+        .await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["effective"], "zh-TW");
+
+    let response = server
+        .put("/api/user/settings/locale")
+        .json(&json!({ "locale": "fr" }))
+        .await;
+    response.assert_status_bad_request();
+
+    let response = server
+        .put("/api/user/settings/locale")
+        .json(&json!({ "locale": "zh-TW" }))
+        .await;
+    response.assert_status_ok();
+
+    // The setting wins over the browser language
+    let response = server
+        .get("/api/user/settings/locale")
+        .add_header(header::ACCEPT_LANGUAGE, HeaderValue::from_static("en"))
+        .await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["locale"], "zh-TW");
+    assert_eq!(body["effective"], "zh-TW");
+
+    let response = server.get("/api/feeds/99999").await;
+    response.assert_status_not_found();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["error"], "找不到訂閱源");
+
+    let response = server.get("/user-settings").await;
+    response.assert_status_ok();
+    let html = response.text();
+    assert!(html.contains(r#"<html lang="zh-TW""#));
+    assert!(html.contains("[訂閱源]"));
+
+    let html = server.get("/entries/1").await.text();
+    assert!(html.contains(r#"data-already-saved="已儲存至""#));
+
+    // Clearing the setting goes back to the browser language
+    let response = server
+        .put("/api/user/settings/locale")
+        .json(&json!({ "locale": null }))
+        .await;
+    response.assert_status_ok();
+    let response = server.get("/api/feeds/99999").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["error"], "Feed not found");
+}
+
+#[tokio::test]
+async fn test_accept_language_without_session() {
+    let server = create_test_server(default_test_config());
+
+    let response = server
+        .get("/api/user/settings/locale")
+        .add_header(header::ACCEPT_LANGUAGE, HeaderValue::from_static("zh-HK"))
+        .await;
+    response.assert_status_unauthorized();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["error"], "未授權");
+
+    let response = server
+        .get("/login")
+        .add_header(header::ACCEPT_LANGUAGE, HeaderValue::from_static("zh-TW"))
+        .await;
+    response.assert_status_ok();
+    assert!(response.text().contains("使用者名稱"));
+}
+
 // ============================================================================
 // Page Handler Tests
 // ============================================================================
@@ -1181,6 +1771,49 @@ async fn test_import_opml_unauthorized() {
     response.assert_status_unauthorized();
 }
 
+#[tokio::test]
+async fn test_import_opml_in_background() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let opml_content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+    <body>
+        <outline text="Queued" title="Queued">
+            <outline type="rss" text="Feed" xmlUrl="https://queued.example.com/feed.xml"/>
+        </outline>
+    </body>
+</opml>"#;
+
+    let response = server
+        .post("/api/opml/import?background=true")
+        .json(&json!({ "content": opml_content }))
+        .await;
+    response.assert_status(StatusCode::ACCEPTED);
+    let job_id = response.json::<serde_json::Value>()["job_id"].clone();
+
+    // The same file again while the first is waiting is the same job
+    let response = server
+        .post("/api/opml/import?background=true")
+        .json(&json!({ "content": opml_content }))
+        .await;
+    assert_eq!(response.json::<serde_json::Value>()["job_id"], job_id);
+
+    let jobs: Vec<serde_json::Value> = server.get("/api/jobs").await.json();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0]["kind"], "import");
+    assert_eq!(jobs[0]["status"], "pending");
+
+    // Nothing is imported until the job runs, but bad OPML is still refused
+    let feeds: Vec<serde_json::Value> = server.get("/api/feeds").await.json();
+    assert!(feeds.is_empty());
+    server
+        .post("/api/opml/import?background=true")
+        .json(&json!({ "content": "not xml" }))
+        .await
+        .assert_status_bad_request();
+}
+
 #[tokio::test]
 async fn test_import_opml_multiple_categories() {
     let server = create_test_server(default_test_config());
@@ -1354,6 +1987,146 @@ async fn test_update_feed_to_other_user_category() {
     response.assert_status_not_found();
 }
 
+// ============================================================================
+// Feed Script Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_feed_script() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let opml_content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+    <body>
+        <outline text="Scripts">
+            <outline type="rss" text="Script Feed" xmlUrl="https://script.example.com/feed.xml"/>
+        </outline>
+    </body>
+</opml>"#;
+    server
+        .post("/api/opml/import")
+        .json(&json!({ "content": opml_content }))
+        .await
+        .assert_status_ok();
+    let feeds: Vec<serde_json::Value> = server.get("/api/feeds").await.json();
+    let feed_id = feeds[0]["id"].as_i64().unwrap();
+    let url = format!("/api/feeds/{}/script", feed_id);
+
+    let body: serde_json::Value = server.get(&url).await.json();
+    assert_eq!(body["script"], serde_json::Value::Null);
+
+    let script = "fn on_entry(entry) { entry.title = \"[x] \" + entry.title; entry }";
+    let response = server.put(&url).json(&json!({ "script": script })).await;
+    response.assert_status_ok();
+    let body: serde_json::Value = server.get(&url).await.json();
+    assert_eq!(body["script"], script);
+
+    // Scripts that do not compile or lack the hook are rejected
+    for invalid in ["fn on_entry(entry) {", "fn other(entry) { entry }"] {
+        let response = server.put(&url).json(&json!({ "script": invalid })).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+    let body: serde_json::Value = server.get(&url).await.json();
+    assert_eq!(body["script"], script);
+
+    server
+        .put(&url)
+        .json(&json!({ "script": "  " }))
+        .await
+        .assert_status_ok();
+    let body: serde_json::Value = server.get(&url).await.json();
+    assert_eq!(body["script"], serde_json::Value::Null);
+
+    let response = server.get("/api/feeds/99999/script").await;
+    response.assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_feed_rewrites() {
+    let app = create_test_app(default_test_config());
+    let server = &app.server;
+    setup_authenticated_user(server).await;
+
+    let opml_content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+    <body>
+        <outline text="Rewrites">
+            <outline type="rss" text="Rewrite Feed" xmlUrl="https://rewrite.example.com/feed.xml"/>
+        </outline>
+    </body>
+</opml>"#;
+    server
+        .post("/api/opml/import")
+        .json(&json!({ "content": opml_content }))
+        .await
+        .assert_status_ok();
+    let feeds: Vec<serde_json::Value> = server.get("/api/feeds").await.json();
+    let feed_id = feeds[0]["id"].as_i64().unwrap();
+    app.db
+        .user(move |conn| {
+            for (guid, title) in [("a", "[Sponsored] |Site| Deals"), ("b", "Plain news")] {
+                rdrs::models::entry::upsert_entry(
+                    conn,
+                    feed_id,
+                    guid,
+                    Some(title),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )?;
+            }
+            Ok::<_, rdrs::error::AppError>(())
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    let url = format!("/api/feeds/{}/rewrites", feed_id);
+
+    let rule = json!({ "field": "title", "pattern": r"^\[Sponsored\]\s*\|[^|]*\|\s*" });
+    let response = server.post(&format!("{}/preview", url)).json(&rule).await;
+    response.assert_status_ok();
+    let previews: Vec<serde_json::Value> = response.json();
+    assert_eq!(previews.len(), 2);
+    let sponsored = previews.iter().find(|p| p["changed"] == true).unwrap();
+    assert_eq!(sponsored["before"], "[Sponsored] |Site| Deals");
+    assert_eq!(sponsored["after"], "Deals");
+
+    let response = server.post(&url).json(&rule).await;
+    response.assert_status(StatusCode::CREATED);
+    let rewrite_id = response.json::<serde_json::Value>()["id"].as_i64().unwrap();
+
+    let response = server
+        .put(&format!("{}/{}", url, rewrite_id))
+        .json(&json!({ "field": "content", "pattern": "http:", "replacement": "https:" }))
+        .await;
+    response.assert_status_ok();
+    let rewrites: Vec<serde_json::Value> = server.get(&url).await.json();
+    assert_eq!(rewrites.len(), 1);
+    assert_eq!(rewrites[0]["field"], "content");
+    assert_eq!(rewrites[0]["replacement"], "https:");
+
+    let response = server
+        .post(&url)
+        .json(&json!({ "field": "title", "pattern": "(unclosed" }))
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+
+    server
+        .delete(&format!("{}/{}", url, rewrite_id))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    server
+        .delete(&format!("{}/{}", url, rewrite_id))
+        .await
+        .assert_status_not_found();
+
+    let response = server.get("/api/feeds/99999/rewrites").await;
+    response.assert_status_not_found();
+}
+
 // ============================================================================
 // Fetch Metadata Tests
 // ============================================================================
@@ -1502,6 +2275,24 @@ async fn test_passkey_auth_start_no_passkeys() {
     assert!(body["error"].as_str().unwrap().contains("No passkeys"));
 }
 
+#[tokio::test]
+async fn test_passkey_auth_options_without_passkeys() {
+    let server = create_test_server(default_test_config());
+
+    // Conditional mediation works before anyone has registered a passkey and
+    // does not reveal which credentials exist
+    let response = server.get("/api/passkey/auth/options").await;
+    response.assert_status_ok();
+
+    let body: serde_json::Value = response.json();
+    assert!(body["options"]["publicKey"]["challenge"].is_string());
+    assert_eq!(body["options"]["mediation"], "conditional");
+    assert!(body["options"]["publicKey"]
+        .get("allowCredentials")
+        .and_then(|c| c.as_array())
+        .is_none_or(|c| c.is_empty()));
+}
+
 #[tokio::test]
 async fn test_passkey_auth_finish_no_challenge() {
     let server = create_test_server(default_test_config());
@@ -2153,6 +2944,93 @@ async fn test_apple_touch_icon() {
     assert_eq!(content_type, "image/png");
 }
 
+#[tokio::test]
+async fn test_fingerprinted_static_asset() {
+    let server = create_test_server(default_test_config());
+
+    let url = rdrs::handlers::favicon::asset_url("favicon.svg");
+    assert!(url.starts_with("/static/"));
+
+    let response = server.get(&url).await;
+    response.assert_status_ok();
+    let cache_control = response.header(header::CACHE_CONTROL);
+    assert_eq!(cache_control, "public, max-age=31536000, immutable");
+
+    // Unversioned paths are cached briefly
+    let response = server.get("/favicon.svg").await;
+    assert_eq!(
+        response.header(header::CACHE_CONTROL),
+        "public, max-age=86400"
+    );
+
+    let response = server.get("/static/0000000000000000/favicon.svg").await;
+    response.assert_status_not_found();
+
+    let response = server.get("/static/0000000000000000/missing.svg").await;
+    response.assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_response_compression() {
+    let server = create_test_server(default_test_config());
+
+    let response = server
+        .get("/sw.js")
+        .add_header(header::ACCEPT_ENCODING, HeaderValue::from_static("gzip"))
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.header(header::CONTENT_ENCODING), "gzip");
+
+    let response = server
+        .get("/sw.js")
+        .add_header(header::ACCEPT_ENCODING, HeaderValue::from_static("br"))
+        .await;
+    assert_eq!(response.header(header::CONTENT_ENCODING), "br");
+
+    let response = server.get("/sw.js").await;
+    assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+}
+
+// ============================================================================
+// PWA Handler Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_manifest() {
+    let server = create_test_server(default_test_config());
+
+    let response = server.get("/manifest.webmanifest").await;
+    response.assert_status_ok();
+
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert_eq!(content_type, "application/manifest+json");
+
+    let body: serde_json::Value = serde_json::from_str(&response.text()).unwrap();
+    assert_eq!(body["start_url"], "/");
+}
+
+#[tokio::test]
+async fn test_service_worker() {
+    let server = create_test_server(default_test_config());
+
+    let response = server.get("/sw.js").await;
+    response.assert_status_ok();
+
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert_eq!(content_type, "application/javascript");
+    assert!(response.text().contains("/api/entries/offline-bundle"));
+}
+
 // ============================================================================
 // Health Check Tests
 // ============================================================================
@@ -2178,3 +3056,400 @@ async fn test_health_check_no_auth_required() {
     let response = server.get("/health").await;
     response.assert_status_ok();
 }
+
+#[tokio::test]
+async fn test_health_live() {
+    let server = create_test_server(default_test_config());
+
+    let response = server.get("/health/live").await;
+    response.assert_status_ok();
+
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["status"], "ok");
+}
+
+#[tokio::test]
+async fn test_health_ready() {
+    let server = create_test_server(default_test_config());
+
+    let response = server.get("/health/ready").await;
+    response.assert_status_ok();
+
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["database"]["status"], "ok");
+    assert_eq!(body["wal"]["status"], "ok");
+    assert!(body["workers"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_health_ready_reports_stalled_worker() {
+    let conn = Connection::open_in_memory().unwrap();
+    db::init_db(&conn).unwrap();
+    let (db, _handle) = DbPool::new(conn);
+    let config = default_test_config();
+    let webauthn = auth::create_webauthn(&config).unwrap();
+    let heartbeats = services::Heartbeats::default();
+    heartbeats.register("stalled", std::time::Duration::ZERO);
+    heartbeats.register("healthy", std::time::Duration::from_secs(60));
+
+    let settings = services::LiveSettings::from_config(&config);
+    let state = AppState {
+        db,
+        config: Arc::new(config),
+        webauthn: Arc::new(webauthn),
+        summary_cache: services::create_summary_cache(100, 24),
+        jobs: services::JobQueue::default(),
+        heartbeats,
+        refreshes: services::RefreshRegistry::default(),
+        blocklist: services::Blocklist::default(),
+        updates: services::UpdateChecker::default(),
+        settings,
+    };
+    let server = TestServer::new(create_router(state)).unwrap();
+
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    let response = server.get("/health/ready").await;
+    response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["status"], "unavailable");
+    assert_eq!(body["database"]["status"], "ok");
+    let workers = body["workers"].as_array().unwrap();
+    assert_eq!(workers.len(), 2);
+    assert_eq!(workers[0]["name"], "healthy");
+    assert_eq!(workers[0]["alive"], true);
+    assert_eq!(workers[1]["name"], "stalled");
+    assert_eq!(workers[1]["alive"], false);
+}
+
+// ============================================================================
+// Push Notification Tests
+// ============================================================================
+
+const TEST_VAPID_KEY: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAE";
+
+#[tokio::test]
+async fn test_push_subscription_requires_vapid_config() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    server
+        .get("/api/user/push-subscriptions/vapid-public-key")
+        .await
+        .assert_status_not_found();
+
+    server
+        .post("/api/user/push-subscriptions")
+        .json(&json!({
+            "endpoint": "https://push.example.com/abc",
+            "keys": { "p256dh": "key", "auth": "auth" }
+        }))
+        .await
+        .assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_push_subscription_lifecycle() {
+    let config = Config {
+        vapid_private_key: Some(TEST_VAPID_KEY.to_string()),
+        ..default_test_config()
+    };
+    let server = create_test_server(config);
+    setup_authenticated_user(&server).await;
+
+    let response = server
+        .get("/api/user/push-subscriptions/vapid-public-key")
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert!(!body["public_key"].as_str().unwrap().is_empty());
+
+    server
+        .post("/api/user/push-subscriptions")
+        .json(&json!({
+            "endpoint": "https://push.example.com/abc",
+            "keys": { "p256dh": "key", "auth": "auth" }
+        }))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    let response = server.get("/api/user/push-subscriptions").await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    assert!(body[0].get("auth").is_none());
+
+    server
+        .delete("/api/user/push-subscriptions")
+        .json(&json!({ "endpoint": "https://push.example.com/abc" }))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    server
+        .delete("/api/user/push-subscriptions")
+        .json(&json!({ "endpoint": "https://push.example.com/abc" }))
+        .await
+        .assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_push_subscription_rejects_http_endpoint() {
+    let config = Config {
+        vapid_private_key: Some(TEST_VAPID_KEY.to_string()),
+        ..default_test_config()
+    };
+    let server = create_test_server(config);
+    setup_authenticated_user(&server).await;
+
+    server
+        .post("/api/user/push-subscriptions")
+        .json(&json!({
+            "endpoint": "http://push.example.com/abc",
+            "keys": { "p256dh": "key", "auth": "auth" }
+        }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_notification_rules_crud() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+    let category_id = create_category(&server, "News").await;
+
+    let response = server
+        .post("/api/user/notification-rules")
+        .json(&json!({ "category_id": category_id, "keyword": "  breaking  " }))
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["keyword"], "breaking");
+    let rule_id = body["id"].as_i64().unwrap();
+
+    let response = server.get("/api/user/notification-rules").await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    server
+        .delete(&format!("/api/user/notification-rules/{}", rule_id))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    server
+        .delete(&format!("/api/user/notification-rules/{}", rule_id))
+        .await
+        .assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_notification_rule_unknown_category() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    server
+        .post("/api/user/notification-rules")
+        .json(&json!({ "category_id": 9999 }))
+        .await
+        .assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_session_sliding_expiration() {
+    let app = create_test_app(default_test_config());
+    setup_authenticated_user(&app.server).await;
+
+    // A fresh session is not renewed on every request
+    let response = app.server.get("/api/user").await;
+    response.assert_status_ok();
+    assert!(response.headers().get(header::SET_COOKIE).is_none());
+
+    // After a day of inactivity the next request renews it
+    app.db
+        .user(|conn| {
+            conn.execute(
+                "UPDATE session SET expires_at = datetime('now', '+6 days')",
+                [],
+            )
+            .unwrap();
+        })
+        .await
+        .unwrap();
+
+    let response = app.server.get("/api/user").await;
+    response.assert_status_ok();
+    let cookie = response.header(header::SET_COOKIE);
+    assert!(cookie.to_str().unwrap().starts_with("session_token="));
+
+    let renewed: bool = app
+        .db
+        .user(|conn| {
+            conn.query_row(
+                "SELECT expires_at > datetime('now', '+6 days', '+23 hours') FROM session",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap()
+        })
+        .await
+        .unwrap();
+    assert!(renewed);
+
+    // Sessions idle past their lifetime are rejected
+    app.db
+        .user(|conn| {
+            conn.execute(
+                "UPDATE session SET expires_at = datetime('now', '-1 minute')",
+                [],
+            )
+            .unwrap();
+        })
+        .await
+        .unwrap();
+
+    app.server
+        .get("/api/user")
+        .await
+        .assert_status_unauthorized();
+}
+
+#[tokio::test]
+async fn test_login_without_remember_uses_browser_session() {
+    let app = create_test_app(default_test_config());
+    app.server
+        .post("/api/register")
+        .json(&json!({
+            "username": "testuser",
+            "password": "password123"
+        }))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    let response = app
+        .server
+        .post("/api/session")
+        .json(&json!({
+            "username": "testuser",
+            "password": "password123",
+            "remember": false
+        }))
+        .await;
+    response.assert_status_ok();
+
+    let cookie = response.header(header::SET_COOKIE);
+    let cookie = cookie.to_str().unwrap();
+    assert!(cookie.starts_with("session_token="));
+    assert!(!cookie.contains("Max-Age"));
+
+    let (persistent, short_lived): (bool, bool) = app
+        .db
+        .user(|conn| {
+            conn.query_row(
+                "SELECT persistent, expires_at <= datetime('now', '+2 hours') FROM session",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap()
+        })
+        .await
+        .unwrap();
+    assert!(!persistent);
+    assert!(short_lived);
+
+    app.server.get("/api/user").await.assert_status_ok();
+}
+
+#[tokio::test]
+async fn test_list_and_delete_sessions() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    server
+        .post("/api/session")
+        .add_header(
+            header::USER_AGENT,
+            HeaderValue::from_static(
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) \
+                 AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1",
+            ),
+        )
+        .json(&json!({
+            "username": "testuser",
+            "password": "password123"
+        }))
+        .await
+        .assert_status_ok();
+
+    let response = server.get("/api/user/sessions").await;
+    response.assert_status_ok();
+    let sessions: Vec<serde_json::Value> = response.json();
+    assert_eq!(sessions.len(), 2);
+
+    let current = sessions.iter().find(|s| s["current"] == true).unwrap();
+    assert_eq!(current["device"], "Mobile · Safari on iOS");
+    assert_eq!(current["persistent"], true);
+
+    let other = sessions.iter().find(|s| s["current"] == false).unwrap();
+    let other_id = other["id"].as_i64().unwrap();
+
+    server
+        .delete(&format!("/api/user/sessions/{}", other_id))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    let sessions: Vec<serde_json::Value> = server.get("/api/user/sessions").await.json();
+    assert_eq!(sessions.len(), 1);
+
+    server
+        .delete(&format!("/api/user/sessions/{}", other_id))
+        .await
+        .assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_sanitize_policy_setting() {
+    let server = create_test_server(default_test_config());
+    setup_authenticated_user(&server).await;
+
+    let response = server.get("/api/user/settings/sanitize").await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["iframe_hosts"], json!([]));
+    assert_eq!(body["keep_inline_styles"], false);
+    assert_eq!(body["proxy_images"], true);
+    assert_eq!(body["video_embeds"], "facade");
+
+    let response = server
+        .put("/api/user/settings/sanitize")
+        .json(&json!({ "video_embeds": "autoplay" }))
+        .await;
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = server
+        .put("/api/user/settings/sanitize")
+        .json(&json!({ "iframe_hosts": ["https://www.youtube.com/embed"] }))
+        .await;
+    response.assert_status_bad_request();
+
+    // Omitted fields take their defaults; hosts are normalized
+    let response = server
+        .put("/api/user/settings/sanitize")
+        .json(&json!({ "iframe_hosts": ["WWW.YouTube.com", "www.youtube.com", " "] }))
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["iframe_hosts"], json!(["www.youtube.com"]));
+    assert_eq!(body["proxy_images"], true);
+
+    let response = server
+        .put("/api/user/settings/sanitize")
+        .json(&json!({ "video_embeds": "embed" }))
+        .await;
+    response.assert_status_ok();
+
+    let response = server.get("/api/user/settings/sanitize").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["iframe_hosts"], json!([]));
+    assert_eq!(body["video_embeds"], "embed");
+}
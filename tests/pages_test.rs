@@ -25,14 +25,19 @@ fn create_test_app(config: Config) -> TestApp {
     let (db, _handle) = DbPool::new(conn);
     let webauthn = auth::create_webauthn(&config).unwrap();
     let summary_cache = services::create_summary_cache(100, 24);
-    let (summary_tx, _summary_rx) = services::create_summary_channel(10);
 
+    let settings = services::LiveSettings::from_config(&config);
     let state = AppState {
         db: db.clone(),
         config: Arc::new(config),
         webauthn: Arc::new(webauthn),
         summary_cache,
-        summary_tx,
+        jobs: services::JobQueue::default(),
+        heartbeats: services::Heartbeats::default(),
+        refreshes: services::RefreshRegistry::default(),
+        blocklist: services::Blocklist::default(),
+        updates: services::UpdateChecker::default(),
+        settings,
     };
 
     let app = create_router(state);
@@ -49,10 +54,27 @@ fn default_test_config() -> Config {
         multi_user_enabled: true,
         image_proxy_secret: vec![0u8; 32],
         image_proxy_secret_generated: false,
+        image_proxy_previous_secrets: vec![],
         user_agent: "RDRS-Test/1.0".to_string(),
         webauthn_rp_id: "localhost".to_string(),
         webauthn_rp_origin: "http://localhost:3000".to_string(),
         webauthn_rp_name: "rdrs-test".to_string(),
+        vapid_private_key: None,
+        vapid_subject: "mailto:admin@localhost".to_string(),
+        tls_cert_path: None,
+        tls_key_path: None,
+        unix_socket_path: None,
+        reuse_port: false,
+        shutdown_drain_timeout_secs: 30,
+        max_body_bytes: 2 * 1024 * 1024,
+        max_import_bytes: 256 * 1024 * 1024,
+        log_json: false,
+        session_ttl_secs: 7 * 24 * 3600,
+        session_short_ttl_secs: 2 * 3600,
+        feed_cookie_secret: None,
+        privacy_mode: false,
+        update_check_enabled: false,
+        db_reader_count: 0,
     }
 }
 
@@ -205,6 +227,38 @@ async fn test_user_settings_page_content() {
     assert!(body.contains("Password") || body.contains("password"));
 }
 
+#[tokio::test]
+async fn test_start_page_redirect() {
+    let app = create_test_app(default_test_config());
+    setup_users(&app.db).await;
+
+    login(&app.server, "user").await;
+
+    app.server
+        .put("/api/user/settings/home")
+        .json(&json!({ "start_page": "starred", "unread_count_cap": 99 }))
+        .await
+        .assert_status_ok();
+
+    let response = app.server.get("/").await;
+    response.assert_status_see_other();
+    assert_eq!(response.header("location"), "/entries/starred");
+
+    // The unread page stays reachable
+    app.server.get("/unread").await.assert_status_ok();
+
+    let response = app.server.get("/api/bootstrap").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["settings"]["start_page"], "starred");
+    assert_eq!(body["settings"]["unread_count_cap"], 99);
+
+    app.server
+        .put("/api/user/settings/home")
+        .json(&json!({ "start_page": "unread", "unread_count_cap": 0 }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test]
 async fn test_settings_page_shows_version() {
     let app = create_test_app(default_test_config());
@@ -222,6 +276,21 @@ async fn test_settings_page_shows_version() {
     assert!(body.contains("User-Agent") || body.contains("user-agent") || body.contains("RDRS"));
 }
 
+#[tokio::test]
+async fn test_stats_page_offers_year_in_review() {
+    let app = create_test_app(default_test_config());
+    setup_users(&app.db).await;
+
+    login(&app.server, "admin").await;
+
+    let response = app.server.get("/stats").await;
+    response.assert_status_ok();
+    let body = response.text();
+
+    assert!(body.contains("Reading Statistics"));
+    assert!(body.contains("in review"));
+}
+
 #[tokio::test]
 async fn test_login_page_hides_signup_when_disabled() {
     let config = Config {
@@ -661,6 +730,19 @@ async fn test_summarized_entries_page() {
     assert!(body.contains("Summarized Entries") || body.contains("summarized"));
 }
 
+#[tokio::test]
+async fn test_read_later_entries_page() {
+    let app = create_test_app(default_test_config());
+    setup_users(&app.db).await;
+    login(&app.server, "admin").await;
+
+    let response = app.server.get("/entries/read-later").await;
+    response.assert_status_ok();
+    let body = response.text();
+    assert!(body.contains("Read Later"));
+    assert!(body.contains("const pageMode = 'read_later';"));
+}
+
 #[tokio::test]
 async fn test_search_page() {
     let app = create_test_app(default_test_config());